@@ -0,0 +1,115 @@
+//! Benchmarks for the git-heavy parts of an analysis run, and for the reply
+//! cache that lets a second run skip the model.
+//!
+//! Run with `cargo bench`. The synthetic repository size can be changed with
+//! `WTF_BENCH_COMMITS` (default 2000).
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use wtf::cache::Cached;
+use wtf::provider::{LlmProvider, Message};
+use wtf::{chunk, git, progress};
+
+const FILES: usize = 200;
+
+/// Time `f` repeatedly for about a second and print min/mean per iteration.
+fn bench<T>(name: &str, mut f: impl FnMut() -> T) {
+    // Warm up caches (libgit2's object cache in particular)
+    black_box(f());
+
+    let mut samples = Vec::new();
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(1) || samples.len() < 5 {
+        let t = Instant::now();
+        black_box(f());
+        samples.push(t.elapsed());
+    }
+
+    let min = samples.iter().min().unwrap();
+    let mean = samples.iter().sum::<Duration>() / samples.len() as u32;
+    println!(
+        "{:<32} {:>12.3?} min {:>12.3?} mean ({} iterations)",
+        name,
+        min,
+        mean,
+        samples.len()
+    );
+}
+
+/// A repository with `commits` commits, each rewriting one of `FILES` files.
+fn synthetic_repo(commits: usize) -> FixtureRepo {
+    let mut fixture = FixtureRepo::new();
+    for i in 0..commits {
+        let path = format!("src/module{}/file{}.rs", i % 20, i % FILES);
+        let content: String = (0..50)
+            .map(|line| format!("fn item_{}_{}() {{ /* revision {} */ }}\n", i % FILES, line, i))
+            .collect();
+        fixture.commit(&format!("Update file {} (revision {})", i % FILES, i), &[Change::Write(&path, content.as_bytes())]);
+    }
+    fixture
+}
+
+fn main() {
+    let commits: usize = std::env::var("WTF_BENCH_COMMITS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(2000);
+
+    println!("Building synthetic repository with {} commits...", commits);
+    let fixture = synthetic_repo(commits);
+    let repo = &fixture.repo;
+
+    for n in [5, 50, 500] {
+        bench(&format!("select_commits({})", n), || git::select_commits(repo, n).unwrap().0.len());
+    }
+
+    let (recent, _) = git::select_commits(repo, 50).unwrap();
+
+    bench("commit_patch x50", || {
+        recent
+            .iter()
+            .map(|c| git::commit_patch(repo, c).unwrap().map_or(0, |p| p.len()))
+            .sum::<usize>()
+    });
+
+    bench("commit prompt assembly x50", || {
+        recent
+            .iter()
+            .map(git::get_commit_details)
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n")
+    });
+
+    let patches: String = recent
+        .iter()
+        .filter_map(|c| git::commit_patch(repo, c).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    bench("chunk_patch x50 (1k tokens)", || chunk::chunk_patch(&patches, 1000).len());
+
+    // One request per commit, as `wtf commit` sends them
+    let requests: Vec<Vec<Message>> = recent
+        .iter()
+        .map(|c| vec![Message::system("Explain the commit."), Message::user(git::get_commit_details(c))])
+        .collect();
+    let cached = Cached::new(Box::new(MockProvider::new()), &fixture.dir.join(".git/bench-cache"), "mock");
+    bench("cache key x50", || requests.iter().map(|messages| cached.key(messages, None).len()).sum::<usize>());
+
+    // The warm-up run stores every reply, so the timed runs are all hits
+    progress::set_quiet(true);
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    bench("cache lookup (hit) x50", || {
+        runtime.block_on(async {
+            let mut total = 0;
+            for messages in &requests {
+                total += cached.chat(messages).await.unwrap().len();
+            }
+            total
+        })
+    });
+}
@@ -0,0 +1,502 @@
+# German messages for wtf.
+
+## Report section headers
+
+section-project = Projektbeschreibung
+section-commits = Die letzten { $count } Commits in einfachen Worten
+section-range = Änderungen zwischen { $from } und { $to } ({ $count } Commits)
+section-edits = Ausführliche Analyse der letzten Änderungen
+section-ci = Änderungen an Build und CI
+section-unverified = Nicht überprüfte Verweise
+section-review-effort = Prüfaufwand
+section-public-api = Änderungen an der öffentlichen API
+section-classification = Einordnung der Commits
+section-revised = { $title } (geprüft)
+group-root = (oberste Ebene)
+group-other = Sonstiges
+topic-other = Weitere Änderungen
+unverified-intro = Diese Dateien oder Commits wurden erwähnt, kommen in den analysierten Daten aber nicht vor:
+unverified-note = diese Dateien oder Commits wurden erwähnt, kommen in den analysierten Daten aber nicht vor:
+note-label = Hinweis
+single-commit-edits = Das Repository hat nur einen Commit, es gibt also keine früheren Versionen, mit denen sich Änderungen vergleichen ließen.
+generated-churn = Generierte/fremde Dateien, nicht mitanalysiert: { $files } Datei(en), +{ $added } -{ $removed } Zeilen ({ $paths })
+generated-churn-more = { $paths } und { $count } weitere
+accessible-section = Abschnitt { $index } von { $total }: { $title }
+accessible-commit = Commit { $sha }
+accessible-commits = Commits { $shas }
+accessible-end = Ende des Berichts.
+timeline-title = Geschichte von { $path }
+timeline-era = { $path }, { $start } bis { $end }: { $count } Commit(s) von { $authors }
+blame-title = Warum die Zeilen { $start }-{ $end } von { $path } so aussehen
+blame-commits = Commits hinter diesen Zeilen ({ $count })
+blame-commit-entry = [{ $sha }] { $summary } ({ $author }, { $date }; { $count } Zeile(n))
+blame-invalid-lines = „{ $lines }“ ist kein Zeilenbereich; gib einen wie 120-160 an, oder eine einzelne Zeilennummer
+stash-title = Stashes ({ $count })
+stash-entry = { $name }: { $message } ({ $date }, { $files } Datei(en))
+stash-explanation = Was sie enthalten
+stash-none = Es gibt keine Stashes.
+tag-title = Was in { $tag } steckt
+tag-about = Über das Release
+tag-since = { $count } Commit(s) seit { $previous }, { $files } Datei(en) geändert (+{ $added } -{ $removed } Zeilen)
+tag-first = Das erste Release: { $count } Commit(s), { $files } Datei(en) (+{ $added } -{ $removed } Zeilen)
+tag-tagged-by = Getaggt von { $tagger }, auf einem Commit vom { $date }
+tag-lightweight = Ein einfaches Tag, auf einem Commit vom { $date }
+conflict-title = Konflikte
+conflict-files = { $count } Datei(en) mit Konflikten
+reflog-title = Was gerade passiert ist
+reflog-movements = Bewegungen von HEAD ({ $count })
+reflog-entry = { $name }, { $ago }: { $message }
+reflog-lost = Zurückgelassen ({ $count })
+reflog-lost-entry = { $sha } { $summary } (siehe { $seen })
+reflog-nothing-lost = Jeder Commit, auf dem HEAD war, ist noch von einem Branch, Tag oder Stash aus erreichbar.
+reflog-just-now = gerade eben
+reflog-minutes-ago = vor { $count } Minute(n)
+reflog-hours-ago = vor { $count } Stunde(n)
+reflog-days-ago = vor { $count } Tag(en)
+reflog-empty = HEAD hat noch kein Reflog.
+broke-title = Was kaputtgegangen ist: { $symptom }
+broke-range = { $count } mögliche Commit(s) zwischen dem guten { $good } und dem schlechten { $bad }
+broke-bisected = git bisect hat den ersten schlechten Commit gefunden: { $sha }
+broke-bisect-running = ein Bisect läuft schon; beende ihn zuerst mit git bisect reset
+broke-suspects = Die wahrscheinlichsten Verursacher ({ $count })
+broke-suspect = [{ $sha }] { $summary }: zu { $likelihood } % wahrscheinlich, { $reason }
+broke-no-candidates = Zwischen { $good } und { $bad } gibt es keine Commits.
+bus-factor-title = Bus-Faktor
+bus-factor-overall = Bus-Faktor des Repositorys: { $count } (die wenigsten Personen, die zusammen mehr als die Hälfte der letzten Änderungen geschrieben haben)
+bus-factor-entry = { $path }: { $author } hat { $share } % von { $lines } geänderten Zeilen in { $commits } Commit(s) geschrieben ({ $risk })
+bus-factor-high = hohes Risiko
+bus-factor-medium = mittleres Risiko
+bus-factor-none = Kein Teil der Codebasis wird von einer einzelnen Person dominiert.
+bus-factor-knowledge = Gefährdetes Wissen
+offline-title = Zusammenfassung
+offline-authors = Autoren
+offline-files = Am häufigsten geänderte Dateien
+offline-types = Commit-Typen
+offline-summary-commits = Die { $commits } analysierten Commit(s) stammen von { $authors } Autor(en) zwischen { $first } und { $last }.
+offline-summary-top-author = Die meisten davon hat { $name } gemacht ({ $commits }).
+offline-summary-merges = { $count } davon sind Merges.
+offline-summary-lines = Zusammen ändern sie { $files } Datei(en), mit { $added } hinzugefügten und { $removed } entfernten Zeilen.
+offline-summary-top-file = Am häufigsten geändert wurde { $path } ({ $commits } Commit(s)).
+offline-summary-types = { $share } % der Commit-Nachrichten folgen Conventional Commits; der häufigste Typ ist { $kind } ({ $commits } Commit(s)).
+offline-summary-no-types = Keine der Commit-Nachrichten folgt Conventional Commits.
+offline-summary-breaking = { $count } Commit(s) sind als Breaking Changes markiert.
+offline-author-entry = { $name }: { $commits } Commit(s), +{ $added } -{ $removed } Zeilen
+offline-file-entry = { $path }: { $commits } Commit(s), +{ $added } -{ $removed } Zeilen
+offline-type-entry = { $kind }: { $commits } Commit(s)
+offline-type-other = nicht konventionell: { $commits } Commit(s)
+commit-title = Commit { $sha }: { $subject }
+commit-author = Autor: { $author }
+commit-date = Datum: { $date }
+commit-files = Geänderte Dateien ({ $count })
+commit-file-entry = { $path }: +{ $added } -{ $removed } Zeilen
+commit-explanation = Was er bewirkt
+diff-title-staged = Was ein Commit der gestagten Änderungen bewirkt
+diff-title-unstaged = Was die nicht gestagten Änderungen bewirken
+diff-title-all = Was die nicht committeten Änderungen bewirken
+adr-heading = [{ $id }] { $date }: { $summary }
+adr-none = In den letzten { $count } Commits wurden keine grundlegenden Änderungen (Austausch von Abhängigkeiten, Umstrukturierungen oder große Refactorings) gefunden.
+similar-heading = Commits ähnlich zu [{ $id }] { $summary }
+similar-heading-staged = Commits ähnlich zu den vorgemerkten Änderungen
+similar-staged = Vorgemerkte Änderungen
+similar-entry = { $id } ({ $date }, { $author }): { $summary } — { $score } % ähnlich, gemeinsam: { $shared }
+similar-summary-heading = Wie sie damit umgegangen sind
+similar-none = Keiner der letzten { $count } Commits ist ähnlich genug für einen Vergleich.
+similar-nothing-staged = Es ist nichts vorgemerkt. Merke Änderungen zuerst mit `git add` vor oder gib einen Commit an.
+mark-set = { $id } auf { $branch } als Lesezeichen gesetzt. `wtf since-last` erklärt, was sich danach ändert.
+since-last-no-mark = Auf { $branch } gibt es noch kein Lesezeichen. Setze eines mit `wtf mark`.
+since-last-lost = Der Commit mit dem Lesezeichen auf { $branch } existiert nicht mehr (wurde der Branch umgeschrieben?). Setze mit `wtf mark` ein neues Lesezeichen.
+since-last-nothing = Nichts Neues seit deinem letzten Blick ({ $date }).
+range-empty = In { $range } gibt es keine Commits.
+branch-nothing-new = { $branch } hat keine Commits, die { $base } nicht hat.
+pr-title = Titel
+pr-summary = Zusammenfassung
+pr-changes = Änderungen
+pr-testing = Tests
+pr-mr-summary = Was macht dieser MR und warum?
+pr-mr-testing = So richtest du es lokal ein und prüfst es
+review-title = Review von { $target }
+review-staged = vorgemerkten Änderungen
+review-overview = { $files } Datei(en) geprüft: { $high } hoch, { $medium } mittel, { $low } niedrig
+review-nothing = Nichts zu prüfen: Jede geänderte Datei ist generiert, eingebunden, ausgeschlossen oder binär.
+review-lines = Zeilen { $lines }
+review-high = hoch
+review-medium = mittel
+review-low = niedrig
+review-bug = Fehler
+review-risk = Risiko
+review-tests = Tests
+review-naming = Benennung
+review-other = Sonstiges
+workspace-title = Arbeitsbereich: { $count } Repositories
+workspace-column-repository = Repository
+workspace-column-branch = Branch
+workspace-column-state = Zustand
+workspace-detached = (losgelöst)
+workspace-clean = sauber, aktuell
+workspace-staged = { $count } vorgemerkt
+workspace-modified = { $count } geändert
+workspace-untracked = { $count } unversioniert
+workspace-conflicted = { $count } mit Konflikten
+workspace-ahead-behind = { $ahead } voraus, { $behind } zurück
+workspace-no-upstream = kein Upstream
+workspace-summary = In Arbeit
+workspace-none = Keine Git-Repositories in { $path } gefunden.
+status-title = Zustand des Repositorys
+status-next = Was das heißt und wie es weitergeht
+status-branch = Auf Branch { $branch }
+status-unborn = Auf Branch { $branch }, der noch keine Commits hat
+status-detached = HEAD ist losgelöst bei { $commit }
+status-operation = Ein git { $operation } ist im Gange
+status-incoming = Hereinkommend: { $commit }
+status-rebase-step = Bei Schritt { $step } von { $total }
+status-conflicts = { $count } Datei(en) mit Konflikten: { $paths }
+status-changes = { $staged } gestagt, { $modified } geändert, { $untracked } nicht verfolgt
+status-upstream = { $ahead } vor und { $behind } hinter { $upstream }
+status-no-upstream = Der Branch hat keinen Upstream
+status-stashes = { $count } Stash(es) gespeichert
+config-title = Repository-Konfiguration
+config-remotes = Remotes
+config-hooks = Hooks in { $path }
+config-none = (keine)
+config-remote-push = { $name }: holt von { $url }, pusht nach { $push }
+config-hook-not-executable = nicht ausführbar, Git überspringt ihn
+config-hook-unknown = kein Hook-Name, den Git kennt, läuft nie
+session-title = wtf-Sitzung { $id }
+session-about = { $repository }, { $mode }, begonnen { $date }
+session-commits = Commits: { $commits }
+session-list-entry = { $id }  { $mode }  { $count } Antwort(en)  { $repository }
+session-none = Noch keine gespeicherten Sitzungen. Sitzungen zeichnen `wtf tui` und `wtf chat` auf.
+session-saved = Sitzung als { $id } gespeichert. Teilen kannst du sie mit `wtf session export { $id } --format markdown`.
+session-tui-question = Erkläre Commit [{ $id }] { $summary }
+adr-written = { $path } geschrieben
+review-effort-entry = { $effort } ({ $score }/100): { $files } Datei(en), { $hunks } Hunk(s), +{ $added } -{ $removed } Zeilen, { $tests }
+effort-low = gering
+effort-medium = mittel
+effort-high = hoch
+effort-no-code = keine Codeänderungen
+effort-no-tests = keine Teständerungen
+effort-test-ratio = Verhältnis Test zu Code { $ratio }
+api-removed = Entfernt (inkompatibel)
+api-changed = Geändert (möglicherweise inkompatibel)
+api-added = Hinzugefügt
+api-change = { $before } wurde zu { $after }
+ci-attention = Sollte auf Sicherheit geprüft werden:
+ci-finding = { $path } [{ $sha }]: { $risk }: { $line }
+ci-risk-secret = verwendet ein Secret
+ci-risk-permissions = ändert die Berechtigungen des CI-Tokens
+ci-risk-privileged-trigger = fügt einen Auslöser hinzu, der Code aus Forks mit Zugriff auf Secrets ausführen kann
+ci-risk-unpinned-action = verwendet eine fremde Action, die nicht auf einen Commit-SHA festgelegt ist
+ci-risk-remote-script = leitet ein heruntergeladenes Skript direkt in eine Shell
+ci-risk-self-hosted = läuft auf einem selbst gehosteten Runner
+kind-feature = Funktion
+kind-fix = Fehlerbehebung
+kind-refactor = Refactoring
+kind-docs = Dokumentation
+kind-chore = Wartung
+risk-low = niedriges
+risk-medium = mittleres
+risk-high = hohes
+classification-count = { $count } von { $total } Commit(s) ({ $share } %)
+classification-risks = Risiko: { $high } hoch, { $medium } mittel, { $low } niedrig
+classification-entry = { $kind }, { $risk } Risiko: { $reason }
+
+## Progress
+
+progress-found-commits = { $total } Commits gefunden, { $count } werden analysiert.
+progress-analyzing-commit = Analysiere Commit { $current } von { $total }...
+progress-refining = Überarbeite den Berichtsentwurf...
+progress-topics = Gruppiere die Commits nach Themen...
+progress-sending = Sende Anfrage an die { $provider }-API...
+progress-retrying = Anfrage fehlgeschlagen ({ $reason }); neuer Versuch in { $seconds } s (Versuch { $attempt } von { $attempts })...
+progress-received = Antwort der { $provider }-API erhalten
+progress-cached = Verwende die zwischengespeicherte Antwort auf eine identische frühere Anfrage.
+redaction-summary = { $count } mögliche(s) Geheimnis(se) vor dem Senden maskiert: { $rules }. Übergib --no-redact, um sie unverändert zu senden.
+progress-estimate = Dieser Lauf sendet { $requests } Anfrage(n) mit etwa { $tokens } Prompt-Tokens, für etwa { $cost }, zuzüglich zur Korrektur zurückgeschickter JSON-Antworten.
+progress-estimate-unpriced = Dieser Lauf sendet { $requests } Anfrage(n) mit etwa { $tokens } Prompt-Tokens, zuzüglich zur Korrektur zurückgeschickter JSON-Antworten.
+cost-summary = { $input } Eingabe- und { $output } Ausgabe-Tokens in { $requests } Anfrage(n) verbraucht, etwa { $cost }.
+cost-summary-unpriced = { $input } Eingabe- und { $output } Ausgabe-Tokens in { $requests } Anfrage(n) verbraucht.
+progress-reask = Strukturierte Antwort war ungültig ({ $errors }), frage erneut ({ $attempt } von { $max })...
+progress-timeline = { $count } Commits gefunden, die die Datei geändert haben.
+progress-timeline-era = Fasse Abschnitt { $current } von { $total } zusammen...
+progress-blame = { $lines } Zeilen gehen auf { $count } Commits zurück.
+progress-stashes = { $count } Stashes gefunden.
+progress-release = Das Release hat { $count } Commits.
+progress-conflicts = Erkläre { $count } Datei(en) mit Konflikten.
+progress-broke = Bewerte { $count } mögliche Commit(s).
+progress-pr = Beschreibe { $count } Commit(s) für den Pull Request.
+progress-review = Prüfe { $count } Datei(en).
+progress-changelog = Sortiere { $count } Commit(s) ins Changelog ein.
+progress-release-notes = Schreibe Release Notes für { $count } Commit(s).
+progress-digest = Schreibe den Rückblick auf { $count } Commit(s).
+progress-onboard = Lese den Baum ({ $count } Dateien) und seine jüngste Geschichte.
+progress-arch = Wähle Beispieldateien aus { $count } Modul(en) aus.
+progress-readme = Lese { $count } Dateipfad(e) für die README.
+progress-ask = Lese { $count } Commit(s) für die Suche.
+progress-semver = Bewerte den Versionssprung von { $count } Commit(s).
+progress-breaking = { $count } mögliche inkompatible Änderung(en) in { $commits } Commit(s) gefunden.
+progress-lint = { $count } von { $checked } Commit-Nachricht(en) bestehen die Prüfung nicht.
+progress-standup = { $count } Commit(s) von { $author } gefunden.
+changelog-updated = { $count } Eintrag/Einträge zu { $path } hinzugefügt.
+changelog-up-to-date = Das Changelog erwähnt schon jeden Commit seit dem letzten Tag.
+notes-highlights = Highlights
+notes-breaking = Inkompatible Änderungen
+notes-upgrade = Aktualisieren
+notes-contributors = Mitwirkende
+notes-contributor = { $name } ({ $count } Commit(s))
+notes-full-changelog = Alle Änderungen: { $url }
+notes-commits = { $count } Commit(s) in { $range }
+digest-title-week = Wochenrückblick, { $from } bis { $to }
+digest-title-month = Monatsrückblick, { $from } bis { $to }
+digest-features = Neue Funktionen
+digest-fixes = Fehlerbehebungen
+digest-stats = In Zahlen
+digest-stats-line = { $commits } Commit(s) von { $contributors } Mitwirkenden, { $files } Datei(en) geändert, +{ $added } -{ $removed } Zeilen.
+digest-empty = Keine Commits von { $from } bis { $to }.
+onboard-title = Willkommen bei { $repo }
+onboard-layout = So ist der Baum aufgebaut
+onboard-entry-points = Einstiegspunkte
+onboard-key-modules = Wichtige Module
+onboard-commands = Bauen und testen
+onboard-no-commands = Kein Build-Manifest im Wurzelverzeichnis gefunden.
+onboard-step-setup = Einrichten
+onboard-step-build = Bauen
+onboard-step-test = Testen
+onboard-step-lint = Prüfen
+onboard-step-run = Starten
+onboard-active = Zuletzt aktive Bereiche
+onboard-area = { $area }: { $count } Commit(s), zuletzt am { $date }
+arch-title = Architektur von { $repo }
+arch-modules = Module
+arch-files = { $count } Datei(en)
+arch-dependencies = Abhängigkeiten
+readme-unchanged = Der Vorschlag stimmt mit { $path } überein.
+ask-answer = Antwort
+ask-sources = Quellen
+ask-nothing = Nichts in der Historie oder im Baum passt zur Frage; versuche andere Wörter oder gib mit --pickaxe eine Zeichenkette an, nach der die Änderungen durchsucht werden.
+chat-welcome = Frag nach { $repo }, eine Frage nach der anderen. Gib /help für die Befehle ein, /quit zum Beenden.
+chat-help =
+    Fragen gehen an das Modell, das die vorigen Fragen und seine Antworten kennt.
+    /diff [Commit]: zeigt den Diff eines Commits, standardmäßig des Commits, um den es in der letzten Antwort ging
+    /show [Commit]: zeigt die Nachricht und die geänderten Dateien eines Commits
+    /history: listet die bisherigen Fragen auf
+    /clear: vergisst das bisherige Gespräch
+    /quit: beendet den Chat
+    "show me the diff for that commit" und "show abc1234" funktionieren auch; was sie zeigen, geht mit der nächsten Frage an das Modell.
+chat-no-commit = Noch kein Commit zum Anzeigen; nenne einen, etwa /diff abc1234.
+chat-no-history = Noch keine Fragen gestellt.
+chat-cleared = Das Gespräch ist vergessen; die nächste Frage beginnt von vorn.
+chat-unknown-command = Unbekannter Befehl /{ $name }. Gib /help für die Befehle ein.
+semver-title = Versionssprung
+semver-commits = Commits
+semver-next = { $bump }: { $since } → { $next }
+semver-bump = { $bump }
+semver-no-release = Kein Release nötig: Nichts, was Nutzer bemerken würden, hat sich geändert.
+semver-no-commits = Seit dem letzten Tag gibt es keine Commits.
+semver-reason-breaking = Als inkompatible Änderung markiert.
+semver-reason-feat = Eine neue Funktion.
+semver-reason-fix = Ein { $kind }-Commit.
+semver-reason-unnoticed = Ein { $kind }-Commit, den Nutzer nicht bemerken.
+semver-reason-api-breaking = Entfernt oder ändert öffentliche API: { $items }.
+semver-reason-api-added = Fügt öffentliche API hinzu: { $items }.
+semver-reason-unrated = Vom Modell nicht bewertet.
+semver-more-items = { $count } weitere
+breaking-title = Inkompatible Änderungen in { $range }
+breaking-overview = { $count } wahrscheinlich inkompatible Änderung(en) in { $commits } Commit(s).
+breaking-none = Keine wahrscheinlich inkompatiblen Änderungen in { $commits } Commit(s).
+breaking-api = Öffentliche API
+breaking-cli = Kommandozeilenoptionen
+breaking-config = Konfiguration
+breaking-migration = Datenbankmigrationen
+breaking-api-removed = `{ $signature }` entfernt.
+breaking-api-changed = Von `{ $before }` zu `{ $after }` geändert.
+lint-title = Commit-Nachrichten in { $range }
+lint-passed = Alle { $checked } Commit-Nachricht(en) folgen Conventional Commits.
+lint-failed = { $count } von { $checked } Commit-Nachricht(en) folgen nicht Conventional Commits.
+lint-failures = Fehlerhafte Nachrichten
+lint-header = die Kopfzeile hat nicht die Form type(scope): description
+lint-body-spacing = keine Leerzeile vor dem Text
+lint-footer-case = BREAKING CHANGE ist nicht großgeschrieben
+lint-suggestion = Vorschlag: { $header }
+standup-title = Standup für { $author }
+standup-overview = { $count } Commit(s) seit { $date }.
+standup-done = Erledigt
+standup-in-progress = In Arbeit
+standup-blockers = Blocker
+standup-none = Keine
+standup-nothing = Keine Commits von { $author } seit { $date }.
+progress-committed = { $sha } committet.
+hook-installed = Der Hook { $hook } wurde in { $path } installiert.
+hook-uninstalled = Der Hook { $hook } wurde aus { $path } entfernt.
+hook-not-installed = Der Hook { $hook } ist nicht installiert.
+hook-skipped = wtf hat den Hook { $hook } übersprungen: { $reason }
+progress-summarizing-commits = Fasse die Commits stapelweise zusammen: Stapel { $current } von { $total }...
+progress-summarizing-edits = Fasse die Änderungen stapelweise zusammen: Stapel { $current } von { $total }...
+progress-summarizing-diff = Fasse einen großen Diff in Teilen zusammen: Teil { $current } von { $total }...
+progress-adr-scanning = Suche in { $count } Commits nach grundlegenden Änderungen...
+progress-adr = Entwerfe ADR { $current } von { $total }: { $summary }
+progress-similar-scanning = Vergleiche mit { $count } Commits...
+progress-similar = Fasse { $count } ähnliche(n) Commit(s) zusammen...
+progress-workspace-scanning = Prüfe { $count } Repositories...
+progress-workspace = Fasse { $count } Repositories zusammen...
+progress-config-explain = Erkläre { $settings } Einstellung(en) und { $hooks } Hook(s)...
+owners-orphaned = { $count } Bereich(e) haben keinen Owner mit einem Commit in den letzten { $days } Tagen; sie sind im Entwurf auskommentiert.
+strict-dropped = Strikter Modus: { $count } Aussage(n) ohne Commit-Beleg entfernt.
+guard-warning = Warnung: Der Bericht erwähnt { $count } Datei(en) oder Commit(s), die in den analysierten Daten nicht vorkommen.
+context-trimmed = Warnung: Die Daten für den Abschnitt { $section } passten nicht in das Kontextfenster des Modells von { $tokens } Tokens und wurden gekürzt; analysiere weniger Commits für das ganze Bild.
+confirm-large-run = Dieser Lauf sendet { $requests } Anfrage(n) mit etwa { $tokens } Prompt-Tokens. Fortfahren? [y/N]
+confirm-costly-run = Dieser Lauf kostet etwa { $cost }, mehr als --max-cost erlaubt ({ $max }). Fortfahren? [y/N]
+unsupported-ui-lang = Warnung: Keine Übersetzung für "{ $lang }" vorhanden, verwende Englisch.
+paths-unsupported = `wtf { $command }` nimmt kein --path; nur der Bericht, pick, since-last, range und branch beschränken ihre Commits auf Pfade.
+offline-unsupported = `wtf { $command }` nimmt kein --offline; nur der Bericht von `wtf --offline` selbst kommt ohne Modell aus.
+
+## Errors
+
+error-label = Fehler
+hint-label = Tipp
+error-not-a-repo = Git-Repository unter { $path } konnte nicht geöffnet werden: { $reason }
+error-no-commits = Im Repository wurden keine Commits gefunden
+error-no-commits-in-paths = Keiner der Commits ändert etwas unter { $paths }
+error-missing-credentials = { $var } nicht gefunden
+error-provider = Fehler der Anbieter-API (HTTP { $status }): { $body }
+error-gave-up = Nach { $attempts } Versuchen aufgegeben. { $error }
+error-context-too-large = Der Prompt ist zu groß für das Kontextfenster des Modells: { $body }
+error-safety-cap = Dieser Lauf würde { $requests } Anfrage(n) mit etwa { $tokens } Prompt-Tokens senden und überschreitet damit die Sicherheitsgrenze
+error-cost-cap = Dieser Lauf würde etwa { $cost } kosten, mehr als --max-cost erlaubt ({ $max })
+error-invalid-response = Ungültige Antwort der OpenAI-API: { $reason }
+error-invalid-structured = Die strukturierte Antwort entsprach nicht dem erwarteten Format: { $errors }
+error-git = Git-Fehler: { $reason }
+error-http = Anfrage an die OpenAI-API fehlgeschlagen: { $reason }
+error-io = E/A-Fehler: { $reason }
+error-no-config-dir = Es konnte kein Verzeichnis für Einstellungen ermittelt werden
+error-network-disabled = Dieser Build von wtf hat keine Netzwerkunterstützung und kann daher keinen Modellanbieter erreichen
+error-not-interactive = Dieser Befehl braucht ein interaktives Terminal
+error-unknown-path = Kein von HEAD erreichbarer Commit betrifft { $path }
+error-no-such-lines = { $path } hat nur { $count } Zeilen
+error-unknown-revision = Kein Commit heißt „{ $rev }“
+error-unknown-tag = Es gibt kein Tag namens „{ $name }“
+error-no-conflicts = Es gibt keine Konflikte aufzulösen
+error-bisect-failed = git bisect ist fehlgeschlagen: { $output }
+error-editor-failed = Der Editor „{ $editor }“ ließ sich nicht starten oder hat sich nicht sauber beendet
+error-empty-message = Die Commit-Nachricht ist leer, also wurde nichts committet
+error-no-identity = Git weiß nicht, in wessen Namen committet werden soll
+error-foreign-hook = { $path } wurde nicht von wtf installiert und bleibt daher unangetastet
+error-not-conventional = „{ $header }“ ist keine Kopfzeile im Conventional-Commits-Format
+error-lint-failed = { $count } Commit-Nachricht(en) folgen nicht der Conventional-Commits-Spezifikation
+error-no-changes = Es gibt keine nicht committeten Änderungen zu erklären
+error-no-staged-changes = Es gibt keine gestagten Änderungen zu erklären
+error-no-base-branch = Es gibt keinen Basis-Branch zum Vergleichen
+error-invalid-config = Ungültige Konfiguration in { $path }: { $reason }
+error-unknown-session = Keine gespeicherte Sitzung mit der ID „{ $id }“.
+error-unknown-provider = Unbekannter Provider „{ $name }“ in WTF_PROVIDER.
+error-missing-setting = { $var } ist nicht gesetzt
+error-invalid-setting = Ungültige Einstellung { $key } „{ $value }“: { $reason }
+error-keyring = Der Schlüsselbund des Systems ließ sich nicht verwenden: { $reason }
+config-no-components = keine [components]-Tabelle mit Einträgen der Form name = "muster" oder name = ["muster", ...]
+prompts-not-a-directory = kein Verzeichnis mit Prompt-Vorlagen
+prompts-unknown-variable = unbekannte Variable { $name }; Vorlagen können { $known } verwenden
+unknown-task = unbekannte Aufgabe „{ $name }“, erwartet wird eine von { $names }
+sampling-out-of-range = { $key } muss zwischen { $min } und { $max } liegen
+group-by-invalid = „{ $spec }“ ist keine Gruppierung; möglich sind dir, dir:<tiefe> oder components
+group-by-bad-depth = „{ $depth }“ ist keine Verzeichnistiefe; gib eine Zahl ab 1 an
+since-invalid = „{ $spec }“ ist kein Zeitpunkt; möglich sind today, yesterday, ein Datum wie 2024-05-01 oder ein Alter wie „3 days ago“
+
+## Hints
+
+hint-not-a-repo = Starte wtf innerhalb eines Git-Repositorys oder gib den Pfad des Repositorys als erstes Argument an.
+hint-no-commits = Lege mindestens einen Commit an und starte wtf dann erneut.
+hint-no-commits-in-paths = Pfade für --path gelten relativ zur Wurzel des Repositorys; prüfe sie mit `git log -- <Pfad>`.
+hint-missing-credentials = Setze { $var } in der Umgebung, übergib --api-key oder lege neben dem Repository eine .env-Datei mit { $var }=dein_api_schluessel an. Oder führe `wtf --offline` aus, für einen Bericht allein aus der Git-Historie.
+hint-unauthorized = Prüfe, ob dein API-Schlüssel gültig ist und nicht widerrufen wurde.
+hint-rate-limited = Du wurdest gedrosselt oder dein Kontingent ist aufgebraucht. Warte kurz oder prüfe deine Abrechnungseinstellungen.
+hint-provider-down = Der Anbieter hat Probleme. Versuche es in ein paar Minuten erneut.
+hint-context-too-large = Analysiere mit --num-commits weniger Commits, um den Prompt zu verkleinern.
+hint-safety-cap = Übergib --force, um trotzdem fortzufahren, erhöhe --max-requests/--max-prompt-tokens oder analysiere weniger Commits.
+hint-cost-cap = Übergib --force, um trotzdem fortzufahren, erhöhe --max-cost oder analysiere weniger Commits.
+hint-http = Prüfe deine Netzwerkverbindung.
+hint-no-config-dir = Setze HOME (oder WTF_CONFIG_DIR) und versuche es erneut.
+hint-network-disabled = Installiere wtf mit den Standard-Features neu (cargo install --path .), um gehostete Modelle zu nutzen, oder führe `wtf --offline` aus, für einen Bericht allein aus der Git-Historie.
+hint-not-interactive = Starte ihn direkt in einem Terminal, ohne Ein- oder Ausgabe umzuleiten.
+hint-unknown-path = Gib den Pfad relativ zum Wurzelverzeichnis des Repositorys an, so wie ihn `git log --follow` erwartet.
+hint-unknown-revision = Gib einen SHA, Branch, Tag oder sonst etwas an, das `git rev-parse` versteht, etwa HEAD~2.
+hint-unknown-tag = `git tag --list` zeigt, welche Tags es gibt.
+hint-no-staged-changes = Stage Änderungen mit `git add`, oder lass --staged weg, um das Arbeitsverzeichnis zu erklären.
+hint-no-base-branch = Gib ihn mit --base an, oder setze base_branch in .wtf.toml.
+hint-bisect-failed = Zum Bisecten braucht es git im PATH und ein Arbeitsverzeichnis ohne uncommittete Änderungen; der Testbefehl muss bei guten Commits mit 0 und bei schlechten mit 1 bis 127 (außer 125) enden.
+hint-editor-failed = Leg den Editor mit GIT_EDITOR, core.editor, VISUAL oder EDITOR fest.
+hint-no-identity = Leg ihn mit git config --global user.name "Dein Name" und git config --global user.email du@example.com fest.
+hint-foreign-hook = Leg ihn beiseite oder führe ihn von Hand zusammen, oder überschreib ihn mit --replace.
+hint-not-conventional = Beginne die Nachricht etwa mit fix(parser): handle empty files, oder überspring die Prüfung mit git commit --no-verify.
+hint-lint-failed = Führe wtf lint-commits --suggest aus, um umgeschriebene Kopfzeilen zu bekommen, und formuliere die Commits dann mit git rebase -i um.
+hint-invalid-config = Das erwartete Format steht im Abschnitt „Configuration“ der README.
+hint-unknown-session = Mit `wtf session list` siehst du die gespeicherten Sitzungen und ihre IDs.
+hint-unknown-provider = Setze WTF_PROVIDER (oder --provider) auf openai, anthropic, gemini, azure, bedrock oder ollama.
+hint-missing-setting = Setze { $var } in der Umgebung oder in einer .env-Datei neben dem Repository.
+hint-keyring = Installiere unter Linux secret-tool (Paket libsecret-tools) und starte einen Schlüsselbund wie GNOME Keyring. Sonst setze den Schlüssel in der Umgebung oder in einer .env-Datei neben dem Repository.
+
+## Telemetry
+
+telemetry-on = Telemetrie ist jetzt an. Danke! Mit `wtf telemetry off` schaltest du sie wieder ab.
+telemetry-off = Telemetrie ist jetzt aus. Es wird nichts gesendet.
+telemetry-status-on = Telemetrie ist an.
+telemetry-status-off = Telemetrie ist aus (Standard). Mit `wtf telemetry on` schaltest du sie ein.
+telemetry-no-endpoint = Dieser Build hat keinen Telemetrie-Endpunkt, es wird also ohnehin nichts gesendet.
+telemetry-fields = Wenn sie an ist, meldet jeder Lauf nur: wtf-Version, Betriebssystem, Unterbefehl, eine Dauerklasse (z. B. "10-60s") und eine Fehlerklasse (z. B. "auth"). DO_NOT_TRACK=1 schaltet sie ab.
+auth-prompt = Füge deinen { $var } ein (er wird nicht angezeigt):
+auth-empty = Kein Schlüssel angegeben, es wurde nichts gespeichert.
+auth-stored = { $var } ist im Schlüsselbund des Systems gespeichert. wtf liest ihn dort, wann immer die Umgebung keinen hat.
+auth-removed = { $var } wurde aus dem Schlüsselbund des Systems entfernt.
+auth-no-key = Der Anbieter { $provider } braucht keinen API-Schlüssel, es gibt also nichts zu speichern.
+keyring-unsupported = unter { $os } nicht unterstützt
+keyring-line-break = der Schlüssel enthält einen Zeilenumbruch; füge ihn in einer einzigen Zeile ein
+
+## Crash reports
+
+crash-saved = wtf ist auf ein unerwartetes Problem gestoßen. Ein Absturzbericht wurde unter { $path } gespeichert. Mit `wtf crash-report --submit` kannst du ihn als Issue melden.
+crash-none = Keine Absturzberichte gefunden.
+crash-latest = Neuester Absturzbericht ({ $path }):
+crash-submit-hint = Prüfe ihn auf private Angaben und öffne dann mit `wtf crash-report --submit` ein Issue damit.
+crash-gh-missing = Das `gh`-CLI konnte nicht gestartet werden. Öffne stattdessen { $url } und hänge { $path } an.
+
+## Interactive views
+
+tui-title = wtf: { $count } Commits
+tui-help = Hoch/Runter auswählen  Enter erklären  Tab Erklärung/Diff  Bild auf/ab blättern  / suchen  q beenden
+tui-no-commits = Keine Commits passen zur Suche.
+tui-commit = Commit:
+tui-author = Autor:
+tui-press-enter = Drücke Enter, um diesen Commit erklären zu lassen.
+tui-no-diff = Keine Änderungen vorhanden.
+tui-explaining = Erkläre den ausgewählten Commit...
+pick-help = Tippen zum Suchen  Hoch/Runter bewegen  Tab markieren  Enter analysieren  Esc abbrechen  ({ $marked } markiert, { $count } angezeigt)
+pick-cancelled = Nichts ausgewählt.
+watch-started = Beobachte { $path } auf neue Commits (Strg-C zum Beenden)...
+watch-skipped = Überspringe die { $count } ältesten neuen Commit(s), um nur die neuesten { $max } zu erklären (--max-commits); erkläre sie mit `wtf commit <rev>`.
+
+## Ignore and attribute rules
+
+attrs-rule = { $pattern } ({ $source } Zeile { $line })
+attrs-entry = { $effect }, festgelegt durch { $rule }
+attrs-none = Es gelten keine Attribute.
+attrs-nothing = Für die in den letzten { $count } Commits geänderten Dateien gelten keine Ignore- oder Attributregeln.
+ignore-no = Nicht ignoriert.
+ignore-yes = Ignoriert wegen { $rule }.
+ignore-via-dir = Ignoriert, weil sein Verzeichnis { $dir } durch { $rule } ignoriert wird; Dateien in einem ignorierten Verzeichnis lassen sich nicht wieder einschließen.
+ignore-reincluded = Nicht ignoriert: { $rule } schließt es wieder ein.
+ignore-untraced = Ignoriert, durch eine Regel, die wtf nicht zurückverfolgen konnte (`git check-ignore -v` zeigt sie).
+ignore-tracked = Es wird trotzdem versioniert: Ignore-Regeln halten nur unversionierte Dateien aus `git add` heraus.
+attr-text = Als Text behandelt: Zeilenenden werden beim Committen zu LF vereinheitlicht
+attr-no-text = Nie als Text behandelt: Git lässt die Zeilenenden unverändert
+attr-text-auto = Git entscheidet, ob es Text ist; wenn ja, werden Zeilenenden beim Committen zu LF vereinheitlicht
+attr-eol = Wird mit { $eol }-Zeilenenden ausgecheckt
+attr-binary = Als binär markiert: keine Umwandlung der Zeilenenden, keine Text-Diffs und kein zeilenweises Mergen
+attr-no-diff = Diffs zeigen nur, dass es sich geändert hat, nicht wie
+attr-diff-driver = Diffs verwenden den Diff-Treiber „{ $driver }“
+attr-no-merge = Wird nie zeilenweise gemergt: bei Konflikten muss eine der Versionen als Ganzes gewählt werden
+attr-merge-driver = Wird mit dem Merge-Treiber „{ $driver }“ gemergt
+attr-lfs = Mit Git LFS gespeichert: das Repository enthält nur einen kleinen Verweis, der Inhalt liegt auf dem LFS-Server
+attr-filter = Läuft beim Auschecken und Committen durch den Filter „{ $filter }“
+attr-export-ignore = Fehlt in Archiven, die mit `git archive` erstellt werden
+attr-generated = Als generiert markiert: GitHub klappt es in Diffs zu und lässt es aus der Sprachstatistik heraus
+attr-vendored = Als fremder Code markiert: GitHub lässt es aus der Sprachstatistik heraus
+attr-encoding = Im Repository als UTF-8 gespeichert und als { $encoding } ausgecheckt
+attr-other = Setzt { $setting }
@@ -0,0 +1,503 @@
+# English messages for wtf. This is the reference catalog: every other
+# catalog falls back to it for missing messages.
+
+## Report section headers
+
+section-project = Project Description
+section-commits = Last { $count } Commits in Plain Language
+section-range = Changes Between { $from } and { $to } ({ $count } Commits)
+section-edits = Detailed Analysis of Recent Edits
+section-ci = Build and CI Changes
+section-unverified = Unverified References
+section-review-effort = Review Effort
+section-public-api = Public API Changes
+section-classification = Commit Classification
+section-revised = { $title } (checked)
+group-root = (top level)
+group-other = Other
+topic-other = Other changes
+unverified-intro = These files or commits were mentioned but not found in the analyzed data:
+unverified-note = these files or commits were mentioned but not found in the analyzed data:
+note-label = Note
+single-commit-edits = Repository has only one commit, so there are no previous versions to compare changes against.
+generated-churn = Generated/vendored churn, left out of the analysis: { $files } file(s), +{ $added } -{ $removed } lines ({ $paths })
+generated-churn-more = { $paths } and { $count } more
+accessible-section = Section { $index } of { $total }: { $title }
+accessible-commit = commit { $sha }
+accessible-commits = commits { $shas }
+accessible-end = End of report.
+timeline-title = History of { $path }
+timeline-era = { $path }, { $start } to { $end }: { $count } commit(s) by { $authors }
+blame-title = Why lines { $start }-{ $end } of { $path } look the way they do
+blame-commits = Commits behind these lines ({ $count })
+blame-commit-entry = [{ $sha }] { $summary } ({ $author }, { $date }; { $count } line(s))
+blame-invalid-lines = "{ $lines }" is not a line range; give one like 120-160, or a single line number
+stash-title = Stashes ({ $count })
+stash-entry = { $name }: { $message } ({ $date }, { $files } file(s))
+stash-explanation = What They Hold
+stash-none = There are no stashes.
+tag-title = What's in { $tag }
+tag-about = About the Release
+tag-since = { $count } commit(s) since { $previous }, { $files } file(s) changed (+{ $added } -{ $removed } lines)
+tag-first = The first release: { $count } commit(s), { $files } file(s) (+{ $added } -{ $removed } lines)
+tag-tagged-by = Tagged by { $tagger }, on a commit from { $date }
+tag-lightweight = A lightweight tag, on a commit from { $date }
+conflict-title = Conflicts
+conflict-files = { $count } file(s) with conflicts
+reflog-title = What Just Happened
+reflog-movements = HEAD Movements ({ $count })
+reflog-entry = { $name }, { $ago }: { $message }
+reflog-lost = Left Behind ({ $count })
+reflog-lost-entry = { $sha } { $summary } (see { $seen })
+reflog-nothing-lost = Every commit HEAD was on is still reached by a branch, tag or stash.
+reflog-just-now = just now
+reflog-minutes-ago = { $count } minute(s) ago
+reflog-hours-ago = { $count } hour(s) ago
+reflog-days-ago = { $count } day(s) ago
+reflog-empty = HEAD has no reflog yet.
+broke-title = What Broke: { $symptom }
+broke-range = { $count } candidate commit(s) between good { $good } and bad { $bad }
+broke-bisected = git bisect found the first bad commit: { $sha }
+broke-bisect-running = a bisect is already under way; finish it with git bisect reset first
+broke-suspects = Most Likely Culprits ({ $count })
+broke-suspect = [{ $sha }] { $summary }: { $likelihood }% likely, { $reason }
+broke-no-candidates = There are no commits between { $good } and { $bad }.
+bus-factor-title = Bus factor
+bus-factor-overall = Repository bus factor: { $count } (the fewest people who together wrote more than half of the recent changes)
+bus-factor-entry = { $path }: { $author } wrote { $share }% of { $lines } changed lines in { $commits } commit(s) ({ $risk })
+bus-factor-high = high risk
+bus-factor-medium = medium risk
+bus-factor-none = No part of the code base is dominated by a single author.
+bus-factor-knowledge = Knowledge at risk
+offline-title = Summary
+offline-authors = Authors
+offline-files = Most changed files
+offline-types = Commit types
+offline-summary-commits = The { $commits } analyzed commit(s) were made by { $authors } author(s) between { $first } and { $last }.
+offline-summary-top-author = { $name } made the most of them ({ $commits }).
+offline-summary-merges = { $count } of them are merges.
+offline-summary-lines = Together they changed { $files } file(s), adding { $added } and removing { $removed } lines.
+offline-summary-top-file = The most frequently changed file is { $path } ({ $commits } commit(s)).
+offline-summary-types = { $share }% of the commit messages follow Conventional Commits; the most common type is { $kind } ({ $commits } commit(s)).
+offline-summary-no-types = None of the commit messages follow Conventional Commits.
+offline-summary-breaking = { $count } commit(s) are marked as breaking changes.
+offline-author-entry = { $name }: { $commits } commit(s), +{ $added } -{ $removed } lines
+offline-file-entry = { $path }: { $commits } commit(s), +{ $added } -{ $removed } lines
+offline-type-entry = { $kind }: { $commits } commit(s)
+offline-type-other = not conventional: { $commits } commit(s)
+commit-title = Commit { $sha }: { $subject }
+commit-author = Author: { $author }
+commit-date = Date: { $date }
+commit-files = Files changed ({ $count })
+commit-file-entry = { $path }: +{ $added } -{ $removed } lines
+commit-explanation = What it does
+diff-title-staged = What committing the staged changes does
+diff-title-unstaged = What the unstaged changes do
+diff-title-all = What the uncommitted changes do
+adr-heading = [{ $id }] { $date }: { $summary }
+adr-none = No pivotal changes (dependency swaps, restructurings or large refactors) found in the last { $count } commits.
+similar-heading = Commits similar to [{ $id }] { $summary }
+similar-heading-staged = Commits similar to the staged changes
+similar-staged = Staged changes
+similar-entry = { $id } ({ $date }, { $author }): { $summary } — { $score }% similar, shares { $shared }
+similar-summary-heading = How They Handled It
+similar-none = No commit among the last { $count } is similar enough to compare with.
+similar-nothing-staged = Nothing is staged. Stage changes with `git add` first, or pass a commit.
+mark-set = Bookmarked { $id } on { $branch }. `wtf since-last` explains what changes after it.
+since-last-no-mark = No bookmark on { $branch } yet. Run `wtf mark` to set one.
+since-last-lost = The bookmarked commit on { $branch } no longer exists (was the branch rewritten?). Run `wtf mark` to set a new bookmark.
+since-last-nothing = Nothing new since you last looked ({ $date }).
+range-empty = There are no commits in { $range }.
+branch-nothing-new = { $branch } has no commits that { $base } does not have.
+pr-title = Title
+pr-summary = Summary
+pr-changes = Changes
+pr-testing = Testing
+pr-mr-summary = What does this MR do and why?
+pr-mr-testing = How to set up and validate locally
+review-title = Review of { $target }
+review-staged = staged changes
+review-overview = { $files } file(s) reviewed: { $high } high, { $medium } medium, { $low } low
+review-nothing = Nothing to review: every changed file is generated, vendored, excluded or binary.
+review-lines = lines { $lines }
+review-high = high
+review-medium = medium
+review-low = low
+review-bug = bug
+review-risk = risk
+review-tests = tests
+review-naming = naming
+review-other = other
+workspace-title = Workspace: { $count } repositories
+workspace-column-repository = Repository
+workspace-column-branch = Branch
+workspace-column-state = State
+workspace-detached = (detached)
+workspace-clean = clean, up to date
+workspace-staged = { $count } staged
+workspace-modified = { $count } modified
+workspace-untracked = { $count } untracked
+workspace-conflicted = { $count } conflicted
+workspace-ahead-behind = { $ahead } ahead, { $behind } behind
+workspace-no-upstream = no upstream
+workspace-summary = In Flight
+workspace-none = No git repositories found in { $path }.
+status-title = Repository Status
+status-next = What It Means and What to Do Next
+status-branch = On branch { $branch }
+status-unborn = On branch { $branch }, which has no commits yet
+status-detached = HEAD is detached at { $commit }
+status-operation = A git { $operation } is in progress
+status-incoming = Incoming: { $commit }
+status-rebase-step = At step { $step } of { $total }
+status-conflicts = { $count } file(s) with conflicts: { $paths }
+status-changes = { $staged } staged, { $modified } modified, { $untracked } untracked
+status-upstream = { $ahead } ahead of and { $behind } behind { $upstream }
+status-no-upstream = The branch has no upstream
+status-stashes = { $count } stash(es) saved
+config-title = Repository Configuration
+config-remotes = Remotes
+config-hooks = Hooks in { $path }
+config-none = (none)
+config-remote-push = { $name }: fetches from { $url }, pushes to { $push }
+config-hook-not-executable = not executable, Git skips it
+config-hook-unknown = not a hook name Git knows, never run
+session-title = wtf session { $id }
+session-about = { $repository }, { $mode }, started { $date }
+session-commits = Commits: { $commits }
+session-list-entry = { $id }  { $mode }  { $count } answer(s)  { $repository }
+session-none = No saved sessions yet. Sessions are recorded by `wtf tui` and `wtf chat`.
+session-saved = Session saved as { $id }. Share it with `wtf session export { $id } --format markdown`.
+session-tui-question = Explain commit [{ $id }] { $summary }
+adr-written = Wrote { $path }
+review-effort-entry = { $effort } ({ $score }/100): { $files } file(s), { $hunks } hunk(s), +{ $added } -{ $removed } lines, { $tests }
+effort-low = low
+effort-medium = medium
+effort-high = high
+effort-no-code = no code changes
+effort-no-tests = no test changes
+effort-test-ratio = test/code ratio { $ratio }
+api-removed = Removed (breaking)
+api-changed = Changed (possibly breaking)
+api-added = Added
+api-change = { $before } became { $after }
+ci-attention = Worth a security review:
+ci-finding = { $path } [{ $sha }]: { $risk }: { $line }
+ci-risk-secret = uses a secret
+ci-risk-permissions = changes the CI token's permissions
+ci-risk-privileged-trigger = adds a trigger that can run code from forks with access to secrets
+ci-risk-unpinned-action = uses a third-party action not pinned to a commit SHA
+ci-risk-remote-script = pipes a downloaded script into a shell
+ci-risk-self-hosted = runs on a self-hosted runner
+kind-feature = Feature
+kind-fix = Fix
+kind-refactor = Refactoring
+kind-docs = Documentation
+kind-chore = Chore
+risk-low = low
+risk-medium = medium
+risk-high = high
+classification-count = { $count } of { $total } commit(s) ({ $share }%)
+classification-risks = Risk: { $high } high, { $medium } medium, { $low } low
+classification-entry = { $kind }, { $risk } risk: { $reason }
+
+## Progress
+
+progress-found-commits = Found { $total } commits, will analyze { $count }.
+progress-analyzing-commit = Analyzing commit { $current } of { $total }...
+progress-refining = Refining the draft report...
+progress-topics = Grouping the commits into themes...
+progress-sending = Sending request to { $provider } API...
+progress-retrying = Request failed ({ $reason }); retrying in { $seconds }s (attempt { $attempt } of { $attempts })...
+progress-received = Received successful response from { $provider } API
+progress-cached = Using the cached reply to an identical earlier request.
+redaction-summary = Masked { $count } possible secret(s) before sending: { $rules }. Pass --no-redact to send them as they are.
+progress-estimate = This run will send { $requests } request(s) with about { $tokens } prompt tokens, for about { $cost }, plus any JSON replies sent back for correction.
+progress-estimate-unpriced = This run will send { $requests } request(s) with about { $tokens } prompt tokens, plus any JSON replies sent back for correction.
+cost-summary = Used { $input } input and { $output } output tokens in { $requests } request(s), about { $cost }.
+cost-summary-unpriced = Used { $input } input and { $output } output tokens in { $requests } request(s).
+progress-reask = Structured reply was invalid ({ $errors }), asking again ({ $attempt } of { $max })...
+progress-timeline = Found { $count } commits that changed the file.
+progress-timeline-era = Summarizing era { $current } of { $total }...
+progress-blame = Blamed { $lines } lines on { $count } commits.
+progress-stashes = Found { $count } stashes.
+progress-release = The release has { $count } commits.
+progress-conflicts = Explaining { $count } file(s) with conflicts.
+progress-broke = Rating { $count } candidate commit(s).
+progress-pr = Describing { $count } commit(s) for the pull request.
+progress-review = Reviewing { $count } file(s).
+progress-changelog = Sorting { $count } commit(s) into the changelog.
+progress-release-notes = Writing release notes for { $count } commit(s).
+progress-digest = Writing the digest of { $count } commit(s).
+progress-onboard = Reading the tree ({ $count } files) and its recent history.
+progress-arch = Sampling { $count } module(s).
+progress-readme = Reading { $count } file path(s) for the README.
+progress-ask = Reading { $count } commit(s) to search.
+progress-semver = Rating the version bump of { $count } commit(s).
+progress-breaking = Found { $count } candidate(s) for breaking changes in { $commits } commit(s).
+progress-lint = { $count } of { $checked } commit message(s) fail the checks.
+progress-standup = Found { $count } commit(s) by { $author }.
+changelog-updated = Added { $count } entry(ies) to { $path }.
+changelog-up-to-date = The changelog already mentions every commit since the last tag.
+notes-highlights = Highlights
+notes-breaking = Breaking changes
+notes-upgrade = Upgrading
+notes-contributors = Contributors
+notes-contributor = { $name } ({ $count } commit(s))
+notes-full-changelog = Full changelog: { $url }
+notes-commits = { $count } commit(s) in { $range }
+digest-title-week = Weekly digest, { $from } to { $to }
+digest-title-month = Monthly digest, { $from } to { $to }
+digest-features = Features
+digest-fixes = Fixes
+digest-stats = In numbers
+digest-stats-line = { $commits } commit(s) by { $contributors } contributor(s), { $files } file(s) changed, +{ $added } -{ $removed } lines.
+digest-empty = No commits from { $from } to { $to }.
+onboard-title = Welcome to { $repo }
+onboard-layout = How the tree is laid out
+onboard-entry-points = Entry points
+onboard-key-modules = Key modules
+onboard-commands = Building and testing
+onboard-no-commands = No build manifest found at the root.
+onboard-step-setup = Set up
+onboard-step-build = Build
+onboard-step-test = Test
+onboard-step-lint = Lint
+onboard-step-run = Run
+onboard-active = Recently active areas
+onboard-area = { $area }: { $count } commit(s), latest on { $date }
+arch-title = Architecture of { $repo }
+arch-modules = Modules
+arch-files = { $count } file(s)
+arch-dependencies = Dependencies
+readme-unchanged = The proposal is the same as { $path }.
+ask-answer = Answer
+ask-sources = Sources
+ask-nothing = Nothing in the history or the tree matches the question; try other words, or a string to search the changes for with --pickaxe.
+chat-welcome = Ask about { $repo }, one question after another. Type /help for the commands, /quit to leave.
+chat-help =
+    Questions go to the model, which sees the earlier ones and its answers.
+    /diff [commit]: print the diff of a commit, by default the one the last answer was about
+    /show [commit]: print the message and changed files of a commit
+    /history: list the questions asked so far
+    /clear: forget the conversation so far
+    /quit: leave the chat
+    "show me the diff for that commit" and "show abc1234" work too; what they print goes to the model with the next question.
+chat-no-commit = No commit to show yet; name one, like /diff abc1234.
+chat-no-history = No questions asked yet.
+chat-cleared = Forgot the conversation; the next question starts afresh.
+chat-unknown-command = Unknown command /{ $name }. Type /help for the commands.
+semver-title = Version bump
+semver-commits = Commits
+semver-next = { $bump }: { $since } → { $next }
+semver-bump = { $bump }
+semver-no-release = No release needed: nothing users would notice changed.
+semver-no-commits = There are no commits since the last tag.
+semver-reason-breaking = Marked as a breaking change.
+semver-reason-feat = A new feature.
+semver-reason-fix = A { $kind } commit.
+semver-reason-unnoticed = A { $kind } commit, which users do not notice.
+semver-reason-api-breaking = Removes or changes public API: { $items }.
+semver-reason-api-added = Adds public API: { $items }.
+semver-reason-unrated = Not rated by the model.
+semver-more-items = { $count } more
+breaking-title = Breaking changes in { $range }
+breaking-overview = { $count } probable breaking change(s) in { $commits } commit(s).
+breaking-none = No probable breaking changes in { $commits } commit(s).
+breaking-api = Public API
+breaking-cli = Command-line flags
+breaking-config = Configuration
+breaking-migration = Database migrations
+breaking-api-removed = Removed `{ $signature }`.
+breaking-api-changed = Changed from `{ $before }` to `{ $after }`.
+lint-title = Commit messages in { $range }
+lint-passed = All { $checked } commit message(s) follow Conventional Commits.
+lint-failed = { $count } of { $checked } commit message(s) do not follow Conventional Commits.
+lint-failures = Failing messages
+lint-header = the header is not type(scope): description
+lint-body-spacing = no blank line before the body
+lint-footer-case = BREAKING CHANGE is not in capitals
+lint-suggestion = Suggested: { $header }
+standup-title = Standup for { $author }
+standup-overview = { $count } commit(s) since { $date }.
+standup-done = Done
+standup-in-progress = In progress
+standup-blockers = Blockers
+standup-none = None
+standup-nothing = No commits by { $author } since { $date }.
+progress-committed = Committed { $sha }.
+hook-installed = Installed the { $hook } hook in { $path }.
+hook-uninstalled = Removed the { $hook } hook from { $path }.
+hook-not-installed = The { $hook } hook is not installed.
+hook-skipped = wtf skipped the { $hook } hook: { $reason }
+progress-summarizing-commits = Summarizing the commits in batches: batch { $current } of { $total }...
+progress-summarizing-edits = Summarizing the changes in batches: batch { $current } of { $total }...
+progress-summarizing-diff = Summarizing a large diff in parts: part { $current } of { $total }...
+progress-adr-scanning = Looking for pivotal changes in { $count } commits...
+progress-adr = Drafting ADR { $current } of { $total }: { $summary }
+progress-similar-scanning = Comparing with { $count } commits...
+progress-similar = Summarizing { $count } similar commit(s)...
+progress-workspace-scanning = Checking { $count } repositories...
+progress-workspace = Summarizing { $count } repositories...
+progress-config-explain = Explaining { $settings } setting(s) and { $hooks } hook(s)...
+owners-orphaned = { $count } area(s) have no owner with a commit in the last { $days } days; they are commented out in the draft.
+strict-dropped = Strict mode: dropped { $count } claim(s) without a commit citation.
+guard-warning = Warning: the report mentions { $count } file(s) or commit(s) not found in the analyzed data.
+context-trimmed = Warning: the data for the { $section } section did not fit into the model's context window of { $tokens } tokens and was cut short; analyze fewer commits for the full picture.
+confirm-large-run = This run will send { $requests } request(s) with about { $tokens } prompt tokens. Continue? [y/N]
+confirm-costly-run = This run will cost about { $cost }, more than --max-cost allows ({ $max }). Continue? [y/N]
+unsupported-ui-lang = Warning: no translations for "{ $lang }", using English.
+paths-unsupported = `wtf { $command }` does not take --path; only the report, pick, since-last, range and branch limit their commits to paths.
+offline-unsupported = `wtf { $command }` does not take --offline; only the report from `wtf --offline` itself works without a model.
+
+## Errors
+
+error-label = Error
+hint-label = Hint
+error-not-a-repo = Failed to open Git repository at { $path }: { $reason }
+error-no-commits = No commits found in the repository
+error-no-commits-in-paths = No commits to analyze change anything under { $paths }
+error-missing-credentials = { $var } not found
+error-provider = Provider API error (HTTP { $status }): { $body }
+error-gave-up = Gave up after { $attempts } attempts. { $error }
+error-context-too-large = Prompt is too large for the model's context window: { $body }
+error-safety-cap = This run would send { $requests } request(s) with about { $tokens } prompt tokens, which exceeds the safety cap
+error-cost-cap = This run would cost about { $cost }, more than --max-cost allows ({ $max })
+error-invalid-response = Invalid response from OpenAI API: { $reason }
+error-invalid-structured = Structured response did not match the expected format: { $errors }
+error-git = Git error: { $reason }
+error-http = Request to OpenAI API failed: { $reason }
+error-io = I/O error: { $reason }
+error-no-config-dir = Could not determine where to store settings
+error-network-disabled = This build of wtf has no network support, so it cannot reach a model provider
+error-not-interactive = This command needs an interactive terminal
+error-unknown-path = No commit reachable from HEAD touches { $path }
+error-no-such-lines = { $path } has only { $count } lines
+error-unknown-revision = No commit is called "{ $rev }"
+error-unknown-tag = There is no tag called "{ $name }"
+error-no-conflicts = There are no conflicts to resolve
+error-bisect-failed = git bisect failed: { $output }
+error-editor-failed = The editor "{ $editor }" could not be started or did not exit cleanly
+error-empty-message = The commit message is empty, so nothing was committed
+error-no-identity = Git does not know who to commit as
+error-foreign-hook = { $path } was not installed by wtf, so it is left alone
+error-not-conventional = "{ $header }" is not a Conventional Commits header
+error-lint-failed = { $count } commit message(s) do not follow the Conventional Commits specification
+error-no-changes = There are no uncommitted changes to explain
+error-no-staged-changes = There are no staged changes to explain
+error-no-base-branch = There is no base branch to compare with
+error-invalid-config = Invalid configuration in { $path }: { $reason }
+error-unknown-session = No saved session with id "{ $id }".
+error-unknown-provider = Unknown provider "{ $name }" in WTF_PROVIDER.
+error-missing-setting = { $var } is not set
+error-invalid-setting = Invalid { $key } setting "{ $value }": { $reason }
+error-keyring = Could not use the system keyring: { $reason }
+config-no-components = no [components] table with name = "pattern" or name = ["pattern", ...] entries
+prompts-not-a-directory = no such directory of prompt templates
+prompts-unknown-variable = unknown variable { $name }; templates can use { $known }
+unknown-task = unknown task "{ $name }", expected one of { $names }
+sampling-out-of-range = { $key } must be between { $min } and { $max }
+group-by-invalid = "{ $spec }" is not a grouping; use dir, dir:<depth> or components
+group-by-bad-depth = "{ $depth }" is not a directory depth; use a number of at least 1
+since-invalid = "{ $spec }" is not a point in time; use today, yesterday, a date like 2024-05-01 or an age like "3 days ago"
+
+## Hints
+
+hint-not-a-repo = Run this from inside a Git repository or pass the repository path as the first argument.
+hint-no-commits = Make at least one commit, then run wtf again.
+hint-no-commits-in-paths = Paths given to --path are relative to the repository root; check them with `git log -- <path>`.
+hint-missing-credentials = Set { $var } in the environment, pass --api-key, or create a .env file containing { $var }=your_api_key_here next to the repository. Or run `wtf --offline` for a report from the Git history alone.
+hint-unauthorized = Check that your API key is valid and has not been revoked.
+hint-rate-limited = You are being rate limited or are out of quota. Wait a moment or check your billing settings.
+hint-provider-down = The provider is having trouble. Try again in a few minutes.
+hint-context-too-large = Analyze fewer commits with --num-commits to shrink the prompt.
+hint-safety-cap = Pass --force to run anyway, raise --max-requests/--max-prompt-tokens, or analyze fewer commits.
+hint-cost-cap = Pass --force to run anyway, raise --max-cost, or analyze fewer commits.
+hint-http = Check your network connection.
+hint-no-config-dir = Set HOME (or WTF_CONFIG_DIR) and try again.
+hint-network-disabled = Reinstall with the default features (cargo install --path .) to use hosted models, or run `wtf --offline` for a report from the Git history alone.
+hint-not-interactive = Run it directly in a terminal, without redirecting input or output.
+hint-unknown-path = Give the path relative to the repository root, as `git log --follow` would take it.
+hint-unknown-revision = Give a SHA, branch, tag or anything else `git rev-parse` understands, like HEAD~2.
+hint-unknown-tag = `git tag --list` shows the tags there are.
+hint-no-staged-changes = Stage changes with `git add`, or leave out --staged to explain the work tree.
+hint-no-base-branch = Name it with --base, or set base_branch in .wtf.toml.
+hint-bisect-failed = Bisecting needs git on the PATH and a work tree without uncommitted changes; the test command must exit with 0 on good commits and 1 to 127 (but not 125) on bad ones.
+hint-editor-failed = Set the editor with GIT_EDITOR, core.editor, VISUAL or EDITOR.
+hint-no-identity = Set it with git config --global user.name "Your Name" and git config --global user.email you@example.com.
+hint-foreign-hook = Move it aside or merge it by hand, or pass --replace to install over it.
+hint-not-conventional = Start the message like fix(parser): handle empty files, or skip the check with git commit --no-verify.
+hint-lint-failed = Run wtf lint-commits --suggest for rewritten headers, then reword the commits with git rebase -i.
+hint-invalid-config = See the Configuration section of the README for the expected format.
+hint-unknown-session = Run `wtf session list` to see the saved sessions and their ids.
+hint-unknown-provider = Set WTF_PROVIDER (or --provider) to openai, anthropic, gemini, azure, bedrock or ollama.
+hint-missing-setting = Set { $var } in the environment or in a .env file next to the repository.
+hint-keyring = On Linux, install secret-tool (the libsecret-tools package) and run a keyring such as GNOME Keyring. Otherwise set the key in the environment or in a .env file next to the repository.
+
+## Telemetry
+
+telemetry-on = Telemetry is now on. Thank you! Turn it off again with `wtf telemetry off`.
+telemetry-off = Telemetry is now off. Nothing will be sent.
+telemetry-status-on = Telemetry is on.
+telemetry-status-off = Telemetry is off (the default). Turn it on with `wtf telemetry on`.
+telemetry-no-endpoint = This build has no telemetry endpoint, so nothing is sent either way.
+telemetry-fields = When on, each run reports only: wtf version, operating system, subcommand, a duration bucket (e.g. "10-60s") and an error class (e.g. "auth"). DO_NOT_TRACK=1 disables it.
+auth-prompt = Paste your { $var } (it is not shown):
+auth-empty = No key given, nothing was stored.
+auth-stored = Stored { $var } in the system keyring. wtf reads it from there whenever the environment has none.
+auth-removed = Removed { $var } from the system keyring.
+auth-no-key = The { $provider } provider takes no API key, so there is nothing to store.
+keyring-unsupported = not supported on { $os }
+keyring-line-break = the key contains a line break; paste it on a single line
+
+## Crash reports
+
+crash-saved = wtf ran into a problem it did not expect. A crash report was saved to { $path }. Run `wtf crash-report --submit` to file it as an issue.
+crash-none = No crash reports found.
+crash-latest = Latest crash report ({ $path }):
+crash-submit-hint = Review it for anything private, then run `wtf crash-report --submit` to open an issue with it.
+crash-gh-missing = Could not run the `gh` CLI. Open { $url } and attach { $path } instead.
+
+## Interactive views
+
+tui-title = wtf: { $count } commits
+tui-help = Up/Down select  Enter explain  Tab explanation/diff  PgUp/PgDn scroll  / search  q quit
+tui-no-commits = No commits match the search.
+tui-commit = Commit:
+tui-author = Author:
+tui-press-enter = Press Enter to explain this commit.
+tui-no-diff = No changes to show.
+tui-explaining = Explaining the selected commit...
+pick-help = Type to search  Up/Down move  Tab mark  Enter analyze  Esc cancel  ({ $marked } marked, { $count } shown)
+pick-cancelled = Nothing selected.
+watch-started = Watching { $path } for new commits (Ctrl-C to stop)...
+watch-skipped = Skipping the { $count } oldest new commit(s) to explain only the newest { $max } (--max-commits); explain them with `wtf commit <rev>`.
+
+## Ignore and attribute rules
+
+attrs-rule = { $pattern } ({ $source } line { $line })
+attrs-entry = { $effect }, set by { $rule }
+attrs-none = No attributes apply.
+attrs-nothing = No ignore or attribute rules apply to the files changed in the last { $count } commits.
+ignore-no = Not ignored.
+ignore-yes = Ignored because of { $rule }.
+ignore-via-dir = Ignored because its directory { $dir } is ignored by { $rule }; files inside an ignored directory cannot be re-included.
+ignore-reincluded = Not ignored: { $rule } re-includes it.
+ignore-untraced = Ignored, by a rule wtf could not trace (`git check-ignore -v` shows it).
+ignore-tracked = It is tracked anyway: ignore rules only keep untracked files out of `git add`.
+attr-text = Treated as text: line endings are normalized to LF when committed
+attr-no-text = Never treated as text: Git leaves its line endings alone
+attr-text-auto = Git decides whether it is text; if it is, line endings are normalized to LF when committed
+attr-eol = Checked out with { $eol } line endings
+attr-binary = Marked binary: no line-ending conversion, no text diffs and no line-by-line merges
+attr-no-diff = Diffs only say that it changed, not how
+attr-diff-driver = Diffs use the "{ $driver }" diff driver
+attr-no-merge = Never merged line by line: on conflicts one whole version has to be picked
+attr-merge-driver = Merged with the "{ $driver }" merge driver
+attr-lfs = Stored with Git LFS: the repository holds a small pointer, the content lives on the LFS server
+attr-filter = Passed through the "{ $filter }" filter on checkout and commit
+attr-export-ignore = Left out of archives made with `git archive`
+attr-generated = Marked as generated: GitHub collapses it in diffs and leaves it out of language statistics
+attr-vendored = Marked as vendored: GitHub leaves it out of language statistics
+attr-encoding = Stored as UTF-8 in the repository and checked out as { $encoding }
+attr-other = Sets { $setting }
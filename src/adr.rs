@@ -0,0 +1,327 @@
+//! `wtf adr`: retroactive Architecture Decision Records.
+//!
+//! Most projects never wrote down why they switched frameworks or moved
+//! half the code base around. [`find_pivots`] looks through the history for
+//! commits that look like such decisions: dependencies swapped for others,
+//! many files renamed at once, or changes that touch a large part of the
+//! tree. The model then drafts an ADR for each one (context, decision and
+//! consequences) from the commit's message and what it changed.
+
+use crate::citations::{self, CITATION_INSTRUCTIONS};
+use crate::conversation::Conversation;
+use crate::dependencies::{self, DependencyChanges};
+use crate::error::Result;
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Commit, Delta, DiffFindOptions, Repository, Sort};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that documents the architecture decisions behind a code base.";
+
+const ADR_PROMPT: &str = "The commit below looks like a pivotal change in this project's history. Draft a retroactive Architecture Decision Record for it in Markdown: a level-one heading naming the decision, then the sections Status, Context, Decision and Consequences. Set Status to \"Accepted\" with the commit's date. Base the record on the commit's message and changes; where the reasons are not stated, give the most likely ones and mark them as inferred. Keep it under 400 words.";
+
+// A commit renaming this many files restructures the project
+const RESTRUCTURE_RENAMES: usize = 5;
+
+// A commit changing this many files and lines is a large refactor
+const REFACTOR_FILES: usize = 20;
+const REFACTOR_LINES: usize = 500;
+
+// Changed files listed per pivot in the prompt
+const MAX_LISTED: usize = 40;
+
+// Budget for the manifest changes quoted in the prompt
+const MANIFEST_PATCH_TOKENS: usize = 1500;
+
+/// Why a commit looks like an architecture decision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PivotReason {
+    /// Dependencies were removed and others added, as when one library
+    /// replaces another.
+    DependencySwap,
+    /// Dependencies were only added or only removed.
+    DependencyChange,
+    /// Many files were renamed or moved.
+    Restructure,
+    /// Many files and lines changed.
+    LargeRefactor,
+}
+
+impl PivotReason {
+    fn describe(self) -> &'static str {
+        match self {
+            PivotReason::DependencySwap => "dependencies were replaced by others",
+            PivotReason::DependencyChange => "dependencies were added or removed",
+            PivotReason::Restructure => "many files were moved or renamed",
+            PivotReason::LargeRefactor => "a large part of the code base changed",
+        }
+    }
+}
+
+/// A commit that looks like an architecture decision.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pivot {
+    pub short_id: String,
+    /// Commit time, seconds since the epoch.
+    pub time: i64,
+    pub author: String,
+    pub summary: String,
+    pub reasons: Vec<PivotReason>,
+    pub dependencies: DependencyChanges,
+    pub files: usize,
+    pub renamed: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// How pivotal the commit looks; only used for ranking.
+    pub score: usize,
+    /// What the model is told about the commit.
+    #[serde(skip)]
+    pub content: String,
+}
+
+/// Look through the last `max_commits` non-merge commits reachable from
+/// HEAD and return the `top` most pivotal ones, oldest first.
+pub fn find_pivots(repo: &Repository, max_commits: usize, top: usize) -> Result<Vec<Pivot>> {
+    let (_, total) = git::select_commits(repo, 0)?;
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    progress!("{}", tr!("progress-adr-scanning", count = max_commits.min(total)));
+
+    let mut pivots = Vec::new();
+    for oid in walk.take(max_commits.min(total)) {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        if let Some(pivot) = pivot(repo, &commit)? {
+            pivots.push(pivot);
+        }
+    }
+    // Stable, so equally pivotal commits keep their newest-first order
+    pivots.sort_by_key(|pivot| std::cmp::Reverse(pivot.score));
+    pivots.truncate(top);
+    pivots.sort_by_key(|pivot| pivot.time);
+    Ok(pivots)
+}
+
+// The commit as a pivot, or `None` if nothing about it stands out
+fn pivot(repo: &Repository, commit: &Commit<'_>) -> Result<Option<Pivot>> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+    let mut listed = Vec::new();
+    let mut manifest_patch = String::new();
+    let (mut renamed, mut added, mut removed) = (0, 0, 0);
+    for idx in 0..diff.deltas().len() {
+        let Some(delta) = diff.get_delta(idx) else { continue };
+        let path = delta.new_file().path_bytes().or(delta.old_file().path_bytes()).map(git::escape_non_utf8).unwrap_or_default();
+        let Some(mut patch) = git2::Patch::from_diff(&diff, idx)? else { continue };
+        let (_, file_added, file_removed) = patch.line_stats()?;
+        added += file_added;
+        removed += file_removed;
+        let entry = if delta.status() == Delta::Renamed {
+            renamed += 1;
+            let from = delta.old_file().path_bytes().map(git::escape_non_utf8).unwrap_or_default();
+            format!("{} -> {} (+{} -{})", from, path, file_added, file_removed)
+        } else {
+            format!("{} (+{} -{})", path, file_added, file_removed)
+        };
+        listed.push(entry);
+        if dependencies::is_manifest(&path) {
+            manifest_patch.push_str(&format!("--- {}\n", path));
+            manifest_patch.push_str(&git::escape_non_utf8(&patch.to_buf()?));
+        }
+    }
+
+    let files = diff.deltas().len();
+    let dependencies = dependencies::changes(repo, commit)?;
+    let mut reasons = Vec::new();
+    if dependencies.is_migration() {
+        reasons.push(PivotReason::DependencySwap);
+    } else if !dependencies.is_empty() {
+        reasons.push(PivotReason::DependencyChange);
+    }
+    if renamed >= RESTRUCTURE_RENAMES {
+        reasons.push(PivotReason::Restructure);
+    }
+    if files >= REFACTOR_FILES && added + removed >= REFACTOR_LINES {
+        reasons.push(PivotReason::LargeRefactor);
+    }
+    if reasons.is_empty() {
+        return Ok(None);
+    }
+
+    let changed_dependencies = dependencies.added.len() + dependencies.removed.len();
+    let score = if dependencies.is_migration() { 40 } else { 0 }
+        + changed_dependencies.min(6) * 5
+        + renamed.min(15) * 2
+        + (files / 2).min(20)
+        + ((added + removed) / 100).min(20);
+
+    let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+    let message = git::commit_message(commit);
+    let mut content = format!(
+        "Commit: {}\nDate: {}\nAuthor: {}\nMessage:\n{}\n\nWhy it stands out: {}.\n",
+        short_id,
+        git::format_date(commit.time().seconds()),
+        git::decode_text(commit.author().name_bytes(), commit.message_encoding()),
+        message.trim_end(),
+        reasons.iter().map(|reason| reason.describe()).collect::<Vec<_>>().join("; ")
+    );
+    if !dependencies.added.is_empty() {
+        content.push_str(&format!("Dependencies added: {}\n", dependencies.added.join(", ")));
+    }
+    if !dependencies.removed.is_empty() {
+        content.push_str(&format!("Dependencies removed: {}\n", dependencies.removed.join(", ")));
+    }
+    content.push_str(&format!("\nFiles changed ({}, +{} -{}):\n", files, added, removed));
+    for entry in listed.iter().take(MAX_LISTED) {
+        content.push_str(&format!("- {}\n", entry));
+    }
+    if listed.len() > MAX_LISTED {
+        content.push_str(&format!("- ... and {} more\n", listed.len() - MAX_LISTED));
+    }
+    if !manifest_patch.is_empty() {
+        let (manifest_patch, _) = crate::chunk::truncate_to_tokens(&manifest_patch, MANIFEST_PATCH_TOKENS);
+        content.push_str(&format!("\nManifest changes:\n{}", manifest_patch));
+    }
+
+    Ok(Some(Pivot {
+        short_id,
+        time: commit.time().seconds(),
+        author: git::decode_text(commit.author().name_bytes(), commit.message_encoding()),
+        summary: message.lines().next().unwrap_or_default().to_string(),
+        reasons,
+        dependencies,
+        files,
+        renamed,
+        added,
+        removed,
+        score,
+        content,
+    }))
+}
+
+/// Estimate how many requests and prompt tokens drafting ADRs for `pivots`
+/// will use.
+pub fn estimate(pivots: &[Pivot]) -> crate::analysis::RunEstimate {
+    let fixed = tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(ADR_PROMPT) + tokens::estimate(CITATION_INSTRUCTIONS);
+    crate::analysis::RunEstimate {
+        requests: pivots.len(),
+        prompt_tokens: pivots.iter().map(|pivot| fixed + tokens::estimate(&pivot.content)).sum(),
+    }
+}
+
+/// A drafted ADR and the commit it documents.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Adr {
+    pub pivot: Pivot,
+    /// The record, in Markdown.
+    pub draft: String,
+}
+
+/// Draft one ADR per pivot. Each draft is a conversation of its own, so
+/// one decision's reasoning doesn't leak into another's.
+pub async fn draft(pivots: Vec<Pivot>, provider: &dyn LlmProvider) -> Result<Vec<Adr>> {
+    let total = pivots.len();
+    let mut adrs = Vec::new();
+    for (i, pivot) in pivots.into_iter().enumerate() {
+        progress!("{}", tr!("progress-adr", current = i + 1, total = total, summary = pivot.summary.as_str()));
+        let question = format!("{} {}\n\n{}", ADR_PROMPT, CITATION_INSTRUCTIONS, pivot.content);
+        let draft = Conversation::new(SYSTEM_PROMPT).ask(provider, question).await?;
+        adrs.push(Adr { pivot, draft });
+    }
+    Ok(adrs)
+}
+
+/// A file name for an ADR: its number, then a slug of the commit summary,
+/// like `0003-switch-to-tokio.md`.
+pub fn file_name(number: usize, summary: &str) -> String {
+    let mut slug = String::new();
+    for c in summary.chars().map(|c| c.to_ascii_lowercase()) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= 50 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    match slug {
+        "" => format!("{:04}.md", number),
+        slug => format!("{:04}-{}.md", number, slug),
+    }
+}
+
+/// Write each ADR to its own file in `dir`, numbered after any ADRs
+/// already there. Returns the paths written.
+pub fn write_all(adrs: &[Adr], dir: &Path, commit_url: Option<&str>) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let first = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.get(..4).and_then(|number| number.parse::<usize>().ok())
+        })
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let mut paths = Vec::new();
+    for (number, adr) in (first..).zip(adrs) {
+        let path = dir.join(file_name(number, &adr.pivot.summary));
+        std::fs::write(&path, format!("{}\n", markdown(adr, commit_url).trim_end()))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+fn markdown(adr: &Adr, commit_url: Option<&str>) -> String {
+    match commit_url {
+        Some(base) => citations::link_citations(adr.draft.trim(), base),
+        None => adr.draft.trim().to_string(),
+    }
+}
+
+/// Render `adrs` in the given format.
+pub fn render(adrs: &[Adr], format: OutputFormat, commit_url: Option<&str>) -> String {
+    let heading = |adr: &Adr| {
+        tr!(
+            "adr-heading",
+            id = adr.pivot.short_id.as_str(),
+            date = git::format_date(adr.pivot.time),
+            summary = adr.pivot.summary.as_str()
+        )
+    };
+
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(adrs).unwrap_or_default() + "\n",
+        OutputFormat::Markdown => {
+            let drafts: Vec<String> = adrs.iter().map(|adr| markdown(adr, commit_url)).collect();
+            drafts.join("\n\n---\n\n") + "\n"
+        }
+        OutputFormat::Terminal => {
+            let mut out = String::new();
+            for adr in adrs {
+                out.push_str(&format!("\n=== {} ===\n\n{}\n", heading(adr), adr.draft.trim()));
+            }
+            out
+        }
+        OutputFormat::Plain => {
+            let sections: Vec<(String, String)> = adrs.iter().map(|adr| (heading(adr), adr.draft.clone())).collect();
+            crate::render::plain_sections(&sections)
+        }
+    }
+}
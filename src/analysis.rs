@@ -0,0 +1,928 @@
+use crate::bookmarks;
+use crate::ci::{self, CiChanges, CiReport};
+use crate::chunk;
+use crate::citations::{self, CITATION_INSTRUCTIONS};
+use crate::classify::{self, Dashboard};
+use crate::complexity::{self, CommitScore};
+use crate::components::GroupBy;
+use crate::condense;
+use crate::conversation::Conversation;
+use crate::error::{Result, WtfError};
+use crate::generated::{self, ChurnFile, Detector};
+use crate::git::{self, RepositoryExt, RevRange};
+use crate::guard::{self, GuardMode, KnownRefs};
+use crate::prompts::{self, PromptVars, Prompts};
+use crate::provider::{LlmProvider, Message};
+use crate::rust_api::{self, ApiChanges};
+use crate::structured::{self, Schema};
+use crate::summarize;
+use crate::tokens;
+use crate::topics::{self, Topic, TOPIC_INSTRUCTIONS};
+use crate::{progress, tr};
+use git2::{Commit, Oid, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const CI_PROMPT: &str = "Summarize the following changes to build and CI configuration in plain language: what the pipelines now do differently. Pay particular attention to security: new uses of secrets, changed token permissions, triggers that run code from forks, third-party actions and scripts downloaded at build time. Lines flagged by automated checks are listed first; for each, say whether it looks intentional and what could go wrong.";
+
+const GROUPED_INSTRUCTIONS: &str = "The data is grouped by component. Organize your answer the same way: for each component, in the order given, start with the component's name on a line of its own followed by a colon, then cover only what changed in that component. A commit that touches several components appears under each; describe the part that concerns the component at hand.";
+
+// Put in front of the commit and edits prompts for `wtf range`
+const RANGE_INSTRUCTIONS: &str = "The commits below are not simply the latest ones: they are the range {range}. Frame your answer as what changed between {from} and {to}, not as recent activity.";
+
+const SCOPE_INSTRUCTIONS: &str = "Only the commits that change something under {paths} are included, and of those only their changes there. Describe what happened to that part of the repository, not to the project as a whole.";
+
+const SINGLE_COMMIT_SYSTEM_PROMPT: &str = "You are an AI assistant that explains Git commits in plain language.";
+
+const SINGLE_COMMIT_PROMPT: &str = "Explain the following git commit in plain language: what changed, and why it was probably changed. Focus on the practical impact of the change rather than listing every line. Keep it under 200 words.";
+
+const REFINE_PROMPT: &str = "Here is the draft report you wrote, as JSON. Fact-check every section against the README, commit data and diffs provided above: remove or correct anything they do not support, and do not add new claims. Then tighten the prose so each section is clear and concise. Return the revised sections with the same fields.";
+
+/// A part of the analysis that sends its own request, and so can use its
+/// own model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    /// The project description.
+    Description,
+    /// The commit explanations.
+    Commits,
+    /// The explanation of the diffs.
+    Edits,
+    /// The summary of build and CI changes.
+    Ci,
+    /// Classifying each commit, with `--classify`.
+    Classify,
+    /// Grouping the commits into themes, with `--topics`.
+    Topics,
+    /// The fact-checking pass, with `--refine`.
+    Refine,
+}
+
+impl Task {
+    pub const ALL: [Task; 7] =
+        [Task::Description, Task::Commits, Task::Edits, Task::Ci, Task::Classify, Task::Topics, Task::Refine];
+
+    /// The task called `name` in flags and settings.
+    pub fn from_name(name: &str) -> Option<Task> {
+        Task::ALL.into_iter().find(|task| task.name() == name)
+    }
+
+    /// The task's name in flags and settings, like `commits`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Task::Description => "description",
+            Task::Commits => "commits",
+            Task::Edits => "edits",
+            Task::Ci => "ci",
+            Task::Classify => "classify",
+            Task::Topics => "topics",
+            Task::Refine => "refine",
+        }
+    }
+}
+
+/// Which provider answers each [`Task`]. Any single provider answers them
+/// all; [`PerTask`] picks one per task.
+pub trait TaskProviders: Sync {
+    fn for_task(&self, task: Task) -> &dyn LlmProvider;
+}
+
+impl<P: LlmProvider> TaskProviders for P {
+    fn for_task(&self, _task: Task) -> &dyn LlmProvider {
+        self
+    }
+}
+
+impl<'a> TaskProviders for dyn LlmProvider + 'a {
+    fn for_task(&self, _task: Task) -> &dyn LlmProvider {
+        self
+    }
+}
+
+/// A default provider, with other providers (usually the same backend with
+/// another model) for some tasks.
+pub struct PerTask {
+    default: Box<dyn LlmProvider>,
+    tasks: Vec<(Task, Box<dyn LlmProvider>)>,
+}
+
+impl PerTask {
+    pub fn new(default: Box<dyn LlmProvider>) -> Self {
+        PerTask { default, tasks: Vec::new() }
+    }
+
+    /// Answer `task` with `provider` instead of the default.
+    pub fn set(&mut self, task: Task, provider: Box<dyn LlmProvider>) {
+        self.tasks.retain(|(known, _)| *known != task);
+        self.tasks.push((task, provider));
+    }
+}
+
+impl TaskProviders for PerTask {
+    fn for_task(&self, task: Task) -> &dyn LlmProvider {
+        match self.tasks.iter().find(|(known, _)| *known == task) {
+            Some((_, provider)) => provider.as_ref(),
+            None => self.default.as_ref(),
+        }
+    }
+}
+
+/// Options controlling what gets analyzed.
+#[derive(Debug, Clone)]
+pub struct AnalysisOptions {
+    /// Number of commits to analyze, starting at HEAD.
+    pub num_commits: usize,
+    /// Send the draft back for a fact-checking and editing pass.
+    pub refine: bool,
+    /// How to treat file paths and SHAs that do not appear in the analyzed data.
+    pub guard: GuardMode,
+    /// Drop claims in the commit and edits sections that cite no commit.
+    pub strict: bool,
+    /// Organize the commit and edits sections by component instead of
+    /// chronologically.
+    pub group_by: Option<GroupBy>,
+    /// Classify every commit in an extra request, for the dashboard.
+    pub classify: bool,
+    /// Group the commits into themes in an extra request, and tell the
+    /// commit and edits sections theme by theme. Not used with `group_by`.
+    pub topics: bool,
+    /// Past this many commits, summarize commits and patches in batches of
+    /// this size before writing the report from the summaries. 0 sends
+    /// everything at once. Not used with `group_by`.
+    pub batch_size: usize,
+    /// Split the patch of a commit estimated above this many tokens by file
+    /// and hunk, and send summaries of the parts instead.
+    pub max_diff_tokens: usize,
+    /// Globs of paths whose changes are left out of the patches, like
+    /// generated files.
+    pub exclude: Vec<String>,
+    /// Pathspecs, like `src/network/`, that limit the analysis to the
+    /// commits changing something under them and to their changes there.
+    /// Empty for the whole repository.
+    pub paths: Vec<String>,
+    /// The prompts of the description, commit and edits sections.
+    pub prompts: Prompts,
+    /// The range the commits were picked from, to present the report as
+    /// what changed between its ends.
+    pub range: Option<RevRange>,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions {
+            num_commits: 5,
+            refine: false,
+            guard: GuardMode::default(),
+            strict: false,
+            group_by: None,
+            classify: false,
+            topics: false,
+            batch_size: summarize::DEFAULT_BATCH_SIZE,
+            max_diff_tokens: summarize::DEFAULT_MAX_DIFF_TOKENS,
+            exclude: Vec::new(),
+            paths: Vec::new(),
+            prompts: Prompts::default(),
+            range: None,
+        }
+    }
+}
+
+/// The plain-language explanation of a repository.
+#[derive(Serialize, Debug, Clone)]
+pub struct Report {
+    pub project_description: String,
+    pub commit_descriptions: String,
+    pub edits_description: String,
+    /// Number of commits that were actually analyzed.
+    pub num_commits: usize,
+    /// File paths and SHAs the model mentioned that could not be verified.
+    pub unverified_references: Vec<String>,
+    /// Base URL for linking cited commits (the SHA is appended), if known.
+    pub commit_url: Option<String>,
+    /// Review-effort scores of the analyzed commits, highest first.
+    pub review_effort: Vec<CommitScore>,
+    /// Public API changes across the analyzed commits, for Rust libraries.
+    pub public_api: Option<ApiChanges>,
+    /// Changes to build and CI configuration, if any.
+    pub ci_changes: Option<CiReport>,
+    /// Kind and risk of each analyzed commit and their distribution, with
+    /// `--classify`.
+    pub classification: Option<Dashboard>,
+    /// The themes the commits were grouped into, with `--topics`.
+    pub topics: Option<Vec<Topic>>,
+    /// Generated and vendored files whose contents were left out.
+    pub generated_churn: Vec<ChurnFile>,
+    /// The range the commits came from, with `wtf range`.
+    pub range: Option<RevRange>,
+}
+
+/// Everything gathered from the repository before any model is called.
+#[derive(Debug, Clone)]
+pub struct AnalysisInput {
+    /// README content (or a note that there is none).
+    pub readme: String,
+    /// Formatted metadata of each analyzed commit, newest first.
+    pub commit_details: Vec<String>,
+    /// The paths each analyzed commit changed, in the order of `commit_details`.
+    pub commit_paths: Vec<Vec<String>>,
+    /// Patches labeled with their commit. Empty when only one commit is analyzed.
+    pub file_changes: Vec<String>,
+    /// Number of commits being analyzed.
+    pub num_commits: usize,
+    /// Number of commits reachable from HEAD.
+    pub commit_count: usize,
+    /// The name of the repository's directory, for the prompt templates.
+    pub repo_name: String,
+    /// The branch HEAD is on, or `HEAD` when it is detached.
+    pub branch: String,
+    /// Commits and paths the report may refer to.
+    pub known: KnownRefs,
+    /// Base URL for linking commits, if known.
+    pub commit_url: Option<String>,
+    /// Review-effort scores of the analyzed commits, highest first.
+    pub scores: Vec<CommitScore>,
+    /// Public API changes across the analyzed commits, if the repository
+    /// contains a Rust library.
+    pub public_api: Option<ApiChanges>,
+    /// Changes to build and CI configuration, if any commit has some.
+    pub ci: Option<CiChanges>,
+    /// Generated and vendored files, left out of `file_changes`.
+    pub generated: Vec<ChurnFile>,
+}
+
+/// Rough size of a run, for deciding whether it needs confirmation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunEstimate {
+    /// Number of requests that will be sent.
+    pub requests: usize,
+    /// Estimated prompt tokens across all requests.
+    pub prompt_tokens: usize,
+}
+
+/// Limits above which a run needs confirmation (or `--force`).
+#[derive(Debug, Clone, Copy)]
+pub struct SafetyCap {
+    pub max_requests: usize,
+    pub max_prompt_tokens: usize,
+}
+
+impl SafetyCap {
+    /// Whether `estimate` goes over either limit.
+    pub fn is_exceeded_by(&self, estimate: &RunEstimate) -> bool {
+        estimate.requests > self.max_requests || estimate.prompt_tokens > self.max_prompt_tokens
+    }
+}
+
+impl Default for SafetyCap {
+    fn default() -> Self {
+        SafetyCap {
+            max_requests: 20,
+            max_prompt_tokens: 100_000,
+        }
+    }
+}
+
+// How long we expect each section to be, for estimating follow-up prompts
+const EXPECTED_REPLY_TOKENS: usize = 400;
+
+// Room kept free in the context window for the model's answer
+const REPLY_RESERVE_TOKENS: usize = 1000;
+
+impl AnalysisInput {
+    /// Whether there are edits to analyze (i.e. more than one commit).
+    pub fn has_edits(&self) -> bool {
+        self.num_commits > 1
+    }
+
+    /// The values of the prompt template variables.
+    pub fn prompt_vars(&self) -> PromptVars {
+        PromptVars {
+            repo: self.repo_name.clone(),
+            branch: self.branch.clone(),
+            num_commits: self.num_commits,
+        }
+    }
+
+    /// The questions asked in turn, each as (task, instructions, content).
+    /// With `topics`, the commits and patches are arranged by theme.
+    fn questions(&self, options: &AnalysisOptions, topics: Option<&[Topic]>) -> Vec<(Task, String, String)> {
+        let group_by = options.group_by.as_ref();
+        let prompts = options.prompts.interpolated(&self.prompt_vars());
+        let (commits, mut edits) = match (group_by, topics) {
+            (Some(group_by), _) => self.grouped_content(group_by),
+            (None, Some(topics)) => topics::arrange(self, topics),
+            (None, None) => (self.commit_details.join("\n\n---\n\n"), self.file_changes.join("\n\n---\n\n")),
+        };
+        if !self.generated.is_empty() {
+            edits.push_str("\n\n---\n\n");
+            edits.push_str(&generated::prompt_summary(&self.generated));
+        }
+        let framing = match &options.range {
+            Some(range) => RANGE_INSTRUCTIONS
+                .replace("{range}", &range.to_string())
+                .replace("{from}", &range.from)
+                .replace("{to}", &range.to)
+                + " ",
+            None => String::new(),
+        };
+        let framing = match options.paths.is_empty() {
+            true => framing,
+            false => framing + &SCOPE_INSTRUCTIONS.replace("{paths}", &options.paths.join(", ")) + " ",
+        };
+        let instructions = |prompt: &str| match (group_by, topics) {
+            (Some(_), _) => format!("{}{} {} {}", framing, prompt, GROUPED_INSTRUCTIONS, CITATION_INSTRUCTIONS),
+            (None, Some(_)) => format!("{}{} {} {}", framing, prompt, TOPIC_INSTRUCTIONS, CITATION_INSTRUCTIONS),
+            (None, None) => format!("{}{} {}", framing, prompt, CITATION_INSTRUCTIONS),
+        };
+        let mut questions = vec![
+            (Task::Description, prompts.description, self.readme.clone()),
+            (Task::Commits, instructions(&prompts.commits), commits),
+        ];
+        if self.has_edits() {
+            questions.push((Task::Edits, instructions(&prompts.edits), edits));
+        }
+        if let Some(ci) = &self.ci {
+            questions.push((Task::Ci, format!("{} {}", CI_PROMPT, CITATION_INSTRUCTIONS), ci.prompt_content()));
+        }
+        questions
+    }
+
+    // The commit details and patches, arranged under a heading per group
+    fn grouped_content(&self, group_by: &GroupBy) -> (String, String) {
+        let commits: Vec<(&str, &[String])> = self
+            .commit_details
+            .iter()
+            .zip(&self.commit_paths)
+            .map(|(details, paths)| (details.as_str(), paths.as_slice()))
+            .collect();
+        let commits = group_by
+            .group_commits(&commits)
+            .into_iter()
+            .map(|(group, details)| format!("Component: {}\n\n{}", group, details.join("\n\n---\n\n")))
+            .collect::<Vec<_>>();
+        let edits = group_by
+            .group_patches(&self.file_changes)
+            .into_iter()
+            .map(|(group, patches)| format!("Component: {}\n\n{}", group, patches))
+            .collect::<Vec<_>>();
+        (commits.join("\n\n=====\n\n"), edits.join("\n\n=====\n\n"))
+    }
+
+    // The commit details or patches that `task` summarizes in batches first,
+    // if there are too many to send at once
+    fn batched(&self, task: Task, options: &AnalysisOptions) -> Option<&[String]> {
+        let items = match task {
+            Task::Commits => &self.commit_details,
+            Task::Edits => &self.file_changes,
+            _ => return None,
+        };
+        let arranged = options.group_by.is_some() || options.topics;
+        let needed = !arranged && summarize::is_needed(items.len(), options.batch_size);
+        needed.then_some(items.as_slice())
+    }
+
+    /// Estimate how many requests and prompt tokens a run will use.
+    ///
+    /// Every request resends the conversation so far, so later sections
+    /// pay again for the content of earlier ones, though never more than a
+    /// context window of `max_context` tokens holds: anything longer is cut
+    /// before it is sent.
+    pub fn estimate(&self, options: &AnalysisOptions, max_context: usize) -> RunEstimate {
+        let mut history = tokens::estimate(&prompts::interpolate(&options.prompts.system, &self.prompt_vars()));
+        let mut prompt_tokens = 0;
+        let mut requests = 0;
+        for patch in self.file_changes.iter().filter(|patch| summarize::is_large_diff(patch, options.max_diff_tokens)) {
+            let parts = summarize::estimate_diff(patch, options.max_diff_tokens);
+            prompt_tokens += parts.prompt_tokens;
+            requests += parts.requests;
+        }
+        // Arranged by theme, the content is about as long as in order
+        for (task, instructions, content) in self.questions(options, None) {
+            let content_tokens = match self.batched(task, options) {
+                Some(items) => {
+                    let batches = summarize::estimate(task, items, options.batch_size);
+                    prompt_tokens += batches.prompt_tokens;
+                    requests += batches.requests;
+                    batches.requests * summarize::EXPECTED_SUMMARY_TOKENS
+                }
+                None => tokens::estimate(&content),
+            };
+            history += tokens::estimate(&instructions) + content_tokens;
+            prompt_tokens += history.min(max_context);
+            history += EXPECTED_REPLY_TOKENS;
+            requests += 1;
+        }
+        if options.classify {
+            prompt_tokens += classify::estimate_tokens(self).min(max_context);
+            requests += 1;
+        }
+        if options.topics && options.group_by.is_none() {
+            prompt_tokens += topics::estimate_tokens(self).min(max_context);
+            requests += 1;
+        }
+        if options.refine {
+            prompt_tokens += (history + tokens::estimate(REFINE_PROMPT) + 3 * EXPECTED_REPLY_TOKENS).min(max_context);
+            requests += 1;
+        }
+        RunEstimate {
+            requests,
+            prompt_tokens,
+        }
+    }
+}
+
+/// Gather the commits, patches and README that will be analyzed.
+pub fn prepare(repo: &Repository, options: &AnalysisOptions) -> Result<AnalysisInput> {
+    // Get the latest commits
+    let (commits, commit_count) = git::select_commits_in(repo, options.num_commits, &options.paths)?;
+    let mut input = gather(repo, &commits, commit_count, options)?;
+    if options.paths.is_empty() {
+        input.public_api = public_api_changes(repo, &commits)?;
+    }
+    Ok(input)
+}
+
+// Compare the public API before the oldest analyzed commit with the newest.
+// Hand-picked commits are not a range, so `prepare_selected` skips this, and
+// neither are those left when `--path` skips the others.
+fn public_api_changes(repo: &Repository, commits: &[Commit<'_>]) -> Result<Option<ApiChanges>> {
+    let (Some(newest), Some(oldest)) = (commits.first(), commits.last()) else {
+        return Ok(None);
+    };
+    let base = match oldest.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    rust_api::changes_between(repo, base.as_ref(), &newest.tree()?)
+}
+
+/// Like [`prepare`], for an explicitly chosen set of commits instead of the
+/// most recent ones. The commits are analyzed in the order given, and
+/// `options.num_commits` is not used. With `options.paths`, those that
+/// change nothing under them are left out.
+pub fn prepare_selected(repo: &Repository, ids: &[Oid], options: &AnalysisOptions) -> Result<AnalysisInput> {
+    let mut commits = Vec::new();
+    for id in ids {
+        let commit = repo.find_commit(*id)?;
+        if git::touches(repo, &commit, &options.paths)? {
+            commits.push(commit);
+        }
+    }
+    // The chosen commits are all there are, not the whole history
+    gather(repo, &commits, commits.len(), options)
+}
+
+/// Like [`prepare_selected`] for `ids`, the commits a branch adds to its
+/// base, the range `base..branch`. The edits are the branch's combined diff
+/// against where it left the base, instead of the patch of each commit.
+pub fn prepare_branch(
+    repo: &Repository,
+    range: &RevRange,
+    ids: &[Oid],
+    options: &AnalysisOptions,
+) -> Result<AnalysisInput> {
+    let mut input = prepare_selected(repo, ids, options)?;
+    let tip = git::resolve_commit(repo, &range.to)?;
+    let base = git::resolve_commit(repo, &range.from)?;
+    let fork_point = match repo.merge_base(base.id(), tip.id()) {
+        Ok(id) => Some(repo.find_commit(id)?.tree()?),
+        // Unrelated histories: everything on the branch is new
+        Err(e) if e.code() == git2::ErrorCode::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let mut diff_options = git::pathspec_options(&options.paths);
+    let diff = repo.diff_tree_to_tree(fork_point.as_ref(), Some(&tip.tree()?), Some(&mut diff_options))?;
+    let patch = git::diff_patch(&diff)?;
+    input.known.add_patch_paths(&patch);
+    let churn = generated::diff_churn(&diff, &mut Detector::with_exclude(repo, &options.exclude))?;
+    let stripped = generated::strip_patch(&patch, &churn);
+    input.file_changes = match stripped.is_empty() {
+        true => Vec::new(),
+        false => vec![format!("Combined diff of {} against {}:\n{}", range.to, range.from, stripped)],
+    };
+    input.generated = churn;
+    input.branch = range.to.clone();
+    Ok(input)
+}
+
+fn gather(
+    repo: &Repository,
+    commits: &[Commit<'_>],
+    commit_count: usize,
+    options: &AnalysisOptions,
+) -> Result<AnalysisInput> {
+    let num_to_analyze = commits.len();
+    let paths = &options.paths;
+
+    if num_to_analyze == 0 && !paths.is_empty() {
+        return Err(WtfError::NoCommitsInPaths { paths: paths.join(", ") });
+    }
+    if num_to_analyze == 0 {
+        return Err(WtfError::NoCommits);
+    }
+
+    progress!("{}", tr!("progress-found-commits", total = commit_count, count = num_to_analyze));
+
+    let mut commit_details = Vec::new();
+    let mut commit_paths = Vec::new();
+    let mut scores = Vec::new();
+    let mut known = KnownRefs::new();
+    for (i, commit) in commits.iter().enumerate() {
+        progress!("{}", tr!("progress-analyzing-commit", current = i + 1, total = num_to_analyze));
+        commit_details.push(git::get_commit_details(commit));
+        commit_paths.push(git::diff_paths(&git::commit_diff(repo, commit, paths)?));
+        scores.push(complexity::score_commit(repo, commit, paths)?);
+        known.add_sha(commit.id().to_string());
+    }
+    // Stable, so equally scored commits stay newest first
+    scores.sort_by_key(|score| std::cmp::Reverse(score.score));
+    for path in git::head_tree_paths(repo)? {
+        known.add_path(path);
+    }
+
+    // Get project description
+    let readme = match repo.find_file("README.md") {
+        Ok(content) => content,
+        Err(_) => "No README.md found".to_string(),
+    };
+
+    // Only analyze file changes if there are multiple commits
+    let mut file_changes = Vec::new();
+    let mut churn = Vec::new();
+    if num_to_analyze > 1 {
+        let mut detector = Detector::with_exclude(repo, &options.exclude);
+        // Root commits have nothing to compare against
+        for commit in commits.iter().filter(|commit| commit.parent_count() > 0) {
+            let patch = git::diff_patch(&git::commit_diff(repo, commit, paths)?)?;
+            known.add_patch_paths(&patch);
+            // Lockfiles, vendored code and binaries would crowd out everything else
+            let excluded = generated::churn(repo, commit, paths, &mut detector)?;
+            let stripped = generated::strip_patch(&patch, &excluded);
+            generated::merge(&mut churn, excluded);
+            // Nothing but generated files is left for the summary
+            if stripped.is_empty() && !patch.is_empty() {
+                continue;
+            }
+            let condensed = condense::condense_patch(repo, commit, &stripped, options.max_diff_tokens)?;
+            // Label each patch so the model can cite where an edit came from
+            let short_id = commit.as_object().short_id()?;
+            file_changes.push(format!("Commit: {}\n{}", short_id.as_str().unwrap_or_default(), condensed));
+        }
+    }
+
+    Ok(AnalysisInput {
+        readme,
+        commit_details,
+        commit_paths,
+        file_changes,
+        num_commits: num_to_analyze,
+        commit_count,
+        repo_name: repo_name(repo),
+        branch: bookmarks::current_branch(repo),
+        known,
+        commit_url: git::commit_url_base(repo),
+        scores,
+        public_api: None,
+        ci: ci::collect(repo, commits, paths)?,
+        generated: churn,
+    })
+}
+
+// The name of the directory the repository is in
+pub(crate) fn repo_name(repo: &Repository) -> String {
+    let path = repo.workdir().unwrap_or_else(|| repo.path());
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.strip_suffix(".git").unwrap_or(&name).to_string()
+}
+
+/// Analyze the most recent commits of `repo` using `providers`, which can
+/// be a single provider for every task.
+pub async fn analyze(
+    repo: &Repository,
+    providers: &(impl TaskProviders + ?Sized),
+    options: &AnalysisOptions,
+) -> Result<Report> {
+    let input = prepare(repo, options)?;
+    run(&input, providers, options).await
+}
+
+/// Produce the report for already gathered `input`.
+pub async fn run(
+    input: &AnalysisInput,
+    providers: &(impl TaskProviders + ?Sized),
+    options: &AnalysisOptions,
+) -> Result<Report> {
+    run_with(input, providers, options, None).await
+}
+
+/// Like [`run`], handing the text of the description, commits, edits and
+/// CI sections to `on_text` as it arrives, for showing it while the model
+/// is still writing. The report holds the final text, which the `refine`,
+/// `strict` and `guard` options may still change.
+pub async fn run_streaming(
+    input: &AnalysisInput,
+    providers: &(impl TaskProviders + ?Sized),
+    options: &AnalysisOptions,
+    on_text: &mut SectionText<'_>,
+) -> Result<Report> {
+    run_with(input, providers, options, Some(on_text)).await
+}
+
+/// Receives the text of a section as it streams in.
+pub type SectionText<'a> = dyn FnMut(Task, &str) + Send + 'a;
+
+async fn run_with(
+    input: &AnalysisInput,
+    providers: &(impl TaskProviders + ?Sized),
+    options: &AnalysisOptions,
+    mut on_text: Option<&mut SectionText<'_>>,
+) -> Result<Report> {
+    let summarized;
+    let input = match summarize_large_diffs(input, providers.for_task(Task::Edits), options.max_diff_tokens).await? {
+        Some(input) => {
+            summarized = input;
+            &summarized
+        }
+        None => input,
+    };
+
+    // All sections share one conversation, so later ones can refer back
+    let mut conversation = Conversation::new(prompts::interpolate(&options.prompts.system, &input.prompt_vars()));
+
+    let topics = match options.topics && options.group_by.is_none() {
+        true => {
+            progress!("{}", tr!("progress-topics"));
+            Some(topics::cluster(input, providers.for_task(Task::Topics)).await?)
+        }
+        false => None,
+    };
+
+    let mut answers = Vec::new();
+    for (task, mut instructions, mut content) in input.questions(options, topics.as_deref()) {
+        let provider = providers.for_task(task);
+        if let Some(items) = input.batched(task, options) {
+            let summaries = summarize::summarize_batches(provider, task, items, options.batch_size).await?;
+            instructions = format!("{} {}", instructions, summarize::SYNTHESIS_INSTRUCTIONS);
+            content = summarize::combine(&summaries);
+            if task == Task::Edits && !input.generated.is_empty() {
+                content.push_str("\n\n---\n\n");
+                content.push_str(&generated::prompt_summary(&input.generated));
+            }
+        }
+        let content = fit_content(&conversation, provider, task, &instructions, content);
+        let question = format!("{}\n\n{}", instructions, content);
+        answers.push(match on_text.as_deref_mut() {
+            Some(on_text) => conversation.ask_streaming(provider, question, &mut |text| on_text(task, text)).await?,
+            None => conversation.ask(provider, question).await?,
+        });
+    }
+
+    let mut answers = answers.into_iter();
+    let project_description = answers.next().unwrap_or_default();
+    let commit_descriptions = answers.next().unwrap_or_default();
+    let has_edits = input.has_edits();
+    let edits_description = match has_edits {
+        true => answers.next().unwrap_or_default(),
+        false => tr!("single-commit-edits"),
+    };
+    let ci_changes = input.ci.as_ref().map(|ci| CiReport {
+        summary: answers.next().unwrap_or_default(),
+        files: ci.files.clone(),
+        findings: ci.findings.clone(),
+    });
+    let known = &input.known;
+    let classification = match options.classify {
+        true => Some(classify::classify(input, providers.for_task(Task::Classify)).await?),
+        false => None,
+    };
+
+    let mut report = Report {
+        project_description,
+        commit_descriptions,
+        edits_description,
+        num_commits: input.num_commits,
+        unverified_references: Vec::new(),
+        commit_url: input.commit_url.clone(),
+        review_effort: input.scores.clone(),
+        public_api: input.public_api.clone(),
+        ci_changes,
+        classification,
+        topics,
+        generated_churn: input.generated.clone(),
+        range: options.range.clone(),
+    };
+
+    if options.refine {
+        progress!("{}", tr!("progress-refining"));
+        refine(&conversation, providers.for_task(Task::Refine), &mut report).await?;
+    }
+
+    if options.strict {
+        let mut sections = vec![&mut report.commit_descriptions];
+        if has_edits {
+            sections.push(&mut report.edits_description);
+        }
+        if let Some(ci) = &mut report.ci_changes {
+            sections.push(&mut ci.summary);
+        }
+        let mut dropped = 0;
+        for section in sections {
+            let (cited, n) = citations::strip_uncited(section);
+            *section = cited;
+            dropped += n;
+        }
+        if dropped > 0 {
+            progress!("{}", tr!("strict-dropped", count = dropped));
+        }
+    }
+
+    // Check the final text for made-up files and commits
+    let mut sections = vec![
+        &mut report.project_description,
+        &mut report.commit_descriptions,
+        &mut report.edits_description,
+    ];
+    if let Some(ci) = &mut report.ci_changes {
+        sections.push(&mut ci.summary);
+    }
+    for section in sections {
+        let (checked, unverified) = guard::check(section, known, options.guard);
+        *section = checked;
+        for reference in unverified {
+            if !report.unverified_references.contains(&reference) {
+                report.unverified_references.push(reference);
+            }
+        }
+    }
+    if !report.unverified_references.is_empty() {
+        progress!("{}", tr!("guard-warning", count = report.unverified_references.len()));
+    }
+
+    Ok(report)
+}
+
+// A copy of `input` with every patch above `max_diff_tokens` replaced by
+// summaries of its parts, or `None` if no patch is that large
+async fn summarize_large_diffs(
+    input: &AnalysisInput,
+    provider: &dyn LlmProvider,
+    max_diff_tokens: usize,
+) -> Result<Option<AnalysisInput>> {
+    if !input.file_changes.iter().any(|patch| summarize::is_large_diff(patch, max_diff_tokens)) {
+        return Ok(None);
+    }
+    let mut summarized = input.clone();
+    for labeled in &mut summarized.file_changes {
+        if !summarize::is_large_diff(labeled, max_diff_tokens) {
+            continue;
+        }
+        // Keep the "Commit: <sha>" label the edits are cited by
+        let (label, patch) = labeled.split_once('\n').unwrap_or(("", labeled.as_str()));
+        let summary = summarize::summarize_diff(provider, Task::Edits, patch, max_diff_tokens).await?;
+        *labeled = format!("{}\n{}", label, summary);
+    }
+    Ok(Some(summarized))
+}
+
+// Cut `content` short if asking about it after everything already in
+// `conversation` would overflow `provider`'s context window. The newest
+// commits come first, so it's the oldest that get left out.
+pub(crate) fn fit_content(
+    conversation: &Conversation,
+    provider: &dyn LlmProvider,
+    task: Task,
+    instructions: &str,
+    content: String,
+) -> String {
+    let used = conversation.tokens(provider) + provider.count_tokens(instructions) + REPLY_RESERVE_TOKENS;
+    let room = provider.max_context().saturating_sub(used);
+    let (fitted, truncated) = chunk::truncate_to_count(&content, room, |text| provider.count_tokens(text));
+    if !truncated {
+        return content;
+    }
+    progress!("{}", tr!("context-trimmed", section = task.name(), tokens = provider.max_context()));
+    fitted
+}
+
+/// The report sections, as exchanged during the refine pass.
+#[derive(Serialize, Deserialize)]
+struct Sections {
+    project_description: String,
+    commit_descriptions: String,
+    edits_description: String,
+}
+
+// Have the model check the draft against the data it was given and tighten it
+async fn refine(conversation: &Conversation, provider: &dyn LlmProvider, report: &mut Report) -> Result<()> {
+    let draft = Sections {
+        project_description: std::mem::take(&mut report.project_description),
+        commit_descriptions: std::mem::take(&mut report.commit_descriptions),
+        edits_description: std::mem::take(&mut report.edits_description),
+    };
+    let draft_json = serde_json::to_string_pretty(&draft).map_err(|e| WtfError::InvalidResponse(e.to_string()))?;
+
+    let mut messages = conversation.messages().to_vec();
+    messages.push(Message::user(format!("{}\n\n{}", REFINE_PROMPT, draft_json)));
+
+    let schema = Schema::new(
+        "refined_report",
+        json!({
+            "type": "object",
+            "properties": {
+                "project_description": { "type": "string" },
+                "commit_descriptions": { "type": "string" },
+                "edits_description": { "type": "string" },
+            },
+            "required": ["project_description", "commit_descriptions", "edits_description"],
+            "additionalProperties": false,
+        }),
+    );
+    let refined: Sections = structured::request(provider, &messages, &schema).await?;
+
+    report.project_description = refined.project_description;
+    report.commit_descriptions = refined.commit_descriptions;
+    report.edits_description = refined.edits_description;
+    Ok(())
+}
+
+// The metadata and patch of `commit` for explaining it on its own, condensed
+// if above `max_diff_tokens`, with files matching an `exclude` glob left out
+// like generated ones
+fn single_commit_content(
+    repo: &Repository,
+    commit: &Commit<'_>,
+    max_diff_tokens: usize,
+    exclude: &[String],
+) -> Result<(String, Option<String>, Vec<ChurnFile>)> {
+    let details = git::get_commit_details(commit);
+    let Some(patch) = git::commit_patch(repo, commit)? else { return Ok((details, None, Vec::new())) };
+    let churn = generated::churn(repo, commit, &[], &mut Detector::with_exclude(repo, exclude))?;
+    let patch = condense::condense_patch(repo, commit, &generated::strip_patch(&patch, &churn), max_diff_tokens)?;
+    Ok((details, Some(patch), churn))
+}
+
+/// Estimate the requests [`explain_commit`] will send with the same
+/// arguments.
+pub fn estimate_commit(
+    repo: &Repository,
+    commit: &Commit<'_>,
+    max_diff_tokens: usize,
+    exclude: &[String],
+) -> Result<RunEstimate> {
+    let (details, patch, _) = single_commit_content(repo, commit, max_diff_tokens, exclude)?;
+    let mut estimate = RunEstimate {
+        requests: 1,
+        prompt_tokens: tokens::estimate(SINGLE_COMMIT_SYSTEM_PROMPT)
+            + tokens::estimate(SINGLE_COMMIT_PROMPT)
+            + tokens::estimate(&details),
+    };
+    match patch {
+        Some(patch) if summarize::is_large_diff(&patch, max_diff_tokens) => {
+            let parts = summarize::estimate_diff(&patch, max_diff_tokens);
+            estimate.requests += parts.requests;
+            estimate.prompt_tokens += parts.prompt_tokens + parts.requests * summarize::EXPECTED_SUMMARY_TOKENS;
+        }
+        Some(patch) => estimate.prompt_tokens += tokens::estimate(&patch),
+        None => {}
+    }
+    Ok(estimate)
+}
+
+/// Explain a single commit on its own, from its metadata and patch. A
+/// patch above `max_diff_tokens` is summarized in parts first, and files
+/// matching an `exclude` glob are left out like generated ones.
+pub async fn explain_commit(
+    repo: &Repository,
+    commit: &Commit<'_>,
+    provider: &dyn LlmProvider,
+    max_diff_tokens: usize,
+    exclude: &[String],
+) -> Result<String> {
+    let (mut content, patch, churn) = single_commit_content(repo, commit, max_diff_tokens, exclude)?;
+    if let Some(mut patch) = patch {
+        if summarize::is_large_diff(&patch, max_diff_tokens) {
+            patch = summarize::summarize_diff(provider, Task::Edits, &patch, max_diff_tokens).await?;
+        }
+        content.push_str("\n\n");
+        content.push_str(&patch);
+        if !churn.is_empty() {
+            content.push('\n');
+            content.push_str(&generated::prompt_summary(&churn));
+        }
+    }
+    let mut conversation = Conversation::new(SINGLE_COMMIT_SYSTEM_PROMPT);
+    describe(&mut conversation, provider, SINGLE_COMMIT_PROMPT, &content).await
+}
+
+// Ask the model to explain `content` following the `prompt` instructions
+async fn describe(
+    conversation: &mut Conversation,
+    provider: &dyn LlmProvider,
+    prompt: &str,
+    content: &str,
+) -> Result<String> {
+    conversation
+        .ask(provider, format!("{}\n\n{}", prompt, content))
+        .await
+}
@@ -0,0 +1,293 @@
+//! `wtf arch`: a module-by-module description of a codebase.
+//!
+//! The tree at HEAD is split into modules, one per directory down to
+//! `--depth` levels, leaving out generated, vendored and `--exclude`d files.
+//! A few representative files of each module are sampled: the ones that
+//! usually hold a module's outline, like `mod.rs`, `index.ts` or
+//! `__init__.py`, then the largest sources, with tests and binary files
+//! last or not at all. Every sample is cut to its share of a fixed token
+//! budget, so large trees cost no more than small ones. One structured
+//! request describes each module and sketches which modules depend on
+//! which; dependencies on modules that are not in the tree are dropped.
+
+use crate::analysis::{self, RunEstimate};
+use crate::chunk;
+use crate::complexity;
+use crate::error::Result;
+use crate::generated::Detector;
+use crate::git;
+use crate::ownership;
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains the architecture of software projects.";
+
+const ARCH_PROMPT: &str = "Below are the modules of a codebase, one per directory, each with its number of files and samples of its representative files, some cut short. Describe the architecture: an overview of three to five sentences on how the codebase is structured and how a request or run flows through it; then, for each module, using its path exactly as given, a description of one or two sentences of what it is responsible for, and the paths of the other modules it depends on, judging by imports and calls in the samples. Only describe what the samples show.";
+
+/// Directories this deep are modules of their own, unless `--depth` says
+/// otherwise.
+pub const DEFAULT_DEPTH: usize = 2;
+
+// The samples of all modules together are cut to this many tokens...
+const CONTENT_TOKENS: usize = 30_000;
+
+// ...and each sample to at most this many
+const FILE_TOKENS: usize = 800;
+
+// Files sampled per module
+const SAMPLES_PER_MODULE: usize = 3;
+
+// Past this many modules, only the largest are sampled
+const MAX_MODULES: usize = 40;
+
+// Names that usually hold a module's outline, sampled first
+const OUTLINE_FILES: &[&str] = &[
+    "mod.rs", "lib.rs", "main.rs", "index.ts", "index.js", "index.tsx", "__init__.py", "main.go", "main.py", "doc.go",
+];
+
+/// A directory of the tree and the files sampled from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Module {
+    /// Empty for the files at the root.
+    pub path: String,
+    pub files: usize,
+    pub sampled: Vec<String>,
+}
+
+/// The modules of the tree at HEAD, sampled before any model is called.
+#[derive(Debug, Clone)]
+pub struct ArchInput {
+    pub repo_name: String,
+    /// Most files first.
+    pub modules: Vec<Module>,
+    // The samples, for the prompt
+    content: String,
+}
+
+// How well a file stands for its module: lower sorts first
+fn rank(path: &str, size: usize) -> (bool, bool, std::cmp::Reverse<usize>) {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    (complexity::is_test_path(path), !OUTLINE_FILES.contains(&name), std::cmp::Reverse(size))
+}
+
+/// Split the tree at HEAD into modules `depth` directories deep and sample
+/// each, leaving out generated files and those matching `exclude`.
+pub fn prepare(repo: &Repository, depth: usize, exclude: &[String]) -> Result<ArchInput> {
+    let tree = repo.head()?.peel_to_tree()?;
+    let mut detector = Detector::with_exclude(repo, exclude);
+    // Each module's text files with their contents
+    let mut modules: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for path in git::head_tree_paths(repo)? {
+        if detector.kind(&path).is_some() {
+            continue;
+        }
+        let Ok(entry) = tree.get_path(Path::new(&path)) else {
+            continue;
+        };
+        let Ok(blob) = repo.find_blob(entry.id()) else {
+            continue;
+        };
+        if blob.is_binary() {
+            continue;
+        }
+        let text = crate::platform::normalize_newlines(&String::from_utf8_lossy(blob.content()));
+        modules.entry(ownership::area_of(&path, depth)).or_default().push((path, text));
+    }
+
+    let mut modules: Vec<(String, Vec<(String, String)>)> = modules.into_iter().collect();
+    // Stable, so modules as large as each other stay in path order
+    modules.sort_by_key(|(_, files)| std::cmp::Reverse(files.len()));
+    progress!("{}", tr!("progress-arch", count = modules.len()));
+    let sampled_modules = modules.len().min(MAX_MODULES);
+    let samples: usize =
+        modules.iter().take(MAX_MODULES).map(|(_, files)| files.len().min(SAMPLES_PER_MODULE)).sum();
+    let file_tokens = FILE_TOKENS.min(CONTENT_TOKENS / samples.max(1));
+
+    let mut content = Vec::new();
+    let mut described = Vec::new();
+    for (index, (path, mut files)) in modules.into_iter().enumerate() {
+        let name = if path.is_empty() { "(root)".to_string() } else { path.clone() };
+        let count = files.len();
+        if index >= sampled_modules {
+            content.push(format!("Module: {}\nFiles: {}\nNot sampled", name, count));
+            described.push(Module { path, files: count, sampled: Vec::new() });
+            continue;
+        }
+        files.sort_by_key(|(file, text)| rank(file, text.len()));
+        files.truncate(SAMPLES_PER_MODULE);
+        let mut module = format!("Module: {}\nFiles: {}", name, count);
+        for (file, text) in &files {
+            let (sample, _) = chunk::truncate_to_tokens(text, file_tokens);
+            module.push_str(&format!("\n\nFile: {}\n{}", file, sample));
+        }
+        content.push(module);
+        let sampled = files.into_iter().map(|(file, _)| file).collect();
+        described.push(Module { path, files: count, sampled });
+    }
+    Ok(ArchInput {
+        repo_name: analysis::repo_name(repo),
+        modules: described,
+        content: content.join("\n\n---\n\n"),
+    })
+}
+
+impl ArchInput {
+    fn question(&self) -> String {
+        format!("{}\n\n{}", ARCH_PROMPT, self.content)
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question()) }
+    }
+
+    // The module the model means by `path`
+    fn module(&self, path: &str) -> Option<&Module> {
+        let path = path.trim().trim_end_matches('/');
+        let path = if path == "(root)" { "" } else { path };
+        self.modules.iter().find(|module| module.path == path)
+    }
+}
+
+/// What a module does and what it uses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleSummary {
+    /// Empty for the files at the root.
+    pub path: String,
+    pub files: usize,
+    pub description: String,
+    /// Paths of the modules it depends on.
+    pub depends_on: Vec<String>,
+}
+
+/// The architecture of the codebase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Architecture {
+    pub repo_name: String,
+    pub overview: String,
+    /// In the order of the modules, most files first.
+    pub modules: Vec<ModuleSummary>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    overview: String,
+    modules: Vec<Described>,
+}
+
+#[derive(Deserialize)]
+struct Described {
+    path: String,
+    description: String,
+    depends_on: Vec<String>,
+}
+
+/// Describe the modules of `input` with one structured request.
+pub async fn explain(input: &ArchInput, provider: &dyn LlmProvider) -> Result<Architecture> {
+    let schema = Schema::new(
+        "architecture",
+        json!({
+            "type": "object",
+            "properties": {
+                "overview": { "type": "string" },
+                "modules": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "path": { "type": "string" },
+                            "description": { "type": "string" },
+                            "depends_on": { "type": "array", "items": { "type": "string" } },
+                        },
+                        "required": ["path", "description", "depends_on"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["overview", "modules"],
+            "additionalProperties": false,
+        }),
+    );
+    let messages = [Message::system(SYSTEM_PROMPT), Message::user(input.question())];
+    let reply: Reply = structured::request(provider, &messages, &schema).await?;
+
+    // Keep the modules' order; the model may answer in any
+    let mut modules = Vec::new();
+    for module in &input.modules {
+        let Some(described) = reply.modules.iter().find(|described| input.module(&described.path) == Some(module))
+        else {
+            continue;
+        };
+        let mut depends_on: Vec<String> = Vec::new();
+        for dependency in described.depends_on.iter().filter_map(|path| input.module(path)) {
+            if dependency != module && !depends_on.contains(&dependency.path) {
+                depends_on.push(dependency.path.clone());
+            }
+        }
+        modules.push(ModuleSummary {
+            path: module.path.clone(),
+            files: module.files,
+            description: described.description.trim().to_string(),
+            depends_on,
+        });
+    }
+    Ok(Architecture { repo_name: input.repo_name.clone(), overview: reply.overview.trim().to_string(), modules })
+}
+
+/// Format `architecture` for output.
+pub fn render(architecture: &Architecture, format: OutputFormat) -> String {
+    let name = |path: &str| match path.is_empty() {
+        true => tr!("group-root"),
+        false => format!("{}/", path),
+    };
+    let title = tr!("arch-title", repo = architecture.repo_name.as_str());
+    let mut sections = vec![(title, architecture.overview.clone() + "\n")];
+    let modules: String = architecture
+        .modules
+        .iter()
+        .map(|module| {
+            let files = tr!("arch-files", count = module.files);
+            format!("- {} ({}): {}\n", name(&module.path), files, module.description)
+        })
+        .collect();
+    if !modules.is_empty() {
+        sections.push((tr!("arch-modules"), modules));
+    }
+    let dependencies: String = architecture
+        .modules
+        .iter()
+        .filter(|module| !module.depends_on.is_empty())
+        .map(|module| {
+            let used: Vec<String> = module.depends_on.iter().map(|path| name(path)).collect();
+            format!("- {} -> {}\n", name(&module.path), used.join(", "))
+        })
+        .collect();
+    if !dependencies.is_empty() {
+        sections.push((tr!("arch-dependencies"), dependencies));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(architecture).expect("architecture serializes to JSON") + "\n"
+        }
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,321 @@
+//! `wtf ask`: free-form questions about a repository, answered from its
+//! history.
+//!
+//! Nothing is embedded; the context is found locally. The question is
+//! reduced to its words, and the last `--commits` non-merge commits are
+//! ranked by how many of them their messages, paths and changed lines share,
+//! weighted by how rare each word is in the history, as in `wtf similar`.
+//! Strings given with `--pickaxe`, or written in backticks in the question,
+//! pick out the commits whose added or removed lines contain them, like
+//! `git log -G`, ahead of the rest. The files at HEAD whose paths name the
+//! question's words are added. The model answers from that bundle alone,
+//! citing the commits it relies on, and references to commits or paths
+//! outside the bundle are flagged or stripped as in the report.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::citations::CITATION_INSTRUCTIONS;
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::git::{self, RepositoryExt};
+use crate::guard::{self, GuardMode, KnownRefs};
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::similar;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::Repository;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that answers questions about a software project from its Git history.";
+
+const ASK_PROMPT: &str = "Answer the question below about this project. The commits and files after it were found by searching the project's history and tree for the question's words, most relevant first; some may turn out to be unrelated. Answer from them alone: say what happened and, where the commit messages or changes tell, why. Name files by their path. If the material does not answer the question, say so plainly and say what it does show. Keep it under 250 words.";
+
+/// How many recent commits are searched, unless `--commits` says otherwise.
+pub const DEFAULT_MAX_COMMITS: usize = 1000;
+
+// Commits and files sent to the model
+const TOP_COMMITS: usize = 8;
+const TOP_FILES: usize = 3;
+
+// Each file is cut to this many tokens; commits are cut as in `wtf similar`
+const FILE_TOKENS: usize = 800;
+
+// A pickaxe hit outweighs any number of shared words
+const PICKAXE_SCORE: f64 = 100.0;
+
+// Words every question has, on top of those `wtf similar` leaves out
+const QUESTION_WORDS: &[&str] = &[
+    "why", "what", "who", "how", "which", "where", "did", "does", "done", "has", "have", "had", "been", "were", "there",
+    "they", "them", "their", "about", "code", "repo", "repository", "project", "commit", "commits", "change", "changes",
+    "changed", "get", "got",
+];
+
+/// A commit found for the question.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Source {
+    pub short_id: String,
+    /// Commit time, seconds since the epoch.
+    pub time: i64,
+    pub author: String,
+    pub summary: String,
+    /// The question's words and pickaxe strings it matched.
+    pub matched: Vec<String>,
+    #[serde(skip)]
+    pub(crate) content: String,
+}
+
+/// What was found for a question, before any model is called.
+#[derive(Debug, Clone)]
+pub struct AskInput {
+    pub question: String,
+    /// Most relevant first.
+    pub commits: Vec<Source>,
+    /// Files at HEAD whose paths name the question's words.
+    pub files: Vec<String>,
+    // The commits and files the answer may refer to
+    pub(crate) known: KnownRefs,
+    // The start of each file, for the prompt
+    pub(crate) file_content: Vec<String>,
+}
+
+impl AskInput {
+    /// Whether the search found nothing to answer from.
+    pub fn is_empty(&self) -> bool {
+        self.commits.is_empty() && self.files.is_empty()
+    }
+
+    fn prompt(&self) -> String {
+        let mut out = format!("{} {}\n\nQuestion: {}", ASK_PROMPT, CITATION_INSTRUCTIONS, self.question);
+        for source in &self.commits {
+            out.push_str(&format!("\n\n---\n\n{}", source.content));
+        }
+        for content in &self.file_content {
+            out.push_str(&format!("\n\n---\n\n{}", content));
+        }
+        out
+    }
+
+    /// Estimate the request [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.prompt()) }
+    }
+}
+
+// The words of `question` worth searching for, each once
+fn question_terms(question: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in similar::words(question, 3) {
+        if !QUESTION_WORDS.contains(&word.as_str()) && !terms.contains(&word) {
+            terms.push(word);
+        }
+    }
+    terms
+}
+
+// The spans of `question` written in backticks
+fn quoted(question: &str) -> Vec<String> {
+    question.split('`').skip(1).step_by(2).map(str::trim).filter(|span| !span.is_empty()).map(String::from).collect()
+}
+
+// The pickaxe strings among the lines `commit` adds or removes
+fn pickaxe_hits(repo: &Repository, commit: &git2::Commit, strings: &[String]) -> Result<Vec<String>> {
+    let mut hits: Vec<String> = Vec::new();
+    git::commit_diff(repo, commit, &[])?.print(git2::DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-') {
+            let content = git::escape_non_utf8(line.content());
+            for string in strings {
+                if content.contains(string.as_str()) && !hits.contains(string) {
+                    hits.push(string.clone());
+                }
+            }
+        }
+        true
+    })?;
+    Ok(hits)
+}
+
+/// Search the last `max_commits` non-merge commits reachable from HEAD and
+/// the tree at HEAD for what `question` is about. The commits whose changed
+/// lines contain one of `pickaxe`, or of the question's spans in backticks,
+/// come first.
+pub fn prepare(repo: &Repository, question: &str, pickaxe: &[String], max_commits: usize) -> Result<AskInput> {
+    History::load(repo, max_commits)?.search(question, pickaxe)
+}
+
+/// The commits and paths questions are searched in, read once so that any
+/// number of questions can be asked of them.
+pub struct History<'r> {
+    repo: &'r Repository,
+    candidates: Vec<(git2::Commit<'r>, similar::Change)>,
+    paths: Vec<String>,
+}
+
+impl<'r> History<'r> {
+    /// Read the last `max_commits` non-merge commits reachable from HEAD and
+    /// the paths at HEAD.
+    pub fn load(repo: &'r Repository, max_commits: usize) -> Result<History<'r>> {
+        let (_, total) = git::select_commits(repo, 0)?;
+        progress!("{}", tr!("progress-ask", count = max_commits.min(total)));
+        let mut walk = repo.revwalk()?;
+        walk.push_head()?;
+        let mut candidates = Vec::new();
+        for oid in walk.take(max_commits.min(total)) {
+            let commit = repo.find_commit(oid?)?;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let change = similar::commit_change(repo, &commit)?;
+            candidates.push((commit, change));
+        }
+        Ok(History { repo, candidates, paths: git::head_tree_paths(repo)? })
+    }
+
+    /// Search the history for what `question` is about, putting the commits
+    /// whose changed lines contain one of `pickaxe`, or of the question's
+    /// spans in backticks, first.
+    pub fn search(&self, question: &str, pickaxe: &[String]) -> Result<AskInput> {
+        let terms = question_terms(question);
+        let mut strings: Vec<String> = pickaxe.to_vec();
+        strings.extend(quoted(question).into_iter().filter(|span| !pickaxe.contains(span)));
+
+        // How rare each of the question's words is across the history
+        let documents = self.candidates.len() as f64;
+        let idf: HashMap<&str, f64> = terms
+            .iter()
+            .map(|term| {
+                let frequency = self.candidates.iter().filter(|(_, change)| change.terms.contains_key(term)).count();
+                (term.as_str(), ((documents + 1.0) / (frequency as f64 + 1.0)).ln() + 1.0)
+            })
+            .collect();
+        let mut ranked = Vec::new();
+        for (commit, change) in &self.candidates {
+            let hits = match strings.is_empty() {
+                true => Vec::new(),
+                false => pickaxe_hits(self.repo, commit, &strings)?,
+            };
+            let mut score = hits.len() as f64 * PICKAXE_SCORE;
+            let mut matched = hits;
+            for term in &terms {
+                let in_paths = change.paths.iter().any(|path| path.to_lowercase().contains(term.as_str()));
+                let count = change.terms.get(term).copied().unwrap_or(0) + usize::from(in_paths);
+                if count > 0 {
+                    score += (1.0 + (count as f64).ln()) * idf[term.as_str()];
+                    if !matched.contains(term) {
+                        matched.push(term.clone());
+                    }
+                }
+            }
+            if score > 0.0 {
+                ranked.push((score, commit, change, matched));
+            }
+        }
+        // Stable, so equally relevant commits stay newest first
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked.truncate(TOP_COMMITS);
+
+        let mut known = KnownRefs::new();
+        let mut commits = Vec::new();
+        for (_, commit, change, matched) in ranked {
+            known.add_sha(commit.id().to_string());
+            for path in &change.paths {
+                known.add_path(path.clone());
+            }
+            commits.push(Source {
+                short_id: change.short_id.clone().unwrap_or_default(),
+                time: commit.time().seconds(),
+                author: git::decode_text(commit.author().name_bytes(), commit.message_encoding()),
+                summary: change.summary.clone(),
+                matched,
+                content: change.content.clone(),
+            });
+        }
+
+        let mut scored: Vec<(usize, &String)> = self
+            .paths
+            .iter()
+            .map(|path| (terms.iter().filter(|term| path.to_lowercase().contains(term.as_str())).count(), path))
+            .filter(|(count, _)| *count > 0)
+            .collect();
+        scored.sort_by_key(|(count, path)| (std::cmp::Reverse(*count), path.len()));
+        let mut files = Vec::new();
+        let mut file_content = Vec::new();
+        for (_, path) in scored.into_iter().take(TOP_FILES) {
+            let Ok(text) = self.repo.find_file(path) else { continue };
+            let (start, _) = chunk::truncate_to_tokens(&text, FILE_TOKENS);
+            file_content.push(format!("File: {}\n\n{}", path, start));
+            files.push(path.clone());
+        }
+        for path in &self.paths {
+            known.add_path(path.clone());
+        }
+
+        Ok(AskInput { question: question.trim().to_string(), commits, files, known, file_content })
+    }
+}
+
+/// The answer to a question and what it was drawn from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Answer {
+    pub question: String,
+    pub answer: String,
+    /// References in the answer to commits or paths outside the material.
+    pub unverified: Vec<String>,
+    pub commits: Vec<Source>,
+    pub files: Vec<String>,
+}
+
+/// Answer the question of `input` from what was found for it, handling
+/// references outside of that according to `guard`.
+pub async fn explain(input: &AskInput, provider: &dyn LlmProvider, guard: GuardMode) -> Result<Answer> {
+    let reply = Conversation::new(SYSTEM_PROMPT).ask(provider, input.prompt()).await?;
+    let (answer, unverified) = guard::check(reply.trim(), &input.known, guard);
+    Ok(Answer {
+        question: input.question.clone(),
+        answer,
+        unverified,
+        commits: input.commits.clone(),
+        files: input.files.clone(),
+    })
+}
+
+/// Format `answer` for output, linking cited commits to `commit_url` in
+/// Markdown.
+pub fn render(answer: &Answer, format: OutputFormat, commit_url: Option<&str>) -> String {
+    let markdown = format == OutputFormat::Markdown;
+    let text = match commit_url {
+        Some(base) if markdown => crate::citations::link_citations(&answer.answer, base),
+        _ => answer.answer.clone(),
+    };
+    let code = |text: &str| if markdown { format!("`{}`", text) } else { text.to_string() };
+    let id = |id: &str| match commit_url {
+        Some(base) if markdown => format!("[`{}`]({}/{})", id, base, id),
+        _ => code(id),
+    };
+    let mut sources: String = answer
+        .commits
+        .iter()
+        .map(|source| format!("- {} {} {}\n", id(&source.short_id), git::format_date(source.time), source.summary))
+        .collect();
+    sources.extend(answer.files.iter().map(|path| format!("- {}\n", code(path))));
+    let mut sections = vec![(tr!("ask-answer"), text + "\n")];
+    if !sources.is_empty() {
+        sections.push((tr!("ask-sources"), sources));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(answer).expect("answer serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
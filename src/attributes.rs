@@ -0,0 +1,521 @@
+//! Which `.gitignore` and `.gitattributes` rules apply to a path, and why.
+//!
+//! Git only answers "is this ignored?" and "what is this attribute?"; to say
+//! which line of which file decided it, the rule files are read and matched
+//! here with Git's own precedence. Whether a path is ignored is still taken
+//! from libgit2, so a rule this module cannot trace is reported as such
+//! rather than guessed.
+
+use crate::error::Result;
+use crate::git;
+use crate::render::OutputFormat;
+use crate::tr;
+use git2::Repository;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Where a rule comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleRef {
+    /// The rule file, relative to the repository root (or an absolute path
+    /// for global files).
+    pub source: String,
+    /// 1-based line number in `source`.
+    pub line: usize,
+    /// The line as written.
+    pub pattern: String,
+}
+
+// A parsed pattern line of an ignore or attributes file
+#[derive(Debug, Clone)]
+struct Pattern {
+    glob: Vec<char>,
+    /// Contains a slash, so it matches relative to its directory rather
+    /// than against the file name at any depth.
+    anchored: bool,
+    dir_only: bool,
+    negated: bool,
+    /// Directory of the rule file, `""` for the root and global files.
+    base: String,
+}
+
+impl Pattern {
+    fn parse(text: &str, base: &str) -> Option<Pattern> {
+        let mut text = text.trim_end();
+        if text.is_empty() || text.starts_with('#') {
+            return None;
+        }
+        let negated = text.starts_with('!');
+        if negated {
+            text = &text[1..];
+        }
+        let text = text.strip_prefix('\\').unwrap_or(text);
+        let dir_only = text.ends_with('/');
+        let text = text.trim_end_matches('/');
+        let anchored = text.contains('/');
+        let text = text.strip_prefix('/').unwrap_or(text);
+        Some(Pattern {
+            glob: text.chars().collect(),
+            anchored,
+            dir_only,
+            negated,
+            base: base.to_string(),
+        })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let relative = if self.base.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(&self.base).and_then(|rest| rest.strip_prefix('/')) {
+                Some(rest) => rest,
+                None => return false,
+            }
+        };
+        let target = if self.anchored {
+            relative
+        } else {
+            relative.rsplit('/').next().unwrap_or(relative)
+        };
+        wildmatch(&self.glob, &target.chars().collect::<Vec<_>>())
+    }
+}
+
+/// Whether `text` matches the gitignore-style glob `pattern`: `*` and `?`
+/// stop at slashes, `**` does not, and `[...]` matches a character class.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    wildmatch(&pattern.chars().collect::<Vec<_>>(), &text.chars().collect::<Vec<_>>())
+}
+
+fn wildmatch(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` also matches no directory at all
+            if rest.first() == Some(&'/') && wildmatch(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|i| wildmatch(rest, &text[i..]))
+        }
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| wildmatch(&pattern[1..], &text[i..])),
+        Some('?') => text.first().is_some_and(|&c| c != '/') && wildmatch(&pattern[1..], &text[1..]),
+        Some('[') => match (text.first(), class_end(pattern)) {
+            (Some(&c), Some(end)) => c != '/' && in_class(&pattern[1..end], c) && wildmatch(&pattern[end + 1..], &text[1..]),
+            (Some(&c), None) => c == '[' && wildmatch(&pattern[1..], &text[1..]),
+            (None, _) => false,
+        },
+        Some('\\') if pattern.len() > 1 => text.first() == Some(&pattern[1]) && wildmatch(&pattern[2..], &text[1..]),
+        Some(&c) => text.first() == Some(&c) && wildmatch(&pattern[1..], &text[1..]),
+    }
+}
+
+// Index of the `]` closing the class that starts at `pattern[0]`
+fn class_end(pattern: &[char]) -> Option<usize> {
+    let mut i = 1;
+    if matches!(pattern.get(i), Some('!' | '^')) {
+        i += 1;
+    }
+    // A `]` right at the start is part of the class
+    if pattern.get(i) == Some(&']') {
+        i += 1;
+    }
+    (i..pattern.len()).find(|&j| pattern[j] == ']')
+}
+
+fn in_class(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!' | '^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            found |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+/// The state an attribute is in for a path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum AttrValue {
+    /// `name` (`true`) or `-name` (`false`).
+    Flag(bool),
+    /// `name=value`.
+    Value(String),
+}
+
+/// An attribute that applies to a path, and the rule that set it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Attribute {
+    pub name: String,
+    pub value: AttrValue,
+    pub rule: RuleRef,
+}
+
+/// Why a path is or is not ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "kebab-case")]
+pub enum IgnoreState {
+    NotIgnored,
+    /// A negated rule would have re-included it.
+    ReIncluded { rule: RuleRef },
+    Ignored { rule: RuleRef },
+    /// A parent directory is ignored, which no rule can undo.
+    IgnoredDirectory { directory: String, rule: RuleRef },
+    /// libgit2 says it is ignored, but by no rule found here.
+    IgnoredUntraced,
+}
+
+/// Everything the ignore and attribute rules say about one path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PathRules {
+    pub path: String,
+    /// Whether the path is in the index. Ignore rules do not apply to
+    /// tracked files.
+    pub tracked: bool,
+    pub ignore: IgnoreState,
+    pub attributes: Vec<Attribute>,
+}
+
+impl PathRules {
+    /// Whether any rule applies at all.
+    pub fn has_effect(&self) -> bool {
+        !self.attributes.is_empty() || self.ignore != IgnoreState::NotIgnored
+    }
+}
+
+// Rules of one file, in the order they appear
+struct RuleFile {
+    source: String,
+    base: String,
+    lines: Vec<(usize, String)>,
+}
+
+/// Reads rule files from a repository, caching them per path.
+pub struct Rules<'a> {
+    repo: &'a Repository,
+    files: HashMap<String, Option<RuleFile>>,
+    global_ignore: Option<PathBuf>,
+    global_attributes: Option<PathBuf>,
+}
+
+impl<'a> Rules<'a> {
+    pub fn new(repo: &'a Repository) -> Rules<'a> {
+        let config = repo.config().ok();
+        let configured = |key: &str| config.as_ref().and_then(|c| c.get_path(key).ok());
+        let xdg = |name: &str| {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+                .map(|dir| dir.join("git").join(name))
+        };
+        Rules {
+            repo,
+            files: HashMap::new(),
+            global_ignore: configured("core.excludesFile").or_else(|| xdg("ignore")),
+            global_attributes: configured("core.attributesFile").or_else(|| xdg("attributes")),
+        }
+    }
+
+    /// What the rules say about `path` (relative to the repository root).
+    pub fn explain(&mut self, path: &str) -> Result<PathRules> {
+        let path = path.trim_matches('/').to_string();
+        let is_dir = self.repo.workdir().is_some_and(|dir| dir.join(&path).is_dir());
+        let tracked = self.repo.index()?.get_path(Path::new(&path), 0).is_some();
+        let ignore = self.ignore_state(&path, is_dir)?;
+        let attributes = if is_dir { Vec::new() } else { self.attributes(&path) };
+        Ok(PathRules {
+            path,
+            tracked,
+            ignore,
+            attributes,
+        })
+    }
+
+    fn ignore_state(&mut self, path: &str, is_dir: bool) -> Result<IgnoreState> {
+        let ignored = self.repo.is_path_ignored(path)?;
+        let components: Vec<&str> = path.split('/').collect();
+
+        // Once a directory is ignored, Git never looks inside it
+        for depth in 1..components.len() {
+            let directory = components[..depth].join("/");
+            if let Some((rule, false)) = self.last_ignore_match(&directory, true) {
+                return Ok(IgnoreState::IgnoredDirectory {
+                    directory: format!("{}/", directory),
+                    rule,
+                });
+            }
+        }
+        Ok(match self.last_ignore_match(path, is_dir) {
+            Some((rule, false)) if ignored => IgnoreState::Ignored { rule },
+            Some((rule, true)) if !ignored => IgnoreState::ReIncluded { rule },
+            _ if ignored => IgnoreState::IgnoredUntraced,
+            _ => IgnoreState::NotIgnored,
+        })
+    }
+
+    // The deciding ignore rule for `path`, and whether it is a negation
+    fn last_ignore_match(&mut self, path: &str, is_dir: bool) -> Option<(RuleRef, bool)> {
+        // Lowest precedence first: global excludes, info/exclude, then
+        // .gitignore files from the root down
+        let mut sources = Vec::new();
+        if let Some(global) = self.global_ignore.clone() {
+            sources.push(Source::Absolute(global));
+        }
+        sources.push(Source::GitDir("info/exclude"));
+        for dir in parent_dirs(path) {
+            sources.push(Source::Work(join(&dir, ".gitignore"), dir));
+        }
+
+        let mut decision = None;
+        for source in sources {
+            let Some(file) = self.load(source) else { continue };
+            for (line, text) in &file.lines {
+                let Some(pattern) = Pattern::parse(text, &file.base) else { continue };
+                if pattern.matches(path, is_dir) {
+                    let rule = RuleRef { source: file.source.clone(), line: *line, pattern: text.trim_end().to_string() };
+                    decision = Some((rule, pattern.negated));
+                }
+            }
+        }
+        decision
+    }
+
+    /// The attributes that apply to the file `path`, by name.
+    pub fn attributes(&mut self, path: &str) -> Vec<Attribute> {
+        // Lowest precedence first: the global file, .gitattributes from the
+        // root down, then info/attributes
+        let mut sources = Vec::new();
+        if let Some(global) = self.global_attributes.clone() {
+            sources.push(Source::Absolute(global));
+        }
+        for dir in parent_dirs(path) {
+            sources.push(Source::Work(join(&dir, ".gitattributes"), dir));
+        }
+        sources.push(Source::GitDir("info/attributes"));
+
+        let mut macros: HashMap<String, Vec<String>> = HashMap::new();
+        macros.insert("binary".to_string(), vec!["-diff".to_string(), "-merge".to_string(), "-text".to_string()]);
+        let mut state: BTreeMap<String, Attribute> = BTreeMap::new();
+        for source in sources {
+            let Some(file) = self.load(source) else { continue };
+            for (line, text) in &file.lines {
+                let mut words = text.split_whitespace();
+                let Some(first) = words.next() else { continue };
+                if first.starts_with('#') {
+                    continue;
+                }
+                if let Some(name) = first.strip_prefix("[attr]") {
+                    macros.insert(name.to_string(), words.map(str::to_string).collect());
+                    continue;
+                }
+                let Some(pattern) = Pattern::parse(first.trim_matches('"'), &file.base) else { continue };
+                // Negated and directory patterns never match attributes
+                if pattern.negated || !pattern.matches(path, false) {
+                    continue;
+                }
+                let rule = RuleRef { source: file.source.clone(), line: *line, pattern: text.trim_end().to_string() };
+                for word in words {
+                    apply_attribute(word, &macros, &rule, &mut state);
+                }
+            }
+        }
+        state.into_values().collect()
+    }
+
+    fn load(&mut self, source: Source) -> Option<&RuleFile> {
+        let (key, base, content) = match source {
+            Source::Absolute(path) => {
+                let key = path.to_string_lossy().into_owned();
+                let content = if self.files.contains_key(&key) { None } else { std::fs::read_to_string(&path).ok() };
+                (key, String::new(), content)
+            }
+            Source::GitDir(name) => {
+                let key = format!(".git/{}", name);
+                let content = if self.files.contains_key(&key) {
+                    None
+                } else {
+                    std::fs::read_to_string(self.repo.path().join(name)).ok()
+                };
+                (key, String::new(), content)
+            }
+            Source::Work(path, base) => {
+                let content = if self.files.contains_key(&path) { None } else { read_repo_file(self.repo, &path) };
+                (path, base, content)
+            }
+        };
+        if !self.files.contains_key(&key) {
+            let file = content.map(|content| RuleFile {
+                source: key.clone(),
+                base,
+                lines: content.lines().enumerate().map(|(i, line)| (i + 1, line.to_string())).collect(),
+            });
+            self.files.insert(key.clone(), file);
+        }
+        self.files.get(&key).and_then(Option::as_ref)
+    }
+}
+
+enum Source {
+    Absolute(PathBuf),
+    /// A file inside the `.git` directory.
+    GitDir(&'static str),
+    /// A file in the working tree, with the directory it applies to.
+    Work(String, String),
+}
+
+fn apply_attribute(word: &str, macros: &HashMap<String, Vec<String>>, rule: &RuleRef, state: &mut BTreeMap<String, Attribute>) {
+    let (name, value) = if let Some(name) = word.strip_prefix('-') {
+        (name, Some(AttrValue::Flag(false)))
+    } else if let Some(name) = word.strip_prefix('!') {
+        (name, None)
+    } else if let Some((name, value)) = word.split_once('=') {
+        (name, Some(AttrValue::Value(value.to_string())))
+    } else {
+        (word, Some(AttrValue::Flag(true)))
+    };
+    // A set macro sets the attributes it stands for, then itself
+    if value == Some(AttrValue::Flag(true)) {
+        if let Some(expansion) = macros.get(name) {
+            for word in expansion {
+                apply_attribute(word, &HashMap::new(), rule, state);
+            }
+        }
+    }
+    match value {
+        Some(value) => {
+            state.insert(name.to_string(), Attribute { name: name.to_string(), value, rule: rule.clone() });
+        }
+        None => {
+            state.remove(name);
+        }
+    }
+}
+
+// The directories whose rule files can apply to `path`, root first
+fn parent_dirs(path: &str) -> Vec<String> {
+    let components: Vec<&str> = path.split('/').collect();
+    (0..components.len()).map(|depth| components[..depth].join("/")).collect()
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+// A file from the working tree, or from HEAD in a bare repository
+fn read_repo_file(repo: &Repository, path: &str) -> Option<String> {
+    match repo.workdir() {
+        Some(workdir) => std::fs::read_to_string(workdir.join(path)).ok(),
+        None => {
+            let tree = repo.head().ok()?.peel_to_tree().ok()?;
+            let blob = tree.get_path(Path::new(path)).ok()?.to_object(repo).ok()?.peel_to_blob().ok()?;
+            Some(String::from_utf8_lossy(blob.content()).into_owned())
+        }
+    }
+}
+
+/// The paths changed in the last `commits` commits, sorted.
+pub fn recently_changed(repo: &Repository, commits: usize) -> Result<Vec<String>> {
+    let (commits, _) = git::select_commits(repo, commits)?;
+    let mut paths = std::collections::BTreeSet::new();
+    for commit in &commits {
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path_bytes() {
+                paths.insert(git::escape_non_utf8(path));
+            }
+        }
+    }
+    Ok(paths.into_iter().collect())
+}
+
+/// What an attribute does, in plain language.
+pub fn describe(attribute: &Attribute) -> String {
+    use AttrValue::{Flag, Value};
+    match (attribute.name.as_str(), &attribute.value) {
+        ("text", Flag(true)) => tr!("attr-text"),
+        ("text", Flag(false)) => tr!("attr-no-text"),
+        ("text", Value(value)) if value == "auto" => tr!("attr-text-auto"),
+        ("eol", Value(eol)) => tr!("attr-eol", eol = eol.to_uppercase()),
+        ("binary", Flag(true)) => tr!("attr-binary"),
+        ("diff", Flag(false)) => tr!("attr-no-diff"),
+        ("diff", Value(driver)) => tr!("attr-diff-driver", driver = driver.as_str()),
+        ("merge", Flag(false)) => tr!("attr-no-merge"),
+        ("merge", Value(driver)) => tr!("attr-merge-driver", driver = driver.as_str()),
+        ("filter", Value(filter)) if filter == "lfs" => tr!("attr-lfs"),
+        ("filter", Value(filter)) => tr!("attr-filter", filter = filter.as_str()),
+        ("export-ignore", Flag(true)) => tr!("attr-export-ignore"),
+        ("linguist-generated", Flag(true)) => tr!("attr-generated"),
+        ("linguist-vendored", Flag(true)) => tr!("attr-vendored"),
+        ("working-tree-encoding", Value(encoding)) => tr!("attr-encoding", encoding = encoding.as_str()),
+        (name, Value(value)) => tr!("attr-other", setting = format!("{}={}", name, value)),
+        (name, Flag(true)) => tr!("attr-other", setting = name),
+        (name, Flag(false)) => tr!("attr-other", setting = format!("-{}", name)),
+    }
+}
+
+/// Explain the rules of each path, as JSON or as text.
+pub fn render(paths: &[PathRules], format: OutputFormat) -> String {
+    if format == OutputFormat::Json {
+        // Serializing plain strings and flags cannot fail
+        let mut out = serde_json::to_string_pretty(paths).expect("rules serialize to JSON");
+        out.push('\n');
+        return out;
+    }
+    let markdown = format == OutputFormat::Markdown;
+    let code = |text: &str| if markdown { format!("`{}`", text) } else { text.to_string() };
+    let rule = |rule: &RuleRef| tr!("attrs-rule", pattern = code(&rule.pattern), source = rule.source.as_str(), line = rule.line);
+
+    let mut out = String::new();
+    for (i, path) in paths.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&if markdown { format!("### `{}`\n\n", path.path) } else { format!("{}\n", path.path) });
+        let ignore = match &path.ignore {
+            IgnoreState::NotIgnored => tr!("ignore-no"),
+            IgnoreState::ReIncluded { rule: r } => tr!("ignore-reincluded", rule = rule(r)),
+            IgnoreState::Ignored { rule: r } => tr!("ignore-yes", rule = rule(r)),
+            IgnoreState::IgnoredDirectory { directory, rule: r } => {
+                tr!("ignore-via-dir", dir = code(directory), rule = rule(r))
+            }
+            IgnoreState::IgnoredUntraced => tr!("ignore-untraced"),
+        };
+        let mut lines = vec![ignore];
+        if path.tracked && !matches!(path.ignore, IgnoreState::NotIgnored | IgnoreState::ReIncluded { .. }) {
+            lines.push(tr!("ignore-tracked"));
+        }
+        if path.attributes.is_empty() {
+            lines.push(tr!("attrs-none"));
+        }
+        for attribute in &path.attributes {
+            lines.push(tr!("attrs-entry", effect = describe(attribute), rule = rule(&attribute.rule)));
+        }
+        for line in lines {
+            out.push_str(&format!("- {}\n", line));
+        }
+    }
+    out
+}
@@ -0,0 +1,246 @@
+//! `wtf blame <path> --lines A-B`: why some lines look the way they do.
+//!
+//! The lines are blamed at HEAD. Every commit that last changed one of them
+//! is shown to the model with its message and its patch of the file, so the
+//! explanation can cite the commits it is based on.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::citations::{self, CITATION_INSTRUCTIONS};
+use crate::conversation::Conversation;
+use crate::error::{Result, WtfError};
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{BlameOptions, DiffOptions, Oid, Repository};
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains, from a file's Git history, why its code looks the way it does.";
+
+const BLAME_PROMPT: &str = "Here are some lines of a file, each prefixed with its line number and the short SHA of the commit that last changed it, followed by those commits with their messages and their changes to the file. Explain in plain language why the lines look the way they do: what each commit was trying to achieve and how that shaped the code. Where something looks odd, say what in the history explains it. Keep it under 250 words.";
+
+// Each commit's patch of the file is cut to this many tokens
+const COMMIT_PATCH_TOKENS: usize = 1500;
+
+/// Lines of a file, numbered from 1, both ends included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FromStr for LineRange {
+    type Err = String;
+
+    /// `120-160`, or `120` for one line.
+    fn from_str(s: &str) -> std::result::Result<LineRange, String> {
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        match (start.trim().parse(), end.trim().parse()) {
+            (Ok(start), Ok(end)) if start >= 1 && end >= start => Ok(LineRange { start, end }),
+            _ => Err(tr!("blame-invalid-lines", lines = s)),
+        }
+    }
+}
+
+/// One blamed line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlamedLine {
+    pub number: usize,
+    pub short_id: String,
+    pub text: String,
+}
+
+/// A commit that last changed some of the lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlamedCommit {
+    #[serde(skip)]
+    pub id: Oid,
+    pub short_id: String,
+    pub author: String,
+    /// Commit time, seconds since the epoch.
+    pub time: i64,
+    pub summary: String,
+    /// How many of the lines it last changed.
+    pub lines: usize,
+    /// Its metadata and its patch of the file, for the prompt.
+    #[serde(skip)]
+    pub content: String,
+}
+
+/// The blamed lines and their commits, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct BlameInput {
+    /// The path, relative to the repository root.
+    pub path: String,
+    /// The lines asked for, cut to the end of the file.
+    pub range: LineRange,
+    pub lines: Vec<BlamedLine>,
+    /// Newest first.
+    pub commits: Vec<BlamedCommit>,
+    /// Base URL for linking commits, if known.
+    pub commit_url: Option<String>,
+}
+
+/// Blame `lines` of `path` (relative to the repository root) at HEAD, or
+/// the whole file without `lines`.
+pub fn prepare(repo: &Repository, path: &str, lines: Option<LineRange>) -> Result<BlameInput> {
+    let head = repo.head().and_then(|head| head.peel_to_commit()).map_err(|_| WtfError::NoCommits)?;
+    let unknown = || WtfError::UnknownPath { path: path.to_string() };
+    let entry = head.tree()?.get_path(Path::new(path)).map_err(|_| unknown())?;
+    let blob = repo.find_blob(entry.id()).map_err(|_| unknown())?;
+    let text = git::escape_non_utf8(blob.content());
+    let file_lines: Vec<&str> = text.lines().collect();
+
+    let wanted = lines.unwrap_or(LineRange { start: 1, end: file_lines.len() });
+    if wanted.start > file_lines.len() {
+        return Err(WtfError::NoSuchLines { path: path.to_string(), count: file_lines.len() });
+    }
+    let range = LineRange { start: wanted.start, end: wanted.end.min(file_lines.len()) };
+    let mut options = BlameOptions::new();
+    options.newest_commit(head.id()).min_line(range.start).max_line(range.end);
+    let blame = repo.blame_file(Path::new(path), Some(&mut options))?;
+
+    let mut blamed = Vec::new();
+    // (commit, the file's path in it, lines) in order of appearance
+    let mut by_commit: Vec<(Oid, String, usize)> = Vec::new();
+    for number in range.start..=range.end {
+        let Some(hunk) = blame.get_line(number) else { continue };
+        let id = hunk.final_commit_id();
+        match by_commit.iter_mut().find(|(known, _, _)| *known == id) {
+            Some((_, _, count)) => *count += 1,
+            None => {
+                let path_then = hunk.path().map_or(path.to_string(), |p| p.to_string_lossy().replace('\\', "/"));
+                by_commit.push((id, path_then, 1));
+            }
+        }
+        let short_id = repo.find_object(id, None)?.short_id()?.as_str().unwrap_or_default().to_string();
+        blamed.push(BlamedLine { number, short_id, text: file_lines[number - 1].to_string() });
+    }
+    progress!("{}", tr!("progress-blame", lines = blamed.len(), count = by_commit.len()));
+
+    let mut commits = Vec::new();
+    for (id, path_then, lines) in by_commit {
+        let commit = repo.find_commit(id)?;
+        let patch = file_patch(repo, &commit, &path_then)?;
+        let (patch, _) = chunk::truncate_to_tokens(&patch, COMMIT_PATCH_TOKENS);
+        commits.push(BlamedCommit {
+            id,
+            short_id: commit.as_object().short_id()?.as_str().unwrap_or_default().to_string(),
+            author: git::decode_text(commit.author().name_bytes(), commit.message_encoding()),
+            time: commit.time().seconds(),
+            summary: git::commit_message(&commit).lines().next().unwrap_or_default().to_string(),
+            lines,
+            content: format!("{}\n\n{}", git::get_commit_details(&commit), patch),
+        });
+    }
+    commits.sort_by_key(|commit| std::cmp::Reverse(commit.time));
+
+    Ok(BlameInput { path: path.to_string(), range, lines: blamed, commits, commit_url: git::commit_url_base(repo) })
+}
+
+// The patch `commit` made to `path`, against its first parent
+fn file_patch(repo: &Repository, commit: &git2::Commit<'_>, path: &str) -> Result<String> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let mut options = DiffOptions::new();
+    options.pathspec(path).disable_pathspec_match(true);
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut options))?;
+    git::diff_patch(&diff)
+}
+
+impl BlameInput {
+    // The lines, then their commits
+    fn content(&self) -> String {
+        let mut out = format!("Lines {}-{} of {}:\n\n", self.range.start, self.range.end, self.path);
+        for line in &self.lines {
+            out.push_str(&format!("{:>5} {} {}\n", line.number, line.short_id, line.text));
+        }
+        for commit in &self.commits {
+            out.push_str("\n---\n\n");
+            out.push_str(&commit.content);
+            out.push('\n');
+        }
+        out
+    }
+
+    fn question(&self) -> String {
+        format!("{} {}\n\n{}", BLAME_PROMPT, CITATION_INSTRUCTIONS, self.content())
+    }
+
+    /// Estimate the request [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate {
+            requests: 1,
+            prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question()),
+        }
+    }
+}
+
+/// Why the lines look the way they do.
+#[derive(Debug, Clone, Serialize)]
+pub struct Blame {
+    pub path: String,
+    pub range: LineRange,
+    pub explanation: String,
+    pub commits: Vec<BlamedCommit>,
+    #[serde(skip)]
+    pub commit_url: Option<String>,
+}
+
+/// Ask the model why the lines in `input` look the way they do.
+pub async fn explain(input: &BlameInput, provider: &dyn LlmProvider) -> Result<Blame> {
+    let explanation = Conversation::new(SYSTEM_PROMPT).ask(provider, input.question()).await?;
+    Ok(Blame {
+        path: input.path.clone(),
+        range: input.range,
+        explanation,
+        commits: input.commits.clone(),
+        commit_url: input.commit_url.clone(),
+    })
+}
+
+/// Format `blame` for output.
+pub fn render(blame: &Blame, format: OutputFormat) -> String {
+    let title = tr!("blame-title", path = blame.path.as_str(), start = blame.range.start, end = blame.range.end);
+    let commits: String = blame
+        .commits
+        .iter()
+        .map(|commit| {
+            let entry = tr!(
+                "blame-commit-entry",
+                sha = commit.short_id.as_str(),
+                summary = commit.summary.as_str(),
+                author = commit.author.as_str(),
+                date = git::format_date(commit.time),
+                count = commit.lines
+            );
+            format!("- {}\n", entry)
+        })
+        .collect();
+    let explanation = match (format, &blame.commit_url) {
+        (OutputFormat::Markdown, Some(base)) => citations::link_citations(blame.explanation.trim(), base),
+        _ => blame.explanation.trim().to_string(),
+    };
+    let sections = [(title, explanation + "\n"), (tr!("blame-commits", count = blame.commits.len()), commits)];
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(blame).expect("blame serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
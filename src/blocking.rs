@@ -0,0 +1,42 @@
+//! A blocking facade over [`analysis`](crate::analysis).
+//!
+//! For callers without an async runtime of their own, such as build scripts,
+//! small tools or FFI wrappers. Each call runs the async API to completion on
+//! a private single-threaded runtime, so callers need neither tokio nor
+//! `.await`:
+//!
+//! ```no_run
+//! # fn provider() -> Box<dyn wtf::provider::LlmProvider> { unimplemented!() }
+//! let repo = wtf::git::open_repository(std::path::Path::new("."))?;
+//! let report = wtf::blocking::analyze(&repo, provider().as_ref(), &Default::default())?;
+//! println!("{}", report.project_description);
+//! # Ok::<(), wtf::WtfError>(())
+//! ```
+//!
+//! These functions must not be called from inside an async runtime; use the
+//! async API there instead.
+
+use crate::analysis::{self, AnalysisInput, AnalysisOptions, Report};
+use crate::error::Result;
+use crate::provider::LlmProvider;
+use git2::Repository;
+use std::future::Future;
+
+/// Blocking version of [`analysis::analyze`].
+pub fn analyze(repo: &Repository, provider: &dyn LlmProvider, options: &AnalysisOptions) -> Result<Report> {
+    block_on(analysis::analyze(repo, provider, options))?
+}
+
+/// Blocking version of [`analysis::run`], for input from [`analysis::prepare`].
+pub fn run(input: &AnalysisInput, provider: &dyn LlmProvider, options: &AnalysisOptions) -> Result<Report> {
+    block_on(analysis::run(input, provider, options))?
+}
+
+/// Run `future` to completion on a fresh single-threaded runtime.
+///
+/// Providers may rely on tokio (for timers and I/O), so this is a real
+/// runtime rather than a bare executor.
+pub fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    Ok(runtime.block_on(future))
+}
@@ -0,0 +1,100 @@
+//! Bookmarks for `wtf mark` and `wtf since-last`.
+//!
+//! A bookmark remembers which commit a branch of a repository was at when
+//! you last looked. Bookmarks live in `bookmarks.json` in wtf's data
+//! directory rather than in the repository, so they belong to one user and
+//! are never pushed.
+
+use crate::error::Result;
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+/// Where a branch was when it was bookmarked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// The full SHA of the bookmarked commit.
+    pub commit: String,
+    /// When the bookmark was set, seconds since the epoch.
+    pub time: i64,
+}
+
+impl Bookmark {
+    /// A bookmark on `commit`, set now.
+    pub fn now(commit: Oid) -> Bookmark {
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        Bookmark { commit: commit.to_string(), time }
+    }
+}
+
+/// All bookmarks, by repository and then branch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmarks {
+    repositories: BTreeMap<String, BTreeMap<String, Bookmark>>,
+}
+
+impl Bookmarks {
+    /// Read the bookmarks from `data_dir`; a missing or unreadable file
+    /// means there are none.
+    pub fn load(data_dir: &Path) -> Bookmarks {
+        std::fs::read_to_string(bookmarks_path(data_dir))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the bookmarks to `data_dir`, creating it if needed.
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(data_dir)?;
+        // Serializing maps of strings cannot fail
+        let json = serde_json::to_string_pretty(self).expect("bookmarks serialize to JSON");
+        std::fs::write(bookmarks_path(data_dir), json)?;
+        Ok(())
+    }
+
+    pub fn get(&self, repository: &str, branch: &str) -> Option<&Bookmark> {
+        self.repositories.get(repository)?.get(branch)
+    }
+
+    pub fn set(&mut self, repository: &str, branch: &str, bookmark: Bookmark) {
+        self.repositories.entry(repository.to_string()).or_default().insert(branch.to_string(), bookmark);
+    }
+}
+
+fn bookmarks_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(BOOKMARKS_FILE)
+}
+
+/// What identifies `repo` among the bookmarks: the absolute path of its
+/// working directory (or of the `.git` directory of a bare repository).
+pub fn repository_key(repo: &Repository) -> String {
+    let path = repo.workdir().unwrap_or_else(|| repo.path());
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    path.to_string_lossy().trim_end_matches(['/', '\\']).to_string()
+}
+
+/// The branch HEAD is on, or `HEAD` when it is detached.
+pub fn current_branch(repo: &Repository) -> String {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().unwrap_or("HEAD").to_string(),
+        _ => "HEAD".to_string(),
+    }
+}
+
+/// The commits reachable from HEAD but not from `bookmark`, newest first,
+/// or `None` if the bookmarked commit no longer exists (for example after
+/// a rebase and garbage collection).
+pub fn commits_since(repo: &Repository, bookmark: &Bookmark) -> Result<Option<Vec<Oid>>> {
+    let Ok(marked) = Oid::from_str(&bookmark.commit).and_then(|oid| repo.find_commit(oid)) else {
+        return Ok(None);
+    };
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.hide(marked.id())?;
+    Ok(Some(walk.collect::<std::result::Result<Vec<_>, _>>()?))
+}
@@ -0,0 +1,338 @@
+//! `wtf breaking <range>`: the probable breaking changes in a range.
+//!
+//! Each commit's diff is searched for the kinds of change that tend to break
+//! users: public functions and types removed or renamed, command-line flags
+//! removed or changed, config schemas changed and database migrations. Files
+//! are picked by name (`migrations/`, `.sql`, `config`, `settings`,
+//! `schema`) and flags by the lines a diff removes, like `#[arg(` or
+//! `add_argument(`. What is found is only evidence: the model reads the
+//! lines of each candidate and keeps the probable breaking changes.
+//!
+//! For Rust library crates the public API is compared before and after each
+//! commit instead, as for `wtf range`; an item removed or changed breaks
+//! dependents by definition, so those are listed without asking.
+//! Generated, vendored and excluded files are left out.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::error::Result;
+use crate::generated::Detector;
+use crate::git::{self, RevRange};
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::rust_api;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that finds changes which break a project's users.";
+
+const BREAKING_PROMPT: &str = "Below are numbered candidates for breaking changes, each a file a commit changed and the lines it removed and added. Decide for each whether it probably breaks users upgrading to this version: removed or renamed command-line flags or options, changed defaults, renamed or removed config keys or changed config formats, and database migrations that drop, rename or change columns or tables or need data to be migrated. Adding something optional, like a new flag, key or table, does not break anyone. For each breaking candidate, describe in one sentence what breaks and for whom.";
+
+// Candidates judged per request
+const BATCH_SIZE: usize = 10;
+
+// The lines of a candidate are cut to this many tokens
+const EVIDENCE_TOKENS: usize = 1_500;
+
+// Removed lines that define command-line flags, in the common parsers
+const FLAG_MARKERS: &[&str] =
+    &["#[arg(", "#[clap(", ".arg(", ".long(", "add_argument(", "add_option(", "@click.option(", ".option(", "flag."];
+
+// Words in a file name that point at a config schema
+const CONFIG_WORDS: &[&str] = &["config", "settings", "schema"];
+
+/// What kind of breaking change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// A public item of a Rust library crate removed or changed.
+    Api,
+    /// A command-line flag removed or changed.
+    Cli,
+    /// A config schema changed.
+    Config,
+    /// A database migration.
+    Migration,
+}
+
+impl Kind {
+    const ALL: [Kind; 4] = [Kind::Api, Kind::Cli, Kind::Config, Kind::Migration];
+
+    fn title(self) -> String {
+        match self {
+            Kind::Api => tr!("breaking-api"),
+            Kind::Cli => tr!("breaking-cli"),
+            Kind::Config => tr!("breaking-config"),
+            Kind::Migration => tr!("breaking-migration"),
+        }
+    }
+}
+
+/// A change the model is asked about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub kind: Kind,
+    pub path: String,
+    pub short_id: String,
+    pub subject: String,
+    /// The lines the commit removed and added in the file.
+    pub evidence: String,
+}
+
+/// The range, searched before any model is called.
+#[derive(Debug, Clone)]
+pub struct BreakingInput {
+    pub range: RevRange,
+    /// How many commits were searched, merges left out.
+    pub commits: usize,
+    /// Public API changes, breaking without asking.
+    pub api: Vec<Finding>,
+    pub candidates: Vec<Candidate>,
+}
+
+// What kind of candidate a file is, from its path and the lines the diff
+// removed from it
+fn classify(path: &str, removed: &[&str]) -> Option<Kind> {
+    let lower = path.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or_default();
+    let in_migrations = lower.split('/').any(|part| matches!(part, "migrations" | "migration" | "migrate"));
+    if in_migrations || name.ends_with(".sql") {
+        return Some(Kind::Migration);
+    }
+    if CONFIG_WORDS.iter().any(|word| name.contains(word)) && !removed.is_empty() {
+        return Some(Kind::Config);
+    }
+    let flag = |line: &&str| FLAG_MARKERS.iter().any(|marker| line.contains(marker)) || line.contains("\"--");
+    if removed.iter().any(flag) {
+        return Some(Kind::Cli);
+    }
+    None
+}
+
+/// Search the commits of `range` for probable breaking changes, leaving out
+/// files matching `exclude`.
+pub fn prepare(repo: &Repository, range: &RevRange, exclude: &[String]) -> Result<BreakingInput> {
+    let ids = git::range_commits(repo, range)?;
+    let mut detector = Detector::with_exclude(repo, exclude);
+    let mut input = BreakingInput { range: range.clone(), commits: 0, api: Vec::new(), candidates: Vec::new() };
+    for id in ids {
+        let commit = repo.find_commit(id)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        input.commits += 1;
+        let Some(patch) = git::commit_patch(repo, &commit)? else { continue };
+        let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+        let subject = git::commit_message(&commit).lines().next().unwrap_or_default().to_string();
+
+        let mut rust = false;
+        for file in chunk::split_files(&patch) {
+            let Some(path) = file.path() else { continue };
+            rust |= path.ends_with(".rs");
+            if detector.kind(path).is_some() {
+                continue;
+            }
+            let lines: Vec<&str> = file
+                .hunks
+                .iter()
+                .flat_map(|hunk| hunk.lines())
+                .filter(|line| line.starts_with(['-', '+']))
+                .collect();
+            let removed: Vec<&str> = lines.iter().copied().filter(|line| line.starts_with('-')).collect();
+            let Some(kind) = classify(path, &removed) else { continue };
+            let (evidence, _) = chunk::truncate_to_tokens(&lines.join("\n"), EVIDENCE_TOKENS);
+            let (short_id, subject) = (short_id.clone(), subject.clone());
+            input.candidates.push(Candidate { kind, path: path.to_string(), short_id, subject, evidence });
+        }
+
+        if rust {
+            let parent = commit.parent(0)?.tree()?;
+            let Some(changes) = rust_api::changes_between(repo, Some(&parent), &commit.tree()?)? else { continue };
+            let finding = |path: &str, description: String| Finding {
+                kind: Kind::Api,
+                path: path.to_string(),
+                short_id: short_id.clone(),
+                subject: subject.clone(),
+                description,
+            };
+            for item in &changes.removed {
+                input.api.push(finding(&item.path, tr!("breaking-api-removed", signature = item.signature.as_str())));
+            }
+            for change in &changes.changed {
+                let description =
+                    tr!("breaking-api-changed", before = change.before.as_str(), after = change.after.as_str());
+                input.api.push(finding(&change.path, description));
+            }
+        }
+    }
+    progress!("{}", tr!("progress-breaking", count = input.candidates.len(), commits = input.commits));
+    Ok(input)
+}
+
+impl BreakingInput {
+    fn question(offset: usize, batch: &[Candidate]) -> String {
+        let mut out = BREAKING_PROMPT.to_string();
+        for (at, candidate) in batch.iter().enumerate() {
+            out.push_str(&format!(
+                "\n\n---\n\nCandidate {}\nFile: {}\nCommit: {} {}\n\n{}",
+                offset + at + 1,
+                candidate.path,
+                candidate.short_id,
+                candidate.subject,
+                candidate.evidence
+            ));
+        }
+        out
+    }
+
+    /// Estimate the requests [`explain`] will send; none without candidates
+    /// outside the public API.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate {
+            requests: self.candidates.len().div_ceil(BATCH_SIZE),
+            prompt_tokens: self
+                .candidates
+                .chunks(BATCH_SIZE)
+                .enumerate()
+                .map(|(at, batch)| {
+                    tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&Self::question(at * BATCH_SIZE, batch))
+                })
+                .sum(),
+        }
+    }
+}
+
+/// A probable breaking change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub kind: Kind,
+    /// The file, or for the public API the item, like `crate::parser::parse`.
+    pub path: String,
+    pub short_id: String,
+    pub subject: String,
+    pub description: String,
+}
+
+/// The probable breaking changes in a range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BreakingChanges {
+    pub range: RevRange,
+    pub commits: usize,
+    /// How many candidates the model was asked about.
+    pub candidates: usize,
+    /// By kind, then newest first.
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    candidates: Vec<Judged>,
+}
+
+#[derive(Deserialize)]
+struct Judged {
+    candidate: usize,
+    breaking: bool,
+    description: String,
+}
+
+/// Ask the model which candidates break users, a batch at a time. Without
+/// a `provider`, which [`BreakingInput::estimate`] makes unnecessary when it
+/// expects no requests, only the public API changes are listed.
+pub async fn explain(input: &BreakingInput, provider: Option<&dyn LlmProvider>) -> Result<BreakingChanges> {
+    let schema = Schema::new(
+        "breaking_changes",
+        json!({
+            "type": "object",
+            "properties": {
+                "candidates": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "candidate": { "type": "integer" },
+                            "breaking": { "type": "boolean" },
+                            "description": { "type": "string" },
+                        },
+                        "required": ["candidate", "breaking", "description"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["candidates"],
+            "additionalProperties": false,
+        }),
+    );
+    let mut findings = input.api.clone();
+    if let Some(provider) = provider {
+        for (at, batch) in input.candidates.chunks(BATCH_SIZE).enumerate() {
+            let offset = at * BATCH_SIZE;
+            let question = BreakingInput::question(offset, batch);
+            let messages = [Message::system(SYSTEM_PROMPT), Message::user(question)];
+            let reply: Reply = structured::request(provider, &messages, &schema).await?;
+            for (at, candidate) in batch.iter().enumerate() {
+                let number = offset + at + 1;
+                let judged = reply.candidates.iter().find(|judged| judged.candidate == number && judged.breaking);
+                let Some(judged) = judged else { continue };
+                findings.push(Finding {
+                    kind: candidate.kind,
+                    path: candidate.path.clone(),
+                    short_id: candidate.short_id.clone(),
+                    subject: candidate.subject.clone(),
+                    description: judged.description.trim().to_string(),
+                });
+            }
+        }
+    }
+    // Stable, so findings of a kind stay newest first
+    findings.sort_by_key(|finding| finding.kind);
+    Ok(BreakingChanges {
+        range: input.range.clone(),
+        commits: input.commits,
+        candidates: input.candidates.len(),
+        findings,
+    })
+}
+
+/// Format `changes` for output, grouped by kind.
+pub fn render(changes: &BreakingChanges, format: OutputFormat) -> String {
+    let overview = match changes.findings.len() {
+        0 => tr!("breaking-none", commits = changes.commits),
+        count => tr!("breaking-overview", count = count, commits = changes.commits),
+    };
+    let mut sections = vec![(tr!("breaking-title", range = changes.range.to_string()), overview + "\n")];
+    for kind in Kind::ALL {
+        let body: String = changes
+            .findings
+            .iter()
+            .filter(|finding| finding.kind == kind)
+            .map(|finding| {
+                format!("- `{}` ({} {}): {}\n", finding.path, finding.short_id, finding.subject, finding.description)
+            })
+            .collect();
+        if !body.is_empty() {
+            sections.push((kind.title(), body));
+        }
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(changes).expect("breaking changes serialize to JSON") + "\n"
+        }
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,282 @@
+//! `wtf broke <symptom> --good <rev>`: which commit most likely broke it.
+//!
+//! Every commit between the good and the bad revision is a candidate. The
+//! model is given the symptom and each candidate's message and patch, in
+//! batches, and rates how likely each one is to have caused it. With a test
+//! command, `git bisect run` is driven first and the commit it finds is
+//! pointed out to the model, so the ranking explains it.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::error::{Result, WtfError};
+use crate::git::{self, RevRange};
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Oid, Repository, RepositoryState};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::process::{Command, Stdio};
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that helps developers find the commit that broke something.";
+
+const RANK_PROMPT: &str = "Something broke between a good and a bad revision of a Git repository. The symptom is described below, followed by candidate commits from between the two, each with its message and its changes. For each commit, summarize in one sentence what it changed, rate from 0 to 100 how likely it is to have caused the symptom, and give the reason in at most 25 words. Judge by whether the changes touch what the symptom involves, not by how large they are. Return one entry per commit, using the short SHA given.";
+
+// Added to the prompt when `git bisect run` found the culprit
+const BISECTED_NOTE: &str = "git bisect run has already found the first bad commit: {sha}. Rate it accordingly, and explain how its changes could cause the symptom.";
+
+// Each candidate's patch is cut to this many tokens
+const CANDIDATE_PATCH_TOKENS: usize = 1500;
+
+// Candidates rated per request
+const BATCH_SIZE: usize = 20;
+
+// Suspects shown outside JSON output
+const SHOWN_SUSPECTS: usize = 10;
+
+/// A commit between the good and the bad revision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Candidate {
+    #[serde(skip)]
+    pub id: Oid,
+    pub short_id: String,
+    pub summary: String,
+    /// Its metadata and truncated patch, for the prompt.
+    #[serde(skip)]
+    pub content: String,
+}
+
+/// The candidates, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct BrokeInput {
+    pub symptom: String,
+    /// From the good to the bad revision.
+    pub range: RevRange,
+    /// Newest first.
+    pub candidates: Vec<Candidate>,
+    /// The short SHA of the first bad commit `git bisect run` found.
+    pub bisected: Option<String>,
+}
+
+/// Gather the commits in `range`, from the good to the bad revision, as
+/// candidates for having caused `symptom`.
+pub fn prepare(repo: &Repository, symptom: &str, range: &RevRange) -> Result<BrokeInput> {
+    let mut candidates = Vec::new();
+    for id in git::range_commits(repo, range)? {
+        let commit = repo.find_commit(id)?;
+        let patch = git::commit_patch(repo, &commit)?.unwrap_or_default();
+        let (patch, _) = chunk::truncate_to_tokens(&patch, CANDIDATE_PATCH_TOKENS);
+        let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+        candidates.push(Candidate {
+            id,
+            content: format!("Short SHA: {}\n{}\n\n{}", short_id, git::get_commit_details(&commit), patch),
+            short_id,
+            summary: git::commit_message(&commit).lines().next().unwrap_or_default().to_string(),
+        });
+    }
+    progress!("{}", tr!("progress-broke", count = candidates.len()));
+    Ok(BrokeInput { symptom: symptom.to_string(), range: range.clone(), candidates, bisected: None })
+}
+
+// Run git with `args` in the work tree of `repo`, its output going to
+// stderr so it stays apart from the report
+fn run_git(repo: &Repository, args: &[&str]) -> Result<()> {
+    let dir = repo.workdir().unwrap_or(repo.path());
+    let output = Command::new("git").args(args).current_dir(dir).stdin(Stdio::null()).output()?;
+    eprint!("{}", String::from_utf8_lossy(&output.stdout));
+    match output.status.success() {
+        true => Ok(()),
+        false => {
+            let output = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            Err(WtfError::BisectFailed { output })
+        }
+    }
+}
+
+/// Drive `git bisect run` with `command` over `range`, from the good to the
+/// bad revision, and return the first bad commit it found. The bisect is
+/// reset afterwards, whatever happened.
+pub fn bisect(repo: &Repository, range: &RevRange, command: &[String]) -> Result<Option<Oid>> {
+    if repo.state() == RepositoryState::Bisect {
+        return Err(WtfError::BisectFailed { output: tr!("broke-bisect-running") });
+    }
+    let (good, bad) = (git::resolve_commit(repo, &range.from)?.id(), git::resolve_commit(repo, &range.to)?.id());
+    run_git(repo, &["bisect", "start", &bad.to_string(), &good.to_string()])?;
+    let mut args = vec!["bisect", "run"];
+    args.extend(command.iter().map(String::as_str));
+    let result = run_git(repo, &args).and_then(|()| Ok(repo.find_reference("refs/bisect/bad")?.target()));
+    run_git(repo, &["bisect", "reset"])?;
+    result
+}
+
+/// How likely one candidate is to have caused the symptom.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suspect {
+    pub short_id: String,
+    pub summary: String,
+    /// What the commit changed, in the model's words.
+    pub change: String,
+    /// From 0 to 100.
+    pub likelihood: u8,
+    pub reason: String,
+}
+
+/// The candidates, most likely culprit first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Verdict {
+    pub symptom: String,
+    pub range: RevRange,
+    pub candidates: usize,
+    pub bisected: Option<String>,
+    pub suspects: Vec<Suspect>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    commits: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    sha: String,
+    change: String,
+    likelihood: u8,
+    reason: String,
+}
+
+impl BrokeInput {
+    fn question(&self, batch: &[Candidate]) -> String {
+        let mut out = RANK_PROMPT.to_string();
+        if let Some(sha) = &self.bisected {
+            out.push(' ');
+            out.push_str(&BISECTED_NOTE.replace("{sha}", sha));
+        }
+        out.push_str(&format!("\n\nSymptom: {}\n", self.symptom));
+        out.push_str(&format!("Good: {}\nBad: {}\n", self.range.from, self.range.to));
+        for candidate in batch {
+            out.push_str("\n---\n\n");
+            out.push_str(&candidate.content);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate {
+            requests: self.candidates.len().div_ceil(BATCH_SIZE),
+            prompt_tokens: self
+                .candidates
+                .chunks(BATCH_SIZE)
+                .map(|batch| tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question(batch)))
+                .sum(),
+        }
+    }
+}
+
+/// Ask the model to rate every candidate, a batch at a time.
+///
+/// Candidates the model skipped are left out of the verdict.
+pub async fn explain(input: &BrokeInput, provider: &dyn LlmProvider) -> Result<Verdict> {
+    let mut suspects = Vec::new();
+    for batch in input.candidates.chunks(BATCH_SIZE) {
+        let messages = [Message::system(SYSTEM_PROMPT), Message::user(input.question(batch))];
+        let schema = Schema::new(
+            "broke_ranking",
+            json!({
+                "type": "object",
+                "properties": {
+                    "commits": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "sha": { "type": "string" },
+                                "change": { "type": "string" },
+                                "likelihood": { "type": "integer", "minimum": 0, "maximum": 100 },
+                                "reason": { "type": "string" },
+                            },
+                            "required": ["sha", "change", "likelihood", "reason"],
+                            "additionalProperties": false,
+                        },
+                    },
+                },
+                "required": ["commits"],
+                "additionalProperties": false,
+            }),
+        );
+        let reply: Reply = structured::request(provider, &messages, &schema).await?;
+        for candidate in batch {
+            let full_id = candidate.id.to_string();
+            let entry = reply.commits.iter().find(|entry| entry.sha.len() >= 7 && full_id.starts_with(&entry.sha));
+            if let Some(entry) = entry {
+                suspects.push(Suspect {
+                    short_id: candidate.short_id.clone(),
+                    summary: candidate.summary.clone(),
+                    change: entry.change.trim().to_string(),
+                    likelihood: entry.likelihood.min(100),
+                    reason: entry.reason.trim().to_string(),
+                });
+            }
+        }
+    }
+    // Stable, so equally likely commits stay newest first
+    suspects.sort_by_key(|suspect| std::cmp::Reverse(suspect.likelihood));
+    Ok(Verdict {
+        symptom: input.symptom.clone(),
+        range: input.range.clone(),
+        candidates: input.candidates.len(),
+        bisected: input.bisected.clone(),
+        suspects,
+    })
+}
+
+/// Format `verdict` for output, with the most likely suspects.
+pub fn render(verdict: &Verdict, format: OutputFormat) -> String {
+    let mut facts = vec![tr!(
+        "broke-range",
+        good = verdict.range.from.as_str(),
+        bad = verdict.range.to.as_str(),
+        count = verdict.candidates
+    )];
+    if let Some(sha) = &verdict.bisected {
+        facts.push(tr!("broke-bisected", sha = sha.as_str()));
+    }
+    let facts: String = facts.iter().map(|fact| format!("- {}\n", fact)).collect();
+    let suspects: String = verdict
+        .suspects
+        .iter()
+        .take(SHOWN_SUSPECTS)
+        .map(|suspect| {
+            let entry = tr!(
+                "broke-suspect",
+                sha = suspect.short_id.as_str(),
+                summary = suspect.summary.as_str(),
+                likelihood = suspect.likelihood,
+                reason = suspect.reason.as_str()
+            );
+            format!("- {}\n  {}\n", entry, suspect.change)
+        })
+        .collect();
+    let sections = [
+        (tr!("broke-title", symptom = verdict.symptom.as_str()), facts),
+        (tr!("broke-suspects", count = verdict.suspects.len().min(SHOWN_SUSPECTS)), suspects),
+    ];
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(verdict).expect("verdict serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,246 @@
+//! `wtf bus-factor`: code that only one person really knows.
+//!
+//! Using the authorship collected by [`ownership::collect`], files or
+//! directories where a single author wrote the overwhelming majority of the
+//! changed lines are flagged and ranked by how much code is at stake. The
+//! model then summarizes, from the commit messages, what knowledge would go
+//! with that person.
+
+use crate::analysis::RunEstimate;
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::ownership::{Contribution, History};
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::tr;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that helps engineering teams plan knowledge sharing.";
+
+const KNOWLEDGE_PROMPT: &str = "Each of the following parts of a code base was written almost entirely by one person. For each part, based on its files and the commit messages of its main author, summarize in plain language what knowledge would be lost if that person left: what the code does, the decisions and workarounds it embodies, and what a successor would need to learn first. Use one short paragraph per part, starting with the part's name.";
+
+// Groups with fewer changed lines than this are too small to worry about
+const MIN_LINES: usize = 20;
+
+// Files and commit messages listed per part in the prompt
+const MAX_LISTED: usize = 30;
+
+/// How worrying a concentration is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Risk {
+    Medium,
+    High,
+}
+
+/// A file or directory mostly written by one person.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Concentration {
+    /// The file or directory (`""` for files at the root).
+    pub path: String,
+    pub author: String,
+    pub email: String,
+    /// The author's share of the changed lines, between 0 and 1.
+    pub share: f64,
+    /// Changed lines in total, by everyone.
+    pub lines: usize,
+    /// The author's commits touching this part.
+    pub commits: usize,
+    pub risk: Risk,
+}
+
+/// The smallest number of authors who together wrote more than half of the
+/// changed lines (0 when nothing changed).
+pub fn bus_factor(authors: &BTreeMap<String, Contribution>) -> usize {
+    let total: usize = authors.values().map(|c| c.lines).sum();
+    let mut lines: Vec<usize> = authors.values().map(|c| c.lines).collect();
+    lines.sort_unstable_by(|a, b| b.cmp(a));
+    let mut covered = 0;
+    for (i, l) in lines.iter().enumerate() {
+        covered += l;
+        if covered * 2 > total {
+            return i + 1;
+        }
+    }
+    0
+}
+
+/// Parts (as grouped by `group_of`) where one author wrote at least
+/// `threshold` of the changed lines, most lines at stake first.
+///
+/// A share of 90% or more is high risk, anything else above the threshold
+/// medium risk.
+pub fn concentrations(
+    history: &History,
+    group_of: impl Fn(&str) -> Option<String>,
+    threshold: f64,
+) -> Vec<Concentration> {
+    let mut found: Vec<Concentration> = history
+        .contributions_by(group_of)
+        .into_iter()
+        .filter_map(|(path, authors)| {
+            let lines: usize = authors.values().map(|c| c.lines).sum();
+            let (email, top) = authors.iter().max_by(|a, b| a.1.lines.cmp(&b.1.lines).then(b.0.cmp(a.0)))?;
+            let share = top.lines as f64 / lines.max(1) as f64;
+            if lines < MIN_LINES || share < threshold {
+                return None;
+            }
+            Some(Concentration {
+                path,
+                author: history.author_name(email),
+                email: email.clone(),
+                share,
+                lines,
+                commits: top.commits,
+                risk: if share >= 0.9 { Risk::High } else { Risk::Medium },
+            })
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        let at_stake = |c: &Concentration| c.lines as f64 * c.share;
+        at_stake(b).total_cmp(&at_stake(a)).then(a.path.cmp(&b.path))
+    });
+    found
+}
+
+/// The result of a bus-factor analysis.
+#[derive(Debug, Clone, Serialize)]
+pub struct BusFactorReport {
+    /// Bus factor of the whole repository over the analyzed commits.
+    pub repository_bus_factor: usize,
+    pub concentrations: Vec<Concentration>,
+    /// What would be lost, per part; absent when no model was asked.
+    pub knowledge: Option<String>,
+}
+
+/// Analyze `history`, grouping files with `group_of`.
+pub fn analyze(history: &History, group_of: impl Fn(&str) -> Option<String>, threshold: f64) -> BusFactorReport {
+    let whole = history.contributions_by(|_| Some(String::new()));
+    BusFactorReport {
+        repository_bus_factor: whole.values().next().map_or(0, bus_factor),
+        concentrations: concentrations(history, group_of, threshold),
+        knowledge: None,
+    }
+}
+
+// The question for the model: each part with its files and its main
+// author's commit messages
+fn question(history: &History, concentrations: &[Concentration], group_of: impl Fn(&str) -> Option<String>) -> String {
+    let in_part = |path: &str, part: &str| group_of(path).as_deref() == Some(part);
+    let mut content = String::new();
+    for part in concentrations {
+        let name = if part.path.is_empty() { "(repository root)" } else { part.path.as_str() };
+        content.push_str(&format!(
+            "Part: {}\nMain author: {} ({:.0}% of {} changed lines)\nFiles:",
+            name,
+            part.author,
+            part.share * 100.0,
+            part.lines
+        ));
+        let mut files: Vec<&str> = history
+            .commits
+            .iter()
+            .flat_map(|c| c.files.iter().map(|(path, _)| path.as_str()))
+            .filter(|path| in_part(path, &part.path))
+            .collect();
+        files.sort_unstable();
+        files.dedup();
+        for file in files.iter().take(MAX_LISTED) {
+            content.push_str(&format!(" {}", file));
+        }
+        content.push_str("\nCommit messages:\n");
+        let messages = history
+            .commits
+            .iter()
+            .filter(|c| c.author == part.email && c.files.iter().any(|(path, _)| in_part(path, &part.path)))
+            .take(MAX_LISTED);
+        for commit in messages {
+            content.push_str(&format!("- {}\n", commit.summary));
+        }
+        content.push('\n');
+    }
+    format!("{}\n\n{}", KNOWLEDGE_PROMPT, content)
+}
+
+/// Estimate the request [`summarize_knowledge`] will send.
+pub fn estimate(
+    history: &History,
+    concentrations: &[Concentration],
+    group_of: impl Fn(&str) -> Option<String>,
+) -> RunEstimate {
+    let question = question(history, concentrations, group_of);
+    RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&question) }
+}
+
+/// Ask the model what knowledge each of `concentrations` holds, with files
+/// grouped by the same `group_of` they were found with.
+pub async fn summarize_knowledge(
+    history: &History,
+    concentrations: &[Concentration],
+    group_of: impl Fn(&str) -> Option<String>,
+    provider: &dyn LlmProvider,
+) -> Result<String> {
+    let mut conversation = Conversation::new(SYSTEM_PROMPT);
+    conversation.ask(provider, question(history, concentrations, group_of)).await
+}
+
+/// Render `report` in the given format.
+pub fn render(report: &BusFactorReport, format: OutputFormat) -> String {
+    let title = tr!("bus-factor-title");
+    let overall = tr!("bus-factor-overall", count = report.repository_bus_factor);
+    let line = |c: &Concentration| {
+        tr!(
+            "bus-factor-entry",
+            path = if c.path.is_empty() { "/" } else { c.path.as_str() },
+            author = c.author.as_str(),
+            share = format!("{:.0}", c.share * 100.0),
+            lines = c.lines,
+            commits = c.commits,
+            risk = tr!(match c.risk {
+                Risk::High => "bus-factor-high",
+                Risk::Medium => "bus-factor-medium",
+            })
+        )
+    };
+    let mut findings = overall;
+    findings.push_str("\n\n");
+    if report.concentrations.is_empty() {
+        findings.push_str(&tr!("bus-factor-none"));
+        findings.push('\n');
+    }
+    for concentration in &report.concentrations {
+        findings.push_str(&format!("- {}\n", line(concentration)));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("report serializes to JSON") + "\n",
+        OutputFormat::Markdown => {
+            let mut out = format!("## {}\n\n{}", title, findings);
+            if let Some(knowledge) = &report.knowledge {
+                out.push_str(&format!("\n## {}\n\n{}\n", tr!("bus-factor-knowledge"), knowledge.trim()));
+            }
+            out
+        }
+        OutputFormat::Terminal => {
+            let mut out = format!("\n=== {} ===\n\n{}", title.to_uppercase(), findings);
+            if let Some(knowledge) = &report.knowledge {
+                out.push_str(&format!(
+                    "\n=== {} ===\n\n{}\n",
+                    tr!("bus-factor-knowledge").to_uppercase(),
+                    knowledge.trim()
+                ));
+            }
+            out
+        }
+        OutputFormat::Plain => {
+            let mut sections = vec![(title, findings)];
+            if let Some(knowledge) = &report.knowledge {
+                sections.push((tr!("bus-factor-knowledge"), knowledge.clone()));
+            }
+            crate::render::plain_sections(&sections)
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! A local cache of model replies.
+//!
+//! Running wtf again on the same commits would send the same prompts again
+//! and pay for them twice. [`Cached`] keeps every reply in wtf's cache
+//! directory, keyed by a hash of the provider, the model and everything
+//! sent. The prompts carry the SHA, metadata and patch of every commit they
+//! are about, so a reply is reused exactly as long as the commits it
+//! explains are unchanged. Each reply is a plain text file,
+//! `responses/<hash>.txt`; deleting any of them is always safe.
+
+use crate::error::Result;
+use crate::provider::sigv4::{hex, sha256};
+use crate::provider::{BoxFuture, LlmProvider, Message, Price};
+use crate::structured::Schema;
+use crate::{progress, tr};
+use std::path::{Path, PathBuf};
+
+const RESPONSES_DIR: &str = "responses";
+
+/// A provider whose replies are kept on disk and reused for identical
+/// requests.
+pub struct Cached {
+    inner: Box<dyn LlmProvider>,
+    dir: PathBuf,
+    // The provider and model, so different ones never share replies
+    namespace: String,
+}
+
+impl Cached {
+    /// Cache the replies of `inner`, the provider registered as `provider`,
+    /// in `cache_dir`.
+    pub fn new(inner: Box<dyn LlmProvider>, cache_dir: &Path, provider: &str) -> Self {
+        let namespace = format!("{}\n{}", provider, inner.model().unwrap_or_default());
+        Cached { inner, dir: cache_dir.join(RESPONSES_DIR), namespace }
+    }
+
+    /// The key `messages` (and `schema`, for structured requests) are
+    /// cached under.
+    pub fn key(&self, messages: &[Message], schema: Option<&Schema>) -> String {
+        // Serializing strings cannot fail
+        let mut request = serde_json::to_string(messages).expect("messages serialize to JSON");
+        if let Some(schema) = schema {
+            request.push_str(&format!("\n{}\n{}", schema.name, schema.schema));
+        }
+        hex(&sha256(format!("{}\n{}", self.namespace, request).as_bytes()))
+    }
+
+    fn lookup(&self, key: &str) -> Option<String> {
+        let reply = std::fs::read_to_string(self.dir.join(format!("{}.txt", key))).ok()?;
+        progress!("{}", tr!("progress-cached"));
+        Some(reply)
+    }
+
+    // A reply that can't be stored only costs the next run a request, so
+    // errors are ignored. Writing to a temporary file first keeps a reply
+    // from being read half-written.
+    fn store(&self, key: &str, reply: &str) {
+        let path = self.dir.join(format!("{}.txt", key));
+        let temporary = self.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        let _ = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&temporary, reply))
+            .and_then(|_| std::fs::rename(&temporary, &path));
+        let _ = std::fs::remove_file(&temporary);
+    }
+
+    async fn cached<'a>(
+        &self,
+        key: String,
+        request: impl FnOnce() -> BoxFuture<'a, Result<String>>,
+    ) -> Result<String> {
+        if let Some(reply) = self.lookup(&key) {
+            return Ok(reply);
+        }
+        let reply = request().await?;
+        self.store(&key, &reply);
+        Ok(reply)
+    }
+}
+
+impl LlmProvider for Cached {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.cached(self.key(messages, None), || self.inner.chat(messages)))
+    }
+
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.cached(self.key(messages, Some(schema)), || self.inner.chat_json(messages, schema)))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let key = self.key(messages, None);
+            if let Some(reply) = self.lookup(&key) {
+                on_text(&reply);
+                return Ok(reply);
+            }
+            let reply = self.inner.stream(messages, on_text).await?;
+            self.store(&key, &reply);
+            Ok(reply)
+        })
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.inner.count_tokens(text)
+    }
+
+    fn max_context(&self) -> usize {
+        self.inner.max_context()
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn price(&self) -> Option<Price> {
+        self.inner.price()
+    }
+}
@@ -0,0 +1,315 @@
+//! `wtf changelog`: a changelog in the Keep a Changelog format.
+//!
+//! The commits since the last tag are sorted by the model into the
+//! changelog's sections, Added, Changed, Fixed and so on, each with an
+//! entry written for users; commits users would not notice are left out.
+//! The entries go under the Unreleased heading of the changelog, after the
+//! ones already there, so nothing written by hand is lost, and commits the
+//! changelog already mentions by their short SHA are not sent again.
+//! Without a changelog, a new one is started.
+
+use crate::analysis::RunEstimate;
+use crate::error::Result;
+use crate::git::{self, RevRange};
+use crate::platform;
+use crate::provider::{LlmProvider, Message};
+use crate::release;
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that keeps a project's changelog for its users.";
+
+const CHANGELOG_PROMPT: &str = "Sort each of the following commits into a section of a changelog in the Keep a Changelog format: \"added\" for new features, \"changed\" for changes in existing behavior, \"deprecated\" for features that will be removed, \"removed\" for features that were removed, \"fixed\" for bug fixes and \"security\" for fixed vulnerabilities. Use \"none\" for commits users would not notice, like refactoring, tests, CI and internal documentation. For every other commit, write the changelog entry: one sentence for the project's users, saying what changed for them rather than how, without a type prefix like feat:. Return one entry per commit, using the short SHA given.";
+
+// The start of a new changelog
+const HEADER: &str = "# Changelog\n\nAll notable changes to this project will be documented in this file.\n\nThe format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/).\n";
+
+// Commits sorted per request
+const BATCH_SIZE: usize = 25;
+
+/// A section of a changelog release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Section {
+    Added,
+    Changed,
+    Deprecated,
+    Removed,
+    Fixed,
+    Security,
+}
+
+impl Section {
+    /// In the order Keep a Changelog lists them.
+    pub const ALL: [Section; 6] =
+        [Section::Added, Section::Changed, Section::Deprecated, Section::Removed, Section::Fixed, Section::Security];
+
+    /// The section's heading, which the format keeps in English.
+    pub fn heading(self) -> &'static str {
+        match self {
+            Section::Added => "Added",
+            Section::Changed => "Changed",
+            Section::Deprecated => "Deprecated",
+            Section::Removed => "Removed",
+            Section::Fixed => "Fixed",
+            Section::Security => "Security",
+        }
+    }
+}
+
+/// A commit that may need an entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub id: Oid,
+    pub short_id: String,
+    /// Its metadata and changed paths, for the prompt.
+    pub content: String,
+}
+
+/// The commits since the last tag that the changelog does not mention yet.
+#[derive(Debug, Clone)]
+pub struct ChangelogInput {
+    /// The last tag, if there is one.
+    pub since: Option<String>,
+    /// Newest first, without merge commits.
+    pub commits: Vec<Candidate>,
+}
+
+/// Gather the commits since the last tag in `repo`, leaving out merges and
+/// those whose short SHA the `existing` changelog already mentions.
+pub fn prepare(repo: &Repository, existing: &str) -> Result<ChangelogInput> {
+    let since = release::latest_tag(repo)?;
+    let ids = match &since {
+        Some(tag) => git::range_commits(
+            repo,
+            &RevRange { from: format!("refs/tags/{}", tag), to: "HEAD".to_string(), symmetric: false },
+        )?,
+        None => git::select_commits(repo, usize::MAX)?.0.iter().map(|commit| commit.id()).collect(),
+    };
+    let mut commits = Vec::new();
+    for id in ids {
+        let commit = repo.find_commit(id)?;
+        let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+        if commit.parent_count() > 1 || existing.contains(&short_id) {
+            continue;
+        }
+        let paths = git::changed_paths(repo, &commit)?;
+        let content =
+            format!("Short SHA: {}\n{}\nFiles: {}\n", short_id, git::get_commit_details(&commit), paths.join(", "));
+        commits.push(Candidate { id, short_id, content });
+    }
+    progress!("{}", tr!("progress-changelog", count = commits.len()));
+    Ok(ChangelogInput { since, commits })
+}
+
+impl ChangelogInput {
+    fn question(batch: &[Candidate]) -> String {
+        let mut out = CHANGELOG_PROMPT.to_string();
+        for candidate in batch {
+            out.push_str("\n\n---\n\n");
+            out.push_str(&candidate.content);
+        }
+        out
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate {
+            requests: self.commits.len().div_ceil(BATCH_SIZE),
+            prompt_tokens: self
+                .commits
+                .chunks(BATCH_SIZE)
+                .map(|batch| tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&Self::question(batch)))
+                .sum(),
+        }
+    }
+}
+
+/// One changelog entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    pub section: Section,
+    pub text: String,
+    pub short_id: String,
+}
+
+impl Entry {
+    /// The entry as a list item, ending with its commit.
+    pub fn line(&self) -> String {
+        format!("- {} ({})", self.text.trim_end_matches('.'), self.short_id)
+    }
+}
+
+/// The new entries, in the order of their sections.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Changelog {
+    pub since: Option<String>,
+    /// How many commits were sorted.
+    pub commits: usize,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    commits: Vec<Sorted>,
+}
+
+#[derive(Deserialize)]
+struct Sorted {
+    sha: String,
+    section: String,
+    entry: String,
+}
+
+/// Ask the model to sort the commits into sections, a batch at a time.
+pub async fn explain(input: &ChangelogInput, provider: &dyn LlmProvider) -> Result<Changelog> {
+    let mut sections: Vec<serde_json::Value> = Section::ALL.iter().map(|section| json!(section)).collect();
+    sections.push(json!("none"));
+    let schema = Schema::new(
+        "changelog_entries",
+        json!({
+            "type": "object",
+            "properties": {
+                "commits": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "sha": { "type": "string" },
+                            "section": { "type": "string", "enum": sections },
+                            "entry": { "type": "string" },
+                        },
+                        "required": ["sha", "section", "entry"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["commits"],
+            "additionalProperties": false,
+        }),
+    );
+    let mut entries = Vec::new();
+    for batch in input.commits.chunks(BATCH_SIZE) {
+        let messages = [Message::system(SYSTEM_PROMPT), Message::user(ChangelogInput::question(batch))];
+        let reply: Reply = structured::request(provider, &messages, &schema).await?;
+        for candidate in batch {
+            let full_id = candidate.id.to_string();
+            let sorted = reply.commits.iter().find(|sorted| sorted.sha.len() >= 7 && full_id.starts_with(&sorted.sha));
+            let Some(sorted) = sorted else { continue };
+            let Ok(section) = serde_json::from_value::<Section>(json!(sorted.section)) else { continue };
+            let text = sorted.entry.trim().to_string();
+            if !text.is_empty() {
+                entries.push(Entry { section, text, short_id: candidate.short_id.clone() });
+            }
+        }
+    }
+    // Stable, so entries in a section stay newest first
+    entries.sort_by_key(|entry| entry.section);
+    Ok(Changelog { since: input.since.clone(), commits: input.commits.len(), entries })
+}
+
+// The entries of each section that has any
+fn by_section(changelog: &Changelog) -> Vec<(Section, Vec<String>)> {
+    Section::ALL
+        .iter()
+        .map(|&section| {
+            let lines = changelog.entries.iter().filter(|entry| entry.section == section).map(Entry::line).collect();
+            (section, lines)
+        })
+        .filter(|(_, lines): &(Section, Vec<String>)| !lines.is_empty())
+        .collect()
+}
+
+// Whether `line` is the heading of a release, like `## [1.2.0] - 2024-05-01`
+fn is_release(line: &str) -> bool {
+    line.starts_with("## ")
+}
+
+// Where the lines of a block starting at `start` and ending before `end`
+// end, leaving out trailing blank lines
+fn content_end(lines: &[String], start: usize, end: usize) -> usize {
+    let mut at = end;
+    while at > start && lines[at - 1].trim().is_empty() {
+        at -= 1;
+    }
+    at
+}
+
+/// `existing`, the text of a changelog, with the entries of `changelog`
+/// added to its Unreleased release. Each entry goes at the end of its
+/// section, which is added if the release has none; the release is added
+/// above the latest one if there is none, and a new changelog is started
+/// when `existing` is empty.
+pub fn update(existing: &str, changelog: &Changelog) -> String {
+    let sections = by_section(changelog);
+    let mut lines: Vec<String> = platform::normalize_newlines(existing).lines().map(str::to_string).collect();
+    if lines.iter().all(|line| line.trim().is_empty()) {
+        lines = HEADER.lines().map(str::to_string).collect();
+    }
+    let unreleased = lines.iter().position(|line| is_release(line) && line.to_lowercase().contains("unreleased"));
+    let start = match unreleased {
+        Some(start) => start,
+        None => {
+            let at = lines.iter().position(|line| is_release(line)).unwrap_or(lines.len());
+            let at = content_end(&lines, 0, at);
+            lines.splice(at..at, [String::new(), "## [Unreleased]".to_string()]);
+            at + 1
+        }
+    };
+
+    for (section, entries) in sections {
+        let end = lines[start + 1..].iter().position(|line| is_release(line)).map_or(lines.len(), |at| start + 1 + at);
+        let heading = format!("### {}", section.heading());
+        let found = (start + 1..end).find(|&at| lines[at].trim().eq_ignore_ascii_case(&heading));
+        match found {
+            Some(found) => {
+                let next = (found + 1..end).find(|&next| lines[next].starts_with("### ")).unwrap_or(end);
+                let at = content_end(&lines, found + 1, next);
+                // An empty section gets a blank line below its heading
+                let mut block = Vec::new();
+                if at == found + 1 {
+                    block.push(String::new());
+                }
+                block.extend(entries);
+                lines.splice(at..at, block);
+            }
+            None => {
+                let at = content_end(&lines, start + 1, end);
+                let mut block = vec![String::new(), heading, String::new()];
+                block.extend(entries);
+                lines.splice(at..at, block);
+            }
+        }
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// Format the new entries of `changelog` for output: markdown is the
+/// Unreleased release as it would go into the changelog.
+pub fn render(changelog: &Changelog, format: OutputFormat) -> String {
+    let sections: Vec<(String, String)> = by_section(changelog)
+        .into_iter()
+        .map(|(section, lines)| (section.heading().to_string(), lines.join("\n") + "\n"))
+        .collect();
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(changelog).expect("changelog serializes to JSON") + "\n",
+        OutputFormat::Markdown => {
+            let body: Vec<String> = sections.iter().map(|(title, body)| format!("### {}\n\n{}", title, body)).collect();
+            format!("## [Unreleased]\n\n{}", body.join("\n"))
+        }
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
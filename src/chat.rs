@@ -0,0 +1,335 @@
+//! `wtf chat`: a conversation about a repository, one question after another.
+//!
+//! The history is read once, when the chat starts, as `wtf ask` reads it for
+//! a single question, and the README, the layout of the tree and the latest
+//! commit subjects open the conversation. Each question is searched in that
+//! history, and the commits and files found go to the model with it, except
+//! those it was shown before, so follow-ups build on the earlier turns. When
+//! the conversation would outgrow the model's context, the oldest turns are
+//! dropped.
+//!
+//! Lines starting with `/` are commands handled without the model. `/diff`
+//! and `/show` print a commit, by default the one the last answer was about,
+//! and what they print goes to the model with the next question. A few plain
+//! requests, like "show me the diff for that commit", are read as commands
+//! too. Every answer is recorded in a [`Session`].
+
+use crate::analysis;
+use crate::ask::History;
+use crate::chunk;
+use crate::citations::{self, CITATION_INSTRUCTIONS};
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::git::{self, RepositoryExt};
+use crate::guard::{self, GuardMode, KnownRefs};
+use crate::onboard;
+use crate::provider::LlmProvider;
+use crate::session::Session;
+use crate::tr;
+use git2::{Commit, Repository};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that answers questions about a software project from its Git history, in a conversation with one of its developers.";
+
+const CONTEXT_PROMPT: &str = "Below are the project's README, the layout of its tree with the number of files in each directory and its latest commit subjects. Each question brings the commits and files found for it.";
+
+const TURN_PROMPT: &str = "Answer the question below, taking the conversation so far into account. The commits and files after it, if any, were found by searching the project's history and tree for the question's words, most relevant first, and some may be unrelated; those shown earlier in the conversation are not repeated. Answer from the material in the conversation alone: say what happened and, where the commit messages or changes tell, why. Name files by their path. If the material does not answer the question, say so plainly. Keep it under 200 words.";
+
+// The README is cut to this many tokens
+const README_TOKENS: usize = 1_500;
+
+// Commit subjects in the opening
+const RECENT_COMMITS: usize = 20;
+
+// What `/diff` and `/show` pass on is cut to this many tokens
+const LOOKED_AT_TOKENS: usize = 2_000;
+
+// Room kept for the answer when trimming the conversation
+const ANSWER_TOKENS: usize = 1_000;
+
+// Words a plain request for a commit may have besides the commit itself
+const FILLER_WORDS: &[&str] = &[
+    "me", "the", "that", "this", "last", "commit", "it", "its", "for", "of", "in", "again", "please", "one", "changes",
+];
+
+/// A line typed into the chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// A question for the model.
+    Ask(String),
+    /// The diff of a commit; `None` for the one the last answer was about.
+    Diff(Option<String>),
+    /// The message and changed files of a commit; `None` as for `Diff`.
+    Show(Option<String>),
+    /// The questions asked so far.
+    History,
+    /// Forget the conversation so far.
+    Clear,
+    Help,
+    Quit,
+    /// A `/` command that does not exist.
+    Unknown(String),
+}
+
+impl Command {
+    /// Read `line`, or `None` when it is blank.
+    pub fn parse(line: &str) -> Option<Command> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        let Some(rest) = line.strip_prefix('/') else {
+            return Some(plain_request(line).unwrap_or_else(|| Command::Ask(line.to_string())));
+        };
+        let mut words = rest.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let rev = words.next().map(String::from);
+        Some(match name {
+            "diff" => Command::Diff(rev),
+            "show" => Command::Show(rev),
+            "history" => Command::History,
+            "clear" => Command::Clear,
+            "help" => Command::Help,
+            "quit" | "exit" => Command::Quit,
+            _ => Command::Unknown(name.to_string()),
+        })
+    }
+}
+
+// "show me the diff for that commit" or "show abc1234": `/diff` and `/show`
+// in plain words. Anything more is a question.
+fn plain_request(line: &str) -> Option<Command> {
+    let lower = line.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '.' | '?' | '!' | '[' | ']' | '`'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    if !matches!(*words.first()?, "show" | "print" | "display" | "give" | "diff") {
+        return None;
+    }
+    let (mut diff, mut rev) = (words[0] == "diff", None);
+    for word in &words[1..] {
+        if matches!(*word, "diff" | "patch") {
+            diff = true;
+        } else if rev.is_none() && guard::looks_like_sha(word) {
+            rev = Some(word.to_string());
+        } else if !FILLER_WORDS.contains(word) {
+            return None;
+        }
+    }
+    match diff {
+        true => Some(Command::Diff(rev)),
+        false if rev.is_some() || words.contains(&"commit") => Some(Command::Show(rev)),
+        false => None,
+    }
+}
+
+/// A chat in progress.
+pub struct Chat<'r> {
+    repo: &'r Repository,
+    repo_name: String,
+    history: History<'r>,
+    guard: GuardMode,
+    opening: String,
+    conversation: Conversation,
+    // Short ids of the commits and paths of the files the conversation holds
+    shown: HashSet<String>,
+    // The commits and paths answers may refer to
+    known: KnownRefs,
+    // What `/diff` and `/show` printed, for the next question
+    looked_at: Vec<String>,
+    // The commits the last answer was about, for "that commit"
+    last: Vec<String>,
+    questions: Vec<String>,
+    session: Option<(Session, PathBuf)>,
+}
+
+impl<'r> Chat<'r> {
+    /// Start a chat about `repo`, searching its last `max_commits` non-merge
+    /// commits and handling references outside of what the model was shown
+    /// according to `guard`.
+    pub fn start(repo: &'r Repository, max_commits: usize, guard: GuardMode) -> Result<Chat<'r>> {
+        let history = History::load(repo, max_commits)?;
+        let paths = git::head_tree_paths(repo)?;
+        let mut opening = format!("{}\n\n{}", SYSTEM_PROMPT, CONTEXT_PROMPT);
+        if let Some(text) = onboard::readme_path(&paths).and_then(|path| repo.find_file(path).ok()) {
+            let (start, _) = chunk::truncate_to_tokens(&text, README_TOKENS);
+            opening.push_str(&format!("\n\n---\n\nREADME:\n\n{}", start));
+        }
+        opening.push_str(&format!("\n\n---\n\nLayout:\n\n{}", onboard::layout(&paths)));
+        let (commits, _) = git::select_commits(repo, RECENT_COMMITS)?;
+        let subjects: Vec<String> = commits
+            .iter()
+            .map(|commit| format!("- {}", git::commit_message(commit).lines().next().unwrap_or_default()))
+            .collect();
+        opening.push_str(&format!("\n\n---\n\nLatest commits:\n\n{}", subjects.join("\n")));
+
+        let mut known = KnownRefs::new();
+        for path in paths {
+            known.add_path(path);
+        }
+        Ok(Chat {
+            repo,
+            repo_name: analysis::repo_name(repo),
+            history,
+            guard,
+            conversation: Conversation::new(opening.clone()),
+            opening,
+            shown: HashSet::new(),
+            known,
+            looked_at: Vec::new(),
+            last: Vec::new(),
+            questions: Vec::new(),
+            session: None,
+        })
+    }
+
+    /// Record every answer to `session`, saving it to `data_dir` as it grows.
+    pub fn record_to(&mut self, session: Session, data_dir: PathBuf) {
+        self.session = Some((session, data_dir));
+    }
+
+    /// The transcript recorded so far, if recording.
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref().map(|(session, _)| session)
+    }
+
+    pub fn repo_name(&self) -> &str {
+        &self.repo_name
+    }
+
+    /// The conversation with the model so far.
+    pub fn conversation(&self) -> &Conversation {
+        &self.conversation
+    }
+
+    /// Carry out `command`, returning what to print, or `None` when the chat
+    /// is over.
+    pub async fn handle(&mut self, command: Command, provider: &dyn LlmProvider) -> Result<Option<String>> {
+        let text = match command {
+            Command::Ask(question) => self.ask(&question, provider).await?,
+            Command::Diff(rev) => self.diff(rev.as_deref())?,
+            Command::Show(rev) => self.show(rev.as_deref())?,
+            Command::History if self.questions.is_empty() => tr!("chat-no-history"),
+            Command::History => {
+                let listed: Vec<String> =
+                    self.questions.iter().enumerate().map(|(i, question)| format!("{}. {}", i + 1, question)).collect();
+                listed.join("\n")
+            }
+            Command::Clear => {
+                self.conversation = Conversation::new(self.opening.clone());
+                self.shown.clear();
+                self.looked_at.clear();
+                self.last.clear();
+                self.questions.clear();
+                tr!("chat-cleared")
+            }
+            Command::Help => tr!("chat-help"),
+            Command::Unknown(name) => tr!("chat-unknown-command", name = name.as_str()),
+            Command::Quit => return Ok(None),
+        };
+        Ok(Some(text))
+    }
+
+    // The question with what was found for it and not shown before
+    fn turn(&self, question: &str, input: &crate::ask::AskInput) -> String {
+        let mut prompt = format!("{} {}\n\nQuestion: {}", TURN_PROMPT, CITATION_INSTRUCTIONS, question);
+        for looked_at in &self.looked_at {
+            prompt.push_str(&format!("\n\n---\n\nThe developer has since looked at this:\n\n{}", looked_at));
+        }
+        for source in input.commits.iter().filter(|source| !self.shown.contains(&source.short_id)) {
+            prompt.push_str(&format!("\n\n---\n\n{}", source.content));
+        }
+        for (path, content) in input.files.iter().zip(&input.file_content) {
+            if !self.shown.contains(path) {
+                prompt.push_str(&format!("\n\n---\n\n{}", content));
+            }
+        }
+        prompt
+    }
+
+    async fn ask(&mut self, question: &str, provider: &dyn LlmProvider) -> Result<String> {
+        let input = self.history.search(question, &[])?;
+        let mut prompt = self.turn(question, &input);
+        let room = provider.max_context().saturating_sub(provider.count_tokens(&prompt) + ANSWER_TOKENS);
+        if self.conversation.trim(provider, room) > 0 {
+            // What the dropped turns showed may have to be shown again
+            self.shown.clear();
+            prompt = self.turn(question, &input);
+        }
+        let reply = self.conversation.ask(provider, prompt).await?;
+
+        self.known.merge(input.known);
+        let (answer, _) = guard::check(reply.trim(), &self.known, self.guard);
+        self.shown.extend(input.commits.iter().map(|source| source.short_id.clone()));
+        self.shown.extend(input.files);
+        self.looked_at.clear();
+        let cited: Vec<String> = citations::cited_shas(&answer).into_iter().map(String::from).collect();
+        if !cited.is_empty() {
+            self.last = cited;
+        } else if let Some(source) = input.commits.first() {
+            self.last = vec![source.short_id.clone()];
+        }
+        self.questions.push(question.to_string());
+        if let Some((session, data_dir)) = &mut self.session {
+            session.record(question, answer.as_str(), &[]);
+            // Losing the transcript is no reason to end the chat
+            let _ = session.save(data_dir);
+        }
+        Ok(answer)
+    }
+
+    // The commit `rev` names, or the one the last answer was about
+    fn commit(&mut self, rev: Option<&str>) -> Result<Option<Commit<'r>>> {
+        let Some(rev) = rev.or(self.last.first().map(String::as_str)) else {
+            return Ok(None);
+        };
+        let commit = git::resolve_commit(self.repo, rev)?;
+        self.known.add_sha(commit.id().to_string());
+        self.last = vec![short_id(&commit)?];
+        Ok(Some(commit))
+    }
+
+    fn diff(&mut self, rev: Option<&str>) -> Result<String> {
+        let Some(commit) = self.commit(rev)? else {
+            return Ok(tr!("chat-no-commit"));
+        };
+        let patch = git::diff_patch(&git::commit_diff(self.repo, &commit, &[])?)?;
+        if patch.is_empty() {
+            return Ok(tr!("tui-no-diff"));
+        }
+        self.known.add_patch_paths(&patch);
+        let (start, _) = chunk::truncate_to_tokens(&patch, LOOKED_AT_TOKENS);
+        self.looked_at.push(format!("The diff of commit {}:\n\n{}", short_id(&commit)?, start));
+        Ok(patch.trim_end().to_string())
+    }
+
+    fn show(&mut self, rev: Option<&str>) -> Result<String> {
+        let Some(commit) = self.commit(rev)? else {
+            return Ok(tr!("chat-no-commit"));
+        };
+        let author = git::decode_text(commit.author().name_bytes(), commit.message_encoding());
+        let paths = git::diff_paths(&git::commit_diff(self.repo, &commit, &[])?);
+        let mut text = format!(
+            "{} {}\n{} {}, {}\n\n{}",
+            tr!("tui-commit"),
+            commit.id(),
+            tr!("tui-author"),
+            author,
+            git::format_date(commit.time().seconds()),
+            git::commit_message(&commit).trim_end(),
+        );
+        if !paths.is_empty() {
+            let listed: Vec<String> = paths.iter().map(|path| format!("- {}", path)).collect();
+            text.push_str(&format!("\n\n{}", listed.join("\n")));
+        }
+        let (start, _) = chunk::truncate_to_tokens(&text, LOOKED_AT_TOKENS);
+        self.looked_at.push(start);
+        Ok(text)
+    }
+}
+
+fn short_id(commit: &Commit) -> Result<String> {
+    Ok(commit.as_object().short_id()?.as_str().unwrap_or_default().to_string())
+}
@@ -0,0 +1,192 @@
+//! Splitting and truncating patches so they fit into a token budget.
+//!
+//! All functions here cut only at character boundaries, and chunked patches
+//! repeat the file and hunk headers they belong to so each chunk can be
+//! understood on its own.
+
+use crate::tokens;
+
+/// Appended to text that had to be cut short.
+pub const TRUNCATION_MARKER: &str = "\n[... truncated ...]\n";
+
+/// Cut `text` down to at most `max_tokens` tokens.
+///
+/// Prefers cutting at a line break and appends [`TRUNCATION_MARKER`] when
+/// there is room for it. Returns the text and whether anything was removed.
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> (String, bool) {
+    if tokens::estimate(text) <= max_tokens {
+        return (text.to_string(), false);
+    }
+
+    let marker_tokens = tokens::estimate(TRUNCATION_MARKER);
+    let (budget, marker) = if max_tokens > marker_tokens * 2 {
+        (max_tokens - marker_tokens, TRUNCATION_MARKER)
+    } else {
+        (max_tokens, "")
+    };
+
+    let cut = tokens::prefix(text, budget);
+    // Cut at the last complete line if that keeps most of the text
+    let cut = match cut.rfind('\n') {
+        Some(pos) if pos >= cut.len() / 2 => &cut[..pos + 1],
+        _ => cut,
+    };
+
+    let mut out = cut.trim_end_matches('\n').to_string();
+    out.push_str(marker);
+    (out, true)
+}
+
+/// Like [`truncate_to_tokens`], for tokens as `count` counts them, such as
+/// a provider's [`count_tokens`](crate::provider::LlmProvider::count_tokens).
+pub fn truncate_to_count(text: &str, max_tokens: usize, count: impl Fn(&str) -> usize) -> (String, bool) {
+    let counted = count(text);
+    if counted <= max_tokens {
+        return (text.to_string(), false);
+    }
+    // Scale the budget to the estimate `truncate_to_tokens` cuts by
+    let budget = (max_tokens as u128 * tokens::estimate(text) as u128 / counted as u128) as usize;
+    truncate_to_tokens(text, budget)
+}
+
+/// One file section of a unified diff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiff<'a> {
+    /// Everything before the first hunk (`diff --git`, index, `---`/`+++` lines).
+    pub header: &'a str,
+    /// Each hunk, starting with its `@@` line.
+    pub hunks: Vec<&'a str>,
+}
+
+impl FileDiff<'_> {
+    /// The file's new path, from its `diff --git a/old b/new` line.
+    pub fn path(&self) -> Option<&str> {
+        let first = self.header.lines().next()?.strip_prefix("diff --git ")?;
+        let (_, new) = first.rsplit_once(" b/")?;
+        Some(new)
+    }
+}
+
+/// Split a unified diff into per-file sections.
+pub fn split_files(patch: &str) -> Vec<FileDiff<'_>> {
+    let mut files = Vec::new();
+    for section in split_keeping(patch, |line| line.starts_with("diff --git ")) {
+        let hunk_start = line_starts(section)
+            .find(|&pos| section[pos..].starts_with("@@"))
+            .unwrap_or(section.len());
+        let (header, body) = section.split_at(hunk_start);
+        files.push(FileDiff {
+            header,
+            hunks: split_keeping(body, |line| line.starts_with("@@")),
+        });
+    }
+    files
+}
+
+// Byte offsets of the start of every line in `text`
+fn line_starts(text: &str) -> impl Iterator<Item = usize> + '_ {
+    std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1).filter(move |&i| i < text.len()))
+}
+
+// Split `text` before every line for which `is_start` holds
+fn split_keeping(text: &str, is_start: impl Fn(&str) -> bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for pos in line_starts(text) {
+        if pos > start && is_start(&text[pos..]) {
+            parts.push(&text[start..pos]);
+            start = pos;
+        }
+    }
+    if start < text.len() {
+        parts.push(&text[start..]);
+    }
+    parts
+}
+
+/// Split `patch` into chunks of at most `max_tokens` tokens each.
+///
+/// Whole hunks are kept together where possible. A hunk that is too large on
+/// its own is split by lines, and every piece starts with the file header and
+/// the hunk's `@@` line. Lines that cannot fit even on their own are truncated.
+pub fn chunk_patch(patch: &str, max_tokens: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for file in split_files(patch) {
+        // Whether `current` already carries this file's header
+        let mut has_header = false;
+
+        if file.hunks.is_empty() {
+            push_piece(&mut chunks, &mut current, file.header, max_tokens);
+            continue;
+        }
+
+        for hunk in &file.hunks {
+            let with_header = format!("{}{}", file.header, hunk);
+            let piece = if has_header { *hunk } else { with_header.as_str() };
+
+            if tokens::estimate(&current) + tokens::estimate(piece) <= max_tokens {
+                current.push_str(piece);
+                has_header = true;
+                continue;
+            }
+
+            flush(&mut chunks, &mut current);
+            if tokens::estimate(&with_header) <= max_tokens {
+                current.push_str(&with_header);
+                has_header = true;
+                continue;
+            }
+
+            // The hunk is too large on its own: split it line by line
+            split_hunk(&mut chunks, &mut current, file.header, hunk, max_tokens);
+            has_header = true;
+        }
+    }
+
+    flush(&mut chunks, &mut current);
+    chunks
+}
+
+// Split a single oversized hunk across several chunks, leaving the last piece in `current`
+fn split_hunk(chunks: &mut Vec<String>, current: &mut String, header: &str, hunk: &str, max_tokens: usize) {
+    let (hunk_header, body) = match hunk.find('\n') {
+        Some(pos) => hunk.split_at(pos + 1),
+        None => (hunk, ""),
+    };
+    let prefix = format!("{}{}", header, hunk_header);
+    let (prefix, _) = truncate_to_tokens(&prefix, max_tokens);
+
+    current.push_str(&prefix);
+    let (mut has_body, mut cut) = (false, false);
+    for line in body.split_inclusive('\n') {
+        // A line cut short ends its chunk, so the next one cannot run on from it
+        if (cut || tokens::estimate(current) + tokens::estimate(line) > max_tokens) && has_body {
+            flush(chunks, current);
+            current.push_str(&prefix);
+        }
+        let room = max_tokens.saturating_sub(tokens::estimate(current));
+        cut = tokens::estimate(line) > room;
+        match cut {
+            false => current.push_str(line),
+            true => current.push_str(tokens::prefix(line, room)),
+        }
+        has_body = true;
+    }
+}
+
+// Add a standalone piece of text, starting a new chunk if needed
+fn push_piece(chunks: &mut Vec<String>, current: &mut String, piece: &str, max_tokens: usize) {
+    if tokens::estimate(current) + tokens::estimate(piece) > max_tokens {
+        flush(chunks, current);
+    }
+    let (piece, _) = truncate_to_tokens(piece, max_tokens);
+    current.push_str(&piece);
+}
+
+fn flush(chunks: &mut Vec<String>, current: &mut String) {
+    if !current.is_empty() {
+        chunks.push(std::mem::take(current));
+    }
+}
@@ -0,0 +1,253 @@
+//! Changes to build and CI configuration.
+//!
+//! Pipelines run with credentials and write access that ordinary code does
+//! not have, so their changes get a section of their own. Besides the
+//! patches for the model to summarize, [`collect`] flags changed lines that
+//! deserve a security review: new uses of secrets, token permissions,
+//! triggers that run untrusted code with secrets, unpinned third-party
+//! actions, downloaded scripts piped into a shell and self-hosted runners.
+
+use crate::chunk;
+use crate::error::Result;
+use crate::git;
+use git2::{Commit, Repository};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+// CI patches beyond this are cut off before they are sent to the model
+const CI_PATCH_TOKENS: usize = 4000;
+
+// Token scopes GitHub Actions' `permissions:` block can grant
+const PERMISSION_SCOPES: &[&str] = &[
+    "actions",
+    "attestations",
+    "checks",
+    "contents",
+    "deployments",
+    "discussions",
+    "id-token",
+    "issues",
+    "packages",
+    "pages",
+    "pull-requests",
+    "repository-projects",
+    "security-events",
+    "statuses",
+];
+
+/// Why a changed CI line deserves attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CiRisk {
+    /// A secret or credential is used.
+    Secret,
+    /// The permissions of the CI token change.
+    Permissions,
+    /// A trigger (`pull_request_target`, `workflow_run`) that can run code
+    /// from forks with access to secrets.
+    PrivilegedTrigger,
+    /// A third-party action referenced by tag or branch instead of a commit SHA.
+    UnpinnedAction,
+    /// A downloaded script is piped straight into a shell.
+    RemoteScript,
+    /// A job runs on a self-hosted runner.
+    SelfHosted,
+}
+
+/// One changed CI line that deserves attention.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CiFinding {
+    pub short_id: String,
+    pub path: String,
+    pub risk: CiRisk,
+    /// The changed line, with its `+` or `-` marker.
+    pub line: String,
+}
+
+/// The CI changes of the analyzed commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CiChanges {
+    /// Changed CI files, sorted.
+    pub files: Vec<String>,
+    pub findings: Vec<CiFinding>,
+    /// The CI part of each commit's patch, labeled with the commit.
+    pub patches: Vec<String>,
+}
+
+/// The build and CI section of a report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CiReport {
+    /// The model's plain-language summary of the changes.
+    pub summary: String,
+    pub files: Vec<String>,
+    pub findings: Vec<CiFinding>,
+}
+
+impl CiChanges {
+    /// The content sent to the model: flagged lines first, then the patches.
+    pub fn prompt_content(&self) -> String {
+        let mut content = String::new();
+        if !self.findings.is_empty() {
+            content.push_str("Flagged lines:\n");
+            for finding in &self.findings {
+                content.push_str(&format!(
+                    "- [{}] {} ({:?}): {}\n",
+                    finding.short_id, finding.path, finding.risk, finding.line
+                ));
+            }
+            content.push('\n');
+        }
+        content.push_str(&self.patches.join("\n\n---\n\n"));
+        content
+    }
+}
+
+/// Whether `path` is build or CI configuration.
+pub fn is_ci_path(path: &str) -> bool {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let yaml = path.ends_with(".yml") || path.ends_with(".yaml");
+    (path.starts_with(".github/workflows/") && yaml)
+        || path.starts_with(".github/actions/")
+        || path.starts_with(".gitlab/ci/")
+        || path.starts_with(".circleci/")
+        || path.starts_with(".buildkite/")
+        || path.starts_with(".woodpecker/")
+        || path.starts_with(".azure-pipelines/")
+        || file.starts_with("Jenkinsfile")
+        || matches!(
+            path,
+            ".gitlab-ci.yml"
+                | ".travis.yml"
+                | ".drone.yml"
+                | ".woodpecker.yml"
+                | "azure-pipelines.yml"
+                | "bitbucket-pipelines.yml"
+                | "appveyor.yml"
+                | ".appveyor.yml"
+                | "cloudbuild.yaml"
+                | "cloudbuild.yml"
+        )
+}
+
+/// The CI changes in `commits` under the pathspecs in `paths` (or anywhere,
+/// without any), or `None` if none of them touches CI files there.
+pub fn collect(repo: &Repository, commits: &[Commit<'_>], paths: &[String]) -> Result<Option<CiChanges>> {
+    let mut files = BTreeSet::new();
+    let mut findings = Vec::new();
+    let mut patches = Vec::new();
+    for commit in commits {
+        let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+        let diff = git::commit_diff(repo, commit, paths)?;
+
+        let mut text = String::new();
+        for idx in 0..diff.deltas().len() {
+            let Some(delta) = diff.get_delta(idx) else { continue };
+            let Some(path) = delta.new_file().path_bytes().or(delta.old_file().path_bytes()).map(git::escape_non_utf8) else {
+                continue;
+            };
+            if !is_ci_path(&path) {
+                continue;
+            }
+            let Some(patch) = git2::Patch::from_diff(&diff, idx)? else { continue };
+            text.push_str(&format!("--- {}\n", path));
+            for hunk in 0..patch.num_hunks() {
+                for line in 0..patch.num_lines_in_hunk(hunk)? {
+                    let line = patch.line_in_hunk(hunk, line)?;
+                    let origin = line.origin();
+                    if !matches!(origin, '+' | '-' | ' ') {
+                        continue;
+                    }
+                    let content = String::from_utf8_lossy(line.content());
+                    let content = content.trim_end();
+                    text.push_str(&format!("{}{}\n", origin, content));
+                    if origin != ' ' {
+                        for risk in risks(content, origin == '+') {
+                            findings.push(CiFinding {
+                                short_id: short_id.clone(),
+                                path: path.clone(),
+                                risk,
+                                line: format!("{} {}", origin, content.trim()),
+                            });
+                        }
+                    }
+                }
+            }
+            files.insert(path);
+        }
+        if !text.is_empty() {
+            let (text, _) = chunk::truncate_to_tokens(&text, CI_PATCH_TOKENS);
+            patches.push(format!("Commit: {}\n{}", short_id, text));
+        }
+    }
+
+    if files.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(CiChanges {
+        files: files.into_iter().collect(),
+        findings,
+        patches,
+    }))
+}
+
+/// What makes a changed CI line worth a closer look. Removed lines only
+/// matter for permissions: dropping a `permissions:` block widens them.
+pub fn risks(line: &str, added: bool) -> Vec<CiRisk> {
+    let line = line.trim();
+    let lower = line.to_lowercase();
+    let mut risks = Vec::new();
+    if is_permission_line(&lower) {
+        risks.push(CiRisk::Permissions);
+    }
+    if !added || lower.starts_with('#') || lower.starts_with("//") {
+        return risks;
+    }
+    if lower.contains("secrets.")
+        || lower.starts_with("secrets:")
+        || lower.contains("withcredentials")
+        || lower.contains("credentials(")
+    {
+        risks.push(CiRisk::Secret);
+    }
+    if lower.contains("pull_request_target") || lower.contains("workflow_run") {
+        risks.push(CiRisk::PrivilegedTrigger);
+    }
+    if is_unpinned_action(line) {
+        risks.push(CiRisk::UnpinnedAction);
+    }
+    let piped = ["| sh", "|sh", "| bash", "|bash", "| sudo", "|sudo"].iter().any(|pipe| lower.contains(pipe));
+    if piped && (lower.contains("curl") || lower.contains("wget")) {
+        risks.push(CiRisk::RemoteScript);
+    }
+    if lower.contains("self-hosted") {
+        risks.push(CiRisk::SelfHosted);
+    }
+    risks
+}
+
+fn is_permission_line(lower: &str) -> bool {
+    let lower = lower.trim_start_matches("- ");
+    if lower.starts_with("permissions:") {
+        return true;
+    }
+    let Some((key, value)) = lower.split_once(':') else { return false };
+    PERMISSION_SCOPES.contains(&key.trim()) && matches!(value.trim(), "read" | "write" | "none")
+}
+
+// `uses: owner/repo@v4` rather than `uses: owner/repo@<40 hex digits>`;
+// local actions (`./...`) and Docker images are not counted
+fn is_unpinned_action(line: &str) -> bool {
+    let line = line.trim_start_matches("- ");
+    let Some(reference) = line.strip_prefix("uses:") else { return false };
+    let reference = reference.trim().trim_matches(['"', '\'']);
+    if reference.starts_with("./") || reference.starts_with("docker://") {
+        return false;
+    }
+    match reference.split_once('@') {
+        Some((_, version)) => {
+            let version = version.split_whitespace().next().unwrap_or_default();
+            !(version.len() == 40 && version.chars().all(|c| c.is_ascii_hexdigit()))
+        }
+        None => true,
+    }
+}
@@ -0,0 +1,144 @@
+//! Commit citations in the commit and edits sections.
+//!
+//! The model is asked to end every claim with the short SHA it is based on,
+//! written as `[abc1234]`. This module finds those citations, turns them into
+//! links and, in strict mode, drops claims that have none.
+
+use crate::guard::looks_like_sha;
+
+/// Appended to the prompts of sections that must cite their sources.
+pub const CITATION_INSTRUCTIONS: &str = "Every claim must cite the commit it is based on by ending with the commit's 7-character short SHA in square brackets, for example [1a2b3c4]. Only cite commits from the data above.";
+
+/// All SHAs cited as `[sha]` (or `[sha, sha]`) in `text`, in order.
+pub fn cited_shas(text: &str) -> Vec<&str> {
+    let mut shas = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let after = &rest[open + 1..];
+        let Some(close) = after.find(']') else { break };
+        let inner = &after[..close];
+        let parts: Vec<&str> = inner.split(',').map(str::trim).collect();
+        if !parts.is_empty() && parts.iter().all(|p| looks_like_sha(p)) {
+            shas.extend(parts);
+        }
+        rest = &after[close + 1..];
+    }
+    shas
+}
+
+/// Whether `text` contains at least one citation.
+pub fn has_citation(text: &str) -> bool {
+    !cited_shas(text).is_empty()
+}
+
+/// Remove claims without a citation.
+///
+/// List items are kept or dropped as a whole; prose is judged sentence by
+/// sentence. Headings and blank lines are kept. Returns the filtered text
+/// and the number of claims that were dropped.
+pub fn strip_uncited(text: &str) -> (String, usize) {
+    let mut out = Vec::new();
+    let mut dropped = 0;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            out.push(line.to_string());
+        } else if is_list_item(trimmed) {
+            if has_citation(line) {
+                out.push(line.to_string());
+            } else {
+                dropped += 1;
+            }
+        } else {
+            let mut kept = Vec::new();
+            for sentence in sentences(line) {
+                if has_citation(sentence) {
+                    kept.push(sentence.trim());
+                } else {
+                    dropped += 1;
+                }
+            }
+            if !kept.is_empty() {
+                out.push(kept.join(" "));
+            }
+        }
+    }
+
+    (out.join("\n"), dropped)
+}
+
+fn is_list_item(line: &str) -> bool {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return true;
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+// Split prose into sentences. A citation written after the full stop
+// ("Did a thing. [abc1234]") still belongs to the sentence before it.
+fn sentences(line: &str) -> Vec<&str> {
+    let bytes = line.as_bytes();
+    let mut raw = Vec::new();
+    let mut start = 0;
+    for (i, c) in line.char_indices() {
+        let at_end = matches!(c, '.' | '!' | '?') && bytes.get(i + 1).is_none_or(|b| b.is_ascii_whitespace());
+        if at_end {
+            raw.push((start, i + 1));
+            start = i + 1;
+        }
+    }
+    if !line[start..].trim().is_empty() {
+        raw.push((start, line.len()));
+    }
+
+    let mut bounds: Vec<(usize, usize)> = Vec::new();
+    for (begin, end) in raw {
+        let part = line[begin..end].trim().trim_end_matches(['.', '!', '?']);
+        let only_citations = part.starts_with('[') && part.ends_with(']') && has_citation(part);
+        match bounds.last_mut() {
+            Some(last) if only_citations => last.1 = end,
+            _ => bounds.push((begin, end)),
+        }
+    }
+    bounds.into_iter().map(|(begin, end)| &line[begin..end]).collect()
+}
+
+/// Turn `[sha]` citations into Markdown links to `{base}/{sha}`.
+pub fn link_citations(text: &str, commit_url_base: &str) -> String {
+    rewrite_citations(text, |shas| {
+        shas.iter()
+            .map(|sha| format!("[{}]({}/{})", sha, commit_url_base, sha))
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+}
+
+/// Replace every `[sha]` / `[sha, sha]` citation with `rewrite(shas)`.
+///
+/// Existing Markdown links (`[text](url)`) and other brackets are left alone.
+pub fn rewrite_citations(text: &str, rewrite: impl Fn(&[&str]) -> String) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let Some(close) = after.find(']') else {
+            rest = &rest[open..];
+            break;
+        };
+        let inner = &after[..close];
+        let parts: Vec<&str> = inner.split(',').map(str::trim).collect();
+        if parts.iter().all(|p| looks_like_sha(p)) && !after[close + 1..].starts_with('(') {
+            out.push_str(&rewrite(&parts));
+        } else {
+            out.push('[');
+            out.push_str(inner);
+            out.push(']');
+        }
+        rest = &after[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
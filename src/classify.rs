@@ -0,0 +1,193 @@
+//! Commit classification for the report's dashboard (`--classify`).
+//!
+//! Every analyzed commit is sorted into one kind of change and given a
+//! risk level in a single structured request. The counts make a small
+//! dashboard next to the narrative: how much of the recent work was new
+//! features, fixes, refactoring, documentation or chores, and how much of
+//! it was risky.
+
+use crate::analysis::AnalysisInput;
+use crate::complexity::CommitScore;
+use crate::error::Result;
+use crate::provider::{LlmProvider, Message};
+use crate::structured::{self, Schema};
+use crate::tokens;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that triages Git commits for an engineering dashboard.";
+
+const CLASSIFY_PROMPT: &str = "Classify each of the following commits. Give its kind: \"feature\" (new behavior for users), \"fix\" (corrects a bug), \"refactor\" (restructures code without changing behavior), \"docs\" (documentation only) or \"chore\" (builds, dependencies, formatting and other upkeep). Give its risk of breaking something: \"low\", \"medium\" or \"high\", judging by what it touches and how much it changes. Add a reason of at most 15 words. Return one entry per commit, using the short SHA given.";
+
+/// What kind of change a commit is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitKind {
+    Feature,
+    Fix,
+    Refactor,
+    Docs,
+    Chore,
+}
+
+impl CommitKind {
+    pub const ALL: [CommitKind; 5] =
+        [CommitKind::Feature, CommitKind::Fix, CommitKind::Refactor, CommitKind::Docs, CommitKind::Chore];
+}
+
+/// How likely a commit is to break something.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    pub const ALL: [RiskLevel; 3] = [RiskLevel::High, RiskLevel::Medium, RiskLevel::Low];
+}
+
+/// The model's verdict on one commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Classification {
+    pub short_id: String,
+    pub kind: CommitKind,
+    pub risk: RiskLevel,
+    pub reason: String,
+}
+
+/// The classified commits and how they are distributed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dashboard {
+    /// In the order the commits were analyzed, newest first.
+    pub commits: Vec<Classification>,
+    /// Commits per kind, including kinds without any.
+    pub kinds: BTreeMap<CommitKind, usize>,
+    /// Commits per risk level, including levels without any.
+    pub risks: BTreeMap<RiskLevel, usize>,
+}
+
+impl Dashboard {
+    /// Count the classifications per kind and risk level.
+    pub fn new(commits: Vec<Classification>) -> Dashboard {
+        let mut kinds: BTreeMap<CommitKind, usize> = CommitKind::ALL.iter().map(|kind| (*kind, 0)).collect();
+        let mut risks: BTreeMap<RiskLevel, usize> = RiskLevel::ALL.iter().map(|risk| (*risk, 0)).collect();
+        for commit in &commits {
+            *kinds.entry(commit.kind).or_default() += 1;
+            *risks.entry(commit.risk).or_default() += 1;
+        }
+        Dashboard { commits, kinds, risks }
+    }
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    commits: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    sha: String,
+    kind: CommitKind,
+    risk: RiskLevel,
+    reason: String,
+}
+
+// Each analyzed commit's full SHA, score, details and changed paths, in
+// the analyzed order
+pub(crate) fn analyzed(input: &AnalysisInput) -> Vec<(&str, &CommitScore, &str, &[String])> {
+    input
+        .commit_details
+        .iter()
+        .zip(&input.commit_paths)
+        .filter_map(|(details, paths)| {
+            let full_id = details.lines().next()?.strip_prefix("Commit: ")?;
+            let score = input.scores.iter().find(|score| full_id.starts_with(&score.short_id))?;
+            Some((full_id, score, details.as_str(), paths.as_slice()))
+        })
+        .collect()
+}
+
+// What the model is told about each commit: its details plus the size of
+// its change, which the risk level depends on
+fn content(input: &AnalysisInput) -> String {
+    let mut content = String::new();
+    for (_, score, details, paths) in analyzed(input) {
+        content.push_str(&format!(
+            "Short SHA: {}\nChanged: {} file(s), +{} -{} lines\n",
+            score.short_id, score.files, score.added, score.removed
+        ));
+        if !paths.is_empty() {
+            content.push_str(&format!("Files: {}\n", paths.join(", ")));
+        }
+        content.push_str(details);
+        content.push_str("\n\n---\n\n");
+    }
+    content
+}
+
+/// Estimated prompt tokens of the classification request.
+pub fn estimate_tokens(input: &AnalysisInput) -> usize {
+    tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(CLASSIFY_PROMPT) + tokens::estimate(&content(input))
+}
+
+/// Classify every commit in `input` with one structured request.
+///
+/// Entries for commits that were not analyzed are dropped, as are analyzed
+/// commits the model skipped: the dashboard never counts a commit twice or
+/// one that was not analyzed.
+pub async fn classify(input: &AnalysisInput, provider: &dyn LlmProvider) -> Result<Dashboard> {
+    let messages = [
+        Message::system(SYSTEM_PROMPT),
+        Message::user(format!("{}\n\n{}", CLASSIFY_PROMPT, content(input))),
+    ];
+    let kinds: Vec<_> = CommitKind::ALL.iter().map(|kind| json!(kind)).collect();
+    let risks: Vec<_> = RiskLevel::ALL.iter().map(|risk| json!(risk)).collect();
+    let schema = Schema::new(
+        "commit_classification",
+        json!({
+            "type": "object",
+            "properties": {
+                "commits": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "sha": { "type": "string" },
+                            "kind": { "type": "string", "enum": kinds },
+                            "risk": { "type": "string", "enum": risks },
+                            "reason": { "type": "string" },
+                        },
+                        "required": ["sha", "kind", "risk", "reason"],
+                        "additionalProperties": false,
+                    },
+                    "minItems": input.scores.len(),
+                    "maxItems": input.scores.len(),
+                },
+            },
+            "required": ["commits"],
+            "additionalProperties": false,
+        }),
+    );
+    let reply: Reply = structured::request(provider, &messages, &schema).await?;
+
+    // Keep the analyzed order; the model may answer in any
+    let mut commits = Vec::new();
+    for (full_id, score, _, _) in analyzed(input) {
+        let entry = reply
+            .commits
+            .iter()
+            .find(|entry| entry.sha.len() >= 7 && (full_id.starts_with(&entry.sha) || entry.sha.starts_with(&score.short_id)));
+        if let Some(entry) = entry {
+            commits.push(Classification {
+                short_id: score.short_id.clone(),
+                kind: entry.kind,
+                risk: entry.risk,
+                reason: entry.reason.trim().to_string(),
+            });
+        }
+    }
+    Ok(Dashboard::new(commits))
+}
@@ -0,0 +1,121 @@
+//! `wtf commit <rev>`: one commit explained on its own.
+//!
+//! The report shows the commit's metadata and the files it changed, from
+//! the repository, above the model's explanation of its patch (see
+//! [`analysis::explain_commit`](crate::analysis::explain_commit)).
+
+use crate::error::Result;
+use crate::git;
+use crate::render::OutputFormat;
+use crate::tr;
+use git2::{Commit, Diff, Repository};
+use serde::Serialize;
+
+/// The lines one file gained and lost.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    pub path: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// A commit and what the model made of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitReport {
+    pub sha: String,
+    pub author: String,
+    /// Commit time, seconds since the epoch.
+    pub time: i64,
+    pub message: String,
+    /// Against the first parent, or everything for a root commit.
+    pub files: Vec<FileChange>,
+    pub explanation: String,
+    /// Link to the commit on its hosting site, if known.
+    pub url: Option<String>,
+}
+
+impl CommitReport {
+    /// Gather the metadata and changed files of `commit` to go with
+    /// `explanation`.
+    pub fn new(repo: &Repository, commit: &Commit<'_>, explanation: String) -> Result<CommitReport> {
+        Ok(CommitReport {
+            sha: commit.id().to_string(),
+            author: git::decode_text(commit.author().name_bytes(), commit.message_encoding()),
+            time: commit.time().seconds(),
+            message: git::commit_message(commit),
+            files: file_changes(repo, commit)?,
+            explanation,
+            url: git::commit_url_base(repo).map(|base| format!("{}/{}", base, commit.id())),
+        })
+    }
+
+    fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(7)]
+    }
+
+    fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+}
+
+/// The files `commit` changed against its first parent, with line counts.
+pub fn file_changes(repo: &Repository, commit: &Commit<'_>) -> Result<Vec<FileChange>> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    diff_files(&diff)
+}
+
+/// The files in `diff`, with line counts.
+pub fn diff_files(diff: &Diff<'_>) -> Result<Vec<FileChange>> {
+    let mut files = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = git2::Patch::from_diff(diff, idx)? else { continue };
+        let delta = patch.delta();
+        let Some(path) = delta.new_file().path_bytes().or(delta.old_file().path_bytes()) else { continue };
+        let (_, added, removed) = patch.line_stats()?;
+        files.push(FileChange { path: git::escape_non_utf8(path), added, removed });
+    }
+    Ok(files)
+}
+
+/// Format `report` for output.
+pub fn render(report: &CommitReport, format: OutputFormat) -> String {
+    let title = tr!("commit-title", sha = report.short_sha(), subject = report.subject());
+    let mut details = vec![
+        tr!("commit-author", author = report.author.as_str()),
+        tr!("commit-date", date = git::format_date(report.time)),
+    ];
+    if let Some(url) = &report.url {
+        details.push(url.clone());
+    }
+    let files: Vec<String> = report
+        .files
+        .iter()
+        .map(|f| tr!("commit-file-entry", path = f.path.as_str(), added = f.added, removed = f.removed))
+        .collect();
+
+    let list = |items: &[String]| items.iter().map(|item| format!("- {}\n", item)).collect::<String>();
+    let mut sections = vec![(title, details.join("\n") + "\n")];
+    if !files.is_empty() {
+        sections.push((tr!("commit-files", count = files.len()), list(&files)));
+    }
+    sections.push((tr!("commit-explanation"), report.explanation.trim().to_string() + "\n"));
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("commit report serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,153 @@
+//! Heuristic review-effort scores per commit.
+//!
+//! A score from 0 to 100 combines how much a commit changes, how many files
+//! it touches, how scattered its changes are across hunks and whether tests
+//! changed along with the code. It is meant for triage ("look at this one
+//! first"), not as a measure of quality.
+
+use crate::error::Result;
+use crate::git;
+use git2::{Commit, Repository};
+use serde::Serialize;
+
+// Points each factor contributes at most; they add up to 100
+const SIZE_POINTS: f64 = 40.0;
+const SPREAD_POINTS: f64 = 25.0;
+const SCATTER_POINTS: f64 = 20.0;
+const UNTESTED_POINTS: f64 = 15.0;
+
+// Where each factor maxes out
+const SIZE_CAP_LINES: f64 = 1000.0;
+const SPREAD_CAP_FILES: f64 = 50.0;
+const SCATTER_CAP_BITS: f64 = 6.0;
+
+/// How much attention a commit probably needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effort {
+    Low,
+    Medium,
+    High,
+}
+
+/// The review-effort score of one commit, with what it is made of.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommitScore {
+    pub short_id: String,
+    /// 0 (trivial) to 100 (review carefully).
+    pub score: u32,
+    pub effort: Effort,
+    pub files: usize,
+    pub hunks: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// Shannon entropy (bits) of how the changed lines spread over hunks;
+    /// 0 for a single hunk, higher for many similar-sized ones.
+    pub hunk_entropy: f64,
+    /// Changed test lines per changed non-test line; `None` when only tests
+    /// (or nothing) changed.
+    pub test_ratio: Option<f64>,
+}
+
+/// Score `commit` against its first parent (root commits against an empty tree),
+/// counting only its changes under the pathspecs in `paths`, if any.
+pub fn score_commit(repo: &Repository, commit: &Commit, paths: &[String]) -> Result<CommitScore> {
+    let diff = git::commit_diff(repo, commit, paths)?;
+
+    let mut hunk_sizes = Vec::new();
+    let (mut added, mut removed, mut test_lines, mut code_lines) = (0, 0, 0, 0);
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = git2::Patch::from_diff(&diff, idx)? else { continue };
+        let is_test = patch
+            .delta()
+            .new_file()
+            .path()
+            .is_some_and(|path| is_test_path(&path.to_string_lossy().replace('\\', "/")));
+        for hunk in 0..patch.num_hunks() {
+            let mut size = 0;
+            for line in 0..patch.num_lines_in_hunk(hunk)? {
+                match patch.line_in_hunk(hunk, line)?.origin() {
+                    '+' => added += 1,
+                    '-' => removed += 1,
+                    _ => continue,
+                }
+                size += 1;
+            }
+            hunk_sizes.push(size);
+            if is_test {
+                test_lines += size;
+            } else {
+                code_lines += size;
+            }
+        }
+    }
+
+    let files = diff.deltas().len();
+    let hunk_entropy = entropy(&hunk_sizes);
+    let test_ratio = (code_lines > 0).then(|| test_lines as f64 / code_lines as f64);
+    let score = score(added + removed, files, hunk_entropy, test_ratio);
+    Ok(CommitScore {
+        short_id: commit.as_object().short_id()?.as_str().unwrap_or_default().to_string(),
+        score,
+        effort: match score {
+            0..=29 => Effort::Low,
+            30..=59 => Effort::Medium,
+            _ => Effort::High,
+        },
+        files,
+        hunks: hunk_sizes.len(),
+        added,
+        removed,
+        hunk_entropy,
+        test_ratio,
+    })
+}
+
+/// Combine the factors into a 0-100 score.
+///
+/// Size and spread grow logarithmically, so the first hundred lines count
+/// for more than the next thousand. Code changes without test changes add
+/// up to [`UNTESTED_POINTS`].
+pub fn score(lines: usize, files: usize, hunk_entropy: f64, test_ratio: Option<f64>) -> u32 {
+    let scaled = |value: f64, cap: f64| ((1.0 + value).ln() / (1.0 + cap).ln()).min(1.0);
+    let untested = test_ratio.map_or(0.0, |ratio| 1.0 - ratio.min(1.0));
+    let total = SIZE_POINTS * scaled(lines as f64, SIZE_CAP_LINES)
+        + SPREAD_POINTS * scaled(files as f64, SPREAD_CAP_FILES)
+        + SCATTER_POINTS * (hunk_entropy / SCATTER_CAP_BITS).min(1.0)
+        + UNTESTED_POINTS * untested;
+    total.round() as u32
+}
+
+fn entropy(sizes: &[usize]) -> f64 {
+    let total: usize = sizes.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    sizes
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Whether `path` looks like a test: inside a `test(s)`/`spec(s)` directory,
+/// or named like `foo_test.go`, `foo.test.ts`, `foo.spec.js` or `test_foo.py`.
+pub fn is_test_path(path: &str) -> bool {
+    let mut components: Vec<&str> = path.split('/').collect();
+    let file = components.pop().unwrap_or_default().to_lowercase();
+    if components
+        .iter()
+        .any(|dir| matches!(dir.to_lowercase().as_str(), "test" | "tests" | "spec" | "specs" | "__tests__"))
+    {
+        return true;
+    }
+    let stem = file.split('.').next().unwrap_or_default();
+    stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || file.contains(".test.")
+        || file.contains(".spec.")
+}
@@ -0,0 +1,217 @@
+//! Grouping changes by component, for `--group-by`.
+//!
+//! In a monorepo one chronological list of commits mixes the API server
+//! with the frontend and the deployment scripts. With `--group-by dir[:depth]`
+//! every changed file belongs to its leading directories; with
+//! `--group-by components` it belongs to the first component in the
+//! `[components]` table of `.wtf.toml` whose patterns match it:
+//!
+//! ```toml
+//! [components]
+//! "API server" = ["server/**", "api"]
+//! frontend = "web/"
+//! ```
+//!
+//! A pattern without wildcards matches that path and everything below it;
+//! other patterns are matched like `.gitignore` globs.
+
+use crate::attributes;
+use crate::chunk;
+use crate::error::{Result, WtfError};
+use crate::ownership;
+use crate::tr;
+use git2::Repository;
+use std::str::FromStr;
+
+/// The configuration file components are read from, at the repository root.
+pub const CONFIG_FILE: &str = ".wtf.toml";
+
+/// How `--group-by` was spelled on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupSpec {
+    /// `dir` or `dir:<depth>`.
+    Directory(usize),
+    /// `components`.
+    Components,
+}
+
+impl FromStr for GroupSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> std::result::Result<Self, Self::Err> {
+        match spec.split_once(':') {
+            None if spec == "dir" => Ok(GroupSpec::Directory(1)),
+            None if spec == "components" => Ok(GroupSpec::Components),
+            Some(("dir", depth)) => match depth.parse() {
+                Ok(depth) if depth > 0 => Ok(GroupSpec::Directory(depth)),
+                _ => Err(tr!("group-by-bad-depth", depth = depth)),
+            },
+            _ => Err(tr!("group-by-invalid", spec = spec)),
+        }
+    }
+}
+
+/// A named part of the code base and the patterns of its paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub name: String,
+    pub patterns: Vec<String>,
+}
+
+impl Component {
+    fn matches(&self, path: &str) -> bool {
+        self.patterns.iter().any(|pattern| {
+            if pattern.contains(['*', '?', '[']) {
+                attributes::glob_matches(pattern.trim_start_matches('/'), path)
+            } else {
+                let prefix = pattern.trim_matches('/');
+                path == prefix || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+            }
+        })
+    }
+}
+
+/// How changed files are assigned to groups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupBy {
+    /// By leading directories, this many levels deep.
+    Directory { depth: usize },
+    /// By configured components, in the order they are defined.
+    Components(Vec<Component>),
+}
+
+impl GroupBy {
+    /// Turn `spec` into a grouping, reading components from the
+    /// [`CONFIG_FILE`] in `repo`'s working directory if needed.
+    pub fn resolve(spec: GroupSpec, repo: &Repository) -> Result<GroupBy> {
+        match spec {
+            GroupSpec::Directory(depth) => Ok(GroupBy::Directory { depth }),
+            GroupSpec::Components => {
+                let path = repo.workdir().unwrap_or_else(|| repo.path()).join(CONFIG_FILE);
+                let text = std::fs::read_to_string(&path).unwrap_or_default();
+                let components = parse_components(&text);
+                if components.is_empty() {
+                    return Err(WtfError::InvalidConfig { path, reason: tr!("config-no-components") });
+                }
+                Ok(GroupBy::Components(components))
+            }
+        }
+    }
+
+    /// The group `path` belongs to.
+    pub fn group_of(&self, path: &str) -> String {
+        match self {
+            GroupBy::Directory { depth } => match ownership::area_of(path, *depth) {
+                area if area.is_empty() => tr!("group-root"),
+                area => area,
+            },
+            GroupBy::Components(components) => components
+                .iter()
+                .find(|component| component.matches(path))
+                .map(|component| component.name.clone())
+                .unwrap_or_else(|| tr!("group-other")),
+        }
+    }
+
+    /// Arrange commits by group: each commit (given with the paths it
+    /// changed) is listed under every group it touches. Commits keep their
+    /// order within a group; commits without changed files go to the
+    /// catch-all group.
+    pub fn group_commits<'a>(&self, commits: &[(&'a str, &[String])]) -> Vec<(String, Vec<&'a str>)> {
+        let mut groups: Vec<(String, Vec<&'a str>)> = Vec::new();
+        for (details, paths) in commits {
+            let mut names = Vec::new();
+            for name in paths.iter().map(|path| self.group_of(path)) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            if names.is_empty() {
+                names.push(tr!("group-other"));
+            }
+            for name in names {
+                match groups.iter_mut().find(|(group, _)| *group == name) {
+                    Some((_, listed)) => listed.push(details),
+                    None => groups.push((name, vec![details])),
+                }
+            }
+        }
+        self.sort_groups(&mut groups);
+        groups
+    }
+
+    /// Split labeled patches (a `Commit: <sha>` line, then the patch) by
+    /// group: each group gets the file diffs in it, still labeled with
+    /// their commit.
+    pub fn group_patches(&self, patches: &[String]) -> Vec<(String, String)> {
+        let mut groups: Vec<(String, String)> = Vec::new();
+        for labeled in patches {
+            let (label, patch) = labeled.split_once('\n').unwrap_or((labeled.as_str(), ""));
+            let mut per_group: Vec<(String, String)> = Vec::new();
+            for file in chunk::split_files(patch) {
+                let Some(path) = file.path() else { continue };
+                let name = self.group_of(path);
+                let diff = format!("{}{}", file.header, file.hunks.concat());
+                match per_group.iter_mut().find(|(group, _)| *group == name) {
+                    Some((_, text)) => text.push_str(&diff),
+                    None => per_group.push((name, diff)),
+                }
+            }
+            for (name, diff) in per_group {
+                let piece = format!("{}\n{}", label, diff);
+                match groups.iter_mut().find(|(group, _)| *group == name) {
+                    Some((_, text)) => {
+                        text.push_str("\n---\n\n");
+                        text.push_str(&piece);
+                    }
+                    None => groups.push((name, piece)),
+                }
+            }
+        }
+        self.sort_groups(&mut groups);
+        groups
+    }
+
+    // Components in the order they are defined, directories by name, and
+    // the catch-all group last
+    fn sort_groups<T>(&self, groups: &mut [(String, T)]) {
+        match self {
+            GroupBy::Directory { .. } => groups.sort_by(|(a, _), (b, _)| a.cmp(b)),
+            GroupBy::Components(components) => groups
+                .sort_by_key(|(name, _)| components.iter().position(|c| &c.name == name).unwrap_or(usize::MAX)),
+        }
+        let other = tr!("group-other");
+        groups.sort_by_key(|(name, _)| *name == other);
+    }
+}
+
+/// The components defined in the `[components]` table of a `.wtf.toml`.
+///
+/// Values are a quoted pattern or a one-line array of them; names may be
+/// quoted. Anything else in the file is ignored.
+pub fn parse_components(text: &str) -> Vec<Component> {
+    let mut components = Vec::new();
+    let mut in_table = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') && !line.starts_with("[[") && line.ends_with(']') {
+            in_table = line.trim_matches(['[', ']']).trim() == "components";
+            continue;
+        }
+        if !in_table || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else { continue };
+        let name = name.trim().trim_matches('"').trim();
+        let patterns: Vec<String> = value
+            .split(',')
+            .map(|item| item.trim().trim_matches(['[', ']']).trim())
+            .filter_map(|item| item.strip_prefix('"').and_then(|item| item.strip_suffix('"')))
+            .filter(|pattern| !pattern.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !name.is_empty() && !patterns.is_empty() {
+            components.push(Component { name: name.to_string(), patterns });
+        }
+    }
+    components
+}
@@ -0,0 +1,250 @@
+//! Condensing large file diffs to the code items they change.
+//!
+//! A diff of several hundred lines costs many tokens and buries what
+//! changed in noise. When a commit's patch doesn't fit its token budget,
+//! each file diff above [`CONDENSE_TOKENS`] is replaced by a structural
+//! summary: which functions, types and methods were added, removed or
+//! modified, and how many lines changed.
+//!
+//! Rust files are parsed with `syn`, as for the public API section, so their
+//! summary is exact. No parser for other languages is bundled; for those the
+//! enclosing definitions Git writes after each hunk's `@@` line (its
+//! function-context heuristic) are listed instead.
+
+use crate::chunk;
+use crate::error::Result;
+use crate::git;
+use crate::tokens;
+use git2::{Commit, Oid, Repository};
+use quote::ToTokens;
+use std::collections::HashMap;
+use syn::{ImplItem, Item};
+
+/// File diffs estimated above this many tokens are condensed.
+pub const CONDENSE_TOKENS: usize = 1500;
+
+// Contexts listed for a file in a language without a parser
+const MAX_CONTEXTS: usize = 20;
+
+/// Which items of a file a change added, removed or modified, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl ItemChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Every item of a Rust source file, in source order, as its name (like
+/// `fn parse`, `fn Config::load` or `impl Display for Config`) and its
+/// tokens. `None` if the file does not parse.
+pub fn rust_items(source: &str) -> Option<Vec<(String, String)>> {
+    let file = syn::parse_file(source).ok()?;
+    let mut items = Vec::new();
+    collect_items(&file.items, "", &mut items);
+    Some(items)
+}
+
+fn collect_items(items: &[Item], prefix: &str, out: &mut Vec<(String, String)>) {
+    for item in items {
+        let name = match item {
+            Item::Fn(item) => format!("fn {}{}", prefix, item.sig.ident),
+            Item::Struct(item) => format!("struct {}{}", prefix, item.ident),
+            Item::Enum(item) => format!("enum {}{}", prefix, item.ident),
+            Item::Union(item) => format!("union {}{}", prefix, item.ident),
+            Item::Trait(item) => format!("trait {}{}", prefix, item.ident),
+            Item::Type(item) => format!("type {}{}", prefix, item.ident),
+            Item::Const(item) => format!("const {}{}", prefix, item.ident),
+            Item::Static(item) => format!("static {}{}", prefix, item.ident),
+            Item::Macro(syn::ItemMacro { ident: Some(ident), .. }) => format!("macro {}{}", prefix, ident),
+            Item::Mod(item) => {
+                if let Some((_, content)) = &item.content {
+                    collect_items(content, &format!("{}{}::", prefix, item.ident), out);
+                }
+                continue;
+            }
+            Item::Impl(item) => {
+                collect_impl(item, prefix, out);
+                continue;
+            }
+            _ => continue,
+        };
+        out.push((name, tokens(item)));
+    }
+}
+
+// Methods of inherent impls count one by one; a trait impl is one item
+fn collect_impl(item: &syn::ItemImpl, prefix: &str, out: &mut Vec<(String, String)>) {
+    let self_ty = match &*item.self_ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+    .unwrap_or_else(|| tokens(&item.self_ty));
+    match &item.trait_ {
+        None => {
+            for impl_item in &item.items {
+                if let ImplItem::Fn(method) = impl_item {
+                    out.push((format!("fn {}{}::{}", prefix, self_ty, method.sig.ident), tokens(method)));
+                }
+            }
+        }
+        Some((_, trait_path, _)) => {
+            let trait_name = trait_path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+            out.push((format!("impl {}{} for {}", prefix, trait_name, self_ty), tokens(item)));
+        }
+    }
+}
+
+fn tokens(node: &impl ToTokens) -> String {
+    node.to_token_stream().to_string()
+}
+
+/// Compare the items of two versions of a file by name. Items sharing a
+/// name, like the two sides of a `#[cfg]` pair, are paired in order, and
+/// each name is listed once.
+pub fn compare_items(old: &[(String, String)], new: &[(String, String)]) -> ItemChanges {
+    let mut old_items: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (name, tokens) in old {
+        old_items.entry(name).or_default().push(tokens);
+    }
+    let mut changes = ItemChanges::default();
+    let push = |names: &mut Vec<String>, name: &str| {
+        if !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+    };
+    // How many items of each name the new version has
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (name, tokens) in new {
+        let count = counts.entry(name).or_default();
+        match old_items.get(name.as_str()).and_then(|twins| twins.get(*count)) {
+            None => push(&mut changes.added, name),
+            Some(old_tokens) if old_tokens != tokens => push(&mut changes.modified, name),
+            Some(_) => {}
+        }
+        *count += 1;
+    }
+    for (name, _) in old {
+        if old_items[name.as_str()].len() > counts.get(name.as_str()).copied().unwrap_or(0) {
+            push(&mut changes.removed, name);
+        }
+    }
+    changes
+}
+
+/// The function context of each hunk, from the text after its `@@ ... @@`
+/// line, without duplicates.
+pub fn hunk_contexts(hunks: &[&str]) -> Vec<String> {
+    let mut contexts = Vec::new();
+    for hunk in hunks {
+        let first = hunk.lines().next().unwrap_or_default();
+        let Some(rest) = first.strip_prefix("@@").and_then(|rest| rest.split_once("@@")) else { continue };
+        let context = rest.1.trim().to_string();
+        if !context.is_empty() && !contexts.contains(&context) {
+            contexts.push(context);
+        }
+    }
+    contexts
+}
+
+// The blobs and line counts of each file a commit changed, by new path
+struct FileChange {
+    old: Option<Oid>,
+    new: Option<Oid>,
+    added: usize,
+    removed: usize,
+}
+
+fn file_changes(repo: &Repository, commit: &Commit) -> Result<HashMap<String, FileChange>> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let mut changes = HashMap::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        let blob = |file: git2::DiffFile| Some(file.id()).filter(|id| !id.is_zero());
+        let Some(path) = delta.new_file().path_bytes().map(git::escape_non_utf8) else { continue };
+        let (added, removed) = match git2::Patch::from_diff(&diff, idx)? {
+            Some(patch) => {
+                let (_, added, removed) = patch.line_stats()?;
+                (added, removed)
+            }
+            None => (0, 0),
+        };
+        let change = FileChange { old: blob(delta.old_file()), new: blob(delta.new_file()), added, removed };
+        changes.insert(path, change);
+    }
+    Ok(changes)
+}
+
+fn blob_text(repo: &Repository, id: Option<Oid>) -> Result<String> {
+    match id {
+        Some(id) => Ok(String::from_utf8_lossy(repo.find_blob(id)?.content()).into_owned()),
+        None => Ok(String::new()),
+    }
+}
+
+/// `patch` (the patch of `commit`) as is if it fits in `max_tokens`, or
+/// else with every file diff above [`CONDENSE_TOKENS`] replaced by a
+/// summary of the items it changes.
+///
+/// File headers are kept, so condensed files can still be told apart.
+pub fn condense_patch(repo: &Repository, commit: &Commit, patch: &str, max_tokens: usize) -> Result<String> {
+    if tokens::estimate(patch) <= max_tokens {
+        return Ok(patch.to_string());
+    }
+    let files = chunk::split_files(patch);
+    let is_large = |file: &chunk::FileDiff| {
+        tokens::estimate(file.header) + tokens::estimate(&file.hunks.concat()) > CONDENSE_TOKENS
+    };
+    if !files.iter().any(is_large) {
+        return Ok(patch.to_string());
+    }
+
+    let changes = file_changes(repo, commit)?;
+    let mut out = String::new();
+    for file in files {
+        out.push_str(file.header);
+        let change = file.path().and_then(|path| changes.get(path));
+        let (Some(change), true) = (change, is_large(&file)) else {
+            out.push_str(&file.hunks.concat());
+            continue;
+        };
+        out.push_str(&format!(
+            "Condensed: +{} -{} lines, the full diff is left out.\n",
+            change.added, change.removed
+        ));
+        let is_rust = file.path().is_some_and(|path| path.ends_with(".rs"));
+        let items = match is_rust {
+            true => rust_items(&blob_text(repo, change.old)?).zip(rust_items(&blob_text(repo, change.new)?)),
+            false => None,
+        };
+        match items {
+            Some((old, new)) => {
+                let items = compare_items(&old, &new);
+                for (label, names) in [("Modified", &items.modified), ("Added", &items.added), ("Removed", &items.removed)] {
+                    if !names.is_empty() {
+                        out.push_str(&format!("{}: {}\n", label, names.join(", ")));
+                    }
+                }
+                if items.is_empty() {
+                    out.push_str("No items changed (only comments, formatting or `use` lines).\n");
+                }
+            }
+            None => {
+                let contexts = hunk_contexts(&file.hunks);
+                if !contexts.is_empty() {
+                    let listed: Vec<_> = contexts.into_iter().take(MAX_CONTEXTS).collect();
+                    out.push_str(&format!("Changed in: {}\n", listed.join("; ")));
+                }
+            }
+        }
+    }
+    Ok(out)
+}
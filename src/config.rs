@@ -0,0 +1,261 @@
+//! Reading settings from `.env` files, wtf's own `config.toml` and a
+//! repository's `.wtf.toml`.
+//!
+//! Besides a `[providers.<name>]` section per provider, `config.toml` can
+//! hold defaults for the command line at its top, which flags override. The
+//! same keys at the top of `.wtf.toml` at a repository's root, where teams
+//! can check them in, override those of `config.toml`:
+//!
+//! ```toml
+//! provider = "anthropic"
+//! model = "claude-sonnet-4-5"
+//! temperature = 0.2
+//! num_commits = 10
+//! format = "markdown"
+//! exclude = ["dist/**", "*.min.js"]
+//! ```
+
+use crate::components;
+use crate::error::{Result, WtfError};
+use crate::prompts::Audience;
+use crate::render::OutputFormat;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// The name of wtf's settings file in its config directory.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Defaults for the command line, from the top of `config.toml` and
+/// `.wtf.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Defaults {
+    /// The provider when neither `--provider` nor `WTF_PROVIDER` names one.
+    pub provider: Option<String>,
+    /// The model for providers whose `[providers.<name>]` section sets none.
+    pub model: Option<String>,
+    /// The sampling settings, likewise.
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub num_commits: Option<usize>,
+    pub format: Option<OutputFormat>,
+    /// Globs left out of the analysis, before those given with `--exclude`.
+    pub exclude: Vec<String>,
+    /// The directory of prompt templates. A relative path is taken from the
+    /// directory of the file that sets it.
+    pub prompts_dir: Option<PathBuf>,
+    /// Who the report is written for when `--for` does not say.
+    pub audience: Option<Audience>,
+    /// The language the model writes in when `--lang` does not say.
+    pub lang: Option<String>,
+    /// The branch `wtf branch` compares against when `--base` does not say.
+    pub base_branch: Option<String>,
+}
+
+impl Defaults {
+    /// The defaults in the contents of a `config.toml`, or why they cannot
+    /// be read. Keys wtf does not know are ignored.
+    pub fn parse(contents: &str) -> std::result::Result<Defaults, String> {
+        let values = toml_table_values(contents, "");
+        serde_json::from_value(Value::Object(values.clone())).map_err(|e| {
+            // serde_json does not say which key was wrong, so find it
+            let wrong = values.into_iter().find_map(|(key, value)| {
+                let single = Map::from_iter([(key.clone(), value)]);
+                serde_json::from_value::<Defaults>(Value::Object(single)).err().map(|e| format!("{}: {}", key, e))
+            });
+            wrong.unwrap_or_else(|| e.to_string())
+        })
+    }
+
+    /// The defaults in `config.toml` in `config_dir`, which are empty if
+    /// there is no such file.
+    pub fn load(config_dir: &Path) -> Result<Defaults> {
+        Defaults::load_file(&config_dir.join(CONFIG_FILE))
+    }
+
+    /// The defaults of `config.toml` in `config_dir`, if there is one, with
+    /// those of the `.wtf.toml` of the repository at `repo_path` over them.
+    pub fn for_repository(config_dir: Option<&Path>, repo_path: &Path) -> Result<Defaults> {
+        let global = match config_dir {
+            Some(dir) => Defaults::load(dir)?,
+            None => Defaults::default(),
+        };
+        let Some(root) = working_dir(repo_path) else { return Ok(global) };
+        Ok(global.merged(Defaults::load_file(&root.join(components::CONFIG_FILE))?))
+    }
+
+    /// These defaults with those set in `over` taking their place. The
+    /// `exclude` globs of both count.
+    pub fn merged(self, over: Defaults) -> Defaults {
+        Defaults {
+            provider: over.provider.or(self.provider),
+            model: over.model.or(self.model),
+            temperature: over.temperature.or(self.temperature),
+            top_p: over.top_p.or(self.top_p),
+            max_tokens: over.max_tokens.or(self.max_tokens),
+            num_commits: over.num_commits.or(self.num_commits),
+            format: over.format.or(self.format),
+            exclude: self.exclude.into_iter().chain(over.exclude).collect(),
+            prompts_dir: over.prompts_dir.or(self.prompts_dir),
+            audience: over.audience.or(self.audience),
+            lang: over.lang.or(self.lang),
+            base_branch: over.base_branch.or(self.base_branch),
+        }
+    }
+
+    /// The defaults in the file at `path`, which are empty if there is no
+    /// such file.
+    pub fn load_file(path: &Path) -> Result<Defaults> {
+        let path = path.to_path_buf();
+        let mut defaults = match std::fs::read_to_string(&path) {
+            Ok(contents) => Defaults::parse(&contents)
+                .map_err(|reason| WtfError::InvalidConfig { path: path.clone(), reason })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Defaults::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let base = path.parent().unwrap_or(Path::new(""));
+        defaults.prompts_dir = defaults.prompts_dir.map(|dir| base.join(dir));
+        Ok(defaults)
+    }
+}
+
+/// Look up `key` in the contents of a `.env` file.
+///
+/// Understands `KEY=value` and `export KEY=value` lines, single- or
+/// double-quoted values, `#` comments, CRLF line endings and a leading BOM
+/// (both common in files saved with Windows editors). Later assignments win.
+pub fn env_file_value(contents: &str, key: &str) -> Option<String> {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let mut value = None;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, raw)) = line.split_once('=') else {
+            continue;
+        };
+        if name.trim() == key {
+            value = Some(unquote(raw.trim()));
+        }
+    }
+
+    value
+}
+
+/// The `.env` file next to the repository at `repo_path`: at the top of its
+/// working directory, or in `repo_path` itself if that is not in one.
+pub fn env_file_path(repo_path: &Path) -> PathBuf {
+    working_dir(repo_path).unwrap_or_else(|| repo_path.to_path_buf()).join(".env")
+}
+
+// The top of the working directory `path` is in, if it is in one
+fn working_dir(path: &Path) -> Option<PathBuf> {
+    git2::Repository::discover(path).ok()?.workdir().map(Path::to_path_buf)
+}
+
+/// Read `key` from the `.env` file at `path`, if the file exists and sets it.
+pub fn read_env_file(path: &Path, key: &str) -> Option<String> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    env_file_value(&contents, key).filter(|v| !v.is_empty())
+}
+
+/// The `key = value` pairs of the `[table]` table in the contents of a TOML
+/// file, keys as written and values unquoted. Array tables, nested values
+/// and multi-line strings are not understood and are skipped.
+pub fn toml_table(contents: &str, table: &str) -> Vec<(String, String)> {
+    table_lines(contents, table)
+        .filter(|(_, raw)| !raw.starts_with(['[', '{']) && !raw.starts_with("\"\"\"") && !raw.starts_with("'''"))
+        .map(|(key, raw)| (key, unquote(raw)))
+        .collect()
+}
+
+/// Like [`toml_table`], with typed values: strings, integers, floats,
+/// booleans and single-line arrays of them. An empty `table` stands for the
+/// keys before the first table header. Values of other kinds are skipped.
+pub fn toml_table_values(contents: &str, table: &str) -> Map<String, Value> {
+    table_lines(contents, table).filter_map(|(key, raw)| Some((key, toml_value(raw)?))).collect()
+}
+
+// The keys of `table` and their values as written
+fn table_lines<'a>(contents: &'a str, table: &'a str) -> impl Iterator<Item = (String, &'a str)> + 'a {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let mut in_table = table.is_empty();
+    contents.lines().map(str::trim).filter_map(move |line| {
+        if line.starts_with('[') && !line.starts_with("[[") && line.ends_with(']') {
+            in_table = line.trim_matches(['[', ']']).trim() == table;
+            return None;
+        }
+        if line.starts_with("[[") {
+            in_table = false;
+        }
+        if !in_table || line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (key, raw) = line.split_once('=')?;
+        Some((key.trim().trim_matches('"').to_string(), raw.trim()))
+    })
+}
+
+fn toml_value(raw: &str) -> Option<Value> {
+    if raw.starts_with(['"', '\'']) {
+        return (!raw.starts_with("\"\"\"") && !raw.starts_with("'''")).then(|| Value::String(unquote(raw)));
+    }
+    if let Some(inner) = raw.strip_prefix('[') {
+        let end = inner.rfind(']')?;
+        return split_items(&inner[..end]).into_iter().map(toml_value).collect::<Option<Vec<_>>>().map(Value::Array);
+    }
+    let raw = unquote(raw);
+    match raw.as_str() {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        number => {
+            let number = number.replace('_', "");
+            match number.parse::<i64>() {
+                Ok(int) => Some(int.into()),
+                Err(_) => number.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number),
+            }
+        }
+    }
+}
+
+// The items of an array's contents, split at commas outside quotes
+fn split_items(contents: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in contents.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, ',') => {
+                items.push(contents[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(contents[start..].trim());
+    // A trailing comma leaves an empty last item
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+fn unquote(raw: &str) -> String {
+    for quote in ['"', '\''] {
+        if let Some(inner) = raw.strip_prefix(quote) {
+            if let Some(end) = inner.find(quote) {
+                return inner[..end].to_string();
+            }
+        }
+    }
+    // Unquoted values may carry a trailing comment
+    match raw.find(" #") {
+        Some(comment) => raw[..comment].trim_end().to_string(),
+        None => raw.to_string(),
+    }
+}
@@ -0,0 +1,198 @@
+//! `wtf conflict`: what the two sides of each conflict were trying to do,
+//! and how to resolve it.
+//!
+//! The conflicted entries of the index hold every version of a file: the
+//! common base, ours and theirs. Each file is explained in its own request,
+//! together with the operation under way, since which side is "ours"
+//! depends on it: in a rebase, ours is the branch being rebased onto and
+//! theirs is your own commit being replayed.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::conversation::Conversation;
+use crate::error::{Result, WtfError};
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::state::{self, Operation, RepoState};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{IndexEntry, Repository};
+use serde::Serialize;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that helps developers understand and resolve merge conflicts in Git.";
+
+const CONFLICT_PROMPT: &str = "Below is a file with a conflict: what the operation under way is, and the file's version in the common base both sides started from, in ours and in theirs. Explain in plain language what each side changed and what it was probably trying to achieve. Then suggest how to resolve the conflict: which side to keep where or how to combine them, and what to check afterwards. Keep it under 250 words.";
+
+const PROPOSE_INSTRUCTIONS: &str = "After that, propose the resolved content of each conflicting part of the file in a fenced code block, and say that it needs reviewing before it is used.";
+
+// Each version of a file is cut to this many tokens
+const VERSION_TOKENS: usize = 2000;
+
+/// A file with a conflict, in each of its versions. A version is missing
+/// when the file does not exist on that side, like when one side deleted it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConflictedFile {
+    pub path: String,
+    #[serde(skip)]
+    pub base: Option<String>,
+    #[serde(skip)]
+    pub ours: Option<String>,
+    #[serde(skip)]
+    pub theirs: Option<String>,
+}
+
+/// The conflicts, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct ConflictInput {
+    pub state: RepoState,
+    pub files: Vec<ConflictedFile>,
+}
+
+/// Read the conflicts in the index of `repo`, or only the one in `path`
+/// (relative to the repository root).
+pub fn prepare(repo: &Repository, path: Option<&str>) -> Result<ConflictInput> {
+    let state = state::collect(repo)?;
+    let version = |entry: Option<IndexEntry>| -> Result<Option<String>> {
+        let Some(entry) = entry else { return Ok(None) };
+        let blob = repo.find_blob(entry.id)?;
+        let text = match blob.is_binary() {
+            true => format!("(binary, {} bytes)", blob.size()),
+            false => chunk::truncate_to_tokens(&git::escape_non_utf8(blob.content()), VERSION_TOKENS).0,
+        };
+        Ok(Some(text))
+    };
+    let mut files = Vec::new();
+    for conflict in repo.index()?.conflicts()? {
+        let conflict = conflict?;
+        let entry = [&conflict.our, &conflict.their, &conflict.ancestor].into_iter().flatten().next();
+        let Some(entry) = entry else { continue };
+        let conflicted = git::escape_non_utf8(&entry.path);
+        if path.is_some_and(|path| path != conflicted) {
+            continue;
+        }
+        files.push(ConflictedFile {
+            path: conflicted,
+            base: version(conflict.ancestor)?,
+            ours: version(conflict.our)?,
+            theirs: version(conflict.their)?,
+        });
+    }
+    if files.is_empty() {
+        return Err(WtfError::NoConflicts);
+    }
+    progress!("{}", tr!("progress-conflicts", count = files.len()));
+    Ok(ConflictInput { state, files })
+}
+
+impl ConflictInput {
+    // Which side is which, in the operation under way
+    fn sides(&self) -> (String, String) {
+        let incoming = self.state.incoming.as_deref().unwrap_or("unknown");
+        match self.state.operation {
+            Some(Operation::Merge) => {
+                ("the branch you are on (HEAD)".to_string(), format!("what is being merged in: {}", incoming))
+            }
+            Some(Operation::Rebase | Operation::ApplyMailbox) => {
+                let onto = self.state.progress.as_ref().and_then(|p| p.onto.as_deref()).unwrap_or("unknown");
+                (
+                    format!("the commits being rebased onto ({}), with your commits replayed so far", onto),
+                    "your commit being replayed".to_string(),
+                )
+            }
+            Some(Operation::CherryPick) => {
+                ("HEAD".to_string(), format!("the commit being cherry-picked: {}", incoming))
+            }
+            Some(Operation::Revert) => ("HEAD".to_string(), format!("undoing the commit {}", incoming)),
+            _ => ("HEAD".to_string(), "the changes being applied, like a stash".to_string()),
+        }
+    }
+
+    fn question(&self, file: &ConflictedFile, propose: bool) -> String {
+        let (ours, theirs) = self.sides();
+        let operation = self.state.operation.map_or("none (like after git stash pop)", |operation| operation.name());
+        let mut out = CONFLICT_PROMPT.to_string();
+        if propose {
+            out.push(' ');
+            out.push_str(PROPOSE_INSTRUCTIONS);
+        }
+        out.push_str(&format!("\n\nFile: {}\nIn progress: git {}\n", file.path, operation));
+        out.push_str(&format!("Ours: {}\nTheirs: {}\n", ours, theirs));
+        for (name, version) in [("Base", &file.base), ("Ours", &file.ours), ("Theirs", &file.theirs)] {
+            match version {
+                Some(text) => out.push_str(&format!("\n--- {} ---\n{}\n", name, text)),
+                None => out.push_str(&format!("\n--- {} ---\n(the file does not exist here)\n", name)),
+            }
+        }
+        out
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self, propose: bool) -> RunEstimate {
+        RunEstimate {
+            requests: self.files.len(),
+            prompt_tokens: self
+                .files
+                .iter()
+                .map(|file| tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question(file, propose)))
+                .sum(),
+        }
+    }
+}
+
+/// The explanation of one conflicted file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileAdvice {
+    pub path: String,
+    pub explanation: String,
+}
+
+/// What the conflicts are about and how to resolve them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConflictReport {
+    pub operation: Option<Operation>,
+    pub incoming: Option<String>,
+    pub files: Vec<FileAdvice>,
+}
+
+/// Ask the model about each conflicted file, and with `propose` for a
+/// resolved version of each conflicting part.
+pub async fn explain(input: &ConflictInput, provider: &dyn LlmProvider, propose: bool) -> Result<ConflictReport> {
+    let mut files = Vec::new();
+    for file in &input.files {
+        let explanation = Conversation::new(SYSTEM_PROMPT).ask(provider, input.question(file, propose)).await?;
+        files.push(FileAdvice { path: file.path.clone(), explanation });
+    }
+    Ok(ConflictReport { operation: input.state.operation, incoming: input.state.incoming.clone(), files })
+}
+
+/// Format `report` for output.
+pub fn render(report: &ConflictReport, format: OutputFormat) -> String {
+    let mut facts = Vec::new();
+    if let Some(operation) = report.operation {
+        facts.push(tr!("status-operation", operation = operation.name()));
+    }
+    if let Some(incoming) = &report.incoming {
+        facts.push(tr!("status-incoming", commit = incoming.as_str()));
+    }
+    facts.push(tr!("conflict-files", count = report.files.len()));
+    let mut sections = vec![(tr!("conflict-title"), facts.iter().map(|fact| format!("- {}\n", fact)).collect())];
+    for file in &report.files {
+        sections.push((file.path.clone(), file.explanation.trim().to_string() + "\n"));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("conflicts serialize to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,61 @@
+//! Commit messages in the Conventional Commits format.
+//!
+//! A conventional header reads `type(scope)!: description`, where the scope
+//! and the `!` marking a breaking change are optional. A `BREAKING CHANGE:`
+//! footer marks a breaking change as well.
+
+// Messages Git writes itself, which are let through as they are
+const GIT_PREFIXES: &[&str] = &["Merge ", "Revert ", "fixup! ", "squash! ", "amend! "];
+
+/// The parts of a conventional commit message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// The type, lowercased: `feat`, `fix`, `docs` and so on.
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+/// Parse `message`, or `None` if its first line is not a conventional header.
+pub fn parse(message: &str) -> Option<Header> {
+    let mut lines = message.lines();
+    let first = lines.next()?.trim();
+    let (prefix, description) = first.split_once(':')?;
+    let description = description.strip_prefix(' ')?.trim();
+    if description.is_empty() {
+        return None;
+    }
+
+    let (prefix, bang) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => {
+            let scope = scope.strip_suffix(')')?;
+            if scope.is_empty() || scope.contains(['(', ')']) {
+                return None;
+            }
+            (kind, Some(scope.to_string()))
+        }
+        None => (prefix, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    let footer = lines.any(|line| line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"));
+    Some(Header {
+        kind: kind.to_ascii_lowercase(),
+        scope,
+        breaking: bang || footer,
+        description: description.to_string(),
+    })
+}
+
+/// Whether Git wrote `message` itself, like a merge, a revert or a
+/// `fixup!` commit, which no one is asked to write in the conventional format.
+pub fn written_by_git(message: &str) -> bool {
+    GIT_PREFIXES.iter().any(|prefix| message.starts_with(prefix))
+}
@@ -0,0 +1,79 @@
+//! Multi-turn conversations with a provider.
+
+use crate::error::Result;
+use crate::provider::{LlmProvider, Message};
+
+/// A running conversation: every question and answer is kept, so later
+/// requests can build on what the model said before.
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    messages: Vec<Message>,
+}
+
+impl Conversation {
+    /// Start a conversation with the given system prompt.
+    pub fn new(system_prompt: impl Into<String>) -> Self {
+        Conversation {
+            messages: vec![Message::system(system_prompt)],
+        }
+    }
+
+    /// Ask the next question and record the answer.
+    ///
+    /// If the request fails, the question is dropped again so the
+    /// conversation stays well-formed.
+    pub async fn ask(&mut self, provider: &dyn LlmProvider, question: impl Into<String>) -> Result<String> {
+        self.messages.push(Message::user(question));
+        let answer = provider.chat(&self.messages).await;
+        self.record(answer)
+    }
+
+    /// Like [`Conversation::ask`], handing the answer to `on_text` piece by
+    /// piece as it arrives.
+    pub async fn ask_streaming(
+        &mut self,
+        provider: &dyn LlmProvider,
+        question: impl Into<String>,
+        on_text: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String> {
+        self.messages.push(Message::user(question));
+        let answer = provider.stream(&self.messages, on_text).await;
+        self.record(answer)
+    }
+
+    // Keep the answer, or drop the question it failed to get one for
+    fn record(&mut self, answer: Result<String>) -> Result<String> {
+        match answer {
+            Ok(answer) => {
+                self.messages.push(Message::assistant(answer.clone()));
+                Ok(answer)
+            }
+            Err(e) => {
+                self.messages.pop();
+                Err(e)
+            }
+        }
+    }
+
+    /// How many tokens the messages so far take up, as `provider` counts.
+    pub fn tokens(&self, provider: &dyn LlmProvider) -> usize {
+        self.messages.iter().map(|message| provider.count_tokens(&message.content)).sum()
+    }
+
+    /// Drop the oldest questions and answers until the messages take up at
+    /// most `max_tokens`, as `provider` counts. The system prompt and the
+    /// latest answer are always kept. Returns how many questions were dropped.
+    pub fn trim(&mut self, provider: &dyn LlmProvider, max_tokens: usize) -> usize {
+        let mut dropped = 0;
+        while self.messages.len() > 3 && self.tokens(provider) > max_tokens {
+            self.messages.drain(1..3);
+            dropped += 1;
+        }
+        dropped
+    }
+
+    /// All messages exchanged so far, starting with the system prompt.
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+}
@@ -0,0 +1,80 @@
+//! What runs cost.
+//!
+//! Before a run, [`estimate`] turns its [`RunEstimate`] into dollars with
+//! the model's [`Price`]. While it runs, every provider [`record`]s the
+//! tokens each request used, as the API reported them (or estimated, where
+//! it reports none), so the real cost can be shown at the end.
+
+use crate::analysis::RunEstimate;
+use crate::provider::{Price, Usage};
+use crate::summarize::EXPECTED_SUMMARY_TOKENS;
+use crate::tr;
+use std::sync::Mutex;
+
+/// The tokens used and dollars spent so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Total {
+    pub requests: usize,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    /// Unknown once any request went to a model we have no price for.
+    pub cost: Option<f64>,
+}
+
+static TOTAL: Mutex<Total> = Mutex::new(Total {
+    requests: 0,
+    input_tokens: 0,
+    output_tokens: 0,
+    cost: Some(0.0),
+});
+
+/// Add one request that used `usage` of a model charging `price`.
+pub fn record(usage: Usage, price: Option<Price>) {
+    let mut total = TOTAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    total.requests += 1;
+    total.input_tokens += usage.input_tokens;
+    total.output_tokens += usage.output_tokens;
+    total.cost = total.cost.zip(price).map(|(cost, price)| cost + price.cost(usage));
+}
+
+/// Everything recorded so far.
+pub fn total() -> Total {
+    *TOTAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The dollars a run of `estimate` would cost with `price`, guessing each
+/// reply at about the length of a batch summary.
+pub fn estimate(estimate: &RunEstimate, price: Price) -> f64 {
+    price.cost(Usage {
+        input_tokens: estimate.prompt_tokens,
+        output_tokens: estimate.requests * EXPECTED_SUMMARY_TOKENS,
+    })
+}
+
+/// `dollars` for people: cents, or "< $0.01" for less.
+pub fn format_dollars(dollars: f64) -> String {
+    if dollars > 0.0 && dollars < 0.01 {
+        "< $0.01".to_string()
+    } else {
+        format!("${:.2}", dollars)
+    }
+}
+
+/// One line summing up `total`, for the end of a run.
+pub fn summary(total: &Total) -> String {
+    match total.cost {
+        Some(cost) => tr!(
+            "cost-summary",
+            requests = total.requests,
+            input = total.input_tokens,
+            output = total.output_tokens,
+            cost = format_dollars(cost)
+        ),
+        None => tr!(
+            "cost-summary-unpriced",
+            requests = total.requests,
+            input = total.input_tokens,
+            output = total.output_tokens
+        ),
+    }
+}
@@ -0,0 +1,235 @@
+//! Which dependencies a project declares, from its manifest files.
+//!
+//! Manifests are read with small line-based parsers rather than full TOML,
+//! Python or Ruby parsers: good enough to tell which packages were added and
+//! removed, not to resolve versions.
+
+use crate::error::Result;
+use crate::git;
+use git2::{Commit, Repository, Tree};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Dependencies a commit added and removed, by name (prefixed with the
+/// manifest's ecosystem for anything but Cargo, like `npm:react`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DependencyChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl DependencyChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Whether dependencies were both dropped and introduced, as when one
+    /// library replaces another.
+    pub fn is_migration(&self) -> bool {
+        !self.added.is_empty() && !self.removed.is_empty()
+    }
+}
+
+/// Whether `path` is a manifest these parsers understand.
+pub fn is_manifest(path: &str) -> bool {
+    ecosystem(path).is_some()
+}
+
+fn ecosystem(path: &str) -> Option<&'static str> {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    match file {
+        "Cargo.toml" => Some("cargo"),
+        "package.json" => Some("npm"),
+        "pyproject.toml" => Some("pypi"),
+        "go.mod" => Some("go"),
+        "Gemfile" => Some("gem"),
+        _ if file.starts_with("requirements") && file.ends_with(".txt") => Some("pypi"),
+        _ => None,
+    }
+}
+
+/// The dependency names declared by the manifest at `path` with `content`.
+/// Empty for files that are not manifests.
+pub fn parse(path: &str, content: &str) -> BTreeSet<String> {
+    let names = match ecosystem(path) {
+        Some("cargo") => cargo(content),
+        Some("npm") => npm(content),
+        Some("pypi") if path.ends_with(".txt") => requirements(content),
+        Some("pypi") => pyproject(content),
+        Some("go") => go_mod(content),
+        Some("gem") => gemfile(content),
+        _ => Vec::new(),
+    };
+    let prefix = match ecosystem(path) {
+        Some("cargo") | None => String::new(),
+        Some(name) => format!("{}:", name),
+    };
+    names.into_iter().map(|name| format!("{}{}", prefix, name)).collect()
+}
+
+fn cargo(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut in_dependencies = false;
+    for line in content.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.trim_end_matches(']').trim_matches(['[', ']']);
+            in_dependencies = is_dependency_table(header);
+            // `[dependencies.serde]` declares one dependency by itself
+            if let Some((parent, name)) = header.rsplit_once('.') {
+                if !in_dependencies && is_dependency_table(parent) {
+                    names.push(name.trim_matches('"').to_string());
+                }
+            }
+            continue;
+        }
+        if in_dependencies {
+            if let Some((key, _)) = line.split_once('=') {
+                let key = key.trim().trim_matches('"');
+                if !key.is_empty() && !key.starts_with('#') {
+                    names.push(key.to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
+fn is_dependency_table(header: &str) -> bool {
+    header == "dependencies"
+        || header == "dev-dependencies"
+        || header == "build-dependencies"
+        || header == "workspace.dependencies"
+        || (header.starts_with("target.") && header.ends_with("dependencies"))
+}
+
+fn npm(content: &str) -> Vec<String> {
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    ["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"]
+        .iter()
+        .filter_map(|section| manifest.get(*section).and_then(|deps| deps.as_object()))
+        .flat_map(|deps| deps.keys().cloned())
+        .collect()
+}
+
+// The package name at the start of a requirement like `requests[socks]>=2.0; python_version > "3"`
+fn requirement_name(spec: &str) -> Option<String> {
+    let name: String = spec
+        .trim()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    (!name.is_empty()).then(|| name.to_lowercase().replace('_', "-"))
+}
+
+fn requirements(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#') && !line.starts_with('-'))
+        .filter_map(requirement_name)
+        .collect()
+}
+
+fn pyproject(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut section = String::new();
+    let mut in_array = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') && !in_array {
+            section = line.trim_matches(['[', ']']).to_string();
+            continue;
+        }
+        if in_array {
+            in_array = !line.starts_with(']');
+            names.extend(quoted(line).and_then(requirement_name));
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let key = key.trim();
+        let value = value.trim();
+        // PEP 621: `dependencies = ["requests>=2", ...]`, possibly over several lines
+        if section == "project" && key == "dependencies" && value.starts_with('[') {
+            for item in value.trim_matches(['[', ']']).split(',') {
+                names.extend(quoted(item).and_then(requirement_name));
+            }
+            in_array = !value.ends_with(']');
+        } else if section.starts_with("tool.poetry") && section.ends_with("dependencies") && key != "python" {
+            names.extend(requirement_name(key.trim_matches('"')));
+        }
+    }
+    names
+}
+
+fn quoted(text: &str) -> Option<&str> {
+    let start = text.find(['"', '\''])?;
+    let rest = &text[start + 1..];
+    let end = rest.find(['"', '\''])?;
+    Some(&rest[..end])
+}
+
+fn go_mod(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut in_block = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with("require (") {
+            in_block = true;
+        } else if in_block && line.starts_with(')') {
+            in_block = false;
+        } else if let Some(spec) = line.strip_prefix("require ").or(in_block.then_some(line)) {
+            if let Some(module) = spec.split_whitespace().next().filter(|m| !m.starts_with("//")) {
+                names.push(module.to_string());
+            }
+        }
+    }
+    names
+}
+
+fn gemfile(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_prefix("gem "))
+        .filter_map(quoted)
+        .map(str::to_string)
+        .collect()
+}
+
+/// How the dependencies declared in all manifests changed from `commit`'s
+/// first parent (or nothing) to `commit`.
+pub fn changes(repo: &Repository, commit: &Commit<'_>) -> Result<DependencyChanges> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let tree = commit.tree()?;
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+    let (mut before, mut after) = (BTreeSet::new(), BTreeSet::new());
+    for delta in diff.deltas() {
+        let Some(path) = delta.new_file().path_bytes().or(delta.old_file().path_bytes()).map(git::escape_non_utf8) else {
+            continue;
+        };
+        if !is_manifest(&path) {
+            continue;
+        }
+        if let Some(old) = parent_tree.as_ref() {
+            before.extend(parse(&path, &read(repo, old, &path)));
+        }
+        after.extend(parse(&path, &read(repo, &tree, &path)));
+    }
+    Ok(DependencyChanges {
+        added: after.difference(&before).cloned().collect(),
+        removed: before.difference(&after).cloned().collect(),
+    })
+}
+
+fn read(repo: &Repository, tree: &Tree, path: &str) -> String {
+    tree.get_path(Path::new(path))
+        .and_then(|entry| entry.to_object(repo))
+        .and_then(|object| object.peel_to_blob())
+        .map(|blob| String::from_utf8_lossy(blob.content()).into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,262 @@
+//! `wtf digest --period week`: a dated summary of a week or month, for
+//! team newsletters.
+//!
+//! The commits HEAD gained in the period, merges left out, are counted
+//! without a model: how many there were, who made them (with the mailmap
+//! and `Co-authored-by` trailers, as in release notes) and how many files
+//! and lines they changed. One structured request reads their messages and
+//! changed files and picks out what is worth telling: a short summary, the
+//! notable features and the notable fixes.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::error::Result;
+use crate::git;
+use crate::provider::{LlmProvider, Message};
+use crate::release_notes::{self, Contributor};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use clap::ValueEnum;
+use git2::{Oid, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that writes a team newsletter from a project's Git history.";
+
+const DIGEST_PROMPT: &str = "Below are the commits a project gained over the {period} from {from} to {to}, with their messages and changed files. Write the project's digest for the period, for a team newsletter: a summary of two or three sentences on what the period brought overall; the notable features, each a sentence saying what can now be done; and the notable fixes, each a sentence saying what no longer goes wrong. Combine commits toward the same goal into one item, leave out routine upkeep like formatting and dependency bumps unless it matters to readers, and leave a list empty when there is nothing for it.";
+
+// The details of each commit are cut to this many tokens
+const COMMIT_TOKENS: usize = 300;
+
+// All commit details together are cut to this many tokens
+const DIGEST_COMMITS_TOKENS: usize = 12_000;
+
+/// How far back a digest goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    /// Today and the six days before.
+    Week,
+    /// Today and the 29 days before.
+    Month,
+}
+
+impl Period {
+    fn days(self) -> i64 {
+        match self {
+            Period::Week => 7,
+            Period::Month => 30,
+        }
+    }
+
+    /// The first and last day of the period ending on the day of `now`,
+    /// as seconds since the epoch of their midnight (UTC).
+    pub fn days_ending(self, now: i64) -> (i64, i64) {
+        let today = now.div_euclid(86_400) * 86_400;
+        (today - (self.days() - 1) * 86_400, today)
+    }
+}
+
+/// What the period's commits changed, counted without a model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    pub commits: usize,
+    /// Distinct paths changed.
+    pub files: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// The period's commits, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct DigestInput {
+    pub period: Period,
+    /// The first day, seconds since the epoch of its midnight (UTC).
+    pub from: i64,
+    /// The last day, likewise.
+    pub to: i64,
+    /// Newest first.
+    pub commits: Vec<Oid>,
+    /// Most commits first.
+    pub contributors: Vec<Contributor>,
+    pub stats: Stats,
+    // Commit details and files, for the prompt
+    details: String,
+}
+
+/// Gather the commits HEAD gained on the days from `from` to `to`, as
+/// [`Period::days_ending`] gives them.
+pub fn prepare(repo: &Repository, period: Period, (from, to): (i64, i64)) -> Result<DigestInput> {
+    let mut commits = Vec::new();
+    let mut paths: Vec<String> = Vec::new();
+    let mut stats = Stats::default();
+    let mut details = Vec::new();
+    // A repository without commits has an unborn HEAD
+    if let Some(head) = repo.head().ok().and_then(|head| head.target()) {
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(Sort::TIME)?;
+        walk.push(head)?;
+        for id in walk {
+            let commit = repo.find_commit(id?)?;
+            let time = commit.time().seconds();
+            if time < from {
+                break;
+            }
+            if time >= to + 86_400 || commit.parent_count() > 1 {
+                continue;
+            }
+            let parent = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), None)?;
+            let diff_stats = diff.stats()?;
+            stats.added += diff_stats.insertions();
+            stats.removed += diff_stats.deletions();
+            let changed = git::changed_paths(repo, &commit)?;
+            for path in &changed {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+            let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+            let entry =
+                format!("Short SHA: {}\nFiles: {}\n{}", short_id, changed.join(", "), git::get_commit_details(&commit));
+            details.push(chunk::truncate_to_tokens(&entry, COMMIT_TOKENS).0);
+            commits.push(commit.id());
+        }
+    }
+    stats.commits = commits.len();
+    stats.files = paths.len();
+    progress!("{}", tr!("progress-digest", count = commits.len()));
+    Ok(DigestInput {
+        period,
+        from,
+        to,
+        contributors: release_notes::contributors(repo, &commits)?,
+        commits,
+        stats,
+        details: details.join("\n\n---\n\n"),
+    })
+}
+
+impl DigestInput {
+    fn question(&self) -> String {
+        let period = match self.period {
+            Period::Week => "week",
+            Period::Month => "month",
+        };
+        let prompt = DIGEST_PROMPT
+            .replace("{period}", period)
+            .replace("{from}", &git::format_date(self.from))
+            .replace("{to}", &git::format_date(self.to));
+        let (details, _) = chunk::truncate_to_tokens(&self.details, DIGEST_COMMITS_TOKENS);
+        format!("{}\n\n{}", prompt, details)
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question()) }
+    }
+}
+
+/// The digest of a period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Digest {
+    pub period: Period,
+    /// The first day, as `YYYY-MM-DD`.
+    pub from: String,
+    /// The last day, as `YYYY-MM-DD`.
+    pub to: String,
+    pub summary: String,
+    pub features: Vec<String>,
+    pub fixes: Vec<String>,
+    pub contributors: Vec<Contributor>,
+    pub stats: Stats,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    summary: String,
+    features: Vec<String>,
+    fixes: Vec<String>,
+}
+
+/// Write the digest of `input` with one structured request.
+pub async fn explain(input: &DigestInput, provider: &dyn LlmProvider) -> Result<Digest> {
+    let messages = [Message::system(SYSTEM_PROMPT), Message::user(input.question())];
+    let list = json!({ "type": "array", "items": { "type": "string" } });
+    let schema = Schema::new(
+        "digest",
+        json!({
+            "type": "object",
+            "properties": { "summary": { "type": "string" }, "features": list, "fixes": list },
+            "required": ["summary", "features", "fixes"],
+            "additionalProperties": false,
+        }),
+    );
+    let reply: Reply = structured::request(provider, &messages, &schema).await?;
+    let trimmed = |items: Vec<String>| -> Vec<String> {
+        items.iter().map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+    };
+    Ok(Digest {
+        period: input.period,
+        from: git::format_date(input.from),
+        to: git::format_date(input.to),
+        summary: reply.summary.trim().to_string(),
+        features: trimmed(reply.features),
+        fixes: trimmed(reply.fixes),
+        contributors: input.contributors.clone(),
+        stats: input.stats,
+    })
+}
+
+/// Format `digest` for output; markdown is ready for a newsletter.
+pub fn render(digest: &Digest, format: OutputFormat) -> String {
+    let list = |items: &[String]| -> String { items.iter().map(|item| format!("- {}\n", item)).collect() };
+    let title = match digest.period {
+        Period::Week => tr!("digest-title-week", from = digest.from.as_str(), to = digest.to.as_str()),
+        Period::Month => tr!("digest-title-month", from = digest.from.as_str(), to = digest.to.as_str()),
+    };
+    let mut sections = vec![(title, digest.summary.clone() + "\n")];
+    if !digest.features.is_empty() {
+        sections.push((tr!("digest-features"), list(&digest.features)));
+    }
+    if !digest.fixes.is_empty() {
+        sections.push((tr!("digest-fixes"), list(&digest.fixes)));
+    }
+    let credits: String = digest
+        .contributors
+        .iter()
+        .map(|contributor| {
+            format!("- {}\n", tr!("notes-contributor", name = contributor.name.as_str(), count = contributor.commits))
+        })
+        .collect();
+    sections.push((tr!("notes-contributors"), credits));
+    let stats = tr!(
+        "digest-stats-line",
+        commits = digest.stats.commits,
+        contributors = digest.contributors.len(),
+        files = digest.stats.files,
+        added = digest.stats.added,
+        removed = digest.stats.removed
+    );
+    sections.push((tr!("digest-stats"), stats + "\n"));
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(digest).expect("digest serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,85 @@
+//! Dry runs: every prompt, and no requests.
+//!
+//! [`DryRun`] stands in for the provider. Instead of sending a request it
+//! shows what would have been sent, message by message, and answers with a
+//! placeholder so the run goes on to assemble the prompts that follow.
+//! Structured requests get the smallest reply their schema allows (see
+//! [`structured::example`]). Nothing touches the network, so a dry run also
+//! works without credentials, and in builds without the `online` feature.
+
+use crate::error::Result;
+use crate::provider::{BoxFuture, LlmProvider, Message};
+use crate::structured::{self, Schema};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// What a dry run answers in place of the model.
+pub const PLACEHOLDER_REPLY: &str = "(dry run: no request was sent)";
+
+/// A provider that shows each request instead of sending it.
+pub struct DryRun {
+    // Print to stdout if None
+    out_dir: Option<PathBuf>,
+    requests: AtomicUsize,
+}
+
+impl DryRun {
+    /// Print every request to stdout.
+    pub fn new() -> Self {
+        DryRun { out_dir: None, requests: AtomicUsize::new(0) }
+    }
+
+    /// Write every request to a file of its own in `out_dir` instead,
+    /// `request-001.txt` and onwards.
+    pub fn to_dir(out_dir: impl Into<PathBuf>) -> Self {
+        DryRun { out_dir: Some(out_dir.into()), requests: AtomicUsize::new(0) }
+    }
+
+    /// How many requests were shown so far.
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    fn show(&self, messages: &[Message], schema: Option<&Schema>) -> Result<()> {
+        let number = self.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut text = String::new();
+        for message in messages {
+            text.push_str(&format!("[{}]\n{}\n\n", message.role, message.content.trim_end()));
+        }
+        if let Some(schema) = schema {
+            // Pretty-printing a JSON value cannot fail
+            let pretty = serde_json::to_string_pretty(&schema.schema).expect("schema serializes to JSON");
+            text.push_str(&format!("[reply schema: {}]\n{}\n", schema.name, pretty));
+        }
+        match &self.out_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)?;
+                std::fs::write(dir.join(format!("request-{:03}.txt", number)), text)?;
+            }
+            None => println!("===== Request {} =====\n\n{}\n", number, text.trim_end()),
+        }
+        Ok(())
+    }
+}
+
+impl Default for DryRun {
+    fn default() -> Self {
+        DryRun::new()
+    }
+}
+
+impl LlmProvider for DryRun {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            self.show(messages, None)?;
+            Ok(PLACEHOLDER_REPLY.to_string())
+        })
+    }
+
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            self.show(messages, Some(schema))?;
+            Ok(structured::example(&schema.schema).to_string())
+        })
+    }
+}
@@ -0,0 +1,295 @@
+use crate::tr;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Process exit codes, so wrapper scripts can branch on the failure class.
+pub mod exit_code {
+    /// Anything not covered by a more specific code.
+    pub const FAILURE: u8 = 1;
+    /// The path is not a Git repository (or it has no commits).
+    pub const NOT_A_REPO: u8 = 2;
+    /// No API key was found, the provider rejected it, or the keyring that
+    /// should hold it could not be used.
+    pub const AUTH: u8 = 3;
+    /// The provider failed or returned something unusable.
+    pub const PROVIDER: u8 = 4;
+    /// The request exceeded the model's context window or a configured budget.
+    pub const BUDGET: u8 = 5;
+    /// Commit messages failed `wtf lint-commits`.
+    pub const LINT: u8 = 6;
+    /// Invalid command-line usage (sysexits' EX_USAGE).
+    pub const USAGE: u8 = 64;
+}
+
+/// Result type used throughout the analysis code.
+pub type Result<T, E = WtfError> = std::result::Result<T, E>;
+
+/// Errors that can occur while analyzing a repository.
+///
+/// Each variant knows how to describe itself and, where possible, what the
+/// user can do about it (see [`WtfError::hint`]).
+#[derive(Debug)]
+pub enum WtfError {
+    /// The given path is not (inside) a Git repository.
+    NotARepo { path: PathBuf, source: git2::Error },
+    /// The repository exists but has no commits to analyze.
+    NoCommits,
+    /// No commit to analyze changed anything under the `--path` pathspecs.
+    NoCommitsInPaths { paths: String },
+    /// No API key could be found for the provider.
+    MissingCredentials { var: &'static str },
+    /// The provider answered with a non-success status code.
+    ProviderError {
+        status: u16,
+        body: String,
+        /// How long the provider asked us to wait (its `Retry-After`).
+        retry_after: Option<std::time::Duration>,
+    },
+    /// The prompt did not fit into the model's context window.
+    ContextTooLarge { body: String },
+    /// The run would be larger than the configured safety cap.
+    SafetyCapExceeded { requests: usize, prompt_tokens: usize },
+    /// The run would cost more than `--max-cost` dollars.
+    CostCapExceeded { cost: f64, max_cost: f64 },
+    /// The provider answered, but not with anything we could use.
+    InvalidResponse(String),
+    /// A structured reply did not match the requested schema.
+    InvalidStructuredResponse { errors: Vec<String> },
+    /// Any other libgit2 failure.
+    Git(git2::Error),
+    /// Network or transport failure while talking to the provider.
+    #[cfg(feature = "online")]
+    Http(reqwest::Error),
+    /// Local I/O failure.
+    Io(std::io::Error),
+    /// No home or configuration directory could be determined.
+    NoConfigDir,
+    /// This build was made without the `online` feature.
+    NetworkDisabled,
+    /// An interactive command was run without a terminal.
+    NotInteractive,
+    /// No commit in the history touches the given path.
+    UnknownPath { path: String },
+    /// The file has fewer lines than asked for.
+    NoSuchLines { path: String, count: usize },
+    /// A revision (SHA, branch, tag or other revspec) names no commit.
+    UnknownRevision { rev: String },
+    /// No tag has the name given to `wtf tag`.
+    UnknownTag { name: String },
+    /// `wtf conflict` found no file with a conflict.
+    NoConflicts,
+    /// `git bisect`, driven by `wtf broke`, failed.
+    BisectFailed { output: String },
+    /// The editor for `wtf msg --edit` could not be started or failed.
+    EditorFailed { editor: String },
+    /// The commit message was left empty.
+    EmptyMessage,
+    /// Git has no user name or email to commit as.
+    NoIdentity,
+    /// A hook wtf did not install is in the way.
+    ForeignHook { path: PathBuf },
+    /// The commit-msg hook found a message that is not a Conventional
+    /// Commits one.
+    NotConventional { header: String },
+    /// `wtf lint-commits` found messages that break the Conventional
+    /// Commits specification.
+    LintFailed { count: usize },
+    /// `wtf branch` was not told what to compare against, and there is no
+    /// usual base branch.
+    NoBaseBranch,
+    /// There are no uncommitted changes (or, with `staged`, none staged).
+    NoChanges { staged: bool },
+    /// A configuration file is missing settings or cannot be understood.
+    InvalidConfig { path: PathBuf, reason: String },
+    /// No saved session has the given id.
+    UnknownSession { id: String },
+    /// A setting the chosen provider needs is not set.
+    MissingSetting { var: &'static str },
+    /// A provider setting, like `temperature` in `config.toml`, has a value
+    /// wtf cannot use.
+    InvalidSetting { key: String, value: String, reason: String },
+    /// `WTF_PROVIDER` names a provider wtf does not know.
+    UnknownProvider { name: String },
+    /// The system keyring could not be reached, or refused.
+    Keyring { reason: String },
+    /// A transient failure kept happening until retries ran out.
+    GaveUp { attempts: u32, last: Box<WtfError> },
+}
+
+impl WtfError {
+    /// A short remediation hint to print after the error message.
+    pub fn hint(&self) -> Option<String> {
+        let key = match self {
+            WtfError::NotARepo { .. } => "hint-not-a-repo",
+            WtfError::NoCommits => "hint-no-commits",
+            WtfError::NoCommitsInPaths { .. } => "hint-no-commits-in-paths",
+            WtfError::MissingCredentials { var } => return Some(tr!("hint-missing-credentials", var = var)),
+            WtfError::ProviderError { status: 401, .. } => "hint-unauthorized",
+            WtfError::ProviderError { status: 429, .. } => "hint-rate-limited",
+            WtfError::ProviderError { status, .. } if *status >= 500 => "hint-provider-down",
+            WtfError::ContextTooLarge { .. } => "hint-context-too-large",
+            WtfError::SafetyCapExceeded { .. } => "hint-safety-cap",
+            WtfError::CostCapExceeded { .. } => "hint-cost-cap",
+            #[cfg(feature = "online")]
+            WtfError::Http(_) => "hint-http",
+            WtfError::NetworkDisabled => "hint-network-disabled",
+            WtfError::NoConfigDir => "hint-no-config-dir",
+            WtfError::NotInteractive => "hint-not-interactive",
+            WtfError::UnknownPath { .. } => "hint-unknown-path",
+            WtfError::UnknownRevision { .. } => "hint-unknown-revision",
+            WtfError::UnknownTag { .. } => "hint-unknown-tag",
+            WtfError::NoChanges { staged: true } => "hint-no-staged-changes",
+            WtfError::BisectFailed { .. } => "hint-bisect-failed",
+            WtfError::EditorFailed { .. } => "hint-editor-failed",
+            WtfError::NoIdentity => "hint-no-identity",
+            WtfError::ForeignHook { .. } => "hint-foreign-hook",
+            WtfError::NotConventional { .. } => "hint-not-conventional",
+            WtfError::LintFailed { .. } => "hint-lint-failed",
+            WtfError::NoBaseBranch => "hint-no-base-branch",
+            WtfError::InvalidConfig { .. } | WtfError::InvalidSetting { .. } => "hint-invalid-config",
+            WtfError::UnknownSession { .. } => "hint-unknown-session",
+            WtfError::UnknownProvider { .. } => "hint-unknown-provider",
+            WtfError::Keyring { .. } => "hint-keyring",
+            WtfError::MissingSetting { var } => return Some(tr!("hint-missing-setting", var = var)),
+            WtfError::GaveUp { last, .. } => return last.hint(),
+            _ => return None,
+        };
+        Some(tr!(key))
+    }
+
+    /// The process exit code for this error, see [`exit_code`].
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            WtfError::NotARepo { .. } | WtfError::NoCommits | WtfError::NoCommitsInPaths { .. } => {
+                exit_code::NOT_A_REPO
+            }
+            WtfError::MissingCredentials { .. } | WtfError::Keyring { .. } => exit_code::AUTH,
+            WtfError::ProviderError { status: 401 | 403, .. } => exit_code::AUTH,
+            WtfError::ProviderError { .. }
+            | WtfError::InvalidResponse(_)
+            | WtfError::InvalidStructuredResponse { .. }
+            | WtfError::NetworkDisabled => exit_code::PROVIDER,
+            #[cfg(feature = "online")]
+            WtfError::Http(_) => exit_code::PROVIDER,
+            WtfError::ContextTooLarge { .. }
+            | WtfError::SafetyCapExceeded { .. }
+            | WtfError::CostCapExceeded { .. } => exit_code::BUDGET,
+            WtfError::NotInteractive
+            | WtfError::UnknownPath { .. }
+            | WtfError::NoSuchLines { .. }
+            | WtfError::UnknownRevision { .. }
+            | WtfError::UnknownTag { .. }
+            | WtfError::NoChanges { .. }
+            | WtfError::NoConflicts
+            | WtfError::BisectFailed { .. }
+            | WtfError::EditorFailed { .. }
+            | WtfError::EmptyMessage
+            | WtfError::NoIdentity
+            | WtfError::ForeignHook { .. }
+            | WtfError::NotConventional { .. }
+            | WtfError::NoBaseBranch
+            | WtfError::InvalidConfig { .. }
+            | WtfError::InvalidSetting { .. }
+            | WtfError::UnknownSession { .. }
+            | WtfError::UnknownProvider { .. }
+            | WtfError::MissingSetting { .. } => exit_code::USAGE,
+            WtfError::Git(_) | WtfError::Io(_) | WtfError::NoConfigDir => exit_code::FAILURE,
+            WtfError::LintFailed { .. } => exit_code::LINT,
+            WtfError::GaveUp { last, .. } => last.exit_code(),
+        }
+    }
+}
+
+impl fmt::Display for WtfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            WtfError::NotARepo { path, source } => {
+                tr!("error-not-a-repo", path = format!("{:?}", path), reason = source.message())
+            }
+            WtfError::NoCommits => tr!("error-no-commits"),
+            WtfError::NoCommitsInPaths { paths } => tr!("error-no-commits-in-paths", paths = paths),
+            WtfError::MissingCredentials { var } => tr!("error-missing-credentials", var = var),
+            WtfError::ProviderError { status, body, .. } => tr!("error-provider", status = status, body = body),
+            WtfError::ContextTooLarge { body } => tr!("error-context-too-large", body = body),
+            WtfError::SafetyCapExceeded { requests, prompt_tokens } => {
+                tr!("error-safety-cap", requests = requests, tokens = prompt_tokens)
+            }
+            WtfError::CostCapExceeded { cost, max_cost } => tr!(
+                "error-cost-cap",
+                cost = crate::cost::format_dollars(*cost),
+                max = crate::cost::format_dollars(*max_cost)
+            ),
+            WtfError::InvalidResponse(msg) => tr!("error-invalid-response", reason = msg),
+            WtfError::InvalidStructuredResponse { errors } => {
+                tr!("error-invalid-structured", errors = errors.join("; "))
+            }
+            WtfError::Git(e) => tr!("error-git", reason = e.message()),
+            #[cfg(feature = "online")]
+            WtfError::Http(e) => tr!("error-http", reason = e),
+            WtfError::Io(e) => tr!("error-io", reason = e),
+            WtfError::NoConfigDir => tr!("error-no-config-dir"),
+            WtfError::NetworkDisabled => tr!("error-network-disabled"),
+            WtfError::NotInteractive => tr!("error-not-interactive"),
+            WtfError::UnknownPath { path } => tr!("error-unknown-path", path = path),
+            WtfError::NoSuchLines { path, count } => tr!("error-no-such-lines", path = path, count = count),
+            WtfError::UnknownRevision { rev } => tr!("error-unknown-revision", rev = rev),
+            WtfError::UnknownTag { name } => tr!("error-unknown-tag", name = name),
+            WtfError::NoChanges { staged: true } => tr!("error-no-staged-changes"),
+            WtfError::NoChanges { staged: false } => tr!("error-no-changes"),
+            WtfError::NoConflicts => tr!("error-no-conflicts"),
+            WtfError::BisectFailed { output } => tr!("error-bisect-failed", output = output),
+            WtfError::EditorFailed { editor } => tr!("error-editor-failed", editor = editor),
+            WtfError::EmptyMessage => tr!("error-empty-message"),
+            WtfError::NoIdentity => tr!("error-no-identity"),
+            WtfError::ForeignHook { path } => tr!("error-foreign-hook", path = path.display()),
+            WtfError::NotConventional { header } => tr!("error-not-conventional", header = header),
+            WtfError::LintFailed { count } => tr!("error-lint-failed", count = count),
+            WtfError::NoBaseBranch => tr!("error-no-base-branch"),
+            WtfError::InvalidConfig { path, reason } => {
+                tr!("error-invalid-config", path = path.display(), reason = reason)
+            }
+            WtfError::UnknownSession { id } => tr!("error-unknown-session", id = id),
+            WtfError::UnknownProvider { name } => tr!("error-unknown-provider", name = name),
+            WtfError::MissingSetting { var } => tr!("error-missing-setting", var = var),
+            WtfError::InvalidSetting { key, value, reason } => {
+                tr!("error-invalid-setting", key = key, value = value, reason = reason)
+            }
+            WtfError::Keyring { reason } => tr!("error-keyring", reason = reason),
+            WtfError::GaveUp { attempts, last } => tr!("error-gave-up", attempts = attempts, error = last.to_string()),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl std::error::Error for WtfError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WtfError::NotARepo { source, .. } => Some(source),
+            WtfError::Git(e) => Some(e),
+            #[cfg(feature = "online")]
+            WtfError::Http(e) => Some(e),
+            WtfError::Io(e) => Some(e),
+            WtfError::GaveUp { last, .. } => Some(last.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<git2::Error> for WtfError {
+    fn from(e: git2::Error) -> Self {
+        WtfError::Git(e)
+    }
+}
+
+#[cfg(feature = "online")]
+impl From<reqwest::Error> for WtfError {
+    fn from(e: reqwest::Error) -> Self {
+        WtfError::Http(e)
+    }
+}
+
+impl From<std::io::Error> for WtfError {
+    fn from(e: std::io::Error) -> Self {
+        WtfError::Io(e)
+    }
+}
@@ -0,0 +1,236 @@
+//! Recognizing generated and vendored files.
+//!
+//! Lockfiles, vendored dependencies and minified bundles change in large
+//! blocks nobody wrote by hand. Their diffs would use up most of the prompt
+//! while telling the model little, so their contents are left out and they
+//! are summarized as "generated/vendored churn" instead: which files, and
+//! how many lines changed.
+//!
+//! Besides recognizing such files by name, the `linguist-generated` and
+//! `linguist-vendored` attributes GitHub uses are honored in both
+//! directions: `-linguist-vendored` on `vendor/` keeps it in the analysis.
+//! Binary files and paths matching an `--exclude` glob are left out the
+//! same way.
+
+use crate::attributes::{self, AttrValue, Rules};
+use crate::chunk;
+use crate::error::Result;
+use crate::git;
+use git2::{Commit, Diff, Repository};
+use serde::Serialize;
+
+// Lockfiles of the common package managers, by file name
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "composer.lock",
+    "Gemfile.lock",
+    "Pipfile.lock",
+    "poetry.lock",
+    "uv.lock",
+    "go.sum",
+    "flake.lock",
+    "mix.lock",
+    "pubspec.lock",
+    "Podfile.lock",
+    "packages.lock.json",
+];
+
+// Directories holding other people's code, at any depth
+const VENDOR_DIRS: &[&str] = &["vendor", "node_modules", "third_party", "bower_components"];
+
+// Suffixes of minified or bundled files
+const MINIFIED_SUFFIXES: &[&str] = &[".min.js", ".min.css", ".min.mjs", ".bundle.js", ".js.map", ".css.map"];
+
+/// Why a file counts as generated or vendored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GeneratedKind {
+    Lockfile,
+    Vendored,
+    Minified,
+    /// Marked `linguist-generated`.
+    Generated,
+    /// Content Git does not diff as text.
+    Binary,
+    /// Matched by an `--exclude` glob.
+    Excluded,
+}
+
+impl GeneratedKind {
+    /// The kind as it appears in JSON and prompts.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GeneratedKind::Lockfile => "lockfile",
+            GeneratedKind::Vendored => "vendored",
+            GeneratedKind::Minified => "minified",
+            GeneratedKind::Generated => "generated",
+            GeneratedKind::Binary => "binary",
+            GeneratedKind::Excluded => "excluded",
+        }
+    }
+}
+
+/// What the file name alone says about `path`.
+pub fn kind_by_path(path: &str) -> Option<GeneratedKind> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    if LOCKFILES.contains(&name) {
+        return Some(GeneratedKind::Lockfile);
+    }
+    let mut dirs = path.split('/').rev().skip(1);
+    if dirs.any(|dir| VENDOR_DIRS.contains(&dir)) {
+        return Some(GeneratedKind::Vendored);
+    }
+    if MINIFIED_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+        return Some(GeneratedKind::Minified);
+    }
+    None
+}
+
+/// Whether `path` matches the `--exclude` glob `pattern`. A pattern without
+/// a slash matches the file name in any directory, as in `.gitignore`.
+pub fn is_excluded(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./");
+    if pattern.contains('/') {
+        attributes::glob_matches(pattern.trim_start_matches('/'), path)
+    } else {
+        attributes::glob_matches(pattern, path.rsplit('/').next().unwrap_or(path))
+    }
+}
+
+/// Decides which paths are generated or vendored, from their names and the
+/// repository's attributes.
+pub struct Detector<'a> {
+    rules: Rules<'a>,
+    exclude: Vec<String>,
+}
+
+impl<'a> Detector<'a> {
+    pub fn new(repo: &'a Repository) -> Detector<'a> {
+        Detector::with_exclude(repo, &[])
+    }
+
+    /// Like [`Detector::new`], also leaving out paths matching any of the
+    /// `exclude` globs.
+    pub fn with_exclude(repo: &'a Repository, exclude: &[String]) -> Detector<'a> {
+        Detector { rules: Rules::new(repo), exclude: exclude.to_vec() }
+    }
+
+    /// Why `path` is generated, vendored or excluded, or `None` if it is
+    /// none of these. Binary content is only known from the diff, see
+    /// [`churn`].
+    pub fn kind(&mut self, path: &str) -> Option<GeneratedKind> {
+        // What the user asked for outweighs any attribute
+        if self.exclude.iter().any(|pattern| is_excluded(pattern, path)) {
+            return Some(GeneratedKind::Excluded);
+        }
+        let mut generated = None;
+        let mut vendored = None;
+        for attribute in self.rules.attributes(path) {
+            let set = match attribute.value {
+                AttrValue::Flag(set) => set,
+                AttrValue::Value(value) => !matches!(value.as_str(), "false" | "0"),
+            };
+            match attribute.name.as_str() {
+                "linguist-generated" => generated = Some(set),
+                "linguist-vendored" => vendored = Some(set),
+                _ => {}
+            }
+        }
+        match (generated, vendored, kind_by_path(path)) {
+            (Some(true), _, _) => Some(GeneratedKind::Generated),
+            (_, Some(true), _) => Some(GeneratedKind::Vendored),
+            (_, Some(false), Some(GeneratedKind::Vendored)) => None,
+            (Some(false), _, Some(GeneratedKind::Lockfile | GeneratedKind::Minified)) => None,
+            (_, _, kind) => kind,
+        }
+    }
+}
+
+/// A generated or vendored file and how much it changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChurnFile {
+    pub path: String,
+    pub kind: GeneratedKind,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// The generated, vendored, excluded and binary files `commit` changed
+/// relative to its first parent under the pathspecs in `paths` (or
+/// anywhere, without any), in diff order.
+pub fn churn(repo: &Repository, commit: &Commit, paths: &[String], detector: &mut Detector) -> Result<Vec<ChurnFile>> {
+    diff_churn(&git::commit_diff(repo, commit, paths)?, detector)
+}
+
+/// The generated, vendored, excluded and binary files in `diff`, in diff
+/// order.
+pub fn diff_churn(diff: &Diff<'_>, detector: &mut Detector) -> Result<Vec<ChurnFile>> {
+    let mut files = Vec::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        let file = if delta.new_file().path_bytes().is_some() { delta.new_file() } else { delta.old_file() };
+        let Some(path) = file.path_bytes().map(git::escape_non_utf8) else { continue };
+        let patch = git2::Patch::from_diff(diff, idx)?;
+        // Loading the patch is what tells binary content apart
+        let binary = patch.as_ref().map_or(delta.flags(), |patch| patch.delta().flags()).is_binary();
+        let kind = match detector.kind(&path) {
+            Some(kind) => kind,
+            None if binary => GeneratedKind::Binary,
+            None => continue,
+        };
+        let (added, removed) = match patch {
+            Some(patch) => {
+                let (_, added, removed) = patch.line_stats()?;
+                (added, removed)
+            }
+            None => (0, 0),
+        };
+        files.push(ChurnFile { path, kind, added, removed });
+    }
+    Ok(files)
+}
+
+/// Add `more` to `total`, adding up the lines of files already in it.
+pub fn merge(total: &mut Vec<ChurnFile>, more: Vec<ChurnFile>) {
+    for file in more {
+        match total.iter_mut().find(|known| known.path == file.path) {
+            Some(known) => {
+                known.added += file.added;
+                known.removed += file.removed;
+            }
+            None => total.push(file),
+        }
+    }
+}
+
+/// `patch` without the file sections of `excluded` paths.
+pub fn strip_patch(patch: &str, excluded: &[ChurnFile]) -> String {
+    let mut out = String::new();
+    for file in chunk::split_files(patch) {
+        if file.path().is_some_and(|path| excluded.iter().any(|churn| churn.path == path)) {
+            continue;
+        }
+        out.push_str(file.header);
+        out.push_str(&file.hunks.concat());
+    }
+    out
+}
+
+/// The churn as told to the model, one line per file.
+pub fn prompt_summary(files: &[ChurnFile]) -> String {
+    let mut summary = String::from("Generated/vendored churn (contents left out):\n");
+    for file in files {
+        summary.push_str(&format!(
+            "- {} ({}, +{} -{} lines)\n",
+            file.path,
+            file.kind.as_str(),
+            file.added,
+            file.removed
+        ));
+    }
+    summary
+}
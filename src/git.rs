@@ -0,0 +1,465 @@
+use crate::error::{Result, WtfError};
+use crate::platform;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use git2::{Commit, Diff, Oid, Repository};
+use serde::Serialize;
+use std::path::Path;
+
+/// Open the repository at `path`, mapping failures to [`WtfError::NotARepo`].
+pub fn open_repository(path: &Path) -> Result<Repository> {
+    Repository::open(path).map_err(|source| WtfError::NotARepo {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Collect up to `limit` commits reachable from HEAD, newest first.
+///
+/// Also returns the total number of commits reachable from HEAD.
+pub fn select_commits(repo: &Repository, limit: usize) -> Result<(Vec<Commit<'_>>, usize)> {
+    // A repository without commits has an unborn HEAD
+    if repo.head().is_err() {
+        return Err(WtfError::NoCommits);
+    }
+
+    // Count available commits
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    let commit_count = revwalk.count();
+
+    // Walk again from the beginning to collect the commits themselves
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk.take(limit) {
+        commits.push(repo.find_commit(oid?)?);
+    }
+
+    Ok((commits, commit_count))
+}
+
+/// Like [`select_commits`], counting and collecting only the commits that
+/// change something under the pathspecs in `paths`, like `git log -- <paths>`.
+/// Without any, every commit counts.
+pub fn select_commits_in<'r>(
+    repo: &'r Repository,
+    limit: usize,
+    paths: &[String],
+) -> Result<(Vec<Commit<'r>>, usize)> {
+    if paths.is_empty() {
+        return select_commits(repo, limit);
+    }
+    if repo.head().is_err() {
+        return Err(WtfError::NoCommits);
+    }
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    let mut commits = Vec::new();
+    let mut commit_count = 0;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if !touches(repo, &commit, paths)? {
+            continue;
+        }
+        commit_count += 1;
+        if commits.len() < limit {
+            commits.push(commit);
+        }
+    }
+    Ok((commits, commit_count))
+}
+
+/// Whether `commit` changes anything under the pathspecs in `paths`
+/// relative to its first parent. Without any, every commit does.
+pub fn touches(repo: &Repository, commit: &Commit, paths: &[String]) -> Result<bool> {
+    Ok(paths.is_empty() || commit_diff(repo, commit, paths)?.deltas().len() > 0)
+}
+
+/// The diff `commit` introduced relative to its first parent, or for a
+/// root commit against an empty tree, limited to the pathspecs in `paths`
+/// unless there are none. Pathspecs are relative to the repository root, so
+/// `src/network/` covers everything below it.
+pub fn commit_diff<'r>(repo: &'r Repository, commit: &Commit, paths: &[String]) -> Result<Diff<'r>> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    Ok(repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut pathspec_options(paths)))?)
+}
+
+/// Diff options that limit a diff to the pathspecs in `paths`, or leave it
+/// whole without any.
+pub fn pathspec_options(paths: &[String]) -> git2::DiffOptions {
+    let mut options = git2::DiffOptions::new();
+    for path in paths {
+        options.pathspec(path);
+    }
+    options
+}
+
+/// The commit `rev` names: a SHA, a branch, a tag or any other revspec Git
+/// understands, like `HEAD~2`.
+pub fn resolve_commit<'r>(repo: &'r Repository, rev: &str) -> Result<Commit<'r>> {
+    let unknown = |e: git2::Error| match e.code() {
+        git2::ErrorCode::NotFound | git2::ErrorCode::InvalidSpec | git2::ErrorCode::Ambiguous => {
+            WtfError::UnknownRevision { rev: rev.to_string() }
+        }
+        _ => WtfError::Git(e),
+    };
+    // A tree or blob is no commit either
+    repo.revparse_single(rev).and_then(|object| object.peel_to_commit()).map_err(unknown)
+}
+
+/// A revision range like `v1.2.0..HEAD`: the commits reachable from `to`
+/// but not from `from`. A symmetric range, `A...B`, has the commits on
+/// either side since the two diverged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RevRange {
+    pub from: String,
+    pub to: String,
+    pub symmetric: bool,
+}
+
+impl RevRange {
+    /// Split `spec` at `..` or `...`. A side left empty is HEAD, as in Git,
+    /// and a single revision stands for everything since it, `rev..HEAD`.
+    pub fn parse(spec: &str) -> RevRange {
+        let (from, to, symmetric) = match spec.split_once("...") {
+            Some((from, to)) => (from, to, true),
+            None => match spec.split_once("..") {
+                Some((from, to)) => (from, to, false),
+                None => (spec, "", false),
+            },
+        };
+        let or_head = |rev: &str| if rev.is_empty() { "HEAD".to_string() } else { rev.to_string() };
+        RevRange { from: or_head(from), to: or_head(to), symmetric }
+    }
+}
+
+impl std::fmt::Display for RevRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dots = if self.symmetric { "..." } else { ".." };
+        write!(f, "{}{}{}", self.from, dots, self.to)
+    }
+}
+
+/// The commits in `range`, newest first.
+pub fn range_commits(repo: &Repository, range: &RevRange) -> Result<Vec<Oid>> {
+    // Resolving the ends first tells which of them is unknown
+    let from = resolve_commit(repo, &range.from)?.id();
+    let to = resolve_commit(repo, &range.to)?.id();
+    let mut revwalk = repo.revwalk()?;
+    if range.symmetric {
+        revwalk.push(from)?;
+        revwalk.push(to)?;
+        match repo.merge_bases(from, to) {
+            Ok(bases) => {
+                for base in bases.iter() {
+                    revwalk.hide(*base)?;
+                }
+            }
+            // Unrelated histories have no common commits to leave out
+            Err(e) if e.code() == git2::ErrorCode::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    } else {
+        revwalk.push_range(&format!("{}..{}", from, to))?;
+    }
+    Ok(revwalk.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+/// The branch others branch off from: the one `origin/HEAD` points at, or
+/// else a local `main`, `master`, `trunk` or `develop`.
+pub fn default_base(repo: &Repository) -> Option<String> {
+    if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
+        if let Some(target) = reference.symbolic_target() {
+            return target.strip_prefix("refs/remotes/").map(str::to_string);
+        }
+    }
+    ["main", "master", "trunk", "develop"]
+        .into_iter()
+        .find(|name| repo.find_branch(name, git2::BranchType::Local).is_ok())
+        .map(str::to_string)
+}
+
+/// Format the metadata of a commit for use in a prompt.
+pub fn get_commit_details(commit: &Commit) -> String {
+    let author = commit.author();
+    let encoding = commit.message_encoding();
+    let message = commit_message(commit);
+    let time = commit.time();
+    let datetime = time.seconds();
+
+    format!(
+        "Commit: {}\nAuthor: {}\nDate: {}\nMessage: {}",
+        commit.id(),
+        decode_text(author.name_bytes(), encoding),
+        datetime,
+        message
+    )
+}
+
+/// The message of a commit, decoded according to its `encoding` header.
+pub fn commit_message(commit: &Commit) -> String {
+    let message = platform::normalize_newlines(&decode_text(commit.message_raw_bytes(), commit.message_encoding()));
+    if message.is_empty() {
+        "No commit message".to_string()
+    } else {
+        message
+    }
+}
+
+/// Decode commit text (message or signature) to UTF-8.
+///
+/// `encoding` is the commit's `encoding` header, if any. Without one, git
+/// assumes UTF-8; older tools wrote latin-1 without saying so, so bytes that
+/// are not valid UTF-8 are read as Windows-1252 rather than replaced.
+pub fn decode_text(bytes: &[u8], encoding: Option<&str>) -> String {
+    let encoding = encoding
+        .and_then(|label| Encoding::for_label(label.trim().as_bytes()))
+        .unwrap_or(UTF_8);
+    if encoding == UTF_8 {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return text.to_string();
+        }
+        return WINDOWS_1252.decode_without_bom_handling(bytes).0.into_owned();
+    }
+    encoding.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+/// Render bytes of unknown encoding (paths, diff lines) as text without
+/// losing information: valid UTF-8 is kept, other bytes become `\xNN`.
+pub fn escape_non_utf8(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        out.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            out.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    out
+}
+
+/// Render the patch a commit introduced relative to its first parent.
+///
+/// Returns `None` for root commits, which have nothing to compare against.
+pub fn commit_patch(repo: &Repository, commit: &Commit) -> Result<Option<String>> {
+    let parent = match commit.parent(0) {
+        Ok(parent) => parent,
+        Err(_) => return Ok(None),
+    };
+
+    let diff = repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None)?;
+    diff_patch(&diff).map(Some)
+}
+
+/// Render `diff` as a patch.
+pub fn diff_patch(diff: &Diff<'_>) -> Result<String> {
+    let mut patch = String::new();
+    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        // Only header lines carry their own prefix
+        if let origin @ ('+' | '-' | ' ') = line.origin() {
+            patch.push(origin);
+        }
+        // Files with CRLF line endings would otherwise end every line with '\r'
+        let content = escape_non_utf8(line.content());
+        match content.strip_suffix("\r\n") {
+            Some(stripped) => {
+                patch.push_str(stripped);
+                patch.push('\n');
+            }
+            None => patch.push_str(&content),
+        }
+        true
+    })?;
+    Ok(patch)
+}
+
+/// Which uncommitted changes to look at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Uncommitted {
+    /// The index against HEAD: what `git commit` would commit.
+    Staged,
+    /// The work tree against the index: what `git add` has not seen yet.
+    Unstaged,
+    /// The work tree against HEAD, staged or not.
+    All,
+}
+
+/// The uncommitted changes `which` selects. Untracked files are left out,
+/// like in `git diff`. Before the first commit, HEAD counts as empty.
+pub fn uncommitted_diff(repo: &Repository, which: Uncommitted) -> Result<Diff<'_>> {
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch || e.code() == git2::ErrorCode::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let diff = match which {
+        Uncommitted::Staged => repo.diff_tree_to_index(head_tree.as_ref(), None, None)?,
+        Uncommitted::Unstaged => repo.diff_index_to_workdir(None, None)?,
+        Uncommitted::All => repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), None)?,
+    };
+    Ok(diff)
+}
+
+/// The paths a commit changed relative to its first parent (or, for a
+/// root commit, every path it adds). Renamed files are listed under both
+/// names.
+pub fn changed_paths(repo: &Repository, commit: &Commit) -> Result<Vec<String>> {
+    Ok(diff_paths(&commit_diff(repo, commit, &[])?))
+}
+
+/// The paths `diff` changes, with renamed files under both names.
+pub fn diff_paths(diff: &Diff<'_>) -> Vec<String> {
+    let mut paths = Vec::new();
+    for delta in diff.deltas() {
+        for file in [delta.old_file(), delta.new_file()] {
+            if let Some(path) = file.path_bytes().map(escape_non_utf8) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// Every file path in the tree HEAD points at.
+///
+/// Path components that are not valid UTF-8 are escaped with
+/// [`escape_non_utf8`], matching how they appear in patches.
+pub fn head_tree_paths(repo: &Repository) -> Result<Vec<String>> {
+    let tree = repo.head()?.peel_to_tree()?;
+    let mut paths = Vec::new();
+    // `Tree::walk` hands directory names to the callback as `&str` and
+    // aborts on ones that are not UTF-8, so recurse by hand instead
+    collect_paths(repo, &tree, "", &mut paths)?;
+    Ok(paths)
+}
+
+fn collect_paths(repo: &Repository, tree: &git2::Tree, prefix: &str, paths: &mut Vec<String>) -> Result<()> {
+    for entry in tree.iter() {
+        let path = format!("{}{}", prefix, escape_non_utf8(entry.name_bytes()));
+        match entry.kind() {
+            Some(git2::ObjectType::Blob) => paths.push(path),
+            Some(git2::ObjectType::Tree) => {
+                let subtree = repo.find_tree(entry.id())?;
+                collect_paths(repo, &subtree, &format!("{}/", path), paths)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The web URL commits of this repository can be linked under, derived
+/// from the `origin` remote (e.g. `https://github.com/owner/repo/commit`).
+pub fn commit_url_base(repo: &Repository) -> Option<String> {
+    let remote = repo.find_remote("origin").ok()?;
+    web_url_for_remote(remote.url()?)
+}
+
+fn web_url_for_remote(url: &str) -> Option<String> {
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+    let (host, path) = if let Some(rest) = url.strip_prefix("git@") {
+        rest.split_once(':')?
+    } else {
+        let rest = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .or_else(|| url.strip_prefix("ssh://git@"))?;
+        let rest = rest.rsplit_once('@').map_or(rest, |(_, host)| host);
+        rest.split_once('/')?
+    };
+
+    let commit_path = if host.contains("gitlab") {
+        "-/commit"
+    } else if host.contains("bitbucket") {
+        "commits"
+    } else {
+        "commit"
+    };
+    Some(format!("https://{}/{}/{}", host, path, commit_path))
+}
+
+/// A path as it appears in the repository's trees: relative to the work
+/// tree root, with `/` separators.
+///
+/// Paths that exist relative to the current directory are resolved first,
+/// so `wtf timeline main.rs` works from inside `src/`.
+pub fn repo_relative_path(repo: &Repository, path: &Path) -> String {
+    let resolved = match (repo.workdir(), path.canonicalize()) {
+        (Some(workdir), Ok(absolute)) => workdir
+            .canonicalize()
+            .ok()
+            .and_then(|workdir| absolute.strip_prefix(workdir).ok().map(Path::to_path_buf)),
+        _ => None,
+    };
+    let path = resolved.as_deref().unwrap_or(path);
+    let path = path.to_string_lossy().replace('\\', "/");
+    path.trim_start_matches("./").trim_end_matches('/').to_string()
+}
+
+/// A commit timestamp (seconds since the epoch) as a UTC `YYYY-MM-DD` date.
+pub fn format_date(seconds: i64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let days = seconds.div_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The start of a `YYYY-MM-DD` date (UTC) as seconds since the epoch, the
+/// inverse of [`format_date`]. `None` for anything else, including dates
+/// that do not exist, like `2023-02-30`.
+pub fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let (year, month, day): (i64, i64, i64) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Howard Hinnant's days-from-civil algorithm
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let seconds = (era * 146_097 + doe - 719_468) * 86_400;
+    // A day past the end of its month comes back as another date
+    (format_date(seconds) == date).then_some(seconds)
+}
+
+// Helper trait to find files in a repository
+pub trait RepositoryExt {
+    fn find_file(&self, path: &str) -> Result<String>;
+}
+
+impl RepositoryExt for Repository {
+    fn find_file(&self, path: &str) -> Result<String> {
+        let head = self.head()?;
+        let tree = head.peel_to_tree()?;
+
+        let entry = tree.get_path(Path::new(path))?;
+        let object = entry.to_object(self)?;
+        let blob = object
+            .as_blob()
+            .ok_or_else(|| git2::Error::from_str("Not a blob"))?;
+
+        let content = platform::normalize_newlines(&String::from_utf8_lossy(blob.content()));
+        Ok(content)
+    }
+}
@@ -0,0 +1,188 @@
+//! Guarding against fabricated file paths and commit SHAs in model output.
+//!
+//! Models sometimes "cite" commits or files that do not exist. After a report
+//! is generated, every SHA-like and path-like token in it is checked against
+//! the data that was actually analyzed.
+
+use clap::ValueEnum;
+use std::collections::HashSet;
+
+/// Marker appended to references that could not be verified.
+pub const UNVERIFIED_MARKER: &str = " [unverified]";
+
+/// Replacement text for stripped references.
+pub const STRIPPED_MARKER: &str = "[unverified reference removed]";
+
+/// What to do with references that do not appear in the analyzed data.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GuardMode {
+    /// Leave the output untouched
+    Off,
+    /// Mark unverified references
+    #[default]
+    Flag,
+    /// Remove unverified references
+    Strip,
+}
+
+// File extensions that make a bare word look like a file name
+const KNOWN_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cfg", "cpp", "cs", "css", "go", "h", "hpp", "html", "ini", "java", "js", "json", "jsx", "kt", "lock",
+    "md", "php", "py", "rb", "rs", "scss", "sh", "sql", "swift", "toml", "ts", "tsx", "txt", "vue", "xml", "yaml",
+    "yml",
+];
+
+/// The commits and paths a report is allowed to mention.
+#[derive(Debug, Clone, Default)]
+pub struct KnownRefs {
+    shas: Vec<String>,
+    paths: HashSet<String>,
+}
+
+impl KnownRefs {
+    pub fn new() -> Self {
+        KnownRefs::default()
+    }
+
+    /// Allow a commit, given its full hex id.
+    pub fn add_sha(&mut self, sha: impl Into<String>) {
+        self.shas.push(sha.into().to_lowercase());
+    }
+
+    /// Allow a path relative to the repository root.
+    pub fn add_path(&mut self, path: impl Into<String>) {
+        self.paths.insert(path.into());
+    }
+
+    /// Allow every path mentioned in the headers of a unified diff.
+    pub fn add_patch_paths(&mut self, patch: &str) {
+        for line in patch.lines() {
+            let path = line
+                .strip_prefix("+++ b/")
+                .or_else(|| line.strip_prefix("--- a/"));
+            if let Some(path) = path {
+                self.add_path(path.trim_end());
+            }
+        }
+    }
+
+    /// Allow everything `other` allows.
+    pub fn merge(&mut self, other: KnownRefs) {
+        self.shas.extend(other.shas);
+        self.paths.extend(other.paths);
+    }
+
+    fn knows_sha(&self, candidate: &str) -> bool {
+        let candidate = candidate.to_lowercase();
+        self.shas.iter().any(|sha| sha.starts_with(&candidate))
+    }
+
+    fn knows_path(&self, candidate: &str) -> bool {
+        // Models answering for Windows users sometimes write `src\main.rs`
+        let candidate = candidate.replace('\\', "/");
+        let candidate = candidate.trim_start_matches("./");
+        if self.paths.contains(candidate) {
+            return true;
+        }
+        // Accept directories ("src/") and bare file names ("main.rs")
+        let dir = format!("{}/", candidate.trim_end_matches('/'));
+        let suffix = format!("/{}", candidate);
+        self.paths
+            .iter()
+            .any(|known| known.starts_with(&dir) || known.ends_with(&suffix))
+    }
+}
+
+/// Check `text` for unknown references and handle them according to `mode`.
+///
+/// Returns the (possibly rewritten) text and the unverified references found,
+/// each once, in the order they first appear.
+pub fn check(text: &str, known: &KnownRefs, mode: GuardMode) -> (String, Vec<String>) {
+    if mode == GuardMode::Off {
+        return (text.to_string(), Vec::new());
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut unverified = Vec::new();
+    let mut seen = HashSet::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (word, tail) = rest.split_at(word_end);
+        let space_end = tail.find(|c: char| !c.is_whitespace()).unwrap_or(tail.len());
+        let (space, next) = tail.split_at(space_end);
+
+        let (lead, core, trail) = trim_punctuation(word);
+        let suspicious = (looks_like_sha(core) && !known.knows_sha(core))
+            || (looks_like_path(core) && !known.knows_path(core));
+
+        if suspicious {
+            if seen.insert(core) {
+                unverified.push(core.to_string());
+            }
+            match mode {
+                GuardMode::Flag => {
+                    out.push_str(lead);
+                    out.push_str(core);
+                    out.push_str(UNVERIFIED_MARKER);
+                    out.push_str(trail);
+                }
+                GuardMode::Strip => {
+                    out.push_str(lead);
+                    out.push_str(STRIPPED_MARKER);
+                    out.push_str(trail);
+                }
+                GuardMode::Off => unreachable!(),
+            }
+        } else {
+            out.push_str(word);
+        }
+        out.push_str(space);
+        rest = next;
+    }
+
+    (out, unverified)
+}
+
+// Split surrounding quotes, brackets and sentence punctuation off a word
+fn trim_punctuation(word: &str) -> (&str, &str, &str) {
+    let is_wrapper = |c: char| "`'\"()[]{}<>*,;:!?".contains(c);
+    let start = word.len() - word.trim_start_matches(is_wrapper).len();
+    let core = &word[start..];
+    let core_trimmed = core.trim_end_matches(|c: char| is_wrapper(c) || c == '.');
+    let end = start + core_trimmed.len();
+    (&word[..start], &word[start..end], &word[end..])
+}
+
+/// Whether `word` looks like an abbreviated or full commit SHA.
+pub fn looks_like_sha(word: &str) -> bool {
+    (7..=40).contains(&word.len())
+        && word.chars().all(|c| c.is_ascii_hexdigit())
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// Whether `word` looks like a repository file path.
+pub fn looks_like_path(word: &str) -> bool {
+    if word.is_empty() || word.contains("://") || word.starts_with('@') || word.contains("..") {
+        return false;
+    }
+    if !word
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-./\\".contains(c))
+    {
+        return false;
+    }
+
+    let file_name = word.rsplit(['/', '\\']).next().unwrap_or(word);
+    let has_known_extension = match file_name.rsplit_once('.') {
+        Some((stem, ext)) => !stem.is_empty() && KNOWN_EXTENSIONS.contains(&ext.to_lowercase().as_str()),
+        None => false,
+    };
+
+    // Directories need a trailing slash, so "and/or" is not mistaken for one
+    let is_directory = word.len() > 1 && word.ends_with(['/', '\\']) && !word.starts_with(['/', '\\']);
+
+    has_known_extension || is_directory
+}
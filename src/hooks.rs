@@ -0,0 +1,159 @@
+//! `wtf hook`: Git hooks that call back into wtf.
+//!
+//! Each hook is a small shell script that runs this binary with `wtf hook
+//! run <hook>`. `prepare-commit-msg` fills in a message written by the
+//! model, `commit-msg` checks that the message is a Conventional Commits
+//! one, and `post-merge` summarizes what a pull brought in. The scripts
+//! carry a marker line, so only hooks wtf installed are ever replaced or
+//! removed without asking.
+//!
+//! Hooks fail open: if the binary is gone, the script does nothing, and the
+//! hooks that need a model skip themselves when there is no API key or the
+//! request fails, so a commit or pull never stops because of wtf.
+
+use crate::conventional;
+use crate::error::{Result, WtfError};
+use crate::platform;
+use crate::repo_config;
+use clap::ValueEnum;
+use git2::{Index, Repository};
+use std::path::{Path, PathBuf};
+
+/// The line that marks a hook script as installed by wtf.
+pub const MARKER: &str = "# Installed by wtf";
+
+/// A hook wtf can install.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    /// Fill in a commit message for the staged changes
+    PrepareCommitMsg,
+    /// Reject commit messages that are not Conventional Commits
+    CommitMsg,
+    /// Summarize what a merge or pull brought in
+    PostMerge,
+}
+
+impl Hook {
+    /// The hook's file name, as Git knows it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PrepareCommitMsg => "prepare-commit-msg",
+            Hook::CommitMsg => "commit-msg",
+            Hook::PostMerge => "post-merge",
+        }
+    }
+}
+
+// `text` quoted for a POSIX shell
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// The script for `hook`, calling `program` with the arguments Git gives it.
+pub fn script(hook: Hook, program: &Path) -> String {
+    // Git for Windows runs hooks in its bundled shell, which wants forward slashes
+    let program = program.display().to_string().replace('\\', "/");
+    format!(
+        "#!/bin/sh\n{marker}; `wtf hook uninstall {name}` removes it.\n\
+         # Nothing happens when wtf is gone, so Git never fails because of it.\n\
+         wtf={program}\n\
+         [ -x \"$wtf\" ] || exit 0\n\
+         exec \"$wtf\" hook run {name} \"$@\"\n",
+        marker = MARKER,
+        name = hook.name(),
+        program = shell_quote(&program),
+    )
+}
+
+/// Where Git looks for `hook` in `repo`.
+pub fn hook_path(repo: &Repository, hook: Hook) -> PathBuf {
+    repo_config::hooks_dir(repo).join(hook.name())
+}
+
+/// Whether the script at `path` was installed by wtf.
+pub fn is_installed(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|script| script.lines().any(|line| line.starts_with(MARKER)))
+}
+
+/// Install `hook` in `repo`, calling `program`, and return where it went.
+///
+/// Another hook of the same name is only replaced with `replace`; one wtf
+/// installed is always updated.
+pub fn install(repo: &Repository, hook: Hook, program: &Path, replace: bool) -> Result<PathBuf> {
+    let path = hook_path(repo, hook);
+    if path.exists() && !replace && !is_installed(&path) {
+        return Err(WtfError::ForeignHook { path });
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, script(hook, program))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(path)
+}
+
+/// Remove `hook` from `repo` if wtf installed it, and return where it was;
+/// `None` when it was not installed.
+pub fn uninstall(repo: &Repository, hook: Hook) -> Result<Option<PathBuf>> {
+    let path = hook_path(repo, hook);
+    if !path.exists() {
+        return Ok(None);
+    }
+    if !is_installed(&path) {
+        return Err(WtfError::ForeignHook { path });
+    }
+    std::fs::remove_file(&path)?;
+    Ok(Some(path))
+}
+
+/// Open the repository at `path` the way a hook sees it: with the index in
+/// `GIT_INDEX_FILE` when Git set one, like `git commit -a` does.
+pub fn open_repository(path: &Path) -> Result<Repository> {
+    let repo = crate::git::open_repository(path)?;
+    if let Some(file) = std::env::var_os("GIT_INDEX_FILE") {
+        let file = PathBuf::from(file);
+        let file = match file.is_absolute() {
+            true => file,
+            false => repo.workdir().unwrap_or(repo.path()).join(file),
+        };
+        repo.set_index(&mut Index::open(&file)?)?;
+    }
+    Ok(repo)
+}
+
+/// Whether prepare-commit-msg should write a message, given where Git says
+/// the message comes from: only when nothing else (`-m`, a template, a
+/// merge, an amend) provided one.
+pub fn should_prefill(source: Option<&str>) -> bool {
+    source.is_none_or(str::is_empty)
+}
+
+/// Put `message` at the top of the message file at `path`, above the help
+/// text Git wrote there.
+pub fn prefill(path: &Path, message: &str) -> Result<()> {
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    std::fs::write(path, format!("{}{}", message, existing))?;
+    Ok(())
+}
+
+/// Check that the commit message `message` is a Conventional Commits one.
+///
+/// Comment lines are left out as Git does. The messages Git writes itself,
+/// for merges, reverts and autosquash commits, are always accepted, and so
+/// is an empty message, which Git rejects on its own.
+pub fn lint(message: &str) -> Result<()> {
+    let message = platform::normalize_newlines(message);
+    let kept: Vec<&str> = message.lines().filter(|line| !line.starts_with('#')).collect();
+    let message = kept.join("\n").trim().to_string();
+    if message.is_empty() || conventional::written_by_git(&message) {
+        return Ok(());
+    }
+    match conventional::parse(&message) {
+        Some(_) => Ok(()),
+        None => Err(WtfError::NotConventional { header: message.lines().next().unwrap_or_default().to_string() }),
+    }
+}
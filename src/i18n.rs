@@ -0,0 +1,167 @@
+//! Translations of the tool's own messages.
+//!
+//! Progress output, errors, hints and report section headers are looked up
+//! in per-language catalogs under `locales/`, written in a subset of the
+//! Fluent syntax: `key = text` lines with `{ $name }` placeables, `#`
+//! comments and indented continuation lines. The language of the
+//! model-written text in a report is a separate setting.
+//!
+//! Use the [`tr!`](crate::tr) macro to look up a message:
+//!
+//! ```
+//! let heading = wtf::tr!("section-commits", count = 5);
+//! assert_eq!(heading, "Last 5 Commits in Plain Language");
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// The language every catalog falls back to.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+// Catalogs compiled into the binary, by language code
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+type Catalog = HashMap<String, String>;
+
+static PARSED: OnceLock<HashMap<&'static str, Catalog>> = OnceLock::new();
+static LANGUAGE: RwLock<&'static str> = RwLock::new(DEFAULT_LANGUAGE);
+
+/// Codes of the languages with a catalog.
+pub fn available_languages() -> Vec<&'static str> {
+    CATALOGS.iter().map(|(code, _)| *code).collect()
+}
+
+/// Use `lang` (e.g. `de`, `de-AT` or `de_DE.UTF-8`) for all messages.
+///
+/// Returns `false`, leaving the language unchanged, when there is no catalog
+/// for it.
+pub fn set_language(lang: &str) -> bool {
+    let wanted = language_code(lang);
+    match CATALOGS.iter().find(|(code, _)| *code == wanted) {
+        Some((code, _)) => {
+            *LANGUAGE.write().unwrap_or_else(|e| e.into_inner()) = code;
+            true
+        }
+        None => false,
+    }
+}
+
+/// The language currently used for messages.
+pub fn language() -> &'static str {
+    *LANGUAGE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The user's preferred message language from the environment.
+///
+/// Checks `WTF_UI_LANG`, then the POSIX locale variables in order of
+/// precedence. The `C` and `POSIX` locales count as no preference.
+pub fn language_from_env() -> Option<String> {
+    ["WTF_UI_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .filter(|value| !matches!(language_code(value).as_str(), "c" | "posix"))
+}
+
+// "de_DE.UTF-8" -> "de"
+fn language_code(lang: &str) -> String {
+    lang.split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Look up `key` in the current language and fill in `args`.
+///
+/// Falls back to English for messages a catalog lacks, and to the key itself
+/// for unknown keys, so a missing translation never hides a message.
+pub fn message(key: &str, args: &[(&str, String)]) -> String {
+    let catalogs = PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(code, source)| (*code, parse_catalog(source)))
+            .collect()
+    });
+    let template = catalogs
+        .get(language())
+        .and_then(|catalog| catalog.get(key))
+        .or_else(|| catalogs.get(DEFAULT_LANGUAGE).and_then(|catalog| catalog.get(key)));
+
+    match template {
+        Some(template) => format_message(template, args),
+        None => key.to_string(),
+    }
+}
+
+/// Parse a catalog in the supported Fluent subset.
+pub fn parse_catalog(source: &str) -> HashMap<String, String> {
+    let mut catalog = HashMap::new();
+    let mut current: Option<(String, String)> = None;
+
+    for line in source.lines() {
+        let continuation = line.starts_with([' ', '\t']) && !line.trim().is_empty();
+        if continuation {
+            if let Some((_, value)) = current.as_mut() {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+
+        if let Some((key, value)) = current.take() {
+            catalog.insert(key, value);
+        }
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            current = Some((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    if let Some((key, value)) = current {
+        catalog.insert(key, value);
+    }
+
+    catalog
+}
+
+// Replace `{ $name }` placeables; unknown ones are left as written
+fn format_message(template: &str, args: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let Some(close) = after.find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let name = after[..close].trim().trim_start_matches('$');
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[open..open + close + 2]),
+        }
+        rest = &after[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Look up a translated message, optionally with named arguments:
+/// `tr!("key")` or `tr!("key", count = n, path = p)`.
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::message($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message($key, &[$((stringify!($name), ($value).to_string())),+])
+    };
+}
@@ -0,0 +1,105 @@
+//! API keys in the system keyring.
+//!
+//! `wtf auth login` stores a provider's key in the keychain of the operating
+//! system, so it does not have to sit in plain text in a `.env` file inside
+//! a repository. The keyring is reached through the tool that comes with it:
+//! `security` on macOS, and `secret-tool` (from libsecret) for the Secret
+//! Service of GNOME Keyring or KWallet on Linux and the BSDs. The `keyring`
+//! crate, which would also reach the Windows Credential Manager, is not
+//! among wtf's dependencies, and Windows has no tool that reads secrets
+//! back, so there the keyring is not available.
+//!
+//! Keys are stored under the service `wtf`, with the name of the variable
+//! that would otherwise hold them, like `OPENAI_API_KEY`, as the account.
+
+use crate::error::{Result, WtfError};
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// The service wtf's keys are stored under.
+pub const SERVICE: &str = "wtf";
+
+/// Store `secret` as `account`, replacing any earlier one.
+///
+/// Secrets with line breaks are refused: no API key has one, and on macOS
+/// it would end the command that stores it and start another.
+pub fn store(account: &str, secret: &str) -> Result<()> {
+    if secret.contains(['\n', '\r']) {
+        return Err(WtfError::Keyring { reason: crate::tr!("keyring-line-break") });
+    }
+    if cfg!(target_os = "macos") {
+        // Commands read from stdin keep the key out of the process list
+        let command = format!("add-generic-password -U -s {} -a {} -w {}\n", SERVICE, quote(account), quote(secret));
+        check(run("security", &["-i"], Some(&command))?)
+    } else if cfg!(unix) {
+        let label = format!("{} {}", SERVICE, account);
+        let args = ["store", "--label", &label, "service", SERVICE, "account", account];
+        check(run("secret-tool", &args, Some(secret))?)
+    } else {
+        Err(unsupported())
+    }
+}
+
+/// The secret stored as `account`, if there is a keyring and it has one.
+pub fn get(account: &str) -> Option<String> {
+    let output = if cfg!(target_os = "macos") {
+        run("security", &["find-generic-password", "-s", SERVICE, "-a", account, "-w"], None).ok()?
+    } else if cfg!(unix) {
+        run("secret-tool", &["lookup", "service", SERVICE, "account", account], None).ok()?
+    } else {
+        return None;
+    };
+    let secret = String::from_utf8(output.stdout).ok()?;
+    let secret = secret.trim_end_matches(['\r', '\n']);
+    (output.status.success() && !secret.is_empty()).then(|| secret.to_string())
+}
+
+/// Remove the secret stored as `account`. Whether there was one is not
+/// reported; the keyring tools do not agree on telling.
+pub fn delete(account: &str) -> Result<()> {
+    if cfg!(target_os = "macos") {
+        let output = run("security", &["delete-generic-password", "-s", SERVICE, "-a", account], None)?;
+        // 44 is errSecItemNotFound: nothing to delete is fine
+        match output.status.code() {
+            Some(44) => Ok(()),
+            _ => check(output),
+        }
+    } else if cfg!(unix) {
+        check(run("secret-tool", &["clear", "service", SERVICE, "account", account], None)?)
+    } else {
+        Err(unsupported())
+    }
+}
+
+// Run `program`, feeding it `input`
+fn run(program: &str, args: &[&str], input: Option<&str>) -> Result<Output> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| WtfError::Keyring { reason: format!("{}: {}", program, e) })?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())?;
+    }
+    Ok(child.wait_with_output()?)
+}
+
+fn check(output: Output) -> Result<()> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Err(WtfError::Keyring { reason: if reason.is_empty() { output.status.to_string() } else { reason } })
+}
+
+fn unsupported() -> WtfError {
+    WtfError::Keyring { reason: crate::tr!("keyring-unsupported", os = std::env::consts::OS) }
+}
+
+// Quote an argument for `security -i`, which splits its commands like a
+// shell; `store` has refused line breaks, which would end the command
+fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,81 @@
+//! Explains Git repositories in plain language.
+//!
+//! The binary in `main.rs` is a thin wrapper around this library: it resolves
+//! credentials, opens the repository and hands both to [`analysis::analyze`].
+//! Callers without an async runtime can use the same API from [`blocking`].
+
+pub mod adr;
+pub mod analysis;
+pub mod arch;
+pub mod ask;
+pub mod attributes;
+pub mod blame;
+pub mod blocking;
+pub mod bookmarks;
+pub mod breaking;
+pub mod broke;
+pub mod bus_factor;
+pub mod cache;
+pub mod changelog;
+pub mod chat;
+pub mod ci;
+pub mod chunk;
+pub mod citations;
+pub mod commit;
+pub mod classify;
+pub mod complexity;
+pub mod components;
+pub mod condense;
+pub mod config;
+pub mod conflict;
+pub mod conventional;
+pub mod conversation;
+pub mod cost;
+pub mod crash;
+pub mod dependencies;
+pub mod digest;
+pub mod dry_run;
+pub mod error;
+pub mod generated;
+pub mod git;
+pub mod guard;
+pub mod hooks;
+pub mod i18n;
+pub mod keyring;
+pub mod lint_commits;
+pub mod message;
+pub mod offline;
+pub mod onboard;
+pub mod ownership;
+pub mod platform;
+pub mod pr;
+pub mod progress;
+pub mod prompts;
+pub mod provider;
+pub mod redact;
+pub mod readme;
+pub mod reflog;
+pub mod release;
+pub mod release_notes;
+pub mod render;
+pub mod repo_config;
+pub mod review;
+pub mod rust_api;
+pub mod semver;
+pub mod session;
+pub mod similar;
+pub mod stash;
+pub mod standup;
+pub mod state;
+pub mod structured;
+pub mod summarize;
+pub mod telemetry;
+pub mod timeline;
+pub mod tokens;
+pub mod topics;
+pub mod tui;
+pub mod uncommitted;
+pub mod watch;
+pub mod workspace;
+
+pub use error::{Result, WtfError};
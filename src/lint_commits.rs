@@ -0,0 +1,299 @@
+//! `wtf lint-commits <range>`: commit messages checked against Conventional
+//! Commits.
+//!
+//! Every message in the range is checked against the specification: the
+//! header reads `type(scope)!: description`, a body starts after a blank
+//! line, and a breaking change footer is written `BREAKING CHANGE:` in
+//! capitals. Merges and the messages Git writes itself, like `fixup!`
+//! commits, are let through. The checks need no model; with `--suggest`,
+//! the model reads the diff of each commit whose header fails and names the
+//! likely type and scope, with the header rewritten. While any message
+//! fails, `wtf lint-commits` exits with its own code, for CI.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::conventional;
+use crate::error::Result;
+use crate::git::{self, RevRange};
+use crate::platform;
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that writes commit messages in the Conventional Commits format.";
+
+const SUGGEST_PROMPT: &str = "The following commits have messages that do not follow the Conventional Commits format. For each, read the message and the diff and give the type that fits best (feat, fix, docs, style, refactor, perf, test, build, ci, chore or revert), the scope, the part of the code it is about in one lowercase word or an empty string when it spans the project, and the header rewritten as `type(scope): description`, with `!` after the scope for a breaking change. Keep what the original message says; the description starts lowercase, uses the imperative mood and has no period at the end. Return one suggestion per commit, using the short SHA given.";
+
+// Commits with suggestions per request
+const BATCH_SIZE: usize = 10;
+
+// The diff of each commit is cut to this many tokens
+const PATCH_TOKENS: usize = 1_500;
+
+/// A way a message breaks the specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Problem {
+    /// The first line is not a `type(scope)!: description` header.
+    Header,
+    /// The body does not start after a blank line.
+    BodySpacing,
+    /// A breaking change footer is not written in capitals.
+    FooterCase,
+}
+
+impl Problem {
+    fn label(self) -> String {
+        match self {
+            Problem::Header => tr!("lint-header"),
+            Problem::BodySpacing => tr!("lint-body-spacing"),
+            Problem::FooterCase => tr!("lint-footer-case"),
+        }
+    }
+}
+
+/// The problems of `message`, none for one that follows the specification
+/// or that Git wrote itself.
+pub fn check(message: &str) -> Vec<Problem> {
+    let message = platform::normalize_newlines(message);
+    let message = message.trim();
+    if message.is_empty() || conventional::written_by_git(message) {
+        return Vec::new();
+    }
+    let mut problems = Vec::new();
+    if conventional::parse(message).is_none() {
+        problems.push(Problem::Header);
+    }
+    if message.lines().nth(1).is_some_and(|line| !line.trim().is_empty()) {
+        problems.push(Problem::BodySpacing);
+    }
+    let footer = |line: &str| {
+        let upper = line.to_uppercase();
+        let is_footer = upper.starts_with("BREAKING CHANGE:") || upper.starts_with("BREAKING-CHANGE:");
+        is_footer && !line.starts_with("BREAKING CHANGE:") && !line.starts_with("BREAKING-CHANGE:")
+    };
+    if message.lines().skip(1).any(footer) {
+        problems.push(Problem::FooterCase);
+    }
+    problems
+}
+
+/// A commit whose message fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failing {
+    pub id: Oid,
+    pub short_id: String,
+    pub subject: String,
+    pub problems: Vec<Problem>,
+    // Its message and diff, for the prompt
+    content: String,
+}
+
+/// The messages of a range, checked before any model is called.
+#[derive(Debug, Clone)]
+pub struct LintInput {
+    pub range: RevRange,
+    /// How many commits were checked, merges left out.
+    pub checked: usize,
+    /// Newest first.
+    pub failing: Vec<Failing>,
+}
+
+/// Check the message of every commit in `range`.
+pub fn prepare(repo: &Repository, range: &RevRange) -> Result<LintInput> {
+    let mut input = LintInput { range: range.clone(), checked: 0, failing: Vec::new() };
+    for id in git::range_commits(repo, range)? {
+        let commit = repo.find_commit(id)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        input.checked += 1;
+        let message = git::commit_message(&commit);
+        let problems = check(&message);
+        if problems.is_empty() {
+            continue;
+        }
+        let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+        let patch = git::commit_patch(repo, &commit)?.unwrap_or_default();
+        let (patch, _) = chunk::truncate_to_tokens(&patch, PATCH_TOKENS);
+        let content = format!("Short SHA: {}\nMessage:\n{}\n\nDiff:\n{}", short_id, message.trim(), patch);
+        let subject = message.lines().next().unwrap_or_default().to_string();
+        input.failing.push(Failing { id, short_id, subject, problems, content });
+    }
+    progress!("{}", tr!("progress-lint", count = input.failing.len(), checked = input.checked));
+    Ok(input)
+}
+
+impl LintInput {
+    // The commits whose header the model is asked to rewrite
+    fn asked(&self) -> Vec<&Failing> {
+        self.failing.iter().filter(|failing| failing.problems.contains(&Problem::Header)).collect()
+    }
+
+    fn question(batch: &[&Failing]) -> String {
+        let mut out = SUGGEST_PROMPT.to_string();
+        for failing in batch {
+            out.push_str("\n\n---\n\n");
+            out.push_str(&failing.content);
+        }
+        out
+    }
+
+    /// Estimate the requests [`explain`] will send with a provider.
+    pub fn estimate(&self) -> RunEstimate {
+        let asked = self.asked();
+        RunEstimate {
+            requests: asked.len().div_ceil(BATCH_SIZE),
+            prompt_tokens: asked
+                .chunks(BATCH_SIZE)
+                .map(|batch| tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&Self::question(batch)))
+                .sum(),
+        }
+    }
+}
+
+/// What a failing header probably should have been.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suggestion {
+    /// The likely type, like `fix`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub scope: Option<String>,
+    /// The header rewritten.
+    pub header: String,
+}
+
+/// A failing message and what to do about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    pub short_id: String,
+    pub subject: String,
+    pub problems: Vec<Problem>,
+    pub suggestion: Option<Suggestion>,
+}
+
+/// The checked range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintReport {
+    pub range: RevRange,
+    pub checked: usize,
+    pub failures: Vec<Failure>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    commits: Vec<Suggested>,
+}
+
+#[derive(Deserialize)]
+struct Suggested {
+    sha: String,
+    #[serde(rename = "type")]
+    kind: String,
+    scope: String,
+    header: String,
+}
+
+/// Report the failing messages. With a `provider`, the model is asked for
+/// a suggestion for each failing header, a batch at a time.
+pub async fn explain(input: &LintInput, provider: Option<&dyn LlmProvider>) -> Result<LintReport> {
+    let schema = Schema::new(
+        "commit_suggestions",
+        json!({
+            "type": "object",
+            "properties": {
+                "commits": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "sha": { "type": "string" },
+                            "type": { "type": "string" },
+                            "scope": { "type": "string" },
+                            "header": { "type": "string" },
+                        },
+                        "required": ["sha", "type", "scope", "header"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["commits"],
+            "additionalProperties": false,
+        }),
+    );
+    let mut suggestions: Vec<(Oid, Suggestion)> = Vec::new();
+    if let Some(provider) = provider {
+        for batch in input.asked().chunks(BATCH_SIZE) {
+            let messages = [Message::system(SYSTEM_PROMPT), Message::user(LintInput::question(batch))];
+            let reply: Reply = structured::request(provider, &messages, &schema).await?;
+            for failing in batch {
+                let full_id = failing.id.to_string();
+                let found = reply.commits.iter().find(|found| found.sha.len() >= 7 && full_id.starts_with(&found.sha));
+                // A rewrite that still fails is no help
+                let Some(found) = found.filter(|found| conventional::parse(found.header.trim()).is_some()) else {
+                    continue;
+                };
+                let scope = Some(found.scope.trim().to_string()).filter(|scope| !scope.is_empty());
+                let kind = found.kind.trim().to_lowercase();
+                suggestions.push((failing.id, Suggestion { kind, scope, header: found.header.trim().to_string() }));
+            }
+        }
+    }
+
+    let failures = input
+        .failing
+        .iter()
+        .map(|failing| Failure {
+            short_id: failing.short_id.clone(),
+            subject: failing.subject.clone(),
+            problems: failing.problems.clone(),
+            suggestion: suggestions.iter().find(|(id, _)| *id == failing.id).map(|(_, found)| found.clone()),
+        })
+        .collect();
+    Ok(LintReport { range: input.range.clone(), checked: input.checked, failures })
+}
+
+/// Format `report` for output.
+pub fn render(report: &LintReport, format: OutputFormat) -> String {
+    let overview = match report.failures.len() {
+        0 => tr!("lint-passed", checked = report.checked),
+        count => tr!("lint-failed", count = count, checked = report.checked),
+    };
+    let mut sections = vec![(tr!("lint-title", range = report.range.to_string()), overview + "\n")];
+    if !report.failures.is_empty() {
+        let body: String = report
+            .failures
+            .iter()
+            .map(|failure| {
+                let problems: Vec<String> = failure.problems.iter().map(|problem| problem.label()).collect();
+                let mut out = format!("- {} {}: {}\n", failure.short_id, failure.subject, problems.join(", "));
+                // The type and scope are the header's own
+                if let Some(suggestion) = &failure.suggestion {
+                    out.push_str(&format!("  {}\n", tr!("lint-suggestion", header = suggestion.header.as_str())));
+                }
+                out
+            })
+            .collect();
+        sections.push((tr!("lint-failures"), body));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("lint report serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
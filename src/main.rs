@@ -1,18 +1,60 @@
-use anyhow::{Context, Result};
-use clap::Parser;
-use git2::{Repository, Commit};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use dotenv::dotenv;
-use std::env;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+use wtf::analysis::{self, AnalysisInput, AnalysisOptions, PerTask, RunEstimate, SafetyCap, Task, TaskProviders};
+use wtf::blame::LineRange;
+use wtf::bookmarks::{self, Bookmark, Bookmarks};
+use wtf::session::{self, Session};
+use wtf::standup::{Author, Since};
+use wtf::components::{GroupBy, GroupSpec};
+use wtf::config::Defaults;
+use wtf::error::{exit_code, Result, WtfError};
+use wtf::crash::{self, CrashReport};
+use wtf::digest::Period;
+use wtf::dry_run::DryRun;
+use wtf::redact::{self, Redacting};
+use wtf::telemetry::{self, Event};
+use wtf::watch::RefWatcher;
+use wtf::{
+    adr, arch, ask, attributes, blame, breaking, broke, bus_factor, changelog, chat, commit, conflict, cost, digest,
+    git, hooks, i18n, lint_commits, message, offline, onboard, ownership, platform, pr, progress, readme, reflog,
+    release, release_notes, repo_config, review, semver, similar, standup, stash, state, structured, summarize,
+    timeline, tui, uncommitted, workspace,
+};
+use wtf::git::{RevRange, Uncommitted};
+use wtf::guard::GuardMode;
+use wtf::prompts::{Audience, InLanguage, Prompts};
+#[cfg(feature = "online")]
+use wtf::cache::Cached;
+#[cfg(feature = "online")]
+use wtf::keyring;
+#[cfg(feature = "online")]
+use wtf::provider::sigv4::{self, Credentials};
+#[cfg(feature = "online")]
+use wtf::provider::{ProviderConfig, Registry, Sampling};
+use wtf::provider::{LlmProvider, ProviderKind};
+use wtf::render::{self, OutputFormat};
+
+// Shown by --version, so bug reports say which HTTP/TLS stack was built in
+#[cfg(feature = "native-tls")]
+const LONG_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (tls: native-tls)");
+#[cfg(all(feature = "online", not(feature = "native-tls")))]
+const LONG_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (tls: none, plain HTTP only)");
+#[cfg(not(feature = "online"))]
+const LONG_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (offline build)");
 
 #[derive(Parser, Debug)]
 #[command(name = "wtf")]
 #[command(author = "Your Name")]
-#[command(version)]
+#[command(version, long_version = LONG_VERSION)]
 #[command(about = "Explains Git repositories in plain language", long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to the git repository
     #[arg(default_value = ".")]
     repo_path: PathBuf,
@@ -20,280 +62,2300 @@ struct Args {
     /// Number of commits to analyze
     #[arg(short, long, default_value_t = 5)]
     num_commits: usize,
+
+    /// Fact-check and tighten the draft report in an extra pass (uses more tokens)
+    #[arg(long)]
+    refine: bool,
+
+    /// Report from the Git history alone (authors, files, line counts, commit types) without asking any model;
+    /// subcommands refuse it
+    #[arg(long)]
+    offline: bool,
+
+    /// What to do with file paths and SHAs in the output that don't exist in the analyzed data
+    #[arg(long, value_enum, default_value_t = GuardMode::Flag)]
+    guard: GuardMode,
+
+    /// Drop claims in the commit and edits sections that don't cite a commit SHA
+    #[arg(long)]
+    strict: bool,
+
+    /// Organize the commit and edits sections by directory (dir, dir:<depth>) or by the components in .wtf.toml
+    #[arg(long, value_name = "GROUPING")]
+    group_by: Option<GroupSpec>,
+
+    /// Classify each commit (feature, fix, refactor, docs, chore; risk level) and add a dashboard (one extra request)
+    #[arg(long)]
+    classify: bool,
+
+    /// Group related commits into themes and tell the commit and edits sections theme by theme (one extra request)
+    #[arg(long, conflicts_with = "group_by")]
+    topics: bool,
+
+    /// Past this many commits, summarize them in batches of this size first (0 to send everything at once)
+    #[arg(long, value_name = "N", default_value_t = summarize::DEFAULT_BATCH_SIZE)]
+    batch_size: usize,
+
+    /// Split diffs larger than this many tokens by file and hunk, and summarize the parts separately
+    #[arg(long, global = true, value_name = "TOKENS", value_parser = clap::value_parser!(u64).range(100..))]
+    #[arg(default_value_t = summarize::DEFAULT_MAX_DIFF_TOKENS as u64)]
+    max_diff_tokens: u64,
+
+    /// Leave the changes of paths matching GLOB out of the analysis, like lockfiles (repeatable)
+    #[arg(long, global = true, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Limit the report to the commits changing something under PATH, relative to the repository root (repeatable;
+    /// the report, pick, since-last, range and branch only)
+    #[arg(long = "path", global = true, value_name = "PATH")]
+    paths: Vec<String>,
+
+    /// Read the prompts of the report from DIR: system.txt, project-description.txt, commits.txt, edits.txt
+    #[arg(long, global = true, value_name = "DIR")]
+    prompts_dir: Option<PathBuf>,
+
+    /// Who the report is written for, from an executive summary for managers to a walkthrough for experts
+    #[arg(long = "for", global = true, value_enum, value_name = "AUDIENCE")]
+    audience: Option<Audience>,
+
+    /// The model for the project description (default: description_model in the provider's config section, or --model)
+    #[arg(long, value_name = "MODEL")]
+    model_description: Option<String>,
+
+    /// The model for the commit explanations (default: commits_model in the provider's config section, or --model)
+    #[arg(long, value_name = "MODEL")]
+    model_commits: Option<String>,
+
+    /// The model for the explanation of the diffs (default: edits_model in the provider's config section, or --model)
+    #[arg(long, value_name = "MODEL")]
+    model_edits: Option<String>,
+
+    /// The model for the build and CI summary (default: ci_model in the provider's config section, or --model)
+    #[arg(long, value_name = "MODEL")]
+    model_ci: Option<String>,
+
+    /// The model for --classify (default: classify_model in the provider's config section, or --model)
+    #[arg(long, value_name = "MODEL")]
+    model_classify: Option<String>,
+
+    /// The model for --topics (default: topics_model in the provider's config section, or --model)
+    #[arg(long, value_name = "MODEL")]
+    model_topics: Option<String>,
+
+    /// The model for --refine (default: refine_model in the provider's config section, or --model)
+    #[arg(long, value_name = "MODEL")]
+    model_refine: Option<String>,
+
+    /// Run even if the request exceeds the safety cap, without asking
+    #[arg(long, global = true)]
+    force: bool,
+
+    /// Ask for confirmation when a run would send more requests than this
+    #[arg(long, global = true, default_value_t = SafetyCap::default().max_requests)]
+    max_requests: usize,
+
+    /// Ask for confirmation when a run would send more prompt tokens than this
+    #[arg(long, global = true, default_value_t = SafetyCap::default().max_prompt_tokens)]
+    max_prompt_tokens: usize,
+
+    /// Ask for confirmation when a run is estimated to cost more than this many dollars
+    #[arg(long, global = true, value_name = "DOLLARS")]
+    max_cost: Option<f64>,
+
+    /// Send secrets found in commits, diffs and READMEs as they are instead of masking them
+    #[arg(long, global = true)]
+    no_redact: bool,
+
+    /// Print every prompt instead of sending it; with =DIR, write each to a file in DIR
+    #[arg(long, global = true, value_name = "DIR", num_args = 0..=1, require_equals = true)]
+    dry_run: Option<Option<PathBuf>>,
+
+    /// Send every request even if an identical one was answered before, instead of reusing the cached reply
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Sampling temperature from 0 to 2, for every request or as TASK=VALUE for one task like commits=0
+    /// [default: the model's own]
+    #[arg(long, global = true, value_name = "[TASK=]VALUE", value_parser = temperature_setting)]
+    temperature: Vec<TaskSetting>,
+
+    /// Sample only from the likeliest tokens making up this share (0 to 1) of the probability; TASK=VALUE for one task
+    #[arg(long, global = true, value_name = "[TASK=]VALUE", value_parser = top_p_setting)]
+    top_p: Vec<TaskSetting>,
+
+    /// Cap the length of each reply at this many tokens; TASK=VALUE for one task
+    #[arg(long, global = true, value_name = "[TASK=]TOKENS", value_parser = max_tokens_setting)]
+    max_tokens: Vec<TaskSetting>,
+
+    /// How often to retry a request that hit a rate limit or a server error (0 to fail at once)
+    #[arg(long, global = true, value_name = "N")]
+    max_retries: Option<u32>,
+
+    /// How often to send a JSON reply that does not match its schema back for correction [default: 2]
+    #[arg(long, global = true, value_name = "N")]
+    max_reasks: Option<usize>,
+
+    /// Send requests through this HTTP(S) proxy (default: HTTPS_PROXY and HTTP_PROXY)
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Give up on connecting to the provider after this many seconds [default: 10]
+    #[arg(long, global = true, value_name = "SECONDS")]
+    connect_timeout: Option<u64>,
+
+    /// Give up on a request, its streamed reply included, after this many seconds [default: 300]
+    #[arg(long, global = true, value_name = "SECONDS")]
+    request_timeout: Option<u64>,
+
+    /// The model provider to use (default: from WTF_PROVIDER, or openai)
+    #[arg(long, global = true, value_enum)]
+    provider: Option<ProviderKind>,
+
+    /// The model to use, like gpt-4o, claude-3-5-sonnet-latest or llama3 (default: the provider's own)
+    #[arg(long, global = true)]
+    model: Option<String>,
+
+    /// The provider's API key (default: from the environment, config.toml or the .env file next to the repository)
+    #[arg(long, global = true, value_name = "KEY")]
+    api_key: Option<String>,
+
+    /// The base URL of an OpenAI-compatible API, like https://openrouter.ai/api/v1 (default: from OPENAI_API_BASE)
+    #[arg(long, global = true, value_name = "URL")]
+    api_base: Option<String>,
+
+    /// The Azure OpenAI resource endpoint, like https://my-org.openai.azure.com (default: from AZURE_OPENAI_ENDPOINT)
+    #[arg(long, global = true, value_name = "URL")]
+    azure_endpoint: Option<String>,
+
+    /// The Azure OpenAI API version (default: from AZURE_OPENAI_API_VERSION, or a recent stable one)
+    #[arg(long, global = true, value_name = "VERSION")]
+    azure_api_version: Option<String>,
+
+    /// The AWS profile whose credentials and region Bedrock uses (default: from AWS_PROFILE, or default)
+    #[arg(long, global = true, value_name = "PROFILE")]
+    aws_profile: Option<String>,
+
+    /// The AWS region to call Bedrock in (default: from AWS_REGION or the profile)
+    #[arg(long, global = true, value_name = "REGION")]
+    aws_region: Option<String>,
+
+    /// Output format of the report
+    #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Terminal)]
+    format: OutputFormat,
+
+    /// Screen-reader-friendly output: plain text sections instead of banners and symbols
+    #[arg(long, global = true)]
+    accessible: bool,
+
+    /// Language the model writes the report in, like de or fr; section headers follow where wtf has a translation
+    #[arg(long, global = true, value_name = "LANG")]
+    lang: Option<String>,
+
+    /// Language of wtf's own messages and section headers (default: --lang, WTF_UI_LANG or the locale)
+    #[arg(long, global = true, value_name = "LANG")]
+    ui_lang: Option<String>,
+
+    /// The defaults at the top of config.toml and .wtf.toml
+    #[arg(skip)]
+    defaults: Defaults,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct OpenAIRequest {
-    model: String,
-    messages: Vec<Message>,
-    temperature: f32,
+// The sampling settings --temperature, --top-p and --max-tokens set
+#[cfg(feature = "online")]
+const SAMPLING_SETTINGS: [&str; 3] = ["temperature", "top_p", "max_tokens"];
+
+/// A setting for every task, or for the one named before a `=`.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "online"), allow(dead_code))]
+struct TaskSetting {
+    task: Option<Task>,
+    value: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Message {
-    role: String,
-    content: String,
+fn task_setting<T>(key: &str, arg: &str) -> std::result::Result<TaskSetting, String>
+where
+    T: std::str::FromStr + Into<f64> + Copy,
+    T::Err: std::fmt::Display,
+{
+    let (task, value) = match arg.split_once('=') {
+        Some((name, value)) => {
+            let task = Task::from_name(name.trim()).ok_or_else(|| {
+                let names = Task::ALL.map(Task::name).join(", ");
+                wtf::tr!("unknown-task", name = name, names = names)
+            })?;
+            (Some(task), value.trim())
+        }
+        None => (None, arg.trim()),
+    };
+    wtf::provider::parse_sampling::<T>(key, value)?;
+    Ok(TaskSetting { task, value: value.to_string() })
 }
 
-#[derive(Deserialize, Debug)]
-struct OpenAIResponse {
-    choices: Vec<Choice>,
+fn temperature_setting(arg: &str) -> std::result::Result<TaskSetting, String> {
+    task_setting::<f32>("temperature", arg)
 }
 
-#[derive(Deserialize, Debug)]
-struct Choice {
-    message: Message,
+fn top_p_setting(arg: &str) -> std::result::Result<TaskSetting, String> {
+    task_setting::<f32>("top_p", arg)
 }
 
-fn get_commit_details(commit: &Commit) -> Result<String> {
-    let author = commit.author();
-    let message = commit.message().unwrap_or("No commit message");
-    let time = commit.time();
-    let datetime = time.seconds();
-    
-    let details = format!(
-        "Commit: {}\nAuthor: {}\nDate: {}\nMessage: {}",
-        commit.id(),
-        author.name().unwrap_or("Unknown"),
-        datetime,
-        message
-    );
-    
-    Ok(details)
+fn max_tokens_setting(arg: &str) -> std::result::Result<TaskSetting, String> {
+    task_setting::<u32>("max_tokens", arg)
 }
 
-async fn get_plain_language_description(api_key: &str, content: &str, prompt: &str) -> Result<String> {
-    let client = Client::new();
-    
-    println!("Sending request to OpenAI API...");
-    
-    let request = OpenAIRequest {
-        model: "gpt-3.5-turbo".to_string(),
-        messages: vec![
-            Message {
-                role: "system".to_string(),
-                content: prompt.to_string(),
-            },
-            Message {
-                role: "user".to_string(),
-                content: content.to_string(),
-            },
-        ],
-        temperature: 0.7,
+// The value --temperature, --top-p or --max-tokens gives `key` for `task`,
+// or for every task with `None`. The last one given wins.
+#[cfg(feature = "online")]
+fn sampling_flag(args: &Args, key: &str, task: Option<Task>) -> Option<String> {
+    let settings = match key {
+        "temperature" => &args.temperature,
+        "top_p" => &args.top_p,
+        _ => &args.max_tokens,
     };
-    
-    let response = client.post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await?;
-    
-    // Check if the response is successful
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        println!("OpenAI API error response: {}", error_text);
-        return Err(anyhow::anyhow!("OpenAI API error: {}", error_text));
+    settings.iter().rev().find(|setting| setting.task == task).map(|setting| setting.value.clone())
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Store the provider's API key in the system keyring, or remove it
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+    /// Manage anonymous usage telemetry (off unless you turn it on)
+    Telemetry {
+        #[arg(value_enum)]
+        action: TelemetryAction,
+    },
+    /// Show the latest crash report, or file it as a GitHub issue
+    CrashReport {
+        /// Open a GitHub issue with the report (uses the `gh` CLI)
+        #[arg(long)]
+        submit: bool,
+    },
+    /// Browse recent commits and their explanations interactively
+    Tui {
+        /// Number of commits to list
+        #[arg(long, default_value_t = 200)]
+        limit: usize,
+    },
+    /// Choose commits from a fuzzy-searchable list and analyze just those
+    Pick {
+        /// Number of recent commits to choose from
+        #[arg(long, default_value_t = 200)]
+        limit: usize,
+    },
+    /// Draft a CODEOWNERS file from who changed which directory recently
+    Owners {
+        /// Group directories this many levels deep
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+        /// Number of recent commits to look at
+        #[arg(long, default_value_t = 500)]
+        commits: usize,
+        /// Authors without a commit in this many days count as inactive
+        #[arg(long, default_value_t = 180)]
+        active_days: u64,
+    },
+    /// Find code that only one person knows, and what would be lost with them
+    BusFactor {
+        /// Look at single files instead of directories
+        #[arg(long)]
+        files: bool,
+        /// Group directories this many levels deep
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+        /// Number of recent commits to look at
+        #[arg(long, default_value_t = 500)]
+        commits: usize,
+        /// Flag parts where one author wrote at least this share of the changed lines
+        #[arg(long, default_value_t = 0.75)]
+        threshold: f64,
+        /// Number of parts to list and summarize
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Only compute the numbers; don't ask the model what knowledge is at risk
+        #[arg(long)]
+        no_summary: bool,
+    },
+    /// Tell the story of one file: creation, rewrites, renames and the people involved
+    #[command(visible_alias = "file")]
+    Timeline {
+        /// The file, relative to the repository root or the current directory
+        path: PathBuf,
+    },
+    /// Explain new commits as they land on any local branch, until interrupted
+    Watch {
+        /// Seconds between checks for new commits
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Explain at most this many of the commits one check finds, the newest
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        max_commits: u64,
+    },
+    /// Explain which .gitignore and .gitattributes rules apply, and what they do
+    Attrs {
+        /// The path to explain; without one, the files changed in recent commits
+        path: Option<PathBuf>,
+        /// Number of recent commits whose files to explain when no path is given
+        #[arg(long, default_value_t = 20)]
+        commits: usize,
+    },
+    /// Draft Architecture Decision Records for pivotal changes in the history
+    Adr {
+        /// Number of recent commits to look through
+        #[arg(long, default_value_t = 1000)]
+        commits: usize,
+        /// Number of pivotal changes to draft records for
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+        /// Write each record to its own numbered file in this directory, like docs/adr
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Find past commits similar to a commit or the staged changes, and how they handled it
+    Similar {
+        /// The commit to compare (a SHA or any revision)
+        #[arg(required_unless_present = "staged")]
+        rev: Option<String>,
+        /// Compare the changes staged in the index instead of a commit
+        #[arg(long, conflicts_with = "rev")]
+        staged: bool,
+        /// Number of recent commits to compare with
+        #[arg(long, default_value_t = 1000)]
+        commits: usize,
+        /// Number of similar commits to show and summarize
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+    },
+    /// Bookmark a commit on the current branch as the last one you looked at
+    Mark {
+        /// The commit to bookmark
+        #[arg(default_value = "HEAD")]
+        rev: String,
+    },
+    /// Explain what changed on the current branch since your bookmark, then move the bookmark
+    SinceLast,
+    /// List or export the transcripts of interactive sessions
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
+    },
+    /// Explain how the repository is configured: push and pull behavior, remotes and installed hooks
+    ConfigExplain,
+    /// Show the branch, changes and upstream status of every repository in a directory, and what's in flight
+    Workspace {
+        /// The directory holding the repositories [default: the current directory]
+        dir: Option<PathBuf>,
+        /// Look for repositories this many directory levels deep
+        #[arg(long, default_value_t = 1)]
+        depth: usize,
+        /// Only show the status; don't ask the model for an overview
+        #[arg(long)]
+        no_summary: bool,
+    },
+    /// Explain a single commit: what it changes and why
+    Commit {
+        /// The commit, as any revision git understands (a SHA, HEAD~2, a tag, ...)
+        rev: String,
+    },
+    /// Explain the changes that are not committed yet, against HEAD
+    Diff {
+        /// Only the staged changes, the ones the next commit will contain
+        #[arg(long, conflicts_with = "unstaged")]
+        staged: bool,
+        /// Only the changes not staged yet
+        #[arg(long)]
+        unstaged: bool,
+    },
+    /// Explain what changed between two revisions, like v1.2.0..HEAD or main...feature
+    Range {
+        /// A..B for the commits in B but not A, A...B for those on either side since they diverged, or a
+        /// single revision for everything since it
+        range: String,
+    },
+    /// Explain what a branch adds to its base: its own commits and their combined diff
+    Branch {
+        /// The branch to review [default: the current branch]
+        name: Option<String>,
+        /// The branch it is compared with [default: base_branch from .wtf.toml, origin's default branch, or main]
+        #[arg(long, value_name = "BRANCH")]
+        base: Option<String>,
+    },
+    /// Explain why some lines of a file look the way they do, from the commits that last changed them
+    Blame {
+        /// The file, relative to the repository root or the current directory
+        path: PathBuf,
+        /// The lines to explain, like 120-160 [default: the whole file]
+        #[arg(long, value_name = "RANGE")]
+        lines: Option<LineRange>,
+    },
+    /// Explain where the repository stands, like a merge or rebase in progress, and what to do next
+    Status {
+        /// Only show the state; don't ask the model what it means
+        #[arg(long)]
+        no_summary: bool,
+    },
+    /// Write a Conventional Commits message for the staged changes
+    Msg {
+        /// Open the message in your editor before using it
+        #[arg(long)]
+        edit: bool,
+        /// Commit the staged changes with the message
+        #[arg(long)]
+        commit: bool,
+    },
+    /// Rank the commits between a good and a bad revision by how likely
+    /// they are to have broken something
+    Broke {
+        /// What broke, like "parsing empty files panics" or a failing test
+        symptom: String,
+        /// A revision that still worked
+        #[arg(long)]
+        good: String,
+        /// A revision that is broken
+        #[arg(long, default_value = "HEAD")]
+        bad: String,
+        /// A test command to drive `git bisect run` with first, after `--`
+        #[arg(last = true)]
+        run: Vec<String>,
+    },
+    /// Explain what recently happened to HEAD, and how to get lost commits
+    /// back
+    Reflog {
+        /// How many of the latest movements of HEAD to look at
+        #[arg(long, default_value_t = 30)]
+        limit: usize,
+        /// Only list the movements; don't ask the model about them
+        #[arg(long)]
+        no_summary: bool,
+    },
+    /// Explain what both sides of each conflict were trying to do, and how
+    /// to resolve it
+    Conflict {
+        /// Only the conflict in this file
+        path: Option<PathBuf>,
+        /// Also propose resolved code for each conflicting part
+        #[arg(long)]
+        propose: bool,
+    },
+    /// Summarize what is in a release, since the tag before it
+    Tag {
+        /// The release's tag, like v2.0.0
+        name: String,
+    },
+    /// Explain what each stash holds, to decide which to pop or drop
+    Stash {
+        /// Only list the stashes; don't ask the model about them
+        #[arg(long)]
+        no_summary: bool,
+    },
+    /// Write a pull request description for a branch
+    Pr {
+        /// The branch to describe [default: the current branch]
+        name: Option<String>,
+        /// The branch it will be merged into [default: base_branch from .wtf.toml, origin's default branch, or main]
+        #[arg(long, value_name = "BRANCH")]
+        base: Option<String>,
+        /// The markdown layout, for GitHub pull requests or GitLab merge requests
+        #[arg(long, value_enum, default_value_t)]
+        template: pr::Template,
+    },
+    /// Review changes like a code reviewer: likely bugs, risks, missing tests and unclear naming, by file
+    Review {
+        /// A..B, A...B or a single revision for everything since it, as for `wtf range`
+        #[arg(required_unless_present = "staged")]
+        range: Option<String>,
+        /// Review the staged changes instead
+        #[arg(long, conflicts_with = "range")]
+        staged: bool,
+    },
+    /// Add the commits since the last tag to the changelog, in the Keep a Changelog format
+    Changelog {
+        /// The changelog, relative to the repository root
+        #[arg(long, default_value = "CHANGELOG.md")]
+        file: PathBuf,
+        /// Print the new entries instead of adding them to the file
+        #[arg(long)]
+        print: bool,
+    },
+    /// Write user-facing release notes for a range, like v1.4.0..v1.5.0, ready for a GitHub release
+    ReleaseNotes {
+        /// A..B, A...B or a single revision for everything since it, as for `wtf range`
+        range: String,
+    },
+    /// Suggest a major, minor or patch bump for the next release, with the reason for each commit
+    Semver,
+    /// List probable breaking changes in a range: public API, command-line flags, config schemas and migrations
+    Breaking {
+        /// A..B, A...B or a single revision for everything since it, as for `wtf range`
+        range: String,
+    },
+    /// Check the commit messages of a range against Conventional Commits, failing when any break it
+    LintCommits {
+        /// A..B, A...B or a single revision for everything since it, as for `wtf range`
+        range: String,
+        /// Ask the model for the likely type and scope of each failing header, with the header rewritten
+        #[arg(long)]
+        suggest: bool,
+    },
+    /// Summarize what an author did recently as a standup update: done, in progress and blockers
+    Standup {
+        /// The start of the window: today, yesterday, a date like 2024-05-01 or an age like "3 days ago"
+        #[arg(long, value_name = "WHEN", default_value = "yesterday")]
+        since: Since,
+        /// Whose commits to cover: me for the identity Git commits as, or part of a name or email
+        #[arg(long, value_name = "WHO", default_value = "me")]
+        author: String,
+    },
+    /// Summarize the last week or month for a team newsletter: features, fixes, contributors and stats
+    Digest {
+        /// How far back to go, ending today
+        #[arg(long, value_enum, default_value_t = Period::Week)]
+        period: Period,
+    },
+    /// Give new contributors a tour: what the project does, its layout, entry points, build commands and active areas
+    Onboard,
+    /// Describe the codebase module by module, with a sketch of which modules depend on which
+    Arch {
+        /// Directories this many levels deep are modules of their own
+        #[arg(long, value_name = "N", default_value_t = arch::DEFAULT_DEPTH)]
+        depth: usize,
+    },
+    /// Propose a README written from the code tree, manifests and recent history, or an update of the current one
+    Readme {
+        /// Print the proposal as a diff against the README at HEAD, for `git apply`
+        #[arg(long)]
+        diff: bool,
+    },
+    /// Answer a question about the repository from the commits and files it is about, citing them
+    Ask {
+        /// The question, like "why was the retry logic removed?"
+        question: String,
+        /// Also pick out the commits adding or removing lines that contain STRING, like `git log -G` (repeatable)
+        #[arg(long, short = 'S', value_name = "STRING")]
+        pickaxe: Vec<String>,
+        /// Number of recent commits to search
+        #[arg(long, default_value_t = ask::DEFAULT_MAX_COMMITS)]
+        commits: usize,
+    },
+    /// Chat about the repository: follow-up questions build on earlier answers, and /help lists the commands
+    Chat {
+        /// Number of recent commits to search for each question
+        #[arg(long, default_value_t = ask::DEFAULT_MAX_COMMITS)]
+        commits: usize,
+    },
+    /// Install or remove Git hooks that call back into wtf
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
+}
+
+impl Command {
+    /// Whether the command limits its commits to `--path`.
+    fn takes_paths(command: Option<&Command>) -> bool {
+        matches!(
+            command,
+            None | Some(Command::Pick { .. } | Command::SinceLast | Command::Range { .. } | Command::Branch { .. })
+        )
     }
-    
-    println!("Received successful response from OpenAI API");
-    
-    // Parse the response
-    match response.json::<OpenAIResponse>().await {
-        Ok(response_data) => {
-            if let Some(choice) = response_data.choices.first() {
-                Ok(choice.message.content.clone())
-            } else {
-                anyhow::bail!("No choices in OpenAI API response")
-            }
-        },
-        Err(e) => {
-            println!("Error parsing OpenAI API response: {}", e);
-            Err(anyhow::anyhow!("Failed to parse OpenAI API response: {}", e))
+
+    /// The name reported in telemetry events.
+    fn name(command: Option<&Command>) -> &'static str {
+        match command {
+            None => "analyze",
+            Some(Command::Auth { .. }) => "auth",
+            Some(Command::Telemetry { .. }) => "telemetry",
+            Some(Command::CrashReport { .. }) => "crash-report",
+            Some(Command::Tui { .. }) => "tui",
+            Some(Command::Pick { .. }) => "pick",
+            Some(Command::Owners { .. }) => "owners",
+            Some(Command::BusFactor { .. }) => "bus-factor",
+            Some(Command::Timeline { .. }) => "timeline",
+            Some(Command::Watch { .. }) => "watch",
+            Some(Command::Attrs { .. }) => "attrs",
+            Some(Command::Adr { .. }) => "adr",
+            Some(Command::Similar { .. }) => "similar",
+            Some(Command::Mark { .. }) => "mark",
+            Some(Command::SinceLast) => "since-last",
+            Some(Command::Session { .. }) => "session",
+            Some(Command::ConfigExplain) => "config-explain",
+            Some(Command::Workspace { .. }) => "workspace",
+            Some(Command::Commit { .. }) => "commit",
+            Some(Command::Diff { .. }) => "diff",
+            Some(Command::Range { .. }) => "range",
+            Some(Command::Branch { .. }) => "branch",
+            Some(Command::Blame { .. }) => "blame",
+            Some(Command::Status { .. }) => "status",
+            Some(Command::Msg { .. }) => "msg",
+            Some(Command::Broke { .. }) => "broke",
+            Some(Command::Reflog { .. }) => "reflog",
+            Some(Command::Conflict { .. }) => "conflict",
+            Some(Command::Tag { .. }) => "tag",
+            Some(Command::Stash { .. }) => "stash",
+            Some(Command::Pr { .. }) => "pr",
+            Some(Command::Review { .. }) => "review",
+            Some(Command::Changelog { .. }) => "changelog",
+            Some(Command::ReleaseNotes { .. }) => "release-notes",
+            Some(Command::Semver) => "semver",
+            Some(Command::Breaking { .. }) => "breaking",
+            Some(Command::LintCommits { .. }) => "lint-commits",
+            Some(Command::Standup { .. }) => "standup",
+            Some(Command::Digest { .. }) => "digest",
+            Some(Command::Onboard) => "onboard",
+            Some(Command::Arch { .. }) => "arch",
+            Some(Command::Readme { .. }) => "readme",
+            Some(Command::Ask { .. }) => "ask",
+            Some(Command::Chat { .. }) => "chat",
+            Some(Command::Hook { .. }) => "hook",
         }
     }
 }
 
-async fn analyze_repository(args: Args) -> Result<()> {
-    // Check if .env file is being loaded
-    println!("Attempting to load .env file...");
-    let env_result = dotenv();
-    match env_result {
-        Ok(path) => println!("Loaded .env from: {:?}", path),
-        Err(e) => println!("Warning: Could not load .env file: {:?}", e),
+#[derive(Subcommand, Debug)]
+enum AuthAction {
+    /// Ask for the key of the provider (--provider or WTF_PROVIDER) and store it in the keyring
+    Login,
+    /// Remove the provider's key from the keyring
+    Logout,
+}
+
+#[derive(Subcommand, Debug)]
+enum HookAction {
+    /// Install a hook where Git looks for them (core.hooksPath or .git/hooks)
+    Install {
+        #[arg(value_enum)]
+        hook: hooks::Hook,
+        /// Replace a hook of the same name that wtf did not install
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Remove a hook wtf installed
+    Uninstall {
+        #[arg(value_enum)]
+        hook: hooks::Hook,
+    },
+    /// Run a hook; the installed scripts call this
+    #[command(hide = true)]
+    Run {
+        #[arg(value_enum)]
+        hook: hooks::Hook,
+        /// The arguments Git passed to the hook
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SessionAction {
+    /// List the saved sessions, newest first
+    List,
+    /// Print a session's questions, answers and commits (use --format markdown to share it)
+    Export {
+        /// The session's id, as shown by `wtf session list`
+        id: String,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum TelemetryAction {
+    /// Send anonymous usage data
+    On,
+    /// Stop sending usage data
+    Off,
+    /// Show whether telemetry is on and what it sends
+    Status,
+}
+
+// A variable from the environment, if set and not empty
+#[cfg(feature = "online")]
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// Which provider to use: --provider, then WTF_PROVIDER from the environment, then config.toml
+#[cfg(feature = "online")]
+fn provider_kind(args: &Args) -> Result<ProviderKind> {
+    if let Some(kind) = args.provider {
+        return Ok(kind);
+    }
+    match env_var("WTF_PROVIDER").or_else(|| args.defaults.provider.clone()) {
+        Some(name) => ProviderKind::from_name(&name).ok_or(WtfError::UnknownProvider { name }),
+        None => Ok(ProviderKind::default()),
+    }
+}
+
+// With --dry-run, a stand-in that shows the prompts instead of sending them
+fn dry_run_provider(args: &Args) -> Option<Box<dyn LlmProvider>> {
+    let out_dir = args.dry_run.as_ref()?;
+    let provider = match out_dir {
+        Some(dir) => DryRun::to_dir(dir),
+        None => DryRun::new(),
+    };
+    Some(redacting(args, in_language(args, Box::new(provider))))
+}
+
+// Have the model answer in the --lang language, if one is given
+fn in_language(args: &Args, provider: Box<dyn LlmProvider>) -> Box<dyn LlmProvider> {
+    match args.lang.as_ref().or(args.defaults.lang.as_ref()) {
+        Some(lang) => Box::new(InLanguage::new(provider, lang)),
+        None => provider,
     }
-    
-    // Check all possible environment variables
-    println!("\nChecking environment variables:");
-    for (key, value) in env::vars() {
-        if key.contains("API") || key.contains("KEY") {
-            let masked_value = if value.len() > 8 {
-                format!("{}...{}", &value[..4], &value[value.len()-4..])
-            } else {
-                "[value too short]".to_string()
-            };
-            println!("Found environment variable: {} = {}", key, masked_value);
+}
+
+// Mask secrets in everything sent, unless --no-redact is given
+fn redacting(args: &Args, provider: Box<dyn LlmProvider>) -> Box<dyn LlmProvider> {
+    if args.no_redact {
+        provider
+    } else {
+        Box::new(Redacting::new(provider))
+    }
+}
+
+#[cfg(feature = "online")]
+fn hosted_provider(args: &Args) -> Result<Box<dyn LlmProvider>> {
+    if let Some(provider) = dry_run_provider(args) {
+        return Ok(provider);
+    }
+    let kind = provider_kind(args)?;
+    build_provider(args, &Registry::builtin(), kind, &provider_config(args, kind)?)
+}
+
+// Build the provider, reusing cached replies unless --no-cache is given,
+// answering in the --lang language and masking secrets unless --no-redact is
+#[cfg(feature = "online")]
+fn build_provider(
+    args: &Args,
+    registry: &Registry,
+    kind: ProviderKind,
+    config: &ProviderConfig,
+) -> Result<Box<dyn LlmProvider>> {
+    let provider = registry.build(kind.name(), config)?;
+    // Replies written with other sampling settings are other replies
+    let sampling = Sampling::from_config(config)?;
+    let namespace = match sampling == Sampling::default() {
+        true => kind.name().to_string(),
+        false => format!("{} {:?}", kind.name(), sampling),
+    };
+    let provider = match platform::cache_dir().filter(|_| !args.no_cache) {
+        Some(dir) => Box::new(Cached::new(provider, &dir, &namespace)),
+        None => provider,
+    };
+    Ok(redacting(args, in_language(args, provider)))
+}
+
+// The provider's settings: its section of config.toml, overridden by the
+// environment and then by flags. The API key is looked up in its own order, and
+// is all that is read from the analyzed repository's .env: whoever controls the
+// repository must not get to pick where requests (and the key) go
+#[cfg(feature = "online")]
+fn provider_config(args: &Args, kind: ProviderKind) -> Result<ProviderConfig> {
+    let mut config = platform::config_dir().map(|dir| ProviderConfig::load(&dir, kind.name())).unwrap_or_default();
+    // The defaults at the top of config.toml count where the section has nothing
+    let defaults = [
+        ("model", args.defaults.model.clone()),
+        ("temperature", args.defaults.temperature.map(|temperature| temperature.to_string())),
+        ("top_p", args.defaults.top_p.map(|top_p| top_p.to_string())),
+        ("max_tokens", args.defaults.max_tokens.map(|tokens| tokens.to_string())),
+    ];
+    for (key, value) in defaults {
+        if config.get(key).is_none() {
+            config.layer(key, value);
         }
     }
-    
-    // Read API key directly from .env file instead of using environment variables
-    println!("\nReading API key directly from .env file...");
-    let env_contents = std::fs::read_to_string(".env")
-        .context("Failed to read .env file")?;
-    
-    let mut api_key = String::new();
-    for line in env_contents.lines() {
-        if line.starts_with("OPENAI_API_KEY=") {
-            api_key = line.trim_start_matches("OPENAI_API_KEY=").to_string();
-            let masked_key = if api_key.len() > 8 {
-                format!("{}...{}", &api_key[..4], &api_key[api_key.len()-4..])
-            } else {
-                "[key too short]".to_string()
-            };
-            println!("Using API key from .env file: {}", masked_key);
-            break;
+    match kind {
+        ProviderKind::OpenAi => {
+            config.layer("api_base", env_var("OPENAI_API_BASE"));
+            config.layer("api_base", args.api_base.clone());
+            config.layer("model", args.model.clone());
+        }
+        ProviderKind::Azure => {
+            config.layer("endpoint", env_var("AZURE_OPENAI_ENDPOINT"));
+            config.layer("endpoint", args.azure_endpoint.clone());
+            // The deployment is what stands in for the model on Azure
+            config.layer("deployment", env_var("AZURE_OPENAI_DEPLOYMENT"));
+            config.layer("deployment", args.model.clone());
+            config.layer("api_version", env_var("AZURE_OPENAI_API_VERSION"));
+            config.layer("api_version", args.azure_api_version.clone());
         }
+        ProviderKind::Ollama => {
+            config.layer("host", env_var("OLLAMA_HOST"));
+            config.layer("model", args.model.clone());
+        }
+        ProviderKind::Bedrock => bedrock_config(args, &mut config),
+        ProviderKind::Anthropic | ProviderKind::Gemini => config.layer("model", args.model.clone()),
     }
-    
-    if api_key.is_empty() {
-        return Err(anyhow::anyhow!("OPENAI_API_KEY not found in .env file"));
+    for key in SAMPLING_SETTINGS {
+        config.layer(key, sampling_flag(args, key, None));
     }
-    
-    // Open the repository with improved error handling
-    let repo = match Repository::open(&args.repo_path) {
-        Ok(repo) => repo,
-        Err(e) => {
-            eprintln!("Error: Failed to open Git repository at {:?}", args.repo_path);
-            eprintln!("Make sure you're running this from a valid Git repository or specify a valid path with --repo-path");
-            eprintln!("Detailed error: {}", e);
-            return Err(anyhow::anyhow!("Repository not found"));
-        }
+    config.layer("max_retries", args.max_retries.map(|retries| retries.to_string()));
+    config.layer("proxy", args.proxy.clone());
+    config.layer("connect_timeout", args.connect_timeout.map(|seconds| seconds.to_string()));
+    config.layer("request_timeout", args.request_timeout.map(|seconds| seconds.to_string()));
+    let Some(var) = kind.api_key_var() else {
+        return Ok(config);
     };
-    
-    // Get the latest commits
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_head()?;
-    
-    let mut commits = Vec::new();
-    let mut commit_details = Vec::new();
-    
-    // Count available commits
-    let commit_count = revwalk.count();
-    
-    // Reset revwalk to start from the beginning again
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_head()?;
-    
-    // Determine how many commits to analyze
-    let num_to_analyze = std::cmp::min(args.num_commits, commit_count);
-    
-    if num_to_analyze == 0 {
-        println!("No commits found in the repository.");
-        return Ok(());
+    // --api-key, then the environment, the keyring, config.toml and the .env next to the repository
+    let api_key = args
+        .api_key
+        .clone()
+        .or_else(|| env_var(var))
+        .or_else(|| keyring::get(var))
+        .or_else(|| config.get("api_key").map(str::to_string))
+        .or_else(|| wtf::config::read_env_file(&wtf::config::env_file_path(&args.repo_path), var));
+    config.layer("api_key", api_key);
+    Ok(config)
+}
+
+// Bedrock signs requests with AWS credentials: from the environment, or the
+// profile's in the shared credentials file (an explicit --aws-profile wins)
+#[cfg(feature = "online")]
+fn bedrock_config(args: &Args, config: &mut ProviderConfig) {
+    let profile = args
+        .aws_profile
+        .clone()
+        .or_else(|| env_var("AWS_PROFILE"))
+        .or_else(|| config.get("profile").map(str::to_string))
+        .unwrap_or_else(|| "default".to_string());
+    let aws_file = |var: &str, name: &str| {
+        let path = env_var(var)
+            .map(PathBuf::from)
+            .or_else(|| platform::home_dir().map(|home| home.join(".aws").join(name)));
+        path.and_then(|path| std::fs::read_to_string(path).ok()).unwrap_or_default()
+    };
+    let from_env = match (env_var("AWS_ACCESS_KEY_ID"), env_var("AWS_SECRET_ACCESS_KEY")) {
+        (Some(access_key_id), Some(secret_access_key)) if args.aws_profile.is_none() => Some(Credentials {
+            access_key_id,
+            secret_access_key,
+            session_token: env_var("AWS_SESSION_TOKEN"),
+        }),
+        _ => None,
+    };
+    let credentials =
+        from_env.or_else(|| Credentials::from_profile(&aws_file("AWS_SHARED_CREDENTIALS_FILE", "credentials"), &profile));
+    if let Some(credentials) = credentials {
+        config.set("access_key_id", credentials.access_key_id);
+        config.set("secret_access_key", credentials.secret_access_key);
+        config.layer("session_token", credentials.session_token);
     }
-    
-    println!("Found {} commits, will analyze {}.", commit_count, num_to_analyze);
-    
-    for (i, oid) in revwalk.take(num_to_analyze).enumerate() {
-        let oid = oid?;
-        let commit = repo.find_commit(oid)?;
-        let details = get_commit_details(&commit)?;
-        
-        println!("Analyzing commit {} of {}...", i + 1, num_to_analyze);
-        commit_details.push(details);
-        commits.push(commit);
+    if config.get("region").is_none() {
+        let config_file = aws_file("AWS_CONFIG_FILE", "config");
+        let region = sigv4::ini_section(&config_file, &profile).into_iter().find(|(key, _)| key == "region");
+        config.layer("region", region.map(|(_, value)| value));
+    }
+    config.layer("region", env_var("AWS_DEFAULT_REGION"));
+    config.layer("region", env_var("AWS_REGION"));
+    config.layer("region", args.aws_region.clone());
+    config.layer("model", args.model.clone());
+}
+
+#[cfg(not(feature = "online"))]
+fn hosted_provider(args: &Args) -> Result<Box<dyn LlmProvider>> {
+    dry_run_provider(args).ok_or(WtfError::NetworkDisabled)
+}
+
+// The providers for each part of the analysis. A task's model comes from
+// --model-<task>, then <task>_model in the provider's config section; tasks
+// without one use the provider as configured.
+#[cfg(feature = "online")]
+fn task_providers(args: &Args) -> Result<PerTask> {
+    if let Some(provider) = dry_run_provider(args) {
+        return Ok(PerTask::new(provider));
+    }
+    let kind = provider_kind(args)?;
+    let config = provider_config(args, kind)?;
+    let registry = Registry::builtin();
+    let mut providers = PerTask::new(build_provider(args, &registry, kind, &config)?);
+    for task in Task::ALL {
+        let flag = match task {
+            Task::Description => &args.model_description,
+            Task::Commits => &args.model_commits,
+            Task::Edits => &args.model_edits,
+            Task::Ci => &args.model_ci,
+            Task::Classify => &args.model_classify,
+            Task::Topics => &args.model_topics,
+            Task::Refine => &args.model_refine,
+        };
+        let mut task_config = config.clone();
+        if let Some(model) = flag.as_deref().or_else(|| config.get(&format!("{}_model", task.name()))) {
+            // The deployment is what stands in for the model on Azure
+            task_config.set(if kind == ProviderKind::Azure { "deployment" } else { "model" }, model);
+        }
+        // Like commits_temperature in the provider's section
+        for key in SAMPLING_SETTINGS {
+            let setting = format!("{}_{}", task.name(), key);
+            let value = sampling_flag(args, key, Some(task)).or_else(|| config.get(&setting).map(str::to_string));
+            task_config.layer(key, value);
+        }
+        if task_config != config {
+            providers.set(task, build_provider(args, &registry, kind, &task_config)?);
+        }
+    }
+    Ok(providers)
+}
+
+#[cfg(not(feature = "online"))]
+fn task_providers(args: &Args) -> Result<PerTask> {
+    dry_run_provider(args).map(PerTask::new).ok_or(WtfError::NetworkDisabled)
+}
+
+async fn analyze_repository(args: &Args) -> Result<()> {
+    if args.offline {
+        return report_offline(args);
     }
+    let providers = task_providers(args)?;
+
+    // Open the repository
+    let repo = git::open_repository(&args.repo_path)?;
     
-    // Get project description
-    let readme_content = match repo.find_file("README.md") {
-        Ok(content) => content,
-        Err(_) => "No README.md found".to_string(),
+    let options = analysis_options(args, &repo)?;
+    let input = analysis::prepare(&repo, &options)?;
+    report_on(&input, &providers, &options, args).await
+}
+
+async fn pick_and_analyze(args: &Args, limit: usize) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(WtfError::NotInteractive);
+    }
+    let repo = git::open_repository(&args.repo_path)?;
+    let commits = tui::load_commits(&repo, limit)?;
+    let providers = task_providers(args)?;
+
+    let chosen = tokio::task::block_in_place(|| {
+        let mut terminal = tui::Terminal::enter()?;
+        tui::picker::pick(&mut terminal, &mut tui::picker::Picker::new(commits))
+    })?;
+    let Some(chosen) = chosen else {
+        eprintln!("{}", wtf::tr!("pick-cancelled"));
+        return Ok(());
     };
-    
-    let project_description_prompt = "You are an AI assistant that provides concise project descriptions. Based on the README content and other information provided, give a brief, clear description of what this project is about in plain English. Keep it under 100 words.";
-    
-    let project_description = get_plain_language_description(
-        &api_key, 
-        &readme_content, 
-        project_description_prompt
-    ).await?;
-    
-    // Get plain language commit descriptions
-    let commit_prompt = "You are an AI assistant that explains git commits in plain language. For each commit, explain what changes were made in simple terms that anyone can understand. Focus on the practical impact of the changes rather than technical details.";
-    
-    let commit_descriptions = get_plain_language_description(
-        &api_key,
-        &commit_details.join("\n\n---\n\n"),
-        commit_prompt
-    ).await?;
-    
-    // Get detailed analysis of the last 5 edits
-    // Only analyze file changes if there are multiple commits
-    let edits_description = if commits.len() > 1 {
-        let mut file_changes = Vec::new();
-        for commit in &commits {
-            if let Some(parent) = commit.parent(0).ok() {
-                let diff = repo.diff_tree_to_tree(
-                    Some(&parent.tree()?),
-                    Some(&commit.tree()?),
-                    None,
-                )?;
-                
-                let mut diff_stats = String::new();
-                diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-                    diff_stats.push_str(&format!("{}", String::from_utf8_lossy(line.content())));
-                    true
-                })?;
-                
-                file_changes.push(diff_stats);
+    let ids = chosen
+        .iter()
+        .map(|commit| git2::Oid::from_str(&commit.id))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let options = analysis_options(args, &repo)?;
+    let input = analysis::prepare_selected(&repo, &ids, &options)?;
+    report_on(&input, &providers, &options, args).await
+}
+
+fn report_offline(args: &Args) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let report = offline::collect(&repo, args.num_commits, &args.paths)?;
+    print!("{}", offline::render(&report, output_format(args)));
+    Ok(())
+}
+
+fn analysis_options(args: &Args, repo: &git2::Repository) -> Result<AnalysisOptions> {
+    Ok(AnalysisOptions {
+        num_commits: args.num_commits,
+        refine: args.refine,
+        guard: args.guard,
+        strict: args.strict,
+        group_by: args.group_by.map(|spec| GroupBy::resolve(spec, repo)).transpose()?,
+        classify: args.classify,
+        topics: args.topics,
+        batch_size: args.batch_size,
+        max_diff_tokens: args.max_diff_tokens as usize,
+        exclude: args.exclude.clone(),
+        paths: args.paths.clone(),
+        prompts: prompts(args)?,
+        range: None,
+    })
+}
+
+// The built-in prompts for the audience, with the templates of the prompts directory over them
+fn prompts(args: &Args) -> Result<Prompts> {
+    let prompts = args.audience.or(args.defaults.audience).map(Prompts::for_audience).unwrap_or_default();
+    match args.prompts_dir.as_ref().or(args.defaults.prompts_dir.as_ref()) {
+        Some(dir) => prompts.with_templates(dir),
+        None => Ok(prompts),
+    }
+}
+
+// Check the safety cap, run the analysis and print the report
+async fn report_on(input: &AnalysisInput, providers: &PerTask, options: &AnalysisOptions, args: &Args) -> Result<()> {
+    let provider = providers.for_task(Task::Commits);
+    check_safety_cap(&input.estimate(options, provider.max_context()), provider, args)?;
+    // A refined report replaces the draft wholesale, so there is nothing
+    // worth showing early
+    let stream = output_format(args) == OutputFormat::Terminal && io::stdout().is_terminal() && !options.refine;
+    if !stream {
+        let report = analysis::run(input, providers, options).await?;
+        print!("{}", render::render(&report, output_format(args)));
+        return Ok(());
+    }
+
+    // Progress lines on stderr would land in the middle of the sections
+    let quiet = progress::is_quiet();
+    progress::set_quiet(true);
+    let mut streamed: Vec<(Task, String)> = Vec::new();
+    let result = analysis::run_streaming(input, providers, options, &mut |task, text| {
+        if streamed.last().map(|(last, _)| *last) != Some(task) {
+            if !streamed.is_empty() {
+                println!();
             }
+            print!("{}", render::stream_banner(task, input.num_commits, options.range.as_ref()).unwrap_or_default());
+            streamed.push((task, String::new()));
         }
-        
-        let edits_prompt = "You are an AI assistant that explains code changes in plain language. For each edit, explain what was changed and why it might have been changed. Focus on the functional impact rather than listing every line change. Make it understandable to non-technical people.";
-        
-        get_plain_language_description(
-            &api_key,
-            &file_changes.join("\n\n---\n\n"),
-            edits_prompt
-        ).await?
-    } else {
-        "Repository has only one commit, so there are no previous versions to compare changes against.".to_string()
+        print!("{}", text);
+        let _ = io::stdout().flush();
+        if let Some((_, shown)) = streamed.last_mut() {
+            shown.push_str(text);
+        }
+    })
+    .await;
+    progress::set_quiet(quiet);
+    let report = result?;
+    if !streamed.is_empty() {
+        println!();
+    }
+    print!("{}", render::render_after_stream(&report, &streamed));
+    Ok(())
+}
+
+fn set_bookmark(args: &Args, rev: &str) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+    let dir = platform::data_dir().ok_or(WtfError::NoConfigDir)?;
+    let branch = bookmarks::current_branch(&repo);
+    let mut marks = Bookmarks::load(&dir);
+    marks.set(&bookmarks::repository_key(&repo), &branch, Bookmark::now(commit.id()));
+    marks.save(&dir)?;
+    let short_id = commit.as_object().short_id()?;
+    println!("{}", wtf::tr!("mark-set", id = short_id.as_str().unwrap_or_default(), branch = branch));
+    Ok(())
+}
+
+async fn explain_since_last(args: &Args) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let dir = platform::data_dir().ok_or(WtfError::NoConfigDir)?;
+    let key = bookmarks::repository_key(&repo);
+    let branch = bookmarks::current_branch(&repo);
+    let mut marks = Bookmarks::load(&dir);
+    let Some(mark) = marks.get(&key, &branch).cloned() else {
+        println!("{}", wtf::tr!("since-last-no-mark", branch = branch.as_str()));
+        return Ok(());
     };
-    
-    // Print results
-    println!("\n=== PROJECT DESCRIPTION ===\n");
-    println!("{}", project_description);
-    
-    println!("\n=== LAST {} COMMITS IN PLAIN LANGUAGE ===\n", args.num_commits);
-    println!("{}", commit_descriptions);
-    
-    println!("\n=== DETAILED ANALYSIS OF RECENT EDITS ===\n");
-    println!("{}", edits_description);
-    
+    let Some(ids) = bookmarks::commits_since(&repo, &mark)? else {
+        println!("{}", wtf::tr!("since-last-lost", branch = branch.as_str()));
+        return Ok(());
+    };
+    if ids.is_empty() {
+        println!("{}", wtf::tr!("since-last-nothing", date = git::format_date(mark.time)));
+        return Ok(());
+    }
+    let providers = task_providers(args)?;
+
+    let options = AnalysisOptions { num_commits: ids.len(), ..analysis_options(args, &repo)? };
+    let input = analysis::prepare_selected(&repo, &ids, &options)?;
+    report_on(&input, &providers, &options, args).await?;
+    // Only a report that made it out counts as looked at
+    marks.set(&key, &branch, Bookmark::now(ids[0]));
+    marks.save(&dir)
+}
+
+async fn explain_range(args: &Args, range: &RevRange) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let ids = git::range_commits(&repo, range)?;
+    if ids.is_empty() {
+        println!("{}", wtf::tr!("range-empty", range = range.to_string()));
+        return Ok(());
+    }
+    let providers = task_providers(args)?;
+
+    let options =
+        AnalysisOptions { num_commits: ids.len(), range: Some(range.clone()), ..analysis_options(args, &repo)? };
+    let input = analysis::prepare_selected(&repo, &ids, &options)?;
+    report_on(&input, &providers, &options, args).await
+}
+
+// From the base to the branch: `base`, base_branch from .wtf.toml or the
+// usual base, to `name` or the current branch
+fn branch_range(args: &Args, repo: &git2::Repository, name: Option<&str>, base: Option<&str>) -> Result<RevRange> {
+    let base = match base.map(str::to_string).or_else(|| args.defaults.base_branch.clone()) {
+        Some(base) => base,
+        None => git::default_base(repo).ok_or(WtfError::NoBaseBranch)?,
+    };
+    let name = name.map_or_else(|| bookmarks::current_branch(repo), str::to_string);
+    Ok(RevRange { from: base, to: name, symmetric: false })
+}
+
+async fn explain_branch(args: &Args, name: Option<&str>, base: Option<&str>) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let range = branch_range(args, &repo, name, base)?;
+    let ids = git::range_commits(&repo, &range)?;
+    if ids.is_empty() {
+        println!("{}", wtf::tr!("branch-nothing-new", branch = range.to.as_str(), base = range.from.as_str()));
+        return Ok(());
+    }
+    let providers = task_providers(args)?;
+
+    let options =
+        AnalysisOptions { num_commits: ids.len(), range: Some(range.clone()), ..analysis_options(args, &repo)? };
+    let input = analysis::prepare_branch(&repo, &range, &ids, &options)?;
+    report_on(&input, &providers, &options, args).await
+}
+
+async fn describe_pull_request(
+    args: &Args,
+    name: Option<&str>,
+    base: Option<&str>,
+    template: pr::Template,
+) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let range = branch_range(args, &repo, name, base)?;
+    let input = pr::prepare(&repo, &range)?;
+    if input.commits.is_empty() {
+        println!("{}", wtf::tr!("branch-nothing-new", branch = range.to.as_str(), base = range.from.as_str()));
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+    let max_diff_tokens = args.max_diff_tokens as usize;
+    check_safety_cap(&input.estimate(max_diff_tokens), provider.as_ref(), args)?;
+    let pr = pr::explain(&input, provider.as_ref(), max_diff_tokens).await?;
+    print!("{}", pr::render(&pr, template, output_format(args)));
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
-    analyze_repository(args).await
+async fn review_changes(args: &Args, range: Option<RevRange>) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let max_diff_tokens = args.max_diff_tokens as usize;
+    let input = match &range {
+        Some(range) => review::prepare_range(&repo, range, &args.exclude, max_diff_tokens)?,
+        None => review::prepare_staged(&repo, &args.exclude, max_diff_tokens)?,
+    };
+    if input.files.is_empty() {
+        println!("{}", wtf::tr!("review-nothing"));
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let review = review::explain(&input, provider.as_ref()).await?;
+    print!("{}", review::render(&review, output_format(args)));
+    Ok(())
+}
+
+async fn update_changelog(args: &Args, file: &Path, print: bool) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let path = repo.workdir().unwrap_or(repo.path()).join(file);
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let input = changelog::prepare(&repo, &existing)?;
+    if input.commits.is_empty() {
+        println!("{}", wtf::tr!("changelog-up-to-date"));
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let changelog = changelog::explain(&input, provider.as_ref()).await?;
+    if print {
+        print!("{}", changelog::render(&changelog, output_format(args)));
+        return Ok(());
+    }
+    std::fs::write(&path, changelog::update(&existing, &changelog))?;
+    println!("{}", wtf::tr!("changelog-updated", count = changelog.entries.len(), path = path.display()));
+    Ok(())
+}
+
+async fn write_release_notes(args: &Args, range: &RevRange) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = release_notes::prepare(&repo, range)?;
+    if input.commits.is_empty() {
+        println!("{}", wtf::tr!("range-empty", range = range.to_string()));
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+    let max_diff_tokens = args.max_diff_tokens as usize;
+    check_safety_cap(&input.estimate(max_diff_tokens), provider.as_ref(), args)?;
+    let notes = release_notes::explain(&input, provider.as_ref(), max_diff_tokens).await?;
+    print!("{}", release_notes::render(&notes, output_format(args)));
+    Ok(())
+}
+
+async fn suggest_version_bump(args: &Args) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = semver::prepare(&repo)?;
+    // Release automation reads a bump of none from JSON
+    if input.commits.is_empty() && output_format(args) != OutputFormat::Json {
+        println!("{}", wtf::tr!("semver-no-commits"));
+        return Ok(());
+    }
+    // Conventional headers and API changes may rate every commit offline
+    let estimate = input.estimate();
+    let provider = match estimate.requests {
+        0 => None,
+        _ => Some(hosted_provider(args)?),
+    };
+    if let Some(provider) = &provider {
+        check_safety_cap(&estimate, provider.as_ref(), args)?;
+    }
+    let suggestion = semver::explain(&input, provider.as_deref()).await?;
+    print!("{}", semver::render(&suggestion, output_format(args)));
+    Ok(())
+}
+
+async fn find_breaking_changes(args: &Args, range: &RevRange) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = breaking::prepare(&repo, range, &args.exclude)?;
+    if input.commits == 0 {
+        println!("{}", wtf::tr!("range-empty", range = range.to_string()));
+        return Ok(());
+    }
+    // Public API changes are found without the model
+    let estimate = input.estimate();
+    let provider = match estimate.requests {
+        0 => None,
+        _ => Some(hosted_provider(args)?),
+    };
+    if let Some(provider) = &provider {
+        check_safety_cap(&estimate, provider.as_ref(), args)?;
+    }
+    let changes = breaking::explain(&input, provider.as_deref()).await?;
+    print!("{}", breaking::render(&changes, output_format(args)));
+    Ok(())
+}
+
+async fn lint_commit_messages(args: &Args, range: &RevRange, suggest: bool) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = lint_commits::prepare(&repo, range)?;
+    if input.checked == 0 {
+        println!("{}", wtf::tr!("range-empty", range = range.to_string()));
+        return Ok(());
+    }
+    let estimate = input.estimate();
+    let provider = match suggest && estimate.requests > 0 {
+        true => Some(hosted_provider(args)?),
+        false => None,
+    };
+    if let Some(provider) = &provider {
+        check_safety_cap(&estimate, provider.as_ref(), args)?;
+    }
+    let report = lint_commits::explain(&input, provider.as_deref()).await?;
+    print!("{}", lint_commits::render(&report, output_format(args)));
+    match report.failures.len() {
+        0 => Ok(()),
+        count => Err(WtfError::LintFailed { count }),
+    }
 }
 
-// Helper trait to find files in a repository
-trait RepositoryExt {
-    fn find_file(&self, path: &str) -> Result<String>;
+async fn write_standup(args: &Args, since: Since, author: &str) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let author = Author::resolve(&repo, author)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let input = standup::prepare(&repo, &author, since.start(now))?;
+    if input.commits.is_empty() {
+        let date = git::format_date(input.since);
+        println!("{}", wtf::tr!("standup-nothing", author = author.name.as_str(), date = date));
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let standup = standup::explain(&input, provider.as_ref()).await?;
+    print!("{}", standup::render(&standup, output_format(args)));
+    Ok(())
 }
 
-impl RepositoryExt for Repository {
-    fn find_file(&self, path: &str) -> Result<String> {
-        let head = self.head()?;
-        let tree = head.peel_to_tree()?;
-        
-        let entry = tree.get_path(std::path::Path::new(path))?;
-        let object = entry.to_object(self)?;
-        let blob = object.as_blob().ok_or_else(|| anyhow::anyhow!("Not a blob"))?;
-        
-        let content = String::from_utf8_lossy(blob.content()).to_string();
-        Ok(content)
+async fn write_digest(args: &Args, period: Period) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let input = digest::prepare(&repo, period, period.days_ending(now))?;
+    if input.commits.is_empty() {
+        let (from, to) = (git::format_date(input.from), git::format_date(input.to));
+        println!("{}", wtf::tr!("digest-empty", from = from, to = to));
+        return Ok(());
     }
-}
\ No newline at end of file
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let digest = digest::explain(&input, provider.as_ref()).await?;
+    print!("{}", digest::render(&digest, output_format(args)));
+    Ok(())
+}
+
+async fn give_onboarding_tour(args: &Args) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = onboard::prepare(&repo)?;
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let tour = onboard::explain(&input, provider.as_ref()).await?;
+    print!("{}", onboard::render(&tour, output_format(args)));
+    Ok(())
+}
+
+async fn describe_architecture(args: &Args, depth: usize) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = arch::prepare(&repo, depth, &args.exclude)?;
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let architecture = arch::explain(&input, provider.as_ref()).await?;
+    print!("{}", arch::render(&architecture, output_format(args)));
+    Ok(())
+}
+
+async fn propose_readme(args: &Args, diff: bool) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = readme::prepare(&repo)?;
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let readme = readme::draft(&input, provider.as_ref()).await?;
+    print!("{}", readme::render(&readme, output_format(args), diff));
+    Ok(())
+}
+
+async fn answer_question(args: &Args, question: &str, pickaxe: &[String], commits: usize) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = ask::prepare(&repo, question, pickaxe, commits)?;
+    if input.is_empty() {
+        println!("{}", wtf::tr!("ask-nothing"));
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let answer = ask::explain(&input, provider.as_ref(), args.guard).await?;
+    print!("{}", ask::render(&answer, output_format(args), git::commit_url_base(&repo).as_deref()));
+    Ok(())
+}
+
+async fn chat_about_repository(args: &Args, commits: usize) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let mut chat = chat::Chat::start(&repo, commits, args.guard)?;
+    let provider = hosted_provider(args)?;
+
+    if let Some(dir) = platform::data_dir() {
+        let session = Session::new("chat", bookmarks::repository_key(&repo), git::commit_url_base(&repo));
+        chat.record_to(session, dir);
+    }
+    // Piped questions are answered one after another, without the prompt
+    let interactive = io::stdin().is_terminal();
+    if interactive {
+        println!("{}", wtf::tr!("chat-welcome", repo = chat.repo_name()));
+    }
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("wtf> ");
+            let _ = io::stdout().flush();
+        }
+        let Some(line) = lines.next() else { break };
+        let Some(command) = chat::Command::parse(&line?) else { continue };
+        // One failed answer should not end the chat
+        match chat.handle(command, provider.as_ref()).await {
+            Ok(Some(text)) => println!("{}\n", text.trim_end()),
+            Ok(None) => break,
+            Err(e) => eprintln!("{}: {}\n", wtf::tr!("error-label"), e),
+        }
+    }
+    if let Some(session) = chat.session().filter(|session| !session.entries.is_empty()) {
+        println!("{}", wtf::tr!("session-saved", id = session.id.as_str()));
+    }
+    Ok(())
+}
+
+async fn explain_file_history(args: &Args, path: &std::path::Path) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = timeline::prepare(&repo, &git::repo_relative_path(&repo, path))?;
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let timeline = timeline::run(&input, provider.as_ref()).await?;
+    print!("{}", timeline::render(&timeline, output_format(args)));
+    Ok(())
+}
+
+async fn explain_blame(args: &Args, path: &std::path::Path, lines: Option<LineRange>) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = blame::prepare(&repo, &git::repo_relative_path(&repo, path), lines)?;
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let blame = blame::explain(&input, provider.as_ref()).await?;
+    print!("{}", blame::render(&blame, output_format(args)));
+    Ok(())
+}
+
+async fn explain_state(args: &Args, summary: bool) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let mut report = state::StatusReport { state: state::collect(&repo)?, explanation: None };
+    if summary {
+        let provider = hosted_provider(args)?;
+        check_safety_cap(&report.state.estimate(), provider.as_ref(), args)?;
+        report.explanation = Some(state::explain(&report.state, provider.as_ref()).await?);
+    }
+    print!("{}", state::render(&report, output_format(args)));
+    Ok(())
+}
+
+async fn write_commit_message(args: &Args, edit: bool, commit: bool) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = uncommitted::prepare(&repo, Uncommitted::Staged, &args.exclude)?;
+    let recent = message::recent_subjects(&repo);
+    let provider = hosted_provider(args)?;
+    let max_diff_tokens = args.max_diff_tokens as usize;
+
+    check_safety_cap(&message::estimate(&input, &recent, max_diff_tokens), provider.as_ref(), args)?;
+    let mut text = message::generate(&input, &recent, provider.as_ref(), max_diff_tokens).await?;
+    if edit {
+        text = message::edit(&repo, &text, &message::editor(&repo))?;
+    }
+    let mut result = message::CommitMessage { message: text, commit: None };
+    if commit {
+        let id = message::commit(&repo, &result.message)?;
+        progress!("{}", wtf::tr!("progress-committed", sha = id.to_string()[..7].to_string()));
+        result.commit = Some(id.to_string());
+    }
+    print!("{}", message::render(&result, output_format(args)));
+    Ok(())
+}
+
+async fn find_culprit(args: &Args, symptom: &str, range: RevRange, run: &[String]) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let mut input = broke::prepare(&repo, symptom, &range)?;
+    if input.candidates.is_empty() {
+        println!("{}", wtf::tr!("broke-no-candidates", good = range.from.as_str(), bad = range.to.as_str()));
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    if !run.is_empty() {
+        let found = broke::bisect(&repo, &range, run)?;
+        input.bisected = found.and_then(|id| input.candidates.iter().find(|c| c.id == id)).map(|c| c.short_id.clone());
+    }
+    let verdict = broke::explain(&input, provider.as_ref()).await?;
+    print!("{}", broke::render(&verdict, output_format(args)));
+    Ok(())
+}
+
+async fn explain_reflog(args: &Args, limit: usize, summary: bool) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let input = reflog::prepare(&repo, limit, now)?;
+    if input.movements.is_empty() {
+        println!("{}", wtf::tr!("reflog-empty"));
+        return Ok(());
+    }
+    let mut report = reflog::ReflogReport { input, explanation: None };
+    if summary {
+        let provider = hosted_provider(args)?;
+        check_safety_cap(&report.input.estimate(), provider.as_ref(), args)?;
+        report.explanation = Some(reflog::explain(&report.input, provider.as_ref()).await?);
+    }
+    print!("{}", reflog::render(&report, output_format(args)));
+    Ok(())
+}
+
+async fn explain_conflicts(args: &Args, path: Option<&std::path::Path>, propose: bool) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let path = path.map(|path| git::repo_relative_path(&repo, path));
+    let input = conflict::prepare(&repo, path.as_deref())?;
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&input.estimate(propose), provider.as_ref(), args)?;
+    let report = conflict::explain(&input, provider.as_ref(), propose).await?;
+    print!("{}", conflict::render(&report, output_format(args)));
+    Ok(())
+}
+
+async fn explain_release(args: &Args, name: &str) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = release::prepare(&repo, name)?;
+    let provider = hosted_provider(args)?;
+    check_safety_cap(&input.estimate(), provider.as_ref(), args)?;
+    let release = release::explain(&input, provider.as_ref()).await?;
+    print!("{}", release::render(&release, output_format(args)));
+    Ok(())
+}
+
+async fn explain_stashes(args: &Args, summary: bool) -> Result<()> {
+    let mut repo = git::open_repository(&args.repo_path)?;
+    let stashes = stash::collect(&mut repo)?;
+    let mut report = stash::StashReport { stashes, explanation: None };
+    if summary && !report.stashes.is_empty() {
+        let provider = hosted_provider(args)?;
+        check_safety_cap(&stash::estimate(&report.stashes), provider.as_ref(), args)?;
+        report.explanation = Some(stash::explain(&report.stashes, provider.as_ref()).await?);
+    }
+    print!("{}", stash::render(&report, output_format(args)));
+    Ok(())
+}
+
+async fn manage_hooks(args: &Args, action: &HookAction) -> Result<()> {
+    match action {
+        HookAction::Install { hook, replace } => {
+            let repo = git::open_repository(&args.repo_path)?;
+            let path = hooks::install(&repo, *hook, &std::env::current_exe()?, *replace)?;
+            println!("{}", wtf::tr!("hook-installed", hook = hook.name(), path = path.display()));
+        }
+        HookAction::Uninstall { hook } => {
+            let repo = git::open_repository(&args.repo_path)?;
+            match hooks::uninstall(&repo, *hook)? {
+                Some(path) => println!("{}", wtf::tr!("hook-uninstalled", hook = hook.name(), path = path.display())),
+                None => println!("{}", wtf::tr!("hook-not-installed", hook = hook.name())),
+            }
+        }
+        HookAction::Run { hook, args: hook_args } => {
+            let result = match hook {
+                // The one hook meant to stop a commit
+                hooks::Hook::CommitMsg => {
+                    let Some(path) = hook_args.first() else { return Ok(()) };
+                    return hooks::lint(&std::fs::read_to_string(path)?);
+                }
+                hooks::Hook::PrepareCommitMsg => prefill_commit_message(args, hook_args).await,
+                hooks::Hook::PostMerge => summarize_merge(args, hook_args).await,
+            };
+            // These only help, so without an API key or on any failure Git carries on
+            if let Err(e) = result {
+                eprintln!("{}", wtf::tr!("hook-skipped", hook = hook.name(), reason = e.to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn prefill_commit_message(args: &Args, hook_args: &[String]) -> Result<()> {
+    let Some(path) = hook_args.first() else { return Ok(()) };
+    if !hooks::should_prefill(hook_args.get(1).map(String::as_str)) {
+        return Ok(());
+    }
+    let repo = hooks::open_repository(&args.repo_path)?;
+    let input = uncommitted::prepare(&repo, Uncommitted::Staged, &args.exclude)?;
+    let recent = message::recent_subjects(&repo);
+    let provider = hosted_provider(args)?;
+    let max_diff_tokens = args.max_diff_tokens as usize;
+
+    check_safety_cap(&message::estimate(&input, &recent, max_diff_tokens), provider.as_ref(), args)?;
+    let text = message::generate(&input, &recent, provider.as_ref(), max_diff_tokens).await?;
+    hooks::prefill(Path::new(path), &text)
+}
+
+async fn summarize_merge(args: &Args, hook_args: &[String]) -> Result<()> {
+    // A squash merge has not committed anything yet
+    if hook_args.first().is_some_and(|squash| squash == "1") {
+        return Ok(());
+    }
+    explain_range(args, &RevRange { from: "ORIG_HEAD".to_string(), to: "HEAD".to_string(), symmetric: false }).await
+}
+
+async fn explain_single_commit(args: &Args, rev: &str) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let commit = git::resolve_commit(&repo, rev)?;
+    let provider = hosted_provider(args)?;
+    let max_diff_tokens = args.max_diff_tokens as usize;
+
+    let estimate = analysis::estimate_commit(&repo, &commit, max_diff_tokens, &args.exclude)?;
+    check_safety_cap(&estimate, provider.as_ref(), args)?;
+    let explanation =
+        analysis::explain_commit(&repo, &commit, provider.as_ref(), max_diff_tokens, &args.exclude).await?;
+    let report = commit::CommitReport::new(&repo, &commit, explanation)?;
+    print!("{}", commit::render(&report, output_format(args)));
+    Ok(())
+}
+
+async fn explain_uncommitted(args: &Args, which: Uncommitted) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let input = uncommitted::prepare(&repo, which, &args.exclude)?;
+    let provider = hosted_provider(args)?;
+    let max_diff_tokens = args.max_diff_tokens as usize;
+
+    check_safety_cap(&input.estimate(max_diff_tokens), provider.as_ref(), args)?;
+    let report = uncommitted::explain(&input, provider.as_ref(), max_diff_tokens).await?;
+    print!("{}", uncommitted::render(&report, output_format(args)));
+    Ok(())
+}
+
+fn suggest_owners(args: &Args, depth: usize, commits: usize, active_days: u64) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let history = ownership::collect(&repo, commits)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let areas = ownership::owners_by_area(&history, depth, now - active_days as i64 * 86_400);
+
+    let orphaned = areas.iter().filter(|area| area.is_orphaned()).count();
+    if orphaned > 0 {
+        eprintln!("{}", wtf::tr!("owners-orphaned", count = orphaned, days = active_days));
+    }
+    match output_format(args) {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&areas).expect("owners serialize to JSON")),
+        _ => print!("{}", ownership::codeowners(&areas, &history)),
+    }
+    Ok(())
+}
+
+fn explain_rules(args: &Args, path: Option<&std::path::Path>, commits: usize) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let mut rules = attributes::Rules::new(&repo);
+    let explained = match path {
+        Some(path) => vec![rules.explain(&git::repo_relative_path(&repo, path))?],
+        None => {
+            let mut explained = Vec::new();
+            for path in attributes::recently_changed(&repo, commits)? {
+                let path_rules = rules.explain(&path)?;
+                if path_rules.has_effect() {
+                    explained.push(path_rules);
+                }
+            }
+            if explained.is_empty() && args.format != OutputFormat::Json {
+                println!("{}", wtf::tr!("attrs-nothing", count = commits));
+                return Ok(());
+            }
+            explained
+        }
+    };
+    print!("{}", attributes::render(&explained, output_format(args)));
+    Ok(())
+}
+
+async fn draft_adrs(args: &Args, commits: usize, top: usize, out_dir: Option<&std::path::Path>) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let pivots = adr::find_pivots(&repo, commits, top)?;
+    if pivots.is_empty() {
+        match output_format(args) {
+            OutputFormat::Json => println!("[]"),
+            _ => println!("{}", wtf::tr!("adr-none", count = commits)),
+        }
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&adr::estimate(&pivots), provider.as_ref(), args)?;
+    let adrs = adr::draft(pivots, provider.as_ref()).await?;
+    let commit_url = git::commit_url_base(&repo);
+    match out_dir {
+        Some(dir) => {
+            for path in adr::write_all(&adrs, dir, commit_url.as_deref())? {
+                println!("{}", wtf::tr!("adr-written", path = path.display()));
+            }
+        }
+        None => print!("{}", adr::render(&adrs, output_format(args), commit_url.as_deref())),
+    }
+    Ok(())
+}
+
+async fn find_similar(args: &Args, target: &similar::Target, commits: usize, top: usize) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let Some(change) = similar::change(&repo, target)? else {
+        println!("{}", wtf::tr!("similar-nothing-staged"));
+        return Ok(());
+    };
+    let matches = similar::find_similar(&repo, &change, commits, top)?;
+    if matches.is_empty() {
+        match output_format(args) {
+            OutputFormat::Json => println!("[]"),
+            _ => println!("{}", wtf::tr!("similar-none", count = commits)),
+        }
+        return Ok(());
+    }
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&similar::estimate(&change, &matches), provider.as_ref(), args)?;
+    let similar = similar::summarize(change, matches, provider.as_ref()).await?;
+    let commit_url = git::commit_url_base(&repo);
+    print!("{}", similar::render(&similar, output_format(args), commit_url.as_deref()));
+    Ok(())
+}
+
+async fn explain_config(args: &Args) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let setup = repo_config::collect(&repo)?;
+    let provider = hosted_provider(args)?;
+
+    check_safety_cap(&repo_config::estimate(&setup), provider.as_ref(), args)?;
+    let explained = repo_config::explain(setup, provider.as_ref()).await?;
+    print!("{}", repo_config::render(&explained, output_format(args)));
+    Ok(())
+}
+
+async fn summarize_workspace(args: &Args, dir: &Path, depth: usize, summary: bool) -> Result<()> {
+    let repositories = workspace::scan(dir, depth)?;
+    if repositories.is_empty() {
+        match output_format(args) {
+            OutputFormat::Json => println!("[]"),
+            _ => println!("{}", wtf::tr!("workspace-none", path = dir.display())),
+        }
+        return Ok(());
+    }
+    let mut report = workspace::Workspace { repositories, summary: None };
+    if summary {
+        let provider = hosted_provider(args)?;
+        check_safety_cap(&workspace::estimate(&report.repositories), provider.as_ref(), args)?;
+        report.summary = Some(workspace::summarize(&report.repositories, provider.as_ref()).await?);
+    }
+    print!("{}", workspace::render(&report, output_format(args)));
+    Ok(())
+}
+
+struct BusFactorOptions {
+    files: bool,
+    depth: usize,
+    commits: usize,
+    threshold: f64,
+    top: usize,
+    summary: bool,
+}
+
+async fn find_bus_factor(args: &Args, options: &BusFactorOptions) -> Result<()> {
+    let repo = git::open_repository(&args.repo_path)?;
+    let history = ownership::collect(&repo, options.commits)?;
+    let group_of = |path: &str| {
+        Some(if options.files { path.to_string() } else { ownership::area_of(path, options.depth) })
+    };
+
+    let mut report = bus_factor::analyze(&history, group_of, options.threshold);
+    report.concentrations.truncate(options.top);
+    if options.summary && !report.concentrations.is_empty() {
+        let provider = hosted_provider(args)?;
+
+        check_safety_cap(&bus_factor::estimate(&history, &report.concentrations, group_of), provider.as_ref(), args)?;
+        let knowledge =
+            bus_factor::summarize_knowledge(&history, &report.concentrations, group_of, provider.as_ref()).await?;
+        report.knowledge = Some(knowledge);
+    }
+    print!("{}", bus_factor::render(&report, output_format(args)));
+    Ok(())
+}
+
+// Don't let a typo like --num-commits 5000 turn into a huge bill
+fn check_safety_cap(estimate: &RunEstimate, provider: &dyn LlmProvider, args: &Args) -> Result<()> {
+    let cost = provider.price().map(|price| cost::estimate(estimate, price));
+    match cost {
+        Some(cost) => progress!(
+            "{}",
+            wtf::tr!(
+                "progress-estimate",
+                requests = estimate.requests,
+                tokens = estimate.prompt_tokens,
+                cost = cost::format_dollars(cost)
+            )
+        ),
+        None => progress!(
+            "{}",
+            wtf::tr!("progress-estimate-unpriced", requests = estimate.requests, tokens = estimate.prompt_tokens)
+        ),
+    }
+    // Nothing is sent, so there is nothing to pay for
+    if args.dry_run.is_some() {
+        return Ok(());
+    }
+
+    let cap = SafetyCap {
+        max_requests: args.max_requests,
+        max_prompt_tokens: args.max_prompt_tokens,
+    };
+    if cap.is_exceeded_by(estimate) && !args.force && !confirm_large_run(estimate) {
+        return Err(WtfError::SafetyCapExceeded {
+            requests: estimate.requests,
+            prompt_tokens: estimate.prompt_tokens,
+        });
+    }
+    if let (Some(cost), Some(max_cost)) = (cost, args.max_cost) {
+        if cost > max_cost && !args.force && !confirm_costly_run(cost, max_cost) {
+            return Err(WtfError::CostCapExceeded { cost, max_cost });
+        }
+    }
+    Ok(())
+}
+
+fn output_format(args: &Args) -> OutputFormat {
+    match args.format {
+        OutputFormat::Terminal if args.accessible => OutputFormat::Plain,
+        format => format,
+    }
+}
+
+fn browse_commits(args: &Args, limit: usize) -> Result<()> {
+    let repo_path = &args.repo_path;
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(WtfError::NotInteractive);
+    }
+    let repo = git::open_repository(repo_path)?;
+    let commits = tui::load_commits(&repo, limit)?;
+    let provider = hosted_provider(args)?;
+
+    // Progress lines on stderr would scribble over the screen
+    progress::set_quiet(true);
+    let runtime = tokio::runtime::Handle::current();
+    let mut source = tui::RepoSource::new(&repo, provider.as_ref(), runtime);
+    source.set_max_diff_tokens(args.max_diff_tokens as usize);
+    source.set_exclude(&args.exclude);
+    if let Some(dir) = platform::data_dir() {
+        let session = Session::new("tui", bookmarks::repository_key(&repo), git::commit_url_base(&repo));
+        source.record_to(session, dir);
+    }
+    tokio::task::block_in_place(|| {
+        let mut terminal = tui::Terminal::enter()?;
+        tui::run(&mut terminal, &mut tui::App::new(commits), &mut source)
+    })?;
+    if let Some(session) = source.session().filter(|session| !session.entries.is_empty()) {
+        println!("{}", wtf::tr!("session-saved", id = session.id.as_str()));
+    }
+    Ok(())
+}
+
+async fn watch_repository(args: &Args, interval: u64, max_commits: usize) -> Result<()> {
+    let repo_path = &args.repo_path;
+    let repo = git::open_repository(repo_path)?;
+    let provider = hosted_provider(args)?;
+    let mut watcher = RefWatcher::new(&repo)?;
+    eprintln!("{}", wtf::tr!("watch-started", path = repo_path.display()));
+    let max_diff_tokens = args.max_diff_tokens as usize;
+
+    // One failed check or explanation should not end the session
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+        let (commits, estimate) = match watched_commits(&repo, &mut watcher, max_commits, args) {
+            Ok(found) => found,
+            Err(e) => {
+                eprintln!("{}: {}\n", wtf::tr!("error-label"), e);
+                continue;
+            }
+        };
+        if commits.is_empty() {
+            continue;
+        }
+        if let Err(e) = check_safety_cap(&estimate, provider.as_ref(), args) {
+            eprintln!("{}: {}\n", wtf::tr!("error-label"), e);
+            continue;
+        }
+        for commit in commits {
+            let summary = git::commit_message(&commit).lines().next().unwrap_or_default().to_string();
+            let author = git::decode_text(commit.author().name_bytes(), commit.message_encoding());
+            println!("== {} {} ({})", &commit.id().to_string()[..7], summary, author);
+            match analysis::explain_commit(&repo, &commit, provider.as_ref(), max_diff_tokens, &args.exclude).await {
+                Ok(explanation) => println!("{}\n", explanation.trim_end()),
+                Err(e) => eprintln!("{}: {}\n", wtf::tr!("error-label"), e),
+            }
+            let _ = io::stdout().flush();
+        }
+    }
+}
+
+// The commits a check of `watcher` finds, newest `max_commits` only, and
+// what explaining them will cost
+fn watched_commits<'r>(
+    repo: &'r git2::Repository,
+    watcher: &mut RefWatcher,
+    max_commits: usize,
+    args: &Args,
+) -> Result<(Vec<git2::Commit<'r>>, RunEstimate)> {
+    let mut oids = watcher.poll(repo)?;
+    // A pull can bring in hundreds of commits
+    if oids.len() > max_commits {
+        let skipped = oids.len() - max_commits;
+        eprintln!("{}", wtf::tr!("watch-skipped", count = skipped, max = max_commits));
+        oids.drain(..skipped);
+    }
+    let mut commits = Vec::new();
+    let mut estimate = RunEstimate { requests: 0, prompt_tokens: 0 };
+    for oid in oids {
+        let commit = repo.find_commit(oid)?;
+        let cost = analysis::estimate_commit(repo, &commit, args.max_diff_tokens as usize, &args.exclude)?;
+        estimate.requests += cost.requests;
+        estimate.prompt_tokens += cost.prompt_tokens;
+        commits.push(commit);
+    }
+    Ok((commits, estimate))
+}
+
+fn manage_sessions(args: &Args, action: &SessionAction) -> Result<()> {
+    let dir = platform::data_dir().ok_or(WtfError::NoConfigDir)?;
+    match action {
+        SessionAction::List => {
+            let sessions = session::list(&dir);
+            match output_format(args) {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&sessions).expect("sessions serialize to JSON")),
+                _ if sessions.is_empty() => println!("{}", wtf::tr!("session-none")),
+                _ => print!("{}", session::render_list(&sessions)),
+            }
+        }
+        SessionAction::Export { id } => print!("{}", session::render(&Session::load(&dir, id)?, output_format(args))),
+    }
+    Ok(())
+}
+
+// Store or remove the chosen provider's key in the system keyring
+#[cfg(feature = "online")]
+fn manage_auth(args: &Args, action: &AuthAction) -> Result<()> {
+    let kind = provider_kind(args)?;
+    let Some(var) = kind.api_key_var() else {
+        println!("{}", wtf::tr!("auth-no-key", provider = kind.name()));
+        return Ok(());
+    };
+    match action {
+        AuthAction::Login => {
+            let key = match &args.api_key {
+                Some(key) => key.clone(),
+                None => read_secret(&wtf::tr!("auth-prompt", var = var))?,
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                println!("{}", wtf::tr!("auth-empty"));
+                return Ok(());
+            }
+            keyring::store(var, key)?;
+            println!("{}", wtf::tr!("auth-stored", var = var));
+        }
+        AuthAction::Logout => {
+            keyring::delete(var)?;
+            println!("{}", wtf::tr!("auth-removed", var = var));
+        }
+    }
+    Ok(())
+}
+
+// Keys are only read from the keyring to talk to a provider
+#[cfg(not(feature = "online"))]
+fn manage_auth(_args: &Args, _action: &AuthAction) -> Result<()> {
+    Err(WtfError::NetworkDisabled)
+}
+
+// Read a line from the terminal without echoing it, or from stdin when piped
+#[cfg(feature = "online")]
+fn read_secret(prompt: &str) -> Result<String> {
+    let mut line = String::new();
+    if !io::stdin().is_terminal() {
+        io::stdin().read_line(&mut line)?;
+        return Ok(line);
+    }
+    eprint!("{} ", prompt);
+    let _ = io::stderr().flush();
+    #[cfg(unix)]
+    {
+        // SAFETY: termios calls on stdin with a struct tcgetattr filled in
+        unsafe {
+            let mut saved: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut saved) == 0 {
+                let mut hidden = saved;
+                hidden.c_lflag &= !libc::ECHO;
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &hidden);
+                let read = io::stdin().read_line(&mut line);
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &saved);
+                eprintln!();
+                read?;
+                return Ok(line);
+            }
+        }
+    }
+    io::stdin().read_line(&mut line)?;
+    Ok(line)
+}
+
+// Fill in what the command line left at its defaults from config.toml and .wtf.toml
+fn apply_defaults(args: &mut Args, matches: &ArgMatches) -> Result<()> {
+    let defaults = Defaults::for_repository(platform::config_dir().as_deref(), &args.repo_path)?;
+    let unset = |id: &str| matches.value_source(id) == Some(ValueSource::DefaultValue);
+    if let Some(num_commits) = defaults.num_commits.filter(|_| unset("num_commits")) {
+        args.num_commits = num_commits;
+    }
+    if let Some(format) = defaults.format.filter(|_| unset("format")) {
+        args.format = format;
+    }
+    // Both lists count, the configured globs first
+    args.exclude.splice(0..0, defaults.exclude.iter().cloned());
+    // Section headers follow the report's language, unless --ui-lang says otherwise
+    if let Some(lang) = args.lang.as_ref().or(defaults.lang.as_ref()).filter(|_| args.ui_lang.is_none()) {
+        i18n::set_language(lang);
+    }
+    args.defaults = defaults;
+    Ok(())
+}
+
+// Telemetry goes through the proxy the provider would: --proxy, then its config section
+#[cfg(feature = "online")]
+fn telemetry_proxy(args: &Args, config_dir: &Path) -> Option<String> {
+    args.proxy.clone().or_else(|| {
+        let kind = provider_kind(args).ok()?;
+        ProviderConfig::load(config_dir, kind.name()).get("proxy").map(str::to_string)
+    })
+}
+
+#[cfg(not(feature = "online"))]
+fn telemetry_proxy(_args: &Args, _config_dir: &Path) -> Option<String> {
+    None
+}
+
+fn manage_telemetry(action: TelemetryAction) -> Result<()> {
+    let dir = platform::config_dir().ok_or(WtfError::NoConfigDir)?;
+    match action {
+        TelemetryAction::On | TelemetryAction::Off => {
+            let enabled = matches!(action, TelemetryAction::On);
+            telemetry::Settings { enabled }.save(&dir)?;
+            println!("{}", wtf::tr!(if enabled { "telemetry-on" } else { "telemetry-off" }));
+        }
+        TelemetryAction::Status => {
+            let key = if telemetry::is_enabled(&dir) { "telemetry-status-on" } else { "telemetry-status-off" };
+            println!("{}", wtf::tr!(key));
+            if telemetry::endpoint().is_none() {
+                println!("{}", wtf::tr!("telemetry-no-endpoint"));
+            }
+            println!("{}", wtf::tr!("telemetry-fields"));
+        }
+    }
+    Ok(())
+}
+
+fn show_crash_report(submit: bool) -> Result<()> {
+    let dir = crash_dir().ok_or(WtfError::NoConfigDir)?;
+    let Some(latest) = crash::reports(&dir).into_iter().next() else {
+        println!("{}", wtf::tr!("crash-none"));
+        return Ok(());
+    };
+
+    if !submit {
+        println!("{}\n", wtf::tr!("crash-latest", path = latest.display()));
+        print!("{}", std::fs::read_to_string(&latest)?);
+        println!("\n{}", wtf::tr!("crash-submit-hint"));
+        return Ok(());
+    }
+
+    let command = crash::issue_command(&latest);
+    let status = std::process::Command::new(&command[0]).args(&command[1..]).status();
+    if !status.is_ok_and(|s| s.success()) {
+        eprintln!(
+            "{}",
+            wtf::tr!(
+                "crash-gh-missing",
+                url = format!("https://github.com/{}/issues/new", crash::ISSUES_REPO),
+                path = latest.display()
+            )
+        );
+    }
+    Ok(())
+}
+
+fn crash_dir() -> Option<PathBuf> {
+    platform::data_dir().map(|dir| dir.join("crashes"))
+}
+
+// Ask on the terminal whether to go ahead with a large run; never when not interactive
+fn confirm_large_run(estimate: &RunEstimate) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+
+    eprint!(
+        "{} ",
+        wtf::tr!("confirm-large-run", requests = estimate.requests, tokens = estimate.prompt_tokens)
+    );
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn confirm_costly_run(cost: f64, max_cost: f64) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+
+    eprint!(
+        "{} ",
+        wtf::tr!("confirm-costly-run", cost = cost::format_dollars(cost), max = cost::format_dollars(max_cost))
+    );
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    platform::enable_ansi_colors();
+    if let Some(dir) = crash_dir() {
+        crash::install_panic_hook(dir);
+    }
+
+    let parsed = Args::command().try_get_matches().and_then(|matches| Ok((Args::from_arg_matches(&matches)?, matches)));
+    let (mut args, matches) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() {
+                ExitCode::from(exit_code::USAGE)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+
+    // An explicit --ui-lang must exist; a locale we have no catalog for is fine
+    if let Some(lang) = &args.ui_lang {
+        if !i18n::set_language(lang) {
+            eprintln!("{}", wtf::tr!("unsupported-ui-lang", lang = lang));
+        }
+    } else if let Some(lang) = i18n::language_from_env() {
+        i18n::set_language(&lang);
+    }
+    if let Err(e) = apply_defaults(&mut args, &matches) {
+        return ExitCode::from(report_error(&e.into()));
+    }
+    if let Some(max_reasks) = args.max_reasks {
+        structured::set_max_reasks(max_reasks);
+    }
+    // --path is global so it can go anywhere, but most commands would ignore it
+    if !args.paths.is_empty() && !Command::takes_paths(args.command.as_ref()) {
+        let name = Command::name(args.command.as_ref());
+        let message = wtf::tr!("paths-unsupported", command = name);
+        let _ = Args::command().error(clap::error::ErrorKind::ArgumentConflict, message).print();
+        return ExitCode::from(exit_code::USAGE);
+    }
+    // Subcommands all need a model (or nothing offline could stand in for one)
+    if args.offline && args.command.is_some() {
+        let name = Command::name(args.command.as_ref());
+        let message = wtf::tr!("offline-unsupported", command = name);
+        let _ = Args::command().error(clap::error::ErrorKind::ArgumentConflict, message).print();
+        return ExitCode::from(exit_code::USAGE);
+    }
+
+    let started = Instant::now();
+    let command = Command::name(args.command.as_ref());
+    // Only stored for a crash report, so harmless for commands without a repository
+    crash::set_repository(&args.repo_path);
+    let result: anyhow::Result<()> = match args.command {
+        Some(Command::Auth { ref action }) => manage_auth(&args, action),
+        Some(Command::Telemetry { action }) => manage_telemetry(action),
+        Some(Command::CrashReport { submit }) => show_crash_report(submit),
+        Some(Command::Tui { limit }) => browse_commits(&args, limit),
+        Some(Command::Pick { limit }) => pick_and_analyze(&args, limit).await,
+        Some(Command::Owners { depth, commits, active_days }) => suggest_owners(&args, depth, commits, active_days),
+        Some(Command::BusFactor { files, depth, commits, threshold, top, no_summary }) => {
+            let options = BusFactorOptions { files, depth, commits, threshold, top, summary: !no_summary };
+            find_bus_factor(&args, &options).await
+        }
+        Some(Command::Timeline { ref path }) => explain_file_history(&args, path).await,
+        Some(Command::Watch { interval, max_commits }) => watch_repository(&args, interval, max_commits as usize).await,
+        Some(Command::Attrs { ref path, commits }) => explain_rules(&args, path.as_deref(), commits),
+        Some(Command::Adr { commits, top, ref out_dir }) => draft_adrs(&args, commits, top, out_dir.as_deref()).await,
+        Some(Command::Similar { ref rev, staged, commits, top }) => {
+            let target = match rev {
+                Some(rev) if !staged => similar::Target::Commit(rev.clone()),
+                _ => similar::Target::Staged,
+            };
+            find_similar(&args, &target, commits, top).await
+        }
+        Some(Command::Mark { ref rev }) => set_bookmark(&args, rev),
+        Some(Command::SinceLast) => explain_since_last(&args).await,
+        Some(Command::Session { ref action }) => manage_sessions(&args, action),
+        Some(Command::ConfigExplain) => explain_config(&args).await,
+        Some(Command::Workspace { ref dir, depth, no_summary }) => {
+            let dir = dir.as_deref().unwrap_or(Path::new("."));
+            summarize_workspace(&args, dir, depth, !no_summary).await
+        }
+        Some(Command::Commit { ref rev }) => explain_single_commit(&args, rev).await,
+        Some(Command::Diff { staged, unstaged }) => {
+            let which = match (staged, unstaged) {
+                (true, _) => Uncommitted::Staged,
+                (_, true) => Uncommitted::Unstaged,
+                _ => Uncommitted::All,
+            };
+            explain_uncommitted(&args, which).await
+        }
+        Some(Command::Range { ref range }) => explain_range(&args, &RevRange::parse(range)).await,
+        Some(Command::Branch { ref name, ref base }) => explain_branch(&args, name.as_deref(), base.as_deref()).await,
+        Some(Command::Blame { ref path, lines }) => explain_blame(&args, path, lines).await,
+        Some(Command::Status { no_summary }) => explain_state(&args, !no_summary).await,
+        Some(Command::Msg { edit, commit }) => write_commit_message(&args, edit, commit).await,
+        Some(Command::Broke { ref symptom, ref good, ref bad, ref run }) => {
+            find_culprit(&args, symptom, RevRange { from: good.clone(), to: bad.clone(), symmetric: false }, run).await
+        }
+        Some(Command::Reflog { limit, no_summary }) => explain_reflog(&args, limit, !no_summary).await,
+        Some(Command::Conflict { ref path, propose }) => explain_conflicts(&args, path.as_deref(), propose).await,
+        Some(Command::Tag { ref name }) => explain_release(&args, name).await,
+        Some(Command::Stash { no_summary }) => explain_stashes(&args, !no_summary).await,
+        Some(Command::Pr { ref name, ref base, template }) => {
+            describe_pull_request(&args, name.as_deref(), base.as_deref(), template).await
+        }
+        Some(Command::Review { ref range, staged }) => {
+            review_changes(&args, range.as_deref().filter(|_| !staged).map(RevRange::parse)).await
+        }
+        Some(Command::Changelog { ref file, print }) => update_changelog(&args, file, print).await,
+        Some(Command::ReleaseNotes { ref range }) => write_release_notes(&args, &RevRange::parse(range)).await,
+        Some(Command::Semver) => suggest_version_bump(&args).await,
+        Some(Command::Breaking { ref range }) => find_breaking_changes(&args, &RevRange::parse(range)).await,
+        Some(Command::LintCommits { ref range, suggest }) => {
+            lint_commit_messages(&args, &RevRange::parse(range), suggest).await
+        }
+        Some(Command::Standup { since, ref author }) => write_standup(&args, since, author).await,
+        Some(Command::Digest { period }) => write_digest(&args, period).await,
+        Some(Command::Onboard) => give_onboarding_tour(&args).await,
+        Some(Command::Arch { depth }) => describe_architecture(&args, depth).await,
+        Some(Command::Readme { diff }) => propose_readme(&args, diff).await,
+        Some(Command::Ask { ref question, ref pickaxe, commits }) => {
+            answer_question(&args, question, pickaxe, commits).await
+        }
+        Some(Command::Chat { commits }) => chat_about_repository(&args, commits).await,
+        Some(Command::Hook { ref action }) => manage_hooks(&args, action).await,
+        None => analyze_repository(&args).await,
+    }
+    .map_err(Into::into);
+    let redacted = redact::found();
+    if !redacted.is_empty() {
+        let rules: Vec<String> = redacted.iter().map(|(rule, count)| format!("{} ({})", rule, count)).collect();
+        let count: usize = redacted.values().sum();
+        progress!("{}", wtf::tr!("redaction-summary", count = count, rules = rules.join(", ")));
+    }
+    let total = cost::total();
+    if total.requests > 0 {
+        progress!("{}", cost::summary(&total));
+    }
+    let code = match result {
+        Ok(()) => 0,
+        Err(err) => {
+            let code = report_error(&err);
+            // Errors we have no specific exit code for are likely bugs
+            if code == exit_code::FAILURE {
+                save_crash_report(&err);
+            }
+            code
+        }
+    };
+
+    if let Some(dir) = platform::config_dir() {
+        let proxy = telemetry_proxy(&args, &dir);
+        telemetry::record(&dir, &Event::new(command, started.elapsed(), code), proxy).await;
+    }
+    ExitCode::from(code)
+}
+
+fn save_crash_report(err: &anyhow::Error) {
+    let Some(dir) = crash_dir() else { return };
+    if let Ok(path) = CrashReport::capture(&format!("{:#}", err), None).write(&dir) {
+        eprintln!("{}", wtf::tr!("crash-saved", path = path.display()));
+    }
+}
+
+// Print an error (plus a hint when we have one) and pick the matching exit code
+fn report_error(err: &anyhow::Error) -> u8 {
+    eprintln!("{}: {}", wtf::tr!("error-label"), err);
+    match err.downcast_ref::<WtfError>() {
+        Some(wtf_err) => {
+            if let Some(hint) = wtf_err.hint() {
+                eprintln!("{}: {}", wtf::tr!("hint-label"), hint);
+            }
+            wtf_err.exit_code()
+        }
+        None => exit_code::FAILURE,
+    }
+}
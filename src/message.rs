@@ -0,0 +1,154 @@
+//! `wtf msg`: a commit message for the staged changes.
+//!
+//! The model writes a Conventional Commits message from the staged patch,
+//! matching the style of the latest commit messages. A reply whose header is
+//! not conventional is sent back once for correcting. The message can then
+//! be edited the way `git commit` does it, and committed.
+
+use crate::analysis::{RunEstimate, Task};
+use crate::conventional;
+use crate::conversation::Conversation;
+use crate::error::{Result, WtfError};
+use crate::generated;
+use crate::git;
+use crate::platform;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::summarize;
+use crate::tokens;
+use crate::uncommitted::UncommittedInput;
+use git2::{Oid, Repository};
+use serde::Serialize;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that writes clear, accurate Git commit messages.";
+
+const MSG_PROMPT: &str = "Write a commit message for the staged changes below, in the Conventional Commits format. The first line is the header, `type(scope): description`, at most 72 characters: the type is one of feat, fix, docs, style, refactor, perf, test, build, ci or chore, the scope is optional and the description is in the imperative mood without a full stop. Mark breaking changes with `!` before the colon and a `BREAKING CHANGE:` footer. After a blank line, a body wrapped at 72 characters explains what changed and why, unless the change is trivial. Match the style of the recent commit messages given, like the scopes they use. Reply with only the message, without code fences.";
+
+const NOT_CONVENTIONAL_PROMPT: &str = "The first line of that message is not a Conventional Commits header like `fix(parser): handle empty files`. Reply with the corrected message only.";
+
+// How many recent commit subjects are shown for style
+const RECENT_SUBJECTS: usize = 10;
+
+/// The subjects of the latest commits on HEAD, newest first; none before
+/// the first commit.
+pub fn recent_subjects(repo: &Repository) -> Vec<String> {
+    let Ok((commits, _)) = git::select_commits(repo, RECENT_SUBJECTS) else { return Vec::new() };
+    commits.iter().map(|commit| git::commit_message(commit).lines().next().unwrap_or_default().to_string()).collect()
+}
+
+fn question(patch: &str, recent: &[String]) -> String {
+    let mut out = MSG_PROMPT.to_string();
+    if !recent.is_empty() {
+        out.push_str(&format!("\n\nRecent commit messages:\n- {}", recent.join("\n- ")));
+    }
+    out.push_str(&format!("\n\nStaged changes:\n\n{}", patch));
+    out
+}
+
+/// Estimate the requests [`generate`] will send.
+pub fn estimate(input: &UncommittedInput, recent: &[String], max_diff_tokens: usize) -> RunEstimate {
+    let mut estimate = RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) };
+    if summarize::is_large_diff(&input.patch, max_diff_tokens) {
+        let parts = summarize::estimate_diff(&input.patch, max_diff_tokens);
+        estimate.requests += parts.requests;
+        estimate.prompt_tokens += parts.prompt_tokens + tokens::estimate(&question("", recent));
+        estimate.prompt_tokens += parts.requests * summarize::EXPECTED_SUMMARY_TOKENS;
+    } else {
+        estimate.prompt_tokens += tokens::estimate(&question(&input.patch, recent));
+    }
+    estimate
+}
+
+// The message in a reply, without code fences
+pub(crate) fn clean(reply: &str) -> String {
+    let reply = reply.trim();
+    let reply = match reply.strip_prefix("```") {
+        Some(fenced) => fenced.split_once('\n').map_or("", |(_, rest)| rest).trim_end().trim_end_matches("```"),
+        None => reply,
+    };
+    reply.trim().to_string() + "\n"
+}
+
+/// Ask the model for a commit message for the staged changes in `input`,
+/// in the style of the `recent` commit subjects. A patch above
+/// `max_diff_tokens` is summarized in parts first.
+pub async fn generate(
+    input: &UncommittedInput,
+    recent: &[String],
+    provider: &dyn LlmProvider,
+    max_diff_tokens: usize,
+) -> Result<String> {
+    let mut patch = input.patch.clone();
+    if summarize::is_large_diff(&patch, max_diff_tokens) {
+        patch = summarize::summarize_diff(provider, Task::Edits, &patch, max_diff_tokens).await?;
+    }
+    if !input.churn.is_empty() {
+        patch.push('\n');
+        patch.push_str(&generated::prompt_summary(&input.churn));
+    }
+    let mut conversation = Conversation::new(SYSTEM_PROMPT);
+    let message = clean(&conversation.ask(provider, question(&patch, recent)).await?);
+    if conventional::parse(&message).is_some() {
+        return Ok(message);
+    }
+    Ok(clean(&conversation.ask(provider, NOT_CONVENTIONAL_PROMPT).await?))
+}
+
+/// The editor Git would use: `GIT_EDITOR`, `core.editor`, `VISUAL`, then
+/// `EDITOR`, or `vi`.
+pub fn editor(repo: &Repository) -> String {
+    let var = |name: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty());
+    var("GIT_EDITOR")
+        .or_else(|| repo.config().ok()?.get_string("core.editor").ok())
+        .or_else(|| var("VISUAL"))
+        .or_else(|| var("EDITOR"))
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Let the user edit `message` in `editor`, from `COMMIT_EDITMSG` in the
+/// Git directory like `git commit` does. Lines starting with `#` are
+/// dropped; an empty message is an error.
+pub fn edit(repo: &Repository, message: &str, editor: &str) -> Result<String> {
+    let path = repo.path().join("COMMIT_EDITMSG");
+    let help = "\n# Edit the message wtf wrote. Lines starting with '#' are left out;\n# an empty message aborts.\n";
+    std::fs::write(&path, format!("{}{}", message, help))?;
+    let status = platform::editor_command(editor, &path).status();
+    if !status.is_ok_and(|status| status.success()) {
+        return Err(WtfError::EditorFailed { editor: editor.to_string() });
+    }
+    let edited = platform::normalize_newlines(&std::fs::read_to_string(&path)?);
+    let kept: Vec<&str> = edited.lines().filter(|line| !line.starts_with('#')).collect();
+    let message = kept.join("\n").trim().to_string();
+    if message.is_empty() {
+        return Err(WtfError::EmptyMessage);
+    }
+    Ok(message + "\n")
+}
+
+/// Commit the index of `repo` on HEAD with `message`, as the user Git is
+/// configured for. Unlike `git commit`, this runs no hooks.
+pub fn commit(repo: &Repository, message: &str) -> Result<Oid> {
+    let signature = repo.signature().map_err(|_| WtfError::NoIdentity)?;
+    let mut index = repo.index()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    Ok(repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?)
+}
+
+/// The message, and the commit made with it if one was.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitMessage {
+    pub message: String,
+    pub commit: Option<String>,
+}
+
+/// Format `message` for output: the bare message, so it can be handed to
+/// `git commit -F -`, or JSON.
+pub fn render(message: &CommitMessage, format: OutputFormat) -> String {
+    match format {
+        // Serializing plain strings cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(message).expect("commit message serializes to JSON") + "\n",
+        _ => message.message.clone(),
+    }
+}
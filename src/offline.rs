@@ -0,0 +1,234 @@
+//! `--offline`: a report from the Git history alone.
+//!
+//! Without a model nothing explains the changes in prose, but the history
+//! still says a lot: who committed how much, which files changed most, and,
+//! for messages in the Conventional Commits format, what kind of change each
+//! commit was. [`collect`] gathers these numbers over the same commits the
+//! regular report analyzes, and [`render`] states the highlights in
+//! templated sentences above the tables.
+
+use crate::conventional;
+use crate::error::Result;
+use crate::generated::Detector;
+use crate::git;
+use crate::render::OutputFormat;
+use crate::tr;
+use git2::Repository;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+// Files listed under the most changed ones
+const TOP_FILES: usize = 10;
+
+/// What one author committed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AuthorStats {
+    pub name: String,
+    pub commits: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// How often one file changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileStats {
+    pub path: String,
+    pub commits: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// How many commits have one conventional type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeCount {
+    pub kind: String,
+    pub commits: usize,
+}
+
+/// The numbers behind an offline report.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OfflineReport {
+    pub num_commits: usize,
+    /// Merge commits, which count as commits but not towards the line counts.
+    pub merges: usize,
+    /// Commit times of the oldest and newest analyzed commit, seconds since
+    /// the epoch.
+    pub first_time: i64,
+    pub last_time: i64,
+    /// Distinct files changed.
+    pub files_changed: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// Most commits first.
+    pub authors: Vec<AuthorStats>,
+    /// The most often changed files that are not generated or vendored,
+    /// most commits first.
+    pub files: Vec<FileStats>,
+    /// Conventional commit types, most commits first.
+    pub types: Vec<TypeCount>,
+    /// Commits whose message is not in the Conventional Commits format.
+    pub unconventional: usize,
+    /// Commits marked as breaking changes.
+    pub breaking: usize,
+    /// The highlights in plain language.
+    pub summary: Vec<String>,
+}
+
+/// Gather the numbers over the `limit` most recent commits reachable from HEAD,
+/// of those changing something under the pathspecs in `paths` if there are any.
+pub fn collect(repo: &Repository, limit: usize, paths: &[String]) -> Result<OfflineReport> {
+    let (commits, _) = git::select_commits_in(repo, limit, paths)?;
+    let mut report = OfflineReport {
+        num_commits: commits.len(),
+        first_time: commits.iter().map(|c| c.time().seconds()).min().unwrap_or_default(),
+        last_time: commits.iter().map(|c| c.time().seconds()).max().unwrap_or_default(),
+        ..OfflineReport::default()
+    };
+    let mut authors: BTreeMap<String, AuthorStats> = BTreeMap::new();
+    let mut files: BTreeMap<String, FileStats> = BTreeMap::new();
+    let mut types: BTreeMap<String, usize> = BTreeMap::new();
+
+    for commit in &commits {
+        let name = git::decode_text(commit.author().name_bytes(), commit.message_encoding());
+        let author = authors.entry(name.clone()).or_insert_with(|| AuthorStats { name, ..AuthorStats::default() });
+        author.commits += 1;
+
+        match conventional::parse(&git::commit_message(commit)) {
+            Some(header) => {
+                *types.entry(header.kind).or_default() += 1;
+                report.breaking += usize::from(header.breaking);
+            }
+            None => report.unconventional += 1,
+        }
+
+        if commit.parent_count() > 1 {
+            report.merges += 1;
+            continue;
+        }
+        let diff = git::commit_diff(repo, commit, paths)?;
+        for idx in 0..diff.deltas().len() {
+            let Some(patch) = git2::Patch::from_diff(&diff, idx)? else { continue };
+            let delta = patch.delta();
+            let Some(path) = delta.new_file().path_bytes().or(delta.old_file().path_bytes()) else { continue };
+            let (_, added, removed) = patch.line_stats()?;
+            let path = git::escape_non_utf8(path);
+            let file = files.entry(path.clone()).or_insert_with(|| FileStats { path, ..FileStats::default() });
+            file.commits += 1;
+            file.added += added;
+            file.removed += removed;
+            author.added += added;
+            author.removed += removed;
+            report.added += added;
+            report.removed += removed;
+        }
+    }
+
+    report.files_changed = files.len();
+    let mut detector = Detector::new(repo);
+    let mut files: Vec<FileStats> = files.into_values().filter(|file| detector.kind(&file.path).is_none()).collect();
+    files.sort_by_key(|file| Reverse((file.commits, file.added + file.removed)));
+    files.truncate(TOP_FILES);
+    report.files = files;
+    report.authors = authors.into_values().collect();
+    report.authors.sort_by_key(|author| Reverse(author.commits));
+    report.types = types.into_iter().map(|(kind, commits)| TypeCount { kind, commits }).collect();
+    report.types.sort_by_key(|kind| Reverse(kind.commits));
+    report.summary = summarize(&report);
+    Ok(report)
+}
+
+// The highlights, one sentence each
+fn summarize(report: &OfflineReport) -> Vec<String> {
+    let mut sentences = vec![tr!(
+        "offline-summary-commits",
+        commits = report.num_commits,
+        authors = report.authors.len(),
+        first = git::format_date(report.first_time),
+        last = git::format_date(report.last_time)
+    )];
+    if let Some(top) = report.authors.first().filter(|_| report.authors.len() > 1) {
+        sentences.push(tr!("offline-summary-top-author", name = top.name.as_str(), commits = top.commits));
+    }
+    if report.merges > 0 {
+        sentences.push(tr!("offline-summary-merges", count = report.merges));
+    }
+    sentences.push(tr!(
+        "offline-summary-lines",
+        files = report.files_changed,
+        added = report.added,
+        removed = report.removed
+    ));
+    if let Some(top) = report.files.first() {
+        sentences.push(tr!("offline-summary-top-file", path = top.path.as_str(), commits = top.commits));
+    }
+    match report.types.first() {
+        Some(top) => {
+            let conventional = report.num_commits - report.unconventional;
+            sentences.push(tr!(
+                "offline-summary-types",
+                share = conventional * 100 / report.num_commits.max(1),
+                kind = top.kind.as_str(),
+                commits = top.commits
+            ));
+        }
+        None => sentences.push(tr!("offline-summary-no-types")),
+    }
+    if report.breaking > 0 {
+        sentences.push(tr!("offline-summary-breaking", count = report.breaking));
+    }
+    sentences
+}
+
+/// Format `report` for output.
+pub fn render(report: &OfflineReport, format: OutputFormat) -> String {
+    let authors: Vec<String> = report
+        .authors
+        .iter()
+        .map(|a| {
+            let name = a.name.as_str();
+            tr!("offline-author-entry", name = name, commits = a.commits, added = a.added, removed = a.removed)
+        })
+        .collect();
+    let files: Vec<String> = report
+        .files
+        .iter()
+        .map(|f| {
+            let path = f.path.as_str();
+            tr!("offline-file-entry", path = path, commits = f.commits, added = f.added, removed = f.removed)
+        })
+        .collect();
+    let mut types: Vec<String> = report
+        .types
+        .iter()
+        .map(|t| tr!("offline-type-entry", kind = t.kind.as_str(), commits = t.commits))
+        .collect();
+    if report.unconventional > 0 && !report.types.is_empty() {
+        types.push(tr!("offline-type-other", commits = report.unconventional));
+    }
+
+    let list = |items: &[String]| items.iter().map(|item| format!("- {}\n", item)).collect::<String>();
+    let mut sections = vec![(tr!("offline-title"), report.summary.join(" ") + "\n")];
+    sections.push((tr!("offline-authors"), list(&authors)));
+    if !files.is_empty() {
+        sections.push((tr!("offline-files"), list(&files)));
+    }
+    if !types.is_empty() {
+        sections.push((tr!("offline-types"), list(&types)));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("report serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
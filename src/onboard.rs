@@ -0,0 +1,480 @@
+//! `wtf onboard`: a guided tour of a repository for new contributors.
+//!
+//! Most of the tour is read from the tree at HEAD without a model: how it
+//! is laid out, which files programs start from, the build and test
+//! commands the manifests at the root declare (Cargo, npm, Go, Python,
+//! Make, just, CMake, Maven and Gradle) and, from the recent history, the
+//! areas that changed most. One structured request reads the README, the
+//! layout and the entry points and writes what the project does, what each
+//! top-level directory is for and which modules matter most. Paths the
+//! model names that are not in the tree are dropped.
+
+use crate::analysis::{self, RunEstimate};
+use crate::chunk;
+use crate::error::Result;
+use crate::git::{self, RepositoryExt};
+use crate::ownership;
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that shows new contributors around a software project.";
+
+const TOUR_PROMPT: &str = "Below are the README of a project, the layout of its tree with the number of files in each directory, and the start of the files its programs start from. Write a tour for a new contributor: an overview of two to four sentences on what the project does and how it is built; for each top-level directory, in one sentence, what it holds; and the three to eight modules or files that matter most for understanding the code, each with a sentence on its role. Use paths exactly as they appear in the layout, and only describe what the material shows.";
+
+// Commits the recently active areas are counted over
+const RECENT_COMMITS: usize = 200;
+
+// Areas are directories this deep
+const AREA_DEPTH: usize = 2;
+
+// Recently active areas listed
+const MAX_AREAS: usize = 5;
+
+// Subdirectories listed under each top-level directory
+const MAX_SUBDIRS: usize = 12;
+
+// Entry points whose start is shown to the model
+const MAX_ENTRY_FILES: usize = 3;
+
+// The README and each entry point are cut to these many tokens
+const README_TOKENS: usize = 3_000;
+const ENTRY_TOKENS: usize = 1_000;
+
+// Files programs commonly start from
+const ENTRY_FILES: &[&str] = &[
+    "src/main.rs",
+    "src/lib.rs",
+    "main.go",
+    "main.py",
+    "app.py",
+    "manage.py",
+    "index.js",
+    "index.ts",
+    "src/index.js",
+    "src/index.ts",
+    "src/main.js",
+    "src/main.ts",
+    "main.c",
+    "src/main.c",
+    "src/main.cpp",
+    "Program.cs",
+];
+
+/// What a build or test command is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Step {
+    Setup,
+    Build,
+    Test,
+    Lint,
+    Run,
+}
+
+impl Step {
+    fn label(self) -> String {
+        match self {
+            Step::Setup => tr!("onboard-step-setup"),
+            Step::Build => tr!("onboard-step-build"),
+            Step::Test => tr!("onboard-step-test"),
+            Step::Lint => tr!("onboard-step-lint"),
+            Step::Run => tr!("onboard-step-run"),
+        }
+    }
+}
+
+/// A command a manifest declares, like `cargo test` for `Cargo.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildCommand {
+    pub step: Step,
+    pub command: String,
+    /// The manifest it comes from.
+    pub source: String,
+}
+
+// The targets of a Makefile or recipes of a justfile, left of their colon
+fn targets(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with([' ', '\t', '.', '#']))
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            // Not an assignment like `CC := gcc`
+            let name = name.split_whitespace().next()?;
+            (!rest.starts_with('=')).then_some(name)
+        })
+        .collect()
+}
+
+/// The build, test and run commands declared by the manifests among
+/// `paths`, with `read` giving the contents of a file.
+pub fn build_commands(paths: &[String], read: impl Fn(&str) -> Option<String>) -> Vec<BuildCommand> {
+    let has = |path: &str| paths.iter().any(|known| known == path);
+    let mut commands = Vec::new();
+    let mut add = |step: Step, command: String, source: &str| {
+        commands.push(BuildCommand { step, command, source: source.to_string() });
+    };
+    if has("Cargo.toml") {
+        add(Step::Build, "cargo build".into(), "Cargo.toml");
+        add(Step::Test, "cargo test".into(), "Cargo.toml");
+    }
+    if let Some(package) = read("package.json").and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok()) {
+        let runner = match (has("pnpm-lock.yaml"), has("yarn.lock")) {
+            (true, _) => "pnpm",
+            (_, true) => "yarn",
+            _ => "npm",
+        };
+        add(Step::Setup, format!("{} install", runner), "package.json");
+        let scripts = package.get("scripts").and_then(|scripts| scripts.as_object());
+        let steps = [("build", Step::Build), ("test", Step::Test), ("lint", Step::Lint), ("start", Step::Run)];
+        for (script, step) in steps {
+            if scripts.is_some_and(|scripts| scripts.contains_key(script)) {
+                add(step, format!("{} run {}", runner, script), "package.json");
+            }
+        }
+    }
+    if has("go.mod") {
+        add(Step::Build, "go build ./...".into(), "go.mod");
+        add(Step::Test, "go test ./...".into(), "go.mod");
+    }
+    let python = ["pyproject.toml", "setup.py", "requirements.txt"].into_iter().find(|path| has(path));
+    if let Some(manifest) = python {
+        let setup = match manifest {
+            "requirements.txt" => "pip install -r requirements.txt",
+            _ => "pip install -e .",
+        };
+        add(Step::Setup, setup.into(), manifest);
+        if has("tox.ini") {
+            add(Step::Test, "tox".into(), "tox.ini");
+        } else if read(manifest).is_some_and(|text| text.contains("pytest")) || has("pytest.ini") {
+            add(Step::Test, "pytest".into(), manifest);
+        }
+    }
+    for (manifest, runner) in [("Makefile", "make"), ("justfile", "just")] {
+        let Some(content) = read(manifest) else {
+            continue;
+        };
+        let declared = targets(&content);
+        for (target, step) in [
+            ("install", Step::Setup),
+            ("build", Step::Build),
+            ("test", Step::Test),
+            ("check", Step::Test),
+            ("lint", Step::Lint),
+            ("run", Step::Run),
+        ] {
+            if declared.contains(&target) {
+                add(step, format!("{} {}", runner, target), manifest);
+            }
+        }
+    }
+    if has("CMakeLists.txt") {
+        add(Step::Build, "cmake -B build && cmake --build build".into(), "CMakeLists.txt");
+        add(Step::Test, "ctest --test-dir build".into(), "CMakeLists.txt");
+    }
+    if has("pom.xml") {
+        add(Step::Build, "mvn package".into(), "pom.xml");
+        add(Step::Test, "mvn test".into(), "pom.xml");
+    }
+    if let Some(manifest) = ["build.gradle", "build.gradle.kts"].into_iter().find(|path| has(path)) {
+        let gradle = if has("gradlew") { "./gradlew" } else { "gradle" };
+        add(Step::Build, format!("{} build", gradle), manifest);
+        add(Step::Test, format!("{} test", gradle), manifest);
+    }
+    // Stable, so each step keeps the manifests' order
+    commands.sort_by_key(|command| command.step);
+    commands
+}
+
+/// The files among `paths` programs probably start from: the usual names,
+/// like `src/main.rs` or `cmd/<name>/main.go`, and the `main` and `bin`
+/// files of `package.json`.
+pub fn entry_points(paths: &[String], package: Option<&str>) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    let mut add = |path: &str| {
+        let path = path.trim_start_matches("./");
+        if paths.iter().any(|known| known == path) && !found.iter().any(|known| known == path) {
+            found.push(path.to_string());
+        }
+    };
+    if let Some(package) = package.and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok()) {
+        if let Some(main) = package.get("main").and_then(|main| main.as_str()) {
+            add(main);
+        }
+        match package.get("bin") {
+            Some(serde_json::Value::String(bin)) => add(bin),
+            Some(serde_json::Value::Object(bins)) => bins.values().filter_map(|bin| bin.as_str()).for_each(&mut add),
+            _ => {}
+        }
+    }
+    for path in ENTRY_FILES {
+        add(path);
+    }
+    for path in paths {
+        let parts: Vec<&str> = path.split('/').collect();
+        let is_entry = match parts.as_slice() {
+            ["src", "bin", file] => file.ends_with(".rs"),
+            ["cmd", _, "main.go"] => true,
+            [.., "__main__.py"] => parts.len() <= 3,
+            _ => false,
+        };
+        if is_entry {
+            add(path);
+        }
+    }
+    found
+}
+
+// The tree as the model sees it: each top-level directory with its file
+// count and those of its subdirectories, then the files at the root
+pub(crate) fn layout(paths: &[String]) -> String {
+    let mut dirs: BTreeMap<&str, (usize, BTreeMap<&str, usize>)> = BTreeMap::new();
+    let mut files = Vec::new();
+    for path in paths {
+        let mut parts = path.split('/');
+        let first = parts.next().unwrap_or_default();
+        let rest: Vec<&str> = parts.collect();
+        if rest.is_empty() {
+            files.push(first);
+            continue;
+        }
+        let (count, subdirs) = dirs.entry(first).or_default();
+        *count += 1;
+        if rest.len() > 1 {
+            *subdirs.entry(rest[0]).or_default() += 1;
+        }
+    }
+    let mut out = String::new();
+    for (dir, (count, subdirs)) in &dirs {
+        out.push_str(&format!("{}/ ({} files)\n", dir, count));
+        let mut subdirs: Vec<(&&str, &usize)> = subdirs.iter().collect();
+        subdirs.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+        for (subdir, count) in subdirs.iter().take(MAX_SUBDIRS) {
+            out.push_str(&format!("  {}/{}/ ({} files)\n", dir, subdir, count));
+        }
+    }
+    for file in files {
+        out.push_str(&format!("{}\n", file));
+    }
+    out
+}
+
+// Any README at the root, whatever its extension, a Markdown one first
+pub(crate) fn readme_path(paths: &[String]) -> Option<&String> {
+    paths
+        .iter()
+        .filter(|path| !path.contains('/') && path.to_lowercase().starts_with("readme"))
+        .min_by_key(|path| (!path.ends_with(".md"), path.len()))
+}
+
+/// An area that changed in many recent commits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActiveArea {
+    /// A directory, or empty for the files at the root.
+    pub path: String,
+    pub commits: usize,
+    /// The latest change, seconds since the epoch.
+    pub last_change: i64,
+}
+
+/// The tree at HEAD and its recent history, read before any model is called.
+#[derive(Debug, Clone)]
+pub struct OnboardInput {
+    pub repo_name: String,
+    pub paths: Vec<String>,
+    pub entry_points: Vec<String>,
+    pub commands: Vec<BuildCommand>,
+    /// Most commits first.
+    pub active_areas: Vec<ActiveArea>,
+    // The README, layout and entry points, for the prompt
+    content: String,
+}
+
+/// Read the tree at HEAD and the recent history of `repo`.
+pub fn prepare(repo: &Repository) -> Result<OnboardInput> {
+    let paths = git::head_tree_paths(repo)?;
+    progress!("{}", tr!("progress-onboard", count = paths.len()));
+    let read = |path: &str| repo.find_file(path).ok();
+    let commands = build_commands(&paths, read);
+    let entry_points = entry_points(&paths, read("package.json").as_deref());
+
+    let history = ownership::collect(repo, RECENT_COMMITS)?;
+    let mut active_areas: Vec<ActiveArea> = history
+        .contributions_by(|path| Some(ownership::area_of(path, AREA_DEPTH)))
+        .into_iter()
+        .map(|(path, authors)| ActiveArea {
+            path,
+            commits: authors.values().map(|contribution| contribution.commits).sum(),
+            last_change: authors.values().map(|contribution| contribution.last_time).max().unwrap_or_default(),
+        })
+        .collect();
+    active_areas.sort_by_key(|area| (std::cmp::Reverse(area.commits), std::cmp::Reverse(area.last_change)));
+    active_areas.truncate(MAX_AREAS);
+
+    let readme = readme_path(&paths).and_then(|path| read(path)).unwrap_or_else(|| "No README found".to_string());
+    let mut content = format!(
+        "README:\n\n{}\n\n---\n\nLayout:\n\n{}",
+        chunk::truncate_to_tokens(&readme, README_TOKENS).0,
+        layout(&paths)
+    );
+    for path in entry_points.iter().take(MAX_ENTRY_FILES) {
+        if let Some(text) = read(path) {
+            let (start, _) = chunk::truncate_to_tokens(&text, ENTRY_TOKENS);
+            content.push_str(&format!("\n---\n\nStart of {}:\n\n{}\n", path, start));
+        }
+    }
+    Ok(OnboardInput { repo_name: analysis::repo_name(repo), paths, entry_points, commands, active_areas, content })
+}
+
+impl OnboardInput {
+    fn question(&self) -> String {
+        format!("{}\n\n{}", TOUR_PROMPT, self.content)
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question()) }
+    }
+
+    // Whether `path` is a file or directory in the tree
+    fn contains(&self, path: &str) -> bool {
+        let path = path.trim_end_matches('/');
+        let dir = format!("{}/", path);
+        !path.is_empty() && self.paths.iter().any(|known| known == path || known.starts_with(&dir))
+    }
+}
+
+/// A path and what it is for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Described {
+    pub path: String,
+    pub description: String,
+}
+
+/// The tour.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Tour {
+    pub repo_name: String,
+    pub overview: String,
+    /// What each top-level directory holds.
+    pub layout: Vec<Described>,
+    pub entry_points: Vec<String>,
+    pub key_modules: Vec<Described>,
+    pub commands: Vec<BuildCommand>,
+    pub active_areas: Vec<ActiveArea>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    overview: String,
+    layout: Vec<Described>,
+    key_modules: Vec<Described>,
+}
+
+/// Write the tour of `input` with one structured request.
+pub async fn explain(input: &OnboardInput, provider: &dyn LlmProvider) -> Result<Tour> {
+    let described = json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": { "path": { "type": "string" }, "description": { "type": "string" } },
+            "required": ["path", "description"],
+            "additionalProperties": false,
+        },
+    });
+    let schema = Schema::new(
+        "onboarding_tour",
+        json!({
+            "type": "object",
+            "properties": { "overview": { "type": "string" }, "layout": described, "key_modules": described },
+            "required": ["overview", "layout", "key_modules"],
+            "additionalProperties": false,
+        }),
+    );
+    let messages = [Message::system(SYSTEM_PROMPT), Message::user(input.question())];
+    let reply: Reply = structured::request(provider, &messages, &schema).await?;
+    // Made-up paths would send a newcomer looking for nothing
+    let checked = |items: Vec<Described>| -> Vec<Described> {
+        items
+            .into_iter()
+            .filter(|item| input.contains(item.path.trim()))
+            .map(|item| Described {
+                path: item.path.trim().trim_end_matches('/').to_string(),
+                description: item.description.trim().to_string(),
+            })
+            .collect()
+    };
+    Ok(Tour {
+        repo_name: input.repo_name.clone(),
+        overview: reply.overview.trim().to_string(),
+        layout: checked(reply.layout),
+        entry_points: input.entry_points.clone(),
+        key_modules: checked(reply.key_modules),
+        commands: input.commands.clone(),
+        active_areas: input.active_areas.clone(),
+    })
+}
+
+/// Format `tour` for output.
+pub fn render(tour: &Tour, format: OutputFormat) -> String {
+    let described = |items: &[Described]| -> String {
+        items.iter().map(|item| format!("- {}: {}\n", item.path, item.description)).collect()
+    };
+    let mut sections = vec![(tr!("onboard-title", repo = tour.repo_name.as_str()), tour.overview.clone() + "\n")];
+    if !tour.layout.is_empty() {
+        sections.push((tr!("onboard-layout"), described(&tour.layout)));
+    }
+    if !tour.entry_points.is_empty() {
+        let entry_points = tour.entry_points.iter().map(|path| format!("- {}\n", path)).collect();
+        sections.push((tr!("onboard-entry-points"), entry_points));
+    }
+    if !tour.key_modules.is_empty() {
+        sections.push((tr!("onboard-key-modules"), described(&tour.key_modules)));
+    }
+    let commands = match tour.commands.is_empty() {
+        true => tr!("onboard-no-commands") + "\n",
+        false => tour
+            .commands
+            .iter()
+            .map(|command| format!("- {}: {} ({})\n", command.step.label(), command.command, command.source))
+            .collect(),
+    };
+    sections.push((tr!("onboard-commands"), commands));
+    if !tour.active_areas.is_empty() {
+        let areas = tour
+            .active_areas
+            .iter()
+            .map(|area| {
+                let path = match area.path.is_empty() {
+                    true => tr!("group-root"),
+                    false => format!("{}/", area.path),
+                };
+                let date = git::format_date(area.last_change);
+                format!("- {}\n", tr!("onboard-area", area = path, count = area.commits, date = date))
+            })
+            .collect();
+        sections.push((tr!("onboard-active"), areas));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(tour).expect("tour serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,271 @@
+//! Who works on what: per-file authorship from recent history.
+//!
+//! [`collect`] walks the most recent commits and records which files that
+//! still exist at HEAD each one changed, by how many lines and who wrote it.
+//! `wtf owners` turns this into a draft CODEOWNERS file.
+
+use crate::error::Result;
+use crate::git;
+use git2::{Repository, Sort};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+// Owners together account for at least this share of an area's changes...
+const OWNER_COVERAGE: f64 = 0.6;
+// ...but nobody below this share is listed, and never more than three people
+const MIN_OWNER_SHARE: f64 = 0.1;
+const MAX_OWNERS: usize = 3;
+
+/// One author's contributions to a file (or area).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Contribution {
+    pub commits: usize,
+    /// Added plus removed lines; binary changes count as one line.
+    pub lines: usize,
+    /// Time of the author's latest change, seconds since the epoch.
+    pub last_time: i64,
+}
+
+impl Contribution {
+    fn add(&mut self, other: &Contribution) {
+        self.commits += other.commits;
+        self.lines += other.lines;
+        self.last_time = self.last_time.max(other.last_time);
+    }
+}
+
+/// The files one commit changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitChanges {
+    /// Author email, lowercased.
+    pub author: String,
+    /// Commit time, seconds since the epoch.
+    pub time: i64,
+    /// First line of the commit message.
+    pub summary: String,
+    /// Changed files that still exist at HEAD, with their changed lines.
+    pub files: Vec<(String, usize)>,
+}
+
+/// Authorship of the files at HEAD over the analyzed commits.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    /// Non-merge commits, newest first.
+    pub commits: Vec<CommitChanges>,
+    /// Display name and latest commit time of each author, by email.
+    pub authors: BTreeMap<String, (String, i64)>,
+}
+
+impl History {
+    /// Each author's contributions per group, keyed by group and author
+    /// email. `group_of` maps a path to its group, or `None` to leave the
+    /// file out. A commit counts once per group however many of its files
+    /// are in it.
+    pub fn contributions_by(
+        &self,
+        group_of: impl Fn(&str) -> Option<String>,
+    ) -> BTreeMap<String, BTreeMap<String, Contribution>> {
+        let mut groups: BTreeMap<String, BTreeMap<String, Contribution>> = BTreeMap::new();
+        for commit in &self.commits {
+            let mut lines_per_group: BTreeMap<String, usize> = BTreeMap::new();
+            for (path, lines) in &commit.files {
+                if let Some(group) = group_of(path) {
+                    *lines_per_group.entry(group).or_default() += lines;
+                }
+            }
+            for (group, lines) in lines_per_group {
+                groups
+                    .entry(group)
+                    .or_default()
+                    .entry(commit.author.clone())
+                    .or_default()
+                    .add(&Contribution { commits: 1, lines, last_time: commit.time });
+            }
+        }
+        groups
+    }
+
+    /// The display name of the author with `email`.
+    pub fn author_name(&self, email: &str) -> String {
+        self.authors.get(email).map(|(name, _)| name.clone()).unwrap_or_else(|| email.to_string())
+    }
+}
+
+/// Record who changed which file in the last `max_commits` non-merge commits
+/// reachable from HEAD. Authors are told apart by email address.
+pub fn collect(repo: &Repository, max_commits: usize) -> Result<History> {
+    let (_, total) = git::select_commits(repo, 0)?;
+    let existing: HashSet<String> = git::head_tree_paths(repo)?.into_iter().collect();
+
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    let mut history = History::default();
+    for oid in walk.take(max_commits.min(total)) {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let author = commit.author();
+        let email = String::from_utf8_lossy(author.email_bytes()).to_lowercase();
+        let name = git::decode_text(author.name_bytes(), commit.message_encoding());
+        let time = commit.time().seconds();
+        let entry = history.authors.entry(email.clone()).or_insert((name, time));
+        entry.1 = entry.1.max(time);
+
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        let mut files = Vec::new();
+        for idx in 0..diff.deltas().len() {
+            let Some(path) = diff.get_delta(idx).and_then(|d| d.new_file().path_bytes().map(git::escape_non_utf8)) else {
+                continue;
+            };
+            if !existing.contains(&path) {
+                continue;
+            }
+            let lines = match git2::Patch::from_diff(&diff, idx)? {
+                Some(patch) => {
+                    let (_, added, removed) = patch.line_stats()?;
+                    (added + removed).max(1)
+                }
+                None => 1,
+            };
+            files.push((path, lines));
+        }
+        let summary = git::commit_message(&commit).lines().next().unwrap_or_default().to_string();
+        history.commits.push(CommitChanges { author: email, time, summary, files });
+    }
+    Ok(history)
+}
+
+/// The area a file belongs to: its directory, cut to at most `depth`
+/// components (`""` for files at the root).
+pub fn area_of(path: &str, depth: usize) -> String {
+    let dirs: Vec<&str> = path.split('/').collect();
+    let dirs = &dirs[..dirs.len() - 1];
+    dirs[..dirs.len().min(depth)].join("/")
+}
+
+/// One of an area's suggested owners.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Owner {
+    pub email: String,
+    pub name: String,
+    /// Share of the area's changed lines, between 0 and 1.
+    pub share: f64,
+    pub commits: usize,
+    /// Whether the author committed anywhere since the cutoff.
+    pub active: bool,
+}
+
+/// Suggested owners of one area.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AreaOwners {
+    /// Directory relative to the repository root; `""` is the root itself.
+    pub area: String,
+    pub owners: Vec<Owner>,
+    /// Time of the area's latest change.
+    pub last_change: i64,
+    /// Who made that change.
+    pub last_author: String,
+}
+
+impl AreaOwners {
+    /// Whether none of the suggested owners is still active.
+    pub fn is_orphaned(&self) -> bool {
+        !self.owners.iter().any(|owner| owner.active)
+    }
+}
+
+/// Suggest owners per area, grouping directories at `depth`.
+///
+/// Authors are ranked by changed lines; the top ones that together account
+/// for most of the changes are owners. Only authors with a commit since
+/// `active_since` count as active.
+pub fn owners_by_area(history: &History, depth: usize, active_since: i64) -> Vec<AreaOwners> {
+    let areas = history.contributions_by(|path| Some(area_of(path, depth)));
+
+    areas
+        .into_iter()
+        .map(|(area, authors)| {
+            let total: usize = authors.values().map(|c| c.lines).sum();
+            let mut ranked: Vec<(String, Contribution)> = authors.into_iter().collect();
+            ranked.sort_by(|a, b| b.1.lines.cmp(&a.1.lines).then(a.0.cmp(&b.0)));
+            let (last_email, last) = ranked
+                .iter()
+                .max_by_key(|(_, c)| c.last_time)
+                .map(|(email, c)| (email.clone(), *c))
+                .unwrap_or_default();
+
+            let mut owners = Vec::new();
+            let mut covered = 0.0;
+            for (email, contribution) in &ranked {
+                let share = contribution.lines as f64 / total.max(1) as f64;
+                if owners.len() == MAX_OWNERS || covered >= OWNER_COVERAGE || share < MIN_OWNER_SHARE {
+                    break;
+                }
+                covered += share;
+                let latest = history.authors.get(email).map_or(0, |(_, time)| *time);
+                owners.push(Owner {
+                    email: email.clone(),
+                    name: history.author_name(email),
+                    share,
+                    commits: contribution.commits,
+                    active: latest >= active_since,
+                });
+            }
+            AreaOwners {
+                area,
+                owners,
+                last_change: last.last_time,
+                last_author: history.author_name(&last_email),
+            }
+        })
+        .collect()
+}
+
+/// A draft CODEOWNERS file for `areas`, explaining each suggestion in a
+/// comment. Areas without an active owner are left commented out.
+pub fn codeowners(areas: &[AreaOwners], history: &History) -> String {
+    let mut out = format!(
+        "# Draft CODEOWNERS generated by `wtf owners` from the last {} commits.\n\
+         # Owners are listed by email; review before committing.\n",
+        history.commits.len()
+    );
+    // Later patterns take precedence, and sorted paths put parents first
+    for area in areas {
+        let pattern = if area.area.is_empty() { "*".to_string() } else { format!("/{}/", area.area) };
+        let explanation: Vec<String> = area
+            .owners
+            .iter()
+            .map(|owner| {
+                format!(
+                    "{} {:.0}% of changed lines in {} commit(s){}",
+                    owner.name,
+                    owner.share * 100.0,
+                    owner.commits,
+                    if owner.active { "" } else { ", inactive" }
+                )
+            })
+            .collect();
+        out.push_str(&format!("\n# {}: {}\n", pattern, explanation.join("; ")));
+
+        let active: Vec<&str> = area.owners.iter().filter(|o| o.active).map(|o| o.email.as_str()).collect();
+        if active.is_empty() {
+            out.push_str(&format!(
+                "# No active owner; last changed {} by {}\n# {} {}\n",
+                git::format_date(area.last_change),
+                area.last_author,
+                pattern,
+                area.owners.iter().map(|o| o.email.as_str()).collect::<Vec<_>>().join(" ")
+            ));
+        } else {
+            out.push_str(&format!("{} {}\n", pattern, active.join(" ")));
+        }
+    }
+    out
+}
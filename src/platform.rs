@@ -0,0 +1,163 @@
+//! Platform differences: home directories, line endings, executable files,
+//! starting editors and console setup.
+//!
+//! Everything that behaves differently on Windows lives here, so the rest of
+//! the crate can assume Unix-style text and a plain ANSI terminal.
+
+use std::path::{Path, PathBuf};
+
+/// The current user's home directory.
+///
+/// Uses `HOME` on Unix. On Windows `HOME` is usually unset (or set by Git
+/// Bash to a Unix-style path), so `USERPROFILE` and then
+/// `HOMEDRIVE`+`HOMEPATH` are tried first.
+pub fn home_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty());
+
+    if cfg!(windows) {
+        if let Some(profile) = var("USERPROFILE") {
+            return Some(PathBuf::from(profile));
+        }
+        if let (Some(drive), Some(path)) = (var("HOMEDRIVE"), var("HOMEPATH")) {
+            let mut home = drive;
+            home.push(path);
+            return Some(PathBuf::from(home));
+        }
+    }
+    var("HOME").map(PathBuf::from)
+}
+
+/// Where wtf keeps its settings, e.g. `~/.config/wtf-git`.
+///
+/// `WTF_CONFIG_DIR` overrides the platform default (`%APPDATA%` on
+/// Windows, `~/Library/Application Support` on macOS, `$XDG_CONFIG_HOME`
+/// or `~/.config` elsewhere).
+pub fn config_dir() -> Option<PathBuf> {
+    app_dir("WTF_CONFIG_DIR", "APPDATA", "Application Support", "XDG_CONFIG_HOME", ".config")
+}
+
+/// Where wtf keeps data it produces, such as crash reports.
+///
+/// `WTF_DATA_DIR` overrides the platform default (`%LOCALAPPDATA%` on
+/// Windows, `~/Library/Application Support` on macOS, `$XDG_DATA_HOME` or
+/// `~/.local/share` elsewhere).
+pub fn data_dir() -> Option<PathBuf> {
+    app_dir("WTF_DATA_DIR", "LOCALAPPDATA", "Application Support", "XDG_DATA_HOME", ".local/share")
+}
+
+/// Where wtf keeps what it can always recreate, such as cached replies.
+///
+/// `WTF_CACHE_DIR` overrides the platform default (`%LOCALAPPDATA%` on
+/// Windows, `~/Library/Caches` on macOS, `$XDG_CACHE_HOME` or `~/.cache`
+/// elsewhere).
+pub fn cache_dir() -> Option<PathBuf> {
+    app_dir("WTF_CACHE_DIR", "LOCALAPPDATA", "Caches", "XDG_CACHE_HOME", ".cache")
+}
+
+fn app_dir(
+    override_var: &str,
+    windows_var: &str,
+    macos_dir: &str,
+    xdg_var: &str,
+    xdg_default: &str,
+) -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+    if let Some(dir) = var(override_var) {
+        return Some(dir);
+    }
+    let base = if cfg!(windows) {
+        var(windows_var)?
+    } else if cfg!(target_os = "macos") {
+        home_dir()?.join("Library").join(macos_dir)
+    } else {
+        var(xdg_var).or_else(|| home_dir().map(|home| home.join(xdg_default)))?
+    };
+    Some(base.join("wtf-git"))
+}
+
+/// Replace CRLF (and lone CR) line endings with LF.
+pub fn normalize_newlines(text: &str) -> String {
+    if !text.contains('\r') {
+        return text.to_string();
+    }
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// The command that opens `path` in `editor`, an editor command line like
+/// `code --wait`, through the shell the way Git starts editors.
+pub fn editor_command(editor: &str, path: &Path) -> std::process::Command {
+    let mut command;
+    if cfg!(windows) {
+        command = std::process::Command::new("cmd");
+        command.arg("/C").arg(format!("{} \"{}\"", editor, path.display()));
+    } else {
+        command = std::process::Command::new("sh");
+        command.arg("-c").arg(format!("{} \"$@\"", editor)).arg(editor).arg(path);
+    }
+    command
+}
+
+/// Whether Git would run `path` as a hook or script.
+///
+/// On Unix that takes the executable bit. Git for Windows runs any hook
+/// file through its bundled shell, so there being a file is enough.
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Make ANSI escape sequences work on the console, where that needs enabling.
+///
+/// Windows 10+ consoles only interpret them once virtual terminal processing
+/// is switched on. Returns whether color output is safe to use.
+pub fn enable_ansi_colors() -> bool {
+    if std::env::var_os("NO_COLOR").is_some() {
+        return false;
+    }
+    enable_virtual_terminal()
+}
+
+#[cfg(windows)]
+fn enable_virtual_terminal() -> bool {
+    use std::ffi::c_void;
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const STD_ERROR_HANDLE: u32 = -12i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+
+    let mut enabled = true;
+    for handle in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+        // SAFETY: plain Win32 calls on the process's own standard handles
+        unsafe {
+            let console = GetStdHandle(handle);
+            let mut mode = 0;
+            if console.is_null() || GetConsoleMode(console, &mut mode) == 0 {
+                // Redirected to a file or pipe; nothing to enable
+                continue;
+            }
+            if SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) == 0 {
+                enabled = false;
+            }
+        }
+    }
+    enabled
+}
+
+#[cfg(not(windows))]
+fn enable_virtual_terminal() -> bool {
+    true
+}
@@ -0,0 +1,181 @@
+//! `wtf pr`: a pull request description for a branch.
+//!
+//! The branch is compared with the base it will be merged into, the way a
+//! pull request shows it: its commits, and the cumulative diff from where
+//! the two split off to the branch. The model writes the title, a summary,
+//! the main changes and testing notes as JSON, which is laid out in the
+//! markdown of a GitHub pull request or a GitLab merge request.
+
+use crate::analysis::{RunEstimate, Task};
+use crate::chunk;
+use crate::commit::{self, FileChange};
+use crate::error::Result;
+use crate::git::{self, RevRange};
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::summarize;
+use crate::tokens;
+use crate::{progress, tr};
+use clap::ValueEnum;
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that writes clear pull request descriptions from Git history.";
+
+const PR_PROMPT: &str = "Below are the commits of a branch and its cumulative diff against the base branch it will be merged into. Write the pull request for it: a title of at most 72 characters in the imperative mood, a summary of two to four sentences on what the branch does and why, the main changes as a list with one short sentence each, and testing notes: how a reviewer can check that it works, and which tests the branch adds or changes. Describe where the diff ends up rather than retelling each commit, and leave out changes that later commits undo.";
+
+// The commit details are cut to this many tokens
+const PR_COMMITS_TOKENS: usize = 6_000;
+
+/// The markdown layout of the description.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Template {
+    /// A GitHub pull request: summary, changes and a testing checklist
+    #[default]
+    Github,
+    /// A GitLab merge request: what it does and why, changes and how to validate it
+    Gitlab,
+}
+
+/// A branch, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct PrInput {
+    /// From the base to the branch.
+    pub range: RevRange,
+    /// The branch's commits, newest first.
+    pub commits: Vec<Oid>,
+    pub files: Vec<FileChange>,
+    /// The cumulative diff, from where the branch split off.
+    pub patch: String,
+    // Commit details, for the prompt
+    details: String,
+}
+
+/// Gather the commits in `range`, from the base to the branch, and the
+/// diff between the two.
+pub fn prepare(repo: &Repository, range: &RevRange) -> Result<PrInput> {
+    let commits = git::range_commits(repo, range)?;
+    progress!("{}", tr!("progress-pr", count = commits.len()));
+    let (base, branch) = (git::resolve_commit(repo, &range.from)?, git::resolve_commit(repo, &range.to)?);
+    let fork = match repo.merge_base(base.id(), branch.id()) {
+        Ok(id) => Some(repo.find_commit(id)?.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(fork.as_ref(), Some(&branch.tree()?), None)?;
+    let mut details = Vec::new();
+    for id in &commits {
+        details.push(git::get_commit_details(&repo.find_commit(*id)?));
+    }
+    Ok(PrInput {
+        range: range.clone(),
+        commits,
+        files: commit::diff_files(&diff)?,
+        patch: git::diff_patch(&diff)?,
+        details: details.join("\n\n---\n\n"),
+    })
+}
+
+impl PrInput {
+    fn question(&self, patch: &str) -> String {
+        let (details, _) = chunk::truncate_to_tokens(&self.details, PR_COMMITS_TOKENS);
+        format!(
+            "{}\n\nBranch: {}\nBase: {}\n\nCommits:\n\n{}\n\nCumulative diff:\n\n{}",
+            PR_PROMPT, self.range.to, self.range.from, details, patch
+        )
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self, max_diff_tokens: usize) -> RunEstimate {
+        let mut estimate = RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) };
+        if summarize::is_large_diff(&self.patch, max_diff_tokens) {
+            let parts = summarize::estimate_diff(&self.patch, max_diff_tokens);
+            estimate.requests += parts.requests;
+            estimate.prompt_tokens += parts.prompt_tokens + tokens::estimate(&self.question(""));
+            estimate.prompt_tokens += parts.requests * summarize::EXPECTED_SUMMARY_TOKENS;
+        } else {
+            estimate.prompt_tokens += tokens::estimate(&self.question(&self.patch));
+        }
+        estimate
+    }
+}
+
+/// A pull request description.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub title: String,
+    pub summary: String,
+    pub changes: Vec<String>,
+    pub testing: Vec<String>,
+}
+
+/// Ask the model for the pull request of the branch. A diff above
+/// `max_diff_tokens` is summarized in parts first.
+pub async fn explain(input: &PrInput, provider: &dyn LlmProvider, max_diff_tokens: usize) -> Result<PullRequest> {
+    let mut patch = input.patch.clone();
+    if summarize::is_large_diff(&patch, max_diff_tokens) {
+        patch = summarize::summarize_diff(provider, Task::Edits, &patch, max_diff_tokens).await?;
+    }
+    let messages = [Message::system(SYSTEM_PROMPT), Message::user(input.question(&patch))];
+    let schema = Schema::new(
+        "pull_request",
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "summary": { "type": "string" },
+                "changes": { "type": "array", "items": { "type": "string" } },
+                "testing": { "type": "array", "items": { "type": "string" } },
+            },
+            "required": ["title", "summary", "changes", "testing"],
+            "additionalProperties": false,
+        }),
+    );
+    let pr: PullRequest = structured::request(provider, &messages, &schema).await?;
+    Ok(PullRequest {
+        title: pr.title.trim().to_string(),
+        summary: pr.summary.trim().to_string(),
+        changes: pr.changes.iter().map(|change| change.trim().to_string()).collect(),
+        testing: pr.testing.iter().map(|note| note.trim().to_string()).collect(),
+    })
+}
+
+/// Format `pr` for output, laid out as `template`. Markdown is the title as
+/// a heading and the description to paste below it.
+pub fn render(pr: &PullRequest, template: Template, format: OutputFormat) -> String {
+    let changes: String = pr.changes.iter().map(|change| format!("- {}\n", change)).collect();
+    let (summary, testing_title, testing): (String, String, String) = match template {
+        Template::Github => (
+            tr!("pr-summary"),
+            tr!("pr-testing"),
+            pr.testing.iter().map(|note| format!("- [ ] {}\n", note)).collect(),
+        ),
+        Template::Gitlab => (
+            tr!("pr-mr-summary"),
+            tr!("pr-mr-testing"),
+            pr.testing.iter().enumerate().map(|(n, note)| format!("{}. {}\n", n + 1, note)).collect(),
+        ),
+    };
+    let mut sections = vec![(summary, pr.summary.clone() + "\n"), (tr!("pr-changes"), changes)];
+    if !pr.testing.is_empty() {
+        sections.push((testing_title, testing));
+    }
+
+    match format {
+        // Serializing plain strings cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(pr).expect("pull request serializes to JSON") + "\n",
+        OutputFormat::Markdown => {
+            let body: Vec<String> = sections.iter().map(|(title, body)| format!("## {}\n\n{}", title, body)).collect();
+            format!("# {}\n\n{}", pr.title, body.join("\n"))
+        }
+        OutputFormat::Terminal => std::iter::once((tr!("pr-title"), pr.title.clone() + "\n"))
+            .chain(sections)
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => {
+            sections.insert(0, (tr!("pr-title"), pr.title.clone() + "\n"));
+            crate::render::plain_sections(&sections)
+        }
+    }
+}
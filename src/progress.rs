@@ -0,0 +1,28 @@
+//! Progress and diagnostic messages on stderr.
+//!
+//! Library code reports what it is doing with [`progress!`](crate::progress),
+//! which full-screen views can silence so stray lines do not corrupt them.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Suppress (or re-enable) progress messages.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether progress messages are currently suppressed.
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Like `eprintln!`, unless progress messages are suppressed.
+#[macro_export]
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if !$crate::progress::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
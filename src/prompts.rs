@@ -0,0 +1,337 @@
+//! The prompts the report is written from, and the templates that replace
+//! them.
+//!
+//! A prompts directory (`--prompts-dir`, or `prompts_dir` in `config.toml`
+//! or `.wtf.toml`) may hold any of `system.txt`, `project-description.txt`,
+//! `commits.txt` and `edits.txt`. Each file found replaces the built-in
+//! prompt of the same name; the others stay as they are. Templates can use
+//! the variables `{repo}`, `{branch}` and `{num_commits}`, and `{{` and `}}`
+//! for literal braces.
+//!
+//! `--for` picks built-in prompts written for one [`Audience`] instead,
+//! from an executive summary to a technical walkthrough. Templates replace
+//! those of the audience like they replace the general ones.
+//!
+//! `--lang` has the model answer in another language. [`InLanguage`] adds
+//! the instruction to the system prompt of every request, so it reaches the
+//! prompts of every command, templates included.
+
+use crate::error::{Result, WtfError};
+use crate::provider::{BoxFuture, LlmProvider, Message, Price};
+use crate::structured::Schema;
+use crate::tr;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::Path;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains Git repositories in plain language. You will be asked for the sections of one report in turn. Keep the sections consistent with each other, and build on what you already said in earlier sections instead of repeating it.";
+
+const PROJECT_DESCRIPTION_PROMPT: &str = "Based on the README content and other information provided, give a brief, clear description of what this project is about in plain English. Keep it under 100 words.";
+
+const COMMIT_PROMPT: &str = "Explain the following git commits in plain language. For each commit, explain what changes were made in simple terms that anyone can understand. Focus on the practical impact of the changes rather than technical details.";
+
+const EDITS_PROMPT: &str = "Explain the following code changes in plain language. For each edit, explain what was changed and why it might have been changed, relating it to the project and commits described above where that helps. Focus on the functional impact rather than listing every line change. Make it understandable to non-technical people.";
+
+const MANAGER_PROMPTS: [&str; 4] = [
+    "You are an AI assistant that briefs engineering managers on the work in a Git repository. Write executive summaries: outcomes, progress and risks rather than implementation details, in short paragraphs or lists. You will be asked for the sections of one report in turn; keep them consistent and do not repeat yourself.",
+    "Based on the README content and other information provided, say in two or three sentences what this project is and who it serves. Keep it under 60 words.",
+    "Summarize the following git commits for a manager as a short list of outcomes: what was delivered, fixed or put at risk, and why it matters to users or the team. Group related commits together and leave out code-level detail. Keep it under 150 words.",
+    "Summarize the following code changes for a manager: which areas of the product changed, how large and risky the changes look, and anything that needs a decision or a follow-up. Do not describe individual lines. Keep it under 150 words.",
+];
+
+const NEW_HIRE_PROMPTS: [&str; 4] = [
+    "You are an AI assistant that helps a developer who just joined the team find their way around a Git repository. Explain the project's structure and vocabulary as they come up, and say why things are done the way they are where the data shows it. You will be asked for the sections of one report in turn; build on what you explained in earlier sections.",
+    "Based on the README content and other information provided, explain what this project does, how it is organized and which terms a newcomer needs to know. Keep it under 150 words.",
+    "Walk a new team member through the following git commits. For each commit, explain what changed, which part of the code base it touches, what that part is for and how the change fits the project. Explain terms a newcomer might not know.",
+    "Explain the following code changes to a developer who is new to this code base: which files and modules changed, what role they play and what each change does. Point out the patterns and conventions the changes follow, so they can be recognized elsewhere in the code.",
+];
+
+const EXPERT_PROMPTS: [&str; 4] = [
+    "You are an AI assistant that walks experienced engineers through the history of a Git repository. Be precise and technical: name files, functions, types and algorithms, and do not explain the basics. You will be asked for the sections of one report in turn; keep them consistent and do not repeat yourself.",
+    "Based on the README content and other information provided, give a technical summary of the project: its purpose, architecture, languages and main dependencies. Keep it under 120 words.",
+    "Give a technical walkthrough of the following git commits. For each commit, describe the concrete changes to code, interfaces and behavior and their likely motivation. Call out API changes, performance or concurrency implications and anything that looks like a bug or a regression.",
+    "Walk through the following diffs in depth: what each change does at the code level, how the pieces interact, which edge cases are handled or missed, and what it means for correctness, performance, compatibility and maintenance. Refer to files and identifiers by name.",
+];
+
+const CUSTOMER_PROMPTS: [&str; 4] = [
+    "You are an AI assistant that tells the users of a product what has changed in it, based on the Git history of its code. Write for people who use the product and have never seen its code: describe changes by what users can now do or will notice, never by files or functions, and leave out internal work they do not notice. You will be asked for the sections of one update in turn.",
+    "Based on the README content and other information provided, describe in plain, friendly words what this product does for the people who use it. Keep it under 60 words.",
+    "From the following git commits, write what users of the product will notice: new features, improvements and fixes, each in a short, friendly sentence. Leave out refactoring, tests, build changes and other internal work. If none of the commits affects users, say so in one sentence.",
+    "From the following code changes, describe what the people who use the product will notice: what is new, what behaves differently and what was fixed. Do not mention files, code or internal work. Keep it under 120 words.",
+];
+
+/// Who the report is written for, with `--for`.
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Audience {
+    /// An executive summary of outcomes and risks
+    Manager,
+    /// The project's structure and vocabulary explained along the way
+    NewHire,
+    /// A deep technical walkthrough
+    Expert,
+    /// What users of the product will notice, without code
+    Customer,
+}
+
+/// The variables a template can use.
+pub const VARIABLES: [&str; 3] = ["repo", "branch", "num_commits"];
+
+/// The templates of the report's prompts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prompts {
+    /// The system prompt the whole report is written under.
+    pub system: String,
+    pub description: String,
+    pub commits: String,
+    pub edits: String,
+}
+
+impl Default for Prompts {
+    fn default() -> Self {
+        Prompts {
+            system: SYSTEM_PROMPT.to_string(),
+            description: PROJECT_DESCRIPTION_PROMPT.to_string(),
+            commits: COMMIT_PROMPT.to_string(),
+            edits: EDITS_PROMPT.to_string(),
+        }
+    }
+}
+
+impl Prompts {
+    /// The built-in prompts written for `audience`.
+    pub fn for_audience(audience: Audience) -> Prompts {
+        let [system, description, commits, edits] = match audience {
+            Audience::Manager => MANAGER_PROMPTS,
+            Audience::NewHire => NEW_HIRE_PROMPTS,
+            Audience::Expert => EXPERT_PROMPTS,
+            Audience::Customer => CUSTOMER_PROMPTS,
+        };
+        Prompts {
+            system: system.to_string(),
+            description: description.to_string(),
+            commits: commits.to_string(),
+            edits: edits.to_string(),
+        }
+    }
+
+    /// The built-in prompts, with those whose file is in `dir` replaced.
+    /// A template that uses a variable there is no value for is an error.
+    pub fn load(dir: &Path) -> Result<Prompts> {
+        Prompts::default().with_templates(dir)
+    }
+
+    /// These prompts, with those whose file is in `dir` replaced, like
+    /// [`Prompts::load`].
+    pub fn with_templates(mut self, dir: &Path) -> Result<Prompts> {
+        if !dir.is_dir() {
+            return Err(WtfError::InvalidConfig { path: dir.to_path_buf(), reason: tr!("prompts-not-a-directory") });
+        }
+        let templates = [
+            ("system.txt", &mut self.system),
+            ("project-description.txt", &mut self.description),
+            ("commits.txt", &mut self.commits),
+            ("edits.txt", &mut self.edits),
+        ];
+        for (name, prompt) in templates {
+            let path = dir.join(name);
+            let template = match std::fs::read_to_string(&path) {
+                Ok(template) => template,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if let Some(unknown) = unknown_variable(&template) {
+                let reason = tr!("prompts-unknown-variable", name = braced(&unknown), known = known_variables());
+                return Err(WtfError::InvalidConfig { path, reason });
+            }
+            *prompt = template.trim().to_string();
+        }
+        Ok(self)
+    }
+
+    /// These templates with their variables filled in from `vars`.
+    pub fn interpolated(&self, vars: &PromptVars) -> Prompts {
+        Prompts {
+            system: interpolate(&self.system, vars),
+            description: interpolate(&self.description, vars),
+            commits: interpolate(&self.commits, vars),
+            edits: interpolate(&self.edits, vars),
+        }
+    }
+}
+
+/// The values of the template variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptVars {
+    /// The name of the repository's directory.
+    pub repo: String,
+    /// The branch HEAD is on, or `HEAD` when it is detached.
+    pub branch: String,
+    /// The number of commits being analyzed.
+    pub num_commits: usize,
+}
+
+impl PromptVars {
+    fn get(&self, name: &str) -> Option<String> {
+        match name {
+            "repo" => Some(self.repo.clone()),
+            "branch" => Some(self.branch.clone()),
+            "num_commits" => Some(self.num_commits.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// `template` with every `{variable}` replaced by its value in `vars`.
+/// Braces around anything that is not a variable are kept as they are.
+pub fn interpolate(template: &str, vars: &PromptVars) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        let value = rest[1..].split_once('}').and_then(|(name, _)| Some((name.len(), vars.get(name)?)));
+        match value {
+            Some((len, value)) => {
+                out.push_str(&value);
+                rest = &rest[len + 2..];
+            }
+            None => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// The first `{name}` in `template` that is not a known variable
+fn unknown_variable(template: &str) -> Option<String> {
+    let template = template.replace("{{", "").replace("}}", "");
+    template.split('{').skip(1).find_map(|part| {
+        let (name, _) = part.split_once('}')?;
+        let is_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        (is_name && !VARIABLES.contains(&name)).then(|| name.to_string())
+    })
+}
+
+fn known_variables() -> String {
+    VARIABLES.iter().map(|name| braced(name)).collect::<Vec<_>>().join(", ")
+}
+
+fn braced(name: &str) -> String {
+    format!("{{{}}}", name)
+}
+
+// Names of common languages by code, for telling the model
+const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "Arabic"),
+    ("cs", "Czech"),
+    ("da", "Danish"),
+    ("de", "German"),
+    ("el", "Greek"),
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fi", "Finnish"),
+    ("fr", "French"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("hu", "Hungarian"),
+    ("id", "Indonesian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("nl", "Dutch"),
+    ("no", "Norwegian"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("th", "Thai"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("vi", "Vietnamese"),
+    ("zh", "Chinese"),
+];
+
+/// The English name of the language `lang` is the code of, like `German`
+/// for `de` or `de-AT`. Anything else, like `Brazilian Portuguese`, is
+/// taken to be a name already.
+pub fn language_name(lang: &str) -> String {
+    let code = lang.split(['_', '-', '.']).next().unwrap_or_default().to_lowercase();
+    match LANGUAGES.iter().find(|(known, _)| *known == code) {
+        Some((_, name)) => name.to_string(),
+        None => lang.to_string(),
+    }
+}
+
+/// A provider that has every answer written in one language.
+pub struct InLanguage {
+    inner: Box<dyn LlmProvider>,
+    instruction: String,
+}
+
+impl InLanguage {
+    /// Answer in `lang`, a language code like `de` or a language's name.
+    pub fn new(inner: Box<dyn LlmProvider>, lang: &str) -> Self {
+        let language = language_name(lang);
+        let instruction = format!(
+            "Write your answers in {}, whatever language the repository is in. Keep file paths, commit SHAs, \
+             code, identifiers and JSON field names as they are.",
+            language
+        );
+        InLanguage { inner, instruction }
+    }
+
+    // The messages with the instruction at the end of the system prompt, or
+    // in a system prompt of its own if there is none
+    fn localize(&self, messages: &[Message]) -> Vec<Message> {
+        let mut messages = messages.to_vec();
+        match messages.iter_mut().find(|message| message.role == "system") {
+            Some(system) => system.content = format!("{} {}", system.content, self.instruction),
+            None => messages.insert(0, Message::system(self.instruction.clone())),
+        }
+        messages
+    }
+}
+
+impl LlmProvider for InLanguage {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move { self.inner.chat(&self.localize(messages)).await })
+    }
+
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move { self.inner.chat_json(&self.localize(messages), schema).await })
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move { self.inner.stream(&self.localize(messages), on_text).await })
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.inner.count_tokens(text)
+    }
+
+    fn max_context(&self) -> usize {
+        self.inner.max_context()
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn price(&self) -> Option<Price> {
+        self.inner.price()
+    }
+}
@@ -0,0 +1,231 @@
+use super::{
+    check_status, http, price_by_name, read_lines, record_usage, split_system, sse_data, BoxFuture, LlmProvider,
+    Message, Price, ProviderConfig, Sampling, Usage, CLAUDE_PRICES,
+};
+use crate::error::{Result, WtfError};
+use crate::{progress, tr};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+const API_VERSION: &str = "2023-06-01";
+
+// The Messages API requires a limit on the reply length, so without a
+// `max_tokens` setting it is this
+const MAX_TOKENS: u32 = 4096;
+
+#[derive(Serialize, Debug)]
+struct AnthropicRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicResponse {
+    content: Vec<ContentBlock>,
+    usage: Option<TokenUsage>,
+}
+
+// Streams report the prompt's tokens when they start and the reply's when
+// they end, each with the other left out
+#[derive(Deserialize, Debug)]
+struct TokenUsage {
+    #[serde(default)]
+    input_tokens: usize,
+    #[serde(default)]
+    output_tokens: usize,
+}
+
+impl From<TokenUsage> for Usage {
+    fn from(usage: TokenUsage) -> Usage {
+        Usage { input_tokens: usage.input_tokens, output_tokens: usage.output_tokens }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+// One server-sent event of a streamed reply. Only `content_block_delta`
+// events carry text, and `error` events report failures mid-stream.
+#[derive(Deserialize, Debug)]
+struct StreamEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    delta: Option<TextDelta>,
+    error: Option<StreamError>,
+    // On `message_start`
+    message: Option<StreamMessage>,
+    // On `message_delta`
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamMessage {
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TextDelta {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamError {
+    message: String,
+}
+
+/// Anthropic's Messages API, for Claude models.
+pub struct AnthropicProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    sampling: Sampling,
+}
+
+impl AnthropicProvider {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        AnthropicProvider {
+            client: http::default_client(),
+            api_key: api_key.into(),
+            model: "claude-3-5-haiku-latest".to_string(),
+            sampling: Sampling::default(),
+        }
+    }
+
+    /// Build from the `api_key` and optional `model` and sampling settings.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let mut provider = AnthropicProvider::new(config.api_key("ANTHROPIC_API_KEY")?);
+        provider.client = http::client_for(config)?;
+        provider.sampling = Sampling::from_config(config)?;
+        Ok(match config.get("model") {
+            Some(model) => provider.with_model(model),
+            None => provider,
+        })
+    }
+
+    /// Use `model` instead of the default.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    // Send the request and check its status, leaving the body unread
+    async fn post(&self, messages: &[Message], stream: bool) -> Result<reqwest::Response> {
+        progress!("{}", tr!("progress-sending", provider = "Anthropic"));
+
+        // System prompts go in their own field, not in the message list
+        let (system, messages) = split_system(messages);
+        let request = AnthropicRequest {
+            model: &self.model,
+            max_tokens: self.sampling.max_tokens.unwrap_or(MAX_TOKENS),
+            system,
+            messages,
+            temperature: self.sampling.temperature,
+            top_p: self.sampling.top_p,
+            stream,
+        };
+
+        let response = self
+            .client
+            .post("https://api.anthropic.com/v1/messages")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await?;
+
+        let response = check_status(response, &["prompt is too long"]).await?;
+        Ok(response)
+    }
+
+    async fn send(&self, messages: &[Message]) -> Result<String> {
+        let response = self.post(messages, false).await?;
+        progress!("{}", tr!("progress-received", provider = "Anthropic"));
+
+        let response_data = response
+            .json::<AnthropicResponse>()
+            .await
+            .map_err(|e| WtfError::InvalidResponse(e.to_string()))?;
+        let usage = response_data.usage.map(Usage::from);
+        let text: String = response_data
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text)
+            .collect();
+        if text.is_empty() {
+            return Err(WtfError::InvalidResponse("no text in response".to_string()));
+        }
+        record_usage(self, usage, messages, &text);
+        Ok(text)
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.send(messages))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let response = self.post(messages, true).await?;
+            let mut reply = String::new();
+            let mut usage: Option<Usage> = None;
+            read_lines(response, |line| {
+                let Some(data) = sse_data(line) else { return Ok(()) };
+                let event: StreamEvent =
+                    serde_json::from_str(data).map_err(|e| WtfError::InvalidResponse(e.to_string()))?;
+                if let Some(error) = event.error {
+                    return Err(WtfError::InvalidResponse(error.message));
+                }
+                if let Some(started) = event.message.and_then(|message| message.usage) {
+                    usage.get_or_insert_with(Usage::default).input_tokens = started.input_tokens;
+                }
+                if let Some(ended) = event.usage {
+                    usage.get_or_insert_with(Usage::default).output_tokens = ended.output_tokens;
+                }
+                if let Some(delta) = event.delta.filter(|_| event.kind == "content_block_delta") {
+                    on_text(&delta.text);
+                    reply.push_str(&delta.text);
+                }
+                Ok(())
+            })
+            .await?;
+            progress!("{}", tr!("progress-received", provider = "Anthropic"));
+            record_usage(self, usage, messages, &reply);
+            Ok(reply)
+        })
+    }
+
+    fn max_context(&self) -> usize {
+        // Every Claude model since Claude 2.1
+        200_000
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn price(&self) -> Option<Price> {
+        price_by_name(&self.model, CLAUDE_PRICES)
+    }
+}
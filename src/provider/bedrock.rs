@@ -0,0 +1,209 @@
+use super::sigv4::{self, Credentials};
+use super::{
+    check_status, http, price_by_name, record_usage, split_system, BoxFuture, LlmProvider, Message, Price,
+    ProviderConfig, Sampling, Usage, CLAUDE_PRICES,
+};
+use crate::error::{Result, WtfError};
+use crate::{progress, tr};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+// Bedrock requires a limit on the reply length, so without a `max_tokens`
+// setting it is this
+const MAX_TOKENS: u32 = 4096;
+
+// The Converse API takes the same shape for every model on Bedrock
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ConverseRequest {
+    messages: Vec<ConverseMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    system: Vec<Text>,
+    inference_config: InferenceConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ConverseMessage {
+    role: String,
+    content: Vec<Text>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Text {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct InferenceConfig {
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ConverseResponse {
+    output: Output,
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TokenUsage {
+    input_tokens: usize,
+    output_tokens: usize,
+}
+
+impl From<TokenUsage> for Usage {
+    fn from(usage: TokenUsage) -> Usage {
+        Usage { input_tokens: usage.input_tokens, output_tokens: usage.output_tokens }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Output {
+    message: ConverseMessage,
+}
+
+/// Amazon Bedrock's Converse API, for Claude, Titan and the other models
+/// it hosts, signed with AWS credentials.
+pub struct BedrockProvider {
+    client: Client,
+    credentials: Credentials,
+    region: String,
+    model: String,
+    sampling: Sampling,
+}
+
+impl BedrockProvider {
+    pub fn new(credentials: Credentials, region: impl Into<String>) -> Self {
+        BedrockProvider {
+            client: http::default_client(),
+            credentials,
+            region: region.into(),
+            model: "anthropic.claude-3-haiku-20240307-v1:0".to_string(),
+            sampling: Sampling::default(),
+        }
+    }
+
+    /// Build from the `access_key_id`, `secret_access_key`, optional
+    /// `session_token`, `region` and optional `model` and sampling settings.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let credentials = Credentials {
+            access_key_id: config
+                .get("access_key_id")
+                .ok_or(WtfError::MissingCredentials { var: "AWS_ACCESS_KEY_ID" })?
+                .to_string(),
+            secret_access_key: config
+                .get("secret_access_key")
+                .ok_or(WtfError::MissingCredentials { var: "AWS_SECRET_ACCESS_KEY" })?
+                .to_string(),
+            session_token: config.get("session_token").map(str::to_string),
+        };
+        let mut provider = BedrockProvider::new(credentials, config.require("region", "AWS_REGION")?);
+        provider.client = http::client_for(config)?;
+        provider.sampling = Sampling::from_config(config)?;
+        Ok(match config.get("model") {
+            Some(model) => provider.with_model(model),
+            None => provider,
+        })
+    }
+
+    /// Use `model` (a Bedrock model or inference profile id) instead of the default.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    async fn send(&self, messages: &[Message]) -> Result<String> {
+        progress!("{}", tr!("progress-sending", provider = "Bedrock"));
+
+        let (system, turns) = split_system(messages);
+        let request = ConverseRequest {
+            messages: turns
+                .into_iter()
+                .map(|message| ConverseMessage { role: message.role, content: vec![Text { text: message.content }] })
+                .collect(),
+            system: system.into_iter().map(|text| Text { text }).collect(),
+            inference_config: InferenceConfig {
+                max_tokens: self.sampling.max_tokens.unwrap_or(MAX_TOKENS),
+                temperature: self.sampling.temperature,
+                top_p: self.sampling.top_p,
+            },
+        };
+        // Serializing strings and numbers cannot fail
+        let body = serde_json::to_vec(&request).expect("request serializes to JSON");
+
+        let host = format!("bedrock-runtime.{}.amazonaws.com", self.region);
+        let path = format!("/model/{}/converse", sigv4::uri_encode(&self.model, false));
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let signable = sigv4::Request {
+            method: "POST",
+            host: &host,
+            path: &path,
+            query: "",
+            headers: &[("content-type", "application/json")],
+            body: &body,
+        };
+        let mut builder = self
+            .client
+            .post(format!("https://{}{}", host, path))
+            .header("Content-Type", "application/json");
+        for (name, value) in sigv4::sign(&signable, &self.credentials, &self.region, "bedrock", &sigv4::amz_date(now)) {
+            builder = builder.header(name, value);
+        }
+        let response = builder.body(body).send().await?;
+
+        let response = check_status(response, &["too long", "too many input tokens"]).await?;
+
+        progress!("{}", tr!("progress-received", provider = "Bedrock"));
+
+        let response_data = response
+            .json::<ConverseResponse>()
+            .await
+            .map_err(|e| WtfError::InvalidResponse(e.to_string()))?;
+        let usage = response_data.usage.map(Usage::from);
+        let text: String = response_data.output.message.content.into_iter().map(|block| block.text).collect();
+        if text.is_empty() {
+            return Err(WtfError::InvalidResponse("no text in response".to_string()));
+        }
+        record_usage(self, usage, messages, &text);
+        Ok(text)
+    }
+}
+
+impl LlmProvider for BedrockProvider {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.send(messages))
+    }
+
+    fn max_context(&self) -> usize {
+        // Ids look like `anthropic.claude-...`, or `us.anthropic.claude-...`
+        // for cross-region inference profiles
+        if self.model.contains("anthropic.claude") {
+            200_000
+        } else if self.model.contains("amazon.titan-text") {
+            32_000
+        } else {
+            super::DEFAULT_MAX_CONTEXT
+        }
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn price(&self) -> Option<Price> {
+        // Other models on Bedrock are priced each their own way
+        if !self.model.contains("anthropic.claude") {
+            return None;
+        }
+        price_by_name(&self.model, CLAUDE_PRICES)
+    }
+}
@@ -0,0 +1,264 @@
+use super::{
+    check_status, http, price_by_name, read_lines, record_usage, schema_instructions, split_system, sse_data,
+    BoxFuture, LlmProvider, Message, Price, ProviderConfig, Sampling, Usage,
+};
+use crate::error::{Result, WtfError};
+use crate::structured::Schema;
+use crate::{progress, tr};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+// Gemini has `user` and `model` turns, and the system prompt on its own
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<Content>,
+    contents: Vec<Content>,
+    generation_config: GenerationConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Content {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Part {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<&'static str>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    prompt_feedback: Option<PromptFeedback>,
+    usage_metadata: Option<UsageMetadata>,
+}
+
+// Streamed chunks each carry the counts so far
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: usize,
+    #[serde(default)]
+    candidates_token_count: usize,
+}
+
+impl From<UsageMetadata> for Usage {
+    fn from(usage: UsageMetadata) -> Usage {
+        Usage { input_tokens: usage.prompt_token_count, output_tokens: usage.candidates_token_count }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Candidate {
+    #[serde(default)]
+    content: Content,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    block_reason: Option<String>,
+}
+
+// The text of a response (or of one chunk of a streamed one)
+fn reply_text(response: GeminiResponse) -> Result<String> {
+    // A prompt caught by the safety filters gets no candidates at all
+    if let Some(reason) = response.prompt_feedback.and_then(|feedback| feedback.block_reason) {
+        return Err(WtfError::InvalidResponse(format!("the prompt was blocked ({})", reason)));
+    }
+    Ok(response
+        .candidates
+        .into_iter()
+        .next()
+        .map(|candidate| candidate.content.parts.into_iter().map(|part| part.text).collect())
+        .unwrap_or_default())
+}
+
+// In dollars per million tokens, for prompts up to 128k (1.5) or 200k
+// (2.5) tokens; longer ones cost more
+const PRICES: &[(&str, Price)] = &[
+    ("gemini-1.5-flash", Price { input: 0.075, output: 0.30 }),
+    ("gemini-1.5-pro", Price { input: 1.25, output: 5.0 }),
+    ("gemini-2.0-flash", Price { input: 0.10, output: 0.40 }),
+    ("gemini-2.0-flash-lite", Price { input: 0.075, output: 0.30 }),
+    ("gemini-2.5-flash", Price { input: 0.30, output: 2.50 }),
+    ("gemini-2.5-flash-lite", Price { input: 0.10, output: 0.40 }),
+    ("gemini-2.5-pro", Price { input: 1.25, output: 10.0 }),
+];
+
+fn text_content(role: Option<&str>, text: String) -> Content {
+    Content {
+        role: role.map(str::to_string),
+        parts: vec![Part { text }],
+    }
+}
+
+/// Google's Generative Language API, for Gemini models.
+pub struct GeminiProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    sampling: Sampling,
+}
+
+impl GeminiProvider {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        GeminiProvider {
+            client: http::default_client(),
+            api_key: api_key.into(),
+            model: "gemini-1.5-flash".to_string(),
+            sampling: Sampling::default(),
+        }
+    }
+
+    /// Build from the `api_key` and optional `model` and sampling settings.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let mut provider = GeminiProvider::new(config.api_key("GEMINI_API_KEY")?);
+        provider.client = http::client_for(config)?;
+        provider.sampling = Sampling::from_config(config)?;
+        Ok(match config.get("model") {
+            Some(model) => provider.with_model(model),
+            None => provider,
+        })
+    }
+
+    /// Use `model` instead of the default.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    // Send the request and check its status, leaving the body unread
+    async fn post(&self, messages: &[Message], json: bool, stream: bool) -> Result<reqwest::Response> {
+        progress!("{}", tr!("progress-sending", provider = "Gemini"));
+
+        let (system, turns) = split_system(messages);
+        let contents = turns
+            .into_iter()
+            .map(|message| {
+                let role = if message.role == "assistant" { "model" } else { "user" };
+                text_content(Some(role), message.content)
+            })
+            .collect();
+        let request = GeminiRequest {
+            system_instruction: system.map(|text| text_content(None, text)),
+            contents,
+            generation_config: GenerationConfig {
+                temperature: self.sampling.temperature,
+                top_p: self.sampling.top_p,
+                max_output_tokens: self.sampling.max_tokens,
+                response_mime_type: json.then_some("application/json"),
+            },
+        };
+
+        let method = if stream { "streamGenerateContent?alt=sse" } else { "generateContent" };
+        let response = self
+            .client
+            .post(format!("https://generativelanguage.googleapis.com/v1beta/models/{}:{}", self.model, method))
+            .header("x-goog-api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await?;
+
+        let response = check_status(response, &["exceeds the maximum number of tokens"]).await?;
+        Ok(response)
+    }
+
+    async fn send(&self, messages: &[Message], json: bool) -> Result<String> {
+        let response = self.post(messages, json, false).await?;
+        progress!("{}", tr!("progress-received", provider = "Gemini"));
+
+        let response_data = response
+            .json::<GeminiResponse>()
+            .await
+            .map_err(|e| WtfError::InvalidResponse(e.to_string()))?;
+        let usage = response_data.usage_metadata.map(Usage::from);
+        let text = reply_text(response_data)?;
+        if text.is_empty() {
+            return Err(WtfError::InvalidResponse("no text in response".to_string()));
+        }
+        record_usage(self, usage, messages, &text);
+        Ok(text)
+    }
+}
+
+impl LlmProvider for GeminiProvider {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.send(messages, false))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let response = self.post(messages, false, true).await?;
+            let mut reply = String::new();
+            let mut usage = None;
+            read_lines(response, |line| {
+                let Some(data) = sse_data(line) else { return Ok(()) };
+                let chunk: GeminiResponse =
+                    serde_json::from_str(data).map_err(|e| WtfError::InvalidResponse(e.to_string()))?;
+                usage = chunk.usage_metadata.map(Usage::from).or(usage);
+                let text = reply_text(chunk)?;
+                on_text(&text);
+                reply.push_str(&text);
+                Ok(())
+            })
+            .await?;
+            progress!("{}", tr!("progress-received", provider = "Gemini"));
+            record_usage(self, usage, messages, &reply);
+            Ok(reply)
+        })
+    }
+
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            // Gemini's responseSchema only takes a subset of JSON Schema, so
+            // the schema goes in the prompt and the reply is just forced to JSON
+            let mut messages = messages.to_vec();
+            messages.push(Message::system(schema_instructions(schema)));
+            self.send(&messages, true).await
+        })
+    }
+
+    fn max_context(&self) -> usize {
+        if self.model.starts_with("gemini-1.5-pro") {
+            2_097_152
+        } else {
+            1_048_576
+        }
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn price(&self) -> Option<Price> {
+        price_by_name(&self.model, PRICES)
+    }
+}
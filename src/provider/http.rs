@@ -0,0 +1,85 @@
+//! The HTTP client the providers send their requests with.
+//!
+//! Building a [`Client`] sets up a connection pool and TLS, so providers
+//! with the same [`HttpSettings`] share one client instead of each building
+//! their own. Without a `proxy` setting, reqwest still honors the usual
+//! `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY` environment variables.
+
+use super::ProviderConfig;
+use crate::error::Result;
+use reqwest::Client;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How long to wait for a connection before giving up.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a whole request, a streamed reply included, may take.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How to reach the provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpSettings {
+    /// The proxy every request goes through, like `http://proxy:3128`.
+    pub proxy: Option<String>,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            proxy: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+        }
+    }
+}
+
+impl HttpSettings {
+    /// The `proxy`, `connect_timeout` and `request_timeout` settings, the
+    /// timeouts in seconds. Unset or unreadable timeouts keep their
+    /// defaults.
+    pub fn from_config(config: &ProviderConfig) -> HttpSettings {
+        let seconds = |key: &str| config.get(key).and_then(|value| value.parse().ok()).map(Duration::from_secs);
+        let defaults = HttpSettings::default();
+        HttpSettings {
+            proxy: config.get("proxy").map(str::to_string),
+            connect_timeout: seconds("connect_timeout").unwrap_or(defaults.connect_timeout),
+            request_timeout: seconds("request_timeout").unwrap_or(defaults.request_timeout),
+        }
+    }
+}
+
+// Every client built so far, by the settings it was built with
+static CLIENTS: Mutex<Vec<(HttpSettings, Client)>> = Mutex::new(Vec::new());
+
+/// The client for `settings`, built the first time it is asked for.
+pub fn client(settings: &HttpSettings) -> Result<Client> {
+    let mut clients = CLIENTS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, client)) = clients.iter().find(|(known, _)| known == settings) {
+        // Clones share the connection pool
+        return Ok(client.clone());
+    }
+    let mut builder = Client::builder()
+        .connect_timeout(settings.connect_timeout)
+        .timeout(settings.request_timeout);
+    if let Some(proxy) = &settings.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
+    }
+    let client = builder.build()?;
+    clients.push((settings.clone(), client.clone()));
+    Ok(client)
+}
+
+/// The client for the given provider settings.
+pub fn client_for(config: &ProviderConfig) -> Result<Client> {
+    client(&HttpSettings::from_config(config))
+}
+
+/// The client with the default settings, for providers built without a
+/// [`ProviderConfig`].
+pub(crate) fn default_client() -> Client {
+    // Like `Client::new`, which panics too if TLS cannot be set up
+    client(&HttpSettings::default()).expect("the default HTTP client builds")
+}
@@ -0,0 +1,482 @@
+//! Language model backends.
+//!
+//! The analysis only talks to the [`LlmProvider`] trait, so tests can swap in
+//! a canned implementation and new backends can be added side by side,
+//! each registered by name in the [`Registry`].
+
+#[cfg(feature = "online")]
+mod anthropic;
+#[cfg(feature = "online")]
+mod bedrock;
+#[cfg(feature = "online")]
+mod gemini;
+#[cfg(feature = "online")]
+pub mod http;
+#[cfg(feature = "online")]
+mod ollama;
+#[cfg(feature = "online")]
+mod openai;
+mod registry;
+mod retry;
+pub mod sigv4;
+
+#[cfg(feature = "online")]
+pub use anthropic::AnthropicProvider;
+#[cfg(feature = "online")]
+pub use bedrock::BedrockProvider;
+#[cfg(feature = "online")]
+pub use gemini::GeminiProvider;
+#[cfg(feature = "online")]
+pub use ollama::OllamaProvider;
+#[cfg(feature = "online")]
+pub use openai::OpenAiProvider;
+pub use registry::{Constructor, ProviderConfig, Registry};
+pub use retry::{parse_retry_after, RetryPolicy, Retrying};
+
+use crate::error::{Result, WtfError};
+use crate::structured::Schema;
+use crate::tokens;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+
+/// A boxed future, used to keep [`LlmProvider`] object safe.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A single chat message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Message {
+            role: "system".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Message {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Message {
+            role: "assistant".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+/// How the model picks its words: the `temperature`, `top_p` and
+/// `max_tokens` settings every provider takes. Unset ones are left out of
+/// the request, as reasoning models refuse anything but their own.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Sampling {
+    pub temperature: Option<f32>,
+    /// Nucleus sampling, if set: only the most likely tokens making up this
+    /// share of the probability are considered.
+    pub top_p: Option<f32>,
+    /// The longest reply, in tokens. Unset leaves it to the provider, or to
+    /// the provider's own default where the API requires one.
+    pub max_tokens: Option<u32>,
+}
+
+impl Sampling {
+    /// The sampling settings of `config`, or an error naming the first one
+    /// that isn't a valid value (see [`parse_sampling`]).
+    pub fn from_config(config: &ProviderConfig) -> Result<Sampling> {
+        fn setting<T: FromStr + Into<f64> + Copy>(config: &ProviderConfig, key: &str) -> Result<Option<T>>
+        where
+            T::Err: fmt::Display,
+        {
+            let Some(value) = config.get(key) else { return Ok(None) };
+            let parsed = parse_sampling(key, value).map_err(|reason| WtfError::InvalidSetting {
+                key: key.to_string(),
+                value: value.to_string(),
+                reason,
+            })?;
+            Ok(Some(parsed))
+        }
+        Ok(Sampling {
+            temperature: setting(config, "temperature")?,
+            top_p: setting(config, "top_p")?,
+            max_tokens: setting(config, "max_tokens")?,
+        })
+    }
+}
+
+/// Parse `value` as the sampling setting `key`: a `temperature` from 0 to
+/// 2, a `top_p` from 0 to 1 or a whole number of `max_tokens`.
+pub fn parse_sampling<T: FromStr + Into<f64> + Copy>(key: &str, value: &str) -> std::result::Result<T, String>
+where
+    T::Err: fmt::Display,
+{
+    let parsed: T = value.trim().parse().map_err(|e: T::Err| e.to_string())?;
+    let range = match key {
+        "temperature" => 0.0..=2.0,
+        "top_p" => 0.0..=1.0,
+        _ => return Ok(parsed),
+    };
+    let number: f64 = parsed.into();
+    if !range.contains(&number) {
+        return Err(crate::tr!("sampling-out-of-range", key = key, min = range.start(), max = range.end()));
+    }
+    Ok(parsed)
+}
+
+/// The hosted model APIs wtf can talk to.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProviderKind {
+    /// OpenAI's chat completions API
+    #[default]
+    #[value(name = "openai")]
+    OpenAi,
+    /// Anthropic's Messages API (Claude models)
+    Anthropic,
+    /// A local Ollama server, so diffs never leave the machine
+    Ollama,
+    /// An Azure OpenAI deployment
+    Azure,
+    /// Google's Generative Language API (Gemini models)
+    Gemini,
+    /// Amazon Bedrock, signed with AWS credentials
+    Bedrock,
+}
+
+impl ProviderKind {
+    /// Parse a provider name as given in `WTF_PROVIDER`, ignoring case.
+    pub fn from_name(name: &str) -> Option<ProviderKind> {
+        ProviderKind::from_str(name.trim(), true).ok()
+    }
+
+    /// The name the provider is registered under, as `--provider` takes it.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProviderKind::OpenAi => "openai",
+            ProviderKind::Anthropic => "anthropic",
+            ProviderKind::Ollama => "ollama",
+            ProviderKind::Azure => "azure",
+            ProviderKind::Gemini => "gemini",
+            ProviderKind::Bedrock => "bedrock",
+        }
+    }
+
+    /// The setting that holds this provider's API key, if it needs one.
+    pub fn api_key_var(self) -> Option<&'static str> {
+        match self {
+            ProviderKind::OpenAi => Some("OPENAI_API_KEY"),
+            ProviderKind::Anthropic => Some("ANTHROPIC_API_KEY"),
+            ProviderKind::Azure => Some("AZURE_OPENAI_API_KEY"),
+            ProviderKind::Gemini => Some("GEMINI_API_KEY"),
+            // Bedrock signs requests with AWS credentials instead
+            ProviderKind::Ollama | ProviderKind::Bedrock => None,
+        }
+    }
+}
+
+/// The OpenAI API's base URL, used unless another OpenAI-compatible server
+/// is configured.
+pub const OPENAI_DEFAULT_API_BASE: &str = "https://api.openai.com/v1";
+
+/// The chat completions URL of an OpenAI-compatible API at `api_base`, like
+/// `https://openrouter.ai/api/v1` or `http://localhost:1234/v1`. A base
+/// that already ends in `/chat/completions` is used as is.
+pub fn openai_chat_url(api_base: &str) -> String {
+    let base = api_base.trim().trim_end_matches('/');
+    if base.ends_with("/chat/completions") {
+        return base.to_string();
+    }
+    format!("{}/chat/completions", base)
+}
+
+/// Whether an OpenAI model takes `json_schema` response formats: GPT-4o and
+/// later, and the o1, o3 and o4 reasoning models. Older models only offer
+/// the schemaless `json_object` mode.
+pub fn openai_supports_json_schema(model: &str) -> bool {
+    ["gpt-4o", "gpt-4.1", "gpt-5"].iter().chain(&OPENAI_REASONING_MODELS).any(|prefix| model.starts_with(prefix))
+}
+
+// The prefixes of OpenAI's o-series reasoning models
+pub(crate) const OPENAI_REASONING_MODELS: [&str; 3] = ["o1", "o3", "o4"];
+
+/// The Azure OpenAI API version used unless another is configured.
+pub const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// An Azure OpenAI model deployment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AzureDeployment {
+    /// The resource endpoint, like `https://my-org.openai.azure.com`.
+    pub endpoint: String,
+    /// The deployment name, which stands in for the model.
+    pub deployment: String,
+    /// The `api-version` query parameter, like `2024-10-21`.
+    pub api_version: String,
+}
+
+impl AzureDeployment {
+    /// The deployment's chat completions URL.
+    pub fn chat_url(&self) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.endpoint.trim().trim_end_matches('/'),
+            self.deployment,
+            self.api_version
+        )
+    }
+
+    /// Whether the API version takes `json_schema` response formats, which
+    /// came with 2024-08-01 (ISO dates compare correctly as strings).
+    pub fn supports_json_schema(&self) -> bool {
+        self.api_version.as_str() >= "2024-08-01"
+    }
+}
+
+/// Where Ollama listens by default.
+pub const OLLAMA_DEFAULT_HOST: &str = "localhost:11434";
+
+/// The base URL for an Ollama host given the way `OLLAMA_HOST` is: a bare
+/// `host` or `host:port` (11434 if the port is missing), or a full URL,
+/// which is used as is.
+pub fn ollama_base_url(host: &str) -> String {
+    let host = host.trim().trim_end_matches('/');
+    if host.contains("://") {
+        return host.to_string();
+    }
+    // An IPv6 address has colons of its own; only a colon after `]` is a port
+    let has_port = match host.rfind(']') {
+        Some(bracket) => host[bracket..].contains(':'),
+        None => host.contains(':'),
+    };
+    let port = if has_port { "" } else { ":11434" };
+    format!("http://{}{}", host, port)
+}
+
+/// Splits a streamed response body into lines, however its chunks happen
+/// to be cut (even in the middle of a UTF-8 character).
+#[derive(Debug, Default)]
+pub struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Add `chunk` and return the lines it completes, without their line
+    /// endings.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            lines.push(String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']).to_string());
+        }
+        lines
+    }
+
+    /// The last line, if the body did not end with a line break.
+    pub fn finish(self) -> Option<String> {
+        let line = String::from_utf8_lossy(&self.pending).trim_end_matches('\r').to_string();
+        (!line.is_empty()).then_some(line)
+    }
+}
+
+/// The payload of a server-sent events `data:` line, if `line` is one.
+pub fn sse_data(line: &str) -> Option<&str> {
+    let data = line.strip_prefix("data:")?;
+    Some(data.strip_prefix(' ').unwrap_or(data))
+}
+
+// Turn a failed response into an error: `ContextTooLarge` if the body
+// contains one of `too_long` (each API words this its own way), otherwise
+// `ProviderError` with the status, body and any `Retry-After`
+#[cfg(feature = "online")]
+async fn check_status(response: reqwest::Response, too_long: &[&str]) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).ok()?;
+            retry::parse_retry_after(value, now.as_secs() as i64)
+        });
+    let body = response.text().await?;
+    if too_long.iter().any(|marker| body.contains(marker)) {
+        return Err(WtfError::ContextTooLarge { body });
+    }
+    Err(WtfError::ProviderError { status: status.as_u16(), body, retry_after })
+}
+
+// Pass each line of a streamed response to `on_line` as it arrives
+#[cfg(feature = "online")]
+async fn read_lines(mut response: reqwest::Response, mut on_line: impl FnMut(&str) -> Result<()>) -> Result<()> {
+    let mut lines = LineBuffer::default();
+    while let Some(chunk) = response.chunk().await? {
+        for line in lines.push(&chunk) {
+            on_line(&line)?;
+        }
+    }
+    match lines.finish() {
+        Some(line) => on_line(&line),
+        None => Ok(()),
+    }
+}
+
+/// Split `messages` the way APIs without a system role want them: all
+/// system messages joined into one system prompt, and the rest with
+/// consecutive messages of the same role merged, so users and the
+/// assistant strictly take turns.
+pub fn split_system(messages: &[Message]) -> (Option<String>, Vec<Message>) {
+    let mut system: Vec<&str> = Vec::new();
+    let mut turns: Vec<Message> = Vec::new();
+    for message in messages {
+        if message.role == "system" {
+            system.push(&message.content);
+            continue;
+        }
+        match turns.last_mut() {
+            Some(last) if last.role == message.role => {
+                last.content.push_str("\n\n");
+                last.content.push_str(&message.content);
+            }
+            _ => turns.push(message.clone()),
+        }
+    }
+    let system = if system.is_empty() { None } else { Some(system.join("\n\n")) };
+    (system, turns)
+}
+
+/// A chat-style language model.
+pub trait LlmProvider: Send + Sync {
+    /// Send `messages` to the model and return the text of its reply.
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>>;
+
+    /// Like [`LlmProvider::chat`], but ask for a JSON reply matching `schema`.
+    ///
+    /// The default implementation describes the schema in an extra system
+    /// message; providers with a native JSON mode should override this. The
+    /// reply is validated by the caller either way.
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let mut messages = messages.to_vec();
+            messages.push(Message::system(schema_instructions(schema)));
+            self.chat(&messages).await
+        })
+    }
+
+    /// Like [`LlmProvider::chat`], but hand the reply to `on_text` piece by
+    /// piece as it arrives. The whole reply is returned as well.
+    ///
+    /// The default implementation waits for the whole reply and passes it
+    /// on in one piece.
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let reply = self.chat(messages).await?;
+            on_text(&reply);
+            Ok(reply)
+        })
+    }
+
+    /// How many tokens `text` takes up in the model's context window.
+    fn count_tokens(&self, text: &str) -> usize {
+        tokens::estimate(text)
+    }
+
+    /// The size of the model's context window in tokens, prompt and reply
+    /// together.
+    fn max_context(&self) -> usize {
+        DEFAULT_MAX_CONTEXT
+    }
+
+    /// The model that answers, if known, so replies of different models
+    /// can be told apart.
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    /// What the model charges, if known.
+    fn price(&self) -> Option<Price> {
+        None
+    }
+}
+
+/// What a model charges, in US dollars per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl Price {
+    /// Models running on your own machine.
+    pub const FREE: Price = Price { input: 0.0, output: 0.0 };
+
+    /// The cost in dollars of `usage`.
+    pub fn cost(&self, usage: Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input + usage.output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// The tokens one request used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+}
+
+// Tally what a request used: what the API reported, or else an estimate
+#[cfg(feature = "online")]
+fn record_usage(provider: &dyn LlmProvider, reported: Option<Usage>, messages: &[Message], reply: &str) {
+    let usage = reported.unwrap_or_else(|| Usage {
+        input_tokens: messages.iter().map(|message| provider.count_tokens(&message.content)).sum(),
+        output_tokens: provider.count_tokens(reply),
+    });
+    crate::cost::record(usage, provider.price());
+}
+
+// Prices at the time of writing, by a name the model contains; the
+// longest match wins, so variants can be listed after their family
+#[cfg(feature = "online")]
+fn price_by_name(model: &str, prices: &[(&str, Price)]) -> Option<Price> {
+    prices
+        .iter()
+        .filter(|(name, _)| model.contains(name))
+        .max_by_key(|(name, _)| name.len())
+        .map(|(_, price)| *price)
+}
+
+// Claude models, from Anthropic or on Bedrock, in dollars per million tokens
+#[cfg(feature = "online")]
+const CLAUDE_PRICES: &[(&str, Price)] = &[
+    ("claude-3-haiku", Price { input: 0.25, output: 1.25 }),
+    ("claude-3-5-haiku", Price { input: 0.80, output: 4.0 }),
+    ("haiku-4", Price { input: 1.0, output: 5.0 }),
+    ("sonnet", Price { input: 3.0, output: 15.0 }),
+    ("opus", Price { input: 15.0, output: 75.0 }),
+    ("opus-4-5", Price { input: 5.0, output: 25.0 }),
+];
+
+/// The context window assumed for models nothing more is known about.
+pub const DEFAULT_MAX_CONTEXT: usize = 8_192;
+
+/// Instructions asking the model to answer with JSON matching `schema`.
+pub fn schema_instructions(schema: &Schema) -> String {
+    format!(
+        "Respond only with a single JSON value, without any surrounding prose or code fences, that conforms to this JSON schema:\n{}",
+        schema.schema
+    )
+}
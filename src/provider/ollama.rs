@@ -0,0 +1,198 @@
+use super::{
+    check_status, http, ollama_base_url, read_lines, record_usage, schema_instructions, BoxFuture, LlmProvider,
+    Message, Price, ProviderConfig, Sampling, Usage, OLLAMA_DEFAULT_HOST,
+};
+use crate::error::{Result, WtfError};
+use crate::structured::Schema;
+use crate::{progress, tr};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Debug)]
+struct OllamaRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    stream: bool,
+    options: Options,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a Value>,
+}
+
+#[derive(Serialize, Debug)]
+struct Options {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    // Ollama's name for the longest reply
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OllamaResponse {
+    message: Message,
+    #[serde(flatten)]
+    counts: EvalCounts,
+}
+
+// One line of a streamed reply; the last one has the counts
+#[derive(Deserialize, Debug)]
+struct StreamChunk {
+    message: Option<Message>,
+    error: Option<String>,
+    #[serde(flatten)]
+    counts: EvalCounts,
+}
+
+// Ollama leaves out the prompt count when it reused an evaluated prompt
+#[derive(Deserialize, Debug)]
+struct EvalCounts {
+    #[serde(default)]
+    prompt_eval_count: usize,
+    eval_count: Option<usize>,
+}
+
+impl EvalCounts {
+    fn usage(&self) -> Option<Usage> {
+        let output_tokens = self.eval_count?;
+        Some(Usage { input_tokens: self.prompt_eval_count, output_tokens })
+    }
+}
+
+/// A local Ollama server; nothing leaves the machine and no API key is needed.
+pub struct OllamaProvider {
+    client: Client,
+    base_url: String,
+    model: String,
+    sampling: Sampling,
+}
+
+impl OllamaProvider {
+    /// Talk to the server at `base_url`, like `http://localhost:11434`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        OllamaProvider {
+            client: http::default_client(),
+            base_url: base_url.into(),
+            model: "llama3".to_string(),
+            sampling: Sampling::default(),
+        }
+    }
+
+    /// Build from the optional `host` (as `OLLAMA_HOST` takes it), `model`
+    /// and sampling settings.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let mut provider = OllamaProvider::new(ollama_base_url(config.get("host").unwrap_or(OLLAMA_DEFAULT_HOST)));
+        provider.client = http::client_for(config)?;
+        provider.sampling = Sampling::from_config(config)?;
+        Ok(match config.get("model") {
+            Some(model) => provider.with_model(model),
+            None => provider,
+        })
+    }
+
+    /// Use `model` instead of the default.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    // Send the request and check its status, leaving the body unread
+    async fn post(&self, messages: &[Message], format: Option<&Value>, stream: bool) -> Result<reqwest::Response> {
+        progress!("{}", tr!("progress-sending", provider = "Ollama"));
+
+        let request = OllamaRequest {
+            model: &self.model,
+            messages,
+            stream,
+            options: Options {
+                temperature: self.sampling.temperature,
+                top_p: self.sampling.top_p,
+                num_predict: self.sampling.max_tokens,
+            },
+            format,
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&request)
+            .send()
+            .await?;
+
+        let response = check_status(response, &[]).await?;
+        Ok(response)
+    }
+
+    async fn send(&self, messages: &[Message], format: Option<&Value>) -> Result<String> {
+        let response = self.post(messages, format, false).await?;
+        progress!("{}", tr!("progress-received", provider = "Ollama"));
+
+        match response.json::<OllamaResponse>().await {
+            Ok(response_data) => {
+                let reply = response_data.message.content;
+                record_usage(self, response_data.counts.usage(), messages, &reply);
+                Ok(reply)
+            }
+            Err(e) => Err(WtfError::InvalidResponse(e.to_string())),
+        }
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.send(messages, None))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let response = self.post(messages, None, true).await?;
+            let mut reply = String::new();
+            let mut usage = None;
+            // A streamed reply is one JSON object per line
+            read_lines(response, |line| {
+                if line.trim().is_empty() {
+                    return Ok(());
+                }
+                let chunk: StreamChunk =
+                    serde_json::from_str(line).map_err(|e| WtfError::InvalidResponse(e.to_string()))?;
+                if let Some(error) = chunk.error {
+                    return Err(WtfError::InvalidResponse(error));
+                }
+                usage = chunk.counts.usage().or(usage);
+                if let Some(message) = chunk.message {
+                    on_text(&message.content);
+                    reply.push_str(&message.content);
+                }
+                Ok(())
+            })
+            .await?;
+            progress!("{}", tr!("progress-received", provider = "Ollama"));
+            record_usage(self, usage, messages, &reply);
+            Ok(reply)
+        })
+    }
+
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            // Ollama constrains the output to the schema, but small local
+            // models still do better with it spelled out in the prompt
+            let mut messages = messages.to_vec();
+            messages.push(Message::system(schema_instructions(schema)));
+            self.send(&messages, Some(&schema.schema)).await
+        })
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn price(&self) -> Option<Price> {
+        Some(Price::FREE)
+    }
+}
@@ -0,0 +1,343 @@
+use super::{
+    check_status, http, openai_chat_url, price_by_name, read_lines, record_usage, schema_instructions, sse_data,
+    AzureDeployment, BoxFuture, LlmProvider, Message, Price, ProviderConfig, Sampling, Usage,
+};
+use crate::error::{Result, WtfError};
+use crate::structured::Schema;
+use crate::{progress, tr};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Serialize, Debug)]
+struct OpenAIRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    // OpenAI has replaced `max_tokens`, which its reasoning models reject;
+    // compatible servers and Azure still take the old name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenAIResponse {
+    choices: Vec<Choice>,
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenUsage {
+    prompt_tokens: usize,
+    completion_tokens: usize,
+}
+
+impl From<TokenUsage> for Usage {
+    fn from(usage: TokenUsage) -> Usage {
+        Usage { input_tokens: usage.prompt_tokens, output_tokens: usage.completion_tokens }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Choice {
+    message: Message,
+}
+
+// One server-sent event of a streamed reply
+#[derive(Deserialize, Debug)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamChoice {
+    delta: Delta,
+}
+
+#[derive(Deserialize, Debug)]
+struct Delta {
+    content: Option<String>,
+}
+
+// In dollars per million tokens
+const PRICES: &[(&str, Price)] = &[
+    ("gpt-3.5-turbo", Price { input: 0.50, output: 1.50 }),
+    ("gpt-4", Price { input: 30.0, output: 60.0 }),
+    ("gpt-4-turbo", Price { input: 10.0, output: 30.0 }),
+    ("gpt-4o", Price { input: 2.50, output: 10.0 }),
+    ("gpt-4o-mini", Price { input: 0.15, output: 0.60 }),
+    ("gpt-4.1", Price { input: 2.0, output: 8.0 }),
+    ("gpt-4.1-mini", Price { input: 0.40, output: 1.60 }),
+    ("gpt-4.1-nano", Price { input: 0.10, output: 0.40 }),
+    ("gpt-5", Price { input: 1.25, output: 10.0 }),
+    ("gpt-5-mini", Price { input: 0.25, output: 2.0 }),
+    ("gpt-5-nano", Price { input: 0.05, output: 0.40 }),
+    ("o1", Price { input: 15.0, output: 60.0 }),
+    ("o3", Price { input: 2.0, output: 8.0 }),
+    ("o3-mini", Price { input: 1.10, output: 4.40 }),
+    ("o4-mini", Price { input: 1.10, output: 4.40 }),
+];
+
+/// The OpenAI chat completions API, from OpenAI, an OpenAI-compatible server
+/// or an Azure OpenAI deployment.
+pub struct OpenAiProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    sampling: Sampling,
+    api_base: Option<String>,
+    azure: Option<AzureDeployment>,
+}
+
+impl OpenAiProvider {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        OpenAiProvider {
+            client: http::default_client(),
+            api_key: api_key.into(),
+            model: "gpt-3.5-turbo".to_string(),
+            sampling: Sampling::default(),
+            api_base: None,
+            azure: None,
+        }
+    }
+
+    /// Send requests to an Azure OpenAI deployment instead, which picks the
+    /// model itself and takes the key in an `api-key` header.
+    pub fn azure(api_key: impl Into<String>, deployment: AzureDeployment) -> Self {
+        OpenAiProvider {
+            model: deployment.deployment.clone(),
+            azure: Some(deployment),
+            ..OpenAiProvider::new(api_key)
+        }
+    }
+
+    /// Send requests to the OpenAI-compatible API at `api_base` (OpenRouter,
+    /// vLLM, LM Studio, a proxy) instead of OpenAI's. Servers that take no
+    /// key can be given an empty one.
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = Some(api_base.into());
+        self
+    }
+
+    /// Build from the `api_key` and optional `model`, `api_base` and
+    /// sampling settings. The key may be missing if there is an `api_base`, since
+    /// local OpenAI-compatible servers often take none.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let api_key = match config.get("api_base") {
+            Some(_) => config.get("api_key").unwrap_or_default(),
+            None => config.api_key("OPENAI_API_KEY")?,
+        };
+        let mut provider = OpenAiProvider::new(api_key);
+        provider.client = http::client_for(config)?;
+        provider.sampling = Sampling::from_config(config)?;
+        if let Some(api_base) = config.get("api_base") {
+            provider = provider.with_api_base(api_base);
+        }
+        Ok(match config.get("model") {
+            Some(model) => provider.with_model(model),
+            None => provider,
+        })
+    }
+
+    /// Build an Azure deployment from the `api_key`, `endpoint`,
+    /// `deployment` and optional `api_version` and sampling settings.
+    pub fn azure_from_config(config: &ProviderConfig) -> Result<Self> {
+        let deployment = AzureDeployment {
+            endpoint: config.require("endpoint", "AZURE_OPENAI_ENDPOINT")?.to_string(),
+            deployment: config.require("deployment", "AZURE_OPENAI_DEPLOYMENT")?.to_string(),
+            api_version: config.get("api_version").unwrap_or(super::AZURE_DEFAULT_API_VERSION).to_string(),
+        };
+        let mut provider = OpenAiProvider::azure(config.api_key("AZURE_OPENAI_API_KEY")?, deployment);
+        provider.client = http::client_for(config)?;
+        provider.sampling = Sampling::from_config(config)?;
+        Ok(provider)
+    }
+
+    /// Use `model` instead of the default.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Whether the model supports `json_schema` response formats. Servers at
+    /// another `api_base` get JSON mode, as their models may share OpenAI's
+    /// names without its schema support.
+    fn supports_json_schema(&self) -> bool {
+        match (&self.azure, &self.api_base) {
+            (Some(azure), _) => azure.supports_json_schema(),
+            (None, Some(_)) => false,
+            (None, None) => super::openai_supports_json_schema(&self.model),
+        }
+    }
+
+    fn is_openai(&self) -> bool {
+        self.azure.is_none() && self.api_base.is_none()
+    }
+
+    fn name(&self) -> &str {
+        match (&self.azure, &self.api_base) {
+            (Some(_), _) => "Azure OpenAI",
+            (None, Some(api_base)) => api_base.as_str(),
+            (None, None) => "OpenAI",
+        }
+    }
+
+    // Send the request and check its status, leaving the body unread
+    async fn post(&self, messages: &[Message], response_format: Option<Value>, stream: bool) -> Result<reqwest::Response> {
+        progress!("{}", tr!("progress-sending", provider = self.name()));
+
+        let request = OpenAIRequest {
+            model: &self.model,
+            messages,
+            temperature: self.sampling.temperature,
+            top_p: self.sampling.top_p,
+            max_completion_tokens: self.sampling.max_tokens.filter(|_| self.is_openai()),
+            max_tokens: self.sampling.max_tokens.filter(|_| !self.is_openai()),
+            response_format,
+            stream,
+            // Only OpenAI itself is known to report usage at the end of a stream
+            stream_options: (stream && self.is_openai()).then(|| json!({ "include_usage": true })),
+        };
+
+        let builder = match &self.azure {
+            Some(azure) => self.client.post(azure.chat_url()).header("api-key", &self.api_key),
+            None => {
+                let api_base = self.api_base.as_deref().unwrap_or(super::OPENAI_DEFAULT_API_BASE);
+                let builder = self.client.post(openai_chat_url(api_base));
+                if self.api_key.is_empty() {
+                    builder
+                } else {
+                    builder.header("Authorization", format!("Bearer {}", self.api_key))
+                }
+            }
+        };
+        let response = builder
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await?;
+
+        let response = check_status(response, &["context_length_exceeded"]).await?;
+        Ok(response)
+    }
+
+    async fn send(&self, messages: &[Message], response_format: Option<Value>) -> Result<String> {
+        let response = self.post(messages, response_format, false).await?;
+        progress!("{}", tr!("progress-received", provider = self.name()));
+
+        // Parse the response
+        match response.json::<OpenAIResponse>().await {
+            Ok(response_data) => {
+                if let Some(choice) = response_data.choices.into_iter().next() {
+                    let reply = choice.message.content;
+                    record_usage(self, response_data.usage.map(Usage::from), messages, &reply);
+                    Ok(reply)
+                } else {
+                    Err(WtfError::InvalidResponse("no choices in response".to_string()))
+                }
+            }
+            Err(e) => Err(WtfError::InvalidResponse(e.to_string())),
+        }
+    }
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.send(messages, None))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let response = self.post(messages, None, true).await?;
+            let mut reply = String::new();
+            let mut usage = None;
+            read_lines(response, |line| {
+                let Some(data) = sse_data(line).filter(|data| *data != "[DONE]") else {
+                    return Ok(());
+                };
+                let chunk: StreamChunk =
+                    serde_json::from_str(data).map_err(|e| WtfError::InvalidResponse(e.to_string()))?;
+                usage = chunk.usage.map(Usage::from).or(usage);
+                // Azure sends an empty `choices` first, for its content filter results
+                for text in chunk.choices.into_iter().filter_map(|choice| choice.delta.content) {
+                    on_text(&text);
+                    reply.push_str(&text);
+                }
+                Ok(())
+            })
+            .await?;
+            progress!("{}", tr!("progress-received", provider = self.name()));
+            record_usage(self, usage, messages, &reply);
+            Ok(reply)
+        })
+    }
+
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            if self.supports_json_schema() {
+                let format = json!({
+                    "type": "json_schema",
+                    "json_schema": { "name": schema.name, "schema": schema.schema },
+                });
+                return self.send(messages, Some(format)).await;
+            }
+
+            // JSON mode still needs the schema spelled out in the prompt
+            let mut messages = messages.to_vec();
+            messages.push(Message::system(schema_instructions(schema)));
+            self.send(&messages, Some(json!({ "type": "json_object" }))).await
+        })
+    }
+
+    fn max_context(&self) -> usize {
+        // Azure deployments and compatible servers can't be told by name
+        if self.azure.is_some() || self.api_base.is_some() {
+            return super::DEFAULT_MAX_CONTEXT;
+        }
+        let model = self.model.as_str();
+        let reasoning = super::OPENAI_REASONING_MODELS.iter().any(|prefix| model.starts_with(prefix));
+        if model.starts_with("gpt-4.1") {
+            1_047_576
+        } else if model.starts_with("gpt-5") {
+            400_000
+        } else if model.starts_with("gpt-4o") || model.starts_with("gpt-4-turbo") || reasoning {
+            128_000
+        } else if model.starts_with("gpt-4-32k") {
+            32_768
+        } else if model.starts_with("gpt-4") {
+            8_192
+        } else {
+            // gpt-3.5-turbo
+            16_385
+        }
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn price(&self) -> Option<Price> {
+        // What Azure deployments and compatible servers charge is up to them
+        if !self.is_openai() {
+            return None;
+        }
+        price_by_name(&self.model, PRICES)
+    }
+}
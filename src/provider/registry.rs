@@ -0,0 +1,204 @@
+//! Building providers by name.
+//!
+//! Each backend registers a constructor under its name. A constructor gets
+//! the provider's [`ProviderConfig`]: its `[providers.<name>]` section of
+//! wtf's `config.toml`, with whatever the command line and environment say
+//! layered on top. Adding a backend means adding a registration, not
+//! another branch wherever a provider is needed.
+
+use super::{BoxFuture, LlmProvider, Message, Price, RetryPolicy, Retrying};
+use crate::config;
+use crate::error::{Result, WtfError};
+use crate::structured::Schema;
+use std::path::Path;
+
+/// One provider's settings, like `model`, `api_key` or `endpoint`. Every
+/// provider also takes the [`Sampling`](super::Sampling) settings `temperature`,
+/// `top_p` and `max_tokens`, as well as `proxy`, `connect_timeout` and
+/// `request_timeout`, and those [`Registry::build`] reads.
+///
+/// In `config.toml`:
+///
+/// ```toml
+/// [providers.openai]
+/// model = "gpt-4o"
+/// temperature = 0.2
+/// commits_temperature = 0
+/// max_tokens = 1024
+/// max_context = 128000
+/// max_retries = 5
+///
+/// [providers.ollama]
+/// host = "gpu-box:11434"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderConfig {
+    values: Vec<(String, String)>,
+}
+
+impl ProviderConfig {
+    /// The `[providers.<name>]` section in the contents of a `config.toml`.
+    pub fn parse(contents: &str, name: &str) -> ProviderConfig {
+        let mut config = ProviderConfig::default();
+        for (key, value) in config::toml_table(contents, &format!("providers.{}", name)) {
+            config.set(&key, value);
+        }
+        config
+    }
+
+    /// The section for `name` in `config.toml` in `config_dir`, which is
+    /// empty if the file or the section doesn't exist.
+    pub fn load(config_dir: &Path, name: &str) -> ProviderConfig {
+        std::fs::read_to_string(config_dir.join(config::CONFIG_FILE))
+            .map(|contents| ProviderConfig::parse(&contents, name))
+            .unwrap_or_default()
+    }
+
+    /// The value of `key`, if set and not empty.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .filter(|v| !v.is_empty())
+    }
+
+    /// The value of `key` parsed as a `T`, if set and readable as one.
+    pub fn parsed<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key).and_then(|value| value.trim().parse().ok())
+    }
+
+    /// Set `key`, replacing any earlier value.
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.values.iter_mut().find(|(k, _)| k == key) {
+            Some((_, old)) => *old = value,
+            None => self.values.push((key.to_string(), value)),
+        }
+    }
+
+    /// Set `key` if `value` is given, so a more specific source can be
+    /// layered over a less specific one.
+    pub fn layer(&mut self, key: &str, value: Option<String>) {
+        if let Some(value) = value {
+            self.set(key, value);
+        }
+    }
+
+    /// The value of `key`, or [`WtfError::MissingSetting`] naming the
+    /// setting `var` that would provide it.
+    pub fn require(&self, key: &str, var: &'static str) -> Result<&str> {
+        self.get(key).ok_or(WtfError::MissingSetting { var })
+    }
+
+    /// The `api_key`, or [`WtfError::MissingCredentials`] naming `var`.
+    pub fn api_key(&self, var: &'static str) -> Result<&str> {
+        self.get("api_key").ok_or(WtfError::MissingCredentials { var })
+    }
+}
+
+/// Builds a provider from its settings.
+pub type Constructor = fn(&ProviderConfig) -> Result<Box<dyn LlmProvider>>;
+
+/// The providers wtf can build, by name.
+#[derive(Default)]
+pub struct Registry {
+    providers: Vec<(String, Constructor)>,
+}
+
+impl Registry {
+    /// A registry with every backend this build of wtf includes.
+    #[cfg_attr(not(feature = "online"), allow(unused_mut))]
+    pub fn builtin() -> Registry {
+        let mut registry = Registry::default();
+        #[cfg(feature = "online")]
+        {
+            use super::{AnthropicProvider, BedrockProvider, GeminiProvider, OllamaProvider, OpenAiProvider};
+            registry.register("openai", |config| Ok(Box::new(OpenAiProvider::from_config(config)?)));
+            registry.register("anthropic", |config| Ok(Box::new(AnthropicProvider::from_config(config)?)));
+            registry.register("ollama", |config| Ok(Box::new(OllamaProvider::from_config(config)?)));
+            registry.register("azure", |config| Ok(Box::new(OpenAiProvider::azure_from_config(config)?)));
+            registry.register("gemini", |config| Ok(Box::new(GeminiProvider::from_config(config)?)));
+            registry.register("bedrock", |config| Ok(Box::new(BedrockProvider::from_config(config)?)));
+        }
+        registry
+    }
+
+    /// Register `constructor` under `name`, replacing any provider of that
+    /// name.
+    pub fn register(&mut self, name: &str, constructor: Constructor) {
+        self.providers.retain(|(known, _)| known != name);
+        self.providers.push((name.to_string(), constructor));
+    }
+
+    /// The registered names, in registration order.
+    pub fn names(&self) -> Vec<&str> {
+        self.providers.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Build the provider registered as `name` (ignoring case) from
+    /// `config`. Transient failures are retried `max_retries` times (3 if
+    /// not set), and a `max_context` setting overrides the provider's own
+    /// idea of its context window.
+    pub fn build(&self, name: &str, config: &ProviderConfig) -> Result<Box<dyn LlmProvider>> {
+        let name = name.trim();
+        let (_, constructor) = self
+            .providers
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .ok_or_else(|| WtfError::UnknownProvider { name: name.to_string() })?;
+        let mut provider = constructor(config)?;
+        let mut policy = RetryPolicy::default();
+        if let Some(retries) = config.get("max_retries").and_then(|retries| retries.parse().ok()) {
+            policy.max_retries = retries;
+        }
+        if policy.max_retries > 0 {
+            provider = Box::new(Retrying::new(provider, policy));
+        }
+        Ok(match config.get("max_context").and_then(|tokens| tokens.parse().ok()) {
+            Some(max_context) => Box::new(WithContext { inner: provider, max_context }),
+            None => provider,
+        })
+    }
+}
+
+// A provider whose context window was configured by hand, for models
+// (fine-tunes, local servers, Azure deployments) that can't be told by name
+struct WithContext {
+    inner: Box<dyn LlmProvider>,
+    max_context: usize,
+}
+
+impl LlmProvider for WithContext {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        self.inner.chat(messages)
+    }
+
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        self.inner.chat_json(messages, schema)
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        self.inner.stream(messages, on_text)
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.inner.count_tokens(text)
+    }
+
+    fn max_context(&self) -> usize {
+        self.max_context
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn price(&self) -> Option<Price> {
+        self.inner.price()
+    }
+}
@@ -0,0 +1,219 @@
+//! Retrying transient provider failures.
+//!
+//! Rate limits (HTTP 429), overloaded or failing servers (5xx) and dropped
+//! connections are usually gone a moment later. [`Retrying`] wraps a
+//! provider and tries again after a while: as long as the provider's
+//! `Retry-After` says, or else with exponential backoff and jitter, so many
+//! clients hit by the same outage don't all come back at once.
+
+use super::{BoxFuture, LlmProvider, Message, Price};
+use crate::error::{Result, WtfError};
+use crate::structured::Schema;
+use crate::{progress, tr};
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How often and how patiently to retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 turns retrying off.
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled for each one after.
+    pub base_delay: Duration,
+    /// The longest wait between attempts, `Retry-After` included.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether `err` is worth another attempt.
+    pub fn is_transient(err: &WtfError) -> bool {
+        match err {
+            // 408 is a request timeout, 529 is Anthropic's "overloaded"
+            WtfError::ProviderError { status, .. } => matches!(status, 408 | 429 | 500..=599),
+            #[cfg(feature = "online")]
+            WtfError::Http(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            _ => false,
+        }
+    }
+
+    /// How long to wait before retry number `retry` (starting at 1) after
+    /// `err`: the provider's `Retry-After` if it sent one, or else
+    /// `base_delay` doubled for each earlier retry, with "equal jitter"
+    /// (half fixed, half random). Either way at most `max_delay`.
+    pub fn delay(&self, retry: u32, err: &WtfError) -> Duration {
+        if let WtfError::ProviderError { retry_after: Some(wait), .. } = err {
+            return (*wait).min(self.max_delay);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = backoff / 2;
+        half + half.mul_f64(random_fraction())
+    }
+}
+
+// A random number in [0, 1), from the standard library's randomly seeded
+// hasher; jitter needs no better
+fn random_fraction() -> f64 {
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Parse a `Retry-After` header: a number of seconds, or an HTTP date
+/// (`Wed, 21 Oct 2015 07:28:00 GMT`) compared against `now`, in seconds
+/// since the epoch. A date in the past means no wait at all.
+pub fn parse_retry_after(value: &str, now: i64) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    // Only the IMF-fixdate form; the obsolete ones are long gone
+    let mut parts = value.split_whitespace().skip(1);
+    let day: i64 = parts.next()?.parse().ok()?;
+    let month = match parts.next()? {
+        "Jan" => 1,
+        "Feb" => 2,
+        "Mar" => 3,
+        "Apr" => 4,
+        "May" => 5,
+        "Jun" => 6,
+        "Jul" => 7,
+        "Aug" => 8,
+        "Sep" => 9,
+        "Oct" => 10,
+        "Nov" => 11,
+        "Dec" => 12,
+        _ => return None,
+    };
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':').map(|part| part.parse::<i64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    let at = days_from_civil(year, month, day) * 86_400 + hours * 3600 + minutes * 60 + seconds;
+    Some(Duration::from_secs((at - now).max(0) as u64))
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's
+// algorithm)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// A provider that retries the requests of another on transient failures.
+pub struct Retrying {
+    inner: Box<dyn LlmProvider>,
+    policy: RetryPolicy,
+}
+
+impl Retrying {
+    pub fn new(inner: Box<dyn LlmProvider>, policy: RetryPolicy) -> Self {
+        Retrying { inner, policy }
+    }
+
+    // Run `attempt` until it succeeds, fails for good or retries run out
+    async fn retry<'a>(&'a self, mut attempt: impl FnMut() -> BoxFuture<'a, Result<String>>) -> Result<String> {
+        let mut retries = 0;
+        loop {
+            match attempt().await {
+                Err(err) if RetryPolicy::is_transient(&err) => self.back_off(&mut retries, err).await?,
+                result => return result,
+            }
+        }
+    }
+
+    // Wait before the next try after `err`, or give up with it if that was
+    // the last one
+    async fn back_off(&self, retries: &mut u32, err: WtfError) -> Result<()> {
+        if *retries == self.policy.max_retries {
+            return Err(match *retries {
+                0 => err,
+                _ => WtfError::GaveUp { attempts: *retries + 1, last: Box::new(err) },
+            });
+        }
+        *retries += 1;
+        let delay = self.policy.delay(*retries, &err);
+        let reason = match &err {
+            WtfError::ProviderError { status, .. } => format!("HTTP {}", status),
+            other => other.to_string(),
+        };
+        progress!(
+            "{}",
+            tr!(
+                "progress-retrying",
+                reason = reason,
+                seconds = format!("{:.1}", delay.as_secs_f64()),
+                attempt = *retries + 1,
+                attempts = self.policy.max_retries + 1
+            )
+        );
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+}
+
+impl LlmProvider for Retrying {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.retry(|| self.inner.chat(messages)))
+    }
+
+    fn chat_json<'a>(&'a self, messages: &'a [Message], schema: &'a Schema) -> BoxFuture<'a, Result<String>> {
+        Box::pin(self.retry(|| self.inner.chat_json(messages, schema)))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Message],
+        on_text: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            // Text already shown can't be taken back, so only a stream that
+            // failed before its first piece is tried again
+            let mut shown = false;
+            let mut retries = 0;
+            loop {
+                let result = self
+                    .inner
+                    .stream(messages, &mut |text| {
+                        shown = true;
+                        on_text(text)
+                    })
+                    .await;
+                match result {
+                    Err(err) if !shown && RetryPolicy::is_transient(&err) => self.back_off(&mut retries, err).await?,
+                    result => return result,
+                }
+            }
+        })
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.inner.count_tokens(text)
+    }
+
+    fn max_context(&self) -> usize {
+        self.inner.max_context()
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn price(&self) -> Option<Price> {
+        self.inner.price()
+    }
+}
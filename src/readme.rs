@@ -0,0 +1,177 @@
+//! `wtf readme`: a README drafted from the code, instead of read by it.
+//!
+//! The rest of wtf takes the README as given; this turns that around. The
+//! layout of the tree at HEAD, the manifests at its root, the build and
+//! test commands they declare, the start of the files programs start from
+//! and the recent commit subjects go to the model, which drafts a README
+//! from them or, when the tree has one, updates it: what the tree still
+//! bears out is kept, in the README's own structure and voice, and what it
+//! contradicts or leaves out is fixed. Nothing is written; the proposal is
+//! printed whole or, with `--diff`, as a patch against the README at HEAD.
+
+use crate::analysis::{self, RunEstimate};
+use crate::chunk;
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::git::{self, RepositoryExt};
+use crate::message;
+use crate::onboard;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::Repository;
+use serde::Serialize;
+use std::path::Path;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that writes the documentation of software projects.";
+
+const DRAFT_PROMPT: &str = "Below are the layout of a project's tree with the number of files in each directory, the manifests at its root, the build and test commands they declare, the start of the files its programs start from and its latest commit subjects. Write a README for it in Markdown: a level-one heading with the project's name, a paragraph on what it does and for whom, then sections on installing it, using it with an example or two, and building and testing it, and a section on its license if the material names one. Use only commands, names and options the material shows, and leave out what it does not tell. Reply with only the README, without code fences.";
+
+const UPDATE_PROMPT: &str = "Below are a project's current README, then the layout of its tree with the number of files in each directory, the manifests at its root, the build and test commands they declare, the start of the files its programs start from and its latest commit subjects. Update the README to match the code: keep what the material still bears out, in the README's own structure, voice and markup; correct what it contradicts, like renamed commands, options or paths; and add what is missing, like a feature the recent commits added or a section on building and testing. Use only commands, names and options the material shows. Reply with only the whole updated README, without code fences.";
+
+// Manifests whose start is shown to the model, for names, descriptions and licenses
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.cfg",
+    "setup.py",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "CMakeLists.txt",
+    "composer.json",
+    "Gemfile",
+];
+
+// Recent commit subjects shown to the model
+const RECENT_COMMITS: usize = 30;
+
+// Entry points whose start is shown to the model
+const MAX_ENTRY_FILES: usize = 3;
+
+// Each manifest and entry point is cut to this many tokens; the current
+// README is sent whole, since what is cut off would be lost from the update
+const MANIFEST_TOKENS: usize = 600;
+const ENTRY_TOKENS: usize = 1_000;
+
+/// The tree at HEAD and its recent history, read before any model is called.
+#[derive(Debug, Clone)]
+pub struct ReadmeInput {
+    pub repo_name: String,
+    /// The README at HEAD, or `README.md` when there is none.
+    pub path: String,
+    /// The README at HEAD, if there is one.
+    pub current: Option<String>,
+    // The layout, manifests, commands, entry points and commits, for the prompt
+    content: String,
+}
+
+/// Read what a README of `repo` should cover from the tree at HEAD and the
+/// latest commits.
+pub fn prepare(repo: &Repository) -> Result<ReadmeInput> {
+    let paths = git::head_tree_paths(repo)?;
+    progress!("{}", tr!("progress-readme", count = paths.len()));
+    let read = |path: &str| repo.find_file(path).ok();
+    let path = onboard::readme_path(&paths).cloned();
+    let current = path.as_deref().and_then(read);
+
+    let mut content = format!("Layout:\n\n{}", onboard::layout(&paths));
+    for manifest in MANIFESTS.iter().filter(|manifest| paths.iter().any(|known| known == *manifest)) {
+        if let Some(text) = read(manifest) {
+            let (start, _) = chunk::truncate_to_tokens(&text, MANIFEST_TOKENS);
+            content.push_str(&format!("\n---\n\n{}:\n\n{}\n", manifest, start));
+        }
+    }
+    let commands = onboard::build_commands(&paths, read);
+    if !commands.is_empty() {
+        let listed: Vec<String> =
+            commands.iter().map(|command| format!("- {} ({})", command.command, command.source)).collect();
+        content.push_str(&format!("\n---\n\nCommands:\n\n{}\n", listed.join("\n")));
+    }
+    for entry in onboard::entry_points(&paths, read("package.json").as_deref()).iter().take(MAX_ENTRY_FILES) {
+        if let Some(text) = read(entry) {
+            let (start, _) = chunk::truncate_to_tokens(&text, ENTRY_TOKENS);
+            content.push_str(&format!("\n---\n\nStart of {}:\n\n{}\n", entry, start));
+        }
+    }
+    let (commits, _) = git::select_commits(repo, RECENT_COMMITS)?;
+    let subjects: Vec<String> = commits
+        .iter()
+        .map(|commit| format!("- {}", git::commit_message(commit).lines().next().unwrap_or_default()))
+        .collect();
+    content.push_str(&format!("\n---\n\nLatest commits:\n\n{}\n", subjects.join("\n")));
+
+    Ok(ReadmeInput {
+        repo_name: analysis::repo_name(repo),
+        path: path.unwrap_or_else(|| "README.md".to_string()),
+        current,
+        content,
+    })
+}
+
+impl ReadmeInput {
+    fn question(&self) -> String {
+        match &self.current {
+            Some(current) => {
+                format!("{}\n\nCurrent {}:\n\n{}\n\n---\n\n{}", UPDATE_PROMPT, self.path, current, self.content)
+            }
+            None => format!("{}\n\n{}", DRAFT_PROMPT, self.content),
+        }
+    }
+
+    /// Estimate the requests [`draft`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question()) }
+    }
+}
+
+/// A proposed README and how it differs from the one at HEAD.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Readme {
+    pub path: String,
+    pub proposed: String,
+    /// A unified diff from the README at HEAD, or from nothing when there
+    /// is none, to the proposal. Empty when they are the same.
+    pub diff: String,
+}
+
+/// Draft a README for `input`, or an update of the current one, with one
+/// request.
+pub async fn draft(input: &ReadmeInput, provider: &dyn LlmProvider) -> Result<Readme> {
+    let proposed = message::clean(&Conversation::new(SYSTEM_PROMPT).ask(provider, input.question()).await?);
+    let diff = diff(input.current.as_deref(), &proposed, &input.path)?;
+    Ok(Readme { path: input.path.clone(), proposed, diff })
+}
+
+// `git diff` from `current` to `proposed`, as `git apply` takes it
+fn diff(current: Option<&str>, proposed: &str, path: &str) -> Result<String> {
+    let (old, path) = (current.unwrap_or_default().as_bytes(), Path::new(path));
+    let mut patch = git2::Patch::from_buffers(old, current.map(|_| path), proposed.as_bytes(), Some(path), None)?;
+    let text = String::from_utf8_lossy(&patch.to_buf()?).into_owned();
+    // The same text gives a header without any hunks
+    let Some(hunks) = text.find("\n@@ ").map(|start| &text[start + 1..]) else {
+        return Ok(String::new());
+    };
+    // Without an old file, libgit2 diffs against an empty one of the same
+    // name, which `git apply` would look for
+    let header = match current {
+        Some(_) => &text[..text.len() - hunks.len()],
+        None => &format!("diff --git a/{0} b/{0}\nnew file mode 100644\n--- /dev/null\n+++ b/{0}\n", path.display()),
+    };
+    Ok(format!("{}{}", header, hunks))
+}
+
+/// Format `readme` for output: the proposal, or with `diff` the change to
+/// the README at HEAD.
+pub fn render(readme: &Readme, format: OutputFormat, diff: bool) -> String {
+    match format {
+        // Serializing plain strings cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(readme).expect("readme serializes to JSON") + "\n",
+        _ if !diff => readme.proposed.clone(),
+        _ if readme.diff.is_empty() => tr!("readme-unchanged", path = readme.path.as_str()) + "\n",
+        _ => readme.diff.clone(),
+    }
+}
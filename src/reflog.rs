@@ -0,0 +1,244 @@
+//! `wtf reflog`: what just happened to HEAD, and how to undo it.
+//!
+//! Every time HEAD moves, Git notes where it was and where it went in the
+//! reflog. Commits HEAD moved away from that no branch, tag or stash reaches
+//! any more are what a `reset --hard`, an amend or a rebase leaves behind;
+//! they stay in the object database until Git prunes them, so the model is
+//! given their SHAs to suggest commands that bring them back.
+
+use crate::analysis::RunEstimate;
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::state;
+use crate::tokens;
+use crate::tr;
+use git2::{Oid, Repository};
+use serde::Serialize;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains to developers what just happened in their Git repository and how to undo it.";
+
+const REFLOG_PROMPT: &str = "Below are the latest movements of HEAD from the reflog of a Git repository, newest first, and the commits they left behind that no branch, tag or stash reaches any more. Explain in plain language what happened recently, newest first, like \"20 minutes ago you ran git reset --hard, leaving commit 1a2b3c4 behind\", and treat the steps of one operation, like a rebase, as one. Then, if any work looks lost, suggest the exact commands to get it back, using the SHAs and HEAD@{n} names given (like git branch rescue 1a2b3c4 or git reset --hard HEAD@{2}), and say what each one does. If nothing is lost, say so. Keep it under 250 words.";
+
+/// One movement of HEAD.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Movement {
+    /// The position in the reflog, as in `HEAD@{0}`.
+    pub index: usize,
+    /// What moved HEAD, like `reset: moving to HEAD~1`.
+    pub message: String,
+    /// Where HEAD was, as a short SHA and summary; none for the first
+    /// commit or clone.
+    pub from: Option<String>,
+    /// Where HEAD went, as a short SHA and summary.
+    pub to: Option<String>,
+    /// When HEAD moved, seconds since the epoch.
+    pub time: i64,
+}
+
+impl Movement {
+    /// The name Git knows the position by, like `HEAD@{0}`.
+    pub fn name(&self) -> String {
+        format!("HEAD@{{{}}}", self.index)
+    }
+}
+
+/// A commit HEAD was on that nothing reaches any more, and no other such
+/// commit contains.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LostCommit {
+    pub short_id: String,
+    pub summary: String,
+    /// The latest movement that left it or went to it.
+    pub seen_at: String,
+}
+
+/// The reflog, read before any model is called.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReflogInput {
+    /// Newest first.
+    pub movements: Vec<Movement>,
+    /// Newest first.
+    pub lost: Vec<LostCommit>,
+    /// The time ages are counted from, seconds since the epoch.
+    #[serde(skip)]
+    pub now: i64,
+}
+
+/// Read the latest `limit` movements of HEAD in `repo`, with ages counted
+/// from `now`.
+pub fn prepare(repo: &Repository, limit: usize, now: i64) -> Result<ReflogInput> {
+    // The commits branches, tags and stashes point at
+    let mut tips = Vec::new();
+    for reference in repo.references()?.flatten() {
+        if let Ok(commit) = reference.peel_to_commit() {
+            tips.push(commit.id());
+        }
+    }
+    let reachable =
+        |id: Oid| tips.iter().any(|&tip| tip == id || repo.graph_descendant_of(tip, id).unwrap_or(false));
+    let described = |id: Oid| match id.is_zero() {
+        true => None,
+        false => repo.find_commit(id).ok().map(|commit| state::describe(&commit)),
+    };
+
+    let reflog = match repo.reflog("HEAD") {
+        Ok(reflog) => reflog,
+        Err(_) => return Ok(ReflogInput { movements: Vec::new(), lost: Vec::new(), now }),
+    };
+    let mut movements = Vec::new();
+    let mut lost: Vec<(Oid, LostCommit)> = Vec::new();
+    for (index, entry) in reflog.iter().take(limit).enumerate() {
+        let movement = Movement {
+            index,
+            message: entry.message_bytes().map(git::escape_non_utf8).unwrap_or_default(),
+            from: described(entry.id_old()),
+            to: described(entry.id_new()),
+            time: entry.committer().when().seconds(),
+        };
+        for id in [entry.id_new(), entry.id_old()] {
+            if id.is_zero() || lost.iter().any(|(known, _)| *known == id) || reachable(id) {
+                continue;
+            }
+            let Ok(commit) = repo.find_commit(id) else { continue };
+            let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+            let summary = git::commit_message(&commit).lines().next().unwrap_or_default().to_string();
+            lost.push((id, LostCommit { short_id, summary, seen_at: movement.name() }));
+        }
+        movements.push(movement);
+    }
+    // A commit that another lost one descends from comes back with it
+    let ids: Vec<Oid> = lost.iter().map(|(id, _)| *id).collect();
+    lost.retain(|(id, _)| !ids.iter().any(|&other| repo.graph_descendant_of(other, *id).unwrap_or(false)));
+    Ok(ReflogInput { movements, lost: lost.into_iter().map(|(_, commit)| commit).collect(), now })
+}
+
+// How long ago `time` was, in English for the prompt
+fn ago(now: i64, time: i64) -> String {
+    let seconds = (now - time).max(0);
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3_600 => format!("{} minutes ago", seconds / 60),
+        3_600..86_400 => format!("{} hours ago", seconds / 3_600),
+        _ => format!("{} days ago", seconds / 86_400),
+    }
+}
+
+// The same, localized for the report
+fn localized_ago(now: i64, time: i64) -> String {
+    let seconds = (now - time).max(0);
+    match seconds {
+        0..60 => tr!("reflog-just-now"),
+        60..3_600 => tr!("reflog-minutes-ago", count = seconds / 60),
+        3_600..86_400 => tr!("reflog-hours-ago", count = seconds / 3_600),
+        _ => tr!("reflog-days-ago", count = seconds / 86_400),
+    }
+}
+
+impl ReflogInput {
+    fn content(&self) -> String {
+        let mut out = String::from("HEAD movements, newest first:\n");
+        for movement in &self.movements {
+            let when = ago(self.now, movement.time);
+            out.push_str(&format!("\n{}, {}: {}\n", movement.name(), when, movement.message));
+            if let Some(from) = &movement.from {
+                out.push_str(&format!("  from {}\n", from));
+            }
+            if let Some(to) = &movement.to {
+                out.push_str(&format!("  to {}\n", to));
+            }
+        }
+        match self.lost.is_empty() {
+            true => out.push_str("\nNo commit was left behind.\n"),
+            false => {
+                out.push_str("\nLeft behind, reached by no branch, tag or stash:\n");
+                for commit in &self.lost {
+                    out.push_str(&format!("- {} {} (see {})\n", commit.short_id, commit.summary, commit.seen_at));
+                }
+            }
+        }
+        out
+    }
+
+    /// Estimate the request [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate {
+            requests: 1,
+            prompt_tokens: tokens::estimate(SYSTEM_PROMPT)
+                + tokens::estimate(REFLOG_PROMPT)
+                + tokens::estimate(&self.content()),
+        }
+    }
+}
+
+/// The movements of HEAD, what they left behind and, if asked for, the
+/// model's explanation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReflogReport {
+    #[serde(flatten)]
+    pub input: ReflogInput,
+    pub explanation: Option<String>,
+}
+
+/// Ask the model what happened and how to recover from it.
+pub async fn explain(input: &ReflogInput, provider: &dyn LlmProvider) -> Result<String> {
+    let question = format!("{}\n\n{}", REFLOG_PROMPT, input.content());
+    Conversation::new(SYSTEM_PROMPT).ask(provider, question).await
+}
+
+/// Format `report` for output.
+pub fn render(report: &ReflogReport, format: OutputFormat) -> String {
+    let input = &report.input;
+    let movements: String = input
+        .movements
+        .iter()
+        .map(|movement| {
+            let entry = tr!(
+                "reflog-entry",
+                name = movement.name(),
+                ago = localized_ago(input.now, movement.time),
+                message = movement.message.as_str()
+            );
+            format!("- {}\n", entry)
+        })
+        .collect();
+    let lost: String = match input.lost.is_empty() {
+        true => tr!("reflog-nothing-lost") + "\n",
+        false => input
+            .lost
+            .iter()
+            .map(|commit| {
+                let entry = tr!(
+                    "reflog-lost-entry",
+                    sha = commit.short_id.as_str(),
+                    summary = commit.summary.as_str(),
+                    seen = commit.seen_at.as_str()
+                );
+                format!("- {}\n", entry)
+            })
+            .collect(),
+    };
+    let mut sections = Vec::new();
+    if let Some(explanation) = &report.explanation {
+        sections.push((tr!("reflog-title"), explanation.trim().to_string() + "\n"));
+    }
+    sections.push((tr!("reflog-movements", count = input.movements.len()), movements));
+    sections.push((tr!("reflog-lost", count = input.lost.len()), lost));
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("reflog serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,256 @@
+//! `wtf tag <name>`: what is in a release.
+//!
+//! The release is compared with the tag before it. That is the highest
+//! lower version among the tags the release contains, when tag names are
+//! versions like `v1.2.0` (leaving out pre-releases for a final release),
+//! and otherwise the nearest tag in its history. The model is given the
+//! tag's message, the commits since the previous tag and the files they
+//! changed.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::commit::{self, FileChange};
+use crate::conversation::Conversation;
+use crate::error::{Result, WtfError};
+use crate::git::{self, RevRange};
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Commit, DescribeFormatOptions, DescribeOptions, Oid, Repository};
+use serde::Serialize;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains software releases from their Git history.";
+
+const RELEASE_PROMPT: &str = "Below are a release tag, the message it was tagged with, the commits it contains since the previous release and the files they changed. Summarize what is in this release: the main new features, changes in behavior and fixes, and anything that may break existing users. Lead with what matters most, group related commits together and leave out minor internal work. Mention the 7-character short SHA of the commits you draw on in square brackets, for example [1a2b3c4]. Keep it under 300 words.";
+
+// The commits and the file list are each cut to this many tokens
+const RELEASE_COMMITS_TOKENS: usize = 12_000;
+const RELEASE_FILES_TOKENS: usize = 2_000;
+
+/// A tag and the commit it points at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Tag {
+    pub name: String,
+    #[serde(skip)]
+    pub target: Oid,
+    /// When the tagged commit was made, seconds since the epoch.
+    pub time: i64,
+    /// Who made an annotated tag.
+    pub tagger: Option<String>,
+    /// The message of an annotated tag.
+    pub message: Option<String>,
+}
+
+/// Look up the tag `name`.
+pub fn find_tag(repo: &Repository, name: &str) -> Result<Tag> {
+    let unknown = || WtfError::UnknownTag { name: name.to_string() };
+    let object = repo.revparse_single(&format!("refs/tags/{}", name)).map_err(|_| unknown())?;
+    let commit = object.peel_to_commit().map_err(|_| unknown())?;
+    let (tagger, message) = match object.as_tag() {
+        Some(tag) => (
+            tag.tagger().map(|tagger| git::escape_non_utf8(tagger.name_bytes())),
+            tag.message_bytes().map(git::escape_non_utf8).map(|message| message.trim().to_string()),
+        ),
+        None => (None, None),
+    };
+    Ok(Tag { name: name.to_string(), target: commit.id(), time: commit.time().seconds(), tagger, message })
+}
+
+// A tag name read as a version: the text before the first digit, then the
+// numbers and whether it is a pre-release, like ("v", [1, 2, 0], true) for
+// v1.2.0-rc1
+pub(crate) fn version(name: &str) -> Option<(&str, Vec<u64>, bool)> {
+    let start = name.find(|c: char| c.is_ascii_digit())?;
+    let (prefix, rest) = name.split_at(start);
+    let (core, pre) = match rest.find(['-', '+']) {
+        Some(at) => (&rest[..at], rest[at..].starts_with('-')),
+        None => (rest, false),
+    };
+    let numbers: Option<Vec<u64>> = core.split('.').map(|part| part.parse().ok()).collect();
+    Some((prefix, numbers?, pre))
+}
+
+/// The tag of the release before `tag`, if there is one.
+pub fn previous_tag(repo: &Repository, tag: &Tag) -> Result<Option<String>> {
+    if let Some((prefix, numbers, pre)) = version(&tag.name) {
+        let mut best: Option<(Vec<u64>, bool, String)> = None;
+        for name in repo.tag_names(None)?.iter().flatten() {
+            let Some((other_prefix, other_numbers, other_pre)) = version(name) else { continue };
+            // A final release is compared with the last final release, not
+            // with its own release candidates
+            let lower = (&other_numbers, !other_pre) < (&numbers, !pre);
+            if other_prefix != prefix || !lower || (other_pre && !pre) {
+                continue;
+            }
+            let Ok(other) = find_tag(repo, name) else { continue };
+            if other.target == tag.target || !repo.graph_descendant_of(tag.target, other.target)? {
+                continue;
+            }
+            if best.as_ref().is_none_or(|(n, release, _)| (&other_numbers, !other_pre) > (n, *release)) {
+                best = Some((other_numbers, !other_pre, name.to_string()));
+            }
+        }
+        if let Some((_, _, name)) = best {
+            return Ok(Some(name));
+        }
+    }
+    // The nearest tag in the history before the tagged commit
+    let Ok(parent) = repo.find_commit(tag.target)?.parent(0) else { return Ok(None) };
+    nearest_tag(&parent)
+}
+
+// The nearest tag in the history of `commit`, `commit` itself included
+fn nearest_tag(commit: &Commit) -> Result<Option<String>> {
+    match commit.as_object().describe(DescribeOptions::new().describe_tags()) {
+        Ok(describe) => Ok(Some(describe.format(Some(DescribeFormatOptions::new().abbreviated_size(0)))?)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The latest tag HEAD contains, its own included: where the next release
+/// starts from. None before the first tag or commit.
+pub fn latest_tag(repo: &Repository) -> Result<Option<String>> {
+    let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) else { return Ok(None) };
+    nearest_tag(&head)
+}
+
+/// A release, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct ReleaseInput {
+    pub tag: Tag,
+    /// The previous release's tag; without one, the release is everything
+    /// up to the tag.
+    pub previous: Option<String>,
+    /// The release's commits, newest first.
+    pub commits: Vec<Oid>,
+    /// The files changed since the previous tag.
+    pub files: Vec<FileChange>,
+    // Commit details, for the prompt
+    details: String,
+}
+
+/// Gather the release tagged `name`.
+pub fn prepare(repo: &Repository, name: &str) -> Result<ReleaseInput> {
+    let tag = find_tag(repo, name)?;
+    let previous = previous_tag(repo, &tag)?;
+    let (commits, base) = match &previous {
+        Some(previous) => {
+            let (from, to) = (format!("refs/tags/{}", previous), format!("refs/tags/{}", name));
+            let range = RevRange { from, to, symmetric: false };
+            let base = git::resolve_commit(repo, &range.from)?.tree()?;
+            (git::range_commits(repo, &range)?, Some(base))
+        }
+        None => {
+            let mut walk = repo.revwalk()?;
+            walk.push(tag.target)?;
+            (walk.collect::<std::result::Result<Vec<_>, _>>()?, None)
+        }
+    };
+    progress!("{}", tr!("progress-release", count = commits.len()));
+    let tree = repo.find_commit(tag.target)?.tree()?;
+    let diff = repo.diff_tree_to_tree(base.as_ref(), Some(&tree), None)?;
+    let mut details = Vec::new();
+    for id in &commits {
+        details.push(git::get_commit_details(&repo.find_commit(*id)?));
+    }
+    let files = commit::diff_files(&diff)?;
+    Ok(ReleaseInput { tag, previous, commits, files, details: details.join("\n\n---\n\n") })
+}
+
+impl ReleaseInput {
+    fn question(&self) -> String {
+        let mut out = format!("{}\n\nTag: {}\n", RELEASE_PROMPT, self.tag.name);
+        match &self.previous {
+            Some(previous) => out.push_str(&format!("Previous release: {}\n", previous)),
+            None => out.push_str("This is the first release.\n"),
+        }
+        if let Some(message) = &self.tag.message {
+            out.push_str(&format!("Tag message:\n{}\n", message));
+        }
+        let files: String =
+            self.files.iter().map(|file| format!("{} (+{} -{})\n", file.path, file.added, file.removed)).collect();
+        let (details, _) = chunk::truncate_to_tokens(&self.details, RELEASE_COMMITS_TOKENS);
+        let (files, _) = chunk::truncate_to_tokens(&files, RELEASE_FILES_TOKENS);
+        out.push_str(&format!("\nCommits:\n\n{}\n\nFiles changed:\n{}", details, files));
+        out
+    }
+
+    /// Estimate the request [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate {
+            requests: 1,
+            prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question()),
+        }
+    }
+}
+
+/// What is in a release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Release {
+    pub tag: Tag,
+    pub previous: Option<String>,
+    pub commits: usize,
+    pub files: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub explanation: String,
+}
+
+/// Ask the model what is in the release.
+pub async fn explain(input: &ReleaseInput, provider: &dyn LlmProvider) -> Result<Release> {
+    let explanation = Conversation::new(SYSTEM_PROMPT).ask(provider, input.question()).await?;
+    Ok(Release {
+        tag: input.tag.clone(),
+        previous: input.previous.clone(),
+        commits: input.commits.len(),
+        files: input.files.len(),
+        added: input.files.iter().map(|file| file.added).sum(),
+        removed: input.files.iter().map(|file| file.removed).sum(),
+        explanation,
+    })
+}
+
+/// Format `release` for output.
+pub fn render(release: &Release, format: OutputFormat) -> String {
+    let (count, files, added, removed) = (release.commits, release.files, release.added, release.removed);
+    let mut facts = vec![match &release.previous {
+        Some(previous) => tr!(
+            "tag-since",
+            previous = previous.as_str(),
+            count = count,
+            files = files,
+            added = added,
+            removed = removed
+        ),
+        None => tr!("tag-first", count = count, files = files, added = added, removed = removed),
+    }];
+    let date = git::format_date(release.tag.time);
+    facts.push(match &release.tag.tagger {
+        Some(tagger) => tr!("tag-tagged-by", tagger = tagger.as_str(), date = date),
+        None => tr!("tag-lightweight", date = date),
+    });
+    let mut about: String = facts.iter().map(|fact| format!("- {}\n", fact)).collect();
+    if let Some(message) = &release.tag.message {
+        about.push_str(&format!("\n{}\n", message));
+    }
+    let sections = [
+        (tr!("tag-title", tag = release.tag.name.as_str()), release.explanation.trim().to_string() + "\n"),
+        (tr!("tag-about"), about),
+    ];
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(release).expect("release serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,267 @@
+//! `wtf release-notes <range>`: release notes for the users of a project.
+//!
+//! Where `wtf tag` explains a release to the people working on it, release
+//! notes are written for its users, in markdown that can go straight into a
+//! GitHub release: highlights, breaking changes and upgrade notes, from the
+//! commits in the range and their combined diff. The contributors are
+//! credited from the commits themselves rather than by the model, authors
+//! and `Co-authored-by` trailers alike, with the repository's mailmap
+//! applied.
+
+use crate::analysis::{RunEstimate, Task};
+use crate::chunk;
+use crate::error::Result;
+use crate::git::{self, RevRange};
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::summarize;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that writes polished release notes for a project's users.";
+
+const NOTES_PROMPT: &str = "Below are the commits of a release, from the previous release up to this one, and their combined diff. Write the release notes for the project's users, as for a GitHub release: a summary of one or two sentences; the highlights, each the main new feature or fix in one sentence saying what users can now do or what no longer goes wrong; the breaking changes, anything that may stop existing setups from working, like removed or renamed options, changed defaults or changed output; and upgrade notes, what users have to do when upgrading, like settings to change or commands to run. Leave out internal work users would not notice. Only list breaking changes the commits or the diff show, and leave a list empty when there is nothing for it.";
+
+// The commit details are cut to this many tokens
+const NOTES_COMMITS_TOKENS: usize = 8_000;
+
+/// Someone who worked on the release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Contributor {
+    pub name: String,
+    /// The commits they authored or co-authored.
+    pub commits: usize,
+}
+
+/// A release, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct NotesInput {
+    pub range: RevRange,
+    /// Newest first.
+    pub commits: Vec<Oid>,
+    /// Most commits first.
+    pub contributors: Vec<Contributor>,
+    /// The combined diff of the range.
+    pub patch: String,
+    /// Where the range can be compared on the web, like a GitHub compare page.
+    pub compare_url: Option<String>,
+    // Commit details, for the prompt
+    details: String,
+}
+
+// The people named in the `Co-authored-by` trailers of `message`
+fn co_authors(message: &str) -> Vec<(String, String)> {
+    message
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            if !key.trim().eq_ignore_ascii_case("co-authored-by") {
+                return None;
+            }
+            let (name, email) = value.trim().split_once('<')?;
+            Some((name.trim().to_string(), email.trim_end_matches('>').trim().to_lowercase()))
+        })
+        .collect()
+}
+
+// The compare page for `range` on the host of the `origin` remote
+fn compare_url(repo: &Repository, range: &RevRange) -> Option<String> {
+    let base = git::commit_url_base(repo)?;
+    let root = match base.strip_suffix("/-/commit") {
+        Some(root) => format!("{}/-/compare", root),
+        None => format!("{}/compare", base.strip_suffix("/commit")?),
+    };
+    Some(format!("{}/{}...{}", root, range.from, range.to))
+}
+
+/// The authors and co-authors of `commits`, most commits first, with the
+/// repository's mailmap applied.
+pub(crate) fn contributors(repo: &Repository, commits: &[Oid]) -> Result<Vec<Contributor>> {
+    let mailmap = repo.mailmap()?;
+    // By email, in the order they first appear
+    let mut people: Vec<(String, Contributor)> = Vec::new();
+    for id in commits {
+        let commit = repo.find_commit(*id)?;
+        let message = git::commit_message(&commit);
+        let author = commit.author_with_mailmap(&mailmap)?;
+        let mut credited = vec![(
+            git::decode_text(author.name_bytes(), commit.message_encoding()),
+            String::from_utf8_lossy(author.email_bytes()).to_lowercase(),
+        )];
+        credited.extend(co_authors(&message));
+        let mut counted = Vec::new();
+        for (name, email) in credited {
+            // Someone named twice in one commit still gets it counted once
+            if counted.contains(&email) {
+                continue;
+            }
+            counted.push(email.clone());
+            match people.iter_mut().find(|(known, _)| *known == email) {
+                Some((_, contributor)) => contributor.commits += 1,
+                None => people.push((email, Contributor { name, commits: 1 })),
+            }
+        }
+    }
+    let mut contributors: Vec<Contributor> = people.into_iter().map(|(_, contributor)| contributor).collect();
+    // Stable, so contributors with as many commits stay in order of appearance
+    contributors.sort_by_key(|contributor| std::cmp::Reverse(contributor.commits));
+    Ok(contributors)
+}
+
+/// Gather the commits in `range` and what they changed.
+pub fn prepare(repo: &Repository, range: &RevRange) -> Result<NotesInput> {
+    let commits = git::range_commits(repo, range)?;
+    progress!("{}", tr!("progress-release-notes", count = commits.len()));
+    let contributors = contributors(repo, &commits)?;
+    let mut details = Vec::new();
+    for id in &commits {
+        details.push(git::get_commit_details(&repo.find_commit(*id)?));
+    }
+
+    let (from, to) = (git::resolve_commit(repo, &range.from)?, git::resolve_commit(repo, &range.to)?);
+    let fork = match repo.merge_base(from.id(), to.id()) {
+        Ok(id) => Some(repo.find_commit(id)?.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(fork.as_ref(), Some(&to.tree()?), None)?;
+    Ok(NotesInput {
+        range: range.clone(),
+        commits,
+        contributors,
+        patch: git::diff_patch(&diff)?,
+        compare_url: compare_url(repo, range),
+        details: details.join("\n\n---\n\n"),
+    })
+}
+
+impl NotesInput {
+    fn question(&self, patch: &str) -> String {
+        let (details, _) = chunk::truncate_to_tokens(&self.details, NOTES_COMMITS_TOKENS);
+        format!(
+            "{}\n\nRelease: {}\n\nCommits:\n\n{}\n\nCombined diff:\n\n{}",
+            NOTES_PROMPT, self.range, details, patch
+        )
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self, max_diff_tokens: usize) -> RunEstimate {
+        let mut estimate = RunEstimate { requests: 1, prompt_tokens: tokens::estimate(SYSTEM_PROMPT) };
+        if summarize::is_large_diff(&self.patch, max_diff_tokens) {
+            let parts = summarize::estimate_diff(&self.patch, max_diff_tokens);
+            estimate.requests += parts.requests;
+            estimate.prompt_tokens += parts.prompt_tokens + tokens::estimate(&self.question(""));
+            estimate.prompt_tokens += parts.requests * summarize::EXPECTED_SUMMARY_TOKENS;
+        } else {
+            estimate.prompt_tokens += tokens::estimate(&self.question(&self.patch));
+        }
+        estimate
+    }
+}
+
+/// The release notes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReleaseNotes {
+    pub range: RevRange,
+    pub commits: usize,
+    pub summary: String,
+    pub highlights: Vec<String>,
+    pub breaking: Vec<String>,
+    pub upgrade: Vec<String>,
+    pub contributors: Vec<Contributor>,
+    pub compare_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    summary: String,
+    highlights: Vec<String>,
+    breaking: Vec<String>,
+    upgrade: Vec<String>,
+}
+
+/// Ask the model for the release notes. A diff above `max_diff_tokens` is
+/// summarized in parts first.
+pub async fn explain(input: &NotesInput, provider: &dyn LlmProvider, max_diff_tokens: usize) -> Result<ReleaseNotes> {
+    let mut patch = input.patch.clone();
+    if summarize::is_large_diff(&patch, max_diff_tokens) {
+        patch = summarize::summarize_diff(provider, Task::Edits, &patch, max_diff_tokens).await?;
+    }
+    let messages = [Message::system(SYSTEM_PROMPT), Message::user(input.question(&patch))];
+    let list = json!({ "type": "array", "items": { "type": "string" } });
+    let schema = Schema::new(
+        "release_notes",
+        json!({
+            "type": "object",
+            "properties": {
+                "summary": { "type": "string" },
+                "highlights": list,
+                "breaking": list,
+                "upgrade": list,
+            },
+            "required": ["summary", "highlights", "breaking", "upgrade"],
+            "additionalProperties": false,
+        }),
+    );
+    let reply: Reply = structured::request(provider, &messages, &schema).await?;
+    let trimmed = |items: Vec<String>| -> Vec<String> {
+        items.iter().map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+    };
+    Ok(ReleaseNotes {
+        range: input.range.clone(),
+        commits: input.commits.len(),
+        summary: reply.summary.trim().to_string(),
+        highlights: trimmed(reply.highlights),
+        breaking: trimmed(reply.breaking),
+        upgrade: trimmed(reply.upgrade),
+        contributors: input.contributors.clone(),
+        compare_url: input.compare_url.clone(),
+    })
+}
+
+/// Format `notes` for output; markdown is ready for a GitHub release.
+pub fn render(notes: &ReleaseNotes, format: OutputFormat) -> String {
+    let list = |items: &[String]| -> String { items.iter().map(|item| format!("- {}\n", item)).collect() };
+    let mut highlights = notes.summary.clone() + "\n";
+    if !notes.highlights.is_empty() {
+        highlights.push_str(&format!("\n{}", list(&notes.highlights)));
+    }
+    let mut sections = vec![(tr!("notes-highlights"), highlights)];
+    if !notes.breaking.is_empty() {
+        sections.push((tr!("notes-breaking"), list(&notes.breaking)));
+    }
+    if !notes.upgrade.is_empty() {
+        sections.push((tr!("notes-upgrade"), list(&notes.upgrade)));
+    }
+    let mut credits: String = notes
+        .contributors
+        .iter()
+        .map(|contributor| {
+            format!("- {}\n", tr!("notes-contributor", name = contributor.name.as_str(), count = contributor.commits))
+        })
+        .collect();
+    let changelog = match &notes.compare_url {
+        Some(url) => tr!("notes-full-changelog", url = url.as_str()),
+        None => tr!("notes-commits", range = notes.range.to_string(), count = notes.commits),
+    };
+    credits.push_str(&format!("\n{}\n", changelog));
+    sections.push((tr!("notes-contributors"), credits));
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(notes).expect("release notes serialize to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,518 @@
+//! Turning a [`Report`] into text.
+
+use crate::analysis::{Report, Task};
+use crate::ci::{CiFinding, CiRisk};
+use crate::citations;
+use crate::classify::{Classification, CommitKind, Dashboard, RiskLevel};
+use crate::complexity::{CommitScore, Effort};
+use crate::generated::ChurnFile;
+use crate::git::RevRange;
+use crate::rust_api::ApiChanges;
+use crate::tr;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// The supported output formats.
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Plain text with section banners, for reading in a terminal
+    #[default]
+    Terminal,
+    /// Markdown, for pasting into issues, wikis or chat
+    Markdown,
+    /// JSON, for scripts
+    Json,
+    /// Plain text for screen readers: no banners or symbols, citations
+    /// spelled out (what `--accessible` selects)
+    Plain,
+}
+
+/// Render `report` in the given format.
+pub fn render(report: &Report, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Terminal => render_terminal(report),
+        OutputFormat::Markdown => render_markdown(report),
+        OutputFormat::Json => render_json(report),
+        OutputFormat::Plain => render_plain(report),
+    }
+}
+
+fn render_terminal(report: &Report) -> String {
+    let mut out = String::new();
+    for (task, title, text) in written_sections(report) {
+        out.push_str(&banner(&title));
+        out.push_str(text);
+        out.push('\n');
+        out.push_str(&section_notes(report, task));
+    }
+    out.push_str(&terminal_trailer(report));
+    out
+}
+
+/// The terminal banner of the section `task` writes, if it writes one, for
+/// printing a section as it streams in.
+pub fn stream_banner(task: Task, num_commits: usize, range: Option<&RevRange>) -> Option<String> {
+    section_title(task, num_commits, range).map(|title| banner(&title))
+}
+
+/// The rest of the terminal report after its sections were streamed:
+/// sections that were not streamed, sections whose final text differs from
+/// what was shown (once checked against the repository or cut by
+/// `--strict`), and everything that was not written by the model.
+pub fn render_after_stream(report: &Report, streamed: &[(Task, String)]) -> String {
+    let mut out = String::new();
+    for (task, title, text) in written_sections(report) {
+        match streamed.iter().find(|(shown, _)| *shown == task) {
+            Some((_, shown)) if shown.trim() == text.trim() => {}
+            Some(_) => out.push_str(&format!("{}{}\n", banner(&tr!("section-revised", title = title)), text)),
+            None => out.push_str(&format!("{}{}\n", banner(&title), text)),
+        }
+        out.push_str(&section_notes(report, task));
+    }
+    out.push_str(&terminal_trailer(report));
+    out
+}
+
+fn section_title(task: Task, num_commits: usize, range: Option<&RevRange>) -> Option<String> {
+    match task {
+        Task::Description => Some(tr!("section-project")),
+        Task::Commits => Some(commits_title(num_commits, range)),
+        Task::Edits => Some(tr!("section-edits")),
+        Task::Ci => Some(tr!("section-ci")),
+        Task::Classify | Task::Topics | Task::Refine => None,
+    }
+}
+
+fn commits_title(num_commits: usize, range: Option<&RevRange>) -> String {
+    match range {
+        Some(range) => tr!("section-range", from = range.from.as_str(), to = range.to.as_str(), count = num_commits),
+        None => tr!("section-commits", count = num_commits),
+    }
+}
+
+// The sections the model wrote, as (task, title, text)
+fn written_sections(report: &Report) -> Vec<(Task, String, &str)> {
+    let mut sections = vec![
+        (Task::Description, report.project_description.as_str()),
+        (Task::Commits, report.commit_descriptions.as_str()),
+        (Task::Edits, report.edits_description.as_str()),
+    ];
+    if let Some(ci) = &report.ci_changes {
+        sections.push((Task::Ci, ci.summary.as_str()));
+    }
+    sections
+        .into_iter()
+        .filter_map(|(task, text)| Some((task, section_title(task, report.num_commits, report.range.as_ref())?, text)))
+        .collect()
+}
+
+// What follows a written section in the terminal report
+fn section_notes(report: &Report, task: Task) -> String {
+    let mut out = String::new();
+    match task {
+        Task::Edits => {
+            if let Some(note) = churn_note(&report.generated_churn, |path| path.to_string()) {
+                out.push_str(&format!("\n{}\n", note));
+            }
+        }
+        Task::Ci => {
+            let findings = report.ci_changes.as_ref().map_or(&[][..], |ci| &ci.findings);
+            if !findings.is_empty() {
+                out.push_str(&format!("\n{}\n", tr!("ci-attention")));
+                for finding in findings {
+                    out.push_str(&format!("- {}\n", finding_line(finding, |code| code.to_string())));
+                }
+            }
+        }
+        _ => {}
+    }
+    out
+}
+
+// The parts of the terminal report after the written sections
+fn terminal_trailer(report: &Report) -> String {
+    let mut out = String::new();
+
+    if !report.unverified_references.is_empty() {
+        out.push_str(&banner(&tr!("section-unverified")));
+        out.push_str(&tr!("unverified-intro"));
+        out.push('\n');
+        for reference in &report.unverified_references {
+            out.push_str(&format!("- {}\n", reference));
+        }
+    }
+
+    if let Some(dashboard) = &report.classification {
+        out.push_str(&banner(&tr!("section-classification")));
+        let rows = kind_rows(dashboard);
+        let width = rows.iter().map(|(kind, _, _)| kind.chars().count()).max().unwrap_or(0);
+        for (kind, count, text) in rows {
+            let bar = "#".repeat(bar_length(count, dashboard.commits.len()));
+            let padding = " ".repeat(width - kind.chars().count());
+            out.push_str(&format!("{}{}  {:<20}  {}\n", kind, padding, bar, text));
+        }
+        out.push_str(&format!("\n{}\n\n", risk_summary(dashboard)));
+        for commit in &dashboard.commits {
+            out.push_str(&format!("- {}: {}\n", commit.short_id, classification_line(commit)));
+        }
+    }
+
+    if !report.review_effort.is_empty() {
+        out.push_str(&banner(&tr!("section-review-effort")));
+        for score in &report.review_effort {
+            out.push_str(&format!("- {}: {}\n", score.short_id, effort_line(score)));
+        }
+    }
+
+    if let Some(changes) = report.public_api.as_ref().filter(|c| !c.is_empty()) {
+        out.push_str(&banner(&tr!("section-public-api")));
+        for (heading, entries) in api_groups(changes, |code| code.to_string()) {
+            out.push_str(&format!("{}:\n", heading));
+            for entry in entries {
+                out.push_str(&format!("- {}\n", entry));
+            }
+        }
+    }
+
+    out
+}
+
+fn banner(title: &str) -> String {
+    format!("\n=== {} ===\n\n", title.to_uppercase())
+}
+
+fn render_markdown(report: &Report) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!("## {}\n\n", tr!("section-project")));
+    out.push_str(report.project_description.trim());
+    out.push_str("\n\n");
+
+    out.push_str(&format!("## {}\n\n", commits_title(report.num_commits, report.range.as_ref())));
+    out.push_str(&linked(report, report.commit_descriptions.trim()));
+    out.push_str("\n\n");
+
+    out.push_str(&format!("## {}\n\n", tr!("section-edits")));
+    out.push_str(&linked(report, report.edits_description.trim()));
+    out.push('\n');
+    if let Some(note) = churn_note(&report.generated_churn, |path| format!("`{}`", path)) {
+        out.push_str(&format!("\n_{}_\n", note));
+    }
+
+    if let Some(ci) = &report.ci_changes {
+        out.push_str(&format!("\n## {}\n\n", tr!("section-ci")));
+        out.push_str(&linked(report, ci.summary.trim()));
+        out.push('\n');
+        if !ci.findings.is_empty() {
+            out.push_str(&format!("\n> **{}**\n", tr!("ci-attention")));
+            for finding in &ci.findings {
+                let line = finding_line(finding, |code| format!("`{}`", code));
+                out.push_str(&format!("> - {}\n", linked(report, &line)));
+            }
+        }
+    }
+
+    if !report.unverified_references.is_empty() {
+        out.push_str(&format!("\n> **{}:** {}\n", tr!("note-label"), tr!("unverified-note")));
+        for reference in &report.unverified_references {
+            out.push_str(&format!("> - `{}`\n", reference));
+        }
+    }
+
+    if let Some(dashboard) = &report.classification {
+        out.push_str(&format!("\n## {}\n\n", tr!("section-classification")));
+        for (kind, _, text) in kind_rows(dashboard) {
+            out.push_str(&format!("- **{}**: {}\n", kind, text));
+        }
+        out.push_str(&format!("\n{}\n\n", risk_summary(dashboard)));
+        for commit in &dashboard.commits {
+            out.push_str(&format!("- `{}`: {}\n", commit.short_id, classification_line(commit)));
+        }
+    }
+
+    if !report.review_effort.is_empty() {
+        out.push_str(&format!("\n## {}\n\n", tr!("section-review-effort")));
+        for score in &report.review_effort {
+            out.push_str(&format!("- `{}`: {}\n", score.short_id, effort_line(score)));
+        }
+    }
+
+    if let Some(changes) = report.public_api.as_ref().filter(|c| !c.is_empty()) {
+        out.push_str(&format!("\n## {}\n", tr!("section-public-api")));
+        for (heading, entries) in api_groups(changes, |code| format!("`{}`", code)) {
+            out.push_str(&format!("\n**{}**\n\n", heading));
+            for entry in entries {
+                out.push_str(&format!("- {}\n", entry));
+            }
+        }
+    }
+
+    out
+}
+
+// Public API changes grouped under headings, breaking ones first, with
+// `code` formatting paths and signatures
+fn api_groups(changes: &ApiChanges, code: impl Fn(&str) -> String) -> Vec<(String, Vec<String>)> {
+    let mut groups = Vec::new();
+    if !changes.removed.is_empty() {
+        let entries = changes
+            .removed
+            .iter()
+            .map(|item| format!("{}: {}", code(&item.path), code(&item.signature)))
+            .collect();
+        groups.push((tr!("api-removed"), entries));
+    }
+    if !changes.changed.is_empty() {
+        let entries = changes
+            .changed
+            .iter()
+            .map(|change| {
+                let change_text = tr!("api-change", before = code(&change.before), after = code(&change.after));
+                format!("{}: {}", code(&change.path), change_text)
+            })
+            .collect();
+        groups.push((tr!("api-changed"), entries));
+    }
+    if !changes.added.is_empty() {
+        let entries = changes
+            .added
+            .iter()
+            .map(|item| format!("{}: {}", code(&item.path), code(&item.signature)))
+            .collect();
+        groups.push((tr!("api-added"), entries));
+    }
+    groups
+}
+
+// A flagged CI line, with `code` formatting the path and the line itself
+fn finding_line(finding: &CiFinding, code: impl Fn(&str) -> String) -> String {
+    let risk = match finding.risk {
+        CiRisk::Secret => tr!("ci-risk-secret"),
+        CiRisk::Permissions => tr!("ci-risk-permissions"),
+        CiRisk::PrivilegedTrigger => tr!("ci-risk-privileged-trigger"),
+        CiRisk::UnpinnedAction => tr!("ci-risk-unpinned-action"),
+        CiRisk::RemoteScript => tr!("ci-risk-remote-script"),
+        CiRisk::SelfHosted => tr!("ci-risk-self-hosted"),
+    };
+    tr!(
+        "ci-finding",
+        path = code(&finding.path),
+        sha = finding.short_id.as_str(),
+        risk = risk,
+        line = code(&finding.line)
+    )
+}
+
+// One commit's review effort and what it is made of
+fn effort_line(score: &CommitScore) -> String {
+    let effort = match score.effort {
+        Effort::Low => tr!("effort-low"),
+        Effort::Medium => tr!("effort-medium"),
+        Effort::High => tr!("effort-high"),
+    };
+    let tests = match score.test_ratio {
+        None => tr!("effort-no-code"),
+        Some(0.0) => tr!("effort-no-tests"),
+        Some(ratio) => tr!("effort-test-ratio", ratio = format!("{:.2}", ratio)),
+    };
+    tr!(
+        "review-effort-entry",
+        effort = effort,
+        score = score.score,
+        files = score.files,
+        hunks = score.hunks,
+        added = score.added,
+        removed = score.removed,
+        tests = tests
+    )
+}
+
+fn kind_name(kind: CommitKind) -> String {
+    match kind {
+        CommitKind::Feature => tr!("kind-feature"),
+        CommitKind::Fix => tr!("kind-fix"),
+        CommitKind::Refactor => tr!("kind-refactor"),
+        CommitKind::Docs => tr!("kind-docs"),
+        CommitKind::Chore => tr!("kind-chore"),
+    }
+}
+
+fn risk_name(risk: RiskLevel) -> String {
+    match risk {
+        RiskLevel::Low => tr!("risk-low"),
+        RiskLevel::Medium => tr!("risk-medium"),
+        RiskLevel::High => tr!("risk-high"),
+    }
+}
+
+// Each kind with its count and the count spelled out with its share
+fn kind_rows(dashboard: &Dashboard) -> Vec<(String, usize, String)> {
+    let total = dashboard.commits.len();
+    dashboard
+        .kinds
+        .iter()
+        .map(|(kind, count)| {
+            let share = (count * 100 + total / 2).checked_div(total).unwrap_or(0);
+            let text = tr!("classification-count", count = *count, total = total, share = share);
+            (kind_name(*kind), *count, text)
+        })
+        .collect()
+}
+
+// Bars are at most 20 characters; any commit at all gets at least one
+fn bar_length(count: usize, total: usize) -> usize {
+    match (count, total) {
+        (0, _) | (_, 0) => 0,
+        _ => (count * 20 / total).max(1),
+    }
+}
+
+fn risk_summary(dashboard: &Dashboard) -> String {
+    let count = |risk| dashboard.risks.get(&risk).copied().unwrap_or(0);
+    tr!(
+        "classification-risks",
+        high = count(RiskLevel::High),
+        medium = count(RiskLevel::Medium),
+        low = count(RiskLevel::Low)
+    )
+}
+
+fn classification_line(commit: &Classification) -> String {
+    tr!(
+        "classification-entry",
+        kind = kind_name(commit.kind),
+        risk = risk_name(commit.risk),
+        reason = commit.reason.as_str()
+    )
+}
+
+// Paths listed in the churn note before the rest are only counted
+const CHURN_NOTE_PATHS: usize = 5;
+
+// One line on the generated and vendored files left out, with `code`
+// formatting the paths
+fn churn_note(files: &[ChurnFile], code: impl Fn(&str) -> String) -> Option<String> {
+    if files.is_empty() {
+        return None;
+    }
+    let mut paths = files.iter().take(CHURN_NOTE_PATHS).map(|file| code(&file.path)).collect::<Vec<_>>().join(", ");
+    if files.len() > CHURN_NOTE_PATHS {
+        paths = tr!("generated-churn-more", paths = paths, count = files.len() - CHURN_NOTE_PATHS);
+    }
+    Some(tr!(
+        "generated-churn",
+        files = files.len(),
+        added = files.iter().map(|file| file.added).sum::<usize>(),
+        removed = files.iter().map(|file| file.removed).sum::<usize>(),
+        paths = paths
+    ))
+}
+
+// Turn commit citations into links when we know where commits live
+fn linked(report: &Report, text: &str) -> String {
+    match &report.commit_url {
+        Some(base) => citations::link_citations(text, base),
+        None => text.to_string(),
+    }
+}
+
+// Screen readers announce every "=" and "*"; describe structure in words instead
+fn render_plain(report: &Report) -> String {
+    let mut sections = vec![
+        (tr!("section-project"), report.project_description.trim().to_string()),
+        (
+            commits_title(report.num_commits, report.range.as_ref()),
+            report.commit_descriptions.trim().to_string(),
+        ),
+        (tr!("section-edits"), report.edits_description.trim().to_string()),
+    ];
+    if let Some(note) = churn_note(&report.generated_churn, |path| path.to_string()) {
+        sections[2].1.push_str(&format!("\n\n{}", note));
+    }
+    if let Some(ci) = &report.ci_changes {
+        let mut text = ci.summary.trim().to_string();
+        if !ci.findings.is_empty() {
+            text.push_str(&format!("\n\n{}", tr!("ci-attention")));
+            for (i, finding) in ci.findings.iter().enumerate() {
+                text.push_str(&format!("\n{}. {}", i + 1, finding_line(finding, |code| code.to_string())));
+            }
+        }
+        sections.push((tr!("section-ci"), text));
+    }
+    if !report.unverified_references.is_empty() {
+        let mut text = tr!("unverified-intro");
+        for (i, reference) in report.unverified_references.iter().enumerate() {
+            text.push_str(&format!("\n{}. {}", i + 1, reference));
+        }
+        sections.push((tr!("section-unverified"), text));
+    }
+    if let Some(dashboard) = &report.classification {
+        let mut lines: Vec<String> = kind_rows(dashboard)
+            .into_iter()
+            .map(|(kind, _, text)| format!("{}: {}", kind, text))
+            .collect();
+        lines.push(String::new());
+        lines.push(risk_summary(dashboard));
+        lines.push(String::new());
+        lines.extend(
+            dashboard
+                .commits
+                .iter()
+                .map(|commit| format!("{}: {}", commit.short_id, classification_line(commit))),
+        );
+        sections.push((tr!("section-classification"), lines.join("\n")));
+    }
+    if !report.review_effort.is_empty() {
+        let lines: Vec<String> = report
+            .review_effort
+            .iter()
+            .map(|score| format!("{}: {}", score.short_id, effort_line(score)))
+            .collect();
+        sections.push((tr!("section-review-effort"), lines.join("\n")));
+    }
+    if let Some(changes) = report.public_api.as_ref().filter(|c| !c.is_empty()) {
+        let groups: Vec<String> = api_groups(changes, |code| code.to_string())
+            .into_iter()
+            .map(|(heading, entries)| format!("{}:\n{}", heading, entries.join("\n")))
+            .collect();
+        sections.push((tr!("section-public-api"), groups.join("\n\n")));
+    }
+
+    plain_sections(&sections)
+}
+
+/// Lay out `(title, text)` sections the way `--accessible` output reads:
+/// numbered "Section i of n" headings, plain text and an end marker.
+pub fn plain_sections(sections: &[(String, String)]) -> String {
+    let total = sections.len();
+    let mut out = String::new();
+    for (i, (title, text)) in sections.iter().enumerate() {
+        out.push_str(&tr!("accessible-section", index = i + 1, total = total, title = title));
+        out.push_str("\n\n");
+        out.push_str(&plain_text(text.trim()));
+        out.push_str("\n\n");
+    }
+    out.push_str(&tr!("accessible-end"));
+    out.push('\n');
+    out
+}
+
+// Drop Markdown emphasis and code marks, and spell out citations
+fn plain_text(text: &str) -> String {
+    let text = citations::rewrite_citations(text, |shas| {
+        if shas.len() == 1 {
+            format!("({})", tr!("accessible-commit", sha = shas[0]))
+        } else {
+            format!("({})", tr!("accessible-commits", shas = shas.join(", ")))
+        }
+    });
+    text.replace("**", "").replace('`', "")
+}
+
+fn render_json(report: &Report) -> String {
+    // Serializing plain strings and numbers cannot fail
+    let mut out = serde_json::to_string_pretty(report).expect("report serializes to JSON");
+    out.push('\n');
+    out
+}
@@ -0,0 +1,313 @@
+//! `wtf config-explain`: how a repository is set up, in plain language.
+//!
+//! Surprises on `git push` or `git pull` usually come from settings nobody
+//! remembers making: a `pull.rebase` in the global config, a push URL that
+//! differs from the fetch URL, a `pre-push` hook someone installed years
+//! ago. This collects the settings that change how Git behaves (from every
+//! config file that applies, not just `.git/config`), the remotes and the
+//! installed hook scripts, and asks the model to explain them. Credentials
+//! are redacted before anything leaves the machine.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::conversation::Conversation;
+use crate::crash;
+use crate::error::Result;
+use crate::platform;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{ConfigLevel, Repository};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains Git setups to developers in plain language.";
+
+const CONFIG_PROMPT: &str = "Below are the Git settings, remotes and hook scripts that apply to a repository. Explain in plain language how this repository is configured and what that means day to day: what `git push` and `git pull` will do (push.default, pull.rebase, upstream tracking, separate push URLs), how merges and rebases behave, anything that rewrites content (filters, autocrlf, URL rewrites) and, for each hook, when it runs and what its script actually does. Point out settings that commonly surprise people, hooks that Git will never run (not executable, or not a hook name Git knows) and settings at one level that are overridden at another. Keep it under 400 words.";
+
+// Budget for each hook script in the prompt
+const HOOK_TOKENS: usize = 600;
+
+// Sections whose settings change how Git behaves; user-level settings
+// outside of these (colors, editors, aliases for other repositories' tools)
+// are left out. The repository's own config is always included in full.
+const RELEVANT_SECTIONS: &[&str] = &[
+    "core", "push", "pull", "fetch", "rebase", "merge", "branch", "remote", "url", "commit", "tag", "gpg", "user",
+    "init", "filter", "diff", "submodule", "lfs", "http", "credential", "rerere", "status", "advice", "alias",
+];
+
+// Words in setting names whose values are secrets
+const SECRET_WORDS: &[&str] = &["token", "password", "pass", "secret", "extraheader", "key"];
+
+// The hooks Git runs, in the order they come up in a typical workflow
+const HOOK_NAMES: &[&str] = &[
+    "applypatch-msg", "pre-applypatch", "post-applypatch", "pre-commit", "pre-merge-commit", "prepare-commit-msg",
+    "commit-msg", "post-commit", "pre-rebase", "post-checkout", "post-merge", "pre-push", "pre-receive", "update",
+    "proc-receive", "post-receive", "post-update", "reference-transaction", "push-to-checkout", "pre-auto-gc",
+    "post-rewrite", "sendemail-validate", "fsmonitor-watchman", "p4-changelist", "p4-prepare-changelist",
+    "p4-post-changelist", "p4-pre-submit", "post-index-change",
+];
+
+/// One configuration setting and the file level it comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Setting {
+    pub key: String,
+    /// The value, with secrets replaced by a placeholder.
+    pub value: String,
+    /// `system`, `global`, `local` and so on.
+    pub level: &'static str,
+}
+
+/// A configured remote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Remote {
+    pub name: String,
+    pub url: Option<String>,
+    /// Set when pushes go somewhere other than `url`.
+    pub push_url: Option<String>,
+    pub fetch: Vec<String>,
+}
+
+/// An installed hook script.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hook {
+    pub name: String,
+    pub path: PathBuf,
+    /// Whether Git would run it at all.
+    pub executable: bool,
+    /// Whether `name` is a hook Git knows about.
+    pub known: bool,
+    #[serde(skip)]
+    pub script: String,
+}
+
+/// Everything that makes up a repository's setup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Setup {
+    pub settings: Vec<Setting>,
+    pub remotes: Vec<Remote>,
+    /// The directory hooks are read from (`core.hooksPath` or `.git/hooks`).
+    pub hooks_dir: PathBuf,
+    pub hooks: Vec<Hook>,
+}
+
+/// The setup and the model's explanation of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Explained {
+    pub setup: Setup,
+    pub explanation: String,
+}
+
+fn level_name(level: ConfigLevel) -> &'static str {
+    match level {
+        ConfigLevel::ProgramData => "programdata",
+        ConfigLevel::System => "system",
+        ConfigLevel::XDG => "xdg",
+        ConfigLevel::Global => "global",
+        ConfigLevel::Local => "local",
+        ConfigLevel::App => "app",
+        ConfigLevel::Highest => "highest",
+    }
+}
+
+/// `value` as it may be shown and sent: secrets and credentials in URLs
+/// are replaced by a placeholder.
+pub fn redact(key: &str, value: &str) -> String {
+    let last = key.rsplit('.').next().unwrap_or(key).to_lowercase();
+    if SECRET_WORDS.iter().any(|word| last.contains(word)) {
+        return crash::REDACTED.to_string();
+    }
+    crash::redact_value(value)
+}
+
+/// Read the settings, remotes and hooks of `repo`.
+pub fn collect(repo: &Repository) -> Result<Setup> {
+    let config = repo.config()?;
+    let mut settings = Vec::new();
+    let mut entries = config.entries(None)?;
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let (Some(key), Some(value)) = (entry.name(), entry.value()) else { continue };
+        let level = entry.level();
+        let section = key.split('.').next().unwrap_or_default();
+        if level != ConfigLevel::Local && !RELEVANT_SECTIONS.contains(&section) {
+            continue;
+        }
+        settings.push(Setting { key: key.to_string(), value: redact(key, value), level: level_name(level) });
+    }
+
+    let mut remotes = Vec::new();
+    for name in repo.remotes()?.iter().flatten() {
+        let remote = repo.find_remote(name)?;
+        let url = remote.url().map(crash::redact_value);
+        let push_url = remote.pushurl().map(crash::redact_value).filter(|push| Some(push) != url.as_ref());
+        let fetch = remote.fetch_refspecs()?.iter().flatten().map(str::to_string).collect();
+        remotes.push(Remote { name: name.to_string(), url, push_url, fetch });
+    }
+
+    let hooks_dir = hooks_dir(repo);
+    let hooks = hooks(&hooks_dir)?;
+    Ok(Setup { settings, remotes, hooks_dir, hooks })
+}
+
+/// Where Git looks for hooks: `core.hooksPath` (relative paths are taken
+/// from the working directory, as Git does) or the repository's `hooks`.
+pub fn hooks_dir(repo: &Repository) -> PathBuf {
+    let configured = repo.config().ok().and_then(|config| config.get_path("core.hooksPath").ok());
+    match configured {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => repo.workdir().unwrap_or_else(|| repo.path()).join(path),
+        None => repo.path().join("hooks"),
+    }
+}
+
+// The hook scripts in `dir`, leaving out Git's `.sample` templates
+fn hooks(dir: &Path) -> Result<Vec<Hook>> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Ok(Vec::new()) };
+    let mut hooks = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !path.is_file() || name.ends_with(".sample") || name.starts_with('.') {
+            continue;
+        }
+        let bytes = std::fs::read(&path)?;
+        let (script, _) = chunk::truncate_to_tokens(&String::from_utf8_lossy(&bytes), HOOK_TOKENS);
+        hooks.push(Hook {
+            known: HOOK_NAMES.contains(&name.as_str()),
+            executable: platform::is_executable(&path),
+            name,
+            path,
+            script,
+        });
+    }
+    hooks.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(hooks)
+}
+
+fn content(setup: &Setup) -> String {
+    let mut content = String::from("Settings (level: key = value):\n");
+    for setting in &setup.settings {
+        content.push_str(&format!("{}: {} = {}\n", setting.level, setting.key, setting.value));
+    }
+    content.push_str("\nRemotes:\n");
+    if setup.remotes.is_empty() {
+        content.push_str("(none)\n");
+    }
+    for remote in &setup.remotes {
+        content.push_str(&format!("{}: {}\n", remote.name, remote.url.as_deref().unwrap_or("(no URL)")));
+        if let Some(push_url) = &remote.push_url {
+            content.push_str(&format!("  pushes to: {}\n", push_url));
+        }
+        for refspec in &remote.fetch {
+            content.push_str(&format!("  fetch: {}\n", refspec));
+        }
+    }
+    content.push_str(&format!("\nHooks in {}:\n", setup.hooks_dir.display()));
+    if setup.hooks.is_empty() {
+        content.push_str("(none)\n");
+    }
+    for hook in &setup.hooks {
+        let mut notes = Vec::new();
+        if !hook.executable {
+            notes.push("not executable, Git skips it");
+        }
+        if !hook.known {
+            notes.push("not a hook name Git knows, never run");
+        }
+        let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join("; ")) };
+        content.push_str(&format!("\n--- {}{} ---\n{}\n", hook.name, notes, hook.script.trim_end()));
+    }
+    content
+}
+
+/// Estimate the request explaining `setup` will use.
+pub fn estimate(setup: &Setup) -> RunEstimate {
+    RunEstimate {
+        requests: 1,
+        prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(CONFIG_PROMPT) + tokens::estimate(&content(setup)),
+    }
+}
+
+/// Ask the model to explain `setup`.
+pub async fn explain(setup: Setup, provider: &dyn LlmProvider) -> Result<Explained> {
+    progress!("{}", tr!("progress-config-explain", settings = setup.settings.len(), hooks = setup.hooks.len()));
+    let question = format!("{}\n\n{}", CONFIG_PROMPT, content(&setup));
+    let explanation = Conversation::new(SYSTEM_PROMPT).ask(provider, question).await?;
+    Ok(Explained { setup, explanation })
+}
+
+// Why Git would not run a hook, if it wouldn't
+fn hook_problem(hook: &Hook) -> Option<String> {
+    if !hook.known {
+        Some(tr!("config-hook-unknown"))
+    } else if !hook.executable {
+        Some(tr!("config-hook-not-executable"))
+    } else {
+        None
+    }
+}
+
+fn hook_line(hook: &Hook) -> String {
+    match hook_problem(hook) {
+        Some(problem) => format!("{} ({})", hook.name, problem),
+        None => hook.name.clone(),
+    }
+}
+
+fn remote_line(remote: &Remote) -> String {
+    let url = remote.url.as_deref().unwrap_or("-");
+    match &remote.push_url {
+        Some(push_url) => tr!("config-remote-push", name = remote.name.as_str(), url = url, push = push_url.as_str()),
+        None => format!("{}: {}", remote.name, url),
+    }
+}
+
+/// Render `explained` in the given format.
+pub fn render(explained: &Explained, format: OutputFormat) -> String {
+    let setup = &explained.setup;
+    let remotes: Vec<String> = setup.remotes.iter().map(remote_line).collect();
+    let hooks: Vec<String> = setup.hooks.iter().map(hook_line).collect();
+    let hooks_title = tr!("config-hooks", path = setup.hooks_dir.display());
+    let none = tr!("config-none");
+    let list = |items: &[String], bullet: &str| {
+        if items.is_empty() {
+            format!("{}{}\n", bullet, none)
+        } else {
+            items.iter().map(|item| format!("{}{}\n", bullet, item)).collect()
+        }
+    };
+
+    match format {
+        // Serializing plain strings and paths cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(explained).expect("setup serializes to JSON") + "\n",
+        OutputFormat::Markdown => {
+            let code: Vec<String> = remotes.iter().map(|line| format!("`{}`", line)).collect();
+            let hooks: Vec<String> = hooks.iter().map(|line| format!("`{}`", line)).collect();
+            format!(
+                "## {}\n\n{}\n\n### {}\n\n{}\n### {}\n\n{}",
+                tr!("config-title"),
+                explained.explanation.trim(),
+                tr!("config-remotes"),
+                list(&code, "- "),
+                hooks_title,
+                list(&hooks, "- ")
+            )
+        }
+        OutputFormat::Terminal => format!(
+            "\n=== {} ===\n\n{}\n\n=== {} ===\n\n{}\n=== {} ===\n\n{}",
+            tr!("config-title"),
+            explained.explanation.trim(),
+            tr!("config-remotes"),
+            list(&remotes, "  "),
+            hooks_title,
+            list(&hooks, "  ")
+        ),
+        OutputFormat::Plain => crate::render::plain_sections(&[
+            (tr!("config-title"), explained.explanation.clone()),
+            (tr!("config-remotes"), list(&remotes, "")),
+            (hooks_title, list(&hooks, "")),
+        ]),
+    }
+}
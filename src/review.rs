@@ -0,0 +1,288 @@
+//! `wtf review`: a critique of changes, rather than an explanation.
+//!
+//! The changes of a revision range, or the staged ones, are split by file,
+//! and each file is reviewed in a request of its own, so a large change set
+//! gets the same attention for every file. A file whose patch is too large
+//! is split further by hunk. The model replies with findings as JSON, each
+//! with a severity and a category, which are grouped by file with the most
+//! severe first. Generated, vendored, excluded and binary files are left
+//! out, as in every other report.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::error::{Result, WtfError};
+use crate::generated::{self, Detector};
+use crate::git::{self, RevRange, Uncommitted};
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Diff, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an experienced, constructive code reviewer.";
+
+const REVIEW_PROMPT: &str = "Review the change to one file below, as a careful reviewer would before it is merged. Do not explain what the change does; point out what is wrong with it or could be better: likely bugs, risky patterns like unchecked errors, races or injection, missing or weakened tests, unclear naming and anything else a reviewer would ask about. Give each finding a severity (high for bugs and security problems that must be fixed, medium for risks that should be addressed, low for suggestions), a category, the lines it is about as numbered in the new version of the file (taken from the @@ hunk headers), and a comment of at most 50 words saying what the problem is and how to fix it. Only report real problems in the lines that changed; return no findings if there are none.";
+
+/// How bad a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// A bug or security problem that must be fixed.
+    High,
+    /// A risk that should be addressed.
+    Medium,
+    /// A suggestion.
+    Low,
+}
+
+impl Severity {
+    fn label(self) -> String {
+        match self {
+            Severity::High => tr!("review-high"),
+            Severity::Medium => tr!("review-medium"),
+            Severity::Low => tr!("review-low"),
+        }
+    }
+}
+
+/// What kind of problem a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Bug,
+    Risk,
+    Tests,
+    Naming,
+    Other,
+}
+
+impl Category {
+    fn label(self) -> String {
+        match self {
+            Category::Bug => tr!("review-bug"),
+            Category::Risk => tr!("review-risk"),
+            Category::Tests => tr!("review-tests"),
+            Category::Naming => tr!("review-naming"),
+            Category::Other => tr!("review-other"),
+        }
+    }
+}
+
+/// A file to review, with its patch in as many parts as it takes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReviewFile {
+    pub path: String,
+    #[serde(skip)]
+    pub parts: Vec<String>,
+}
+
+/// The changes to review, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct ReviewInput {
+    /// What is reviewed, like `main..feature` or `staged changes`.
+    pub target: String,
+    /// The subjects of the commits in a range, for what they meant to do.
+    pub subjects: Vec<String>,
+    pub files: Vec<ReviewFile>,
+}
+
+// The files of `diff`, without generated and excluded ones, each patch
+// split into parts of at most `max_diff_tokens`
+fn segment(repo: &Repository, diff: &Diff<'_>, exclude: &[String], max_diff_tokens: usize) -> Result<Vec<ReviewFile>> {
+    let churn = generated::diff_churn(diff, &mut Detector::with_exclude(repo, exclude))?;
+    let patch = generated::strip_patch(&git::diff_patch(diff)?, &churn);
+    let mut files = Vec::new();
+    for file in chunk::split_files(&patch) {
+        let Some(path) = file.path() else { continue };
+        if file.hunks.is_empty() {
+            continue;
+        }
+        let text = format!("{}{}", file.header, file.hunks.concat());
+        files.push(ReviewFile { path: path.to_string(), parts: chunk::chunk_patch(&text, max_diff_tokens) });
+    }
+    progress!("{}", tr!("progress-review", count = files.len()));
+    Ok(files)
+}
+
+/// Gather what `range` changed, from where its two ends split off, leaving
+/// out files matching `exclude`.
+pub fn prepare_range(
+    repo: &Repository,
+    range: &RevRange,
+    exclude: &[String],
+    max_diff_tokens: usize,
+) -> Result<ReviewInput> {
+    let (from, to) = (git::resolve_commit(repo, &range.from)?, git::resolve_commit(repo, &range.to)?);
+    let fork = match repo.merge_base(from.id(), to.id()) {
+        Ok(id) => Some(repo.find_commit(id)?.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(fork.as_ref(), Some(&to.tree()?), None)?;
+    let mut subjects = Vec::new();
+    for id in git::range_commits(repo, range)? {
+        let commit = repo.find_commit(id)?;
+        subjects.push(git::commit_message(&commit).lines().next().unwrap_or_default().to_string());
+    }
+    let files = segment(repo, &diff, exclude, max_diff_tokens)?;
+    Ok(ReviewInput { target: range.to_string(), subjects, files })
+}
+
+/// Gather the staged changes, leaving out files matching `exclude`.
+pub fn prepare_staged(repo: &Repository, exclude: &[String], max_diff_tokens: usize) -> Result<ReviewInput> {
+    let diff = git::uncommitted_diff(repo, Uncommitted::Staged)?;
+    if diff.deltas().len() == 0 {
+        return Err(WtfError::NoChanges { staged: true });
+    }
+    let files = segment(repo, &diff, exclude, max_diff_tokens)?;
+    Ok(ReviewInput { target: tr!("review-staged"), subjects: Vec::new(), files })
+}
+
+impl ReviewInput {
+    fn question(&self, file: &ReviewFile, part: &str) -> String {
+        let mut out = format!("{}\n\nFile: {}\n", REVIEW_PROMPT, file.path);
+        if !self.subjects.is_empty() {
+            out.push_str(&format!("The commits making the change:\n- {}\n", self.subjects.join("\n- ")));
+        }
+        out.push_str(&format!("\n{}", part));
+        out
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        let questions: Vec<String> = self
+            .files
+            .iter()
+            .flat_map(|file| file.parts.iter().map(|part| self.question(file, part)))
+            .collect();
+        RunEstimate {
+            requests: questions.len(),
+            prompt_tokens: questions.iter().map(|q| tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(q)).sum(),
+        }
+    }
+}
+
+/// One problem the review found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub category: Category,
+    /// The lines it is about, like `42` or `42-48`; empty for the whole file.
+    pub lines: String,
+    pub comment: String,
+}
+
+/// The findings in one file, most severe first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReview {
+    pub path: String,
+    pub findings: Vec<Finding>,
+}
+
+/// The review of all files that have findings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Review {
+    pub target: String,
+    /// How many files were reviewed.
+    pub reviewed: usize,
+    pub files: Vec<FileReview>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    findings: Vec<Finding>,
+}
+
+/// Ask the model to review each file, a part at a time.
+pub async fn explain(input: &ReviewInput, provider: &dyn LlmProvider) -> Result<Review> {
+    let schema = Schema::new(
+        "code_review",
+        json!({
+            "type": "object",
+            "properties": {
+                "findings": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "severity": { "type": "string", "enum": ["high", "medium", "low"] },
+                            "category": { "type": "string", "enum": ["bug", "risk", "tests", "naming", "other"] },
+                            "lines": { "type": "string" },
+                            "comment": { "type": "string" },
+                        },
+                        "required": ["severity", "category", "lines", "comment"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["findings"],
+            "additionalProperties": false,
+        }),
+    );
+    let mut files = Vec::new();
+    for file in &input.files {
+        let mut findings = Vec::new();
+        for part in &file.parts {
+            let messages = [Message::system(SYSTEM_PROMPT), Message::user(input.question(file, part))];
+            let reply: Reply = structured::request(provider, &messages, &schema).await?;
+            findings.extend(reply.findings.into_iter().map(|finding| Finding {
+                lines: finding.lines.trim().to_string(),
+                comment: finding.comment.trim().to_string(),
+                ..finding
+            }));
+        }
+        // Stable, so findings of the same severity keep the model's order
+        findings.sort_by_key(|finding| finding.severity);
+        if !findings.is_empty() {
+            files.push(FileReview { path: file.path.clone(), findings });
+        }
+    }
+    Ok(Review { target: input.target.clone(), reviewed: input.files.len(), files })
+}
+
+/// Format `review` for output, a section per file.
+pub fn render(review: &Review, format: OutputFormat) -> String {
+    let count = |severity: Severity| {
+        review.files.iter().flat_map(|file| &file.findings).filter(|finding| finding.severity == severity).count()
+    };
+    let overview = tr!(
+        "review-overview",
+        files = review.reviewed,
+        high = count(Severity::High),
+        medium = count(Severity::Medium),
+        low = count(Severity::Low)
+    );
+    let mut sections = vec![(tr!("review-title", target = review.target.as_str()), format!("{}\n", overview))];
+    for file in &review.files {
+        let body: String = file
+            .findings
+            .iter()
+            .map(|finding| {
+                let place = match finding.lines.is_empty() {
+                    true => String::new(),
+                    false => format!(", {}", tr!("review-lines", lines = finding.lines.as_str())),
+                };
+                let label = format!("[{}] {}{}", finding.severity.label(), finding.category.label(), place);
+                format!("- {}: {}\n", label, finding.comment)
+            })
+            .collect();
+        sections.push((file.path.clone(), body));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(review).expect("review serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
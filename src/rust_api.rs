@@ -0,0 +1,494 @@
+//! Public API changes of Rust library crates between two trees.
+//!
+//! Every library crate in a tree (a `src/lib.rs` and the modules next to it)
+//! is parsed with `syn`, and the signatures of its public items are compared
+//! by path. Bodies, private fields and doc comments are left out, so only
+//! changes a dependent crate could notice show up.
+//!
+//! Module visibility is followed through `mod` declarations, but `#[path]`
+//! attributes, `#[cfg]` and what re-exports make reachable are not. Impl
+//! blocks count for a public type when they name it in the module that
+//! defines it.
+
+use crate::error::Result;
+use crate::git;
+use git2::{ObjectType, Oid, Repository, Tree};
+use quote::ToTokens;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use syn::{ImplItem, Item, TraitItem, Visibility};
+
+/// What kind of item a public API entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKind {
+    Fn,
+    Method,
+    Struct,
+    Enum,
+    Union,
+    Trait,
+    Type,
+    Const,
+    Static,
+    Macro,
+    Impl,
+    Use,
+}
+
+/// One public item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiItem {
+    /// Crate and module path, like `mycrate::parser::Parser::parse`; the
+    /// crate in the repository root is called `crate`.
+    pub path: String,
+    pub kind: ApiKind,
+    /// The item's signature, without bodies or doc comments.
+    pub signature: String,
+}
+
+/// A public item whose signature changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiChange {
+    pub path: String,
+    pub kind: ApiKind,
+    pub before: String,
+    pub after: String,
+}
+
+/// The difference between two versions of the public API.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ApiChanges {
+    pub added: Vec<ApiItem>,
+    pub removed: Vec<ApiItem>,
+    pub changed: Vec<ApiChange>,
+}
+
+impl ApiChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Whether anything was removed or changed, which can break dependents.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty() || !self.changed.is_empty()
+    }
+}
+
+/// Compare the public API of two lists of items.
+pub fn compare(old: &[ApiItem], new: &[ApiItem]) -> ApiChanges {
+    let key = |item: &ApiItem| (item.path.clone(), item.kind, item.signature.clone());
+    let by_name = |items: &[ApiItem]| -> BTreeMap<(String, ApiKind), Vec<String>> {
+        let mut map: BTreeMap<(String, ApiKind), Vec<String>> = BTreeMap::new();
+        for item in items {
+            map.entry((item.path.clone(), item.kind)).or_default().push(item.signature.clone());
+        }
+        map
+    };
+    let (old_names, new_names) = (by_name(old), by_name(new));
+    let (old_keys, new_keys): (HashSet<_>, HashSet<_>) = (old.iter().map(key).collect(), new.iter().map(key).collect());
+
+    let mut changes = ApiChanges::default();
+    for ((path, kind), before) in &old_names {
+        match new_names.get(&(path.clone(), *kind)) {
+            // A single item under this name on both sides whose signature differs
+            Some(after) if before.len() == 1 && after.len() == 1 => {
+                if before[0] != after[0] {
+                    changes.changed.push(ApiChange {
+                        path: path.clone(),
+                        kind: *kind,
+                        before: before[0].clone(),
+                        after: after[0].clone(),
+                    });
+                }
+            }
+            _ => {
+                for signature in before {
+                    if !new_keys.contains(&(path.clone(), *kind, signature.clone())) {
+                        changes.removed.push(ApiItem { path: path.clone(), kind: *kind, signature: signature.clone() });
+                    }
+                }
+            }
+        }
+    }
+    for ((path, kind), after) in &new_names {
+        let before = old_names.get(&(path.clone(), *kind));
+        if before.is_some_and(|before| before.len() == 1 && after.len() == 1) {
+            continue;
+        }
+        for signature in after {
+            if !old_keys.contains(&(path.clone(), *kind, signature.clone())) {
+                changes.added.push(ApiItem { path: path.clone(), kind: *kind, signature: signature.clone() });
+            }
+        }
+    }
+    changes
+}
+
+/// How the public API changed from `old` (or nothing, for the start of
+/// history) to `new`. `None` when `new` contains no Rust library crate.
+pub fn changes_between(repo: &Repository, old: Option<&Tree>, new: &Tree) -> Result<Option<ApiChanges>> {
+    let new_files = rust_files(repo, new)?;
+    if !new_files.keys().any(|path| path == "src/lib.rs" || path.ends_with("/src/lib.rs")) {
+        return Ok(None);
+    }
+    let old_files = match old {
+        Some(tree) => rust_files(repo, tree)?,
+        None => BTreeMap::new(),
+    };
+
+    let mut cache = HashMap::new();
+    let old_items = crate_items(repo, &old_files, &mut cache)?;
+    let new_items = crate_items(repo, &new_files, &mut cache)?;
+    Ok(Some(compare(&old_items, &new_items)))
+}
+
+/// The public items of the library crates in `files`, given as
+/// `(path, source)` pairs relative to the repository root. Files that do
+/// not parse are skipped.
+pub fn public_items(files: &[(String, String)]) -> Vec<ApiItem> {
+    let apis: Vec<FileApi> = files
+        .iter()
+        .filter_map(|(path, source)| file_api(path, source))
+        .collect();
+    assemble(apis.iter())
+}
+
+// Everything one source file contributes
+#[derive(Debug, Clone, Default)]
+struct FileApi {
+    /// Crate label, used to keep crates apart when assembling.
+    krate: String,
+    items: Vec<ApiItem>,
+    /// Module paths declared by this file, and whether each is `pub`.
+    mods: Vec<(String, bool)>,
+    /// Paths of public types, which trait impls are kept for.
+    types: Vec<String>,
+    /// Trait impls and inherent methods, with the path of their type.
+    impls: Vec<(String, ApiItem)>,
+    /// The module path of the file itself.
+    module: String,
+}
+
+fn rust_files(repo: &Repository, tree: &Tree) -> Result<BTreeMap<String, Oid>> {
+    let mut files = BTreeMap::new();
+    collect_rust_files(repo, tree, "", &mut files)?;
+    Ok(files)
+}
+
+fn collect_rust_files(repo: &Repository, tree: &Tree, prefix: &str, files: &mut BTreeMap<String, Oid>) -> Result<()> {
+    for entry in tree.iter() {
+        let path = format!("{}{}", prefix, git::escape_non_utf8(entry.name_bytes()));
+        match entry.kind() {
+            Some(ObjectType::Blob) if path.ends_with(".rs") => {
+                files.insert(path, entry.id());
+            }
+            Some(ObjectType::Tree) if path != "target" => {
+                let subtree = repo.find_tree(entry.id())?;
+                collect_rust_files(repo, &subtree, &format!("{}/", path), files)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn crate_items(
+    repo: &Repository,
+    files: &BTreeMap<String, Oid>,
+    cache: &mut HashMap<(String, Oid), Option<FileApi>>,
+) -> Result<Vec<ApiItem>> {
+    let mut apis = Vec::new();
+    for (path, oid) in files {
+        let key = (path.clone(), *oid);
+        if !cache.contains_key(&key) {
+            let blob = repo.find_blob(*oid)?;
+            let api = file_api(path, &String::from_utf8_lossy(blob.content()));
+            cache.insert(key.clone(), api);
+        }
+        if let Some(api) = &cache[&key] {
+            apis.push(api.clone());
+        }
+    }
+    Ok(assemble(apis.iter()))
+}
+
+// Keep the items of public modules of crates that have a lib.rs
+fn assemble<'a>(apis: impl Iterator<Item = &'a FileApi> + Clone) -> Vec<ApiItem> {
+    let libraries: HashSet<&str> = apis
+        .clone()
+        .filter(|api| api.module == api.krate)
+        .map(|api| api.krate.as_str())
+        .collect();
+    let declared: HashMap<&str, bool> = apis
+        .clone()
+        .flat_map(|api| api.mods.iter().map(|(path, public)| (path.as_str(), *public)))
+        .collect();
+    let types: HashSet<&str> = apis.clone().flat_map(|api| api.types.iter().map(String::as_str)).collect();
+
+    // A module is reachable if every module on the way to it is declared `pub`
+    let reachable = |krate: &str, module: &str| -> bool {
+        let mut path = krate.to_string();
+        for segment in module.strip_prefix(krate).unwrap_or_default().split("::").filter(|s| !s.is_empty()) {
+            path = format!("{}::{}", path, segment);
+            if declared.get(path.as_str()) != Some(&true) {
+                return false;
+            }
+        }
+        true
+    };
+
+    let mut items = Vec::new();
+    for api in apis.filter(|api| libraries.contains(api.krate.as_str())) {
+        items.extend(api.items.iter().filter(|item| reachable(&api.krate, parent(&item.path))).cloned());
+        items.extend(
+            api.impls
+                .iter()
+                .filter(|(ty, _)| types.contains(ty.as_str()) && reachable(&api.krate, parent(ty)))
+                .map(|(_, item)| item.clone()),
+        );
+    }
+    items.sort_by(|a, b| (&a.path, a.kind, &a.signature).cmp(&(&b.path, b.kind, &b.signature)));
+    items.dedup();
+    items
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once("::").map_or("", |(parent, _)| parent)
+}
+
+// The crate label and module path of a library source file, or `None` for
+// binaries, build scripts, tests and anything outside a `src` directory
+fn module_of(path: &str) -> Option<(String, String)> {
+    let (crate_dir, relative) = if let Some(relative) = path.strip_prefix("src/") {
+        ("", relative)
+    } else {
+        let index = path.rfind("/src/")?;
+        (&path[..index], &path[index + "/src/".len()..])
+    };
+    if relative == "main.rs" || relative.starts_with("bin/") {
+        return None;
+    }
+    let krate = match crate_dir.rsplit('/').next() {
+        Some(name) if !name.is_empty() => name.replace('-', "_"),
+        _ => "crate".to_string(),
+    };
+    let relative = relative.strip_suffix(".rs")?;
+    let relative = relative.strip_suffix("/mod").unwrap_or(relative);
+    let mut module = krate.clone();
+    if relative != "lib" {
+        for segment in relative.split('/') {
+            module = format!("{}::{}", module, segment);
+        }
+    }
+    Some((krate, module))
+}
+
+fn file_api(path: &str, source: &str) -> Option<FileApi> {
+    let (krate, module) = module_of(path)?;
+    let file = syn::parse_file(source).ok()?;
+    let mut api = FileApi {
+        krate,
+        module: module.clone(),
+        ..FileApi::default()
+    };
+    collect_items(&file.items, &module, &mut api);
+    Some(api)
+}
+
+fn collect_items(items: &[Item], module: &str, api: &mut FileApi) {
+    let push = |api: &mut FileApi, name: String, kind: ApiKind, signature: String| {
+        api.items.push(ApiItem {
+            path: format!("{}::{}", module, name),
+            kind,
+            signature: tidy(&signature),
+        });
+    };
+    for item in items {
+        match item {
+            Item::Mod(item) => {
+                let path = format!("{}::{}", module, item.ident);
+                api.mods.push((path.clone(), is_public(&item.vis)));
+                if let Some((_, content)) = &item.content {
+                    collect_items(content, &path, api);
+                }
+            }
+            Item::Fn(item) if is_public(&item.vis) => {
+                push(api, item.sig.ident.to_string(), ApiKind::Fn, format!("pub {}", tokens(&item.sig)));
+            }
+            Item::Struct(item) if is_public(&item.vis) => {
+                let mut item = item.clone();
+                item.attrs = kept_attrs(&item.attrs);
+                for field in item.fields.iter_mut() {
+                    field.attrs.clear();
+                }
+                if let syn::Fields::Named(fields) = &mut item.fields {
+                    fields.named = fields.named.iter().filter(|f| is_public(&f.vis)).cloned().collect();
+                }
+                api.types.push(format!("{}::{}", module, item.ident));
+                push(api, item.ident.to_string(), ApiKind::Struct, tokens(&item));
+            }
+            Item::Enum(item) if is_public(&item.vis) => {
+                let mut item = item.clone();
+                item.attrs = kept_attrs(&item.attrs);
+                for variant in item.variants.iter_mut() {
+                    variant.attrs.clear();
+                    for field in variant.fields.iter_mut() {
+                        field.attrs.clear();
+                    }
+                }
+                api.types.push(format!("{}::{}", module, item.ident));
+                push(api, item.ident.to_string(), ApiKind::Enum, tokens(&item));
+            }
+            Item::Union(item) if is_public(&item.vis) => {
+                let mut item = item.clone();
+                item.attrs = kept_attrs(&item.attrs);
+                item.fields.named = item.fields.named.iter().filter(|f| is_public(&f.vis)).cloned().collect();
+                for field in item.fields.named.iter_mut() {
+                    field.attrs.clear();
+                }
+                api.types.push(format!("{}::{}", module, item.ident));
+                push(api, item.ident.to_string(), ApiKind::Union, tokens(&item));
+            }
+            Item::Trait(item) if is_public(&item.vis) => {
+                let mut item = item.clone();
+                item.attrs.clear();
+                for trait_item in item.items.iter_mut() {
+                    match trait_item {
+                        TraitItem::Fn(f) => {
+                            f.attrs.clear();
+                            f.default = None;
+                            f.semi_token = Some(Default::default());
+                        }
+                        TraitItem::Const(c) => c.attrs.clear(),
+                        TraitItem::Type(t) => t.attrs.clear(),
+                        _ => {}
+                    }
+                }
+                push(api, item.ident.to_string(), ApiKind::Trait, tokens(&item));
+            }
+            Item::Type(item) if is_public(&item.vis) => {
+                let mut item = item.clone();
+                item.attrs.clear();
+                api.types.push(format!("{}::{}", module, item.ident));
+                push(api, item.ident.to_string(), ApiKind::Type, tokens(&item));
+            }
+            // Values are left out: changing a constant's value rarely breaks callers
+            Item::Const(item) if is_public(&item.vis) => {
+                let signature = format!("pub const {}: {}", item.ident, tokens(&item.ty));
+                push(api, item.ident.to_string(), ApiKind::Const, signature);
+            }
+            Item::Static(item) if is_public(&item.vis) => {
+                let mutability = if matches!(item.mutability, syn::StaticMutability::Mut(_)) { "mut " } else { "" };
+                let signature = format!("pub static {}{}: {}", mutability, item.ident, tokens(&item.ty));
+                push(api, item.ident.to_string(), ApiKind::Static, signature);
+            }
+            Item::Use(item) if is_public(&item.vis) => {
+                let tree = tidy(&tokens(&item.tree));
+                push(api, tree.clone(), ApiKind::Use, format!("pub use {}", tree));
+            }
+            // Exported macros live at the crate root whatever module defines them
+            Item::Macro(item) if item.attrs.iter().any(|attr| attr.path().is_ident("macro_export")) => {
+                if let Some(ident) = &item.ident {
+                    api.items.push(ApiItem {
+                        path: format!("{}::{}", api.krate, ident),
+                        kind: ApiKind::Macro,
+                        signature: format!("macro_rules! {}", ident),
+                    });
+                }
+            }
+            Item::Impl(item) => collect_impl(item, module, api),
+            _ => {}
+        }
+    }
+}
+
+fn collect_impl(item: &syn::ItemImpl, module: &str, api: &mut FileApi) {
+    let Some(type_name) = type_name(&item.self_ty) else { return };
+    let self_path = format!("{}::{}", module, type_name);
+    let self_ty = tokens(&item.self_ty);
+    match &item.trait_ {
+        // Inherent impls contribute their public methods
+        None => {
+            for impl_item in &item.items {
+                if let ImplItem::Fn(method) = impl_item {
+                    if is_public(&method.vis) {
+                        let item = ApiItem {
+                            path: format!("{}::{}", self_path, method.sig.ident),
+                            kind: ApiKind::Method,
+                            signature: tidy(&format!("pub {}", tokens(&method.sig))),
+                        };
+                        api.impls.push((self_path.clone(), item));
+                    }
+                }
+            }
+        }
+        Some((negative, trait_path, _)) => {
+            let negative = if negative.is_some() { "!" } else { "" };
+            let signature = tidy(&format!(
+                "impl{} {}{} for {}",
+                tokens(&item.generics),
+                negative,
+                tokens(trait_path),
+                self_ty
+            ));
+            let trait_name = trait_path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+            let item = ApiItem {
+                path: format!("{}::{}", module, tidy(&format!("impl {}{} for {}", negative, trait_name, self_ty))),
+                kind: ApiKind::Impl,
+                signature,
+            };
+            api.impls.push((self_path, item));
+        }
+    }
+}
+
+fn type_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn is_public(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+// Attributes that are part of a type's contract
+fn kept_attrs(attrs: &[syn::Attribute]) -> Vec<syn::Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| ["derive", "non_exhaustive", "repr"].iter().any(|name| attr.path().is_ident(name)))
+        .cloned()
+        .collect()
+}
+
+fn tokens(node: &impl ToTokens) -> String {
+    node.to_token_stream().to_string()
+}
+
+// Token streams print with a space between every token (groups come out as
+// "(a , b)"); close the gaps a person would not write, so signatures read
+// like source code
+fn tidy(text: &str) -> String {
+    let mut out = String::new();
+    let mut previous = "";
+    for token in text.split_whitespace() {
+        let opens = token.starts_with(['(', '[', '<']);
+        let glued = previous.is_empty()
+            || token.starts_with([',', ';', ':', ')', ']', '>', '.'])
+            || previous.ends_with(['(', '[', '<', '&', '#', '.', '?'])
+            || previous.ends_with("::")
+            || (opens && previous != "->" && previous.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '>'));
+        // Trailing commas are a matter of formatting, not of the API
+        if token.starts_with([')', ']', '}', '>']) && out.ends_with(',') {
+            out.pop();
+        }
+        if !glued {
+            out.push(' ');
+        }
+        out.push_str(token);
+        previous = token;
+    }
+    out
+}
@@ -0,0 +1,411 @@
+//! `wtf semver`: the version bump the next release calls for.
+//!
+//! Every commit since the last tag is rated a major, minor or patch bump, or
+//! none, with the reason. A conventional commit header decides without the
+//! model: a breaking change is major, `feat` minor, `fix` and `perf` patch
+//! and any other type none. So does the public API of the Rust library
+//! crates in the repository, compared before and after the commit: removing
+//! or changing an item is major, adding one minor, whichever is larger. The
+//! model rates the commits left over. The release takes the largest bump of
+//! its commits.
+
+use crate::analysis::RunEstimate;
+use crate::conventional;
+use crate::error::Result;
+use crate::git::{self, RevRange};
+use crate::provider::{LlmProvider, Message};
+use crate::release;
+use crate::render::OutputFormat;
+use crate::rust_api::{self, ApiChanges};
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that decides how a project's version number changes under semantic versioning.";
+
+const SEMVER_PROMPT: &str = "Rate the version bump each of the following commits calls for under semantic versioning: \"major\" for changes that can break existing users, like removed or renamed options, commands or functions, changed defaults or changed output formats; \"minor\" for new features that leave existing behavior alone; \"patch\" for bug fixes and performance improvements; \"none\" for changes users would not notice, like refactoring, tests, CI and internal documentation. Give the reason in one short sentence naming what in the commit calls for the bump. Return one rating per commit, using the short SHA given.";
+
+// Commits rated per request
+const BATCH_SIZE: usize = 25;
+
+// Public API items named in a reason
+const REASON_ITEMS: usize = 3;
+
+/// A version bump, from none to major.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    pub fn name(self) -> &'static str {
+        match self {
+            Bump::None => "none",
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+/// What decided a commit's bump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Basis {
+    /// Its conventional commit header.
+    Header,
+    /// The public API it changed.
+    Api,
+    Model,
+}
+
+/// Why a commit calls for its bump. It serializes the same in every UI
+/// language; [`Reason::text`] says it in the current one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Reason {
+    /// A conventional commit header of type `type`, marked breaking or not.
+    Header {
+        #[serde(rename = "type")]
+        kind: String,
+        breaking: bool,
+    },
+    /// Public API removed or changed, by path.
+    ApiBreaking { items: Vec<String> },
+    /// Public API added, by path.
+    ApiAdded { items: Vec<String> },
+    /// The model's reason, in its own words.
+    Model { text: String },
+    /// Left out of the model's reply.
+    Unrated,
+}
+
+impl Reason {
+    /// The reason in one sentence, for display.
+    pub fn text(&self) -> String {
+        match self {
+            Reason::Header { breaking: true, .. } => tr!("semver-reason-breaking"),
+            Reason::Header { kind, .. } => match kind.as_str() {
+                "feat" => tr!("semver-reason-feat"),
+                "fix" | "perf" => tr!("semver-reason-fix", kind = kind.as_str()),
+                kind => tr!("semver-reason-unnoticed", kind = kind),
+            },
+            Reason::ApiBreaking { items: paths } => tr!("semver-reason-api-breaking", items = items(paths)),
+            Reason::ApiAdded { items: paths } => tr!("semver-reason-api-added", items = items(paths)),
+            Reason::Model { text } => text.clone(),
+            Reason::Unrated => tr!("semver-reason-unrated"),
+        }
+    }
+}
+
+/// The bump one commit calls for and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rating {
+    pub bump: Bump,
+    pub basis: Basis,
+    pub reason: Reason,
+}
+
+/// A commit since the last tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub id: Oid,
+    pub short_id: String,
+    pub subject: String,
+    /// The rating its conventional header gives, if it has one.
+    pub header: Option<Rating>,
+    /// The rating its public API changes give, if it made any.
+    pub api: Option<Rating>,
+    // Its metadata, changed paths and public API changes, for the prompt
+    content: String,
+}
+
+impl Candidate {
+    /// Whether the model has to rate the commit: neither its header nor a
+    /// breaking API change decides it.
+    pub fn needs_model(&self) -> bool {
+        self.header.is_none() && self.api.as_ref().is_none_or(|api| api.bump < Bump::Major)
+    }
+}
+
+/// The commits since the last tag.
+#[derive(Debug, Clone)]
+pub struct SemverInput {
+    /// The last tag, if there is one.
+    pub since: Option<String>,
+    /// Newest first, without merge commits.
+    pub commits: Vec<Candidate>,
+}
+
+fn header_rating(message: &str) -> Option<Rating> {
+    let header = conventional::parse(message)?;
+    let bump = match header.kind.as_str() {
+        _ if header.breaking => Bump::Major,
+        "feat" => Bump::Minor,
+        "fix" | "perf" => Bump::Patch,
+        _ => Bump::None,
+    };
+    let reason = Reason::Header { kind: header.kind, breaking: header.breaking };
+    Some(Rating { bump, basis: Basis::Header, reason })
+}
+
+// The first few of `paths`, for a reason
+fn items(paths: &[String]) -> String {
+    let mut out = paths.iter().take(REASON_ITEMS).map(String::as_str).collect::<Vec<_>>().join(", ");
+    if paths.len() > REASON_ITEMS {
+        out.push_str(&format!(", {}", tr!("semver-more-items", count = paths.len() - REASON_ITEMS)));
+    }
+    out
+}
+
+fn api_rating(changes: &ApiChanges) -> Option<Rating> {
+    let (bump, reason) = if changes.is_breaking() {
+        let paths = changes.removed.iter().map(|item| item.path.clone());
+        let items = paths.chain(changes.changed.iter().map(|change| change.path.clone())).collect();
+        (Bump::Major, Reason::ApiBreaking { items })
+    } else if !changes.added.is_empty() {
+        let items = changes.added.iter().map(|item| item.path.clone()).collect();
+        (Bump::Minor, Reason::ApiAdded { items })
+    } else {
+        return None;
+    };
+    Some(Rating { bump, basis: Basis::Api, reason })
+}
+
+/// Gather the commits since the last tag in `repo`, leaving out merges,
+/// with what their headers and public API changes say.
+pub fn prepare(repo: &Repository) -> Result<SemverInput> {
+    let since = release::latest_tag(repo)?;
+    let ids = match &since {
+        Some(tag) => git::range_commits(
+            repo,
+            &RevRange { from: format!("refs/tags/{}", tag), to: "HEAD".to_string(), symmetric: false },
+        )?,
+        None => git::select_commits(repo, usize::MAX)?.0.iter().map(|commit| commit.id()).collect(),
+    };
+    progress!("{}", tr!("progress-semver", count = ids.len()));
+    let mut commits = Vec::new();
+    for id in ids {
+        let commit = repo.find_commit(id)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+        let message = git::commit_message(&commit);
+        let paths = git::changed_paths(repo, &commit)?;
+        let mut content =
+            format!("Short SHA: {}\n{}\nFiles: {}\n", short_id, git::get_commit_details(&commit), paths.join(", "));
+        // Only commits that touch Rust code can change a crate's API
+        let mut api = None;
+        if paths.iter().any(|path| path.ends_with(".rs")) {
+            let parent = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            if let Some(changes) = rust_api::changes_between(repo, parent.as_ref(), &commit.tree()?)? {
+                api = api_rating(&changes);
+                if let Some(rating) = &api {
+                    content.push_str(&format!("Public API: {}\n", rating.reason.text()));
+                }
+            }
+        }
+        commits.push(Candidate {
+            id,
+            short_id,
+            subject: message.lines().next().unwrap_or_default().to_string(),
+            header: header_rating(&message),
+            api,
+            content,
+        });
+    }
+    Ok(SemverInput { since, commits })
+}
+
+impl SemverInput {
+    fn asked(&self) -> Vec<&Candidate> {
+        self.commits.iter().filter(|candidate| candidate.needs_model()).collect()
+    }
+
+    fn question(batch: &[&Candidate]) -> String {
+        let mut out = SEMVER_PROMPT.to_string();
+        for candidate in batch {
+            out.push_str("\n\n---\n\n");
+            out.push_str(&candidate.content);
+        }
+        out
+    }
+
+    /// Estimate the requests [`explain`] will send; none when the headers
+    /// and API changes decide every commit.
+    pub fn estimate(&self) -> RunEstimate {
+        let asked = self.asked();
+        RunEstimate {
+            requests: asked.len().div_ceil(BATCH_SIZE),
+            prompt_tokens: asked
+                .chunks(BATCH_SIZE)
+                .map(|batch| tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&Self::question(batch)))
+                .sum(),
+        }
+    }
+}
+
+/// One commit's rating.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitBump {
+    pub short_id: String,
+    pub subject: String,
+    #[serde(flatten)]
+    pub rating: Rating,
+}
+
+/// The bump the next release calls for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suggestion {
+    pub since: Option<String>,
+    /// The largest bump of the commits.
+    pub bump: Bump,
+    /// The version the next release gets, when the last tag is a version.
+    pub next: Option<String>,
+    /// Newest first.
+    pub commits: Vec<CommitBump>,
+}
+
+/// The version after `tag` with `bump` applied, keeping its prefix, like
+/// v1.3.0 after v1.2.4 for a minor bump. Below 1.0.0 a major bump raises
+/// the minor version and a minor bump the patch version, as Cargo reads
+/// versions; after a pre-release like v2.0.0-rc.1 comes its final release.
+/// None without a bump or when `tag` is not a version.
+pub fn next_version(tag: &str, bump: Bump) -> Option<String> {
+    let (prefix, mut numbers, pre) = release::version(tag)?;
+    let len = numbers.len().max(3);
+    numbers.resize(len, 0);
+    let at = match (bump, numbers[0]) {
+        (Bump::None, _) => return None,
+        _ if pre => len,
+        (Bump::Major, 0) => 1,
+        (Bump::Major, _) => 0,
+        (Bump::Minor, 0) => 2,
+        (Bump::Minor, _) => 1,
+        (Bump::Patch, _) => 2,
+    };
+    if at < len {
+        numbers[at] += 1;
+        numbers[at + 1..].fill(0);
+    }
+    let numbers: Vec<String> = numbers.iter().map(u64::to_string).collect();
+    Some(format!("{}{}", prefix, numbers.join(".")))
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    commits: Vec<Rated>,
+}
+
+#[derive(Deserialize)]
+struct Rated {
+    sha: String,
+    bump: Bump,
+    reason: String,
+}
+
+/// Rate every commit, asking the model about those [`Candidate::needs_model`]
+/// picks a batch at a time. Without a `provider` those go unrated, which
+/// [`SemverInput::estimate`] makes unnecessary when it expects no requests.
+pub async fn explain(input: &SemverInput, provider: Option<&dyn LlmProvider>) -> Result<Suggestion> {
+    let schema = Schema::new(
+        "semver_bumps",
+        json!({
+            "type": "object",
+            "properties": {
+                "commits": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "sha": { "type": "string" },
+                            "bump": { "type": "string", "enum": ["major", "minor", "patch", "none"] },
+                            "reason": { "type": "string" },
+                        },
+                        "required": ["sha", "bump", "reason"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["commits"],
+            "additionalProperties": false,
+        }),
+    );
+    let mut rated: Vec<(Oid, Rating)> = Vec::new();
+    if let Some(provider) = provider {
+        for batch in input.asked().chunks(BATCH_SIZE) {
+            let messages = [Message::system(SYSTEM_PROMPT), Message::user(SemverInput::question(batch))];
+            let reply: Reply = structured::request(provider, &messages, &schema).await?;
+            for candidate in batch {
+                let full_id = candidate.id.to_string();
+                let found = reply.commits.iter().find(|rated| rated.sha.len() >= 7 && full_id.starts_with(&rated.sha));
+                if let Some(found) = found {
+                    let reason = Reason::Model { text: found.reason.trim().to_string() };
+                    rated.push((candidate.id, Rating { bump: found.bump, basis: Basis::Model, reason }));
+                }
+            }
+        }
+    }
+
+    let mut commits = Vec::new();
+    for candidate in &input.commits {
+        let model = rated.iter().find(|(id, _)| *id == candidate.id).map(|(_, rating)| rating.clone());
+        let unrated = || Rating { bump: Bump::None, basis: Basis::Model, reason: Reason::Unrated };
+        // The first of the largest, so a header beats an API change of the same size
+        let rating = [candidate.header.clone(), candidate.api.clone(), model]
+            .into_iter()
+            .flatten()
+            .reduce(|best, rating| if rating.bump > best.bump { rating } else { best })
+            .unwrap_or_else(unrated);
+        commits.push(CommitBump { short_id: candidate.short_id.clone(), subject: candidate.subject.clone(), rating });
+    }
+    let bump = commits.iter().map(|commit| commit.rating.bump).max().unwrap_or(Bump::None);
+    let next = input.since.as_deref().and_then(|tag| next_version(tag, bump));
+    Ok(Suggestion { since: input.since.clone(), bump, next, commits })
+}
+
+/// Format `suggestion` for output; JSON is meant for release automation.
+pub fn render(suggestion: &Suggestion, format: OutputFormat) -> String {
+    let verdict = match (suggestion.bump, &suggestion.since, &suggestion.next) {
+        (Bump::None, _, _) => tr!("semver-no-release"),
+        (bump, Some(since), Some(next)) => {
+            tr!("semver-next", bump = bump.name(), since = since.as_str(), next = next.as_str())
+        }
+        (bump, _, _) => tr!("semver-bump", bump = bump.name()),
+    };
+    let commits: String = suggestion
+        .commits
+        .iter()
+        .map(|commit| {
+            let rating = &commit.rating;
+            format!("- [{}] {} {}: {}\n", rating.bump.name(), commit.short_id, commit.subject, rating.reason.text())
+        })
+        .collect();
+    let sections = [(tr!("semver-title"), verdict + "\n"), (tr!("semver-commits"), commits)];
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(suggestion).expect("suggestion serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,212 @@
+//! Transcripts of interactive sessions, for `wtf session`.
+//!
+//! Interactive modes record every question, the model's answer and the
+//! commits involved in a [`Session`], saved after each answer as
+//! `sessions/<id>.json` in wtf's data directory. `wtf session export` turns
+//! a saved session back into a report, so an investigation like "how did
+//! this bug get here" can be shared instead of redone.
+
+use crate::citations;
+use crate::error::{Result, WtfError};
+use crate::git;
+use crate::render::OutputFormat;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const SESSIONS_DIR: &str = "sessions";
+
+/// One question and its answer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// When the answer came in, seconds since the epoch.
+    pub time: i64,
+    pub question: String,
+    pub answer: String,
+    /// Short SHAs of the commits the question was about or the answer cites.
+    pub commits: Vec<String>,
+}
+
+/// A recorded interactive session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// The start time as `YYYY-MM-DD-HHMMSS` (UTC), made unique on saving.
+    pub id: String,
+    /// The command that recorded it, like `tui`.
+    pub mode: String,
+    /// The repository's working directory.
+    pub repository: String,
+    /// Where commits link to, if the repository has a known web host.
+    pub commit_url: Option<String>,
+    /// Seconds since the epoch.
+    pub started: i64,
+    pub entries: Vec<Entry>,
+    // Whether the id is ours on disk, so later saves overwrite the same file
+    #[serde(skip)]
+    saved: bool,
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+// `seconds` as `YYYY-MM-DD-HHMMSS`, UTC
+fn timestamp_id(seconds: i64) -> String {
+    let time = seconds.rem_euclid(86_400);
+    format!("{}-{:02}{:02}{:02}", git::format_date(seconds), time / 3600, time / 60 % 60, time % 60)
+}
+
+// `seconds` as `YYYY-MM-DD HH:MM UTC`
+fn format_time(seconds: i64) -> String {
+    let time = seconds.rem_euclid(86_400);
+    format!("{} {:02}:{:02} UTC", git::format_date(seconds), time / 3600, time / 60 % 60)
+}
+
+impl Session {
+    /// Start recording a `mode` session, starting now.
+    pub fn new(mode: &str, repository: String, commit_url: Option<String>) -> Session {
+        let started = now();
+        Session {
+            id: timestamp_id(started),
+            mode: mode.to_string(),
+            repository,
+            commit_url,
+            started,
+            entries: Vec::new(),
+            saved: false,
+        }
+    }
+
+    /// Record an answer. The commits it cites are added to `commits`.
+    pub fn record(&mut self, question: impl Into<String>, answer: impl Into<String>, commits: &[&str]) {
+        let answer = answer.into();
+        let mut referenced: Vec<String> = Vec::new();
+        for sha in commits.iter().copied().chain(citations::cited_shas(&answer)) {
+            if !referenced.iter().any(|known| known.starts_with(sha) || sha.starts_with(known.as_str())) {
+                referenced.push(sha.to_string());
+            }
+        }
+        self.entries.push(Entry { time: now(), question: question.into(), answer, commits: referenced });
+    }
+
+    /// Write the session to `data_dir`, creating the directory if needed.
+    ///
+    /// If another session already has this one's id (two sessions started
+    /// within the same second), the first save adds a `-2`, `-3`, ... suffix.
+    pub fn save(&mut self, data_dir: &Path) -> Result<PathBuf> {
+        let dir = data_dir.join(SESSIONS_DIR);
+        std::fs::create_dir_all(&dir)?;
+        if !self.saved {
+            let base = timestamp_id(self.started);
+            let mut suffix = 1;
+            while dir.join(format!("{}.json", self.id)).exists() {
+                suffix += 1;
+                self.id = format!("{}-{}", base, suffix);
+            }
+            self.saved = true;
+        }
+        let path = dir.join(format!("{}.json", self.id));
+        // Serializing strings and numbers cannot fail
+        let json = serde_json::to_string_pretty(self).expect("session serializes to JSON");
+        std::fs::write(&path, json)?;
+        Ok(path)
+    }
+
+    /// Read the session `id` from `data_dir`.
+    pub fn load(data_dir: &Path, id: &str) -> Result<Session> {
+        let path = data_dir.join(SESSIONS_DIR).join(format!("{}.json", id));
+        let contents = std::fs::read_to_string(&path).map_err(|_| WtfError::UnknownSession { id: id.to_string() })?;
+        let mut session: Session = serde_json::from_str(&contents).map_err(std::io::Error::from)?;
+        session.saved = true;
+        Ok(session)
+    }
+}
+
+/// All saved sessions in `data_dir`, newest first. Files that cannot be
+/// read are skipped.
+pub fn list(data_dir: &Path) -> Vec<Session> {
+    let mut sessions: Vec<Session> = std::fs::read_dir(data_dir.join(SESSIONS_DIR))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|contents| serde_json::from_str(&contents).ok())
+        .collect();
+    sessions.sort_by(|a, b| b.started.cmp(&a.started).then_with(|| b.id.cmp(&a.id)));
+    sessions
+}
+
+/// One line per session for `wtf session list`.
+pub fn render_list(sessions: &[Session]) -> String {
+    sessions
+        .iter()
+        .map(|session| {
+            tr!(
+                "session-list-entry",
+                id = session.id.as_str(),
+                mode = session.mode.as_str(),
+                count = session.entries.len(),
+                repository = session.repository.as_str()
+            ) + "\n"
+        })
+        .collect()
+}
+
+/// Render `session` as a shareable transcript in the given format.
+pub fn render(session: &Session, format: OutputFormat) -> String {
+    let title = tr!("session-title", id = session.id.as_str());
+    let about = tr!(
+        "session-about",
+        repository = session.repository.as_str(),
+        mode = session.mode.as_str(),
+        date = format_time(session.started)
+    );
+    let commits = |entry: &Entry| tr!("session-commits", commits = entry.commits.join(", "));
+
+    match format {
+        // Serializing strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(session).expect("session serializes to JSON") + "\n",
+        OutputFormat::Markdown => {
+            let mut out = format!("# {}\n\n_{}_\n", title, about);
+            for (i, entry) in session.entries.iter().enumerate() {
+                let mut answer = entry.answer.trim().to_string();
+                let mut cited = commits(entry);
+                if let Some(base) = &session.commit_url {
+                    answer = citations::link_citations(&answer, base);
+                    let links: Vec<String> =
+                        entry.commits.iter().map(|sha| format!("[{}]({}/{})", sha, base, sha)).collect();
+                    cited = tr!("session-commits", commits = links.join(", "));
+                }
+                out.push_str(&format!("\n## {}. {}\n\n{}\n", i + 1, entry.question, answer));
+                if !entry.commits.is_empty() {
+                    out.push_str(&format!("\n_{}_\n", cited));
+                }
+            }
+            out
+        }
+        OutputFormat::Terminal => {
+            let mut out = format!("\n=== {} ===\n\n{}\n", title, about);
+            for (i, entry) in session.entries.iter().enumerate() {
+                out.push_str(&format!("\n--- {}. {} ---\n\n{}\n", i + 1, entry.question, entry.answer.trim()));
+                if !entry.commits.is_empty() {
+                    out.push_str(&format!("\n{}\n", commits(entry)));
+                }
+            }
+            out
+        }
+        OutputFormat::Plain => {
+            let mut sections = vec![(title, about)];
+            for entry in &session.entries {
+                let mut text = entry.answer.clone();
+                if !entry.commits.is_empty() {
+                    text = format!("{}\n\n{}", text.trim(), commits(entry));
+                }
+                sections.push((entry.question.clone(), text));
+            }
+            crate::render::plain_sections(&sections)
+        }
+    }
+}
@@ -0,0 +1,342 @@
+//! `wtf similar`: past commits that resemble a change.
+//!
+//! Every change is reduced to a bag of terms: the words of its message, the
+//! files and directories it touches and the identifiers on the lines it
+//! adds or removes. Terms are weighted by how rare they are in the history
+//! (TF-IDF), and commits are ranked by the cosine similarity of their terms
+//! to the change's. This runs locally and needs no embeddings; the model is
+//! only asked afterwards, to summarize how the most similar commits handled
+//! things: prior art, and fixes that keep coming back.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::citations::CITATION_INSTRUCTIONS;
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Commit, Diff, Repository};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that helps developers learn from the history of their code base.";
+
+const SIMILAR_PROMPT: &str = "The first change below is new. The commits after it are the most similar ones from the project's history, most similar first. Summarize how those earlier commits handled similar changes: what they did and why, which of them look like earlier attempts at the same fix (a regression that keeps coming back), and what the new change could learn from them. Say so plainly if a commit turns out to be unrelated. Keep it under 300 words.";
+
+// Commits less similar than this are not worth showing
+const MIN_SCORE: f64 = 0.1;
+
+// Budget for each change's diff in the prompt
+const PATCH_TOKENS: usize = 800;
+
+// Terms listed as what a match has in common with the change
+const SHARED_TERMS: usize = 5;
+
+// Common words that say nothing about what a change is about
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "this", "that", "into", "when", "are", "was", "not", "but", "use", "all",
+    "add", "adds", "added", "make", "makes", "more", "some", "also", "let", "new", "its", "now", "than", "then",
+];
+
+/// What is compared: a commit, or the changes staged in the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Commit(String),
+    Staged,
+}
+
+/// A change and its terms.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    /// `None` for staged changes.
+    pub short_id: Option<String>,
+    pub summary: String,
+    pub paths: Vec<String>,
+    /// What the model is told about the change.
+    #[serde(skip)]
+    pub content: String,
+    #[serde(skip)]
+    pub(crate) terms: HashMap<String, usize>,
+}
+
+/// A past commit similar to the change.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Match {
+    pub short_id: String,
+    /// Commit time, seconds since the epoch.
+    pub time: i64,
+    pub author: String,
+    pub summary: String,
+    /// Cosine similarity, from 0 (nothing in common) to 1.
+    pub score: f64,
+    /// The terms contributing most to the similarity.
+    pub shared: Vec<String>,
+    #[serde(skip)]
+    pub content: String,
+}
+
+/// The change to compare, or `None` when `target` is the index and nothing
+/// is staged.
+pub fn change(repo: &Repository, target: &Target) -> Result<Option<Change>> {
+    match target {
+        Target::Commit(rev) => {
+            let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+            Ok(Some(commit_change(repo, &commit)?))
+        }
+        Target::Staged => {
+            let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+            let diff = repo.diff_tree_to_index(head_tree.as_ref(), None, None)?;
+            if diff.deltas().len() == 0 {
+                return Ok(None);
+            }
+            let summary = tr!("similar-staged");
+            Ok(Some(diff_change(None, summary, "", &diff)?))
+        }
+    }
+}
+
+pub(crate) fn commit_change(repo: &Repository, commit: &Commit) -> Result<Change> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+    let message = git::commit_message(commit);
+    let summary = message.lines().next().unwrap_or_default().to_string();
+    diff_change(Some(short_id), summary, &message, &diff)
+}
+
+fn diff_change(short_id: Option<String>, summary: String, message: &str, diff: &Diff) -> Result<Change> {
+    let mut paths = Vec::new();
+    for delta in diff.deltas() {
+        if let Some(path) = delta.new_file().path_bytes().or(delta.old_file().path_bytes()).map(git::escape_non_utf8) {
+            paths.push(path);
+        }
+    }
+
+    // The changed lines, with their +/- markers, for terms and the prompt
+    let mut patch = String::new();
+    let mut changed = String::new();
+    diff.print(git2::DiffFormat::Patch, |_, _, line| {
+        let content = git::escape_non_utf8(line.content());
+        match line.origin() {
+            origin @ ('+' | '-') => {
+                patch.push(origin);
+                changed.push_str(&content);
+            }
+            ' ' => patch.push(' '),
+            _ => {}
+        }
+        patch.push_str(&content);
+        true
+    })?;
+
+    let mut terms = HashMap::new();
+    for word in words(message, 3) {
+        *terms.entry(word).or_default() += 2;
+    }
+    for path in &paths {
+        *terms.entry(format!("path:{}", path)).or_default() += 3;
+        let mut dirs: Vec<&str> = path.split('/').collect();
+        dirs.pop();
+        for depth in 1..=dirs.len() {
+            *terms.entry(format!("dir:{}", dirs[..depth].join("/"))).or_default() += 1;
+        }
+    }
+    for word in words(&changed, 4) {
+        *terms.entry(word).or_default() += 1;
+    }
+
+    let label = match &short_id {
+        Some(id) => format!("Commit: {}", id),
+        None => "Staged changes (not committed yet)".to_string(),
+    };
+    let (patch, _) = chunk::truncate_to_tokens(&patch, PATCH_TOKENS);
+    let content = format!("{}\nMessage:\n{}\n\nFiles: {}\n\n{}", label, message.trim_end(), paths.join(", "), patch);
+    Ok(Change { short_id, summary, paths, content, terms })
+}
+
+// Lowercased words and identifiers of at least `min_len` characters
+pub(crate) fn words(text: &str, min_len: usize) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(move |word| word.chars().count() >= min_len && !word.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
+
+/// The `top` commits among the last `max_commits` non-merge commits
+/// reachable from HEAD that are most similar to `change`, most similar
+/// first. The change itself is never among them.
+pub fn find_similar(repo: &Repository, change: &Change, max_commits: usize, top: usize) -> Result<Vec<Match>> {
+    let (_, total) = git::select_commits(repo, 0)?;
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    progress!("{}", tr!("progress-similar-scanning", count = max_commits.min(total)));
+
+    let mut candidates = Vec::new();
+    for oid in walk.take(max_commits.min(total)) {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 || is_change(&commit, change) {
+            continue;
+        }
+        candidates.push((commit.id(), commit_change(repo, &commit)?));
+    }
+
+    // How rare each term is across the history
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for (_, candidate) in &candidates {
+        for term in candidate.terms.keys() {
+            *document_frequency.entry(term).or_default() += 1;
+        }
+    }
+    let documents = candidates.len() as f64;
+    let idf = |term: &str| {
+        let frequency = document_frequency.get(term).copied().unwrap_or(0) as f64;
+        ((documents + 1.0) / (frequency + 1.0)).ln() + 1.0
+    };
+    let weigh = |terms: &HashMap<String, usize>| -> HashMap<String, f64> {
+        terms.iter().map(|(term, count)| (term.clone(), (1.0 + (*count as f64).ln()) * idf(term))).collect()
+    };
+
+    let target = weigh(&change.terms);
+    let target_norm = norm(&target);
+    let mut matches = Vec::new();
+    for (oid, candidate) in &candidates {
+        let weights = weigh(&candidate.terms);
+        let mut shared: Vec<(&String, f64)> = target
+            .iter()
+            .filter_map(|(term, weight)| weights.get(term).map(|other| (term, weight * other)))
+            .collect();
+        let dot: f64 = shared.iter().map(|(_, product)| product).sum();
+        let score = match target_norm * norm(&weights) {
+            denominator if denominator > 0.0 => dot / denominator,
+            _ => 0.0,
+        };
+        if score < MIN_SCORE {
+            continue;
+        }
+        shared.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let commit = repo.find_commit(*oid)?;
+        matches.push(Match {
+            short_id: candidate.short_id.clone().unwrap_or_default(),
+            time: commit.time().seconds(),
+            author: git::decode_text(commit.author().name_bytes(), commit.message_encoding()),
+            summary: candidate.summary.clone(),
+            score,
+            shared: shared.iter().take(SHARED_TERMS).map(|(term, _)| display_term(term)).collect(),
+            content: candidate.content.clone(),
+        });
+    }
+    // Stable, so equally similar commits stay newest first
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(top);
+    Ok(matches)
+}
+
+fn is_change(commit: &Commit, change: &Change) -> bool {
+    change.short_id.as_deref().is_some_and(|id| commit.id().to_string().starts_with(id))
+}
+
+fn norm(weights: &HashMap<String, f64>) -> f64 {
+    weights.values().map(|weight| weight * weight).sum::<f64>().sqrt()
+}
+
+fn display_term(term: &str) -> String {
+    match term.strip_prefix("path:") {
+        Some(path) => path.to_string(),
+        None => term.strip_prefix("dir:").map_or(term.to_string(), |dir| format!("{}/", dir)),
+    }
+}
+
+// What the model is asked, with the change first
+fn question(change: &Change, matches: &[Match]) -> String {
+    let mut question = format!("{} {}\n\n{}", SIMILAR_PROMPT, CITATION_INSTRUCTIONS, change.content);
+    for found in matches {
+        question.push_str(&format!("\n\n---\n\nSimilar commit ({:.0}% similar)\n{}", found.score * 100.0, found.content));
+    }
+    question
+}
+
+/// Estimate the request summarizing `matches` will use.
+pub fn estimate(change: &Change, matches: &[Match]) -> RunEstimate {
+    RunEstimate {
+        requests: 1,
+        prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&question(change, matches)),
+    }
+}
+
+/// A change, the commits similar to it and what they teach.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Similar {
+    pub change: Change,
+    pub matches: Vec<Match>,
+    pub summary: String,
+}
+
+/// Ask the model how the similar commits handled the change.
+pub async fn summarize(change: Change, matches: Vec<Match>, provider: &dyn LlmProvider) -> Result<Similar> {
+    progress!("{}", tr!("progress-similar", count = matches.len()));
+    let summary = Conversation::new(SYSTEM_PROMPT).ask(provider, question(&change, &matches)).await?;
+    Ok(Similar { change, matches, summary })
+}
+
+/// Render `similar` in the given format.
+pub fn render(similar: &Similar, format: OutputFormat, commit_url: Option<&str>) -> String {
+    let heading = match &similar.change.short_id {
+        Some(id) => tr!("similar-heading", id = id.as_str(), summary = similar.change.summary.as_str()),
+        None => tr!("similar-heading-staged"),
+    };
+    let entry = |found: &Match, id: String| {
+        tr!(
+            "similar-entry",
+            id = id,
+            date = git::format_date(found.time),
+            author = found.author.as_str(),
+            summary = found.summary.as_str(),
+            score = format!("{:.0}", found.score * 100.0),
+            shared = found.shared.join(", ")
+        )
+    };
+
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(similar).unwrap_or_default() + "\n",
+        OutputFormat::Markdown => {
+            let mut out = format!("## {}\n\n", heading);
+            for found in &similar.matches {
+                let id = match commit_url {
+                    Some(base) => format!("[`{}`]({}/{})", found.short_id, base, found.short_id),
+                    None => format!("`{}`", found.short_id),
+                };
+                out.push_str(&format!("- {}\n", entry(found, id)));
+            }
+            let summary = match commit_url {
+                Some(base) => crate::citations::link_citations(similar.summary.trim(), base),
+                None => similar.summary.trim().to_string(),
+            };
+            out.push_str(&format!("\n## {}\n\n{}\n", tr!("similar-summary-heading"), summary));
+            out
+        }
+        OutputFormat::Terminal => {
+            let mut out = format!("\n=== {} ===\n\n", heading);
+            for found in &similar.matches {
+                out.push_str(&format!("- {}\n", entry(found, found.short_id.clone())));
+            }
+            out.push_str(&format!("\n=== {} ===\n\n{}\n", tr!("similar-summary-heading"), similar.summary.trim()));
+            out
+        }
+        OutputFormat::Plain => {
+            let list: Vec<String> = similar
+                .matches
+                .iter()
+                .enumerate()
+                .map(|(i, found)| format!("{}. {}", i + 1, entry(found, found.short_id.clone())))
+                .collect();
+            let sections = [(heading, list.join("\n")), (tr!("similar-summary-heading"), similar.summary.clone())];
+            crate::render::plain_sections(&sections)
+        }
+    }
+}
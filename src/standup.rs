@@ -0,0 +1,319 @@
+//! `wtf standup`: what one author did over a time window, as a standup
+//! update.
+//!
+//! The author's commits since the start of the window are collected from
+//! HEAD and every local branch, merges left out; commits only on a branch
+//! not merged into HEAD are noted with the branch. One structured request
+//! turns them into the three parts of a standup: what is done, what is in
+//! progress and what blocks it. Commits marked as work in progress (`WIP`,
+//! `fixup!` and the like) and unmerged branches count as in progress;
+//! blockers are only what the messages suggest, like reverts and repeated
+//! attempts at the same fix.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::error::{Result, WtfError};
+use crate::git;
+use crate::provider::{LlmProvider, Message};
+use crate::render::OutputFormat;
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{BranchType, Oid, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::str::FromStr;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that writes standup updates from a developer's Git history.";
+
+const STANDUP_PROMPT: &str = "Write a standup update for {author} from the following commits, which are all theirs. Give three lists of short items, each a sentence of at most 20 words ending with the short SHAs of its commits in parentheses. \"done\": finished work, combining commits toward the same goal into one item. \"in_progress\": work that is not finished yet, such as commits marked as work in progress and commits on branches not merged yet. \"blockers\": what seems to hold the work up, going only by what the commits show, like reverts, repeated attempts at the same fix or messages saying something is blocked or failing; leave it empty when nothing does. Write for teammates, in the first person, about what the work achieves rather than which files changed.";
+
+// The diff of each commit is cut to this many tokens
+const PATCH_TOKENS: usize = 600;
+
+// Subjects starting with these mark a commit as unfinished
+const WIP_MARKERS: &[&str] = &["wip", "[wip]", "fixup!", "squash!", "amend!", "draft"];
+
+/// The start of the window, as given to `--since`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Since {
+    /// Midnight (UTC) this many days before today: 0 for `today`, 1 for
+    /// `yesterday`.
+    DaysBack(i64),
+    /// This many seconds before now, like `3 days ago`.
+    Ago(i64),
+    /// Midnight (UTC) of a date like `2024-05-01`, in seconds since the
+    /// epoch.
+    Date(i64),
+}
+
+impl Since {
+    /// The start of the window in seconds since the epoch, with `now` the
+    /// current time.
+    pub fn start(self, now: i64) -> i64 {
+        match self {
+            Since::DaysBack(days) => (now.div_euclid(86_400) - days) * 86_400,
+            Since::Ago(seconds) => now - seconds,
+            Since::Date(seconds) => seconds,
+        }
+    }
+}
+
+impl FromStr for Since {
+    type Err = String;
+
+    fn from_str(spec: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || tr!("since-invalid", spec = spec);
+        match spec.trim().to_lowercase().as_str() {
+            "today" => return Ok(Since::DaysBack(0)),
+            "yesterday" => return Ok(Since::DaysBack(1)),
+            _ => {}
+        }
+        if let Some(date) = git::parse_date(spec.trim()) {
+            return Ok(Since::Date(date));
+        }
+        // Like "3 days ago", or Git's "3.days.ago"
+        let spec = spec.trim().to_lowercase().replace('.', " ");
+        let mut words = spec.split_whitespace();
+        let count: i64 = words.next().and_then(|count| count.parse().ok()).ok_or_else(invalid)?;
+        let unit = words.next().ok_or_else(invalid)?;
+        if !matches!(words.next(), None | Some("ago")) || words.next().is_some() {
+            return Err(invalid());
+        }
+        let seconds = match unit.strip_suffix('s').unwrap_or(unit) {
+            "minute" => 60,
+            "hour" => 3_600,
+            "day" => 86_400,
+            "week" => 7 * 86_400,
+            _ => return Err(invalid()),
+        };
+        Ok(Since::Ago(count * seconds))
+    }
+}
+
+/// Whose commits go into the standup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Author {
+    /// How the update refers to the author.
+    pub name: String,
+    // Matched against "Name <email>", ignoring case
+    pattern: String,
+}
+
+impl Author {
+    /// The author `spec` stands for: `me` for the identity Git commits as,
+    /// anything else for the authors whose name or email contains it.
+    pub fn resolve(repo: &Repository, spec: &str) -> Result<Author> {
+        if spec != "me" {
+            return Ok(Author { name: spec.to_string(), pattern: spec.to_lowercase() });
+        }
+        let signature = repo.signature().map_err(|_| WtfError::NoIdentity)?;
+        let name = String::from_utf8_lossy(signature.name_bytes()).to_string();
+        let pattern = match String::from_utf8_lossy(signature.email_bytes()).to_lowercase() {
+            email if email.is_empty() => name.to_lowercase(),
+            email => format!("<{}>", email),
+        };
+        Ok(Author { name, pattern })
+    }
+
+    fn matches(&self, name: &str, email: &str) -> bool {
+        format!("{} <{}>", name, email).to_lowercase().contains(&self.pattern)
+    }
+}
+
+/// Whether `subject` marks its commit as unfinished, like `WIP: parser`.
+pub fn is_wip(subject: &str) -> bool {
+    let subject = subject.trim().to_lowercase();
+    let first = subject.split_whitespace().next().unwrap_or_default();
+    WIP_MARKERS.contains(&first.trim_end_matches(':')) || subject.starts_with("work in progress")
+}
+
+/// A commit of the author's in the window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contribution {
+    pub id: Oid,
+    pub short_id: String,
+    pub subject: String,
+    /// Author time, seconds since the epoch.
+    pub time: i64,
+    /// The local branch it is on, when HEAD does not contain it.
+    pub branch: Option<String>,
+    /// Marked as work in progress in its subject.
+    pub wip: bool,
+    // Its details, files and diff, for the prompt
+    content: String,
+}
+
+/// The author's commits in the window, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct StandupInput {
+    pub author: Author,
+    /// The start of the window, seconds since the epoch.
+    pub since: i64,
+    /// Newest first.
+    pub commits: Vec<Contribution>,
+}
+
+/// Collect the commits `author` made since `since` (seconds since the
+/// epoch) on HEAD and on the local branches HEAD does not contain.
+pub fn prepare(repo: &Repository, author: &Author, since: i64) -> Result<StandupInput> {
+    let mailmap = repo.mailmap()?;
+    let head = repo.head().ok().and_then(|head| head.target());
+    let mut tips: Vec<(Option<String>, Oid)> = head.map(|head| (None, head)).into_iter().collect();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let (Some(name), Some(tip)) = (branch.name()?, branch.get().target()) {
+            tips.push((Some(name.to_string()), tip));
+        }
+    }
+
+    let mut seen: Vec<Oid> = Vec::new();
+    let mut commits = Vec::new();
+    for (branch, tip) in tips {
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(Sort::TIME)?;
+        walk.push(tip)?;
+        if let (Some(_), Some(head)) = (&branch, head) {
+            walk.hide(head)?;
+        }
+        for id in walk {
+            let id = id?;
+            let commit = repo.find_commit(id)?;
+            // Committed before the window, so authored before it too
+            if commit.time().seconds() < since {
+                break;
+            }
+            if seen.contains(&id) || commit.parent_count() > 1 {
+                continue;
+            }
+            seen.push(id);
+            let signature = commit.author_with_mailmap(&mailmap)?;
+            let name = git::decode_text(signature.name_bytes(), commit.message_encoding());
+            let time = signature.when().seconds();
+            if time < since || !author.matches(&name, &String::from_utf8_lossy(signature.email_bytes())) {
+                continue;
+            }
+            let short_id = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+            let message = git::commit_message(&commit);
+            let subject = message.lines().next().unwrap_or_default().to_string();
+            let wip = is_wip(&subject);
+            let mut content = format!("Short SHA: {}\n", short_id);
+            if let Some(branch) = &branch {
+                content.push_str(&format!("Branch: {} (not merged)\n", branch));
+            }
+            if wip {
+                content.push_str("Marked as work in progress\n");
+            }
+            content.push_str(&format!("Files: {}\n", git::changed_paths(repo, &commit)?.join(", ")));
+            content.push_str(&git::get_commit_details(&commit));
+            let patch = git::commit_patch(repo, &commit)?.unwrap_or_default();
+            content.push_str(&format!("\n\nDiff:\n{}", chunk::truncate_to_tokens(&patch, PATCH_TOKENS).0));
+            let branch = branch.clone();
+            commits.push(Contribution { id, short_id, subject, time, branch, wip, content });
+        }
+    }
+    commits.sort_by_key(|commit| std::cmp::Reverse(commit.time));
+    progress!("{}", tr!("progress-standup", count = commits.len(), author = author.name.as_str()));
+    Ok(StandupInput { author: author.clone(), since, commits })
+}
+
+impl StandupInput {
+    fn question(&self) -> String {
+        let mut out = STANDUP_PROMPT.replace("{author}", &self.author.name);
+        for commit in &self.commits {
+            out.push_str("\n\n---\n\n");
+            out.push_str(&commit.content);
+        }
+        out
+    }
+
+    /// Estimate the requests [`explain`] will send: one, unless the author
+    /// made no commits in the window.
+    pub fn estimate(&self) -> RunEstimate {
+        match self.commits.is_empty() {
+            true => RunEstimate { requests: 0, prompt_tokens: 0 },
+            false => RunEstimate {
+                requests: 1,
+                prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(&self.question()),
+            },
+        }
+    }
+}
+
+/// The standup update.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Standup {
+    pub author: String,
+    /// The start of the window, seconds since the epoch.
+    pub since: i64,
+    pub commits: usize,
+    pub done: Vec<String>,
+    pub in_progress: Vec<String>,
+    pub blockers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    done: Vec<String>,
+    in_progress: Vec<String>,
+    blockers: Vec<String>,
+}
+
+/// Write the standup update for `input` with one structured request.
+pub async fn explain(input: &StandupInput, provider: &dyn LlmProvider) -> Result<Standup> {
+    let list = json!({ "type": "array", "items": { "type": "string" } });
+    let schema = Schema::new(
+        "standup",
+        json!({
+            "type": "object",
+            "properties": { "done": list, "in_progress": list, "blockers": list },
+            "required": ["done", "in_progress", "blockers"],
+            "additionalProperties": false,
+        }),
+    );
+    let messages = [Message::system(SYSTEM_PROMPT), Message::user(input.question())];
+    let reply: Reply = structured::request(provider, &messages, &schema).await?;
+    let items = |items: Vec<String>| -> Vec<String> {
+        items.into_iter().map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+    };
+    Ok(Standup {
+        author: input.author.name.clone(),
+        since: input.since,
+        commits: input.commits.len(),
+        done: items(reply.done),
+        in_progress: items(reply.in_progress),
+        blockers: items(reply.blockers),
+    })
+}
+
+/// Format `standup` for output.
+pub fn render(standup: &Standup, format: OutputFormat) -> String {
+    let list = |items: &[String]| -> String {
+        match items.is_empty() {
+            true => format!("- {}\n", tr!("standup-none")),
+            false => items.iter().map(|item| format!("- {}\n", item)).collect(),
+        }
+    };
+    let overview = tr!("standup-overview", count = standup.commits, date = git::format_date(standup.since));
+    let sections = [
+        (tr!("standup-title", author = standup.author.as_str()), overview + "\n"),
+        (tr!("standup-done"), list(&standup.done)),
+        (tr!("standup-in-progress"), list(&standup.in_progress)),
+        (tr!("standup-blockers"), list(&standup.blockers)),
+    ];
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(standup).expect("standup serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
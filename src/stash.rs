@@ -0,0 +1,166 @@
+//! `wtf stash`: what each stash holds, to decide which to pop or drop.
+//!
+//! A stash is a commit whose tree is the work tree as it was stashed, with
+//! HEAD at the time as its first parent and, with `--include-untracked`, a
+//! third parent holding the untracked files. Each stash is diffed against
+//! its first parent, and the model explains all of them in one request.
+
+use crate::analysis::RunEstimate;
+use crate::chunk;
+use crate::commit::{self, FileChange};
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{Oid, Repository};
+use serde::Serialize;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that helps developers keep track of their unfinished work in Git.";
+
+const STASH_PROMPT: &str = "Below are the stashes of a Git repository, newest first, each with the message Git gave it, when it was made and its changes against the commit it was made on. For each stash, in order, write one short paragraph that starts with its name (like stash@{0}) and a colon: what the work in it is about, how complete it looks, and whether it is probably still worth keeping. Stashes that seem to hold the same work should be said to. Keep each paragraph under 80 words.";
+
+// Each stash's patch is cut to this many tokens
+const STASH_PATCH_TOKENS: usize = 1500;
+
+/// One stash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Stash {
+    /// The position in the stash list, as in `stash@{0}`.
+    pub index: usize,
+    pub message: String,
+    #[serde(skip)]
+    pub id: Oid,
+    /// When it was made, seconds since the epoch.
+    pub time: i64,
+    /// The changes to tracked files, staged or not.
+    pub files: Vec<FileChange>,
+    /// Untracked files stashed with `--include-untracked`.
+    pub untracked: Vec<String>,
+    /// The changes as a patch, for the prompt.
+    #[serde(skip)]
+    pub patch: String,
+}
+
+impl Stash {
+    /// The name Git knows it by, like `stash@{0}`.
+    pub fn name(&self) -> String {
+        format!("stash@{{{}}}", self.index)
+    }
+}
+
+/// Every stash of `repo`, newest first.
+pub fn collect(repo: &mut Repository) -> Result<Vec<Stash>> {
+    let mut found = Vec::new();
+    repo.stash_foreach(|index, message, id| {
+        found.push((index, message.to_string(), *id));
+        true
+    })?;
+    let mut stashes = Vec::new();
+    for (index, message, id) in found {
+        let commit = repo.find_commit(id)?;
+        let base = commit.parent(0)?.tree()?;
+        let diff = repo.diff_tree_to_tree(Some(&base), Some(&commit.tree()?), None)?;
+        let untracked = match commit.parent(2) {
+            Ok(parent) => {
+                let mut paths = Vec::new();
+                parent.tree()?.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+                    if entry.kind() == Some(git2::ObjectType::Blob) {
+                        paths.push(format!("{}{}", dir, git::escape_non_utf8(entry.name_bytes())));
+                    }
+                    git2::TreeWalkResult::Ok
+                })?;
+                paths
+            }
+            Err(_) => Vec::new(),
+        };
+        stashes.push(Stash {
+            index,
+            message,
+            id,
+            time: commit.time().seconds(),
+            files: commit::diff_files(&diff)?,
+            untracked,
+            patch: chunk::truncate_to_tokens(&git::diff_patch(&diff)?, STASH_PATCH_TOKENS).0,
+        });
+    }
+    progress!("{}", tr!("progress-stashes", count = stashes.len()));
+    Ok(stashes)
+}
+
+// What the model is told about the stashes
+fn content(stashes: &[Stash]) -> String {
+    let mut out = String::new();
+    for stash in stashes {
+        out.push_str(&format!("Stash: {}\nMessage: {}\n", stash.name(), stash.message));
+        out.push_str(&format!("Date: {}\n", git::format_date(stash.time)));
+        if !stash.untracked.is_empty() {
+            out.push_str(&format!("Untracked files: {}\n", stash.untracked.join(", ")));
+        }
+        out.push_str(&format!("\n{}\n---\n\n", stash.patch));
+    }
+    out
+}
+
+/// Estimate the request explaining `stashes` will use.
+pub fn estimate(stashes: &[Stash]) -> RunEstimate {
+    RunEstimate {
+        requests: 1,
+        prompt_tokens: tokens::estimate(SYSTEM_PROMPT)
+            + tokens::estimate(STASH_PROMPT)
+            + tokens::estimate(&content(stashes)),
+    }
+}
+
+/// The stashes and, if asked for, the model's explanation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StashReport {
+    pub stashes: Vec<Stash>,
+    pub explanation: Option<String>,
+}
+
+/// Ask the model what each of `stashes` holds.
+pub async fn explain(stashes: &[Stash], provider: &dyn LlmProvider) -> Result<String> {
+    let question = format!("{}\n\n{}", STASH_PROMPT, content(stashes));
+    Conversation::new(SYSTEM_PROMPT).ask(provider, question).await
+}
+
+/// Format `report` for output.
+pub fn render(report: &StashReport, format: OutputFormat) -> String {
+    let list: String = report
+        .stashes
+        .iter()
+        .map(|stash| {
+            let entry = tr!(
+                "stash-entry",
+                name = stash.name(),
+                message = stash.message.as_str(),
+                date = git::format_date(stash.time),
+                files = stash.files.len() + stash.untracked.len()
+            );
+            format!("- {}\n", entry)
+        })
+        .collect();
+    let list = if list.is_empty() { tr!("stash-none") + "\n" } else { list };
+    let mut sections = vec![(tr!("stash-title", count = report.stashes.len()), list)];
+    if let Some(explanation) = &report.explanation {
+        sections.push((tr!("stash-explanation"), explanation.trim().to_string() + "\n"));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("stashes serialize to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
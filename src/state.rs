@@ -0,0 +1,325 @@
+//! `wtf status`: where the repository stands, and what to do next.
+//!
+//! On top of what [`workspace::status`] finds out about every repository,
+//! this reads the state Git keeps in the `.git` directory while an
+//! operation is under way: the `*_HEAD` files of a merge, cherry-pick or
+//! revert and the `rebase-merge` and `rebase-apply` directories of a
+//! rebase. The model is told all of it and explains the state in plain
+//! language, with the commands that probably come next.
+
+use crate::analysis::RunEstimate;
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::tr;
+use crate::workspace::{self, RepoStatus};
+use git2::{Repository, RepositoryState, StatusOptions};
+use serde::Serialize;
+use std::path::Path;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains the state of a Git repository to developers who may not know Git well.";
+
+const STATUS_PROMPT: &str = "Below is the current state of a Git repository. Explain in plain language where it stands: whether an operation like a merge or rebase is under way and how far along it is, what any conflicts mean, and how the branch relates to its upstream. Then suggest what the developer probably wants to do next, with the exact git commands, including how to back out of an operation in progress. Keep it under 200 words.";
+
+/// A Git operation that was started and is not finished yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+    /// `git am` applying patches from a mailbox.
+    ApplyMailbox,
+}
+
+impl Operation {
+    fn from_state(state: RepositoryState) -> Option<Operation> {
+        match state {
+            RepositoryState::Clean => None,
+            RepositoryState::Merge => Some(Operation::Merge),
+            RepositoryState::Revert | RepositoryState::RevertSequence => Some(Operation::Revert),
+            RepositoryState::CherryPick | RepositoryState::CherryPickSequence => Some(Operation::CherryPick),
+            RepositoryState::Bisect => Some(Operation::Bisect),
+            RepositoryState::Rebase | RepositoryState::RebaseInteractive | RepositoryState::RebaseMerge => {
+                Some(Operation::Rebase)
+            }
+            RepositoryState::ApplyMailbox | RepositoryState::ApplyMailboxOrRebase => Some(Operation::ApplyMailbox),
+        }
+    }
+
+    /// The git command that started it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Merge => "merge",
+            Operation::Rebase => "rebase",
+            Operation::CherryPick => "cherry-pick",
+            Operation::Revert => "revert",
+            Operation::Bisect => "bisect",
+            Operation::ApplyMailbox => "am",
+        }
+    }
+}
+
+/// How far a rebase (or `git am`) has got.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    /// The branch being rebased, if Git noted it.
+    pub branch: Option<String>,
+    /// What it is being rebased onto, as a short SHA and summary.
+    pub onto: Option<String>,
+    /// The step being worked on, counting from 1.
+    pub step: usize,
+    pub total: usize,
+}
+
+/// Where the repository stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepoState {
+    #[serde(flatten)]
+    pub status: RepoStatus,
+    /// The operation under way, if any.
+    pub operation: Option<Operation>,
+    /// The commit being merged, picked or reverted, as a short SHA and
+    /// summary, or the message Git prepared for the merge.
+    pub incoming: Option<String>,
+    pub progress: Option<Progress>,
+    /// The commit HEAD points at when it is detached, as a short SHA and
+    /// summary.
+    pub detached_at: Option<String>,
+    /// Whether the branch has no commits yet.
+    pub unborn: bool,
+    /// The files with conflicts.
+    pub conflicts: Vec<String>,
+    pub stashes: usize,
+}
+
+/// Read where `repo` stands.
+pub fn collect(repo: &Repository) -> Result<RepoState> {
+    let dir = repo.workdir().unwrap_or(repo.path());
+    let name = dir.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let mut status = workspace::status(repo, name)?;
+    let unborn = repo.head().is_err();
+    if unborn {
+        // HEAD names the branch the first commit will start
+        let head = repo.find_reference("HEAD")?;
+        status.branch = head.symbolic_target().map(|target| target.trim_start_matches("refs/heads/").to_string());
+    }
+    let detached_at = match repo.head_detached().unwrap_or(false) {
+        true => repo.head()?.peel_to_commit().ok().map(|commit| describe(&commit)),
+        false => None,
+    };
+    let operation = Operation::from_state(repo.state());
+    let git_dir = repo.path();
+    let incoming = match operation {
+        Some(Operation::Merge) => std::fs::read_to_string(git_dir.join("MERGE_MSG"))
+            .ok()
+            .and_then(|message| message.lines().next().map(str::to_string))
+            .or_else(|| head_file(repo, "MERGE_HEAD")),
+        Some(Operation::CherryPick) => head_file(repo, "CHERRY_PICK_HEAD"),
+        Some(Operation::Revert) => head_file(repo, "REVERT_HEAD"),
+        _ => None,
+    };
+    let progress = match operation {
+        Some(Operation::Rebase | Operation::ApplyMailbox) => rebase_progress(repo, git_dir),
+        _ => None,
+    };
+
+    let mut conflicts = Vec::new();
+    if !repo.is_bare() {
+        let mut options = StatusOptions::new();
+        options.include_untracked(false);
+        for entry in repo.statuses(Some(&mut options))?.iter() {
+            if entry.status().is_conflicted() {
+                conflicts.push(git::escape_non_utf8(entry.path_bytes()));
+            }
+        }
+    }
+    let stashes = repo.reflog("refs/stash").map_or(0, |reflog| reflog.len());
+
+    Ok(RepoState { status, operation, incoming, progress, detached_at, unborn, conflicts, stashes })
+}
+
+// A commit as its short SHA and summary
+pub(crate) fn describe(commit: &git2::Commit<'_>) -> String {
+    let short_id = commit.as_object().short_id().ok().and_then(|id| id.as_str().map(str::to_string));
+    let summary = git::commit_message(commit).lines().next().unwrap_or_default().to_string();
+    format!("{} {}", short_id.unwrap_or_default(), summary)
+}
+
+// The commit named in a file like MERGE_HEAD, described
+fn head_file(repo: &Repository, file: &str) -> Option<String> {
+    let sha = std::fs::read_to_string(repo.path().join(file)).ok()?;
+    let commit = repo.revparse_single(sha.lines().next()?.trim()).ok()?.peel_to_commit().ok()?;
+    Some(describe(&commit))
+}
+
+// The state files of `git rebase` (rebase-merge) or `git am` and the old
+// rebase backend (rebase-apply)
+fn rebase_progress(repo: &Repository, git_dir: &Path) -> Option<Progress> {
+    let read = |dir: &Path, file: &str| std::fs::read_to_string(dir.join(file)).ok().map(|s| s.trim().to_string());
+    let (dir, step, total) = [("rebase-merge", "msgnum", "end"), ("rebase-apply", "next", "last")]
+        .into_iter()
+        .map(|(dir, step, total)| (git_dir.join(dir), step, total))
+        .find(|(dir, _, _)| dir.is_dir())?;
+    let number = |file: &str| read(&dir, file).and_then(|n| n.parse().ok()).unwrap_or(0);
+    let onto = read(&dir, "onto")
+        .and_then(|sha| repo.revparse_single(&sha).ok()?.peel_to_commit().ok())
+        .map(|commit| describe(&commit));
+    Some(Progress {
+        branch: read(&dir, "head-name").map(|name| name.trim_start_matches("refs/heads/").to_string()),
+        onto,
+        step: number(step),
+        total: number(total),
+    })
+}
+
+impl RepoState {
+    // What the model is told, in the words of `git status`
+    fn content(&self) -> String {
+        let status = &self.status;
+        let mut out = String::new();
+        match (&status.branch, &self.detached_at) {
+            (_, Some(commit)) => out.push_str(&format!("HEAD is detached at {}\n", commit)),
+            (Some(branch), None) if self.unborn => {
+                out.push_str(&format!("On branch {}, which has no commits yet\n", branch))
+            }
+            (Some(branch), None) => out.push_str(&format!("On branch {}\n", branch)),
+            (None, None) => {}
+        }
+        if let Some(operation) = self.operation {
+            out.push_str(&format!("In progress: git {}\n", operation.name()));
+        }
+        if let Some(incoming) = &self.incoming {
+            out.push_str(&format!("Incoming: {}\n", incoming));
+        }
+        if let Some(progress) = &self.progress {
+            let branch = progress.branch.as_deref().unwrap_or("HEAD");
+            let onto = progress.onto.as_deref().unwrap_or("unknown");
+            out.push_str(&format!(
+                "Rebasing {} onto {}, at step {} of {}\n",
+                branch, onto, progress.step, progress.total
+            ));
+        }
+        if !self.conflicts.is_empty() {
+            out.push_str(&format!("Files with conflicts:\n- {}\n", self.conflicts.join("\n- ")));
+        }
+        let changes = &status.changes;
+        out.push_str(&format!(
+            "Uncommitted: {} staged, {} modified, {} untracked\n",
+            changes.staged, changes.modified, changes.untracked
+        ));
+        match &status.upstream {
+            Some(upstream) => {
+                out.push_str(&format!("Upstream: {} ({} ahead, {} behind)\n", upstream, status.ahead, status.behind))
+            }
+            None => out.push_str("Upstream: none\n"),
+        }
+        if !status.unpushed.is_empty() {
+            out.push_str(&format!("Unpushed commits:\n- {}\n", status.unpushed.join("\n- ")));
+        }
+        if self.stashes > 0 {
+            out.push_str(&format!("Stashes: {}\n", self.stashes));
+        }
+        if !status.recent.is_empty() {
+            out.push_str(&format!("Recent commits:\n- {}\n", status.recent.join("\n- ")));
+        }
+        out
+    }
+
+    /// Estimate the request [`explain`] will send.
+    pub fn estimate(&self) -> RunEstimate {
+        RunEstimate {
+            requests: 1,
+            prompt_tokens: tokens::estimate(SYSTEM_PROMPT)
+                + tokens::estimate(STATUS_PROMPT)
+                + tokens::estimate(&self.content()),
+        }
+    }
+
+    // The state in short localized lines, for the report
+    fn facts(&self) -> Vec<String> {
+        let status = &self.status;
+        let mut facts = Vec::new();
+        match (&status.branch, &self.detached_at) {
+            (_, Some(commit)) => facts.push(tr!("status-detached", commit = commit.as_str())),
+            (Some(branch), None) if self.unborn => facts.push(tr!("status-unborn", branch = branch.as_str())),
+            (Some(branch), None) => facts.push(tr!("status-branch", branch = branch.as_str())),
+            (None, None) => {}
+        }
+        if let Some(operation) = self.operation {
+            facts.push(tr!("status-operation", operation = operation.name()));
+        }
+        if let Some(incoming) = &self.incoming {
+            facts.push(tr!("status-incoming", commit = incoming.as_str()));
+        }
+        if let Some(progress) = &self.progress {
+            facts.push(tr!("status-rebase-step", step = progress.step, total = progress.total));
+        }
+        if !self.conflicts.is_empty() {
+            facts.push(tr!("status-conflicts", count = self.conflicts.len(), paths = self.conflicts.join(", ")));
+        }
+        let changes = &status.changes;
+        facts.push(tr!(
+            "status-changes",
+            staged = changes.staged,
+            modified = changes.modified,
+            untracked = changes.untracked
+        ));
+        match &status.upstream {
+            Some(upstream) => facts.push(tr!(
+                "status-upstream",
+                upstream = upstream.as_str(),
+                ahead = status.ahead,
+                behind = status.behind
+            )),
+            None if status.branch.is_some() && !self.unborn => facts.push(tr!("status-no-upstream")),
+            None => {}
+        }
+        if self.stashes > 0 {
+            facts.push(tr!("status-stashes", count = self.stashes));
+        }
+        facts
+    }
+}
+
+/// The repository's state and, if asked for, the model's explanation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatusReport {
+    pub state: RepoState,
+    pub explanation: Option<String>,
+}
+
+/// Ask the model what `state` means and what to do next.
+pub async fn explain(state: &RepoState, provider: &dyn LlmProvider) -> Result<String> {
+    let question = format!("{}\n\n{}", STATUS_PROMPT, state.content());
+    Conversation::new(SYSTEM_PROMPT).ask(provider, question).await
+}
+
+/// Format `report` for output.
+pub fn render(report: &StatusReport, format: OutputFormat) -> String {
+    let facts: String = report.state.facts().iter().map(|fact| format!("- {}\n", fact)).collect();
+    let mut sections = vec![(tr!("status-title"), facts)];
+    if let Some(explanation) = &report.explanation {
+        sections.push((tr!("status-next"), explanation.trim().to_string() + "\n"));
+    }
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("status serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
@@ -0,0 +1,260 @@
+//! Structured (JSON) responses from the model.
+//!
+//! Subcommands that need machine-readable results describe the expected shape
+//! with a JSON schema. Providers that support it enforce the schema natively
+//! (see [`LlmProvider::chat_json`]); either way the reply is parsed and
+//! validated here before anything downstream sees it.
+
+use crate::error::{Result, WtfError};
+use crate::provider::{LlmProvider, Message};
+use crate::{progress, tr};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A named JSON schema describing the expected reply.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    pub name: String,
+    pub schema: Value,
+}
+
+impl Schema {
+    pub fn new(name: impl Into<String>, schema: Value) -> Self {
+        Schema {
+            name: name.into(),
+            schema,
+        }
+    }
+}
+
+/// How often a reply that fails validation is sent back for correction.
+pub const DEFAULT_MAX_REASKS: usize = 2;
+
+static MAX_REASKS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_REASKS);
+
+/// Change how often [`request`] re-asks, for the rest of the run.
+pub fn set_max_reasks(max_reasks: usize) {
+    MAX_REASKS.store(max_reasks, Ordering::Relaxed);
+}
+
+/// How often [`request`] re-asks: [`DEFAULT_MAX_REASKS`] unless
+/// [`set_max_reasks`] said otherwise.
+pub fn max_reasks() -> usize {
+    MAX_REASKS.load(Ordering::Relaxed)
+}
+
+/// Ask for a reply matching `schema` and deserialize it into `T`.
+///
+/// Invalid replies are re-asked up to [`max_reasks`] times.
+pub async fn request<T: DeserializeOwned>(
+    provider: &dyn LlmProvider,
+    messages: &[Message],
+    schema: &Schema,
+) -> Result<T> {
+    request_with_reasks(provider, messages, schema, max_reasks()).await
+}
+
+/// Like [`request`], with an explicit number of re-asks.
+///
+/// When a reply fails to parse or validate, it is sent back together with
+/// the validation errors so the model can correct itself. The last error is
+/// returned once `max_reasks` corrections have failed too.
+pub async fn request_with_reasks<T: DeserializeOwned>(
+    provider: &dyn LlmProvider,
+    messages: &[Message],
+    schema: &Schema,
+    max_reasks: usize,
+) -> Result<T> {
+    let mut conversation = messages.to_vec();
+    let mut attempt = 0;
+    loop {
+        let reply = provider.chat_json(&conversation, schema).await?;
+        match parse_reply(&reply, schema) {
+            Ok(value) => return Ok(value),
+            Err(WtfError::InvalidStructuredResponse { errors }) if attempt < max_reasks => {
+                attempt += 1;
+                progress!(
+                    "{}",
+                    tr!("progress-reask", errors = errors.join("; "), attempt = attempt, max = max_reasks)
+                );
+                conversation.push(Message::assistant(reply));
+                conversation.push(Message::user(correction_prompt(&errors)));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn correction_prompt(errors: &[String]) -> String {
+    format!(
+        "Your previous reply did not match the required JSON schema:\n- {}\nReply again with only the corrected JSON.",
+        errors.join("\n- ")
+    )
+}
+
+/// Parse and validate a raw reply against `schema`.
+pub fn parse_reply<T: DeserializeOwned>(reply: &str, schema: &Schema) -> Result<T> {
+    let value: Value = serde_json::from_str(strip_code_fence(reply)).map_err(|e| {
+        WtfError::InvalidStructuredResponse {
+            errors: vec![format!("reply is not valid JSON: {}", e)],
+        }
+    })?;
+
+    let errors = validate(&value, &schema.schema);
+    if !errors.is_empty() {
+        return Err(WtfError::InvalidStructuredResponse { errors });
+    }
+
+    serde_json::from_value(value).map_err(|e| WtfError::InvalidStructuredResponse {
+        errors: vec![e.to_string()],
+    })
+}
+
+// Models like to wrap JSON in ```json fences even when asked not to
+fn strip_code_fence(reply: &str) -> &str {
+    let trimmed = reply.trim();
+    match trimmed.strip_prefix("```") {
+        Some(rest) => {
+            let rest = rest.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+            rest.strip_suffix("```").unwrap_or(rest).trim()
+        }
+        None => trimmed,
+    }
+}
+
+/// Check `value` against a JSON schema, returning one message per violation.
+///
+/// Supports the subset of JSON Schema we use for prompts: `type` (single or
+/// list), `properties`, `required`, `additionalProperties: false`, `items`,
+/// `enum`, `minItems` and `maxItems`.
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at("$", value, schema, &mut errors);
+    errors
+}
+
+fn validate_at(path: &str, value: &Value, schema: &Value, errors: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            errors.push(format!("{}: expected {}, got {}", path, types.join(" or "), type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            errors.push(format!("{}: {} is not one of {}", path, value, allowed.join(", ")));
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    errors.push(format!("{}: missing required field \"{}\"", path, name));
+                }
+            }
+        }
+
+        for (name, field) in fields {
+            let field_path = format!("{}.{}", path, name);
+            match properties.and_then(|p| p.get(name)) {
+                Some(field_schema) => validate_at(&field_path, field, field_schema, errors),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    errors.push(format!("{}: unexpected field", field_path));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let Value::Array(items) = value {
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                errors.push(format!("{}: expected at least {} items, got {}", path, min, items.len()));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if (items.len() as u64) > max {
+                errors.push(format!("{}: expected at most {} items, got {}", path, max, items.len()));
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                validate_at(&format!("{}[{}]", path, i), item, item_schema, errors);
+            }
+        }
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// The smallest value matching `schema`: empty strings, zeros, the first
+/// `enum` value, and objects with just their required fields. Stands in for
+/// a reply where no model is asked.
+pub fn example(schema: &Value) -> Value {
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if let Some(first) = allowed.first() {
+            return first.clone();
+        }
+    }
+    let kind = match schema.get("type") {
+        Some(Value::String(t)) => t.as_str(),
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).find(|t| *t != "null").unwrap_or("null"),
+        _ => "null",
+    };
+    match kind {
+        "object" => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let required = schema.get("required").and_then(Value::as_array).into_iter().flatten();
+            let fields = required
+                .filter_map(Value::as_str)
+                .map(|name| {
+                    let field = properties.and_then(|p| p.get(name)).map_or(Value::Null, example);
+                    (name.to_string(), field)
+                })
+                .collect();
+            Value::Object(fields)
+        }
+        "array" => {
+            let min = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let item = schema.get("items").map_or(Value::Null, example);
+            Value::Array(vec![item; min])
+        }
+        "string" => Value::String(String::new()),
+        "integer" | "number" => Value::from(0),
+        "boolean" => Value::Bool(false),
+        _ => Value::Null,
+    }
+}
@@ -0,0 +1,147 @@
+//! Map-reduce summaries of long histories.
+//!
+//! A few hundred commits don't fit into one prompt, and a model asked about
+//! that much at once glosses over most of it. Past a batch size the commit
+//! details and patches are instead summarized a batch at a time, each batch
+//! in a request of its own, and the report's sections are written from the
+//! batch summaries in a final synthesis pass.
+//!
+//! Diffs too large to send are handled the same way: split by file and hunk
+//! with [`chunk::chunk_patch`], each part summarized on its own, and the
+//! summaries sent in place of the diff.
+
+use crate::analysis::{self, RunEstimate, Task};
+use crate::chunk;
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::provider::LlmProvider;
+use crate::tokens;
+use crate::{progress, tr};
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that takes notes on parts of a Git history, for a report on the whole history written later.";
+
+const COMMIT_BATCH_PROMPT: &str = "Here is one batch of commits from a longer history, newest first. Summarize in plain language what they changed and why, grouping related commits together. Mention the 7-character short SHA of every commit you describe in square brackets, for example [1a2b3c4], and leave out nothing a reader of the whole history would want to know. Keep it under 300 words.";
+
+const DIFF_PART_PROMPT: &str = "Here is one part of a diff too large to read at once, split by file and hunk. Summarize in plain language what this part changes in the code and how, naming the files. Keep it under 200 words.";
+
+const PATCH_BATCH_PROMPT: &str = "Here are the code changes of one batch of commits from a longer history, each labeled with its commit. Summarize in plain language what was changed in the code and how, grouping related changes together. Mention the 7-character short SHA of the commit behind every change in square brackets, for example [1a2b3c4]. Keep it under 300 words.";
+
+/// Added to the instructions of a section that is written from batch
+/// summaries instead of the data itself.
+pub const SYNTHESIS_INSTRUCTIONS: &str = "The history was too long to send at once, so what follows are your notes on it, one batch at a time, newest batch first. Combine them into one account of the whole history instead of going through the batches one by one, and cite the commits the notes cite.";
+
+/// How many commits are summarized together unless configured otherwise.
+pub const DEFAULT_BATCH_SIZE: usize = 25;
+
+/// Diffs estimated above this many tokens are split and summarized in
+/// parts unless configured otherwise.
+pub const DEFAULT_MAX_DIFF_TOKENS: usize = 6000;
+
+/// How long we expect each batch summary to be, for estimates.
+pub const EXPECTED_SUMMARY_TOKENS: usize = 400;
+
+/// Whether `count` items are summarized in batches of `batch_size` rather
+/// than sent as they are. A batch size of 0 turns batching off.
+pub fn is_needed(count: usize, batch_size: usize) -> bool {
+    batch_size > 0 && count > batch_size
+}
+
+fn batch_prompt(task: Task) -> &'static str {
+    match task {
+        Task::Edits => PATCH_BATCH_PROMPT,
+        _ => COMMIT_BATCH_PROMPT,
+    }
+}
+
+/// Estimate the requests and prompt tokens of summarizing `items` for
+/// `task` in batches of `batch_size`.
+pub fn estimate(task: Task, items: &[String], batch_size: usize) -> RunEstimate {
+    let batches: Vec<&[String]> = items.chunks(batch_size.max(1)).collect();
+    RunEstimate {
+        requests: batches.len(),
+        prompt_tokens: batches
+            .iter()
+            .map(|batch| {
+                tokens::estimate(SYSTEM_PROMPT)
+                    + tokens::estimate(batch_prompt(task))
+                    + tokens::estimate(&batch.join("\n\n---\n\n"))
+            })
+            .sum(),
+    }
+}
+
+/// Summarize `items` (commit details or patches, newest first) for `task`
+/// in batches of `batch_size`, each in a request of its own.
+pub async fn summarize_batches(
+    provider: &dyn LlmProvider,
+    task: Task,
+    items: &[String],
+    batch_size: usize,
+) -> Result<Vec<String>> {
+    let total = items.len().div_ceil(batch_size.max(1));
+    let mut summaries = Vec::new();
+    for (i, batch) in items.chunks(batch_size.max(1)).enumerate() {
+        let key = match task {
+            Task::Edits => "progress-summarizing-edits",
+            _ => "progress-summarizing-commits",
+        };
+        progress!("{}", tr!(key, current = i + 1, total = total));
+        let mut conversation = Conversation::new(SYSTEM_PROMPT);
+        let prompt = batch_prompt(task);
+        let content = analysis::fit_content(&conversation, provider, task, prompt, batch.join("\n\n---\n\n"));
+        summaries.push(conversation.ask(provider, format!("{}\n\n{}", prompt, content)).await?);
+    }
+    Ok(summaries)
+}
+
+/// The batch summaries as the content of the synthesis question.
+pub fn combine(summaries: &[String]) -> String {
+    summaries
+        .iter()
+        .enumerate()
+        .map(|(i, summary)| format!("Batch {} of {}:\n\n{}", i + 1, summaries.len(), summary.trim()))
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+/// Whether `patch` is summarized in parts rather than sent as it is.
+pub fn is_large_diff(patch: &str, max_diff_tokens: usize) -> bool {
+    tokens::estimate(patch) > max_diff_tokens
+}
+
+/// Estimate the requests and prompt tokens of summarizing `patch` in parts
+/// of at most `max_diff_tokens`.
+pub fn estimate_diff(patch: &str, max_diff_tokens: usize) -> RunEstimate {
+    let parts = chunk::chunk_patch(patch, max_diff_tokens);
+    RunEstimate {
+        requests: parts.len(),
+        prompt_tokens: parts
+            .iter()
+            .map(|part| tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(DIFF_PART_PROMPT) + tokens::estimate(part))
+            .sum(),
+    }
+}
+
+/// Split `patch` into parts of at most `max_diff_tokens` by file and hunk,
+/// summarize each in a request of its own, and merge the summaries into
+/// text that stands in for the diff.
+pub async fn summarize_diff(
+    provider: &dyn LlmProvider,
+    task: Task,
+    patch: &str,
+    max_diff_tokens: usize,
+) -> Result<String> {
+    let parts = chunk::chunk_patch(patch, max_diff_tokens);
+    let mut out = format!(
+        "The diff is too large to show, so here are summaries of its {} parts, split by file and hunk.\n",
+        parts.len()
+    );
+    for (i, part) in parts.iter().enumerate() {
+        progress!("{}", tr!("progress-summarizing-diff", current = i + 1, total = parts.len()));
+        let mut conversation = Conversation::new(SYSTEM_PROMPT);
+        let content = analysis::fit_content(&conversation, provider, task, DIFF_PART_PROMPT, part.clone());
+        let summary = conversation.ask(provider, format!("{}\n\n{}", DIFF_PART_PROMPT, content)).await?;
+        out.push_str(&format!("\nPart {} of {}:\n{}\n", i + 1, parts.len(), summary.trim()));
+    }
+    Ok(out)
+}
@@ -0,0 +1,139 @@
+//! Opt-in, anonymous usage telemetry.
+//!
+//! Nothing is collected unless the user runs `wtf telemetry on`. Even then an
+//! event only says which subcommand ran, roughly how long it took and which
+//! class of error (if any) it ended with: no paths, repository names,
+//! commit data, model output or identifiers of any kind. `DO_NOT_TRACK=1`
+//! turns telemetry off regardless of the stored setting.
+
+use crate::error::{exit_code, Result};
+#[cfg(feature = "online")]
+use crate::provider::http::{self, HttpSettings};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where events are sent. Builds without one (the default for source
+/// builds) never send anything; `WTF_TELEMETRY_ENDPOINT` overrides it.
+const BUILD_ENDPOINT: Option<&str> = option_env!("WTF_TELEMETRY_ENDPOINT");
+
+const SETTINGS_FILE: &str = "telemetry.json";
+
+/// The stored telemetry choice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings {
+    pub enabled: bool,
+}
+
+impl Settings {
+    /// Read the settings from `config_dir`; a missing or unreadable file
+    /// means telemetry is off.
+    pub fn load(config_dir: &Path) -> Settings {
+        std::fs::read_to_string(settings_path(config_dir))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the settings to `config_dir`, creating it if needed.
+    pub fn save(&self, config_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(config_dir)?;
+        // Serializing a struct with one bool cannot fail
+        let json = serde_json::to_string_pretty(self).expect("settings serialize to JSON");
+        std::fs::write(settings_path(config_dir), json)?;
+        Ok(())
+    }
+}
+
+fn settings_path(config_dir: &Path) -> PathBuf {
+    config_dir.join(SETTINGS_FILE)
+}
+
+/// Whether the user opted in and has not set `DO_NOT_TRACK`.
+pub fn is_enabled(config_dir: &Path) -> bool {
+    let do_not_track = std::env::var("DO_NOT_TRACK").is_ok_and(|v| !v.is_empty() && v != "0");
+    !do_not_track && Settings::load(config_dir).enabled
+}
+
+/// The endpoint events go to, if this build has one.
+pub fn endpoint() -> Option<String> {
+    if cfg!(not(feature = "online")) {
+        return None;
+    }
+    std::env::var("WTF_TELEMETRY_ENDPOINT")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .or_else(|| BUILD_ENDPOINT.map(str::to_string))
+}
+
+/// Everything one run reports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+    pub version: &'static str,
+    pub os: &'static str,
+    pub command: &'static str,
+    pub duration: &'static str,
+    pub outcome: &'static str,
+}
+
+impl Event {
+    /// Describe a run of `command` that took `elapsed` and exited with `exit_code`.
+    pub fn new(command: &'static str, elapsed: Duration, exit_code: u8) -> Event {
+        Event {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            command,
+            duration: duration_bucket(elapsed),
+            outcome: outcome(exit_code),
+        }
+    }
+}
+
+/// Coarse duration buckets, so timings cannot fingerprint a repository.
+pub fn duration_bucket(elapsed: Duration) -> &'static str {
+    match elapsed.as_secs() {
+        0 => "<1s",
+        1..=9 => "1-10s",
+        10..=59 => "10-60s",
+        60..=299 => "1-5m",
+        _ => ">5m",
+    }
+}
+
+fn outcome(code: u8) -> &'static str {
+    match code {
+        0 => "ok",
+        exit_code::NOT_A_REPO => "not_a_repo",
+        exit_code::AUTH => "auth",
+        exit_code::PROVIDER => "provider",
+        exit_code::BUDGET => "budget",
+        exit_code::LINT => "lint",
+        exit_code::USAGE => "usage",
+        _ => "failure",
+    }
+}
+
+/// Send `event` if telemetry is enabled and the build has an endpoint.
+///
+/// Best effort: failures are ignored and the request gives up after two
+/// seconds, so telemetry can never break or noticeably slow down a run.
+/// It goes through `proxy`, like requests to the provider. Builds without
+/// the `online` feature never send anything.
+#[cfg(feature = "online")]
+pub async fn record(config_dir: &Path, event: &Event, proxy: Option<String>) {
+    if !is_enabled(config_dir) {
+        return;
+    }
+    let Some(endpoint) = endpoint() else { return };
+    let settings = HttpSettings {
+        proxy,
+        connect_timeout: Duration::from_secs(2),
+        request_timeout: Duration::from_secs(2),
+    };
+    if let Ok(client) = http::client(&settings) {
+        let _ = client.post(endpoint).json(event).send().await;
+    }
+}
+
+#[cfg(not(feature = "online"))]
+pub async fn record(_config_dir: &Path, _event: &Event, _proxy: Option<String>) {}
@@ -0,0 +1,373 @@
+//! `wtf timeline <path>`: the life story of one file.
+//!
+//! The file's history is followed back from HEAD across renames, like
+//! `git log --follow`, and split into eras at renames and rewrites. Each era
+//! is summarized on its own before the model tells the whole story, so long
+//! histories never have to fit into a single prompt.
+
+use crate::chunk;
+use crate::citations::{self, CITATION_INSTRUCTIONS};
+use crate::conversation::Conversation;
+use crate::error::{Result, WtfError};
+use crate::git;
+use crate::platform;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{DiffFindOptions, Oid, Repository, Sort, Tree};
+use serde::Serialize;
+use std::path::Path;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains the history of source files in plain language.";
+
+const ERA_PROMPT: &str = "Here is one era in the history of a file: the commits that changed it, oldest first, followed by what they changed. Summarize in plain language what happened to the file during this era, why it probably happened, and who was involved. Keep it under 150 words.";
+
+const NARRATIVE_PROMPT: &str = "Now tell the whole life story of the file as one chronological narrative, based on your era summaries above: when and why it was created, how it grew and changed purpose, its major rewrites and renames, and the people involved. Keep it under 300 words.";
+
+// Patch budget per era; the commit list itself is always sent in full
+const ERA_PATCH_TOKENS: usize = 4000;
+
+// Longer eras are split so each summary covers a manageable stretch
+const MAX_ERA_COMMITS: usize = 20;
+
+// A change that removes at least half of a file of some size is a rewrite
+const REWRITE_SHARE: f64 = 0.5;
+const REWRITE_MIN_LINES: usize = 10;
+
+// How long we expect each summary to be, for estimating follow-up prompts
+const EXPECTED_REPLY_TOKENS: usize = 250;
+
+/// How a commit changed the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    /// Modified, removing at least half of its lines.
+    Rewritten,
+    Renamed { from: String },
+    Deleted,
+}
+
+impl ChangeKind {
+    fn describe(&self) -> String {
+        match self {
+            ChangeKind::Created => "created".to_string(),
+            ChangeKind::Modified => "modified".to_string(),
+            ChangeKind::Rewritten => "rewritten".to_string(),
+            ChangeKind::Renamed { from } => format!("renamed from {}", from),
+            ChangeKind::Deleted => "deleted".to_string(),
+        }
+    }
+}
+
+/// One commit that changed the file.
+#[derive(Debug, Clone)]
+pub struct FileEvent {
+    pub id: Oid,
+    pub short_id: String,
+    /// Commit time, seconds since the epoch.
+    pub time: i64,
+    pub author: String,
+    pub summary: String,
+    /// The file's path after this commit.
+    pub path: String,
+    pub kind: ChangeKind,
+    pub added: usize,
+    pub removed: usize,
+    /// The commit's patch of this file only.
+    pub patch: String,
+}
+
+/// Every commit reachable from HEAD that changed `path`, oldest first,
+/// following the file back across renames.
+///
+/// Merge commits are skipped; the commits they bring in are listed on
+/// their own.
+pub fn file_history(repo: &Repository, path: &str) -> Result<Vec<FileEvent>> {
+    if repo.head().is_err() {
+        return Err(WtfError::NoCommits);
+    }
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    let mut current = path.to_string();
+    let mut events = Vec::new();
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+
+        let new = blob_at(&tree, &current);
+        let old = parent_tree.as_ref().and_then(|t| blob_at(t, &current));
+        let (mut kind, old_path, old) = match (old, new) {
+            (Some(old), Some(new)) if old == new => continue,
+            (Some(old), Some(_)) => (ChangeKind::Modified, current.clone(), Some(old)),
+            (None, Some(_)) => match parent_tree.as_ref().map(|t| renamed_from(repo, t, &tree, &current)) {
+                Some(Some((from, old))) => (ChangeKind::Renamed { from: from.clone() }, from, Some(old)),
+                _ => (ChangeKind::Created, current.clone(), None),
+            },
+            (Some(old), None) => (ChangeKind::Deleted, current.clone(), Some(old)),
+            (None, None) => continue,
+        };
+
+        let old_content = old.map(|id| repo.find_blob(id)).transpose()?;
+        let new_content = new.map(|id| repo.find_blob(id)).transpose()?;
+        let old_bytes = old_content.as_ref().map_or(&[][..], |b| b.content());
+        let new_bytes = new_content.as_ref().map_or(&[][..], |b| b.content());
+        let mut patch = git2::Patch::from_buffers(
+            old_bytes,
+            Some(Path::new(&old_path)),
+            new_bytes,
+            Some(Path::new(&current)),
+            None,
+        )?;
+        let (_, added, removed) = patch.line_stats()?;
+
+        let lines_before = old_bytes.iter().filter(|&&b| b == b'\n').count();
+        if kind == ChangeKind::Modified
+            && lines_before >= REWRITE_MIN_LINES
+            && removed as f64 >= REWRITE_SHARE * lines_before as f64
+        {
+            kind = ChangeKind::Rewritten;
+        }
+
+        events.push(FileEvent {
+            id: commit.id(),
+            short_id: commit.as_object().short_id()?.as_str().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+            author: git::decode_text(commit.author().name_bytes(), commit.message_encoding()),
+            summary: git::commit_message(&commit).lines().next().unwrap_or_default().to_string(),
+            path: current.clone(),
+            kind,
+            added,
+            removed,
+            patch: platform::normalize_newlines(&git::escape_non_utf8(&patch.to_buf()?)),
+        });
+        // Older commits knew the file under its previous name
+        current = old_path;
+    }
+
+    events.reverse();
+    Ok(events)
+}
+
+fn blob_at(tree: &Tree, path: &str) -> Option<Oid> {
+    let entry = tree.get_path(Path::new(path)).ok()?;
+    (entry.kind() == Some(git2::ObjectType::Blob)).then(|| entry.id())
+}
+
+// If `path` appeared in `new` because a file was renamed, that file's old path and blob
+fn renamed_from(repo: &Repository, old: &Tree, new: &Tree, path: &str) -> Option<(String, Oid)> {
+    let mut diff = repo.diff_tree_to_tree(Some(old), Some(new), None).ok()?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true))).ok()?;
+    diff.deltas()
+        .filter(|delta| delta.status() == git2::Delta::Renamed)
+        .find(|delta| delta.new_file().path() == Some(Path::new(path)))
+        .and_then(|delta| {
+            let from = delta.old_file().path()?.to_string_lossy().replace('\\', "/");
+            Some((from, delta.old_file().id()))
+        })
+}
+
+/// A stretch of the file's history between renames and rewrites.
+#[derive(Debug, Clone)]
+pub struct Era {
+    pub events: Vec<FileEvent>,
+}
+
+impl Era {
+    /// Contributors in the order they first appear.
+    pub fn authors(&self) -> Vec<String> {
+        let mut authors: Vec<String> = Vec::new();
+        for event in &self.events {
+            if !authors.contains(&event.author) {
+                authors.push(event.author.clone());
+            }
+        }
+        authors
+    }
+
+    fn content(&self) -> String {
+        let mut content = format!("File: {}\n\n", self.events.last().map_or("", |e| e.path.as_str()));
+        for event in &self.events {
+            content.push_str(&format!(
+                "{} {} {}: {} ({}, +{} -{})\n",
+                git::format_date(event.time),
+                event.short_id,
+                event.author,
+                event.summary,
+                event.kind.describe(),
+                event.added,
+                event.removed
+            ));
+        }
+        let patches: Vec<String> = self
+            .events
+            .iter()
+            .map(|event| format!("Commit: {}\n{}", event.short_id, event.patch))
+            .collect();
+        let (patches, _) = chunk::truncate_to_tokens(&patches.join("\n"), ERA_PATCH_TOKENS);
+        content.push('\n');
+        content.push_str(&patches);
+        content
+    }
+}
+
+/// Split a file's history into eras: a rename or a rewrite starts a new
+/// one, and so does reaching [`MAX_ERA_COMMITS`] commits.
+pub fn eras(events: Vec<FileEvent>) -> Vec<Era> {
+    let mut eras: Vec<Era> = Vec::new();
+    for event in events {
+        let starts_era = matches!(event.kind, ChangeKind::Renamed { .. } | ChangeKind::Rewritten);
+        match eras.last_mut() {
+            Some(era) if !starts_era && era.events.len() < MAX_ERA_COMMITS => era.events.push(event),
+            _ => eras.push(Era { events: vec![event] }),
+        }
+    }
+    eras
+}
+
+/// Everything gathered about the file before any model is called.
+#[derive(Debug, Clone)]
+pub struct TimelineInput {
+    /// The path as given, relative to the repository root.
+    pub path: String,
+    pub eras: Vec<Era>,
+    /// Base URL for linking commits, if known.
+    pub commit_url: Option<String>,
+}
+
+/// Gather the history of `path` (relative to the repository root).
+pub fn prepare(repo: &Repository, path: &str) -> Result<TimelineInput> {
+    let events = file_history(repo, path)?;
+    if events.is_empty() {
+        return Err(WtfError::UnknownPath { path: path.to_string() });
+    }
+    progress!("{}", tr!("progress-timeline", count = events.len()));
+    Ok(TimelineInput {
+        path: path.to_string(),
+        eras: eras(events),
+        commit_url: git::commit_url_base(repo),
+    })
+}
+
+impl TimelineInput {
+    /// Estimate how many requests and prompt tokens the timeline will use.
+    pub fn estimate(&self) -> crate::analysis::RunEstimate {
+        let mut history = tokens::estimate(SYSTEM_PROMPT);
+        let mut prompt_tokens = 0;
+        for era in &self.eras {
+            history += tokens::estimate(ERA_PROMPT) + tokens::estimate(&era.content());
+            prompt_tokens += history;
+            history += EXPECTED_REPLY_TOKENS;
+        }
+        prompt_tokens += history + tokens::estimate(NARRATIVE_PROMPT);
+        crate::analysis::RunEstimate {
+            requests: self.eras.len() + 1,
+            prompt_tokens,
+        }
+    }
+}
+
+/// The summary of one era, as reported.
+#[derive(Serialize, Debug, Clone)]
+pub struct EraSummary {
+    /// The file's path during this era.
+    pub path: String,
+    /// Date of the first and last commit, `YYYY-MM-DD`.
+    pub start: String,
+    pub end: String,
+    pub commits: Vec<String>,
+    pub authors: Vec<String>,
+    pub summary: String,
+}
+
+/// The plain-language history of a file.
+#[derive(Serialize, Debug, Clone)]
+pub struct Timeline {
+    pub path: String,
+    pub narrative: String,
+    pub eras: Vec<EraSummary>,
+    #[serde(skip)]
+    pub commit_url: Option<String>,
+}
+
+/// Summarize each era, then the file's whole story.
+pub async fn run(input: &TimelineInput, provider: &dyn LlmProvider) -> Result<Timeline> {
+    // One conversation, so the narrative can draw on every era summary
+    let mut conversation = Conversation::new(SYSTEM_PROMPT);
+    let mut eras = Vec::new();
+    for (i, era) in input.eras.iter().enumerate() {
+        progress!("{}", tr!("progress-timeline-era", current = i + 1, total = input.eras.len()));
+        let question = format!("{} {}\n\n{}", ERA_PROMPT, CITATION_INSTRUCTIONS, era.content());
+        let summary = conversation.ask(provider, question).await?;
+        let first = &era.events[0];
+        let last = &era.events[era.events.len() - 1];
+        eras.push(EraSummary {
+            path: last.path.clone(),
+            start: git::format_date(first.time),
+            end: git::format_date(last.time),
+            commits: era.events.iter().map(|e| e.short_id.clone()).collect(),
+            authors: era.authors(),
+            summary,
+        });
+    }
+    let narrative = conversation.ask(provider, NARRATIVE_PROMPT).await?;
+
+    Ok(Timeline {
+        path: input.path.clone(),
+        narrative,
+        eras,
+        commit_url: input.commit_url.clone(),
+    })
+}
+
+/// Render `timeline` in the given format.
+pub fn render(timeline: &Timeline, format: OutputFormat) -> String {
+    let title = tr!("timeline-title", path = timeline.path.as_str());
+    let era_heading = |era: &EraSummary| {
+        tr!(
+            "timeline-era",
+            path = era.path.as_str(),
+            start = era.start.as_str(),
+            end = era.end.as_str(),
+            count = era.commits.len(),
+            authors = era.authors.join(", ")
+        )
+    };
+
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(timeline).unwrap_or_default() + "\n",
+        OutputFormat::Markdown => {
+            let link = |text: &str| match &timeline.commit_url {
+                Some(base) => citations::link_citations(text, base),
+                None => text.to_string(),
+            };
+            let mut out = format!("## {}\n\n{}\n", title, link(timeline.narrative.trim()));
+            for era in &timeline.eras {
+                out.push_str(&format!("\n### {}\n\n{}\n", era_heading(era), link(era.summary.trim())));
+            }
+            out
+        }
+        OutputFormat::Terminal => {
+            let mut out = format!("\n=== {} ===\n\n{}\n", title.to_uppercase(), timeline.narrative.trim());
+            for era in &timeline.eras {
+                out.push_str(&format!("\n--- {} ---\n\n{}\n", era_heading(era), era.summary.trim()));
+            }
+            out
+        }
+        OutputFormat::Plain => {
+            let mut sections = vec![(title, timeline.narrative.clone())];
+            sections.extend(timeline.eras.iter().map(|era| (era_heading(era), era.summary.clone())));
+            crate::render::plain_sections(&sections)
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Token estimates for prompt budgeting.
+//!
+//! No model's tokenizer is bundled; the estimates follow the usual rules of
+//! thumb instead, erring towards too many tokens rather than too few.
+
+/// Estimate how many tokens `text` will use.
+///
+/// ASCII text and code take about four characters per token. Other
+/// characters, like CJK, accented letters or emoji, take about one token
+/// each, and often more, so each is counted as one. The estimate never
+/// undercounts by splitting: the estimate of a concatenation is at most the
+/// sum of the parts' estimates.
+pub fn estimate(text: &str) -> usize {
+    let ascii = text.bytes().filter(u8::is_ascii).count();
+    ascii.div_ceil(4) + (text.chars().count() - ascii)
+}
+
+/// The longest prefix of `text` that [`estimate`] puts at `tokens` tokens
+/// or fewer, cut at a character boundary.
+pub fn prefix(text: &str, tokens: usize) -> &str {
+    let (mut ascii, mut other) = (0usize, 0usize);
+    for (idx, c) in text.char_indices() {
+        match c.is_ascii() {
+            true => ascii += 1,
+            false => other += 1,
+        }
+        if ascii.div_ceil(4) + other > tokens {
+            return &text[..idx];
+        }
+    }
+    text
+}
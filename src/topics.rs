@@ -0,0 +1,154 @@
+//! Commits grouped into themes for the report (`--topics`).
+//!
+//! Read in order, the commit section of a report is a list: a fix, a
+//! dependency bump, half of a feature, another fix. With `--topics`, one
+//! structured request first sorts the analyzed commits into a few themes,
+//! like "authentication rework", "CI fixes" or "dependency bumps", from
+//! their messages and changed paths. The commit and edits sections then get
+//! their data under a heading per theme, largest first, and tell each theme
+//! as one story.
+
+use crate::analysis::AnalysisInput;
+use crate::classify;
+use crate::error::Result;
+use crate::provider::{LlmProvider, Message};
+use crate::structured::{self, Schema};
+use crate::tokens;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that finds the threads of work in a project's Git history.";
+
+const TOPICS_PROMPT: &str = "Group the following commits into themes: sets of commits that work toward the same goal or change the same part of the project, like \"authentication rework\", \"CI fixes\" or \"dependency bumps\". Name each theme in two to five words. Use as few themes as fit the commits, usually between two and six; a commit unrelated to all others can be a theme of its own. Put every commit in exactly one theme, using the short SHA given.";
+
+/// Put in front of the commit and edits prompts when their data is grouped
+/// by theme.
+pub const TOPIC_INSTRUCTIONS: &str = "The data is grouped by theme. Write your answer as a narrative organized the same way: for each theme, in the order given, start with the theme's name on a line of its own followed by a colon, then tell what its commits did together as one story rather than commit by commit.";
+
+/// A theme and the commits in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Topic {
+    pub name: String,
+    /// Short SHAs, newest first.
+    pub commits: Vec<String>,
+}
+
+// What the model is told about each commit
+fn content(input: &AnalysisInput) -> String {
+    let mut content = String::new();
+    for (_, score, details, paths) in classify::analyzed(input) {
+        content.push_str(&format!("Short SHA: {}\n", score.short_id));
+        if !paths.is_empty() {
+            content.push_str(&format!("Files: {}\n", paths.join(", ")));
+        }
+        content.push_str(details);
+        content.push_str("\n\n---\n\n");
+    }
+    content
+}
+
+/// Estimated prompt tokens of the request sorting the commits into themes.
+pub fn estimate_tokens(input: &AnalysisInput) -> usize {
+    tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(TOPICS_PROMPT) + tokens::estimate(&content(input))
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    topics: Vec<Named>,
+}
+
+#[derive(Deserialize)]
+struct Named {
+    name: String,
+    shas: Vec<String>,
+}
+
+/// Sort the commits of `input` into themes with one structured request.
+///
+/// Every analyzed commit ends up in exactly one theme: the first the model
+/// put it in, or a catch-all theme if it put it in none. Themes come
+/// largest first, and so do the model's when they are the same size.
+pub async fn cluster(input: &AnalysisInput, provider: &dyn LlmProvider) -> Result<Vec<Topic>> {
+    let messages = [
+        Message::system(SYSTEM_PROMPT),
+        Message::user(format!("{}\n\n{}", TOPICS_PROMPT, content(input))),
+    ];
+    let schema = Schema::new(
+        "commit_topics",
+        json!({
+            "type": "object",
+            "properties": {
+                "topics": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "shas": { "type": "array", "items": { "type": "string" } },
+                        },
+                        "required": ["name", "shas"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["topics"],
+            "additionalProperties": false,
+        }),
+    );
+    let reply: Reply = structured::request(provider, &messages, &schema).await?;
+
+    let mut topics: Vec<Topic> = Vec::new();
+    let mut other = Topic { name: tr!("topic-other"), commits: Vec::new() };
+    for (full_id, score, _, _) in classify::analyzed(input) {
+        let listed =
+            |sha: &String| sha.len() >= 7 && (full_id.starts_with(sha.as_str()) || sha.starts_with(&score.short_id));
+        let named = reply.topics.iter().find(|named| named.shas.iter().any(listed));
+        let short_id = score.short_id.clone();
+        let Some(named) = named.filter(|named| !named.name.trim().is_empty()) else {
+            other.commits.push(short_id);
+            continue;
+        };
+        let name = named.name.trim();
+        match topics.iter_mut().find(|topic| topic.name == name) {
+            Some(topic) => topic.commits.push(short_id),
+            None => topics.push(Topic { name: name.to_string(), commits: vec![short_id] }),
+        }
+    }
+    // Stable, so themes as large as each other keep the model's order
+    topics.sort_by_key(|topic| std::cmp::Reverse(topic.commits.len()));
+    if !other.commits.is_empty() {
+        topics.push(other);
+    }
+    Ok(topics)
+}
+
+/// The commit details and the patches of `input`, arranged under a heading
+/// per theme of `topics`.
+pub fn arrange(input: &AnalysisInput, topics: &[Topic]) -> (String, String) {
+    let analyzed = classify::analyzed(input);
+    let mut commits = Vec::new();
+    let mut edits = Vec::new();
+    for topic in topics {
+        let details: Vec<&str> = analyzed
+            .iter()
+            .filter(|(_, score, _, _)| topic.commits.contains(&score.short_id))
+            .map(|(_, _, details, _)| *details)
+            .collect();
+        commits.push(format!("Theme: {}\n\n{}", topic.name, details.join("\n\n---\n\n")));
+        // The patches are labeled with their commit's short SHA
+        let patches: Vec<&str> = input
+            .file_changes
+            .iter()
+            .filter(|labeled| {
+                let label = labeled.lines().next().unwrap_or_default();
+                label.strip_prefix("Commit: ").is_some_and(|short_id| topic.commits.iter().any(|id| id == short_id))
+            })
+            .map(String::as_str)
+            .collect();
+        if !patches.is_empty() {
+            edits.push(format!("Theme: {}\n\n{}", topic.name, patches.join("\n\n---\n\n")));
+        }
+    }
+    (commits.join("\n\n=====\n\n"), edits.join("\n\n=====\n\n"))
+}
@@ -0,0 +1,444 @@
+//! `wtf tui`: browse recent commits and their explanations interactively.
+//!
+//! The screen is a commit list on the left and a detail pane on the right,
+//! showing either the model's explanation of the selected commit or its
+//! diff. Explanations are requested only when asked for (Enter) and kept for
+//! the rest of the session, and can be recorded in a [`Session`] transcript.
+//!
+//! [`App`] holds all state and reacts to [`Key`]s; [`App::render`] turns it
+//! into screen lines. Neither touches the terminal, so both can be tested
+//! without one.
+
+pub mod picker;
+pub mod terminal;
+
+use crate::analysis;
+use crate::error::Result;
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::session::Session;
+use crate::summarize;
+use crate::tr;
+use git2::{Oid, Repository};
+use std::collections::HashMap;
+use std::path::PathBuf;
+pub use terminal::{Key, Terminal};
+
+/// One row of the commit list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitEntry {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author: String,
+    /// Commit time, seconds since the epoch.
+    pub time: i64,
+}
+
+impl CommitEntry {
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.summary.to_lowercase().contains(&query)
+            || self.author.to_lowercase().contains(&query)
+            || self.id.starts_with(&query)
+    }
+}
+
+/// Up to `limit` commits reachable from HEAD, newest first.
+pub fn load_commits(repo: &Repository, limit: usize) -> Result<Vec<CommitEntry>> {
+    let (commits, _) = git::select_commits(repo, limit)?;
+    commits
+        .iter()
+        .map(|commit| {
+            Ok(CommitEntry {
+                id: commit.id().to_string(),
+                short_id: commit.as_object().short_id()?.as_str().unwrap_or_default().to_string(),
+                summary: git::commit_message(commit).lines().next().unwrap_or_default().to_string(),
+                author: git::decode_text(commit.author().name_bytes(), commit.message_encoding()),
+                time: commit.time().seconds(),
+            })
+        })
+        .collect()
+}
+
+/// Where the detail pane gets its content from.
+pub trait CommitSource {
+    /// The patch of `commit`.
+    fn diff(&mut self, commit: &CommitEntry) -> Result<String>;
+    /// A plain-language explanation of `commit` (this may call a model).
+    fn explain(&mut self, commit: &CommitEntry) -> Result<String>;
+}
+
+/// Diffs from a repository and explanations from a model provider.
+pub struct RepoSource<'a> {
+    repo: &'a Repository,
+    provider: &'a dyn LlmProvider,
+    runtime: tokio::runtime::Handle,
+    /// The transcript explanations are recorded in, and where it is saved.
+    session: Option<(Session, PathBuf)>,
+    max_diff_tokens: usize,
+    exclude: Vec<String>,
+}
+
+impl<'a> RepoSource<'a> {
+    /// `runtime` drives the provider's requests; the calling thread must be
+    /// allowed to block (e.g. inside `tokio::task::block_in_place`).
+    pub fn new(repo: &'a Repository, provider: &'a dyn LlmProvider, runtime: tokio::runtime::Handle) -> Self {
+        RepoSource {
+            repo,
+            provider,
+            runtime,
+            session: None,
+            max_diff_tokens: summarize::DEFAULT_MAX_DIFF_TOKENS,
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Summarize patches above `tokens` in parts before explaining them.
+    pub fn set_max_diff_tokens(&mut self, tokens: usize) {
+        self.max_diff_tokens = tokens;
+    }
+
+    /// Leave files matching any of the `exclude` globs out of explanations.
+    pub fn set_exclude(&mut self, exclude: &[String]) {
+        self.exclude = exclude.to_vec();
+    }
+
+    /// Record every explanation in `session`, saving it to `data_dir` after
+    /// each one.
+    pub fn record_to(&mut self, session: Session, data_dir: PathBuf) {
+        self.session = Some((session, data_dir));
+    }
+
+    /// The transcript recorded so far, if recording.
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref().map(|(session, _)| session)
+    }
+}
+
+impl CommitSource for RepoSource<'_> {
+    fn diff(&mut self, commit: &CommitEntry) -> Result<String> {
+        let commit = self.repo.find_commit(Oid::from_str(&commit.id)?)?;
+        Ok(git::commit_patch(self.repo, &commit)?.unwrap_or_default())
+    }
+
+    fn explain(&mut self, entry: &CommitEntry) -> Result<String> {
+        let commit = self.repo.find_commit(Oid::from_str(&entry.id)?)?;
+        let explanation =
+            analysis::explain_commit(self.repo, &commit, self.provider, self.max_diff_tokens, &self.exclude);
+        let text = self.runtime.block_on(explanation)?;
+        if let Some((session, data_dir)) = &mut self.session {
+            let question = tr!("session-tui-question", id = entry.short_id.as_str(), summary = entry.summary.as_str());
+            session.record(question, text.as_str(), &[&entry.short_id]);
+            // Losing the transcript is no reason to interrupt browsing
+            let _ = session.save(data_dir);
+        }
+        Ok(text)
+    }
+}
+
+/// What the detail pane shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailView {
+    Explanation,
+    Diff,
+}
+
+/// What the event loop should do after a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    /// Redraw with a "working" status, then call [`App::explain_selected`].
+    Explain,
+    Quit,
+}
+
+/// The state of the commit browser.
+pub struct App {
+    commits: Vec<CommitEntry>,
+    /// Indices into `commits` matching the search, in list order.
+    visible: Vec<usize>,
+    selected: usize,
+    list_offset: usize,
+    detail_offset: usize,
+    view: DetailView,
+    searching: bool,
+    query: String,
+    status: Option<String>,
+    explanations: HashMap<String, String>,
+    diffs: HashMap<String, String>,
+}
+
+impl App {
+    pub fn new(commits: Vec<CommitEntry>) -> App {
+        let visible = (0..commits.len()).collect();
+        App {
+            commits,
+            visible,
+            selected: 0,
+            list_offset: 0,
+            detail_offset: 0,
+            view: DetailView::Explanation,
+            searching: false,
+            query: String::new(),
+            status: None,
+            explanations: HashMap::new(),
+            diffs: HashMap::new(),
+        }
+    }
+
+    /// The commit under the cursor, if the (filtered) list is not empty.
+    pub fn selected(&self) -> Option<&CommitEntry> {
+        self.visible.get(self.selected).map(|&i| &self.commits[i])
+    }
+
+    /// The commits currently listed, after filtering.
+    pub fn visible(&self) -> Vec<&CommitEntry> {
+        self.visible.iter().map(|&i| &self.commits[i]).collect()
+    }
+
+    pub fn view(&self) -> DetailView {
+        self.view
+    }
+
+    /// Whether an explanation of `commit` is already cached.
+    pub fn is_explained(&self, commit: &CommitEntry) -> bool {
+        self.explanations.contains_key(&commit.id)
+    }
+
+    /// Show `status` in the bottom line until the next key press.
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    /// React to a key press. `page` is the height of the panes.
+    pub fn handle_key(&mut self, key: Key, page: usize) -> Action {
+        self.status = None;
+        if key == Key::CtrlC {
+            return Action::Quit;
+        }
+        if self.searching {
+            match key {
+                Key::Enter => self.searching = false,
+                Key::Esc => {
+                    self.searching = false;
+                    self.query.clear();
+                    self.filter();
+                }
+                Key::Backspace => {
+                    self.query.pop();
+                    self.filter();
+                }
+                Key::Char(c) => {
+                    self.query.push(c);
+                    self.filter();
+                }
+                _ => {}
+            }
+            return Action::Continue;
+        }
+
+        match key {
+            Key::Char('q') | Key::Esc => return Action::Quit,
+            Key::Char('/') => self.searching = true,
+            Key::Up | Key::Char('k') => self.select(self.selected.saturating_sub(1)),
+            Key::Down | Key::Char('j') => self.select(self.selected + 1),
+            Key::Home | Key::Char('g') => self.select(0),
+            Key::End | Key::Char('G') => self.select(self.visible.len().saturating_sub(1)),
+            Key::PageDown | Key::Char(' ') => self.detail_offset += page.max(1),
+            Key::PageUp => self.detail_offset = self.detail_offset.saturating_sub(page.max(1)),
+            Key::Tab | Key::Char('d') => {
+                self.view = match self.view {
+                    DetailView::Explanation => DetailView::Diff,
+                    DetailView::Diff => DetailView::Explanation,
+                };
+                self.detail_offset = 0;
+            }
+            Key::Enter | Key::Char('e') => {
+                if let Some(commit) = self.selected() {
+                    if !self.is_explained(commit) {
+                        return Action::Explain;
+                    }
+                }
+                self.view = DetailView::Explanation;
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    /// Fetch (and cache) the explanation of the selected commit.
+    pub fn explain_selected(&mut self, source: &mut dyn CommitSource) {
+        let Some(commit) = self.selected().cloned() else { return };
+        match source.explain(&commit) {
+            Ok(text) => {
+                self.status = None;
+                self.explanations.insert(commit.id, text);
+                self.view = DetailView::Explanation;
+                self.detail_offset = 0;
+            }
+            Err(e) => self.set_status(format!("{}: {}", tr!("error-label"), e)),
+        }
+    }
+
+    /// Make sure the diff of the selected commit is loaded if it is shown.
+    pub fn load_diff(&mut self, source: &mut dyn CommitSource) {
+        if self.view != DetailView::Diff {
+            return;
+        }
+        let Some(commit) = self.selected().cloned() else { return };
+        if self.diffs.contains_key(&commit.id) {
+            return;
+        }
+        let diff = source.diff(&commit).unwrap_or_else(|e| format!("{}: {}", tr!("error-label"), e));
+        self.diffs.insert(commit.id, diff);
+    }
+
+    fn select(&mut self, index: usize) {
+        let index = index.min(self.visible.len().saturating_sub(1));
+        if index != self.selected {
+            self.selected = index;
+            self.detail_offset = 0;
+        }
+    }
+
+    fn filter(&mut self) {
+        self.visible = (0..self.commits.len())
+            .filter(|&i| self.query.is_empty() || self.commits[i].matches(&self.query))
+            .collect();
+        self.selected = 0;
+        self.list_offset = 0;
+        self.detail_offset = 0;
+    }
+
+    /// Draw the screen as `height` lines of at most `width` characters.
+    pub fn render(&mut self, width: usize, height: usize) -> Vec<String> {
+        let body = height.saturating_sub(2).max(1);
+        let list_width = (width * 2 / 5).clamp(20, 60).min(width.saturating_sub(10));
+        let detail_width = width.saturating_sub(list_width + 3).max(1);
+
+        // Keep the selection within the visible part of the list
+        if self.selected < self.list_offset {
+            self.list_offset = self.selected;
+        } else if self.selected >= self.list_offset + body {
+            self.list_offset = self.selected + 1 - body;
+        }
+
+        let list: Vec<String> = self
+            .visible
+            .iter()
+            .enumerate()
+            .skip(self.list_offset)
+            .take(body)
+            .map(|(row, &i)| {
+                let commit = &self.commits[i];
+                let marker = if row == self.selected { ">" } else { " " };
+                let line = fit(&format!("{} {} {}", marker, commit.short_id, commit.summary), list_width);
+                if row == self.selected {
+                    format!("\x1b[7m{}\x1b[0m", line)
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let detail = self.detail_lines(detail_width);
+        let max_offset = detail.len().saturating_sub(body);
+        self.detail_offset = self.detail_offset.min(max_offset);
+
+        let mut lines = vec![fit(&tr!("tui-title", count = self.visible.len()), width)];
+        for row in 0..body {
+            let left = list.get(row).cloned().unwrap_or_else(|| " ".repeat(list_width));
+            let right = detail.get(self.detail_offset + row).map(String::as_str).unwrap_or("");
+            lines.push(format!("{} | {}", left, fit(right, detail_width).trim_end()));
+        }
+        let footer = match (&self.status, self.searching) {
+            (Some(status), _) => status.clone(),
+            (None, true) => format!("/{}", self.query),
+            (None, false) => tr!("tui-help"),
+        };
+        lines.push(fit(&footer, width));
+        lines
+    }
+
+    fn detail_lines(&self, width: usize) -> Vec<String> {
+        let Some(commit) = self.selected() else {
+            return vec![tr!("tui-no-commits")];
+        };
+        let mut lines = vec![
+            format!("{} {}", tr!("tui-commit"), commit.id),
+            format!("{} {}", tr!("tui-author"), commit.author),
+            String::new(),
+        ];
+        lines.extend(wrap(&commit.summary, width));
+        lines.push(String::new());
+
+        match self.view {
+            DetailView::Explanation => match self.explanations.get(&commit.id) {
+                Some(text) => lines.extend(wrap(text, width)),
+                None => lines.push(tr!("tui-press-enter")),
+            },
+            DetailView::Diff => match self.diffs.get(&commit.id) {
+                Some(diff) => lines.extend(diff.lines().map(|l| l.replace('\t', "    "))),
+                None => lines.push(tr!("tui-no-diff")),
+            },
+        }
+        lines
+    }
+}
+
+/// Pad or cut `text` to exactly `width` characters.
+pub fn fit(text: &str, width: usize) -> String {
+    let mut out: String = text.chars().take(width).collect();
+    let len = out.chars().count();
+    out.extend(std::iter::repeat_n(' ', width - len));
+    out
+}
+
+/// Wrap `text` at word boundaries into lines of at most `width` characters.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let needed = if line.is_empty() { 0 } else { 1 } + word.chars().count();
+            if !line.is_empty() && line.chars().count() + needed > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+            // Words longer than a line are split
+            while line.chars().count() > width {
+                let head: String = line.chars().take(width).collect();
+                line = line.chars().skip(width).collect();
+                lines.push(head);
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Run the commit browser until the user quits.
+pub fn run(terminal: &mut Terminal, app: &mut App, source: &mut dyn CommitSource) -> Result<()> {
+    loop {
+        let (width, height) = terminal.size();
+        app.load_diff(source);
+        terminal.draw(&app.render(width, height))?;
+
+        for key in terminal.read_keys()? {
+            match app.handle_key(key, height.saturating_sub(2)) {
+                Action::Continue => {}
+                Action::Quit => return Ok(()),
+                Action::Explain => {
+                    app.set_status(tr!("tui-explaining"));
+                    terminal.draw(&app.render(width, height))?;
+                    app.explain_selected(source);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! `wtf pick`: a fuzzy-searchable, multi-select commit list.
+//!
+//! Typing filters the list (the letters of the query have to appear in
+//! order, not necessarily next to each other), Tab marks commits, and Enter
+//! confirms the marked set, or just the commit under the cursor when nothing
+//! is marked.
+
+use super::{fit, CommitEntry, Key, Terminal};
+use crate::error::Result;
+use crate::tr;
+use std::collections::BTreeSet;
+
+/// How well `query` matches `text`, or `None` if it does not match at all.
+///
+/// Higher is better. Letters matched right after the previous one or at the
+/// start of a word score extra, so "fixlog" prefers "Fix login" over
+/// "Fix a typo in the changelog".
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..text.len()).find(|&i| text[i] == wanted)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+/// What the event loop should do after a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickAction {
+    Continue,
+    /// The user confirmed; see [`Picker::chosen`].
+    Done,
+    Cancel,
+}
+
+/// The state of the commit picker.
+pub struct Picker {
+    commits: Vec<CommitEntry>,
+    query: String,
+    /// Indices into `commits` matching the query, best match first.
+    visible: Vec<usize>,
+    cursor: usize,
+    offset: usize,
+    marked: BTreeSet<usize>,
+}
+
+impl Picker {
+    pub fn new(commits: Vec<CommitEntry>) -> Picker {
+        let visible = (0..commits.len()).collect();
+        Picker {
+            commits,
+            query: String::new(),
+            visible,
+            cursor: 0,
+            offset: 0,
+            marked: BTreeSet::new(),
+        }
+    }
+
+    /// The commits matching the query, best match first.
+    pub fn visible(&self) -> Vec<&CommitEntry> {
+        self.visible.iter().map(|&i| &self.commits[i]).collect()
+    }
+
+    /// The commit under the cursor, if any matches the query.
+    pub fn current(&self) -> Option<&CommitEntry> {
+        self.visible.get(self.cursor).map(|&i| &self.commits[i])
+    }
+
+    /// The marked commits in their original (log) order, or the one under
+    /// the cursor when nothing is marked.
+    pub fn chosen(&self) -> Vec<CommitEntry> {
+        if self.marked.is_empty() {
+            return self.current().cloned().into_iter().collect();
+        }
+        self.marked.iter().map(|&i| self.commits[i].clone()).collect()
+    }
+
+    /// React to a key press.
+    pub fn handle_key(&mut self, key: Key) -> PickAction {
+        match key {
+            Key::CtrlC | Key::Esc => return PickAction::Cancel,
+            Key::Enter if !self.chosen().is_empty() => return PickAction::Done,
+            Key::Up => self.cursor = self.cursor.saturating_sub(1),
+            Key::Down => self.cursor = (self.cursor + 1).min(self.visible.len().saturating_sub(1)),
+            Key::Tab => {
+                if let Some(&i) = self.visible.get(self.cursor) {
+                    if !self.marked.remove(&i) {
+                        self.marked.insert(i);
+                    }
+                    self.cursor = (self.cursor + 1).min(self.visible.len().saturating_sub(1));
+                }
+            }
+            Key::Backspace => {
+                self.query.pop();
+                self.filter();
+            }
+            Key::Char(c) => {
+                self.query.push(c);
+                self.filter();
+            }
+            _ => {}
+        }
+        PickAction::Continue
+    }
+
+    fn filter(&mut self) {
+        let mut scored: Vec<(u32, usize)> = (0..self.commits.len())
+            .filter_map(|i| {
+                let commit = &self.commits[i];
+                let text = format!("{} {} {}", commit.short_id, commit.summary, commit.author);
+                fuzzy_score(&self.query, &text).map(|score| (score, i))
+            })
+            .collect();
+        // Stable, so equally good matches stay in log order
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.visible = scored.into_iter().map(|(_, i)| i).collect();
+        self.cursor = 0;
+        self.offset = 0;
+    }
+
+    /// Draw the screen as `height` lines of at most `width` characters.
+    pub fn render(&mut self, width: usize, height: usize) -> Vec<String> {
+        let body = height.saturating_sub(2).max(1);
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + body {
+            self.offset = self.cursor + 1 - body;
+        }
+
+        let mut lines = vec![fit(&format!("> {}", self.query), width)];
+        for row in 0..body {
+            let Some(&i) = self.visible.get(self.offset + row) else {
+                lines.push(String::new());
+                continue;
+            };
+            let commit = &self.commits[i];
+            let mark = if self.marked.contains(&i) { "[x]" } else { "[ ]" };
+            let line = fit(
+                &format!("{} {} {} ({})", mark, commit.short_id, commit.summary, commit.author),
+                width,
+            );
+            if self.offset + row == self.cursor {
+                lines.push(format!("\x1b[7m{}\x1b[0m", line));
+            } else {
+                lines.push(line.trim_end().to_string());
+            }
+        }
+        lines.push(fit(
+            &tr!("pick-help", marked = self.marked.len(), count = self.visible.len()),
+            width,
+        ));
+        lines
+    }
+}
+
+/// Run the picker until the user confirms (returning the chosen commits) or
+/// cancels (returning `None`).
+pub fn pick(terminal: &mut Terminal, picker: &mut Picker) -> Result<Option<Vec<CommitEntry>>> {
+    loop {
+        let (width, height) = terminal.size();
+        terminal.draw(&picker.render(width, height))?;
+        for key in terminal.read_keys()? {
+            match picker.handle_key(key) {
+                PickAction::Continue => {}
+                PickAction::Done => return Ok(Some(picker.chosen())),
+                PickAction::Cancel => return Ok(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Raw terminal access: raw mode, the alternate screen, key input and size.
+//!
+//! Only what the interactive views need, written against termios (Unix) and
+//! the console API (Windows) directly.
+
+use crate::error::Result;
+use std::io::{self, Read, Write};
+use std::sync::{Mutex, Once};
+
+// The mode to go back to while a Terminal is active, shared with the panic
+// hook so a panic message isn't printed to (and wiped with) the alternate screen
+static SAVED: Mutex<Option<sys::SavedMode>> = Mutex::new(None);
+
+static PANIC_HOOK: Once = Once::new();
+
+/// A key press, decoded from terminal input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Esc,
+    Backspace,
+    Tab,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    CtrlC,
+}
+
+/// Decode the bytes of one read from a terminal in raw mode.
+///
+/// A lone ESC is the Escape key; ESC followed by `[` or `O` starts a CSI or
+/// SS3 sequence. Unknown sequences are dropped.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        i += 1;
+        match byte {
+            0x1b if matches!(bytes.get(i), Some(b'[') | Some(b'O')) => {
+                // Parameters are digits and ';', the final byte is a letter or '~'
+                let start = i + 1;
+                let mut end = start;
+                while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b';') {
+                    end += 1;
+                }
+                let Some(&last) = bytes.get(end) else {
+                    i = bytes.len();
+                    continue;
+                };
+                let key = match (&bytes[start..end], last) {
+                    (_, b'A') => Some(Key::Up),
+                    (_, b'B') => Some(Key::Down),
+                    (_, b'C') => Some(Key::Right),
+                    (_, b'D') => Some(Key::Left),
+                    (_, b'H') | (b"1", b'~') | (b"7", b'~') => Some(Key::Home),
+                    (_, b'F') | (b"4", b'~') | (b"8", b'~') => Some(Key::End),
+                    (b"5", b'~') => Some(Key::PageUp),
+                    (b"6", b'~') => Some(Key::PageDown),
+                    _ => None,
+                };
+                keys.extend(key);
+                i = end + 1;
+            }
+            0x1b => keys.push(Key::Esc),
+            b'\r' | b'\n' => keys.push(Key::Enter),
+            b'\t' => keys.push(Key::Tab),
+            0x7f | 0x08 => keys.push(Key::Backspace),
+            0x03 => keys.push(Key::CtrlC),
+            _ if byte < 0x20 => {}
+            _ => {
+                // Decode one UTF-8 character starting at this byte
+                let len = match byte {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1,
+                };
+                let end = (i - 1 + len).min(bytes.len());
+                if let Ok(text) = std::str::from_utf8(&bytes[i - 1..end]) {
+                    keys.extend(text.chars().map(Key::Char));
+                }
+                i = end;
+            }
+        }
+    }
+    keys
+}
+
+/// The terminal in raw mode on the alternate screen; restored on drop, and
+/// before a panic message is printed.
+pub struct Terminal {
+    out: io::Stdout,
+}
+
+impl Terminal {
+    /// Switch to raw mode and the alternate screen.
+    pub fn enter() -> Result<Terminal> {
+        PANIC_HOOK.call_once(|| {
+            let previous = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                restore();
+                previous(info);
+            }));
+        });
+        let saved = sys::enable_raw_mode()?;
+        *SAVED.lock().unwrap_or_else(|e| e.into_inner()) = Some(saved);
+        let mut out = io::stdout();
+        // Alternate screen, hidden cursor
+        write!(out, "\x1b[?1049h\x1b[?25l")?;
+        out.flush()?;
+        Ok(Terminal { out })
+    }
+
+    /// Columns and rows, falling back to 80x24 when the size is unknown.
+    pub fn size(&self) -> (usize, usize) {
+        sys::size().unwrap_or((80, 24))
+    }
+
+    /// Replace the screen contents with `lines`.
+    pub fn draw(&mut self, lines: &[String]) -> Result<()> {
+        let mut frame = String::from("\x1b[H");
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                frame.push_str("\r\n");
+            }
+            frame.push_str(line);
+            frame.push_str("\x1b[K");
+        }
+        frame.push_str("\x1b[J");
+        self.out.write_all(frame.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+
+    /// Block until at least one key is pressed.
+    pub fn read_keys(&mut self) -> Result<Vec<Key>> {
+        let mut buf = [0u8; 64];
+        loop {
+            let n = io::stdin().read(&mut buf)?;
+            if n == 0 {
+                // End of input; treat like Ctrl-C so callers exit
+                return Ok(vec![Key::CtrlC]);
+            }
+            let keys = parse_keys(&buf[..n]);
+            if !keys.is_empty() {
+                return Ok(keys);
+            }
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+// Show the cursor, leave the alternate screen and restore the saved mode,
+// once: after a panic the hook has done it before the Terminal is dropped
+fn restore() {
+    let Some(saved) = SAVED.lock().unwrap_or_else(|e| e.into_inner()).take() else { return };
+    let mut out = io::stdout();
+    let _ = write!(out, "\x1b[?25h\x1b[?1049l");
+    let _ = out.flush();
+    sys::restore(saved);
+}
+
+#[cfg(unix)]
+mod sys {
+    use crate::error::Result;
+
+    pub struct SavedMode(libc::termios);
+
+    pub fn enable_raw_mode() -> Result<SavedMode> {
+        // SAFETY: termios calls on stdin with properly initialized structs
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            // Keep output post-processing off but let reads return per key
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(SavedMode(original))
+        }
+    }
+
+    pub fn restore(saved: SavedMode) {
+        // SAFETY: restores the attributes read in `enable_raw_mode`
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &saved.0);
+        }
+    }
+
+    pub fn size() -> Option<(usize, usize)> {
+        // SAFETY: TIOCGWINSZ fills in a winsize struct
+        unsafe {
+            let mut size: libc::winsize = std::mem::zeroed();
+            if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) != 0 || size.ws_col == 0 {
+                return None;
+            }
+            Some((size.ws_col as usize, size.ws_row as usize))
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use crate::error::Result;
+    use std::ffi::c_void;
+
+    const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_PROCESSED_INPUT: u32 = 0x0001;
+    const ENABLE_LINE_INPUT: u32 = 0x0002;
+    const ENABLE_ECHO_INPUT: u32 = 0x0004;
+    const ENABLE_VIRTUAL_TERMINAL_INPUT: u32 = 0x0200;
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct Coord {
+        x: i16,
+        y: i16,
+    }
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct SmallRect {
+        left: i16,
+        top: i16,
+        right: i16,
+        bottom: i16,
+    }
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct ScreenBufferInfo {
+        size: Coord,
+        cursor: Coord,
+        attributes: u16,
+        window: SmallRect,
+        max_size: Coord,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+        fn GetConsoleScreenBufferInfo(console: *mut c_void, info: *mut ScreenBufferInfo) -> i32;
+    }
+
+    pub struct SavedMode(u32);
+
+    pub fn enable_raw_mode() -> Result<SavedMode> {
+        // SAFETY: plain console API calls on the process's stdin handle
+        unsafe {
+            let input = GetStdHandle(STD_INPUT_HANDLE);
+            let mut mode = 0;
+            if GetConsoleMode(input, &mut mode) == 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let raw = (mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_PROCESSED_INPUT))
+                | ENABLE_VIRTUAL_TERMINAL_INPUT;
+            if SetConsoleMode(input, raw) == 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(SavedMode(mode))
+        }
+    }
+
+    pub fn restore(saved: SavedMode) {
+        // SAFETY: restores the mode read in `enable_raw_mode`
+        unsafe {
+            SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), saved.0);
+        }
+    }
+
+    pub fn size() -> Option<(usize, usize)> {
+        // SAFETY: the struct is plain data filled in by the call
+        unsafe {
+            let mut info: ScreenBufferInfo = std::mem::zeroed();
+            if GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) == 0 {
+                return None;
+            }
+            let cols = (info.window.right - info.window.left + 1) as usize;
+            let rows = (info.window.bottom - info.window.top + 1) as usize;
+            Some((cols, rows))
+        }
+    }
+}
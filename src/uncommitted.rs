@@ -0,0 +1,143 @@
+//! `wtf diff`: the changes that are not committed yet.
+//!
+//! With `--staged` this is the index against HEAD, what `git commit` would
+//! commit; with `--unstaged` the work tree against the index. Without
+//! either it is everything in the work tree that differs from HEAD. The
+//! patch is filtered like a commit's: generated, vendored, excluded and
+//! binary files are only counted, and a large patch is summarized in parts
+//! before the model explains it.
+
+use crate::analysis::{RunEstimate, Task};
+use crate::commit::{self, FileChange};
+use crate::conversation::Conversation;
+use crate::error::{Result, WtfError};
+use crate::generated::{self, ChurnFile, Detector};
+use crate::git::{self, Uncommitted};
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::summarize;
+use crate::tokens;
+use crate::tr;
+use git2::Repository;
+use serde::Serialize;
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that explains code changes in plain language, before they are committed.";
+
+const STAGED_PROMPT: &str = "Here are the staged changes in a Git repository, the ones the next commit will contain. Explain in plain language what committing them will change, and why it was probably done. Point out anything that looks unfinished or accidental, like debugging output, commented-out code or unrelated edits mixed in. Keep it under 200 words.";
+
+const UNSTAGED_PROMPT: &str = "Here are the changes in a Git repository's work tree that are not staged yet. Explain in plain language what they change, and why they were probably made. Point out anything that looks unfinished or accidental, like debugging output, commented-out code or unrelated edits mixed in. Keep it under 200 words.";
+
+const ALL_PROMPT: &str = "Here are all uncommitted changes in a Git repository, staged or not. Explain in plain language what committing them will change, and why it was probably done. Point out anything that looks unfinished or accidental, like debugging output, commented-out code or unrelated edits mixed in. Keep it under 200 words.";
+
+/// The uncommitted changes, gathered before any model is called.
+#[derive(Debug, Clone)]
+pub struct UncommittedInput {
+    pub which: Uncommitted,
+    pub files: Vec<FileChange>,
+    /// The patch without generated and excluded files.
+    pub patch: String,
+    pub churn: Vec<ChurnFile>,
+}
+
+/// Gather the changes `which` selects, leaving out files matching
+/// `exclude` like [`analysis::explain_commit`](crate::analysis::explain_commit)
+/// does.
+pub fn prepare(repo: &Repository, which: Uncommitted, exclude: &[String]) -> Result<UncommittedInput> {
+    let diff = git::uncommitted_diff(repo, which)?;
+    let files = commit::diff_files(&diff)?;
+    if files.is_empty() {
+        return Err(WtfError::NoChanges { staged: which == Uncommitted::Staged });
+    }
+    let churn = generated::diff_churn(&diff, &mut Detector::with_exclude(repo, exclude))?;
+    let patch = generated::strip_patch(&git::diff_patch(&diff)?, &churn);
+    Ok(UncommittedInput { which, files, patch, churn })
+}
+
+impl UncommittedInput {
+    fn prompt(&self) -> &'static str {
+        match self.which {
+            Uncommitted::Staged => STAGED_PROMPT,
+            Uncommitted::Unstaged => UNSTAGED_PROMPT,
+            Uncommitted::All => ALL_PROMPT,
+        }
+    }
+
+    /// Estimate the requests [`explain`] will send.
+    pub fn estimate(&self, max_diff_tokens: usize) -> RunEstimate {
+        let mut estimate = RunEstimate {
+            requests: 1,
+            prompt_tokens: tokens::estimate(SYSTEM_PROMPT) + tokens::estimate(self.prompt()),
+        };
+        if summarize::is_large_diff(&self.patch, max_diff_tokens) {
+            let parts = summarize::estimate_diff(&self.patch, max_diff_tokens);
+            estimate.requests += parts.requests;
+            estimate.prompt_tokens += parts.prompt_tokens + parts.requests * summarize::EXPECTED_SUMMARY_TOKENS;
+        } else {
+            estimate.prompt_tokens += tokens::estimate(&self.patch);
+        }
+        estimate
+    }
+}
+
+/// Uncommitted changes and what the model made of them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UncommittedReport {
+    pub which: Uncommitted,
+    pub files: Vec<FileChange>,
+    pub explanation: String,
+}
+
+/// Explain the changes in `input`. A patch above `max_diff_tokens` is
+/// summarized in parts first.
+pub async fn explain(
+    input: &UncommittedInput,
+    provider: &dyn LlmProvider,
+    max_diff_tokens: usize,
+) -> Result<UncommittedReport> {
+    let mut patch = input.patch.clone();
+    if summarize::is_large_diff(&patch, max_diff_tokens) {
+        patch = summarize::summarize_diff(provider, Task::Edits, &patch, max_diff_tokens).await?;
+    }
+    if !input.churn.is_empty() {
+        patch.push('\n');
+        patch.push_str(&generated::prompt_summary(&input.churn));
+    }
+    let explanation = Conversation::new(SYSTEM_PROMPT)
+        .ask(provider, format!("{}\n\n{}", input.prompt(), patch))
+        .await?;
+    Ok(UncommittedReport { which: input.which, files: input.files.clone(), explanation })
+}
+
+/// Format `report` for output.
+pub fn render(report: &UncommittedReport, format: OutputFormat) -> String {
+    let title = match report.which {
+        Uncommitted::Staged => tr!("diff-title-staged"),
+        Uncommitted::Unstaged => tr!("diff-title-unstaged"),
+        Uncommitted::All => tr!("diff-title-all"),
+    };
+    let files: String = report
+        .files
+        .iter()
+        .map(|f| tr!("commit-file-entry", path = f.path.as_str(), added = f.added, removed = f.removed))
+        .map(|entry| format!("- {}\n", entry))
+        .collect();
+    let sections = [
+        (tr!("commit-files", count = report.files.len()), files),
+        (title, report.explanation.trim().to_string() + "\n"),
+    ];
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(report).expect("diff report serializes to JSON") + "\n",
+        OutputFormat::Markdown => sections
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}", title, body))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Terminal => sections
+            .iter()
+            .map(|(title, body)| format!("\n=== {} ===\n\n{}", title.to_uppercase(), body))
+            .collect(),
+        OutputFormat::Plain => crate::render::plain_sections(&sections),
+    }
+}
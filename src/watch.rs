@@ -0,0 +1,69 @@
+//! `wtf watch`: notice commits as they land, so they can be explained.
+//!
+//! Watching works by polling the local branch tips: each poll compares them
+//! with the previous snapshot and returns the commits that became reachable
+//! in between. Polling needs no file system notification support, works on
+//! network drives and also sees refs rewritten by `git pack-refs`.
+
+use crate::error::Result;
+use git2::{BranchType, Oid, Repository, Sort};
+use std::collections::HashMap;
+
+/// The local branch tips seen by the last poll.
+#[derive(Debug, Clone, Default)]
+pub struct RefWatcher {
+    tips: HashMap<String, Oid>,
+}
+
+impl RefWatcher {
+    /// Start watching from the current state; existing commits are not new.
+    pub fn new(repo: &Repository) -> Result<RefWatcher> {
+        Ok(RefWatcher { tips: branch_tips(repo)? })
+    }
+
+    /// Commits that landed on any local branch since the last poll, oldest
+    /// first.
+    ///
+    /// Branches that were reset or rewound yield nothing: only commits not
+    /// reachable from any previously seen tip count as new.
+    pub fn poll(&mut self, repo: &Repository) -> Result<Vec<Oid>> {
+        let tips = branch_tips(repo)?;
+        let moved: Vec<Oid> = tips
+            .iter()
+            .filter(|(name, oid)| self.tips.get(*name) != Some(oid))
+            .map(|(_, oid)| *oid)
+            .collect();
+        if moved.is_empty() {
+            self.tips = tips;
+            return Ok(Vec::new());
+        }
+
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        for oid in &moved {
+            walk.push(*oid)?;
+        }
+        for oid in self.tips.values() {
+            // A tip may have been garbage collected after a force push
+            if repo.find_commit(*oid).is_ok() {
+                walk.hide(*oid)?;
+            }
+        }
+        let new = walk.collect::<std::result::Result<Vec<_>, _>>()?;
+        self.tips = tips;
+        Ok(new)
+    }
+}
+
+/// The commit each local branch points at, by branch name.
+pub fn branch_tips(repo: &Repository) -> Result<HashMap<String, Oid>> {
+    let mut tips = HashMap::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let reference = branch.get();
+        if let (Some(name), Some(oid)) = (reference.name(), reference.target()) {
+            tips.insert(name.to_string(), oid);
+        }
+    }
+    Ok(tips)
+}
@@ -0,0 +1,317 @@
+//! `wtf workspace`: what is in flight across a directory of repositories.
+//!
+//! Each repository below the directory is checked the way `git status`
+//! would be: which branch it is on, whether the working tree has changes
+//! and how far the branch is ahead of or behind its upstream (as of the
+//! last fetch; nothing is fetched). The model then gets one line of state
+//! and the unpushed and recent commits of every repository, and writes a
+//! single summary of the work in progress.
+
+use crate::analysis::RunEstimate;
+use crate::conversation::Conversation;
+use crate::error::Result;
+use crate::git;
+use crate::provider::LlmProvider;
+use crate::render::OutputFormat;
+use crate::tokens;
+use crate::{progress, tr};
+use git2::{BranchType, Repository, Status, StatusOptions};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+const SYSTEM_PROMPT: &str = "You are an AI assistant that gives developers an overview of their work across many repositories.";
+
+const WORKSPACE_PROMPT: &str = "Below is the state of each repository in a developer's workspace: its branch, uncommitted changes, how far it is ahead of or behind its upstream, and its unpushed and most recent commits. Write one short overview of what is in flight across all of them, fitting on one screen: group related work, point out what looks unfinished (uncommitted changes, unpushed commits, branches behind their upstream) and what probably needs attention first. Mention repositories by name. Do not list repositories that are clean and up to date one by one. Keep it under 250 words.";
+
+// Commits per repository the model is told about
+const RECENT_COMMITS: usize = 3;
+const UNPUSHED_COMMITS: usize = 10;
+
+/// Uncommitted changes in a working tree, by kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Changes {
+    /// Files with changes in the index.
+    pub staged: usize,
+    /// Tracked files with changes not yet staged.
+    pub modified: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+}
+
+impl Changes {
+    pub fn is_clean(&self) -> bool {
+        *self == Changes::default()
+    }
+}
+
+/// The state of one repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepoStatus {
+    /// The repository's directory, relative to the scanned one.
+    pub name: String,
+    /// The checked-out branch, or `None` when HEAD is detached (or unborn).
+    pub branch: Option<String>,
+    pub changes: Changes,
+    /// The branch's upstream, like `origin/main`, if it has one.
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Summaries of commits not on the upstream yet, newest first.
+    pub unpushed: Vec<String>,
+    /// The latest commits on HEAD, as `date: summary`.
+    pub recent: Vec<String>,
+}
+
+impl RepoStatus {
+    /// Whether there is nothing to commit, push or pull.
+    pub fn is_settled(&self) -> bool {
+        self.changes.is_clean() && self.ahead == 0 && self.behind == 0
+    }
+}
+
+/// Find the repositories in `dir` and up to `depth` levels below it (not
+/// looking inside repositories themselves), sorted by path.
+pub fn find_repositories(dir: &Path, depth: usize) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    if dir.join(".git").exists() {
+        found.push(dir.to_path_buf());
+        return Ok(found);
+    }
+    if depth == 0 {
+        return Ok(found);
+    }
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| entry.path())
+        .filter(|path| !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')))
+        .collect();
+    entries.sort();
+    for entry in entries {
+        // Unreadable directories are skipped rather than failing the scan
+        found.extend(find_repositories(&entry, depth - 1).unwrap_or_default());
+    }
+    Ok(found)
+}
+
+/// The state of every repository in `dir`, `depth` levels deep.
+pub fn scan(dir: &Path, depth: usize) -> Result<Vec<RepoStatus>> {
+    let paths = find_repositories(dir, depth)?;
+    progress!("{}", tr!("progress-workspace-scanning", count = paths.len()));
+    let mut statuses = Vec::new();
+    for path in paths {
+        let Ok(repo) = Repository::open(&path) else { continue };
+        let name = match path.strip_prefix(dir) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.to_string_lossy().replace('\\', "/"),
+            _ => path.file_name().map_or_else(|| path.to_string_lossy().into_owned(), |n| n.to_string_lossy().into_owned()),
+        };
+        statuses.push(status(&repo, name)?);
+    }
+    Ok(statuses)
+}
+
+/// The state of `repo`, listed as `name`.
+pub fn status(repo: &Repository, name: String) -> Result<RepoStatus> {
+    let mut changes = Changes::default();
+    if !repo.is_bare() {
+        let mut options = StatusOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(false);
+        for entry in repo.statuses(Some(&mut options))?.iter() {
+            let status = entry.status();
+            if status.is_conflicted() {
+                changes.conflicted += 1;
+                continue;
+            }
+            if status.is_wt_new() {
+                changes.untracked += 1;
+                continue;
+            }
+            let index = Status::INDEX_NEW
+                | Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_RENAMED
+                | Status::INDEX_TYPECHANGE;
+            if status.intersects(index) {
+                changes.staged += 1;
+            }
+            if status.intersects(Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE) {
+                changes.modified += 1;
+            }
+        }
+    }
+
+    let head = repo.head().ok();
+    let branch = head.as_ref().filter(|head| head.is_branch()).and_then(|head| head.shorthand()).map(str::to_string);
+    let mut upstream = None;
+    let (mut ahead, mut behind) = (0, 0);
+    let mut unpushed = Vec::new();
+    if let Some(name) = &branch {
+        let local = repo.find_branch(name, BranchType::Local)?;
+        if let Ok(remote) = local.upstream() {
+            upstream = remote.name()?.map(str::to_string);
+            if let (Some(local_id), Some(remote_id)) = (local.get().target(), remote.get().target()) {
+                (ahead, behind) = repo.graph_ahead_behind(local_id, remote_id)?;
+                let mut walk = repo.revwalk()?;
+                walk.push(local_id)?;
+                walk.hide(remote_id)?;
+                for oid in walk.take(UNPUSHED_COMMITS) {
+                    unpushed.push(summary(&repo.find_commit(oid?)?));
+                }
+            }
+        }
+    }
+
+    let mut recent = Vec::new();
+    if head.is_some() {
+        let mut walk = repo.revwalk()?;
+        walk.push_head()?;
+        for oid in walk.take(RECENT_COMMITS) {
+            let commit = repo.find_commit(oid?)?;
+            recent.push(format!("{}: {}", git::format_date(commit.time().seconds()), summary(&commit)));
+        }
+    }
+
+    Ok(RepoStatus { name, branch, changes, upstream, ahead, behind, unpushed, recent })
+}
+
+fn summary(commit: &git2::Commit) -> String {
+    git::commit_message(commit).lines().next().unwrap_or_default().to_string()
+}
+
+// What the model is told about each repository
+fn content(statuses: &[RepoStatus]) -> String {
+    let mut content = String::new();
+    for status in statuses {
+        content.push_str(&format!("Repository: {}\n", status.name));
+        content.push_str(&format!("Branch: {}\n", status.branch.as_deref().unwrap_or("(detached HEAD)")));
+        let changes = &status.changes;
+        content.push_str(&format!(
+            "Uncommitted: {} staged, {} modified, {} untracked, {} conflicted\n",
+            changes.staged, changes.modified, changes.untracked, changes.conflicted
+        ));
+        match &status.upstream {
+            Some(upstream) => content.push_str(&format!(
+                "Upstream: {} ({} ahead, {} behind)\n",
+                upstream, status.ahead, status.behind
+            )),
+            None => content.push_str("Upstream: none\n"),
+        }
+        if !status.unpushed.is_empty() {
+            content.push_str(&format!("Unpushed commits:\n- {}\n", status.unpushed.join("\n- ")));
+        }
+        if !status.recent.is_empty() {
+            content.push_str(&format!("Recent commits:\n- {}\n", status.recent.join("\n- ")));
+        }
+        content.push_str("\n---\n\n");
+    }
+    content
+}
+
+/// Estimate the request summarizing `statuses` will use.
+pub fn estimate(statuses: &[RepoStatus]) -> RunEstimate {
+    RunEstimate {
+        requests: 1,
+        prompt_tokens: tokens::estimate(SYSTEM_PROMPT)
+            + tokens::estimate(WORKSPACE_PROMPT)
+            + tokens::estimate(&content(statuses)),
+    }
+}
+
+/// The state of a workspace and, if asked for, the model's overview.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Workspace {
+    pub repositories: Vec<RepoStatus>,
+    pub summary: Option<String>,
+}
+
+/// Ask the model for one overview of everything in flight.
+pub async fn summarize(statuses: &[RepoStatus], provider: &dyn LlmProvider) -> Result<String> {
+    progress!("{}", tr!("progress-workspace", count = statuses.len()));
+    let question = format!("{}\n\n{}", WORKSPACE_PROMPT, content(statuses));
+    Conversation::new(SYSTEM_PROMPT).ask(provider, question).await
+}
+
+// One repository's state in a few words
+fn state(status: &RepoStatus) -> String {
+    let mut parts = Vec::new();
+    let changes = &status.changes;
+    if changes.conflicted > 0 {
+        parts.push(tr!("workspace-conflicted", count = changes.conflicted));
+    }
+    if changes.staged > 0 {
+        parts.push(tr!("workspace-staged", count = changes.staged));
+    }
+    if changes.modified > 0 {
+        parts.push(tr!("workspace-modified", count = changes.modified));
+    }
+    if changes.untracked > 0 {
+        parts.push(tr!("workspace-untracked", count = changes.untracked));
+    }
+    match &status.upstream {
+        Some(_) if status.ahead > 0 || status.behind > 0 => {
+            parts.push(tr!("workspace-ahead-behind", ahead = status.ahead, behind = status.behind));
+        }
+        Some(_) => {}
+        None => parts.push(tr!("workspace-no-upstream")),
+    }
+    if parts.is_empty() {
+        parts.push(tr!("workspace-clean"));
+    }
+    parts.join(", ")
+}
+
+/// Render `workspace` in the given format.
+pub fn render(workspace: &Workspace, format: OutputFormat) -> String {
+    let title = tr!("workspace-title", count = workspace.repositories.len());
+    let branch = |status: &RepoStatus| status.branch.clone().unwrap_or_else(|| tr!("workspace-detached"));
+
+    match format {
+        // Serializing plain strings and numbers cannot fail
+        OutputFormat::Json => serde_json::to_string_pretty(workspace).expect("workspace serializes to JSON") + "\n",
+        OutputFormat::Markdown => {
+            let mut out = format!(
+                "## {}\n\n| {} | {} | {} |\n| --- | --- | --- |\n",
+                title,
+                tr!("workspace-column-repository"),
+                tr!("workspace-column-branch"),
+                tr!("workspace-column-state")
+            );
+            for status in &workspace.repositories {
+                out.push_str(&format!("| {} | `{}` | {} |\n", status.name, branch(status), state(status)));
+            }
+            if let Some(summary) = &workspace.summary {
+                out.push_str(&format!("\n## {}\n\n{}\n", tr!("workspace-summary"), summary.trim()));
+            }
+            out
+        }
+        OutputFormat::Terminal => {
+            let name_width = workspace.repositories.iter().map(|s| s.name.chars().count()).max().unwrap_or(0);
+            let branch_width = workspace.repositories.iter().map(|s| branch(s).chars().count()).max().unwrap_or(0);
+            let mut out = format!("\n=== {} ===\n\n", title.to_uppercase());
+            for status in &workspace.repositories {
+                out.push_str(&format!(
+                    "{:name_width$}  {:branch_width$}  {}\n",
+                    status.name,
+                    branch(status),
+                    state(status)
+                ));
+            }
+            if let Some(summary) = &workspace.summary {
+                out.push_str(&format!("\n=== {} ===\n\n{}\n", tr!("workspace-summary").to_uppercase(), summary.trim()));
+            }
+            out
+        }
+        OutputFormat::Plain => {
+            let lines: Vec<String> = workspace
+                .repositories
+                .iter()
+                .map(|status| format!("{}, {}: {}", status.name, branch(status), state(status)))
+                .collect();
+            let mut sections = vec![(title, lines.join("\n"))];
+            if let Some(summary) = &workspace.summary {
+                sections.push((tr!("workspace-summary"), summary.clone()));
+            }
+            crate::render::plain_sections(&sections)
+        }
+    }
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::adr::{self, PivotReason};
+use wtf::render::OutputFormat;
+
+// Small commits around a dependency swap, a restructuring and a large refactor
+fn history() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(2);
+    let initial: Vec<(String, Vec<u8>)> = (0..5).map(|i| (format!("lib/mod{}.rs", i), format!("// {}\n", i).into_bytes())).collect();
+    let writes: Vec<Change> = initial.iter().map(|(path, content)| Change::Write(path, content)).collect();
+    fixture.commit("Add modules", &writes);
+    fixture.commit("Add manifest", &[Change::Write("Cargo.toml", b"[dependencies]\nreqwest = \"0.11\"\n")]);
+    fixture.commit("Switch from reqwest to ureq", &[Change::Write("Cargo.toml", b"[dependencies]\nureq = \"2\"\n")]);
+    fixture.commit("Fix a typo", &[Change::Write("README.md", b"Hello\n")]);
+
+    let renames: Vec<(String, String)> = (0..5).map(|i| (format!("lib/mod{}.rs", i), format!("src/mod{}.rs", i))).collect();
+    let moves: Vec<Change> = renames.iter().map(|(from, to)| Change::Rename(from, to)).collect();
+    fixture.commit("Move lib to src", &moves);
+
+    let content = "line\n".repeat(30).into_bytes();
+    let paths: Vec<String> = (0..20).map(|i| format!("src/gen/file{}.rs", i)).collect();
+    let writes: Vec<Change> = paths.iter().map(|path| Change::Write(path, &content)).collect();
+    fixture.commit("Generate bindings", &writes);
+    fixture
+}
+
+#[test]
+fn finds_pivotal_commits_oldest_first() {
+    let fixture = history();
+
+    let pivots = adr::find_pivots(&fixture.repo, 100, 10).unwrap();
+    let found: Vec<_> = pivots.iter().map(|p| (p.summary.as_str(), p.reasons.clone())).collect();
+    assert_eq!(
+        found,
+        [
+            ("Add manifest", vec![PivotReason::DependencyChange]),
+            ("Switch from reqwest to ureq", vec![PivotReason::DependencySwap]),
+            ("Move lib to src", vec![PivotReason::Restructure]),
+            ("Generate bindings", vec![PivotReason::LargeRefactor]),
+        ]
+    );
+    let swap = &pivots[1];
+    assert_eq!((swap.dependencies.added.as_slice(), swap.dependencies.removed.as_slice()), (&["ureq".to_string()][..], &["reqwest".to_string()][..]));
+    assert!(swap.content.contains("Dependencies removed: reqwest"), "{}", swap.content);
+    assert!(swap.content.contains("+ureq = \"2\""), "{}", swap.content);
+    assert_eq!(pivots[2].renamed, 5);
+    assert!(pivots[2].content.contains("lib/mod0.rs -> src/mod0.rs"));
+
+    // The swap outranks everything else
+    let top = adr::find_pivots(&fixture.repo, 100, 1).unwrap();
+    assert_eq!(top[0].summary, "Switch from reqwest to ureq");
+    assert_eq!(adr::find_pivots(&fixture.repo, 1, 10).unwrap().len(), 1);
+    assert!(adr::find_pivots(&FixtureRepo::linear(3).repo, 100, 10).unwrap().is_empty());
+}
+
+#[test]
+fn file_names_are_numbered_slugs() {
+    assert_eq!(adr::file_name(3, "Switch from reqwest to ureq"), "0003-switch-from-reqwest-to-ureq.md");
+    assert_eq!(adr::file_name(12, "feat(http)!: use ureq"), "0012-feat-http-use-ureq.md");
+    assert_eq!(adr::file_name(1, "???"), "0001.md");
+    assert!(adr::file_name(1, &"word ".repeat(40)).len() <= "0001-".len() + 50 + ".md".len());
+}
+
+#[tokio::test]
+async fn drafts_one_record_per_pivot() {
+    let fixture = history();
+    let pivots = adr::find_pivots(&fixture.repo, 100, 2).unwrap();
+    let provider = MockProvider::with_replies(&["# Use ureq\n\n## Status\n\nAccepted", "# Generate bindings"]);
+
+    let estimate = adr::estimate(&pivots);
+    assert_eq!(estimate.requests, 2);
+    let adrs = adr::draft(pivots, &provider).await.unwrap();
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 2);
+    // Separate conversations: system prompt plus one question each
+    assert!(requests.iter().all(|messages| messages.len() == 2));
+    assert!(requests[0][1].content.contains("Architecture Decision Record"));
+    assert!(requests[0][1].content.contains("Commit: "));
+
+    let markdown = adr::render(&adrs, OutputFormat::Markdown, None);
+    assert_eq!(markdown, "# Use ureq\n\n## Status\n\nAccepted\n\n---\n\n# Generate bindings\n");
+    let json: serde_json::Value = serde_json::from_str(&adr::render(&adrs, OutputFormat::Json, None)).unwrap();
+    assert_eq!(json[0]["pivot"]["reasons"][0], "dependency-swap");
+    assert!(json[0]["pivot"].get("content").is_none());
+
+    let dir = fixture.dir.join("docs/adr");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("0002-use-git.md"), "# Use Git\n").unwrap();
+    let written = adr::write_all(&adrs, &dir, None).unwrap();
+    let names: Vec<_> = written.iter().map(|path| path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+    assert_eq!(names, ["0003-switch-from-reqwest-to-ureq.md", "0004-generate-bindings.md"]);
+    assert_eq!(std::fs::read_to_string(&written[1]).unwrap(), "# Generate bindings\n");
+}
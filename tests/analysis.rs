@@ -0,0 +1,450 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions, PerTask, SafetyCap, Task};
+use wtf::WtfError;
+
+fn options(num_commits: usize) -> AnalysisOptions {
+    AnalysisOptions {
+        num_commits,
+        ..AnalysisOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn linear_history_makes_three_requests() {
+    let fixture = FixtureRepo::linear(4);
+    let provider = MockProvider::new();
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options(3)).await.unwrap();
+
+    assert_eq!(report.num_commits, 3);
+    assert_eq!(report.project_description, "mock response 1");
+    assert_eq!(report.commit_descriptions, "mock response 2");
+    assert_eq!(report.edits_description, "mock response 3");
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].last().unwrap().content.contains("A test project."));
+    assert!(requests[1].last().unwrap().content.contains("Add file 3"));
+    assert!(requests[1].last().unwrap().content.contains("Add file 1"));
+    assert!(!requests[1].last().unwrap().content.contains("Add README"));
+    assert!(requests[2].last().unwrap().content.contains("+++ b/src/file3.txt"));
+}
+
+#[tokio::test]
+async fn each_task_can_have_its_own_provider() {
+    let fixture = FixtureRepo::linear(4);
+    let mut providers = PerTask::new(Box::new(MockProvider::with_replies(&["About", "Edits"])));
+    providers.set(Task::Commits, Box::new(MockProvider::with_replies(&["Cheap commits"])));
+
+    let report = analysis::analyze(&fixture.repo, &providers, &options(3)).await.unwrap();
+
+    assert_eq!(report.project_description, "About");
+    assert_eq!(report.commit_descriptions, "Cheap commits");
+    assert_eq!(report.edits_description, "Edits");
+    assert_eq!(Task::ALL.map(Task::name)[..3], ["description", "commits", "edits"]);
+}
+
+#[tokio::test]
+async fn streamed_sections_arrive_in_order() {
+    let fixture = FixtureRepo::linear(4);
+    let provider = MockProvider::with_replies(&["About", "Commits", "Edits"]);
+    let input = analysis::prepare(&fixture.repo, &options(3)).unwrap();
+
+    let mut streamed = Vec::new();
+    let report = analysis::run_streaming(&input, &provider, &options(3), &mut |task, text| {
+        streamed.push((task, text.to_string()))
+    })
+    .await
+    .unwrap();
+
+    let expected = [(Task::Description, "About"), (Task::Commits, "Commits"), (Task::Edits, "Edits")];
+    assert_eq!(streamed, expected.map(|(task, text)| (task, text.to_string())));
+    assert_eq!(report.edits_description, "Edits");
+}
+
+#[tokio::test]
+async fn sections_share_one_conversation() {
+    let fixture = FixtureRepo::linear(3);
+    let provider = MockProvider::new();
+
+    analysis::analyze(&fixture.repo, &provider, &options(3)).await.unwrap();
+
+    let requests = provider.requests();
+    let edits = &requests[2];
+    let roles: Vec<&str> = edits.iter().map(|m| m.role.as_str()).collect();
+    assert_eq!(roles, ["system", "user", "assistant", "user", "assistant", "user"]);
+    assert_eq!(edits[2].content, "mock response 1");
+    assert_eq!(edits[4].content, "mock response 2");
+}
+
+#[tokio::test]
+async fn refine_replaces_the_draft() {
+    let fixture = FixtureRepo::linear(3);
+    let provider = MockProvider::with_replies(&[
+        "draft project",
+        "draft commits",
+        "draft edits",
+        r#"{"project_description": "final project", "commit_descriptions": "final commits", "edits_description": "final edits"}"#,
+    ]);
+    let options = AnalysisOptions {
+        refine: true,
+        ..options(3)
+    };
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    assert_eq!(report.project_description, "final project");
+    assert_eq!(report.commit_descriptions, "final commits");
+    assert_eq!(report.edits_description, "final edits");
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 4);
+    let refine = requests[3].iter().rev().find(|m| m.role == "user").unwrap();
+    assert!(refine.content.contains("\"commit_descriptions\": \"draft commits\""));
+}
+
+#[tokio::test]
+async fn fabricated_references_are_flagged() {
+    let fixture = FixtureRepo::linear(3);
+    let provider = MockProvider::with_replies(&[
+        "A test project, see README.md.",
+        "Commit 0badc0de1 touched src/file2.txt and src/imaginary.rs.",
+        "Nothing else.",
+    ]);
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options(3)).await.unwrap();
+
+    assert_eq!(report.project_description, "A test project, see README.md.");
+    assert!(report.commit_descriptions.contains("0badc0de1 [unverified]"));
+    assert!(report.commit_descriptions.contains("src/imaginary.rs [unverified]."));
+    assert!(!report.commit_descriptions.contains("src/file2.txt [unverified]"));
+    assert_eq!(report.unverified_references, ["0badc0de1", "src/imaginary.rs"]);
+}
+
+#[tokio::test]
+async fn strict_mode_keeps_only_cited_claims() {
+    let fixture = FixtureRepo::linear(3);
+    let head = fixture.head().to_string();
+    let short = &head[..7];
+    let commits = format!("- Added file 2 [{}]\n- Made everything faster", short);
+    let provider = MockProvider::with_replies(&["A test project.", &commits, "Nothing cited here."]);
+    let options = AnalysisOptions {
+        strict: true,
+        ..options(3)
+    };
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    assert_eq!(report.commit_descriptions, format!("- Added file 2 [{}]", short));
+    assert_eq!(report.edits_description, "");
+    assert!(report.unverified_references.is_empty());
+
+    let requests = provider.requests();
+    let edits_prompt = &requests[2].last().unwrap().content;
+    assert!(edits_prompt.contains(&format!("Commit: {}", short)));
+}
+
+#[tokio::test]
+async fn single_commit_skips_edit_analysis() {
+    let fixture = FixtureRepo::linear(1);
+    let provider = MockProvider::new();
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options(5)).await.unwrap();
+
+    assert_eq!(report.num_commits, 1);
+    assert_eq!(provider.requests().len(), 2);
+    assert!(report.edits_description.contains("only one commit"));
+}
+
+#[tokio::test]
+async fn merge_commits_are_analyzed() {
+    let fixture = FixtureRepo::with_merge();
+    let provider = MockProvider::new();
+
+    analysis::analyze(&fixture.repo, &provider, &options(10)).await.unwrap();
+
+    let requests = provider.requests();
+    let commits = &requests[1].last().unwrap().content;
+    assert!(commits.contains("Merge branch 'feature'"));
+    assert!(commits.contains("Change on feature"));
+    assert!(commits.contains("Change on master"));
+}
+
+#[tokio::test]
+async fn renames_show_up_in_the_edits() {
+    let fixture = FixtureRepo::with_rename();
+    let provider = MockProvider::new();
+
+    analysis::analyze(&fixture.repo, &provider, &options(2)).await.unwrap();
+
+    let requests = provider.requests();
+    let edits = &requests[2].last().unwrap().content;
+    assert!(edits.contains("src/renamed.txt"));
+}
+
+#[tokio::test]
+async fn binary_files_do_not_break_the_diff() {
+    let fixture = FixtureRepo::with_binary();
+    let provider = MockProvider::new();
+
+    analysis::analyze(&fixture.repo, &provider, &options(2)).await.unwrap();
+
+    let requests = provider.requests();
+    let edits = &requests[2].last().unwrap().content;
+    assert!(edits.contains("logo.png"));
+}
+
+#[tokio::test]
+async fn latin1_messages_are_decoded() {
+    let fixture = FixtureRepo::with_latin1_messages();
+    let provider = MockProvider::new();
+
+    analysis::analyze(&fixture.repo, &provider, &options(3)).await.unwrap();
+
+    let requests = provider.requests();
+    let commits = &requests[1].last().unwrap().content;
+    assert!(commits.contains("Füge Größe hinzu"), "{}", commits);
+    assert!(commits.contains("Author: Jürgen"));
+    assert!(commits.contains("Corrige l'étiquette"));
+    assert!(commits.contains("Author: André"));
+    assert!(!commits.contains('\u{fffd}'));
+}
+
+#[tokio::test]
+async fn non_utf8_paths_are_escaped() {
+    let fixture = FixtureRepo::with_non_utf8_path();
+    let provider = MockProvider::new();
+
+    analysis::analyze(&fixture.repo, &provider, &options(2)).await.unwrap();
+
+    let requests = provider.requests();
+    let edits = &requests[2].last().unwrap().content;
+    // Like git, libgit2 quotes non-UTF-8 paths with octal escapes
+    assert!(edits.contains("+++ \"b/caf\\351.txt\""), "{}", edits);
+    assert!(!edits.contains('\u{fffd}'));
+
+    let paths = wtf::git::head_tree_paths(&fixture.repo).unwrap();
+    assert!(paths.contains(&"caf\\xe9.txt".to_string()), "{:?}", paths);
+}
+
+#[test]
+fn text_decoding_follows_the_encoding_header() {
+    use wtf::git::{decode_text, escape_non_utf8};
+
+    assert_eq!(decode_text("déjà vu".as_bytes(), None), "déjà vu");
+    assert_eq!(decode_text(b"d\xe9j\xe0 vu", None), "déjà vu");
+    assert_eq!(decode_text(b"d\xe9j\xe0 vu", Some("ISO-8859-1")), "déjà vu");
+    assert_eq!(decode_text(b"\x82\xa0\x82\xa2", Some("Shift_JIS")), "あい");
+    assert_eq!(escape_non_utf8(b"caf\xe9/\xc3\xa9t\xe9"), "caf\\xe9/ét\\xe9");
+}
+
+#[tokio::test]
+async fn orphan_branch_only_sees_its_own_history() {
+    let fixture = FixtureRepo::with_orphan_branch();
+    let provider = MockProvider::new();
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options(10)).await.unwrap();
+
+    assert_eq!(report.num_commits, 1);
+    let requests = provider.requests();
+    assert!(requests[0].last().unwrap().content.contains("No README.md found"));
+    assert!(requests[1].last().unwrap().content.contains("Start orphan history"));
+}
+
+#[tokio::test]
+async fn empty_repository_reports_no_commits() {
+    let fixture = FixtureRepo::new();
+    let provider = MockProvider::new();
+
+    let err = analysis::analyze(&fixture.repo, &provider, &options(5)).await.unwrap_err();
+
+    assert!(matches!(err, WtfError::NoCommits));
+    assert!(provider.requests().is_empty());
+}
+
+#[test]
+fn opening_a_non_repository_fails() {
+    let dir = std::env::temp_dir().join(format!("wtf-not-a-repo-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let err = match wtf::git::open_repository(&dir) {
+        Ok(_) => panic!("opened a directory that is not a repository"),
+        Err(err) => err,
+    };
+
+    assert!(matches!(err, WtfError::NotARepo { .. }));
+    assert_eq!(err.exit_code(), wtf::error::exit_code::NOT_A_REPO);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn estimate_grows_with_the_history() {
+    let fixture = FixtureRepo::linear(6);
+    let small = analysis::prepare(&fixture.repo, &options(2)).unwrap();
+    let large = analysis::prepare(&fixture.repo, &options(6)).unwrap();
+
+    let small_estimate = small.estimate(&options(2), usize::MAX);
+    let large_estimate = large.estimate(&options(6), usize::MAX);
+    assert_eq!(small_estimate.requests, 3);
+    assert!(large_estimate.prompt_tokens > small_estimate.prompt_tokens);
+
+    let refined = AnalysisOptions {
+        refine: true,
+        ..options(6)
+    };
+    assert_eq!(large.estimate(&refined, usize::MAX).requests, 4);
+
+    let cap = SafetyCap {
+        max_requests: 3,
+        max_prompt_tokens: small_estimate.prompt_tokens,
+    };
+    assert!(!cap.is_exceeded_by(&small_estimate));
+    assert!(cap.is_exceeded_by(&large_estimate));
+}
+
+#[test]
+fn no_request_is_estimated_above_the_context_window() {
+    let fixture = FixtureRepo::linear(6);
+    let input = analysis::prepare(&fixture.repo, &options(6)).unwrap();
+
+    let estimate = input.estimate(&options(6), 100);
+    assert_eq!(estimate.requests, 3);
+    assert!(estimate.prompt_tokens <= 3 * 100, "{:?}", estimate);
+}
+
+#[test]
+fn prepare_selected_analyzes_only_the_chosen_commits() {
+    let fixture = FixtureRepo::linear(5);
+    let (commits, _) = wtf::git::select_commits(&fixture.repo, 5).unwrap();
+    let chosen = [commits[0].id(), commits[3].id()];
+
+    let input = analysis::prepare_selected(&fixture.repo, &chosen, &AnalysisOptions::default()).unwrap();
+
+    assert_eq!(input.num_commits, 2);
+    assert_eq!(input.commit_count, 2);
+    assert!(input.commit_details[0].contains("Add file 4"));
+    assert!(input.commit_details[1].contains("Add file 1"));
+    assert_eq!(input.file_changes.len(), 2);
+    assert!(!input.file_changes.join("\n").contains("src/file2.txt"));
+}
+
+#[tokio::test]
+async fn prompts_are_trimmed_to_the_context_window() {
+    let fixture = FixtureRepo::linear(40);
+    let provider = MockProvider::new().with_max_context(2_000);
+    let options = AnalysisOptions { batch_size: 0, ..options(40) };
+
+    analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 3);
+    for request in &requests {
+        let used: usize = request.iter().map(|message| wtf::tokens::estimate(&message.content)).sum();
+        assert!(used <= 2_000, "{} tokens", used);
+    }
+    // The newest commits are kept, the oldest left out
+    let commits = &requests[1].last().unwrap().content;
+    assert!(commits.contains("Add file 39"));
+    assert!(!commits.contains("Add file 2\n"));
+    assert!(commits.ends_with(wtf::chunk::TRUNCATION_MARKER));
+}
+
+#[tokio::test]
+async fn long_histories_are_summarized_in_batches() {
+    let fixture = FixtureRepo::linear(12);
+    let provider = MockProvider::new();
+    let options = AnalysisOptions { batch_size: 5, ..options(12) };
+    let input = analysis::prepare(&fixture.repo, &options).unwrap();
+
+    analysis::run(&input, &provider, &options).await.unwrap();
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), input.estimate(&options, usize::MAX).requests);
+    // Description, then three batches of commits and their synthesis
+    let batches: Vec<_> = requests[1..4].iter().map(|request| request.last().unwrap().content.clone()).collect();
+    assert!(requests[1..4].iter().all(|request| request.len() == 2), "each batch is a request of its own");
+    assert!(batches[0].contains("Add file 11") && !batches[0].contains("Add file 6\n"));
+    assert!(batches[2].contains("Add README"));
+    let synthesis = &requests[4].last().unwrap().content;
+    assert!(synthesis.contains(wtf::summarize::SYNTHESIS_INSTRUCTIONS));
+    assert!(synthesis.contains("Batch 1 of 3:\n\nmock response 2"));
+    assert!(synthesis.contains("Batch 3 of 3:\n\nmock response 4"));
+    assert!(!synthesis.contains("Add file 11"));
+}
+
+#[tokio::test]
+async fn large_diffs_are_summarized_in_parts() {
+    let mut fixture = FixtureRepo::linear(2);
+    let lines: String = (0..200).map(|i| format!("line number {}\n", i)).collect();
+    fixture.commit(
+        "Big change",
+        &[Change::Write("big/a.txt", lines.as_bytes()), Change::Write("big/b.txt", lines.as_bytes())],
+    );
+    let provider = MockProvider::new();
+    let options = AnalysisOptions { max_diff_tokens: 1_000, ..options(2) };
+    let input = analysis::prepare(&fixture.repo, &options).unwrap();
+
+    analysis::run(&input, &provider, &options).await.unwrap();
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), input.estimate(&options, usize::MAX).requests);
+    // The parts are summarized first, each in a request of its own
+    let parts = &requests[..requests.len() - 3];
+    assert!(parts.len() >= 2 && parts.iter().all(|part| part.len() == 2), "{} parts", parts.len());
+    assert!(parts.iter().all(|part| wtf::tokens::estimate(&part[1].content) < 1_200));
+    assert!(parts[0][1].content.contains("+line number 0\n"));
+    let edits = &requests.last().unwrap().last().unwrap().content;
+    assert!(edits.contains("Part 1 of "));
+    assert!(!edits.contains("line number 100"));
+}
+
+#[tokio::test]
+async fn paths_limit_the_commits_and_their_changes() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Add the client",
+        &[Change::Write("src/net/client.rs", b"fn get() {}\n"), Change::Write("docs/net.md", b"# Net\n")],
+    );
+    fixture.commit("Touch the docs", &[Change::Write("docs/guide.md", b"# Guide\n")]);
+    fixture.commit(
+        "Retry requests",
+        &[Change::Write("src/net/client.rs", b"fn get() { retry() }\n"), Change::Write("README.md", b"# Net\n")],
+    );
+    let provider = MockProvider::new();
+    let options = AnalysisOptions { paths: vec!["src/net/".to_string()], ..options(5) };
+
+    let input = analysis::prepare(&fixture.repo, &options).unwrap();
+    assert_eq!((input.num_commits, input.commit_count), (2, 2));
+    assert_eq!(input.commit_paths, [["src/net/client.rs"], ["src/net/client.rs"]]);
+    analysis::run(&input, &provider, &options).await.unwrap();
+
+    let requests = provider.requests();
+    let commits = &requests[1].last().unwrap().content;
+    assert!(commits.contains("Only the commits that change something under src/net/ are included"), "{}", commits);
+    assert!(commits.contains("Retry requests") && !commits.contains("Touch the docs"), "{}", commits);
+    let edits = &requests[2].last().unwrap().content;
+    assert!(edits.contains("+++ b/src/net/client.rs"), "{}", edits);
+    assert!(!edits.contains("README.md") && !edits.contains("docs/net.md"), "{}", edits);
+
+    let elsewhere = AnalysisOptions { paths: vec!["nowhere".to_string()], ..options };
+    let err = analysis::prepare(&fixture.repo, &elsewhere).unwrap_err();
+    assert!(matches!(err, WtfError::NoCommitsInPaths { ref paths } if paths == "nowhere"), "{:?}", err);
+}
+
+#[tokio::test]
+async fn patches_keep_their_add_and_remove_markers() {
+    let mut fixture = FixtureRepo::linear(2);
+    fixture.commit("Rename the greeting", &[Change::Write("src/file1.txt", b"hello\n")]);
+    let commit = fixture.repo.find_commit(fixture.head()).unwrap();
+
+    let patch = wtf::git::commit_patch(&fixture.repo, &commit).unwrap().unwrap();
+    assert!(patch.contains("\n-content of file 1\n+hello\n"), "{}", patch);
+
+    // What the model is sent has them too
+    let provider = MockProvider::new();
+    analysis::explain_commit(&fixture.repo, &commit, &provider, 10_000, &[]).await.unwrap();
+    let prompt = &provider.requests()[0][1].content;
+    assert!(prompt.contains("\n-content of file 1\n+hello\n"), "{}", prompt);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::arch;
+use wtf::render::OutputFormat;
+
+fn fixture() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Add the parser",
+        &[
+            Change::Write("src/main.rs", b"fn main() { parse::run() }\n"),
+            Change::Write("src/parse/mod.rs", b"pub fn run() {}\n"),
+            Change::Write("src/parse/lexer.rs", b"pub fn lex() { /* a longer lexer */ }\n"),
+            Change::Write("src/parse/tree.rs", b"pub struct Tree;\n"),
+            Change::Write("src/parse/lexer_test.rs", b"#[test] fn lexes() { /* the longest file of all */ }\n"),
+            Change::Write("docs/guide.md", b"# Guide\n"),
+            Change::Write("logo.png", b"\x89PNG\r\n\x1a\n\0\0\0"),
+        ],
+    );
+    fixture
+}
+
+#[test]
+fn modules_sample_their_outline_first_and_tests_last() {
+    let fixture = fixture();
+    let input = arch::prepare(&fixture.repo, arch::DEFAULT_DEPTH, &["docs/**".to_string()]).unwrap();
+    let modules: Vec<(&str, usize)> = input.modules.iter().map(|m| (m.path.as_str(), m.files)).collect();
+    assert_eq!(modules, [("src/parse", 4), ("", 1), ("src", 1)]);
+    assert_eq!(input.modules[0].sampled, ["src/parse/mod.rs", "src/parse/lexer.rs", "src/parse/tree.rs"]);
+    assert_eq!(input.modules[1].sampled, ["README.md"]);
+
+    let shallow = arch::prepare(&fixture.repo, 1, &[]).unwrap();
+    let modules: Vec<(&str, usize)> = shallow.modules.iter().map(|m| (m.path.as_str(), m.files)).collect();
+    assert_eq!(modules, [("src", 5), ("", 1), ("docs", 1)]);
+}
+
+#[tokio::test]
+async fn dependencies_on_modules_outside_the_tree_are_dropped() {
+    let fixture = fixture();
+    let input = arch::prepare(&fixture.repo, arch::DEFAULT_DEPTH, &["docs/**".to_string()]).unwrap();
+    let provider = MockProvider::with_replies(&[r#"{
+        "overview": " A parser with a command line. ",
+        "modules": [
+            {"path": "src", "description": "The command line.", "depends_on": ["src/parse/", "src", "net"]},
+            {"path": "src/parse/", "description": " Parses input. ", "depends_on": []},
+            {"path": "web", "description": "Made up.", "depends_on": ["src"]}
+        ]
+    }"#]);
+    let architecture = arch::explain(&input, &provider).await.unwrap();
+    let requests = provider.requests();
+    let question = &requests[0][1].content;
+    let parse = "Module: src/parse\nFiles: 4\n\nFile: src/parse/mod.rs\npub fn run() {}\n";
+    assert!(question.contains(parse), "{}", question);
+    assert!(question.contains("Module: (root)\nFiles: 1\n\nFile: README.md\n# Fixture\n"), "{}", question);
+    assert!(!question.contains("lexer_test.rs"), "{}", question);
+
+    let out = arch::render(&architecture, OutputFormat::Markdown);
+    let modules = "## Modules\n\n- src/parse/ (4 file(s)): Parses input.\n- src/ (1 file(s)): The command line.\n\n";
+    assert!(out.starts_with("## Architecture of "), "{}", out);
+    assert!(out.contains(&format!("A parser with a command line.\n\n{}", modules)), "{}", out);
+    assert!(out.ends_with("## Dependencies\n\n- src/ -> src/parse/\n"), "{}", out);
+    assert!(!out.contains("web") && !out.contains("net"), "{}", out);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::ask;
+use wtf::guard::GuardMode;
+use wtf::render::OutputFormat;
+
+fn fixture() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(2);
+    fixture.commit(
+        "Add retry logic to the client",
+        &[Change::Write("src/net/retry.rs", b"pub fn retry_with_backoff() {}\n")],
+    );
+    fixture.commit("Tidy the guide", &[Change::Write("docs/guide.md", b"# Guide\n")]);
+    fixture.commit("Drop the backoff helper\n\nServers throttle us anyway.", &[Change::Delete("src/net/retry.rs")]);
+    fixture.commit("Keep the guide short", &[Change::Write("docs/guide.md", b"# Guide\n\nShort.\n")]);
+    fixture
+}
+
+fn summaries(input: &ask::AskInput) -> Vec<&str> {
+    input.commits.iter().map(|source| source.summary.as_str()).collect()
+}
+
+#[test]
+fn commits_are_found_by_the_question_words_and_by_pickaxe() {
+    let fixture = fixture();
+    let input = ask::prepare(&fixture.repo, "Why was the retry logic removed?", &[], 100).unwrap();
+    assert_eq!(summaries(&input), ["Add retry logic to the client", "Drop the backoff helper"]);
+    assert_eq!(input.commits[0].matched, ["retry", "logic"]);
+    assert!(input.files.is_empty());
+
+    // A span in backticks is searched for in the changed lines
+    let input = ask::prepare(&fixture.repo, "When did `retry_with_backoff` go away?", &[], 100).unwrap();
+    assert_eq!(summaries(&input), ["Drop the backoff helper", "Add retry logic to the client"]);
+    assert_eq!(input.commits[0].matched, ["retry_with_backoff"]);
+
+    let input = ask::prepare(&fixture.repo, "What is in the guide?", &[], 100).unwrap();
+    assert_eq!(summaries(&input), ["Tidy the guide", "Keep the guide short"]);
+    assert_eq!(input.files, ["docs/guide.md"]);
+}
+
+#[tokio::test]
+async fn the_answer_cites_the_commits_it_was_given() {
+    let fixture = fixture();
+    let input = ask::prepare(&fixture.repo, "Why was the retry logic removed?", &[], 100).unwrap();
+    let dropped = input.commits[1].short_id.clone();
+    let reply = format!("Servers throttle requests anyway [{}], unlike in [dead123] and src/net/backoff.rs.", dropped);
+    let provider = MockProvider::with_replies(&[&reply]);
+
+    let answer = ask::explain(&input, &provider, GuardMode::Flag).await.unwrap();
+    let requests = provider.requests();
+    let question = &requests[0][1].content;
+    assert!(question.contains("Question: Why was the retry logic removed?\n\n---\n\nCommit: "), "{}", question);
+    assert!(question.contains("Servers throttle us anyway."), "{}", question);
+    assert!(!question.contains("Tidy the guide"), "{}", question);
+    assert_eq!(answer.unverified, ["dead123", "src/net/backoff.rs"]);
+
+    let out = ask::render(&answer, OutputFormat::Markdown, None);
+    let start = format!("## Answer\n\nServers throttle requests anyway [{}], unlike in", dropped);
+    assert!(out.starts_with(&start), "{}", out);
+    assert!(out.contains(&format!("## Sources\n\n- `{}` 2023-11-14 Add retry logic", input.commits[0].short_id)));
+    assert!(out.ends_with(&format!("- `{}` 2023-11-14 Drop the backoff helper\n", dropped)), "{}", out);
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{Change, FixtureRepo};
+use wtf::attributes::{self, AttrValue, IgnoreState, Rules};
+use wtf::render::OutputFormat;
+
+fn write(fixture: &FixtureRepo, path: &str, content: &str) {
+    let path = fixture.dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+fn fixture() -> FixtureRepo {
+    let fixture = FixtureRepo::linear(1);
+    write(&fixture, ".gitignore", "# build output\n*.log\n!keep.log\nbuild/\n");
+    write(&fixture, "docs/.gitignore", "/draft.md\n");
+    write(
+        &fixture,
+        ".gitattributes",
+        "* text=auto\n*.png binary\n*.bat eol=crlf\nvendor/** linguist-vendored\n*.psd filter=lfs diff=lfs merge=lfs -text\n",
+    );
+    write(&fixture, "vendor/.gitattributes", "*.png -linguist-vendored\n");
+    fixture
+}
+
+#[test]
+fn globs_follow_gitignore_rules() {
+    assert!(attributes::glob_matches("*.log", "debug.log"));
+    assert!(!attributes::glob_matches("*.log", "logs/debug.log"));
+    assert!(attributes::glob_matches("**/debug.log", "debug.log"));
+    assert!(attributes::glob_matches("**/debug.log", "a/b/debug.log"));
+    assert!(attributes::glob_matches("a/**/b", "a/x/y/b"));
+    assert!(attributes::glob_matches("a/**", "a/x/y"));
+    assert!(attributes::glob_matches("file[0-9].[!c]", "file3.h"));
+    assert!(!attributes::glob_matches("file[0-9].[!c]", "file3.c"));
+    assert!(attributes::glob_matches("?at", "cat"));
+    assert!(!attributes::glob_matches("?at", "/at"));
+}
+
+#[test]
+fn explains_why_a_path_is_ignored() {
+    let fixture = fixture();
+    let mut rules = Rules::new(&fixture.repo);
+
+    match rules.explain("debug.log").unwrap().ignore {
+        IgnoreState::Ignored { rule } => assert_eq!((rule.source.as_str(), rule.line, rule.pattern.as_str()), (".gitignore", 2, "*.log")),
+        other => panic!("{:?}", other),
+    }
+    match rules.explain("keep.log").unwrap().ignore {
+        IgnoreState::ReIncluded { rule } => assert_eq!(rule.pattern, "!keep.log"),
+        other => panic!("{:?}", other),
+    }
+    match rules.explain("build/keep.log").unwrap().ignore {
+        IgnoreState::IgnoredDirectory { directory, rule } => {
+            assert_eq!(directory, "build/");
+            assert_eq!(rule.line, 4);
+        }
+        other => panic!("{:?}", other),
+    }
+    match rules.explain("docs/draft.md").unwrap().ignore {
+        IgnoreState::Ignored { rule } => assert_eq!(rule.source, "docs/.gitignore"),
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(rules.explain("docs/sub/draft.md").unwrap().ignore, IgnoreState::NotIgnored);
+    assert_eq!(rules.explain("src/main.rs").unwrap().ignore, IgnoreState::NotIgnored);
+}
+
+#[test]
+fn resolves_attributes_with_precedence_and_macros() {
+    let fixture = fixture();
+    let mut rules = Rules::new(&fixture.repo);
+    let attrs = |rules: &mut Rules, path: &str| -> Vec<(String, AttrValue)> {
+        rules.explain(path).unwrap().attributes.into_iter().map(|a| (a.name, a.value)).collect()
+    };
+
+    assert_eq!(attrs(&mut rules, "src/main.rs"), [("text".to_string(), AttrValue::Value("auto".to_string()))]);
+    // `binary` expands to -diff -merge -text, overriding text=auto
+    assert_eq!(
+        attrs(&mut rules, "logo.png"),
+        [
+            ("binary".to_string(), AttrValue::Flag(true)),
+            ("diff".to_string(), AttrValue::Flag(false)),
+            ("merge".to_string(), AttrValue::Flag(false)),
+            ("text".to_string(), AttrValue::Flag(false)),
+        ]
+    );
+    // The deeper .gitattributes wins
+    let vendored = rules.explain("vendor/lib/logo.png").unwrap();
+    let linguist = vendored.attributes.iter().find(|a| a.name == "linguist-vendored").unwrap();
+    assert_eq!(linguist.value, AttrValue::Flag(false));
+    assert_eq!(linguist.rule.source, "vendor/.gitattributes");
+
+    let art = rules.explain("art/cover.psd").unwrap();
+    let filter = art.attributes.iter().find(|a| a.name == "filter").unwrap();
+    assert!(attributes::describe(filter).contains("Git LFS"));
+}
+
+#[test]
+fn render_describes_each_rule() {
+    let fixture = fixture();
+    let mut rules = Rules::new(&fixture.repo);
+    let explained = vec![rules.explain("run.bat").unwrap(), rules.explain("debug.log").unwrap()];
+
+    let text = attributes::render(&explained, OutputFormat::Terminal);
+    assert!(text.contains("run.bat\n- Not ignored."), "{}", text);
+    assert!(text.contains("Checked out with CRLF line endings, set by *.bat eol=crlf (.gitattributes line 3)"), "{}", text);
+    assert!(text.contains("Ignored because of *.log (.gitignore line 2)."), "{}", text);
+
+    let json: serde_json::Value = serde_json::from_str(&attributes::render(&explained, OutputFormat::Json)).unwrap();
+    assert_eq!(json[1]["ignore"]["state"], "ignored");
+    assert_eq!(json[0]["attributes"][0]["value"], "crlf");
+}
+
+#[test]
+fn recently_changed_lists_paths_from_recent_commits() {
+    let mut fixture = FixtureRepo::linear(2);
+    fixture.commit("Add assets", &[Change::Write("assets/logo.png", b"\x89PNG")]);
+
+    let paths = attributes::recently_changed(&fixture.repo, 2).unwrap();
+    assert_eq!(paths, ["assets/logo.png", "src/file1.txt"]);
+}
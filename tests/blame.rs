@@ -0,0 +1,59 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::blame::{self, LineRange};
+use wtf::render::OutputFormat;
+use wtf::WtfError;
+
+// lib.txt written in one commit, then lines 3 and 8 changed in another
+fn repo_with_edited_file() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Add the library", &[Change::Write("lib.txt", b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n")]);
+    fixture.commit("Fix the odd lines", &[Change::Write("lib.txt", b"1\n2\nthree\n4\n5\n6\n7\neight\n9\n10\n")]);
+    fixture
+}
+
+#[test]
+fn line_ranges_are_parsed_and_checked() {
+    assert_eq!("120-160".parse::<LineRange>(), Ok(LineRange { start: 120, end: 160 }));
+    assert_eq!("7".parse::<LineRange>(), Ok(LineRange { start: 7, end: 7 }));
+    for bad in ["", "0-3", "9-2", "a-b", "3-"] {
+        assert!(bad.parse::<LineRange>().is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn each_line_is_blamed_on_the_commit_that_last_changed_it() {
+    let fixture = repo_with_edited_file();
+
+    let input = blame::prepare(&fixture.repo, "lib.txt", Some(LineRange { start: 2, end: 4 })).unwrap();
+
+    let summaries: Vec<_> = input.commits.iter().map(|c| (c.summary.as_str(), c.lines)).collect();
+    assert_eq!(summaries, [("Fix the odd lines", 1), ("Add the library", 2)]);
+    assert_eq!(input.lines[1].text, "three");
+    assert_eq!(input.lines[1].short_id, input.commits[0].short_id);
+    assert!(input.commits[0].content.contains("+three"), "{}", input.commits[0].content);
+
+    let whole = blame::prepare(&fixture.repo, "lib.txt", Some(LineRange { start: 8, end: 400 })).unwrap();
+    assert_eq!(whole.range, LineRange { start: 8, end: 10 });
+    assert!(matches!(
+        blame::prepare(&fixture.repo, "lib.txt", Some(LineRange { start: 11, end: 12 })),
+        Err(WtfError::NoSuchLines { count: 10, .. })
+    ));
+    assert!(matches!(blame::prepare(&fixture.repo, "missing.txt", None), Err(WtfError::UnknownPath { .. })));
+}
+
+#[tokio::test]
+async fn the_explanation_is_shown_above_the_commits() {
+    let fixture = repo_with_edited_file();
+    let input = blame::prepare(&fixture.repo, "lib.txt", Some(LineRange { start: 3, end: 3 })).unwrap();
+    let provider = MockProvider::with_replies(&["Line 3 was spelled out to fix it."]);
+
+    let blame = blame::explain(&input, &provider).await.unwrap();
+
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.contains(&format!("    3 {} three\n", input.commits[0].short_id)), "{}", question);
+    let markdown = blame::render(&blame, OutputFormat::Markdown);
+    assert!(markdown.starts_with("## Why lines 3-3 of lib.txt look the way they do\n"), "{}", markdown);
+    assert!(markdown.contains("] Fix the odd lines (Fixture Author"), "{}", markdown);
+}
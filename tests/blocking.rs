@@ -0,0 +1,28 @@
+mod common;
+
+use common::{FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+
+#[test]
+fn blocking_analyze_needs_no_runtime() {
+    let fixture = FixtureRepo::linear(3);
+    let provider = MockProvider::new();
+
+    let report = wtf::blocking::analyze(&fixture.repo, &provider, &AnalysisOptions::default()).unwrap();
+
+    assert_eq!(report.num_commits, 3);
+    assert_eq!(report.edits_description, "mock response 3");
+}
+
+#[test]
+fn blocking_run_uses_prepared_input() {
+    let fixture = FixtureRepo::linear(2);
+    let provider = MockProvider::new();
+    let options = AnalysisOptions::default();
+
+    let input = analysis::prepare(&fixture.repo, &options).unwrap();
+    let report = wtf::blocking::run(&input, &provider, &options).unwrap();
+
+    assert_eq!(report.commit_descriptions, "mock response 2");
+    assert_eq!(provider.requests().len(), 3);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{Change, FixtureRepo};
+use git2::Oid;
+use wtf::bookmarks::{self, Bookmark, Bookmarks};
+
+fn temp_data_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("wtf-bookmarks-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn bookmarks_are_kept_per_repository_and_branch() {
+    let dir = temp_data_dir("roundtrip");
+    assert_eq!(Bookmarks::load(&dir), Bookmarks::default());
+
+    let mut marks = Bookmarks::default();
+    let mark = Bookmark { commit: "a".repeat(40), time: 1_700_000_000 };
+    marks.set("/work/app", "main", mark.clone());
+    marks.set("/work/app", "feature", Bookmark { commit: "b".repeat(40), time: 1 });
+    marks.save(&dir).unwrap();
+
+    let loaded = Bookmarks::load(&dir);
+    assert_eq!(loaded.get("/work/app", "main"), Some(&mark));
+    assert_eq!(loaded.get("/work/app", "feature").unwrap().time, 1);
+    assert_eq!(loaded.get("/work/other", "main"), None);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn commits_since_the_bookmark_are_newest_first() {
+    let mut fixture = FixtureRepo::linear(2);
+    let marked = fixture.head();
+    let first = fixture.commit("Add feature", &[Change::Write("feature.txt", b"on\n")]);
+    let second = fixture.commit("Fix feature", &[Change::Write("feature.txt", b"off\n")]);
+
+    let since = bookmarks::commits_since(&fixture.repo, &Bookmark::now(marked)).unwrap();
+    assert_eq!(since, Some(vec![second, first]));
+    let since = bookmarks::commits_since(&fixture.repo, &Bookmark::now(second)).unwrap();
+    assert_eq!(since, Some(Vec::new()));
+
+    let gone = Bookmark::now(Oid::from_str(&"1".repeat(40)).unwrap());
+    assert_eq!(bookmarks::commits_since(&fixture.repo, &gone).unwrap(), None);
+}
+
+#[test]
+fn branch_and_repository_identify_a_bookmark() {
+    let fixture = FixtureRepo::linear(1);
+    let head = fixture.repo.head().unwrap();
+    assert_eq!(bookmarks::current_branch(&fixture.repo), head.shorthand().unwrap());
+    fixture.repo.set_head_detached(fixture.head()).unwrap();
+    assert_eq!(bookmarks::current_branch(&fixture.repo), "HEAD");
+
+    let key = bookmarks::repository_key(&fixture.repo);
+    assert_eq!(key, std::fs::canonicalize(&fixture.dir).unwrap().to_string_lossy());
+}
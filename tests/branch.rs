@@ -0,0 +1,63 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::config::Defaults;
+use wtf::git::{self, RevRange};
+
+// master gets one more commit after feature branches off with two of its own
+fn repo_with_feature_branch() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(2);
+    let base = fixture.head();
+    fixture.commit("Change on master", &[Change::Write("main.txt", b"main\n")]);
+    let first = fixture.commit_on(
+        "refs/heads/feature",
+        &[base],
+        "Start the feature",
+        &[Change::Write("feature.txt", b"one\n")],
+    );
+    fixture.commit_on(
+        "refs/heads/feature",
+        &[first],
+        "Finish the feature",
+        &[Change::Write("feature.txt", b"one\ntwo\n"), Change::Write("Cargo.lock", b"lock\n")],
+    );
+    fixture
+}
+
+#[test]
+fn the_base_is_origin_head_or_a_usual_branch_name() {
+    let fixture = repo_with_feature_branch();
+    assert_eq!(git::default_base(&fixture.repo), Some("master".to_string()));
+
+    let head = fixture.head();
+    fixture.repo.reference("refs/remotes/origin/develop", head, false, "test").unwrap();
+    fixture.repo.reference_symbolic("refs/remotes/origin/HEAD", "refs/remotes/origin/develop", false, "test").unwrap();
+    assert_eq!(git::default_base(&fixture.repo), Some("origin/develop".to_string()));
+
+    let defaults = Defaults::parse("base_branch = \"release\"\n").unwrap();
+    assert_eq!(defaults.base_branch.as_deref(), Some("release"));
+}
+
+#[tokio::test]
+async fn the_branch_is_explained_from_its_own_commits_and_combined_diff() {
+    let fixture = repo_with_feature_branch();
+    let range = RevRange { from: "master".to_string(), to: "feature".to_string(), symmetric: false };
+    let ids = git::range_commits(&fixture.repo, &range).unwrap();
+    assert_eq!(ids.len(), 2);
+    let provider = MockProvider::new();
+
+    let options = AnalysisOptions { num_commits: ids.len(), range: Some(range.clone()), ..AnalysisOptions::default() };
+    let input = analysis::prepare_branch(&fixture.repo, &range, &ids, &options).unwrap();
+    assert_eq!(input.branch, "feature");
+    assert_eq!(input.file_changes.len(), 1);
+    let combined = &input.file_changes[0];
+    assert!(combined.starts_with("Combined diff of feature against master:\n"), "{}", combined);
+    assert!(combined.contains("+one\n+two\n"), "{}", combined);
+    assert!(!combined.contains("main.txt") && !combined.contains("Cargo.lock"), "{}", combined);
+    assert_eq!(input.generated[0].path, "Cargo.lock");
+
+    analysis::run(&input, &provider, &options).await.unwrap();
+    let question = provider.requests()[2].last().unwrap().content.clone();
+    assert!(question.contains("what changed between master and feature"), "{}", question);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::breaking::{self, Kind};
+use wtf::git::RevRange;
+use wtf::render::OutputFormat;
+
+// A library, a command line and a config file, then a commit for each kind
+// of breaking change and one breaking nothing
+fn repo_with_changes() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Release 1.0",
+        &[
+            Change::Write("src/lib.rs", b"pub fn parse() {}\npub fn check() {}\n"),
+            Change::Write("src/cli.rs", b"struct Args {\n    #[arg(long)]\n    verbose: bool,\n}\n"),
+            Change::Write("config/settings.toml", b"timeout = 5\n"),
+        ],
+    );
+    fixture.commit("Drop check", &[Change::Write("src/lib.rs", b"pub fn parse() {}\n")]);
+    fixture.commit("Remove --verbose", &[Change::Write("src/cli.rs", b"struct Args {\n}\n")]);
+    fixture.commit("Rename timeout", &[Change::Write("config/settings.toml", b"timeout_secs = 5\n")]);
+    fixture.commit("Add a migration", &[Change::Write("migrations/002_users.sql", b"DROP TABLE users;\n")]);
+    fixture.commit("Update docs", &[Change::Write("README.md", b"Use --quiet.\n")]);
+    fixture
+}
+
+#[tokio::test]
+async fn candidates_are_found_by_kind_and_kept_when_the_model_says_they_break() {
+    let fixture = repo_with_changes();
+    let input = breaking::prepare(&fixture.repo, &RevRange::parse("HEAD~5..HEAD"), &[]).unwrap();
+
+    assert_eq!(input.commits, 5);
+    let kinds: Vec<(Kind, &str)> = input.candidates.iter().map(|c| (c.kind, c.path.as_str())).collect();
+    let expected = [
+        (Kind::Migration, "migrations/002_users.sql"),
+        (Kind::Config, "config/settings.toml"),
+        (Kind::Cli, "src/cli.rs"),
+    ];
+    assert_eq!(kinds, expected);
+    assert_eq!(input.api.len(), 1);
+    assert_eq!(input.estimate().requests, 1);
+
+    let provider = MockProvider::with_replies(&[r#"{"candidates": [
+        {"candidate": 1, "breaking": true, "description": " Drops the users table. "},
+        {"candidate": 2, "breaking": false, "description": "Only a rename."},
+        {"candidate": 3, "breaking": true, "description": "--verbose is gone."}
+    ]}"#]);
+    let changes = breaking::explain(&input, Some(&provider)).await.unwrap();
+
+    let found: Vec<(Kind, &str, &str)> =
+        changes.findings.iter().map(|f| (f.kind, f.path.as_str(), f.description.as_str())).collect();
+    assert_eq!(
+        found,
+        [
+            (Kind::Api, "crate::check", "Removed `pub fn check()`."),
+            (Kind::Cli, "src/cli.rs", "--verbose is gone."),
+            (Kind::Migration, "migrations/002_users.sql", "Drops the users table."),
+        ]
+    );
+
+    let requests = provider.requests();
+    let question = requests[0].iter().find(|m| m.content.contains("Candidate 1")).unwrap().content.clone();
+    assert!(question.contains("Candidate 3\nFile: src/cli.rs\nCommit: "), "{}", question);
+    assert!(question.contains(" Remove --verbose\n\n-    #[arg(long)]\n"), "{}", question);
+    assert!(!question.contains("README.md"), "{}", question);
+}
+
+#[tokio::test]
+async fn public_api_changes_are_listed_without_a_model() {
+    let fixture = repo_with_changes();
+    let input = breaking::prepare(&fixture.repo, &RevRange::parse("HEAD~5..HEAD~4"), &[]).unwrap();
+    assert!(input.candidates.is_empty());
+    assert_eq!(input.estimate().requests, 0);
+
+    let changes = breaking::explain(&input, None).await.unwrap();
+    let out = breaking::render(&changes, OutputFormat::Markdown);
+
+    let start = "## Breaking changes in HEAD~5..HEAD~4\n\n1 probable breaking change(s) in 1 commit(s).\n";
+    assert!(out.starts_with(start), "{}", out);
+    assert!(out.contains("## Public API\n\n- `crate::check` ("), "{}", out);
+    assert!(out.ends_with(" Drop check): Removed `pub fn check()`.\n"), "{}", out);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::broke;
+use wtf::git::RevRange;
+use wtf::render::OutputFormat;
+
+// A good commit, then three more of which the middle one adds the bug
+fn repo_with_regression() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Add the app", &[Change::Write("app.txt", b"works\n")]);
+    fixture.commit("Add docs", &[Change::Write("README", b"docs\n")]);
+    fixture.commit("Rework the app", &[Change::Write("app.txt", b"works\nbug\n")]);
+    fixture.commit("Add more docs", &[Change::Write("README", b"more docs\n")]);
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    fixture
+}
+
+fn range() -> RevRange {
+    RevRange { from: "HEAD~3".to_string(), to: "HEAD".to_string(), symmetric: false }
+}
+
+#[tokio::test]
+async fn candidates_are_ranked_most_likely_first() {
+    let fixture = repo_with_regression();
+    let input = broke::prepare(&fixture.repo, "the app prints bug", &range()).unwrap();
+    let shas: Vec<_> = input.candidates.iter().map(|c| c.short_id.clone()).collect();
+    assert_eq!(shas.len(), 3);
+    let reply = format!(
+        r#"{{"commits": [
+            {{"sha": "{}", "change": "More docs.", "likelihood": 5, "reason": "Only docs."}},
+            {{"sha": "{}", "change": "Reworks the app.", "likelihood": 90, "reason": "Adds the bug line."}}
+        ]}}"#,
+        shas[0], shas[1]
+    );
+    let provider = MockProvider::with_replies(&[&reply]);
+
+    let verdict = broke::explain(&input, &provider).await.unwrap();
+
+    let question = provider.requests()[0].iter().find(|m| m.content.contains("Symptom:")).unwrap().content.clone();
+    assert!(question.contains("Symptom: the app prints bug\nGood: HEAD~3\n"), "{}", question);
+    assert!(question.contains("+bug"), "{}", question);
+    let ranked: Vec<_> = verdict.suspects.iter().map(|s| (s.summary.as_str(), s.likelihood)).collect();
+    assert_eq!(ranked, [("Rework the app", 90), ("Add more docs", 5)]);
+    let markdown = broke::render(&verdict, OutputFormat::Markdown);
+    assert!(markdown.starts_with("## What Broke: the app prints bug\n\n- 3 candidate commit(s) between good HEAD~3"));
+    let suspect = format!("- [{}] Rework the app: 90% likely, Adds the bug line.\n  Reworks the app.\n", shas[1]);
+    assert!(markdown.contains(&suspect), "{}", markdown);
+}
+
+#[test]
+fn git_bisect_finds_the_first_bad_commit() {
+    let fixture = repo_with_regression();
+    let command: Vec<String> = ["sh", "-c", "! grep -q bug app.txt"].iter().map(|s| s.to_string()).collect();
+
+    let found = broke::bisect(&fixture.repo, &range(), &command).unwrap();
+
+    let culprit = fixture.repo.revparse_single("HEAD~1").unwrap().id();
+    assert_eq!(found, Some(culprit));
+    assert_eq!(fixture.repo.state(), git2::RepositoryState::Clean);
+    assert!(fixture.repo.head().unwrap().is_branch(), "the bisect was reset");
+}
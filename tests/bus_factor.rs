@@ -0,0 +1,82 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use std::collections::BTreeMap;
+use wtf::bus_factor::{self, Risk};
+use wtf::ownership::{self, Contribution};
+use wtf::render::OutputFormat;
+
+const TWENTY_LINES: &[u8] = b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n17\n18\n19\n20\n";
+
+// Alice alone writes src/engine; src/ui is shared between Alice and Bob
+fn repo() -> FixtureRepo {
+    let mut fixture = FixtureRepo::new();
+    fixture.set_author("Alice", "alice@example.com");
+    fixture.commit("Write the engine", &[Change::Write("src/engine/core.rs", TWENTY_LINES)]);
+    fixture.commit("Tune the engine", &[Change::Write("src/engine/tune.rs", TWENTY_LINES)]);
+    fixture.commit("Start the UI", &[Change::Write("src/ui/view.rs", TWENTY_LINES)]);
+    fixture.set_author("Bob", "bob@example.com");
+    fixture.commit("Add UI widgets", &[Change::Write("src/ui/widgets.rs", TWENTY_LINES)]);
+    fixture
+}
+
+fn by_area(path: &str) -> Option<String> {
+    Some(ownership::area_of(path, 2))
+}
+
+#[test]
+fn bus_factor_counts_people_covering_half_the_lines() {
+    let contribution = |lines| Contribution { commits: 1, lines, last_time: 0 };
+    let authors: BTreeMap<String, Contribution> = [("a", 50), ("b", 30), ("c", 20)]
+        .into_iter()
+        .map(|(name, lines)| (name.to_string(), contribution(lines)))
+        .collect();
+
+    assert_eq!(bus_factor::bus_factor(&authors), 2);
+    assert_eq!(bus_factor::bus_factor(&BTreeMap::new()), 0);
+}
+
+#[test]
+fn flags_parts_dominated_by_one_author() {
+    let fixture = repo();
+    let history = ownership::collect(&fixture.repo, 100).unwrap();
+
+    let report = bus_factor::analyze(&history, by_area, 0.75);
+
+    assert_eq!(report.repository_bus_factor, 1);
+    assert_eq!(report.concentrations.len(), 1);
+    let engine = &report.concentrations[0];
+    assert_eq!(engine.path, "src/engine");
+    assert_eq!(engine.author, "Alice");
+    assert_eq!((engine.lines, engine.commits), (40, 2));
+    assert_eq!(engine.risk, Risk::High);
+
+    // Per file, every file has a single author
+    let files = bus_factor::concentrations(&history, |path| Some(path.to_string()), 0.75);
+    assert_eq!(files.len(), 4);
+}
+
+#[tokio::test]
+async fn summary_lists_the_authors_work() {
+    let fixture = repo();
+    let history = ownership::collect(&fixture.repo, 100).unwrap();
+    let provider = MockProvider::with_replies(&["src/engine: the tuning rules."]);
+
+    let mut report = bus_factor::analyze(&history, by_area, 0.75);
+    let knowledge = bus_factor::summarize_knowledge(&history, &report.concentrations, by_area, &provider)
+        .await
+        .unwrap();
+    report.knowledge = Some(knowledge);
+
+    let prompt = provider.requests()[0].last().unwrap().content.clone();
+    assert!(prompt.contains("Part: src/engine\nMain author: Alice (100% of 40 changed lines)"));
+    assert!(prompt.contains("- Tune the engine\n- Write the engine\n"));
+    assert!(!prompt.contains("Start the UI"));
+    let estimate = bus_factor::estimate(&history, &report.concentrations, by_area);
+    assert_eq!(estimate.requests, 1);
+    assert!(estimate.prompt_tokens > wtf::tokens::estimate(&prompt), "{:?}", estimate);
+
+    let out = bus_factor::render(&report, OutputFormat::Markdown);
+    assert!(out.contains("- src/engine: Alice wrote 100% of 40 changed lines in 2 commit(s) (high risk)"), "{}", out);
+    assert!(out.contains("## Knowledge at risk\n\nsrc/engine: the tuning rules."));
+}
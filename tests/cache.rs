@@ -0,0 +1,55 @@
+mod common;
+
+use common::MockProvider;
+use std::sync::Arc;
+use wtf::cache::Cached;
+use wtf::provider::{BoxFuture, LlmProvider, Message};
+use wtf::structured::Schema;
+
+// Lets the test look at the requests after handing the provider over
+struct Shared(Arc<MockProvider>);
+
+impl LlmProvider for Shared {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, wtf::Result<String>> {
+        self.0.chat(messages)
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some("mock-1")
+    }
+}
+
+fn cache_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("wtf-cache-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn identical_requests_are_answered_from_the_cache() {
+    let dir = cache_dir("identical");
+    let mock = Arc::new(MockProvider::with_replies(&["first", "second", "third"]));
+    let cached = Cached::new(Box::new(Shared(mock.clone())), &dir, "mock");
+    let question = [Message::system("Be brief."), Message::user("What changed in 1a2b3c4?")];
+
+    assert_eq!(cached.chat(&question).await.unwrap(), "first");
+    assert_eq!(cached.chat(&question).await.unwrap(), "first");
+    let mut streamed = String::new();
+    assert_eq!(cached.stream(&question, &mut |text| streamed.push_str(text)).await.unwrap(), "first");
+    assert_eq!(streamed, "first");
+    assert_eq!(mock.requests().len(), 1);
+
+    // A different commit, or a structured request, is a different request
+    let other = [Message::system("Be brief."), Message::user("What changed in 5d6e7f8?")];
+    assert_eq!(cached.chat(&other).await.unwrap(), "second");
+    let schema = Schema::new("answer", serde_json::json!({ "type": "string" }));
+    assert_ne!(cached.key(&question, Some(&schema)), cached.key(&question, None));
+
+    // The cache outlives the provider, but isn't shared with other models
+    let again = Cached::new(Box::new(Shared(mock.clone())), &dir, "mock");
+    assert_eq!(again.chat(&question).await.unwrap(), "first");
+    let other_model = Cached::new(Box::new(MockProvider::new()), &dir, "mock");
+    assert_ne!(other_model.key(&question, None), again.key(&question, None));
+    assert_eq!(mock.requests().len(), 2);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::changelog::{self, Changelog, Entry, Section};
+
+fn tag(fixture: &FixtureRepo, name: &str, id: git2::Oid) {
+    fixture.repo.tag_lightweight(name, &fixture.repo.find_object(id, None).unwrap(), false).unwrap();
+}
+
+fn entry(section: Section, text: &str, short_id: &str) -> Entry {
+    Entry { section, text: text.to_string(), short_id: short_id.to_string() }
+}
+
+fn changelog(entries: Vec<Entry>) -> Changelog {
+    Changelog { since: Some("v1.0.0".to_string()), commits: entries.len(), entries }
+}
+
+#[tokio::test]
+async fn commits_since_the_last_tag_are_sorted_into_sections() {
+    let mut fixture = FixtureRepo::linear(1);
+    let release = fixture.commit("Release 1.0", &[Change::Write("version.txt", b"1.0\n")]);
+    tag(&fixture, "v1.0.0", release);
+    let fix = fixture.commit("fix: accept empty input", &[Change::Write("parse.rs", b"fn parse() {}\n")]);
+    let chore = fixture.commit("ci: cache builds", &[Change::Write("ci.yml", b"cache: true\n")]);
+    let feat = fixture.commit("feat: add --json", &[Change::Write("main.rs", b"fn main() {}\n")]);
+
+    // The changelog already mentions the fix
+    let short = |id: git2::Oid| {
+        let object = fixture.repo.find_object(id, None).unwrap();
+        object.short_id().unwrap().as_str().unwrap().to_string()
+    };
+    let existing = format!("## [Unreleased]\n\n### Fixed\n\n- Empty input is accepted ({})\n", short(fix));
+    let input = changelog::prepare(&fixture.repo, &existing).unwrap();
+    assert_eq!(input.since.as_deref(), Some("v1.0.0"));
+    assert_eq!(input.commits.iter().map(|c| c.id).collect::<Vec<_>>(), [feat, chore]);
+
+    let reply = format!(
+        r#"{{"commits": [{{"sha": "{}", "section": "added", "entry": "A --json flag prints JSON."}},
+            {{"sha": "{}", "section": "none", "entry": ""}}]}}"#,
+        feat,
+        short(chore)
+    );
+    let provider = MockProvider::with_replies(&[&reply]);
+    let result = changelog::explain(&input, &provider).await.unwrap();
+
+    assert_eq!(result.entries, [entry(Section::Added, "A --json flag prints JSON.", &short(feat))]);
+    let out = changelog::update(&existing, &result);
+    assert!(out.starts_with("## [Unreleased]\n\n### Fixed\n\n- Empty input is accepted"), "{}", out);
+    assert!(out.ends_with(&format!("\n\n### Added\n\n- A --json flag prints JSON ({})\n", short(feat))), "{}", out);
+}
+
+#[test]
+fn entries_go_under_unreleased_without_touching_the_rest() {
+    let existing = "# Changelog\n\nNotes.\n\n## [Unreleased]\n\n### Added\n\n- Hand-written entry\n\n### Fixed\n\n\
+                    ## [1.0.0] - 2024-05-01\n\n### Added\n\n- First release\n";
+    let new = changelog(vec![
+        entry(Section::Added, "A --json flag.", "1a2b3c4"),
+        entry(Section::Fixed, "Empty input is accepted.", "2b3c4d5"),
+        entry(Section::Security, "Tokens are no longer logged.", "3c4d5e6"),
+    ]);
+
+    let out = changelog::update(existing, &new);
+
+    assert_eq!(
+        out,
+        "# Changelog\n\nNotes.\n\n## [Unreleased]\n\n### Added\n\n- Hand-written entry\n- A --json flag (1a2b3c4)\n\n\
+         ### Fixed\n\n- Empty input is accepted (2b3c4d5)\n\n\
+         ### Security\n\n- Tokens are no longer logged (3c4d5e6)\n\n\
+         ## [1.0.0] - 2024-05-01\n\n### Added\n\n- First release\n"
+    );
+}
+
+#[test]
+fn an_unreleased_release_or_a_whole_changelog_is_started_when_missing() {
+    let new = changelog(vec![entry(Section::Changed, "Output is sorted.", "1a2b3c4")]);
+
+    let out = changelog::update("# Changelog\n\n## [1.0.0]\n\n- First\n", &new);
+    assert_eq!(
+        out,
+        "# Changelog\n\n## [Unreleased]\n\n### Changed\n\n- Output is sorted (1a2b3c4)\n\n## [1.0.0]\n\n- First\n"
+    );
+
+    let out = changelog::update("", &new);
+    assert!(out.starts_with("# Changelog\n\nAll notable changes"), "{}", out);
+    let end = "(https://keepachangelog.com/en/1.1.0/).\n\n## [Unreleased]\n\n\
+               ### Changed\n\n- Output is sorted (1a2b3c4)\n";
+    assert!(out.ends_with(end), "{}", out);
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::chat::{Chat, Command};
+use wtf::guard::GuardMode;
+
+fn fixture() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(2);
+    fixture.commit(
+        "Add retry logic to the client",
+        &[Change::Write("src/net/retry.rs", b"pub fn retry_with_backoff() {}\n")],
+    );
+    fixture.commit("Drop the backoff helper\n\nServers throttle us anyway.", &[Change::Delete("src/net/retry.rs")]);
+    fixture
+}
+
+#[test]
+fn lines_are_read_as_commands_or_questions() {
+    assert_eq!(Command::parse("  "), None);
+    assert_eq!(Command::parse("/diff"), Some(Command::Diff(None)));
+    assert_eq!(Command::parse("/show HEAD~1"), Some(Command::Show(Some("HEAD~1".to_string()))));
+    assert_eq!(Command::parse("/exit"), Some(Command::Quit));
+    assert_eq!(Command::parse("/blame"), Some(Command::Unknown("blame".to_string())));
+
+    // Plain requests for a commit, but not questions that merely start alike
+    assert_eq!(Command::parse("Show me the diff for that commit."), Some(Command::Diff(None)));
+    assert_eq!(Command::parse("show [abc1234]"), Some(Command::Show(Some("abc1234".to_string()))));
+    assert_eq!(Command::parse("show me that commit"), Some(Command::Show(None)));
+    let question = "show me the commits that touched retries";
+    assert_eq!(Command::parse(question), Some(Command::Ask(question.to_string())));
+}
+
+#[tokio::test]
+async fn follow_ups_build_on_the_conversation_and_that_commit() {
+    let fixture = fixture();
+    let dropped = fixture.repo.find_commit(fixture.head()).unwrap();
+    let short_id = dropped.as_object().short_id().unwrap().as_str().unwrap().to_string();
+    let reply = format!("The helper went away because servers throttle requests anyway [{}].", short_id);
+    let provider = MockProvider::with_replies(&[&reply, "It deletes src/net/retry.rs."]);
+    let mut chat = Chat::start(&fixture.repo, 100, GuardMode::Flag).unwrap();
+
+    let ask = |question: &str| Command::parse(question).unwrap();
+    let answer = chat.handle(ask("Why was the retry logic removed?"), &provider).await.unwrap().unwrap();
+    assert!(answer.contains(&short_id), "{}", answer);
+
+    // "that commit" is the one the answer cited
+    let diff = chat.handle(ask("show me the diff for that commit"), &provider).await.unwrap().unwrap();
+    assert!(diff.contains("-pub fn retry_with_backoff() {}"), "{}", diff);
+    chat.handle(ask("What does that diff remove?"), &provider).await.unwrap();
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 2);
+    let (first, second) = (&requests[0], &requests[1]);
+    assert!(first[0].content.contains("Latest commits:\n\n- Drop the backoff helper"), "{}", first[0].content);
+    assert!(first[1].content.contains("Servers throttle us anyway."), "{}", first[1].content);
+    // The follow-up carries the earlier turn and the diff, but not the commits again
+    assert_eq!(second.len(), 4);
+    assert_eq!(second[2].role, "assistant");
+    assert!(second[3].content.contains(&format!("The diff of commit {}:", short_id)), "{}", second[3].content);
+    assert!(!second[3].content.contains("Servers throttle us anyway."), "{}", second[3].content);
+
+    assert_eq!(chat.handle(Command::History, &provider).await.unwrap().unwrap().lines().count(), 2);
+    assert_eq!(chat.handle(Command::Quit, &provider).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn the_oldest_turns_are_dropped_when_the_context_fills_up() {
+    let fixture = fixture();
+    let long = "word ".repeat(400);
+    let provider = MockProvider::with_replies(&[&long, &long, &long]).with_max_context(2_500);
+    let mut chat = Chat::start(&fixture.repo, 100, GuardMode::Off).unwrap();
+
+    for question in ["Anything more?", "Anything else?", "Anything further?"] {
+        chat.handle(Command::Ask(question.to_string()), &provider).await.unwrap();
+    }
+    // The system prompt stays; the first question made room for the third
+    let messages = chat.conversation().messages();
+    assert_eq!(messages.len(), 5);
+    assert_eq!(messages[0].role, "system");
+    assert!(messages[1].content.ends_with("Question: Anything else?"), "{}", messages[1].content);
+}
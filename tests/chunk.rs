@@ -0,0 +1,198 @@
+//! Property tests for patch chunking and truncation.
+//!
+//! Cases are generated from a small seeded PRNG so failures are reproducible:
+//! a failing case reports its seed, and `CHUNK_SEED=<seed>` replays just that
+//! case.
+
+use wtf::chunk::{chunk_patch, split_files, truncate_to_tokens};
+use wtf::tokens;
+
+const CASES: u64 = 300;
+
+/// xorshift64*, good enough for generating test inputs.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn range(&mut self, lo: usize, hi: usize) -> usize {
+        lo + self.below(hi - lo + 1)
+    }
+}
+
+// A mix of ASCII, multi-byte and astral-plane characters
+const ALPHABET: &[char] = &[
+    'a', 'b', 'z', '0', '9', ' ', '_', '(', ')', '{', '}', ';', 'é', 'ß', 'Ω', 'ж', '日', '本', '語', '🚀', '🦀',
+];
+
+fn random_text(rng: &mut Rng, max_len: usize) -> String {
+    let len = rng.below(max_len + 1);
+    (0..len).map(|_| ALPHABET[rng.below(ALPHABET.len())]).collect()
+}
+
+fn random_patch(rng: &mut Rng) -> String {
+    let mut patch = String::new();
+    for f in 0..rng.range(1, 4) {
+        let path = format!("src/{}{}.rs", random_text(rng, 6).replace(' ', "_"), f);
+        patch.push_str(&format!(
+            "diff --git a/{p} b/{p}\nindex 1234567..89abcde 100644\n--- a/{p}\n+++ b/{p}\n",
+            p = path
+        ));
+        for h in 0..rng.below(4) {
+            patch.push_str(&format!("@@ -{},3 +{},4 @@ fn hunk_{}()\n", h * 10 + 1, h * 10 + 1, h));
+            for _ in 0..rng.range(1, 30) {
+                let origin = ['+', '-', ' '][rng.below(3)];
+                patch.push(origin);
+                patch.push_str(&random_text(rng, 120));
+                patch.push('\n');
+            }
+        }
+    }
+    patch
+}
+
+fn hunk_headers(patch: &str) -> Vec<&str> {
+    patch.lines().filter(|l| l.starts_with("@@")).collect()
+}
+
+fn for_each_case(mut check: impl FnMut(u64, &mut Rng)) {
+    if let Ok(seed) = std::env::var("CHUNK_SEED") {
+        let seed = seed.parse().expect("CHUNK_SEED must be a number");
+        check(seed, &mut Rng::new(seed));
+        return;
+    }
+    for seed in 0..CASES {
+        check(seed, &mut Rng::new(seed));
+    }
+}
+
+#[test]
+fn chunks_never_exceed_the_budget() {
+    for_each_case(|seed, rng| {
+        let patch = random_patch(rng);
+        let budget = rng.range(48, 600);
+        for chunk in chunk_patch(&patch, budget) {
+            assert!(
+                tokens::estimate(&chunk) <= budget,
+                "seed {}: chunk of {} tokens exceeds budget {}",
+                seed,
+                tokens::estimate(&chunk),
+                budget
+            );
+        }
+    });
+}
+
+#[test]
+fn chunks_preserve_every_hunk_header() {
+    for_each_case(|seed, rng| {
+        let patch = random_patch(rng);
+        let budget = rng.range(48, 600);
+        let chunks = chunk_patch(&patch, budget);
+        for header in hunk_headers(&patch) {
+            assert!(
+                chunks.iter().any(|c| c.lines().any(|l| l == header)),
+                "seed {}: hunk header {:?} lost",
+                seed,
+                header
+            );
+        }
+    });
+}
+
+#[test]
+fn every_hunk_line_in_a_chunk_follows_its_hunk_header() {
+    for_each_case(|seed, rng| {
+        let patch = random_patch(rng);
+        let budget = rng.range(48, 600);
+        for chunk in chunk_patch(&patch, budget) {
+            let mut in_hunk = false;
+            for line in chunk.lines() {
+                if line.starts_with("diff --git ") {
+                    in_hunk = false;
+                } else if line.starts_with("@@") {
+                    in_hunk = true;
+                } else if line.starts_with(['+', '-', ' ']) && !line.starts_with("+++") && !line.starts_with("---") {
+                    assert!(in_hunk, "seed {}: hunk line {:?} without a header", seed, line);
+                }
+            }
+        }
+    });
+}
+
+#[test]
+fn chunked_lines_are_prefixes_of_original_lines() {
+    for_each_case(|seed, rng| {
+        let patch = random_patch(rng);
+        let budget = rng.range(48, 600);
+        let original: Vec<&str> = patch.lines().collect();
+        for chunk in chunk_patch(&patch, budget) {
+            for line in chunk.lines() {
+                assert!(
+                    original.iter().any(|o| o.starts_with(line)),
+                    "seed {}: line {:?} does not come from the patch",
+                    seed,
+                    line
+                );
+            }
+        }
+    });
+}
+
+#[test]
+fn large_budget_keeps_the_patch_intact() {
+    for_each_case(|seed, rng| {
+        let patch = random_patch(rng);
+        let chunks = chunk_patch(&patch, tokens::estimate(&patch) + 1);
+        assert_eq!(chunks.concat(), patch, "seed {}", seed);
+    });
+}
+
+#[test]
+fn truncation_respects_budget_and_char_boundaries() {
+    for_each_case(|seed, rng| {
+        let text = random_text(rng, 2000);
+        let budget = rng.below(300);
+        let (out, truncated) = truncate_to_tokens(&text, budget);
+        assert!(tokens::estimate(&out) <= budget, "seed {}: over budget", seed);
+        assert_eq!(truncated, tokens::estimate(&text) > budget, "seed {}", seed);
+        if !truncated {
+            assert_eq!(out, text, "seed {}", seed);
+        }
+    });
+}
+
+#[test]
+fn split_files_round_trips() {
+    for_each_case(|seed, rng| {
+        let patch = random_patch(rng);
+        let rebuilt: String = split_files(&patch)
+            .iter()
+            .map(|f| format!("{}{}", f.header, f.hunks.concat()))
+            .collect();
+        assert_eq!(rebuilt, patch, "seed {}", seed);
+    });
+}
+
+#[test]
+fn non_ascii_characters_count_as_a_token_each() {
+    assert_eq!(tokens::estimate("fn main() {}"), 3);
+    assert_eq!(tokens::estimate("日本語のテキスト"), 8);
+    assert_eq!(tokens::estimate("abcd 🦀"), 3);
+    let text = "変更履歴".repeat(500);
+    let (out, truncated) = truncate_to_tokens(&text, 100);
+    assert!(truncated && tokens::estimate(&out) <= 100, "{}", tokens::estimate(&out));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::ci::{self, CiRisk};
+
+const WORKFLOW: &[u8] = b"on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n";
+
+#[test]
+fn recognizes_ci_configuration() {
+    for path in [
+        ".github/workflows/test.yml",
+        ".github/actions/setup/action.yml",
+        ".gitlab-ci.yml",
+        "Jenkinsfile",
+        "ci/Jenkinsfile.release",
+        ".circleci/config.yml",
+        "azure-pipelines.yml",
+    ] {
+        assert!(ci::is_ci_path(path), "{}", path);
+    }
+    for path in [".github/workflows/README.md", ".github/CODEOWNERS", "src/ci.rs", "docs/.gitlab-ci.yml"] {
+        assert!(!ci::is_ci_path(path), "{}", path);
+    }
+}
+
+#[test]
+fn flags_security_sensitive_lines() {
+    let cases: &[(&str, bool, &[CiRisk])] = &[
+        ("token: ${{ secrets.NPM_TOKEN }}", true, &[CiRisk::Secret]),
+        ("permissions: write-all", true, &[CiRisk::Permissions]),
+        ("contents: write", true, &[CiRisk::Permissions]),
+        ("permissions:", false, &[CiRisk::Permissions]),
+        ("pull_request_target:", true, &[CiRisk::PrivilegedTrigger]),
+        ("- uses: someone/action@v1", true, &[CiRisk::UnpinnedAction]),
+        ("- uses: someone/action@0123456789abcdef0123456789abcdef01234567", true, &[]),
+        ("- uses: ./.github/actions/setup", true, &[]),
+        ("run: curl -sSL https://example.com/install.sh | bash", true, &[CiRisk::RemoteScript]),
+        ("runs-on: [self-hosted, linux]", true, &[CiRisk::SelfHosted]),
+        ("# uses secrets.TOKEN later", true, &[]),
+        ("token: ${{ secrets.OLD }}", false, &[]),
+        ("run: cargo test", true, &[]),
+    ];
+    for (line, added, expected) in cases {
+        assert_eq!(ci::risks(line, *added), *expected, "{}", line);
+    }
+}
+
+#[test]
+fn collects_only_ci_files() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Publish on tags",
+        &[
+            Change::Write(".github/workflows/test.yml", WORKFLOW),
+            Change::Write("src/lib.rs", b"secrets.not_ci\n"),
+        ],
+    );
+    let (commits, _) = wtf::git::select_commits(&fixture.repo, 2).unwrap();
+
+    let changes = ci::collect(&fixture.repo, &commits, &[]).unwrap().unwrap();
+    assert_eq!(changes.files, [".github/workflows/test.yml"]);
+    assert_eq!(changes.findings.len(), 1);
+    assert_eq!(changes.findings[0].risk, CiRisk::UnpinnedAction);
+    assert_eq!(changes.findings[0].line, "+ - uses: actions/checkout@v4");
+    assert!(changes.patches[0].contains("+on: push"));
+    assert!(!changes.prompt_content().contains("src/lib.rs"));
+
+    assert_eq!(ci::collect(&fixture.repo, &commits[1..], &[]).unwrap(), None);
+}
+
+#[tokio::test]
+async fn report_has_a_ci_section_when_ci_changed() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Add CI", &[Change::Write(".github/workflows/test.yml", WORKFLOW)]);
+    let provider = MockProvider::with_replies(&["A project.", "Commits.", "Edits.", "CI now runs the tests."]);
+
+    let options = AnalysisOptions { num_commits: 2, ..AnalysisOptions::default() };
+    let report = analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    let ci = report.ci_changes.unwrap();
+    assert_eq!(ci.summary, "CI now runs the tests.");
+    assert_eq!(ci.findings[0].risk, CiRisk::UnpinnedAction);
+    let requests = provider.requests();
+    let question = &requests[3].last().unwrap().content;
+    assert!(question.contains("Flagged lines:"), "{}", question);
+    assert!(question.contains("uses: actions/checkout@v4"));
+
+    let quiet = FixtureRepo::linear(2);
+    let report = analysis::analyze(&quiet.repo, &MockProvider::new(), &options).await.unwrap();
+    assert!(report.ci_changes.is_none());
+}
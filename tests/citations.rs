@@ -0,0 +1,36 @@
+use wtf::citations::{cited_shas, link_citations, strip_uncited};
+
+#[test]
+fn finds_single_and_grouped_citations() {
+    let text = "Added login [1a2b3c4]. Fixed tests [5d6e7f8, 9a8b7c6]. See [the docs].";
+    assert_eq!(cited_shas(text), ["1a2b3c4", "5d6e7f8", "9a8b7c6"]);
+}
+
+#[test]
+fn strict_mode_drops_uncited_list_items() {
+    let text = "# Commits\n\n- Added login [1a2b3c4]\n- Probably improved speed\n1. Fixed a crash [5d6e7f8]";
+    let (out, dropped) = strip_uncited(text);
+    assert_eq!(out, "# Commits\n\n- Added login [1a2b3c4]\n1. Fixed a crash [5d6e7f8]");
+    assert_eq!(dropped, 1);
+}
+
+#[test]
+fn strict_mode_judges_prose_by_sentence() {
+    let text = "The login page was added [1a2b3c4]. This is exciting! Tests were fixed. [5d6e7f8]";
+    let (out, dropped) = strip_uncited(text);
+    assert_eq!(out, "The login page was added [1a2b3c4]. Tests were fixed. [5d6e7f8]");
+    assert_eq!(dropped, 1);
+}
+
+#[test]
+fn citations_become_markdown_links() {
+    let base = "https://github.com/o/r/commit";
+    assert_eq!(
+        link_citations("Added login [1a2b3c4].", base),
+        "Added login [1a2b3c4](https://github.com/o/r/commit/1a2b3c4)."
+    );
+    assert_eq!(
+        link_citations("Both [1a2b3c4, 5d6e7f8], not [this] or [1a2b3c4](x)", base),
+        "Both [1a2b3c4](https://github.com/o/r/commit/1a2b3c4), [5d6e7f8](https://github.com/o/r/commit/5d6e7f8), not [this] or [1a2b3c4](x)"
+    );
+}
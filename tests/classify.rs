@@ -0,0 +1,80 @@
+mod common;
+
+use common::{FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::classify::{CommitKind, RiskLevel};
+
+fn options() -> AnalysisOptions {
+    AnalysisOptions { num_commits: 3, classify: true, ..AnalysisOptions::default() }
+}
+
+fn short_ids(fixture: &FixtureRepo) -> Vec<String> {
+    let (commits, _) = wtf::git::select_commits(&fixture.repo, 3).unwrap();
+    commits.iter().map(|c| c.as_object().short_id().unwrap().as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn classifies_every_commit_in_one_request() {
+    let fixture = FixtureRepo::linear(4);
+    let ids = short_ids(&fixture);
+    // Out of order, with a commit that was not analyzed
+    let reply = format!(
+        r#"{{"commits": [
+            {{"sha": "{}", "kind": "docs", "risk": "low", "reason": "Adds a file. "}},
+            {{"sha": "{}", "kind": "feature", "risk": "high", "reason": "Adds a file."}},
+            {{"sha": "0000000", "kind": "fix", "risk": "low", "reason": "Made up."}}
+        ]}}"#,
+        ids[2], ids[0]
+    );
+    let provider = MockProvider::with_replies(&["Project.", "Commits.", "Edits.", &reply]);
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options()).await.unwrap();
+
+    let dashboard = report.classification.unwrap();
+    let classified: Vec<_> = dashboard.commits.iter().map(|c| (c.short_id.as_str(), c.kind, c.risk)).collect();
+    assert_eq!(
+        classified,
+        [(ids[0].as_str(), CommitKind::Feature, RiskLevel::High), (ids[2].as_str(), CommitKind::Docs, RiskLevel::Low)]
+    );
+    assert_eq!(dashboard.commits[1].reason, "Adds a file.");
+    assert_eq!(dashboard.kinds[&CommitKind::Feature], 1);
+    assert_eq!(dashboard.kinds[&CommitKind::Refactor], 0);
+    assert_eq!(dashboard.risks[&RiskLevel::Medium], 0);
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 4);
+    let question = &requests[3][1].content;
+    assert!(question.contains(&format!("Short SHA: {}", ids[1])), "{}", question);
+    assert!(question.contains("Files: src/file2.txt"));
+    // The classification is a request of its own, not part of the report conversation
+    assert_eq!(requests[3][0].role, "system");
+    assert!(!requests[3].iter().any(|m| m.content == "Commits."));
+}
+
+#[tokio::test]
+async fn invalid_classifications_are_asked_again() {
+    let fixture = FixtureRepo::linear(2);
+    let ids = short_ids(&fixture);
+    let valid = format!(
+        r#"{{"commits": [{{"sha": "{}", "kind": "chore", "risk": "low", "reason": "x"}}, {{"sha": "{}", "kind": "chore", "risk": "low", "reason": "y"}}]}}"#,
+        ids[0], ids[1]
+    );
+    let invalid = valid.replace("chore", "cleanup");
+    let provider = MockProvider::with_replies(&["Project.", "Commits.", "Edits.", &invalid, &valid]);
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options()).await.unwrap();
+
+    assert_eq!(report.classification.unwrap().kinds[&CommitKind::Chore], 2);
+    assert_eq!(provider.requests().len(), 5);
+}
+
+#[test]
+fn estimate_counts_the_classification_request() {
+    let fixture = FixtureRepo::linear(4);
+    let input = analysis::prepare(&fixture.repo, &options()).unwrap();
+
+    let plain = input.estimate(&AnalysisOptions { classify: false, ..options() }, usize::MAX);
+    let classified = input.estimate(&options(), usize::MAX);
+    assert_eq!(classified.requests, plain.requests + 1);
+    assert!(classified.prompt_tokens > plain.prompt_tokens);
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis;
+use wtf::commit::{self, CommitReport, FileChange};
+use wtf::git;
+use wtf::render::OutputFormat;
+use wtf::WtfError;
+
+#[test]
+fn any_revision_resolves_to_its_commit() {
+    let mut fixture = FixtureRepo::linear(3);
+    let previous = fixture.head();
+    let head = fixture.commit("Add notes", &[Change::Write("notes.txt", b"one\ntwo\n")]);
+
+    assert_eq!(git::resolve_commit(&fixture.repo, "HEAD").unwrap().id(), head);
+    assert_eq!(git::resolve_commit(&fixture.repo, "HEAD~1").unwrap().id(), previous);
+    assert_eq!(git::resolve_commit(&fixture.repo, &head.to_string()[..8]).unwrap().id(), head);
+    for rev in ["no-such-branch", "HEAD~40", "a..b..c"] {
+        let err = git::resolve_commit(&fixture.repo, rev).unwrap_err();
+        assert!(matches!(&err, WtfError::UnknownRevision { rev: r } if r == rev), "{}: {:?}", rev, err);
+    }
+}
+
+#[tokio::test]
+async fn the_report_shows_the_commit_above_the_explanation() {
+    let mut fixture = FixtureRepo::linear(2);
+    fixture.commit("Add notes\n\nThey were missing.", &[Change::Write("notes.txt", b"one\ntwo\n")]);
+    let commit = git::resolve_commit(&fixture.repo, "HEAD").unwrap();
+    let provider = MockProvider::with_replies(&["It adds a notes file."]);
+
+    let estimate = analysis::estimate_commit(&fixture.repo, &commit, 10_000, &[]).unwrap();
+    let explanation = analysis::explain_commit(&fixture.repo, &commit, &provider, 10_000, &[]).await.unwrap();
+    let report = CommitReport::new(&fixture.repo, &commit, explanation).unwrap();
+
+    assert_eq!(provider.requests().len(), 1);
+    assert_eq!(estimate.requests, 1);
+    assert_eq!(report.files, [FileChange { path: "notes.txt".to_string(), added: 2, removed: 0 }]);
+    assert_eq!(report.message, "Add notes\n\nThey were missing.");
+    let markdown = commit::render(&report, OutputFormat::Markdown);
+    assert!(markdown.starts_with(&format!("## Commit {}: Add notes\n", &report.sha[..7])), "{}", markdown);
+    assert!(markdown.contains("- notes.txt: +2 -0 lines\n"), "{}", markdown);
+    assert!(markdown.ends_with("It adds a notes file.\n"), "{}", markdown);
+    let json: serde_json::Value = serde_json::from_str(&commit::render(&report, OutputFormat::Json)).unwrap();
+    assert_eq!(json["explanation"], "It adds a notes file.");
+}
@@ -0,0 +1,328 @@
+//! Shared helpers for the integration tests: programmatically built fixture
+//! repositories and a mock language model provider.
+
+#![allow(dead_code)]
+
+use git2::build::TreeUpdateBuilder;
+use git2::{FileMode, Oid, Repository, Signature, Time};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use wtf::provider::{BoxFuture, LlmProvider, Message};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A change applied to the tree of the parent commit.
+pub enum Change<'a> {
+    Write(&'a str, &'a [u8]),
+    /// Like `Write`, for paths that are not valid UTF-8.
+    WriteRaw(&'a [u8], &'a [u8]),
+    Delete(&'a str),
+    Rename(&'a str, &'a str),
+}
+
+/// A throwaway repository in the system temp directory, removed on drop.
+pub struct FixtureRepo {
+    pub dir: PathBuf,
+    pub repo: Repository,
+    clock: i64,
+    author: (String, String),
+}
+
+impl FixtureRepo {
+    pub fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "wtf-fixture-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).expect("init fixture repository");
+        FixtureRepo {
+            dir,
+            repo,
+            clock: 1_700_000_000,
+            author: ("Fixture Author".to_string(), "fixture@example.com".to_string()),
+        }
+    }
+
+    /// Author (and commit) everything from now on as `name <email>`.
+    pub fn set_author(&mut self, name: &str, email: &str) {
+        self.author = (name.to_string(), email.to_string());
+    }
+
+    /// The time the last commit was made at, seconds since the epoch.
+    pub fn clock(&self) -> i64 {
+        self.clock
+    }
+
+    /// A repository with `n` commits, each adding one file.
+    pub fn linear(n: usize) -> Self {
+        let mut fixture = FixtureRepo::new();
+        fixture.commit("Add README", &[Change::Write("README.md", b"# Fixture\n\nA test project.\n")]);
+        for i in 1..n {
+            let path = format!("src/file{}.txt", i);
+            let content = format!("content of file {}\n", i);
+            fixture.commit(
+                &format!("Add file {}", i),
+                &[Change::Write(&path, content.as_bytes())],
+            );
+        }
+        fixture
+    }
+
+    /// A feature branch merged back into `master` with a merge commit.
+    pub fn with_merge() -> Self {
+        let mut fixture = FixtureRepo::linear(2);
+        let base = fixture.head();
+        let main = fixture.commit("Change on master", &[Change::Write("main.txt", b"main\n")]);
+        let feature = fixture.commit_on(
+            "refs/heads/feature",
+            &[base],
+            "Change on feature",
+            &[Change::Write("feature.txt", b"feature\n")],
+        );
+        fixture.commit_on(
+            "HEAD",
+            &[main, feature],
+            "Merge branch 'feature'",
+            &[Change::Write("feature.txt", b"feature\n")],
+        );
+        fixture
+    }
+
+    /// A history in which a file is renamed.
+    pub fn with_rename() -> Self {
+        let mut fixture = FixtureRepo::linear(2);
+        fixture.commit(
+            "Rename file",
+            &[Change::Rename("src/file1.txt", "src/renamed.txt")],
+        );
+        fixture
+    }
+
+    /// A history containing a binary file change.
+    pub fn with_binary() -> Self {
+        let mut fixture = FixtureRepo::linear(2);
+        fixture.commit(
+            "Add logo",
+            &[Change::Write("logo.png", &[0x89, b'P', b'N', b'G', 0, 0, 0xff, 0xfe])],
+        );
+        fixture.commit(
+            "Update logo",
+            &[Change::Write("logo.png", &[0x89, b'P', b'N', b'G', 0, 1, 0xff, 0xfd])],
+        );
+        fixture
+    }
+
+    /// A latin-1 commit message, declared with an `encoding` header, on top
+    /// of a commit that is latin-1 but does not say so.
+    pub fn with_latin1_messages() -> Self {
+        let mut fixture = FixtureRepo::linear(2);
+        fixture.commit_raw(b"Corrige l'\xe9tiquette\n", b"Andr\xe9", None);
+        fixture.commit_raw(b"F\xfcge Gr\xf6\xdfe hinzu\n", b"J\xfcrgen", Some("ISO-8859-1"));
+        fixture
+    }
+
+    /// A file whose name is not valid UTF-8.
+    pub fn with_non_utf8_path() -> Self {
+        let mut fixture = FixtureRepo::linear(2);
+        fixture.commit(
+            "Add latin-1 file name",
+            &[Change::WriteRaw(b"caf\xe9.txt", b"coffee\n")],
+        );
+        fixture
+    }
+
+    /// HEAD points at an orphan branch with its own unrelated history.
+    pub fn with_orphan_branch() -> Self {
+        let mut fixture = FixtureRepo::linear(3);
+        fixture
+            .repo
+            .set_head("refs/heads/orphan")
+            .expect("switch to orphan branch");
+        fixture.commit_on(
+            "HEAD",
+            &[],
+            "Start orphan history",
+            &[Change::Write("ORPHAN.md", b"# Orphan\n")],
+        );
+        fixture
+    }
+
+    /// The commit HEAD points at.
+    pub fn head(&self) -> Oid {
+        self.repo.head().unwrap().target().unwrap()
+    }
+
+    /// Commit `changes` on top of HEAD (or as a root commit if HEAD is unborn).
+    pub fn commit(&mut self, message: &str, changes: &[Change]) -> Oid {
+        let parents: Vec<Oid> = self.repo.head().ok().and_then(|h| h.target()).into_iter().collect();
+        self.commit_on("HEAD", &parents, message, changes)
+    }
+
+    /// Commit `changes` applied to the first parent's tree and update `refname`.
+    pub fn commit_on(&mut self, refname: &str, parents: &[Oid], message: &str, changes: &[Change]) -> Oid {
+        let parent_commits: Vec<git2::Commit> = parents
+            .iter()
+            .map(|oid| self.repo.find_commit(*oid).unwrap())
+            .collect();
+
+        let base_tree = match parent_commits.first() {
+            Some(parent) => parent.tree().unwrap(),
+            None => {
+                let empty = self.repo.treebuilder(None).unwrap().write().unwrap();
+                self.repo.find_tree(empty).unwrap()
+            }
+        };
+
+        let mut update = TreeUpdateBuilder::new();
+        for change in changes {
+            match change {
+                Change::Write(path, content) => {
+                    let blob = self.repo.blob(content).unwrap();
+                    update.upsert(*path, blob, FileMode::Blob);
+                }
+                Change::WriteRaw(path, content) => {
+                    let blob = self.repo.blob(content).unwrap();
+                    update.upsert(*path, blob, FileMode::Blob);
+                }
+                Change::Delete(path) => {
+                    update.remove(*path);
+                }
+                Change::Rename(from, to) => {
+                    let entry = base_tree.get_path(std::path::Path::new(from)).unwrap();
+                    update.remove(*from);
+                    update.upsert(*to, entry.id(), FileMode::Blob);
+                }
+            }
+        }
+        let tree_oid = update.create_updated(&self.repo, &base_tree).unwrap();
+        let tree = self.repo.find_tree(tree_oid).unwrap();
+
+        self.clock += 60;
+        let sig = Signature::new(&self.author.0, &self.author.1, &Time::new(self.clock, 0)).unwrap();
+        let parent_refs: Vec<&git2::Commit> = parent_commits.iter().collect();
+        self.repo
+            .commit(Some(refname), &sig, &sig, message, &tree, &parent_refs)
+            .unwrap()
+    }
+
+    /// Write a commit object by hand, so the message and author name can be
+    /// arbitrary bytes with an optional `encoding` header. Reuses HEAD's tree.
+    pub fn commit_raw(&mut self, message: &[u8], author: &[u8], encoding: Option<&str>) -> Oid {
+        let parent = self.head();
+        let tree = self.repo.find_commit(parent).unwrap().tree_id();
+        self.clock += 60;
+
+        let mut signature = author.to_vec();
+        signature.extend_from_slice(format!(" <fixture@example.com> {} +0000", self.clock).as_bytes());
+
+        let mut raw = format!("tree {}\nparent {}\n", tree, parent).into_bytes();
+        for header in [&b"author "[..], &b"committer "[..]] {
+            raw.extend_from_slice(header);
+            raw.extend_from_slice(&signature);
+            raw.push(b'\n');
+        }
+        if let Some(encoding) = encoding {
+            raw.extend_from_slice(format!("encoding {}\n", encoding).as_bytes());
+        }
+        raw.push(b'\n');
+        raw.extend_from_slice(message);
+
+        let oid = self.repo.odb().unwrap().write(git2::ObjectType::Commit, &raw).unwrap();
+        let head = self.repo.head().unwrap();
+        self.repo
+            .reference(head.name().unwrap(), oid, true, "commit_raw")
+            .unwrap();
+        oid
+    }
+}
+
+impl Drop for FixtureRepo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A provider that answers with scripted replies (or a numbered canned reply
+/// once those run out) and records the conversations it was sent.
+#[derive(Default)]
+pub struct MockProvider {
+    pub requests: Mutex<Vec<Vec<Message>>>,
+    replies: Mutex<VecDeque<String>>,
+    max_context: Option<usize>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        MockProvider::default()
+    }
+
+    /// A provider that answers with `replies`, in order.
+    pub fn with_replies(replies: &[&str]) -> Self {
+        MockProvider {
+            requests: Mutex::default(),
+            replies: Mutex::new(replies.iter().map(|r| r.to_string()).collect()),
+            max_context: None,
+        }
+    }
+
+    /// The same provider with a context window of `tokens`.
+    pub fn with_max_context(mut self, tokens: usize) -> Self {
+        self.max_context = Some(tokens);
+        self
+    }
+
+    /// The conversations received so far.
+    pub fn requests(&self) -> Vec<Vec<Message>> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl LlmProvider for MockProvider {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, wtf::Result<String>> {
+        Box::pin(async move {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(messages.to_vec());
+            match self.replies.lock().unwrap().pop_front() {
+                Some(reply) => Ok(reply),
+                None => Ok(format!("mock response {}", requests.len())),
+            }
+        })
+    }
+
+    fn max_context(&self) -> usize {
+        self.max_context.unwrap_or(wtf::provider::DEFAULT_MAX_CONTEXT)
+    }
+}
+
+/// Compare `actual` against the golden file `tests/snapshots/<name>.snap`.
+///
+/// Run with `UPDATE_SNAPSHOTS=1` to (re)write the golden files after an
+/// intentional formatting change.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.snap", name));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {}; run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    assert_eq!(
+        expected, actual,
+        "snapshot {} does not match; run with UPDATE_SNAPSHOTS=1 to accept the change",
+        name
+    );
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::complexity::{self, Effort};
+
+#[test]
+fn recognizes_test_files() {
+    for path in ["tests/render.rs", "src/__tests__/app.js", "pkg/foo_test.go", "web/app.test.ts", "test_main.py", "spec/models/user_spec.rb"] {
+        assert!(complexity::is_test_path(path), "{}", path);
+    }
+    for path in ["src/main.rs", "src/testing.rs", "docs/contest.md", "latest/notes.txt"] {
+        assert!(!complexity::is_test_path(path), "{}", path);
+    }
+}
+
+#[test]
+fn score_grows_with_size_spread_and_missing_tests() {
+    let small = complexity::score(5, 1, 0.0, Some(1.0));
+    let large = complexity::score(500, 1, 0.0, Some(1.0));
+    let spread = complexity::score(500, 20, 0.0, Some(1.0));
+    let untested = complexity::score(500, 20, 0.0, Some(0.0));
+    assert!(small < large && large < spread && spread < untested);
+    assert_eq!(complexity::score(0, 0, 0.0, None), 0);
+    assert!(complexity::score(100_000, 1000, 20.0, Some(0.0)) <= 100);
+}
+
+#[test]
+fn scores_a_commit_from_its_diff() {
+    let mut fixture = FixtureRepo::new();
+    fixture.commit("Add code", &[Change::Write("src/lib.rs", b"a\nb\nc\nd\n")]);
+    let lines: String = (0..40).map(|i| format!("line {}\n", i)).collect();
+    fixture.commit(
+        "Rewrite everything",
+        &[
+            Change::Write("src/lib.rs", lines.as_bytes()),
+            Change::Write("src/other.rs", lines.as_bytes()),
+            Change::Write("tests/lib.rs", b"check\n"),
+        ],
+    );
+
+    let commit = fixture.repo.find_commit(fixture.head()).unwrap();
+    let score = complexity::score_commit(&fixture.repo, &commit, &[]).unwrap();
+    assert_eq!(score.files, 3);
+    assert_eq!(score.hunks, 3);
+    assert_eq!((score.added, score.removed), (81, 4));
+    assert!(score.hunk_entropy > 0.0);
+    let ratio = score.test_ratio.unwrap();
+    assert!(ratio > 0.0 && ratio < 0.05, "{}", ratio);
+
+    let root = commit.parent(0).unwrap();
+    let first = complexity::score_commit(&fixture.repo, &root, &[]).unwrap();
+    assert_eq!((first.files, first.added, first.hunk_entropy), (1, 4, 0.0));
+    assert_eq!(first.effort, Effort::Low);
+    assert!(first.score < score.score);
+}
+
+#[tokio::test]
+async fn report_lists_review_effort_highest_first() {
+    let mut fixture = FixtureRepo::linear(2);
+    let lines: String = (0..200).map(|i| format!("line {}\n", i)).collect();
+    fixture.commit("Add a big file", &[Change::Write("src/big.rs", lines.as_bytes())]);
+    let provider = MockProvider::new();
+
+    let options = AnalysisOptions { num_commits: 3, ..AnalysisOptions::default() };
+    let report = analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    assert_eq!(report.review_effort.len(), 3);
+    let big = commit_short_id(&fixture);
+    assert_eq!(report.review_effort[0].short_id, big);
+    assert!(report.review_effort.windows(2).all(|pair| pair[0].score >= pair[1].score));
+}
+
+fn commit_short_id(fixture: &FixtureRepo) -> String {
+    let commit = fixture.repo.find_commit(fixture.head()).unwrap();
+    let short_id = commit.as_object().short_id().unwrap();
+    short_id.as_str().unwrap().to_string()
+}
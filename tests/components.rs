@@ -0,0 +1,116 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::components::{self, GroupBy, GroupSpec};
+use wtf::WtfError;
+
+const CONFIG: &str = "[package]\nname = \"not = a component\"\n\n[components]\n# the backend\n\"API server\" = [\"server/**\", \"api\"]\nfrontend = \"web/\"\nempty = []\n\n[other]\nx = \"y\"\n";
+
+fn monorepo() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Add login endpoint", &[Change::Write("server/auth/login.rs", b"fn login() {}\n")]);
+    fixture.commit(
+        "Show login form",
+        &[Change::Write("web/login.tsx", b"<form/>\n"), Change::Write("api/login.json", b"{}\n")],
+    );
+    fixture.commit("Update docs", &[Change::Write("docs/login.md", b"# Login\n")]);
+    fixture
+}
+
+#[test]
+fn parses_group_specs() {
+    assert_eq!("dir".parse(), Ok(GroupSpec::Directory(1)));
+    assert_eq!("dir:3".parse(), Ok(GroupSpec::Directory(3)));
+    assert_eq!("components".parse(), Ok(GroupSpec::Components));
+    assert!("dir:0".parse::<GroupSpec>().is_err());
+    assert!("dir:x".parse::<GroupSpec>().is_err());
+    assert!("files".parse::<GroupSpec>().is_err());
+}
+
+#[test]
+fn components_come_from_the_config_table() {
+    let parsed = components::parse_components(CONFIG);
+    let names: Vec<_> = parsed.iter().map(|c| (c.name.as_str(), c.patterns.clone())).collect();
+    assert_eq!(
+        names,
+        [
+            ("API server", vec!["server/**".to_string(), "api".to_string()]),
+            ("frontend", vec!["web/".to_string()]),
+        ]
+    );
+
+    let group_by = GroupBy::Components(parsed);
+    assert_eq!(group_by.group_of("server/auth/login.rs"), "API server");
+    assert_eq!(group_by.group_of("api/login.json"), "API server");
+    assert_eq!(group_by.group_of("api"), "API server");
+    assert_eq!(group_by.group_of("apis/x"), "Other");
+    assert_eq!(group_by.group_of("web/login.tsx"), "frontend");
+    assert_eq!(GroupBy::Directory { depth: 2 }.group_of("server/auth/login.rs"), "server/auth");
+    assert_eq!(GroupBy::Directory { depth: 2 }.group_of("README.md"), "(top level)");
+}
+
+#[test]
+fn resolving_components_needs_a_config() {
+    let fixture = monorepo();
+    let err = GroupBy::resolve(GroupSpec::Components, &fixture.repo).unwrap_err();
+    assert!(matches!(err, WtfError::InvalidConfig { .. }));
+    assert_eq!(err.exit_code(), wtf::error::exit_code::USAGE);
+
+    std::fs::write(fixture.dir.join(components::CONFIG_FILE), CONFIG).unwrap();
+    let group_by = GroupBy::resolve(GroupSpec::Components, &fixture.repo).unwrap();
+    assert!(matches!(group_by, GroupBy::Components(ref c) if c.len() == 2));
+}
+
+#[test]
+fn groups_commits_and_patches() {
+    let group_by = GroupBy::Directory { depth: 1 };
+    let web = vec!["web/a.ts".to_string(), "server/b.rs".to_string()];
+    let server = vec!["server/c.rs".to_string()];
+    let groups = group_by.group_commits(&[("first", &web), ("second", &server), ("empty", &[])]);
+    assert_eq!(
+        groups,
+        [
+            ("server".to_string(), vec!["first", "second"]),
+            ("web".to_string(), vec!["first"]),
+            ("Other".to_string(), vec!["empty"]),
+        ]
+    );
+
+    let patch = "Commit: abc1234\ndiff --git a/web/a.ts b/web/a.ts\n@@ -1 +1 @@\n-a\n+b\ndiff --git a/server/b.rs b/server/b.rs\n@@ -1 +1 @@\n-c\n+d\n";
+    let groups = group_by.group_patches(&[patch.to_string()]);
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].0, "server");
+    assert_eq!(groups[0].1, "Commit: abc1234\ndiff --git a/server/b.rs b/server/b.rs\n@@ -1 +1 @@\n-c\n+d\n");
+    assert!(!groups[1].1.contains("server/b.rs"));
+}
+
+#[tokio::test]
+async fn grouped_report_organizes_prompts_by_component() {
+    let fixture = monorepo();
+    std::fs::write(fixture.dir.join(components::CONFIG_FILE), CONFIG).unwrap();
+    let provider = MockProvider::new();
+    let options = AnalysisOptions {
+        num_commits: 3,
+        group_by: Some(GroupBy::resolve(GroupSpec::Components, &fixture.repo).unwrap()),
+        ..AnalysisOptions::default()
+    };
+
+    analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    let requests = provider.requests();
+    let commits = &requests[1].last().unwrap().content;
+    assert!(commits.contains("grouped by component"));
+    let api = commits.find("Component: API server").unwrap();
+    let frontend = commits.find("Component: frontend").unwrap();
+    let other = commits.find("Component: Other").unwrap();
+    assert!(api < frontend && frontend < other, "{}", commits);
+    // "Show login form" touches both components
+    assert_eq!(commits.matches("Show login form").count(), 2);
+
+    let edits = &requests[2].last().unwrap().content;
+    let frontend = &edits[edits.find("Component: frontend").unwrap()..];
+    let frontend = &frontend[..frontend.find("=====").unwrap_or(frontend.len())];
+    assert!(frontend.contains("web/login.tsx"));
+    assert!(!frontend.contains("api/login.json"));
+}
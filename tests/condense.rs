@@ -0,0 +1,116 @@
+mod common;
+
+use common::{Change, FixtureRepo};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::condense;
+
+const OLD: &str = "
+struct Config { path: String }
+
+impl Config {
+    fn load() -> Config { todo!() }
+    fn save(&self) {}
+}
+
+// helper
+fn old_helper() {}
+
+mod tests {
+    fn check() {}
+}
+";
+
+const NEW: &str = "
+struct Config { path: String }
+
+impl Config {
+    fn load() -> Option<Config> { None }
+    // saving stays the same
+    fn save(&self) {}
+}
+
+struct Options;
+
+impl Default for Options {
+    fn default() -> Self { Options }
+}
+
+mod tests {
+    fn check() { assert!(true) }
+}
+";
+
+#[test]
+fn compares_rust_items_by_name() {
+    let old = condense::rust_items(OLD).unwrap();
+    let new = condense::rust_items(NEW).unwrap();
+    let changes = condense::compare_items(&old, &new);
+
+    assert_eq!(changes.modified, ["fn Config::load", "fn tests::check"]);
+    assert_eq!(changes.added, ["struct Options", "impl Default for Options"]);
+    assert_eq!(changes.removed, ["fn old_helper"]);
+    assert!(condense::rust_items("fn broken(").is_none());
+}
+
+#[test]
+fn items_sharing_a_name_are_paired_in_order() {
+    let twins = |online: &str| {
+        format!(
+            "#[cfg(feature = \"online\")]\nconst VERSION: &str = \"{}\";\n\
+             #[cfg(not(feature = \"online\"))]\nconst VERSION: &str = \"offline\";\n",
+            online
+        )
+    };
+    let old = condense::rust_items(&twins("1.0")).unwrap();
+
+    let unchanged = condense::compare_items(&old, &condense::rust_items(&twins("1.0")).unwrap());
+    assert!(unchanged.is_empty(), "{:?}", unchanged);
+    let changed = condense::compare_items(&old, &condense::rust_items(&twins("2.0")).unwrap());
+    assert_eq!(changed.modified, ["const VERSION"]);
+}
+
+#[test]
+fn lists_hunk_contexts_once() {
+    let hunks = [
+        "@@ -10,3 +10,4 @@ def handle(request):\n a\n b\n",
+        "@@ -20,2 +21,2 @@ def handle(request):\n c\n",
+        "@@ -40 +41 @@\n d\n",
+        "@@ -50,2 +51,3 @@ class Server:\n e\n",
+    ];
+    assert_eq!(condense::hunk_contexts(&hunks), ["def handle(request):", "class Server:"]);
+}
+
+#[test]
+fn large_diffs_are_condensed_in_the_prompt() {
+    let mut fixture = FixtureRepo::linear(1);
+    let functions: String = (0..200).map(|i| format!("fn generated_{}() -> usize {{ {} * 2 + 1 }}\n", i, i)).collect();
+    fixture.commit(
+        "Add many functions",
+        &[Change::Write("src/many.rs", functions.as_bytes()), Change::Write("src/small.rs", b"fn tiny_body() {}\n")],
+    );
+    let options = AnalysisOptions { num_commits: 2, max_diff_tokens: 2_000, ..AnalysisOptions::default() };
+
+    let input = analysis::prepare(&fixture.repo, &options).unwrap();
+
+    let patch = &input.file_changes[0];
+    assert!(patch.contains("diff --git a/src/many.rs b/src/many.rs"), "{}", patch);
+    assert!(patch.contains("Condensed: +200 -0 lines"));
+    assert!(patch.contains("Added: fn generated_0, fn generated_1,"));
+    assert!(!patch.contains("* 2 + 1"));
+    // Small files keep their diff
+    assert!(patch.contains("fn tiny_body() {}"));
+}
+
+#[test]
+fn diffs_within_the_budget_are_left_whole() {
+    let mut fixture = FixtureRepo::linear(1);
+    let functions: String = (0..200).map(|i| format!("fn generated_{}() -> usize {{ {} * 2 + 1 }}\n", i, i)).collect();
+    fixture.commit("Add many functions", &[Change::Write("src/many.rs", functions.as_bytes())]);
+    let options = AnalysisOptions { num_commits: 2, ..AnalysisOptions::default() };
+
+    let input = analysis::prepare(&fixture.repo, &options).unwrap();
+
+    let patch = &input.file_changes[0];
+    assert!(!patch.contains("Condensed:"), "{}", patch);
+    assert!(patch.contains("fn generated_199() -> usize { 199 * 2 + 1 }"));
+}
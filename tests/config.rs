@@ -0,0 +1,62 @@
+mod common;
+
+use common::FixtureRepo;
+use wtf::config::{self, Defaults};
+use wtf::error::WtfError;
+use wtf::render::OutputFormat;
+
+#[test]
+fn defaults_are_read_from_the_top_of_config_toml() {
+    let contents = "\u{feff}# wtf settings\nprovider = \"anthropic\"\nnum_commits = 1_0 # a few more\ntemperature = 0.2\n\
+        exclude = [\"dist/**\", '*.min.js',]\nformat = \"markdown\"\n\n[providers.anthropic]\nmodel = \"claude-haiku\"\n";
+
+    let defaults = Defaults::parse(contents).unwrap();
+
+    assert_eq!(defaults.provider.as_deref(), Some("anthropic"));
+    assert_eq!(defaults.num_commits, Some(10));
+    assert_eq!(defaults.temperature, Some(0.2));
+    assert_eq!(defaults.exclude, ["dist/**", "*.min.js"]);
+    assert_eq!(defaults.format, Some(OutputFormat::Markdown));
+    // Only the section sets a model
+    assert_eq!(defaults.model, None);
+    assert_eq!(config::toml_table(contents, "providers.anthropic"), [("model".to_string(), "claude-haiku".to_string())]);
+}
+
+#[test]
+fn wrongly_typed_defaults_name_their_key() {
+    let err = Defaults::parse("model = \"gpt-4o\"\nnum_commits = \"ten\"\n").unwrap_err();
+    assert!(err.starts_with("num_commits: "), "{}", err);
+
+    let dir = std::env::temp_dir().join(format!("wtf-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    assert_eq!(Defaults::load(&dir).unwrap(), Defaults::default());
+    std::fs::write(dir.join(config::CONFIG_FILE), "exclude = \"dist/**\"\n").unwrap();
+    assert!(matches!(Defaults::load(&dir), Err(WtfError::InvalidConfig { .. })));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn the_repositorys_wtf_toml_overrides_config_toml() {
+    let fixture = FixtureRepo::linear(1);
+    let dir = std::env::temp_dir().join(format!("wtf-config-merge-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let global = "model = \"gpt-4o\"\nnum_commits = 3\nexclude = [\"*.lock\"]\n";
+    std::fs::write(dir.join(config::CONFIG_FILE), global).unwrap();
+    std::fs::write(
+        fixture.dir.join(".wtf.toml"),
+        "num_commits = 20\nexclude = [\"dist/**\"]\n\n[components]\napi = \"server/\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(fixture.dir.join("src")).unwrap();
+
+    let defaults = Defaults::for_repository(Some(&dir), &fixture.dir.join("src")).unwrap();
+
+    assert_eq!(defaults.model.as_deref(), Some("gpt-4o"));
+    assert_eq!(defaults.num_commits, Some(20));
+    assert_eq!(defaults.exclude, ["*.lock", "dist/**"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    std::fs::write(fixture.dir.join(".wtf.toml"), "format = \"yaml\"\n").unwrap();
+    let err = Defaults::for_repository(None, &fixture.dir).unwrap_err();
+    assert!(err.to_string().contains("format: unknown variant `yaml`"), "{}", err);
+}
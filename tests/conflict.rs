@@ -0,0 +1,71 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::conflict;
+use wtf::render::OutputFormat;
+use wtf::WtfError;
+
+// master and feature both change notes.txt, and feature deletes old.txt
+// that master changes; feature is being merged into master
+fn repo_in_conflicted_merge() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    let base =
+        fixture.commit("Add notes", &[Change::Write("notes.txt", b"one\n"), Change::Write("old.txt", b"old\n")]);
+    fixture.commit(
+        "Say it on master",
+        &[Change::Write("notes.txt", b"master\n"), Change::Write("old.txt", b"older\n")],
+    );
+    let feature = fixture.commit_on(
+        "refs/heads/feature",
+        &[base],
+        "Say it on feature",
+        &[Change::Write("notes.txt", b"feature\n"), Change::Delete("old.txt")],
+    );
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    let incoming = fixture.repo.find_annotated_commit(feature).unwrap();
+    fixture.repo.merge(&[&incoming], None, None).unwrap();
+    drop(incoming);
+    fixture
+}
+
+#[test]
+fn every_version_of_a_conflicted_file_is_read() {
+    let fixture = repo_in_conflicted_merge();
+
+    let input = conflict::prepare(&fixture.repo, None).unwrap();
+
+    let paths: Vec<_> = input.files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, ["notes.txt", "old.txt"]);
+    let notes = &input.files[0];
+    assert_eq!(
+        (notes.base.as_deref(), notes.ours.as_deref(), notes.theirs.as_deref()),
+        (Some("one\n"), Some("master\n"), Some("feature\n"))
+    );
+    assert_eq!(input.files[1].theirs, None);
+    let only = conflict::prepare(&fixture.repo, Some("old.txt")).unwrap();
+    assert_eq!(only.files.len(), 1);
+}
+
+#[tokio::test]
+async fn each_file_is_explained_with_which_side_is_which() {
+    let fixture = repo_in_conflicted_merge();
+    let input = conflict::prepare(&fixture.repo, Some("notes.txt")).unwrap();
+    let provider = MockProvider::with_replies(&["Both sides reworded the line; keep feature's."]);
+
+    let report = conflict::explain(&input, &provider, true).await.unwrap();
+
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.contains("In progress: git merge\nOurs: the branch you are on (HEAD)\n"), "{}", question);
+    assert!(question.contains("\n--- Theirs ---\nfeature\n"), "{}", question);
+    assert!(question.contains("propose the resolved content"), "{}", question);
+    let markdown = conflict::render(&report, OutputFormat::Markdown);
+    assert!(markdown.contains("- 1 file(s) with conflicts\n"), "{}", markdown);
+    assert!(markdown.ends_with("## notes.txt\n\nBoth sides reworded the line; keep feature's.\n"), "{}", markdown);
+}
+
+#[test]
+fn no_conflicts_is_an_error() {
+    let fixture = FixtureRepo::linear(2);
+
+    assert!(matches!(conflict::prepare(&fixture.repo, None), Err(WtfError::NoConflicts)));
+}
@@ -0,0 +1,38 @@
+use wtf::analysis::RunEstimate;
+use wtf::cost;
+use wtf::provider::{Price, Usage};
+
+#[test]
+fn estimates_price_prompts_and_expected_replies() {
+    let price = Price { input: 2.0, output: 10.0 };
+    let estimate = RunEstimate { requests: 5, prompt_tokens: 500_000 };
+
+    // $1 for the prompts, plus about 400 reply tokens per request
+    let dollars = cost::estimate(&estimate, price);
+    assert!((dollars - 1.02).abs() < 1e-9, "{}", dollars);
+
+    assert_eq!(cost::estimate(&estimate, Price::FREE), 0.0);
+    assert_eq!(cost::format_dollars(1.02), "$1.02");
+    assert_eq!(cost::format_dollars(0.0001), "< $0.01");
+    assert_eq!(cost::format_dollars(0.0), "$0.00");
+}
+
+#[test]
+fn recorded_usage_adds_up_until_a_price_is_unknown() {
+    let before = cost::total();
+    let usage = Usage { input_tokens: 1_000_000, output_tokens: 100_000 };
+    cost::record(usage, Some(Price { input: 1.0, output: 5.0 }));
+    cost::record(usage, Some(Price::FREE));
+
+    let total = cost::total();
+    assert_eq!(total.requests - before.requests, 2);
+    assert_eq!(total.input_tokens - before.input_tokens, 2_000_000);
+    assert_eq!(total.output_tokens - before.output_tokens, 200_000);
+    let spent = total.cost.unwrap() - before.cost.unwrap();
+    assert!((spent - 1.5).abs() < 1e-9, "{}", spent);
+    assert!(cost::summary(&total).contains("$1.50"));
+
+    // Past a model without a price, the total can only be guessed
+    cost::record(usage, None);
+    assert_eq!(cost::total().cost, None);
+}
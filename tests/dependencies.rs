@@ -0,0 +1,43 @@
+mod common;
+
+use common::{Change, FixtureRepo};
+use wtf::dependencies;
+
+fn names(path: &str, content: &str) -> Vec<String> {
+    dependencies::parse(path, content).into_iter().collect()
+}
+
+#[test]
+fn parses_each_manifest_kind() {
+    let cargo = "[package]\nname = \"app\"\n\n[dependencies]\nserde = \"1\"\ntokio = { version = \"1\" }\n\n[dev-dependencies.insta]\nversion = \"1\"\n\n[target.'cfg(unix)'.dependencies]\nlibc = \"0.2\"\n";
+    assert_eq!(names("Cargo.toml", cargo), ["insta", "libc", "serde", "tokio"]);
+
+    let npm = r#"{"name": "app", "dependencies": {"react": "^18"}, "devDependencies": {"jest": "^29"}}"#;
+    assert_eq!(names("web/package.json", npm), ["npm:jest", "npm:react"]);
+
+    let requirements = "# pinned\nDjango>=4.2\nrequests[socks]==2.31 ; python_version > '3'\n-r base.txt\n";
+    assert_eq!(names("requirements-dev.txt", requirements), ["pypi:django", "pypi:requests"]);
+
+    let pyproject = "[project]\nname = \"app\"\ndependencies = [\n  \"httpx>=0.27\",\n  \"Pydantic_Core\",\n]\n\n[tool.poetry.dependencies]\npython = \"^3.11\"\nrich = \"*\"\n";
+    assert_eq!(names("pyproject.toml", pyproject), ["pypi:httpx", "pypi:pydantic-core", "pypi:rich"]);
+
+    let go = "module example.com/app\n\nrequire github.com/spf13/cobra v1.8.0\n\nrequire (\n\tgolang.org/x/sync v0.6.0 // indirect\n)\n";
+    assert_eq!(names("go.mod", go), ["go:github.com/spf13/cobra", "go:golang.org/x/sync"]);
+
+    assert_eq!(names("Gemfile", "source 'https://rubygems.org'\ngem 'rails', '~> 7.1'\n"), ["gem:rails"]);
+    assert!(names("README.md", "serde = \"1\"").is_empty());
+}
+
+#[test]
+fn changes_compare_against_the_parent() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Add manifest", &[Change::Write("Cargo.toml", b"[dependencies]\nreqwest = \"0.11\"\nserde = \"1\"\n")]);
+    let swap = fixture.commit("Use ureq", &[Change::Write("Cargo.toml", b"[dependencies]\nserde = \"1\"\nureq = \"2\"\n")]);
+    let code = fixture.commit("Touch code", &[Change::Write("src/main.rs", b"fn main() {}\n")]);
+
+    let changes = dependencies::changes(&fixture.repo, &fixture.repo.find_commit(swap).unwrap()).unwrap();
+    assert_eq!(changes.added, ["ureq"]);
+    assert_eq!(changes.removed, ["reqwest"]);
+    assert!(changes.is_migration());
+    assert!(dependencies::changes(&fixture.repo, &fixture.repo.find_commit(code).unwrap()).unwrap().is_empty());
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::digest::{self, Period};
+use wtf::git;
+use wtf::render::OutputFormat;
+
+#[test]
+fn periods_end_today_and_leave_older_commits_out() {
+    let fixture = FixtureRepo::linear(3);
+    let (from, to) = Period::Week.days_ending(fixture.clock());
+    assert_eq!((git::format_date(from), git::format_date(to)), ("2023-11-08".into(), "2023-11-14".into()));
+    let (from, _) = Period::Month.days_ending(fixture.clock());
+    assert_eq!(git::format_date(from), "2023-10-16");
+
+    let input = digest::prepare(&fixture.repo, Period::Week, Period::Week.days_ending(fixture.clock())).unwrap();
+    assert_eq!((input.stats.commits, input.stats.files, input.stats.added), (3, 3, 5));
+    let later = Period::Week.days_ending(fixture.clock() + 7 * 86_400);
+    assert!(digest::prepare(&fixture.repo, Period::Week, later).unwrap().commits.is_empty());
+}
+
+#[tokio::test]
+async fn the_digest_credits_contributors_and_counts_what_changed() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.set_author("Ada Lovelace", "ada@example.com");
+    fixture.commit("Add export to CSV", &[Change::Write("src/export.rs", b"fn export() {}\nfn csv() {}\n")]);
+    fixture.commit(
+        "Fix the crash on empty files\n\nCo-authored-by: Grace Hopper <grace@example.com>",
+        &[Change::Write("src/export.rs", b"fn export() {}\n")],
+    );
+    let days = Period::Month.days_ending(fixture.clock());
+    let input = digest::prepare(&fixture.repo, Period::Month, days).unwrap();
+    assert_eq!(input.estimate().requests, 1);
+
+    let provider = MockProvider::with_replies(&[
+        r#"{"summary": " Exports arrived. ", "features": ["You can export to CSV."], "fixes": []}"#,
+    ]);
+    let digest = digest::explain(&input, &provider).await.unwrap();
+    let names: Vec<(&str, usize)> = digest.contributors.iter().map(|c| (c.name.as_str(), c.commits)).collect();
+    assert_eq!(names, [("Ada Lovelace", 2), ("Grace Hopper", 1), ("Fixture Author", 1)]);
+    let requests = provider.requests();
+    let question = &requests[0][1].content;
+    assert!(question.contains("over the month from 2023-10-16 to 2023-11-14"), "{}", question);
+    assert!(question.contains("Files: src/export.rs\nCommit: "), "{}", question);
+
+    let out = digest::render(&digest, OutputFormat::Markdown);
+    assert!(out.starts_with("## Monthly digest, 2023-10-16 to 2023-11-14\n\nExports arrived.\n"), "{}", out);
+    assert!(out.contains("## Features\n\n- You can export to CSV.\n\n## Contributors\n"), "{}", out);
+    let stats = "## In numbers\n\n3 commit(s) by 3 contributor(s), 2 file(s) changed, +5 -1 lines.\n";
+    assert!(out.ends_with(stats), "{}", out);
+}
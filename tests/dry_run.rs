@@ -0,0 +1,52 @@
+mod common;
+
+use common::FixtureRepo;
+use serde_json::{json, Value};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::dry_run::{DryRun, PLACEHOLDER_REPLY};
+use wtf::provider::Message;
+use wtf::structured::{self, Schema};
+
+#[tokio::test]
+async fn prompts_are_written_instead_of_sent() {
+    let fixture = FixtureRepo::linear(4);
+    let dir = std::env::temp_dir().join(format!("wtf-dry-run-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let provider = DryRun::to_dir(&dir);
+    let options = AnalysisOptions { num_commits: 3, ..AnalysisOptions::default() };
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    assert_eq!(report.project_description, PLACEHOLDER_REPLY);
+    assert_eq!(provider.requests(), 3);
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert!(read("request-001.txt").contains("A test project."));
+    assert!(read("request-002.txt").contains("Add file 3"));
+    assert!(read("request-003.txt").contains("+++ b/src/file3.txt"));
+    // Later prompts carry the placeholders as earlier replies
+    assert!(read("request-003.txt").contains(&format!("[assistant]\n{}", PLACEHOLDER_REPLY)));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn structured_requests_get_the_smallest_reply_their_schema_allows() {
+    let schema = Schema::new(
+        "classification",
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": { "type": "string", "enum": ["feature", "fix"] },
+                "files": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+                "risk": { "type": ["integer", "null"] },
+                "note": { "type": "string" },
+            },
+            "required": ["kind", "files", "risk"],
+            "additionalProperties": false,
+        }),
+    );
+
+    let reply: Value = structured::request(&DryRun::new(), &[Message::user("Classify")], &schema).await.unwrap();
+
+    assert_eq!(reply, json!({ "kind": "feature", "files": [""], "risk": 0 }));
+    assert!(structured::validate(&reply, &schema.schema).is_empty());
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::generated::{self, Detector, GeneratedKind};
+
+const LOCKFILE: &[u8] = b"[[package]]\nname = \"serde\"\nversion = \"1.0.0\"\n\n[[package]]\nname = \"syn\"\nversion = \"2.0.0\"\n";
+
+#[test]
+fn recognizes_generated_files_by_name() {
+    assert_eq!(generated::kind_by_path("Cargo.lock"), Some(GeneratedKind::Lockfile));
+    assert_eq!(generated::kind_by_path("web/package-lock.json"), Some(GeneratedKind::Lockfile));
+    assert_eq!(generated::kind_by_path("vendor/github.com/x/y.go"), Some(GeneratedKind::Vendored));
+    assert_eq!(generated::kind_by_path("web/node_modules/left-pad/index.js"), Some(GeneratedKind::Vendored));
+    assert_eq!(generated::kind_by_path("static/app.min.js"), Some(GeneratedKind::Minified));
+    assert_eq!(generated::kind_by_path("src/vendor.rs"), None);
+    assert_eq!(generated::kind_by_path("docs/Cargo.lock.md"), None);
+}
+
+#[test]
+fn linguist_attributes_override_names() {
+    let fixture = FixtureRepo::linear(1);
+    std::fs::write(
+        fixture.dir.join(".gitattributes"),
+        "src/schema.rs linguist-generated\nvendor/ours/** -linguist-vendored\n*.lock -linguist-generated\n",
+    )
+    .unwrap();
+    let mut detector = Detector::new(&fixture.repo);
+
+    assert_eq!(detector.kind("src/schema.rs"), Some(GeneratedKind::Generated));
+    assert_eq!(detector.kind("src/main.rs"), None);
+    assert_eq!(detector.kind("vendor/ours/lib.c"), None);
+    assert_eq!(detector.kind("vendor/theirs/lib.c"), Some(GeneratedKind::Vendored));
+    assert_eq!(detector.kind("Cargo.lock"), None);
+    assert_eq!(detector.kind("package-lock.json"), Some(GeneratedKind::Lockfile));
+}
+
+#[tokio::test]
+async fn churn_is_summarized_instead_of_sent() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Add serde",
+        &[Change::Write("src/main.rs", b"use serde;\n"), Change::Write("Cargo.lock", LOCKFILE)],
+    );
+    fixture.commit("Vendor a library", &[Change::Write("vendor/lib/lib.js", b"var a = 1;\nvar b = 2;\n")]);
+    let options = AnalysisOptions { num_commits: 2, ..AnalysisOptions::default() };
+
+    let input = analysis::prepare(&fixture.repo, &options).unwrap();
+    let churn: Vec<_> = input.generated.iter().map(|f| (f.path.as_str(), f.kind, f.added, f.removed)).collect();
+    assert_eq!(
+        churn,
+        [("vendor/lib/lib.js", GeneratedKind::Vendored, 2, 0), ("Cargo.lock", GeneratedKind::Lockfile, 7, 0)]
+    );
+    // The commit that only vendored code has no patch left
+    assert_eq!(input.file_changes.len(), 1);
+    assert!(input.file_changes[0].contains("src/main.rs"));
+    assert!(!input.file_changes[0].contains("Cargo.lock"));
+
+    let provider = MockProvider::new();
+    let report = analysis::run(&input, &provider, &options).await.unwrap();
+    assert_eq!(report.generated_churn, input.generated);
+    let requests = provider.requests();
+    let edits = &requests[2].last().unwrap().content;
+    assert!(edits.contains("Generated/vendored churn"), "{}", edits);
+    assert!(edits.contains("- Cargo.lock (lockfile, +7 -0 lines)"));
+    assert!(!edits.contains("version = "));
+}
+
+#[test]
+fn excluded_and_binary_files_are_left_out() {
+    assert!(generated::is_excluded("*.min.js", "static/js/app.min.js"));
+    assert!(generated::is_excluded("dist/**", "dist/assets/index.js"));
+    assert!(!generated::is_excluded("dist/**", "src/dist/index.js"));
+
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Build the site",
+        &[
+            Change::Write("src/site.js", b"render();\n"),
+            Change::Write("dist/site.js", b"render();\nhelpers();\n"),
+            Change::Write("logo.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+        ],
+    );
+    let exclude = vec!["dist/**".to_string()];
+    let options = AnalysisOptions { num_commits: 2, exclude, ..AnalysisOptions::default() };
+
+    let input = analysis::prepare(&fixture.repo, &options).unwrap();
+    let churn: Vec<_> = input.generated.iter().map(|f| (f.path.as_str(), f.kind)).collect();
+    assert_eq!(churn, [("dist/site.js", GeneratedKind::Excluded), ("logo.png", GeneratedKind::Binary)]);
+    assert!(input.file_changes[0].contains("src/site.js"));
+    assert!(!input.file_changes[0].contains("dist/site.js"));
+    assert!(!input.file_changes[0].contains("logo.png"));
+}
@@ -0,0 +1,58 @@
+use wtf::guard::{check, looks_like_path, looks_like_sha, GuardMode, KnownRefs};
+
+fn known() -> KnownRefs {
+    let mut known = KnownRefs::new();
+    known.add_sha("3f2a9c1d5e7b8a90123456789abcdef012345678");
+    known.add_path("src/main.rs");
+    known.add_path("README.md");
+    known.add_patch_paths("diff --git a/old.txt b/old.txt\ndeleted file mode 100644\n--- a/old.txt\n+++ /dev/null\n");
+    known
+}
+
+#[test]
+fn recognizes_shas_and_paths() {
+    assert!(looks_like_sha("3f2a9c1"));
+    assert!(!looks_like_sha("defaced"));
+    assert!(!looks_like_sha("1234567"));
+    assert!(looks_like_path("src/lib.rs"));
+    assert!(looks_like_path("docs/"));
+    assert!(!looks_like_path("and/or"));
+    assert!(!looks_like_path("https://example.com/a.js"));
+    assert!(!looks_like_path("v1.2.0"));
+}
+
+#[test]
+fn known_references_pass_untouched() {
+    let text = "Commit `3f2a9c1` changed main.rs, src/ and README.md. It also removed old.txt.";
+    let (out, unverified) = check(text, &known(), GuardMode::Flag);
+    assert_eq!(out, text);
+    assert!(unverified.is_empty());
+}
+
+#[test]
+fn unknown_references_are_flagged() {
+    let text = "Commit 9e8d7c6b rewrote (src/parser.rs), really.";
+    let (out, unverified) = check(text, &known(), GuardMode::Flag);
+    assert_eq!(out, "Commit 9e8d7c6b [unverified] rewrote (src/parser.rs [unverified]), really.");
+    assert_eq!(unverified, ["9e8d7c6b", "src/parser.rs"]);
+}
+
+#[test]
+fn repeated_references_are_reported_once() {
+    let text = "src/parser.rs parses, 9e8d7c6b broke src/parser.rs and src/parser.rs is slow.";
+    let (_, unverified) = check(text, &known(), GuardMode::Flag);
+    assert_eq!(unverified, ["src/parser.rs", "9e8d7c6b"]);
+}
+
+#[test]
+fn unknown_references_can_be_stripped() {
+    let (out, _) = check("See `src/parser.rs`.", &known(), GuardMode::Strip);
+    assert_eq!(out, "See `[unverified reference removed]`.");
+}
+
+#[test]
+fn off_mode_changes_nothing() {
+    let (out, unverified) = check("src/parser.rs", &known(), GuardMode::Off);
+    assert_eq!(out, "src/parser.rs");
+    assert!(unverified.is_empty());
+}
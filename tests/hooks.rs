@@ -0,0 +1,73 @@
+mod common;
+
+use common::FixtureRepo;
+use std::path::Path;
+use wtf::hooks::{self, Hook};
+use wtf::WtfError;
+
+#[test]
+fn installed_hooks_call_back_into_wtf_and_can_be_removed() {
+    let fixture = FixtureRepo::linear(1);
+    let program = Path::new("/opt/it's/wtf");
+
+    let path = hooks::install(&fixture.repo, Hook::PrepareCommitMsg, program, false).unwrap();
+
+    assert_eq!(path, fixture.repo.path().join("hooks/prepare-commit-msg"));
+    let script = std::fs::read_to_string(&path).unwrap();
+    assert!(script.starts_with("#!/bin/sh\n# Installed by wtf"), "{}", script);
+    assert!(script.contains("wtf='/opt/it'\\''s/wtf'\n"), "{}", script);
+    assert!(script.contains("[ -x \"$wtf\" ] || exit 0\n"), "{}", script);
+    assert!(script.contains("exec \"$wtf\" hook run prepare-commit-msg \"$@\"\n"), "{}", script);
+    #[cfg(unix)]
+    assert!(wtf::platform::is_executable(&path));
+
+    // Installing again updates it
+    hooks::install(&fixture.repo, Hook::PrepareCommitMsg, Path::new("/usr/bin/wtf"), false).unwrap();
+    assert_eq!(hooks::uninstall(&fixture.repo, Hook::PrepareCommitMsg).unwrap(), Some(path.clone()));
+    assert!(!path.exists());
+    assert_eq!(hooks::uninstall(&fixture.repo, Hook::PrepareCommitMsg).unwrap(), None);
+}
+
+#[test]
+fn a_hook_wtf_did_not_install_is_only_replaced_when_asked() {
+    let fixture = FixtureRepo::linear(1);
+    let path = fixture.repo.path().join("hooks/commit-msg");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+    let program = Path::new("/usr/bin/wtf");
+
+    let err = hooks::install(&fixture.repo, Hook::CommitMsg, program, false).unwrap_err();
+    assert!(matches!(err, WtfError::ForeignHook { .. }), "{:?}", err);
+    let err = hooks::uninstall(&fixture.repo, Hook::CommitMsg).unwrap_err();
+    assert!(matches!(err, WtfError::ForeignHook { .. }), "{:?}", err);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "#!/bin/sh\nexit 0\n");
+
+    hooks::install(&fixture.repo, Hook::CommitMsg, program, true).unwrap();
+    assert!(hooks::is_installed(&path));
+}
+
+#[test]
+fn only_conventional_messages_pass_the_commit_msg_check() {
+    assert!(hooks::lint("feat(parser): accept empty files\n\n# Please enter the commit message\n").is_ok());
+    assert!(hooks::lint("Merge branch 'main' into topic\n").is_ok());
+    assert!(hooks::lint("fixup! feat: accept empty files\n").is_ok());
+    assert!(hooks::lint("# only comments\n").is_ok());
+
+    let err = hooks::lint("# Please enter the commit message\nAccept empty files\n").unwrap_err();
+    assert!(matches!(err, WtfError::NotConventional { ref header } if header == "Accept empty files"), "{:?}", err);
+}
+
+#[test]
+fn the_message_is_only_prefilled_when_git_has_none() {
+    let fixture = FixtureRepo::linear(1);
+    let path = fixture.repo.path().join("COMMIT_EDITMSG");
+    std::fs::write(&path, "\n# Please enter the commit message\n").unwrap();
+
+    assert!(hooks::should_prefill(None));
+    assert!(!hooks::should_prefill(Some("message")));
+    assert!(!hooks::should_prefill(Some("merge")));
+    hooks::prefill(&path, "fix: handle empty files\n").unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(text, "fix: handle empty files\n\n# Please enter the commit message\n");
+}
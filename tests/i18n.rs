@@ -0,0 +1,97 @@
+//! Message catalogs and the localized parts of rendered reports.
+
+use std::sync::Mutex;
+use wtf::analysis::Report;
+use wtf::i18n::{self, parse_catalog};
+use wtf::render::{render, OutputFormat};
+use wtf::{tr, WtfError};
+
+// The message language is process-wide, so tests that change it take turns
+static LANGUAGE: Mutex<()> = Mutex::new(());
+
+fn with_language<T>(lang: &str, f: impl FnOnce() -> T) -> T {
+    let _guard = LANGUAGE.lock().unwrap_or_else(|e| e.into_inner());
+    assert!(i18n::set_language(lang));
+    let result = f();
+    i18n::set_language(i18n::DEFAULT_LANGUAGE);
+    result
+}
+
+fn report() -> Report {
+    Report {
+        project_description: "Ein kleines Projekt.".to_string(),
+        commit_descriptions: "Eine README wurde angelegt [1a2b3c4].".to_string(),
+        edits_description: "Nichts weiter.".to_string(),
+        num_commits: 2,
+        unverified_references: vec!["src/ghost.rs".to_string()],
+        commit_url: None,
+        review_effort: Vec::new(),
+        public_api: None,
+        ci_changes: None,
+        classification: None,
+        topics: None,
+        generated_churn: Vec::new(),
+        range: None,
+    }
+}
+
+#[test]
+fn every_catalog_translates_every_message() {
+    let english = parse_catalog(include_str!("../locales/en.ftl"));
+    let german = parse_catalog(include_str!("../locales/de.ftl"));
+    let mut missing: Vec<&String> = english.keys().filter(|key| !german.contains_key(*key)).collect();
+    missing.sort();
+    assert!(missing.is_empty(), "de.ftl lacks {:?}", missing);
+    assert!(i18n::available_languages().contains(&"de"));
+}
+
+#[test]
+fn catalogs_support_comments_placeables_and_continuations() {
+    let catalog = parse_catalog("# comment\n\ngreeting = Hello, { $name }!\nlong = first\n    second\nempty =\n");
+    assert_eq!(catalog["greeting"], "Hello, { $name }!");
+    assert_eq!(catalog["long"], "first\nsecond");
+    assert_eq!(catalog["empty"], "");
+}
+
+#[test]
+fn messages_fill_in_arguments_and_fall_back() {
+    with_language("en", || {
+        assert_eq!(tr!("section-commits", count = 3), "Last 3 Commits in Plain Language");
+        assert_eq!(tr!("no-such-message"), "no-such-message");
+        assert_eq!(tr!("section-commits"), "Last { $count } Commits in Plain Language");
+    });
+}
+
+#[test]
+fn locale_names_select_a_catalog() {
+    let _guard = LANGUAGE.lock().unwrap_or_else(|e| e.into_inner());
+    assert!(i18n::set_language("de_DE.UTF-8"));
+    assert_eq!(i18n::language(), "de");
+    assert!(i18n::set_language("en-GB"));
+    assert_eq!(i18n::language(), "en");
+    assert!(!i18n::set_language("tlh"));
+    assert_eq!(i18n::language(), "en");
+}
+
+#[test]
+fn reports_render_in_german() {
+    let (terminal, markdown) = with_language("de", || {
+        (render(&report(), OutputFormat::Terminal), render(&report(), OutputFormat::Markdown))
+    });
+
+    assert!(terminal.contains("=== PROJEKTBESCHREIBUNG ==="));
+    assert!(terminal.contains("=== DIE LETZTEN 2 COMMITS IN EINFACHEN WORTEN ==="));
+    assert!(terminal.contains("=== NICHT ÜBERPRÜFTE VERWEISE ==="));
+    assert!(markdown.contains("## Ausführliche Analyse der letzten Änderungen"));
+    assert!(markdown.contains("> **Hinweis:** diese Dateien"));
+}
+
+#[test]
+fn errors_and_hints_are_translated() {
+    let (message, hint) = with_language("de", || {
+        let err = WtfError::SafetyCapExceeded { requests: 30, prompt_tokens: 120_000 };
+        (err.to_string(), err.hint().unwrap())
+    });
+    assert!(message.starts_with("Dieser Lauf würde 30 Anfrage(n)"), "{}", message);
+    assert!(hint.contains("--force"));
+}
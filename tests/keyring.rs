@@ -0,0 +1,24 @@
+use wtf::error::{exit_code, WtfError};
+use wtf::keyring;
+
+#[test]
+fn keys_never_stored_are_not_found() {
+    // Whether or not this machine has a keyring
+    assert_eq!(keyring::get("WTF_TEST_NEVER_STORED_KEY"), None);
+}
+
+#[test]
+fn keyring_failures_say_what_to_install() {
+    let err = WtfError::Keyring { reason: "secret-tool: No such file or directory".to_string() };
+    assert!(err.to_string().contains("secret-tool: No such file"), "{}", err);
+    assert!(err.hint().unwrap().contains("libsecret-tools"), "{:?}", err.hint());
+    assert_eq!(err.exit_code(), exit_code::AUTH);
+}
+
+#[test]
+fn keys_with_line_breaks_are_refused() {
+    // Before any keyring tool runs, so on every machine
+    let err = keyring::store("WTF_TEST_NEVER_STORED_KEY", "sk-one\ndelete-generic-password -s wtf").unwrap_err();
+    assert!(err.to_string().contains("line break"), "{}", err);
+    assert_eq!(keyring::get("WTF_TEST_NEVER_STORED_KEY"), None);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::error::{exit_code, WtfError};
+use wtf::git::RevRange;
+use wtf::lint_commits::{self, Problem};
+use wtf::render::OutputFormat;
+
+#[test]
+fn messages_are_checked_against_the_specification() {
+    assert_eq!(lint_commits::check("feat(parser): handle empty files\n\nBody.\n"), []);
+    assert_eq!(lint_commits::check("Handle empty files"), [Problem::Header]);
+    assert_eq!(lint_commits::check("fix: handle empty files\nBody right below.\n"), [Problem::BodySpacing]);
+    assert_eq!(lint_commits::check("feat!: drop --old\n\nbreaking change: --old is gone\n"), [Problem::FooterCase]);
+    assert_eq!(lint_commits::check("Merge branch 'main' into feature\n"), []);
+    assert_eq!(lint_commits::check("fixup! feat: add --json\n"), []);
+    assert_eq!(WtfError::LintFailed { count: 2 }.exit_code(), exit_code::LINT);
+}
+
+#[tokio::test]
+async fn the_model_suggests_a_header_only_for_messages_whose_header_fails() {
+    let mut fixture = FixtureRepo::linear(1);
+    let base = fixture.head();
+    let parser = fixture.commit("Fixed the parser", &[Change::Write("src/parse.rs", b"fn parse() {}\n")]);
+    fixture.commit("fix: trim input\nTrims it.", &[Change::Write("src/trim.rs", b"fn trim() {}\n")]);
+    fixture.commit("Update things", &[Change::Write("notes.txt", b"notes\n")]);
+    fixture.commit("feat: add --json", &[Change::Write("main.rs", b"fn main() {}\n")]);
+
+    let input = lint_commits::prepare(&fixture.repo, &RevRange::parse(&format!("{}..HEAD", base))).unwrap();
+    assert_eq!((input.checked, input.failing.len()), (4, 3));
+    assert_eq!(input.estimate().requests, 1);
+
+    // A rewrite that would fail again is dropped
+    let reply = format!(
+        r#"{{"commits": [{{"sha": "{}", "type": "fix", "scope": "parser", "header": "fix(parser): handle empty input"}},
+            {{"sha": "{}", "type": "chore", "scope": "", "header": "Update things"}}]}}"#,
+        parser,
+        fixture.head()
+    );
+    let provider = MockProvider::with_replies(&[&reply]);
+    let report = lint_commits::explain(&input, Some(&provider)).await.unwrap();
+
+    let suggested: Vec<Option<&str>> =
+        report.failures.iter().map(|f| f.suggestion.as_ref().map(|s| s.header.as_str())).collect();
+    assert_eq!(suggested, [None, None, Some("fix(parser): handle empty input")]);
+    let requests = provider.requests();
+    let question = requests[0].iter().find(|m| m.content.contains("Short SHA:")).unwrap().content.clone();
+    assert!(question.contains("Message:\nFixed the parser\n\nDiff:\n") && question.contains("+fn parse() {}"));
+    assert!(!question.contains("trim input"), "{}", question);
+
+    let out = lint_commits::render(&report, OutputFormat::Markdown);
+    assert!(out.contains("3 of 4 commit message(s) do not follow Conventional Commits.\n"), "{}", out);
+    assert!(out.contains(" fix: trim input: no blank line before the body\n"), "{}", out);
+    let end = " Fixed the parser: the header is not type(scope): description\n  \
+               Suggested: fix(parser): handle empty input\n";
+    assert!(out.ends_with(end), "{}", out);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use std::path::Path;
+use wtf::git::Uncommitted;
+use wtf::{message, uncommitted, WtfError};
+
+// One commit, then a staged edit to notes.txt
+fn repo_with_staged_change() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("docs: add notes", &[Change::Write("notes.txt", b"one\n")]);
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    std::fs::write(fixture.dir.join("notes.txt"), "one\ntwo\n").unwrap();
+    let mut index = fixture.repo.index().unwrap();
+    index.add_path(Path::new("notes.txt")).unwrap();
+    index.write().unwrap();
+    fixture
+}
+
+#[tokio::test]
+async fn the_message_is_written_from_the_staged_patch_in_the_repos_style() {
+    let fixture = repo_with_staged_change();
+    let input = uncommitted::prepare(&fixture.repo, Uncommitted::Staged, &[]).unwrap();
+    let recent = message::recent_subjects(&fixture.repo);
+    let provider = MockProvider::with_replies(&["```\ndocs(notes): add a second line\n\nTwo is next.\n```"]);
+
+    let text = message::generate(&input, &recent, &provider, 6000).await.unwrap();
+
+    assert_eq!(text, "docs(notes): add a second line\n\nTwo is next.\n");
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.contains("Recent commit messages:\n- docs: add notes\n"), "{}", question);
+    assert!(question.contains("+two"), "{}", question);
+}
+
+#[tokio::test]
+async fn a_message_that_is_not_conventional_is_sent_back_once() {
+    let fixture = repo_with_staged_change();
+    let input = uncommitted::prepare(&fixture.repo, Uncommitted::Staged, &[]).unwrap();
+    let provider = MockProvider::with_replies(&["Add a second line", "docs: add a second line"]);
+
+    let text = message::generate(&input, &[], &provider, 6000).await.unwrap();
+
+    assert_eq!(text, "docs: add a second line\n");
+    assert_eq!(provider.requests().len(), 2);
+}
+
+#[test]
+fn the_edited_message_is_committed() {
+    let fixture = repo_with_staged_change();
+    let mut config = fixture.repo.config().unwrap();
+    config.set_str("user.name", "Committer").unwrap();
+    config.set_str("user.email", "committer@example.com").unwrap();
+    let parent = fixture.head();
+
+    let edited = message::edit(&fixture.repo, "fix: two\n", "sed -i s/fix/docs/").unwrap();
+    let id = message::commit(&fixture.repo, &edited).unwrap();
+
+    assert_eq!(edited, "docs: two\n");
+    let commit = fixture.repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!((commit.id(), commit.message(), commit.parent_id(0).unwrap()), (id, Some("docs: two\n"), parent));
+    assert!(commit.tree().unwrap().get_path(Path::new("notes.txt")).is_ok());
+    assert!(matches!(message::edit(&fixture.repo, "fix: two\n", "false"), Err(WtfError::EditorFailed { .. })));
+    let emptied = message::edit(&fixture.repo, "fix: two\n", "sed -i /fix/d");
+    assert!(matches!(emptied, Err(WtfError::EmptyMessage)));
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{Change, FixtureRepo};
+use wtf::conventional;
+use wtf::offline;
+use wtf::render::OutputFormat;
+
+#[test]
+fn conventional_headers_are_parsed() {
+    let header = conventional::parse("feat(cli)!: add --offline\n\nMore text.").unwrap();
+    assert_eq!(header.kind, "feat");
+    assert_eq!(header.scope.as_deref(), Some("cli"));
+    assert!(header.breaking);
+    assert_eq!(header.description, "add --offline");
+
+    let footer = conventional::parse("Fix: handle empty repos\n\nBREAKING CHANGE: exits with 3 now").unwrap();
+    assert_eq!(footer.kind, "fix");
+    assert!(footer.breaking);
+
+    assert_eq!(conventional::parse("Merge branch 'main'"), None);
+    assert_eq!(conventional::parse("feat():  "), None);
+    assert_eq!(conventional::parse("Update docs for v2: part 1").map(|h| h.kind), None);
+}
+
+#[test]
+fn history_is_summed_up_without_a_model() {
+    let mut fixture = FixtureRepo::linear(2);
+    fixture.set_author("Second Author", "second@example.com");
+    fixture.commit("fix(core): trim input", &[Change::Write("src/file1.txt", b"content\nmore\n")]);
+    fixture.commit("feat: add lockfile", &[Change::Write("Cargo.lock", b"# generated\n")]);
+    fixture.commit("feat: grow file", &[Change::Write("src/file1.txt", b"content\nmore\nand more\n")]);
+
+    let report = offline::collect(&fixture.repo, 10, &[]).unwrap();
+
+    assert_eq!(report.num_commits, 5);
+    assert_eq!(report.authors[0].name, "Second Author");
+    assert_eq!(report.authors[0].commits, 3);
+    assert_eq!(report.files_changed, 3);
+    // Lockfiles count towards the totals but are not listed
+    assert_eq!(report.files[0].path, "src/file1.txt");
+    assert_eq!(report.files[0].commits, 3);
+    assert!(report.files.iter().all(|file| file.path != "Cargo.lock"));
+    assert_eq!((report.types[0].kind.as_str(), report.types[0].commits), ("feat", 2));
+    assert_eq!(report.unconventional, 2);
+
+    let text = offline::render(&report, OutputFormat::Markdown);
+    assert!(text.contains("The 5 analyzed commit(s) were made by 2 author(s)"), "{}", text);
+    assert!(text.contains("60% of the commit messages follow Conventional Commits"), "{}", text);
+    assert!(text.contains("- feat: 2 commit(s)"), "{}", text);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::onboard::{self, Step};
+use wtf::render::OutputFormat;
+
+#[test]
+fn commands_and_entry_points_come_from_the_manifests_and_usual_names() {
+    let paths: Vec<String> = ["package.json", "yarn.lock", "Makefile", "bin/cli.js", "src/index.ts", "cmd/srv/main.go"]
+        .map(String::from)
+        .to_vec();
+    let package = r#"{"main": "./src/index.ts", "bin": {"tool": "bin/cli.js"},
+        "scripts": {"test": "jest", "dev": "x"}}"#;
+    let read = |path: &str| match path {
+        "package.json" => Some(package.to_string()),
+        "Makefile" => Some("CC := gcc\n.PHONY: test\nbuild: deps\n\tcc main.c\nlint:\n".to_string()),
+        _ => None,
+    };
+
+    let commands: Vec<(Step, String, String)> =
+        onboard::build_commands(&paths, read).into_iter().map(|c| (c.step, c.command, c.source)).collect();
+    let expected = [
+        (Step::Setup, "yarn install", "package.json"),
+        (Step::Build, "make build", "Makefile"),
+        (Step::Test, "yarn run test", "package.json"),
+        (Step::Lint, "make lint", "Makefile"),
+    ]
+    .map(|(step, command, source)| (step, command.to_string(), source.to_string()));
+    assert_eq!(commands, expected);
+
+    let entry_points = onboard::entry_points(&paths, Some(package));
+    assert_eq!(entry_points, ["src/index.ts", "bin/cli.js", "cmd/srv/main.go"]);
+}
+
+#[tokio::test]
+async fn the_tour_keeps_only_paths_that_are_in_the_tree() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Add the parser",
+        &[
+            Change::Write("Cargo.toml", b"[package]\nname = \"tool\"\n"),
+            Change::Write("src/main.rs", b"fn main() { parse::run() }\n"),
+            Change::Write("src/parse/mod.rs", b"pub fn run() {}\n"),
+        ],
+    );
+    fixture.commit("Speed up the parser", &[Change::Write("src/parse/mod.rs", b"pub fn run() { fast() }\n")]);
+
+    let input = onboard::prepare(&fixture.repo).unwrap();
+    assert_eq!(input.entry_points, ["src/main.rs"]);
+    let areas: Vec<(&str, usize)> = input.active_areas.iter().map(|a| (a.path.as_str(), a.commits)).collect();
+    assert_eq!(areas, [("src/parse", 2), ("", 2), ("src", 1)]);
+
+    let provider = MockProvider::with_replies(&[r#"{
+        "overview": " A command-line parser. ",
+        "layout": [{"path": "src/", "description": "The code."}, {"path": "docs", "description": "Made up."}],
+        "key_modules": [{"path": "src/parse/mod.rs", "description": "Parses input."}]
+    }"#]);
+    let tour = onboard::explain(&input, &provider).await.unwrap();
+    let requests = provider.requests();
+    let question = &requests[0][1].content;
+    assert!(question.contains("README:\n\n# Fixture\n"), "{}", question);
+    assert!(question.contains("src/ (2 files)\n  src/parse/ (1 files)\nCargo.toml\nREADME.md\n"), "{}", question);
+    assert!(question.contains("Start of src/main.rs:\n\nfn main() { parse::run() }\n"), "{}", question);
+
+    let out = onboard::render(&tour, OutputFormat::Markdown);
+    assert!(out.contains("A command-line parser.\n\n## How the tree is laid out\n\n- src: The code.\n\n"), "{}", out);
+    assert!(!out.contains("docs"), "{}", out);
+    assert!(out.contains("## Building and testing\n\n- Build: cargo build (Cargo.toml)\n"), "{}", out);
+    assert!(out.contains("## Recently active areas\n\n- src/parse/: 2 commit(s), latest on 2023-11-14\n"), "{}", out);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{Change, FixtureRepo};
+use wtf::ownership;
+
+// Alice builds src/core, Bob does most of docs/, Carol touches core once
+fn team_repo() -> FixtureRepo {
+    let mut fixture = FixtureRepo::new();
+    fixture.set_author("Alice", "alice@example.com");
+    fixture.commit("Add README", &[Change::Write("README.md", b"# Team\n")]);
+    fixture.commit(
+        "Add core",
+        &[
+            Change::Write("src/core/a.rs", b"1\n2\n3\n4\n5\n6\n7\n8\n"),
+            Change::Write("src/core/b.rs", b"1\n2\n3\n4\n"),
+        ],
+    );
+    fixture.commit("Extend core", &[Change::Write("src/core/a.rs", b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n")]);
+    fixture.set_author("Bob", "BOB@example.com");
+    fixture.commit("Write docs", &[Change::Write("docs/guide.md", b"a\nb\nc\nd\n")]);
+    fixture.commit("Remove draft", &[Change::Write("docs/draft.md", b"x\n")]);
+    fixture.commit("Drop draft", &[Change::Delete("docs/draft.md")]);
+    fixture.set_author("Carol", "carol@example.com");
+    fixture.commit("Fix core typo", &[Change::Write("src/core/b.rs", b"1\n2\n3\nfour\n")]);
+    fixture
+}
+
+#[test]
+fn collects_changes_to_existing_files() {
+    let fixture = team_repo();
+
+    let history = ownership::collect(&fixture.repo, 100).unwrap();
+
+    assert_eq!(history.commits.len(), 7);
+    // Newest first; deleted files are left out
+    assert_eq!(history.commits[0].author, "carol@example.com");
+    assert_eq!(history.commits[0].files, [("src/core/b.rs".to_string(), 2)]);
+    assert!(history.commits.iter().all(|c| c.files.iter().all(|(path, _)| path != "docs/draft.md")));
+    assert_eq!(history.author_name("bob@example.com"), "Bob");
+}
+
+#[test]
+fn a_commit_counts_once_per_area() {
+    let fixture = team_repo();
+    let history = ownership::collect(&fixture.repo, 100).unwrap();
+
+    let areas = history.contributions_by(|path| Some(ownership::area_of(path, 2)));
+
+    let alice = areas["src/core"]["alice@example.com"];
+    assert_eq!(alice.commits, 2);
+    assert_eq!(alice.lines, 12 + 2);
+}
+
+#[test]
+fn area_is_the_directory_cut_to_depth() {
+    assert_eq!(ownership::area_of("README.md", 2), "");
+    assert_eq!(ownership::area_of("src/main.rs", 2), "src");
+    assert_eq!(ownership::area_of("src/tui/deep/mod.rs", 2), "src/tui");
+    assert_eq!(ownership::area_of("src/tui/mod.rs", 0), "");
+}
+
+#[test]
+fn owners_cover_most_changes_and_inactive_areas_are_flagged() {
+    let fixture = team_repo();
+    let history = ownership::collect(&fixture.repo, 100).unwrap();
+
+    // Only Carol's last commit is recent enough to count as active
+    let areas = ownership::owners_by_area(&history, 2, fixture.clock());
+
+    let core = areas.iter().find(|a| a.area == "src/core").unwrap();
+    let names: Vec<_> = core.owners.iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(names, ["Alice"]);
+    assert!(core.is_orphaned());
+    assert_eq!(core.last_author, "Carol");
+
+    let codeowners = ownership::codeowners(&areas, &history);
+    assert!(codeowners.contains("# /src/core/: Alice 88% of changed lines in 2 commit(s), inactive"), "{}", codeowners);
+    assert!(codeowners.contains("# /src/core/ alice@example.com\n"));
+
+    let areas = ownership::owners_by_area(&history, 2, 0);
+    let codeowners = ownership::codeowners(&areas, &history);
+    assert!(codeowners.contains("\n* alice@example.com\n"), "{}", codeowners);
+    assert!(codeowners.contains("\n/docs/ bob@example.com\n"));
+}
@@ -0,0 +1,91 @@
+//! Platform-sensitive behavior of the git and config layers: line endings,
+//! `.env` files written on Windows, backslash paths and home directories.
+
+mod common;
+
+use common::{Change, FixtureRepo};
+use wtf::config::{self, env_file_value};
+use wtf::git::{self, RepositoryExt};
+use wtf::guard::{check, looks_like_path, GuardMode, KnownRefs};
+use wtf::platform;
+
+#[test]
+fn env_files_with_crlf_and_bom_are_read() {
+    let contents = "\u{feff}# written by Notepad\r\nOTHER=1\r\nOPENAI_API_KEY=sk-test\r\n";
+    assert_eq!(env_file_value(contents, "OPENAI_API_KEY").as_deref(), Some("sk-test"));
+    assert_eq!(env_file_value(contents, "OTHER").as_deref(), Some("1"));
+    assert_eq!(env_file_value(contents, "MISSING"), None);
+}
+
+#[test]
+fn env_file_values_may_be_quoted_exported_or_commented() {
+    assert_eq!(env_file_value("export KEY=\"a b\"\n", "KEY").as_deref(), Some("a b"));
+    assert_eq!(env_file_value("KEY='x#y'\n", "KEY").as_deref(), Some("x#y"));
+    assert_eq!(env_file_value("KEY = value # comment\n", "KEY").as_deref(), Some("value"));
+    assert_eq!(env_file_value("KEY=first\nKEY=second\n", "KEY").as_deref(), Some("second"));
+    assert_eq!(env_file_value("KEYS=no\n", "KEY"), None);
+}
+
+#[test]
+fn env_files_are_found_next_to_the_repository() {
+    let fixture = FixtureRepo::linear(1);
+    std::fs::create_dir_all(fixture.dir.join("src/deep")).unwrap();
+    std::fs::write(fixture.dir.join(".env"), "OPENAI_API_KEY=sk-repo\n").unwrap();
+
+    let path = config::env_file_path(&fixture.dir.join("src/deep"));
+    assert_eq!(path.canonicalize().unwrap(), fixture.dir.join(".env").canonicalize().unwrap());
+    assert_eq!(config::read_env_file(&path, "OPENAI_API_KEY").as_deref(), Some("sk-repo"));
+    let outside = std::env::temp_dir().join(format!("wtf-no-repo-{}", std::process::id()));
+    assert_eq!(config::env_file_path(&outside), outside.join(".env"));
+}
+
+#[test]
+fn newlines_are_normalized() {
+    assert_eq!(platform::normalize_newlines("a\r\nb\rc\n"), "a\nb\nc\n");
+    assert_eq!(platform::normalize_newlines("plain\n"), "plain\n");
+}
+
+#[test]
+fn crlf_files_produce_clean_patches() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Add notes", &[Change::Write("notes.txt", b"one\r\ntwo\r\n")]);
+    fixture.commit("Edit notes", &[Change::Write("notes.txt", b"one\r\nthree\r\n")]);
+
+    let commit = fixture.repo.find_commit(fixture.head()).unwrap();
+    let patch = git::commit_patch(&fixture.repo, &commit).unwrap().unwrap();
+    assert!(!patch.contains('\r'), "{:?}", patch);
+    assert!(patch.contains("\n-two\n"));
+    assert!(patch.contains("\n+three\n"));
+    assert!(patch.contains("\n one\n"));
+}
+
+#[test]
+fn crlf_readmes_and_messages_are_normalized() {
+    let mut fixture = FixtureRepo::new();
+    fixture.commit("Subject\r\n\r\nBody line\r\n", &[Change::Write("README.md", b"# Title\r\n\r\nText\r\n")]);
+
+    assert_eq!(fixture.repo.find_file("README.md").unwrap(), "# Title\n\nText\n");
+    let commit = fixture.repo.find_commit(fixture.head()).unwrap();
+    assert_eq!(git::commit_message(&commit), "Subject\n\nBody line\n");
+}
+
+#[test]
+fn backslash_paths_are_matched_against_the_tree() {
+    let mut known = KnownRefs::new();
+    known.add_path("src/main.rs");
+
+    assert!(looks_like_path(r"src\main.rs"));
+    let (out, unverified) = check(r"Edited src\main.rs and src\made_up.rs.", &known, GuardMode::Flag);
+    assert_eq!(unverified, [r"src\made_up.rs"]);
+    assert!(out.contains(r"src\main.rs and"));
+}
+
+#[test]
+fn home_dir_follows_platform_conventions() {
+    // The only test in this binary that touches the environment
+    std::env::set_var("HOME", "/home/fixture");
+    std::env::set_var("USERPROFILE", r"C:\Users\fixture");
+
+    let expected = if cfg!(windows) { r"C:\Users\fixture" } else { "/home/fixture" };
+    assert_eq!(platform::home_dir().unwrap(), std::path::PathBuf::from(expected));
+}
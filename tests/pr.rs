@@ -0,0 +1,70 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::git::RevRange;
+use wtf::pr::{self, PullRequest, Template};
+use wtf::render::OutputFormat;
+
+// master gets one more commit after feature branches off with two of its own
+fn repo_with_feature_branch() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(2);
+    let base = fixture.head();
+    fixture.commit("Change on master", &[Change::Write("main.txt", b"main\n")]);
+    let first = fixture.commit_on(
+        "refs/heads/feature",
+        &[base],
+        "Start the feature",
+        &[Change::Write("feature.txt", b"one\n")],
+    );
+    fixture.commit_on(
+        "refs/heads/feature",
+        &[first],
+        "Finish the feature",
+        &[Change::Write("feature.txt", b"one\ntwo\n")],
+    );
+    fixture
+}
+
+fn feature_range() -> RevRange {
+    RevRange { from: "master".to_string(), to: "feature".to_string(), symmetric: false }
+}
+
+#[tokio::test]
+async fn the_branch_is_described_from_its_commits_and_diff_since_it_split_off() {
+    let fixture = repo_with_feature_branch();
+    let input = pr::prepare(&fixture.repo, &feature_range()).unwrap();
+    assert_eq!(input.commits.len(), 2);
+    assert_eq!(input.files.iter().map(|file| file.path.as_str()).collect::<Vec<_>>(), ["feature.txt"]);
+
+    let reply = r#"{"title": "Add the feature", "summary": "Adds feature.txt.", "changes": ["Add feature.txt"],
+        "testing": ["Read feature.txt"]}"#;
+    let provider = MockProvider::with_replies(&[reply]);
+    let pr = pr::explain(&input, &provider, 6000).await.unwrap();
+
+    assert_eq!(pr.title, "Add the feature");
+    assert_eq!(pr.testing, ["Read feature.txt"]);
+    let requests = provider.requests();
+    let question = requests[0].iter().find(|m| m.content.contains("Cumulative diff:")).unwrap().content.clone();
+    assert!(question.contains("Branch: feature\nBase: master\n"), "{}", question);
+    assert!(question.contains("Finish the feature") && question.contains("+two"), "{}", question);
+    assert!(!question.contains("Change on master"), "{}", question);
+}
+
+#[test]
+fn the_markdown_follows_the_chosen_template() {
+    let pr = PullRequest {
+        title: "Add the feature".to_string(),
+        summary: "Adds feature.txt.".to_string(),
+        changes: vec!["Add feature.txt".to_string()],
+        testing: vec!["Read feature.txt".to_string(), "Run the tests".to_string()],
+    };
+
+    let github = pr::render(&pr, Template::Github, OutputFormat::Markdown);
+    assert!(github.starts_with("# Add the feature\n\n## Summary\n\nAdds feature.txt.\n"), "{}", github);
+    assert!(github.contains("## Testing\n\n- [ ] Read feature.txt\n- [ ] Run the tests\n"), "{}", github);
+
+    let gitlab = pr::render(&pr, Template::Gitlab, OutputFormat::Markdown);
+    assert!(gitlab.contains("## What does this MR do and why?\n\nAdds feature.txt.\n"), "{}", gitlab);
+    assert!(gitlab.contains("## How to set up and validate locally\n\n1. Read feature.txt\n2. Run"), "{}", gitlab);
+    assert!(gitlab.contains("## Changes\n\n- Add feature.txt\n"), "{}", gitlab);
+}
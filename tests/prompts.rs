@@ -0,0 +1,100 @@
+mod common;
+
+use common::{FixtureRepo, MockProvider};
+use std::sync::Arc;
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::config::Defaults;
+use wtf::conversation::Conversation;
+use wtf::error::WtfError;
+use wtf::prompts::{self, Audience, InLanguage, PromptVars, Prompts};
+use wtf::provider::{BoxFuture, LlmProvider, Message};
+
+fn vars() -> PromptVars {
+    PromptVars { repo: "demo".to_string(), branch: "main".to_string(), num_commits: 3 }
+}
+
+#[test]
+fn variables_are_filled_in_and_other_braces_kept() {
+    let template = "Explain {num_commits} commits of {repo} on {branch}. Reply as {\"text\": ...}, not {{repo}}.";
+
+    assert_eq!(
+        prompts::interpolate(template, &vars()),
+        "Explain 3 commits of demo on main. Reply as {\"text\": ...}, not {repo}."
+    );
+}
+
+#[tokio::test]
+async fn templates_in_the_prompts_dir_replace_the_built_in_prompts() {
+    let fixture = FixtureRepo::linear(4);
+    let dir = fixture.dir.join("prompts");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("system.txt"), "You write release notes for {repo}.\n").unwrap();
+    std::fs::write(dir.join("commits.txt"), "List the {num_commits} commits on {branch}.").unwrap();
+    let prompts = Prompts::load(&dir).unwrap();
+    assert_eq!(prompts.edits, Prompts::default().edits);
+    let provider = MockProvider::new();
+
+    let options = AnalysisOptions { num_commits: 3, prompts, ..AnalysisOptions::default() };
+    analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    let requests = provider.requests();
+    let repo = fixture.dir.file_name().unwrap().to_string_lossy().to_string();
+    assert_eq!(requests[0][0].content, format!("You write release notes for {}.", repo));
+    let branch = wtf::bookmarks::current_branch(&fixture.repo);
+    assert!(requests[1].last().unwrap().content.starts_with(&format!("List the 3 commits on {}.", branch)));
+    assert!(requests[2].last().unwrap().content.starts_with("Explain the following code changes"));
+}
+
+#[test]
+fn unknown_variables_and_missing_directories_are_errors() {
+    let fixture = FixtureRepo::new();
+    assert!(matches!(Prompts::load(&fixture.dir.join("missing")), Err(WtfError::InvalidConfig { .. })));
+
+    std::fs::write(fixture.dir.join("edits.txt"), "Explain the edits for {audience}.").unwrap();
+    let err = Prompts::load(&fixture.dir).unwrap_err();
+    assert!(err.to_string().contains("{audience}"), "{}", err);
+}
+
+#[test]
+fn each_audience_has_its_own_prompts_that_templates_replace() {
+    let manager = Prompts::for_audience(Audience::Manager);
+    let customer = Prompts::for_audience(Audience::Customer);
+    assert_ne!(manager.system, Prompts::default().system);
+    assert_ne!(manager.commits, customer.commits);
+
+    let fixture = FixtureRepo::new();
+    std::fs::write(fixture.dir.join("commits.txt"), "Custom commits.").unwrap();
+    let prompts = manager.clone().with_templates(&fixture.dir).unwrap();
+    assert_eq!(prompts.commits, "Custom commits.");
+    assert_eq!(prompts.edits, manager.edits);
+
+    let defaults = Defaults::parse("audience = \"new-hire\"\n").unwrap();
+    assert_eq!(defaults.audience, Some(Audience::NewHire));
+}
+
+// Lets the test look at the requests after handing the provider over
+struct Shared(Arc<MockProvider>);
+
+impl LlmProvider for Shared {
+    fn chat<'a>(&'a self, messages: &'a [Message]) -> BoxFuture<'a, wtf::Result<String>> {
+        self.0.chat(messages)
+    }
+}
+
+#[tokio::test]
+async fn every_system_prompt_asks_for_the_chosen_language() {
+    assert_eq!(prompts::language_name("de-AT"), "German");
+    assert_eq!(prompts::language_name("pt_BR.UTF-8"), "Portuguese");
+    assert_eq!(prompts::language_name("Klingon"), "Klingon");
+
+    let mock = Arc::new(MockProvider::new());
+    let provider = InLanguage::new(Box::new(Shared(mock.clone())), "fr");
+    Conversation::new("You explain commits.").ask(&provider, "Explain this.").await.unwrap();
+    provider.chat(&[Message::user("No system prompt here.")]).await.unwrap();
+
+    let requests = mock.requests();
+    assert!(requests[0][0].content.starts_with("You explain commits. Write your answers in French"));
+    assert_eq!(requests[0][1].content, "Explain this.");
+    assert_eq!(requests[1][0].role, "system");
+    assert!(requests[1][0].content.starts_with("Write your answers in French"));
+}
@@ -0,0 +1,330 @@
+mod common;
+
+use std::time::Duration;
+use wtf::error::WtfError;
+use wtf::provider::{self, AzureDeployment, LlmProvider, Message, ProviderKind};
+
+#[test]
+fn provider_names_parse_ignoring_case() {
+    assert_eq!(ProviderKind::from_name("anthropic"), Some(ProviderKind::Anthropic));
+    assert_eq!(ProviderKind::from_name(" OpenAI\n"), Some(ProviderKind::OpenAi));
+    assert_eq!(ProviderKind::from_name("claude"), None);
+    assert_eq!(ProviderKind::default(), ProviderKind::OpenAi);
+    assert_eq!(ProviderKind::Anthropic.api_key_var(), Some("ANTHROPIC_API_KEY"));
+    assert_eq!(ProviderKind::from_name("ollama"), Some(ProviderKind::Ollama));
+    assert_eq!(ProviderKind::Ollama.api_key_var(), None);
+    assert_eq!(ProviderKind::from_name("Gemini").unwrap().api_key_var(), Some("GEMINI_API_KEY"));
+}
+
+#[test]
+fn ollama_hosts_are_read_like_ollama_does() {
+    assert_eq!(provider::ollama_base_url(provider::OLLAMA_DEFAULT_HOST), "http://localhost:11434");
+    assert_eq!(provider::ollama_base_url("gpu-box"), "http://gpu-box:11434");
+    assert_eq!(provider::ollama_base_url("10.0.0.5:8080"), "http://10.0.0.5:8080");
+    assert_eq!(provider::ollama_base_url("[::1]"), "http://[::1]:11434");
+    assert_eq!(provider::ollama_base_url("https://ollama.example.com/"), "https://ollama.example.com");
+}
+
+#[test]
+fn openai_compatible_bases_get_the_chat_completions_path() {
+    assert_eq!(provider::openai_chat_url(provider::OPENAI_DEFAULT_API_BASE), "https://api.openai.com/v1/chat/completions");
+    assert_eq!(provider::openai_chat_url("https://openrouter.ai/api/v1/"), "https://openrouter.ai/api/v1/chat/completions");
+    assert_eq!(
+        provider::openai_chat_url("http://localhost:8000/v1/chat/completions"),
+        "http://localhost:8000/v1/chat/completions"
+    );
+}
+
+#[test]
+fn only_newer_openai_models_take_json_schemas() {
+    for model in ["gpt-4o-mini", "gpt-4.1", "gpt-5", "o1", "o3-mini", "o4-mini"] {
+        assert!(provider::openai_supports_json_schema(model), "{}", model);
+    }
+    for model in ["gpt-4-turbo", "gpt-3.5-turbo", "openchat-3.5", "olmo-2"] {
+        assert!(!provider::openai_supports_json_schema(model), "{}", model);
+    }
+}
+
+#[cfg(feature = "online")]
+#[test]
+fn openai_context_windows_are_known_by_model() {
+    let window = |model: &str| provider::OpenAiProvider::new("sk-test").with_model(model).max_context();
+    assert_eq!(window("o3-mini"), 128_000);
+    assert_eq!(window("gpt-4o-mini"), 128_000);
+    assert_eq!(window("omni-local"), window("gpt-3.5-turbo"));
+}
+
+#[test]
+fn azure_deployments_have_their_own_urls() {
+    let mut deployment = AzureDeployment {
+        endpoint: "https://my-org.openai.azure.com/".to_string(),
+        deployment: "gpt4o-prod".to_string(),
+        api_version: provider::AZURE_DEFAULT_API_VERSION.to_string(),
+    };
+    assert_eq!(
+        deployment.chat_url(),
+        "https://my-org.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-10-21"
+    );
+    assert!(deployment.supports_json_schema());
+    deployment.api_version = "2024-02-15-preview".to_string();
+    assert!(!deployment.supports_json_schema());
+    assert_eq!(ProviderKind::from_name("azure").unwrap().api_key_var(), Some("AZURE_OPENAI_API_KEY"));
+}
+
+#[test]
+fn system_messages_are_split_off_and_turns_merged() {
+    let messages = [
+        Message::system("You explain commits."),
+        Message::user("Explain this diff."),
+        Message::assistant("It adds a file."),
+        Message::user("Shorter, please."),
+        Message::system("Respond only with JSON."),
+        Message::user("And as JSON."),
+    ];
+
+    let (system, turns) = provider::split_system(&messages);
+
+    assert_eq!(system.as_deref(), Some("You explain commits.\n\nRespond only with JSON."));
+    assert_eq!(
+        turns,
+        [
+            Message::user("Explain this diff."),
+            Message::assistant("It adds a file."),
+            Message::user("Shorter, please.\n\nAnd as JSON."),
+        ]
+    );
+    assert_eq!(provider::split_system(&[Message::user("Hi")]).0, None);
+}
+
+#[test]
+fn missing_credentials_name_the_providers_key() {
+    let err = WtfError::MissingCredentials { var: ProviderKind::Anthropic.api_key_var().unwrap() };
+    assert!(err.hint().unwrap().contains("ANTHROPIC_API_KEY="), "{:?}", err.hint());
+    let err = WtfError::UnknownProvider { name: "claude".to_string() };
+    assert_eq!(err.exit_code(), wtf::error::exit_code::USAGE);
+    assert!(err.to_string().contains("claude"));
+}
+
+#[test]
+fn sha256_and_hmac_match_the_reference_vectors() {
+    use wtf::provider::sigv4::{hex, hmac_sha256, sha256};
+    assert_eq!(hex(&sha256(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    assert_eq!(hex(&sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    let long = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    assert_eq!(hex(&sha256(long)), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+    // RFC 4231, test case 2
+    assert_eq!(
+        hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[test]
+fn requests_are_signed_like_the_aws_test_suite() {
+    use wtf::provider::sigv4::{self, Credentials, Request};
+    let credentials = Credentials {
+        access_key_id: "AKIDEXAMPLE".to_string(),
+        secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+        session_token: None,
+    };
+    // get-vanilla from AWS's SigV4 test suite
+    let request = Request { method: "GET", host: "example.amazonaws.com", path: "/", query: "", headers: &[], body: b"" };
+    let headers = sigv4::sign(&request, &credentials, "us-east-1", "service", "20150830T123600Z");
+    assert_eq!(headers[0], ("x-amz-date", "20150830T123600Z".to_string()));
+    assert_eq!(
+        headers[1].1,
+        "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+         SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+    );
+    assert_eq!(sigv4::amz_date(1_440_938_160), "20150830T123600Z");
+
+    let file = "[default]\naws_access_key_id = AKIDEXAMPLE\naws_secret_access_key = secret\n\n\
+                [profile work]\naws_access_key_id=AKIDWORK\naws_secret_access_key=worksecret\naws_session_token=tok\n";
+    assert_eq!(Credentials::from_profile(file, "default").unwrap().secret_access_key, "secret");
+    assert_eq!(Credentials::from_profile(file, "work").unwrap().session_token.as_deref(), Some("tok"));
+    assert_eq!(Credentials::from_profile(file, "missing"), None);
+}
+
+#[test]
+fn provider_sections_are_read_from_config_toml() {
+    let contents = "[providers.openai]\nmodel = \"gpt-4o\" # the strong one\nmax_context = 128000\n\n[providers.ollama]\nhost = 'gpu-box'\n";
+    let mut config = provider::ProviderConfig::parse(contents, "openai");
+    assert_eq!(config.get("model"), Some("gpt-4o"));
+    assert_eq!(config.get("host"), None);
+    config.layer("model", None);
+    config.layer("model", Some("gpt-4o-mini".to_string()));
+    assert_eq!(config.get("model"), Some("gpt-4o-mini"));
+    assert!(matches!(config.api_key("OPENAI_API_KEY"), Err(WtfError::MissingCredentials { var: "OPENAI_API_KEY" })));
+    assert_eq!(provider::ProviderConfig::parse(contents, "ollama").get("host"), Some("gpu-box"));
+}
+
+#[test]
+fn the_registry_builds_providers_by_name() {
+    let mut registry = provider::Registry::default();
+    registry.register("canned", |config| {
+        Ok(Box::new(common::MockProvider::with_replies(&[config.require("reply", "CANNED_REPLY")?])))
+    });
+    assert_eq!(registry.names(), ["canned"]);
+
+    let mut config = provider::ProviderConfig::default();
+    assert!(matches!(registry.build("canned", &config), Err(WtfError::MissingSetting { var: "CANNED_REPLY" })));
+    assert!(matches!(registry.build("openai", &config), Err(WtfError::UnknownProvider { .. })));
+
+    config.set("reply", "ok");
+    let canned = registry.build("Canned", &config).unwrap();
+    assert_eq!(canned.max_context(), provider::DEFAULT_MAX_CONTEXT);
+    config.set("max_context", "32768");
+    assert_eq!(registry.build("canned", &config).unwrap().max_context(), 32_768);
+    assert_eq!(ProviderKind::Bedrock.name(), "bedrock");
+}
+
+#[test]
+fn streamed_lines_survive_any_chunking() {
+    let body = "data: {\"text\":\"Grüße\"}\r\n\r\ndata: [DONE]\n{\"done\":true}";
+    for cut in 0..body.len() {
+        let mut lines = provider::LineBuffer::default();
+        let mut all = lines.push(&body.as_bytes()[..cut]);
+        all.extend(lines.push(&body.as_bytes()[cut..]));
+        all.extend(lines.finish());
+        assert_eq!(all, ["data: {\"text\":\"Grüße\"}", "", "data: [DONE]", "{\"done\":true}"], "cut at {}", cut);
+    }
+    assert_eq!(provider::sse_data("data: [DONE]"), Some("[DONE]"));
+    assert_eq!(provider::sse_data("data:{}"), Some("{}"));
+    assert_eq!(provider::sse_data("event: ping"), None);
+}
+
+// Fails with each of `errors` in turn, then answers "ok"
+struct Flaky {
+    errors: std::sync::Mutex<Vec<WtfError>>,
+}
+
+impl provider::LlmProvider for Flaky {
+    fn chat<'a>(&'a self, _messages: &'a [Message]) -> provider::BoxFuture<'a, wtf::error::Result<String>> {
+        let next = self.errors.lock().unwrap().pop();
+        Box::pin(async move { next.map_or(Ok("ok".to_string()), Err) })
+    }
+}
+
+fn flaky(errors: Vec<WtfError>, max_retries: u32) -> provider::Retrying {
+    let policy = provider::RetryPolicy { max_retries, base_delay: Duration::ZERO, max_delay: Duration::ZERO };
+    provider::Retrying::new(Box::new(Flaky { errors: std::sync::Mutex::new(errors) }), policy)
+}
+
+fn http(status: u16) -> WtfError {
+    WtfError::ProviderError { status, body: String::new(), retry_after: None }
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried_until_they_run_out() {
+    let messages = [Message::user("hi")];
+    assert_eq!(flaky(vec![http(503), http(429)], 2).chat(&messages).await.unwrap(), "ok");
+
+    let err = flaky(vec![http(429), http(529), http(500)], 2).chat(&messages).await.unwrap_err();
+    assert!(matches!(&err, WtfError::GaveUp { attempts: 3, last } if matches!(**last, WtfError::ProviderError { status: 429, .. })));
+    assert_eq!(err.exit_code(), http(429).exit_code());
+
+    // Bad requests and bad keys don't get better by asking again
+    let err = flaky(vec![http(401)], 2).chat(&messages).await.unwrap_err();
+    assert!(matches!(err, WtfError::ProviderError { status: 401, .. }));
+    let err = flaky(vec![http(503)], 0).chat(&messages).await.unwrap_err();
+    assert!(matches!(err, WtfError::ProviderError { status: 503, .. }));
+}
+
+#[test]
+fn backoff_honors_retry_after_and_stays_capped() {
+    let policy = provider::RetryPolicy::default();
+    for retry in 1..10 {
+        let delay = policy.delay(retry, &http(503));
+        let full = policy.base_delay.saturating_mul(1 << (retry - 1)).min(policy.max_delay);
+        assert!(delay >= full / 2 && delay <= full, "retry {}: {:?}", retry, delay);
+    }
+    let limited = WtfError::ProviderError { status: 429, body: String::new(), retry_after: Some(Duration::from_secs(7)) };
+    assert_eq!(policy.delay(1, &limited), Duration::from_secs(7));
+
+    assert_eq!(provider::parse_retry_after(" 120 ", 0), Some(Duration::from_secs(120)));
+    // 2015-10-21 07:28:00 UTC is 1445412480
+    let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+    assert_eq!(provider::parse_retry_after(date, 1_445_412_450), Some(Duration::from_secs(30)));
+    assert_eq!(provider::parse_retry_after(date, 1_445_412_490), Some(Duration::ZERO));
+    assert_eq!(provider::parse_retry_after("soon", 0), None);
+}
+
+#[cfg(feature = "online")]
+#[tokio::test]
+async fn requests_to_a_silent_server_time_out() {
+    use provider::http::{self, HttpSettings};
+
+    let mut config = provider::ProviderConfig::default();
+    assert_eq!(HttpSettings::from_config(&config), HttpSettings::default());
+    config.set("connect_timeout", "soon");
+    config.set("request_timeout", "1");
+    let settings = HttpSettings::from_config(&config);
+    assert_eq!(settings.connect_timeout, http::DEFAULT_CONNECT_TIMEOUT);
+    assert_eq!(settings.request_timeout, Duration::from_secs(1));
+
+    // Accepts the connection and never answers
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    config.set("host", listener.local_addr().unwrap().to_string());
+    let ollama = provider::OllamaProvider::from_config(&config).unwrap();
+    let err = ollama.chat(&[Message::user("Hello")]).await.unwrap_err();
+    assert!(matches!(&err, WtfError::Http(e) if e.is_timeout()), "{:?}", err);
+
+    config.set("proxy", "not a proxy");
+    assert!(provider::OllamaProvider::from_config(&config).is_err());
+}
+
+#[test]
+fn sampling_settings_are_checked() {
+    assert_eq!(provider::parse_sampling::<f32>("temperature", " 1.5 "), Ok(1.5));
+    assert!(provider::parse_sampling::<f32>("temperature", "5").is_err());
+    assert!(provider::parse_sampling::<f32>("top_p", "3").is_err());
+    assert!(provider::parse_sampling::<u32>("max_tokens", "-1").is_err());
+
+    let mut config = provider::ProviderConfig::default();
+    config.set("top_p", "0.9");
+    assert_eq!(provider::Sampling::from_config(&config).unwrap().top_p, Some(0.9));
+    config.set("temperature", "warm");
+    let err = provider::Sampling::from_config(&config).unwrap_err();
+    assert!(matches!(&err, WtfError::InvalidSetting { key, .. } if key == "temperature"), "{:?}", err);
+}
+
+#[cfg(feature = "online")]
+#[tokio::test]
+async fn sampling_settings_are_sent_with_the_request() {
+    use std::io::{Read, Write};
+
+    let mut config = provider::ProviderConfig::default();
+    assert_eq!(provider::Sampling::from_config(&config).unwrap(), provider::Sampling::default());
+    config.set("temperature", "0");
+    config.set("top_p", "0.9");
+    config.set("max_tokens", "256");
+
+    // Answers one request and hands back what it was sent
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    config.set("host", listener.local_addr().unwrap().to_string());
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        // Until the headers and as much of the body as they announce are in
+        loop {
+            let n = stream.read(&mut buf).unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_lowercase();
+            let Some((head, body)) = text.split_once("\r\n\r\n") else { continue };
+            let length = head.lines().find_map(|line| line.strip_prefix("content-length: ")).map(str::trim);
+            if n == 0 || length.and_then(|length| length.parse().ok()) <= Some(body.len()) {
+                break;
+            }
+        }
+        let body = r#"{"message":{"role":"assistant","content":"Hi"},"eval_count":1}"#;
+        let reply = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        stream.write_all(reply.as_bytes()).unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    let ollama = provider::OllamaProvider::from_config(&config).unwrap();
+    assert_eq!(ollama.chat(&[Message::user("Hello")]).await.unwrap(), "Hi");
+    let request = server.join().unwrap();
+    assert!(request.contains(r#""options":{"temperature":0.0,"top_p":0.9,"num_predict":256}"#), "{}", request);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::git::{self, RevRange};
+use wtf::render::{self, OutputFormat};
+use wtf::WtfError;
+
+#[test]
+fn ranges_are_split_like_git_does() {
+    let parse = |spec: &str| {
+        let range = RevRange::parse(spec);
+        (range.from, range.to, range.symmetric)
+    };
+    assert_eq!(parse("v1.2.0..HEAD"), ("v1.2.0".to_string(), "HEAD".to_string(), false));
+    assert_eq!(parse("main...feature"), ("main".to_string(), "feature".to_string(), true));
+    assert_eq!(parse("origin/main.."), ("origin/main".to_string(), "HEAD".to_string(), false));
+    assert_eq!(parse("v1.0"), ("v1.0".to_string(), "HEAD".to_string(), false));
+    assert_eq!(RevRange::parse("main...").to_string(), "main...HEAD");
+}
+
+#[test]
+fn two_dots_exclude_the_start_and_three_dots_take_both_sides() {
+    let mut fixture = FixtureRepo::linear(2);
+    let base = fixture.head();
+    let main = fixture.commit("Change on master", &[Change::Write("main.txt", b"main\n")]);
+    let feature = fixture.commit_on(
+        "refs/heads/feature",
+        &[base],
+        "Change on feature",
+        &[Change::Write("feature.txt", b"feature\n")],
+    );
+
+    let base = base.to_string();
+    assert_eq!(git::range_commits(&fixture.repo, &RevRange::parse(&format!("{}..HEAD", base))).unwrap(), [main]);
+    let mut both = git::range_commits(&fixture.repo, &RevRange::parse("HEAD...feature")).unwrap();
+    both.sort();
+    let mut expected = vec![main, feature];
+    expected.sort();
+    assert_eq!(both, expected);
+    assert!(git::range_commits(&fixture.repo, &RevRange::parse("HEAD..HEAD")).unwrap().is_empty());
+
+    let err = git::range_commits(&fixture.repo, &RevRange::parse("v9..HEAD")).unwrap_err();
+    assert!(matches!(&err, WtfError::UnknownRevision { rev } if rev == "v9"), "{:?}", err);
+}
+
+#[tokio::test]
+async fn the_report_is_about_what_changed_between_the_ends() {
+    let fixture = FixtureRepo::linear(4);
+    let range = RevRange::parse("HEAD~2..HEAD");
+    let ids = git::range_commits(&fixture.repo, &range).unwrap();
+    let provider = MockProvider::new();
+
+    let options = AnalysisOptions { num_commits: ids.len(), range: Some(range), ..AnalysisOptions::default() };
+    let input = analysis::prepare_selected(&fixture.repo, &ids, &options).unwrap();
+    let report = analysis::run(&input, &provider, &options).await.unwrap();
+
+    let question = provider.requests()[1].last().unwrap().content.clone();
+    assert!(question.contains("what changed between HEAD~2 and HEAD"), "{}", question);
+    let markdown = render::render(&report, OutputFormat::Markdown);
+    assert!(markdown.contains("## Changes Between HEAD~2 and HEAD (2 Commits)\n"), "{}", markdown);
+}
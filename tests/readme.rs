@@ -0,0 +1,58 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::readme;
+use wtf::render::OutputFormat;
+
+#[tokio::test]
+async fn an_update_is_shown_as_a_diff_against_the_readme() {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Add the binary",
+        &[
+            Change::Write("Cargo.toml", b"[package]\nname = \"fixture\"\nlicense = \"MIT\"\n"),
+            Change::Write("src/main.rs", b"fn main() {}\n"),
+        ],
+    );
+    let input = readme::prepare(&fixture.repo).unwrap();
+    assert_eq!((input.path.as_str(), input.current.as_deref()), ("README.md", Some("# Fixture\n\nA test project.\n")));
+
+    let provider = MockProvider::with_replies(&[
+        "```markdown\n# Fixture\n\nA test project.\n\n## Building\n\n```sh\ncargo build\n```\n```",
+        "# Fixture\n\nA test project.\n",
+    ]);
+    let proposal = readme::draft(&input, &provider).await.unwrap();
+    let requests = provider.requests();
+    let question = &requests[0][1].content;
+    assert!(question.contains("Current README.md:\n\n# Fixture\n"), "{}", question);
+    assert!(question.contains("Cargo.toml:\n\n[package]\nname = \"fixture\"\n"), "{}", question);
+    assert!(question.contains("Commands:\n\n- cargo build (Cargo.toml)\n"), "{}", question);
+    assert!(question.contains("Latest commits:\n\n- Add the binary\n- Add README\n"), "{}", question);
+
+    assert_eq!(proposal.proposed, "# Fixture\n\nA test project.\n\n## Building\n\n```sh\ncargo build\n```\n");
+    assert_eq!(readme::render(&proposal, OutputFormat::Markdown, false), proposal.proposed);
+    let diff = readme::render(&proposal, OutputFormat::Markdown, true);
+    assert!(diff.contains("--- a/README.md\n+++ b/README.md\n"), "{}", diff);
+    assert!(diff.contains(" A test project.\n+\n+## Building\n"), "{}", diff);
+
+    let unchanged = readme::draft(&input, &provider).await.unwrap();
+    assert_eq!(readme::render(&unchanged, OutputFormat::Terminal, true), "The proposal is the same as README.md.\n");
+}
+
+#[tokio::test]
+async fn without_a_readme_one_is_drafted_from_nothing() {
+    let mut fixture = FixtureRepo::new();
+    let package = br#"{"name": "tool", "scripts": {"test": "jest"}}"#;
+    fixture.commit("Add the package", &[Change::Write("package.json", package)]);
+    let input = readme::prepare(&fixture.repo).unwrap();
+    assert_eq!((input.path.as_str(), input.current.as_deref()), ("README.md", None));
+
+    let provider = MockProvider::with_replies(&["# tool\n\nRun `npm test`."]);
+    let proposal = readme::draft(&input, &provider).await.unwrap();
+    let question = &provider.requests()[0][1].content;
+    assert!(question.starts_with("Below are the layout of a project's tree"), "{}", question);
+    let commands = "Commands:\n\n- npm install (package.json)\n- npm run test (package.json)\n";
+    assert!(question.contains(commands), "{}", question);
+    assert!(proposal.diff.contains("--- /dev/null\n+++ b/README.md\n"), "{}", proposal.diff);
+    assert!(proposal.diff.ends_with("+# tool\n+\n+Run `npm test`.\n"), "{}", proposal.diff);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{FixtureRepo, MockProvider};
+use wtf::reflog::{self, ReflogReport};
+use wtf::render::OutputFormat;
+
+// Three commits, then a reset --hard to the first one that leaves the other
+// two behind
+fn repo_after_hard_reset() -> FixtureRepo {
+    let fixture = FixtureRepo::linear(3);
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    let first = fixture.repo.revparse_single("HEAD~2").unwrap();
+    fixture.repo.reset(&first, git2::ResetType::Hard, None).unwrap();
+    drop(first);
+    fixture
+}
+
+#[test]
+fn commits_the_branch_moved_away_from_are_left_behind() {
+    let fixture = repo_after_hard_reset();
+    let now = fixture.repo.reflog("HEAD").unwrap().get(0).unwrap().committer().when().seconds() + 20 * 60;
+
+    let input = reflog::prepare(&fixture.repo, 30, now).unwrap();
+
+    assert!(input.movements[0].message.starts_with("reset: moving to"), "{:?}", input.movements);
+    // Only the commit HEAD was on is named; its parent comes back with it
+    assert_eq!(input.lost.len(), 1, "{:?}", input.lost);
+    assert_eq!(input.lost[0].seen_at, "HEAD@{0}");
+    let head = fixture.repo.reflog("HEAD").unwrap().get(0).unwrap().id_old();
+    assert!(head.to_string().starts_with(&input.lost[0].short_id));
+    let report = ReflogReport { input, explanation: None };
+    let markdown = reflog::render(&report, OutputFormat::Markdown);
+    assert!(markdown.starts_with("## HEAD Movements ("), "{}", markdown);
+    assert!(markdown.contains("- HEAD@{0}, 20 minute(s) ago: reset: moving to"), "{}", markdown);
+}
+
+#[test]
+fn nothing_is_lost_when_every_commit_is_on_a_branch() {
+    let fixture = FixtureRepo::linear(2);
+
+    let input = reflog::prepare(&fixture.repo, 30, 0).unwrap();
+
+    assert!(input.lost.is_empty());
+    let markdown = reflog::render(&ReflogReport { input, explanation: None }, OutputFormat::Markdown);
+    assert!(markdown.contains("Every commit HEAD was on is still reached"), "{}", markdown);
+}
+
+#[tokio::test]
+async fn the_model_is_given_the_movements_and_what_they_left_behind() {
+    let fixture = repo_after_hard_reset();
+    let input = reflog::prepare(&fixture.repo, 1, i64::MAX / 2).unwrap();
+    let provider = MockProvider::with_replies(&["You reset --hard. Run git branch rescue HEAD@{1}."]);
+
+    let explanation = reflog::explain(&input, &provider).await.unwrap();
+
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.contains("\nHEAD@{0}, "), "{}", question);
+    assert!(question.contains("\nLeft behind, reached by no branch, tag or stash:\n- "), "{}", question);
+    let report = ReflogReport { input, explanation: Some(explanation) };
+    let markdown = reflog::render(&report, OutputFormat::Markdown);
+    assert!(markdown.starts_with("## What Just Happened\n\nYou reset --hard."), "{}", markdown);
+}
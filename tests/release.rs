@@ -0,0 +1,81 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::release;
+use wtf::render::OutputFormat;
+use wtf::WtfError;
+
+fn tag(fixture: &FixtureRepo, name: &str, id: git2::Oid) {
+    fixture.repo.tag_lightweight(name, &fixture.repo.find_object(id, None).unwrap(), false).unwrap();
+}
+
+// v1.0.0, an annotated v1.1.0 and a lightweight v2.0.0-rc1 and v2.0.0 on
+// master; v1.2.0 on a maintenance branch v2.0.0 does not contain
+fn repo_with_releases() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    let first = fixture.commit("Add the parser", &[Change::Write("parser.rs", b"fn parse() {}\n")]);
+    tag(&fixture, "v1.0.0", first);
+    let second = fixture.commit("Parse faster", &[Change::Write("parser.rs", b"fn parse() { fast() }\n")]);
+    let signature = git2::Signature::now("Release Manager", "release@example.com").unwrap();
+    let object = fixture.repo.find_object(second, None).unwrap();
+    fixture.repo.tag("v1.1.0", &object, &signature, "Faster parsing", false).unwrap();
+    drop(object);
+    let fix = fixture.commit_on("refs/heads/maint", &[second], "Fix a crash", &[Change::Write("fix.rs", b"\n")]);
+    tag(&fixture, "v1.2.0", fix);
+    let rc = fixture.commit("Drop the old API", &[Change::Delete("parser.rs")]);
+    tag(&fixture, "v2.0.0-rc1", rc);
+    let last = fixture.commit("Add the new API", &[Change::Write("api.rs", b"pub fn run() {}\n")]);
+    tag(&fixture, "v2.0.0", last);
+    tag(&fixture, "nightly", last);
+    fixture
+}
+
+fn previous(fixture: &FixtureRepo, name: &str) -> Option<String> {
+    release::previous_tag(&fixture.repo, &release::find_tag(&fixture.repo, name).unwrap()).unwrap()
+}
+
+#[test]
+fn the_previous_release_is_found_by_version_then_by_history() {
+    let fixture = repo_with_releases();
+
+    assert_eq!(previous(&fixture, "v2.0.0").as_deref(), Some("v1.1.0"));
+    assert_eq!(previous(&fixture, "v2.0.0-rc1").as_deref(), Some("v1.1.0"));
+    assert_eq!(previous(&fixture, "v1.2.0").as_deref(), Some("v1.1.0"));
+    assert_eq!(previous(&fixture, "v1.0.0"), None);
+    assert_eq!(previous(&fixture, "nightly").as_deref(), Some("v2.0.0-rc1"));
+    assert!(matches!(release::find_tag(&fixture.repo, "v9"), Err(WtfError::UnknownTag { .. })));
+}
+
+#[tokio::test]
+async fn a_release_is_summarized_from_its_commits_and_tag_message() {
+    let fixture = repo_with_releases();
+    let input = release::prepare(&fixture.repo, "v2.0.0").unwrap();
+    assert_eq!(input.commits.len(), 2);
+    let provider = MockProvider::with_replies(&["A new API replaces the old one."]);
+
+    let release = release::explain(&input, &provider).await.unwrap();
+
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.contains("Previous release: v1.1.0\n"), "{}", question);
+    assert!(question.contains("Drop the old API"), "{}", question);
+    assert!(!question.contains("Parse faster"), "{}", question);
+    let markdown = release::render(&release, OutputFormat::Markdown);
+    assert!(markdown.starts_with("## What's in v2.0.0\n\nA new API replaces the old one.\n"), "{}", markdown);
+    assert!(markdown.contains("- 2 commit(s) since v1.1.0, 2 file(s) changed (+1 -1 lines)\n"), "{}", markdown);
+    assert!(markdown.contains("- A lightweight tag, on a commit from "), "{}", markdown);
+}
+
+#[tokio::test]
+async fn an_annotated_tag_shows_its_message() {
+    let fixture = repo_with_releases();
+    let input = release::prepare(&fixture.repo, "v1.1.0").unwrap();
+    let provider = MockProvider::new();
+
+    let release = release::explain(&input, &provider).await.unwrap();
+
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.contains("Tag message:\nFaster parsing\n"), "{}", question);
+    let markdown = release::render(&release, OutputFormat::Markdown);
+    assert!(markdown.contains("- Tagged by Release Manager, on a commit from "), "{}", markdown);
+    assert!(markdown.ends_with("\n\nFaster parsing\n"), "{}", markdown);
+}
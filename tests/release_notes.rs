@@ -0,0 +1,69 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::git::RevRange;
+use wtf::release_notes;
+use wtf::render::OutputFormat;
+
+// Three commits since v1.0, by two people under three addresses, one of
+// them co-authored
+fn repo_with_release() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    let release = fixture.head();
+    fixture.repo.tag_lightweight("v1.0", &fixture.repo.find_object(release, None).unwrap(), false).unwrap();
+    fixture.set_author("Ada", "ada@old.example.com");
+    fixture.commit("feat: add --json", &[Change::Write("main.rs", b"fn main() {}\n")]);
+    fixture.set_author("Grace", "grace@example.com");
+    fixture.commit(
+        "fix: accept empty input\n\nCo-authored-by: Ada Lovelace <ada@example.com>\n",
+        &[Change::Write("parse.rs", b"fn parse() {}\n")],
+    );
+    fixture.set_author("Ada", "ada@example.com");
+    fixture.commit("docs: describe --json", &[Change::Write("README.md", b"--json\n")]);
+    std::fs::write(fixture.dir.join(".mailmap"), "Ada Lovelace <ada@example.com> <ada@old.example.com>\n").unwrap();
+    fixture
+}
+
+#[tokio::test]
+async fn the_notes_come_from_the_range_and_credit_everyone_who_worked_on_it() {
+    let fixture = repo_with_release();
+    fixture.repo.remote("origin", "git@github.com:owner/project.git").unwrap();
+    let input = release_notes::prepare(&fixture.repo, &RevRange::parse("v1.0..HEAD")).unwrap();
+
+    let credits: Vec<(&str, usize)> = input.contributors.iter().map(|c| (c.name.as_str(), c.commits)).collect();
+    assert_eq!(credits, [("Ada", 3), ("Grace", 1)]);
+    assert_eq!(input.compare_url.as_deref(), Some("https://github.com/owner/project/compare/v1.0...HEAD"));
+
+    let reply = r#"{"summary": "JSON output arrives.", "highlights": ["Print JSON with --json."], "breaking": [" "],
+        "upgrade": []}"#;
+    let provider = MockProvider::with_replies(&[reply]);
+    let notes = release_notes::explain(&input, &provider, 6000).await.unwrap();
+
+    assert!(notes.breaking.is_empty());
+    let requests = provider.requests();
+    let question = requests[0].iter().find(|m| m.content.contains("Combined diff:")).unwrap().content.clone();
+    assert!(question.contains("Release: v1.0..HEAD\n") && question.contains("+fn parse() {}"), "{}", question);
+}
+
+#[test]
+fn the_markdown_leaves_out_empty_sections() {
+    let notes = release_notes::ReleaseNotes {
+        range: RevRange::parse("v1.0..v1.1"),
+        commits: 2,
+        summary: "JSON output arrives.".to_string(),
+        highlights: vec!["Print JSON with --json.".to_string()],
+        breaking: Vec::new(),
+        upgrade: vec!["Rename `output` to `format` in .wtf.toml.".to_string()],
+        contributors: vec![release_notes::Contributor { name: "Ada Lovelace".to_string(), commits: 2 }],
+        compare_url: None,
+    };
+
+    let out = release_notes::render(&notes, OutputFormat::Markdown);
+
+    assert_eq!(
+        out,
+        "## Highlights\n\nJSON output arrives.\n\n- Print JSON with --json.\n\n\
+         ## Upgrading\n\n- Rename `output` to `format` in .wtf.toml.\n\n\
+         ## Contributors\n\n- Ada Lovelace (2 commit(s))\n\n2 commit(s) in v1.0..v1.1\n"
+    );
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use common::assert_snapshot;
+use wtf::analysis::{Report, Task};
+use wtf::ci::{CiFinding, CiReport, CiRisk};
+use wtf::classify::{Classification, CommitKind, Dashboard, RiskLevel};
+use wtf::complexity::{CommitScore, Effort};
+use wtf::generated::{ChurnFile, GeneratedKind};
+use wtf::rust_api::{ApiChange, ApiChanges, ApiItem, ApiKind};
+use wtf::render::{self, render, OutputFormat};
+
+fn typical_report() -> Report {
+    Report {
+        project_description: "WTF Git explains Git repositories in plain language.".to_string(),
+        commit_descriptions: "1. Added a README describing the project [1a2b3c4].\n2. Introduced a configuration file [5d6e7f8].\n3. Fixed a typo in the help text [9a8b7c6, 5d6e7f8].".to_string(),
+        edits_description: "The help text now spells \"repository\" correctly, and a new `config.toml` sets sensible defaults.".to_string(),
+        num_commits: 3,
+        unverified_references: Vec::new(),
+        commit_url: Some("https://github.com/kevinschueller/wtf-git/commit".to_string()),
+        review_effort: Vec::new(),
+        public_api: None,
+        ci_changes: None,
+        classification: None,
+        topics: None,
+        generated_churn: Vec::new(),
+        range: None,
+    }
+}
+
+fn reviewed_report() -> Report {
+    Report {
+        review_effort: vec![
+            CommitScore {
+                short_id: "5d6e7f8".to_string(),
+                score: 64,
+                effort: Effort::High,
+                files: 12,
+                hunks: 30,
+                added: 410,
+                removed: 25,
+                hunk_entropy: 4.2,
+                test_ratio: Some(0.0),
+            },
+            CommitScore {
+                short_id: "9a8b7c6".to_string(),
+                score: 37,
+                effort: Effort::Medium,
+                files: 2,
+                hunks: 3,
+                added: 20,
+                removed: 8,
+                hunk_entropy: 1.5,
+                test_ratio: Some(0.5),
+            },
+            CommitScore {
+                short_id: "1a2b3c4".to_string(),
+                score: 12,
+                effort: Effort::Low,
+                files: 1,
+                hunks: 1,
+                added: 5,
+                removed: 0,
+                hunk_entropy: 0.0,
+                test_ratio: None,
+            },
+        ],
+        public_api: Some(ApiChanges {
+            added: vec![ApiItem {
+                path: "crate::config::Config::load".to_string(),
+                kind: ApiKind::Method,
+                signature: "pub fn load(path: &Path) -> Result<Config>".to_string(),
+            }],
+            removed: vec![ApiItem {
+                path: "crate::help".to_string(),
+                kind: ApiKind::Fn,
+                signature: "pub fn help()".to_string(),
+            }],
+            changed: vec![ApiChange {
+                path: "crate::run".to_string(),
+                kind: ApiKind::Fn,
+                before: "pub fn run(args: Args)".to_string(),
+                after: "pub fn run(args: Args, config: &Config)".to_string(),
+            }],
+        }),
+        ci_changes: Some(CiReport {
+            summary: "The test workflow now also publishes a package on every tag [9a8b7c6].".to_string(),
+            files: vec![".github/workflows/test.yml".to_string()],
+            findings: vec![CiFinding {
+                short_id: "9a8b7c6".to_string(),
+                path: ".github/workflows/test.yml".to_string(),
+                risk: CiRisk::Secret,
+                line: "+ token: ${{ secrets.NPM_TOKEN }}".to_string(),
+            }],
+        }),
+        classification: Some(Dashboard::new(vec![
+            Classification {
+                short_id: "9a8b7c6".to_string(),
+                kind: CommitKind::Fix,
+                risk: RiskLevel::Medium,
+                reason: "Corrects the help text and changes the release workflow.".to_string(),
+            },
+            Classification {
+                short_id: "5d6e7f8".to_string(),
+                kind: CommitKind::Feature,
+                risk: RiskLevel::High,
+                reason: "New configuration file read on every start.".to_string(),
+            },
+            Classification {
+                short_id: "1a2b3c4".to_string(),
+                kind: CommitKind::Docs,
+                risk: RiskLevel::Low,
+                reason: "README only.".to_string(),
+            },
+        ])),
+        topics: None,
+        generated_churn: vec![
+            ChurnFile {
+                path: "Cargo.lock".to_string(),
+                kind: GeneratedKind::Lockfile,
+                added: 412,
+                removed: 97,
+            },
+            ChurnFile {
+                path: "vendor/highlight.min.js".to_string(),
+                kind: GeneratedKind::Vendored,
+                added: 1,
+                removed: 1,
+            },
+        ],
+        ..typical_report()
+    }
+}
+
+fn single_commit_report() -> Report {
+    Report {
+        project_description: "A tiny test project.".to_string(),
+        commit_descriptions: "The first commit added a README.".to_string(),
+        edits_description: "Repository has only one commit, so there are no previous versions to compare changes against.".to_string(),
+        num_commits: 1,
+        unverified_references: Vec::new(),
+        commit_url: None,
+        review_effort: Vec::new(),
+        public_api: None,
+        ci_changes: None,
+        classification: None,
+        topics: None,
+        generated_churn: Vec::new(),
+        range: None,
+    }
+}
+
+fn messy_report() -> Report {
+    Report {
+        project_description: "\n  Leading and trailing whitespace, <html> & \"quotes\"  \n\n".to_string(),
+        commit_descriptions: "- **bold** claim\n- `code` span\n- unicode: café, 日本語, 🚀".to_string(),
+        edits_description: String::new(),
+        num_commits: 2,
+        unverified_references: vec!["src/ghost.rs".to_string(), "abc1234".to_string()],
+        commit_url: None,
+        review_effort: Vec::new(),
+        public_api: None,
+        ci_changes: None,
+        classification: None,
+        topics: None,
+        generated_churn: Vec::new(),
+        range: None,
+    }
+}
+
+fn check_all_formats(name: &str, report: &Report) {
+    assert_snapshot(&format!("{}_terminal", name), &render(report, OutputFormat::Terminal));
+    assert_snapshot(&format!("{}_markdown", name), &render(report, OutputFormat::Markdown));
+    assert_snapshot(&format!("{}_json", name), &render(report, OutputFormat::Json));
+    assert_snapshot(&format!("{}_plain", name), &render(report, OutputFormat::Plain));
+}
+
+#[test]
+fn typical_report_snapshots() {
+    check_all_formats("typical", &typical_report());
+}
+
+#[test]
+fn review_effort_snapshots() {
+    check_all_formats("reviewed", &reviewed_report());
+}
+
+#[test]
+fn single_commit_report_snapshots() {
+    check_all_formats("single_commit", &single_commit_report());
+}
+
+#[test]
+fn messy_report_snapshots() {
+    check_all_formats("messy", &messy_report());
+}
+
+#[test]
+fn json_output_round_trips() {
+    let json = render(&typical_report(), OutputFormat::Json);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["num_commits"], 3);
+    assert_eq!(
+        value["project_description"],
+        "WTF Git explains Git repositories in plain language."
+    );
+}
+
+#[test]
+fn plain_output_has_no_symbol_decoration() {
+    let plain = render(&typical_report(), OutputFormat::Plain);
+    assert!(!plain.contains("==="));
+    assert!(!plain.contains('`'));
+    assert!(plain.contains("Section 2 of 3: Last 3 Commits in Plain Language"));
+    assert!(plain.contains("(commit 1a2b3c4)"));
+    assert!(plain.contains("(commits 9a8b7c6, 5d6e7f8)"));
+}
+
+#[test]
+fn streamed_sections_are_not_printed_twice() {
+    let report = typical_report();
+    let sections = [
+        (Task::Description, report.project_description.clone()),
+        (Task::Commits, report.commit_descriptions.clone()),
+        (Task::Edits, report.edits_description.clone()),
+    ];
+    let mut shown: String = sections
+        .iter()
+        .map(|(task, text)| format!("{}{}\n", render::stream_banner(*task, 3, None).unwrap(), text))
+        .collect();
+    shown.push_str(&render::render_after_stream(&report, &sections));
+    assert_eq!(shown, render(&report, OutputFormat::Terminal));
+
+    // Text changed by checks after streaming is shown again, marked as such
+    let mut drafts = sections.to_vec();
+    drafts[1].1 = "1. Added a README.".to_string();
+    let rest = render::render_after_stream(&report, &drafts);
+    assert!(rest.starts_with("\n=== LAST 3 COMMITS IN PLAIN LANGUAGE (CHECKED) ===\n\n1. Added a README"), "{}", rest);
+    assert!(!rest.contains(&report.project_description));
+}
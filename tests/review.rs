@@ -0,0 +1,73 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::git::RevRange;
+use wtf::render::OutputFormat;
+use wtf::review::{self, Category, Severity};
+
+// Two files changed since the first commit, plus a lockfile
+fn repo_with_changes() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "feat: parse input",
+        &[
+            Change::Write("src/parse.rs", b"fn parse(s: &str) -> u32 { s.parse().unwrap() }\n"),
+            Change::Write("README.md", b"Parses input.\n"),
+            Change::Write("Cargo.lock", b"lock\n"),
+        ],
+    );
+    fixture
+}
+
+#[tokio::test]
+async fn each_file_is_reviewed_on_its_own_and_findings_are_sorted_by_severity() {
+    let fixture = repo_with_changes();
+    let range = RevRange { from: "HEAD~1".to_string(), to: "HEAD".to_string(), symmetric: false };
+    let input = review::prepare_range(&fixture.repo, &range, &[], 6000).unwrap();
+    let paths: Vec<&str> = input.files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, ["README.md", "src/parse.rs"]);
+    assert_eq!(input.estimate().requests, 2);
+
+    let provider = MockProvider::with_replies(&[
+        r#"{"findings": []}"#,
+        r#"{"findings": [
+            {"severity": "low", "category": "naming", "lines": "1", "comment": "Call `s` input."},
+            {"severity": "high", "category": "bug", "lines": " 1 ", "comment": "unwrap panics on bad input."}
+        ]}"#,
+    ]);
+    let review = review::explain(&input, &provider).await.unwrap();
+
+    assert_eq!(review.reviewed, 2);
+    assert_eq!(review.files.len(), 1);
+    let findings = &review.files[0].findings;
+    assert_eq!((findings[0].severity, findings[0].category), (Severity::High, Category::Bug));
+    assert_eq!(findings[0].lines, "1");
+    assert_eq!(findings[1].severity, Severity::Low);
+
+    let requests = provider.requests();
+    let question = requests[1].iter().find(|m| m.content.contains("File: ")).unwrap().content.clone();
+    assert!(question.contains("File: src/parse.rs\nThe commits making the change:\n- feat: parse input\n"));
+    assert!(question.contains("+fn parse") && !question.contains("Parses input."), "{}", question);
+}
+
+#[test]
+fn findings_are_grouped_by_file_with_their_severity() {
+    let review = review::Review {
+        target: "HEAD~1..HEAD".to_string(),
+        reviewed: 2,
+        files: vec![review::FileReview {
+            path: "src/parse.rs".to_string(),
+            findings: vec![review::Finding {
+                severity: Severity::High,
+                category: Category::Bug,
+                lines: "1".to_string(),
+                comment: "unwrap panics on bad input.".to_string(),
+            }],
+        }],
+    };
+
+    let out = review::render(&review, OutputFormat::Markdown);
+
+    assert!(out.starts_with("## Review of HEAD~1..HEAD\n\n2 file(s) reviewed: 1 high, 0 medium, 0 low\n"), "{}", out);
+    assert!(out.contains("## src/parse.rs\n\n- [high] bug, lines 1: unwrap panics on bad input.\n"), "{}", out);
+}
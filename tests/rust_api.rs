@@ -0,0 +1,133 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+use wtf::rust_api::{self, ApiItem, ApiKind};
+
+const LIB: &str = r#"
+//! A library.
+
+/// Parse things.
+pub fn parse(input: &str) -> Result<Vec<u8>, Error> { todo!() }
+fn helper() {}
+pub(crate) fn internal() {}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The name.
+    pub name: String,
+    secret: u32,
+}
+
+impl Config {
+    pub fn new<T: Into<String>>(name: T) -> Self { todo!() }
+    fn private(&self) {}
+}
+
+impl Default for Config {
+    fn default() -> Self { todo!() }
+}
+
+pub mod public;
+mod private;
+
+#[macro_export]
+macro_rules! shout { () => {} }
+"#;
+
+fn files(lib: &str) -> Vec<(String, String)> {
+    vec![
+        ("src/lib.rs".to_string(), lib.to_string()),
+        ("src/public.rs".to_string(), "pub trait Render { fn render(&self) -> String { String::new() } }".to_string()),
+        ("src/private.rs".to_string(), "pub fn hidden() {}".to_string()),
+        ("src/main.rs".to_string(), "pub fn main() {}".to_string()),
+    ]
+}
+
+fn signature(items: &[ApiItem], path: &str) -> String {
+    items
+        .iter()
+        .find(|item| item.path == path)
+        .unwrap_or_else(|| panic!("{} not in {:#?}", path, items))
+        .signature
+        .clone()
+}
+
+#[test]
+fn collects_public_signatures_only() {
+    let items = rust_api::public_items(&files(LIB));
+    let paths: Vec<&str> = items.iter().map(|item| item.path.as_str()).collect();
+
+    assert_eq!(signature(&items, "crate::parse"), "pub fn parse(input: &str) -> Result<Vec<u8>, Error>");
+    assert_eq!(
+        signature(&items, "crate::Config"),
+        "#[derive(Debug, Clone)] pub struct Config { pub name: String }"
+    );
+    assert_eq!(signature(&items, "crate::Config::new"), "pub fn new<T: Into<String>>(name: T) -> Self");
+    assert_eq!(signature(&items, "crate::impl Default for Config"), "impl Default for Config");
+    assert_eq!(signature(&items, "crate::public::Render"), "pub trait Render { fn render(&self) -> String; }");
+    assert_eq!(signature(&items, "crate::shout"), "macro_rules! shout");
+    for hidden in ["crate::helper", "crate::internal", "crate::Config::private", "crate::private::hidden", "crate::main"] {
+        assert!(!paths.contains(&hidden), "{} should not be public", hidden);
+    }
+}
+
+#[test]
+fn compare_separates_added_removed_and_changed() {
+    let old = rust_api::public_items(&files(LIB));
+    let new_lib = LIB
+        .replace("pub fn parse(input: &str)", "pub fn parse(input: &[u8])")
+        .replace("pub mod public;", "pub mod public;\npub fn format() {}")
+        .replace("#[macro_export]", "");
+    let new = rust_api::public_items(&files(&new_lib));
+
+    let changes = rust_api::compare(&old, &new);
+    assert!(changes.is_breaking());
+    assert_eq!(changes.added.len(), 1);
+    assert_eq!(changes.added[0].path, "crate::format");
+    assert_eq!(changes.removed.len(), 1);
+    assert_eq!(changes.removed[0].kind, ApiKind::Macro);
+    assert_eq!(changes.changed.len(), 1);
+    assert_eq!(changes.changed[0].after, "pub fn parse(input: &[u8]) -> Result<Vec<u8>, Error>");
+
+    // Doc comments and bodies are not part of the API
+    let redocumented = LIB.replace("/// Parse things.", "/// Parse anything.").replace("todo!()", "unimplemented!()");
+    assert!(rust_api::compare(&old, &rust_api::public_items(&files(&redocumented))).is_empty());
+}
+
+#[test]
+fn changes_between_trees_of_a_repository() {
+    let mut fixture = FixtureRepo::new();
+    let start = fixture.commit("Start", &[Change::Write("src/lib.rs", b"pub fn run() {}\n")]);
+    let flag = fixture.commit("Take a flag", &[Change::Write("src/lib.rs", b"pub fn run(fast: bool) {}\n")]);
+    let before = fixture.repo.find_commit(start).unwrap().tree().unwrap();
+    let after = fixture.repo.find_commit(flag).unwrap().tree().unwrap();
+
+    let changes = rust_api::changes_between(&fixture.repo, Some(&before), &after).unwrap().unwrap();
+    assert_eq!(changes.changed.len(), 1);
+    assert_eq!(changes.changed[0].before, "pub fn run()");
+
+    let everything = rust_api::changes_between(&fixture.repo, None, &before).unwrap().unwrap();
+    assert_eq!(everything.added.len(), 1);
+
+    let plain = FixtureRepo::linear(1);
+    let tree = plain.repo.find_commit(plain.head()).unwrap().tree().unwrap();
+    assert_eq!(rust_api::changes_between(&plain.repo, None, &tree).unwrap(), None);
+}
+
+#[tokio::test]
+async fn report_calls_out_public_api_changes() {
+    let mut fixture = FixtureRepo::new();
+    fixture.commit("Start", &[Change::Write("src/lib.rs", b"pub fn run() {}\npub fn stop() {}\n")]);
+    fixture.commit("Drop stop", &[Change::Write("src/lib.rs", b"pub fn run() {}\n")]);
+    fixture.commit("Add docs", &[Change::Write("README.md", b"# Demo\n")]);
+    let provider = MockProvider::new();
+
+    let options = AnalysisOptions { num_commits: 2, ..AnalysisOptions::default() };
+    let report = analysis::analyze(&fixture.repo, &provider, &options).await.unwrap();
+
+    let changes = report.public_api.unwrap();
+    assert_eq!(changes.removed.len(), 1);
+    assert_eq!(changes.removed[0].path, "crate::stop");
+    assert!(changes.added.is_empty() && changes.changed.is_empty());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::render::OutputFormat;
+use wtf::semver::{self, Basis, Bump};
+
+fn tag(fixture: &FixtureRepo, name: &str, id: git2::Oid) {
+    fixture.repo.tag_lightweight(name, &fixture.repo.find_object(id, None).unwrap(), false).unwrap();
+}
+
+#[tokio::test]
+async fn headers_and_api_changes_decide_and_the_model_rates_the_rest() {
+    let mut fixture = FixtureRepo::linear(1);
+    let release = fixture.commit("Release 1.2.4", &[Change::Write("src/lib.rs", b"pub fn parse() {}\n")]);
+    tag(&fixture, "v1.2.4", release);
+    let lib = b"pub fn parse() {}\npub fn check() {}\n";
+    let added = fixture.commit("Add a checker", &[Change::Write("src/lib.rs", lib)]);
+    let docs = fixture.commit("Reword the README", &[Change::Write("README.md", b"Parses.\n")]);
+    fixture.commit("fix: accept empty input", &[Change::Write("input.txt", b"\n")]);
+    fixture.commit("refactor!: drop the old format", &[Change::Write("format.txt", b"new\n")]);
+
+    let input = semver::prepare(&fixture.repo).unwrap();
+    assert_eq!(input.since.as_deref(), Some("v1.2.4"));
+    assert_eq!(input.commits.len(), 4);
+    assert_eq!(input.commits.iter().filter(|candidate| candidate.needs_model()).count(), 2);
+    assert_eq!(input.estimate().requests, 1);
+
+    let reply = format!(
+        r#"{{"commits": [{{"sha": "{}", "bump": "patch", "reason": "Adds a helper."}},
+            {{"sha": "{}", "bump": "none", "reason": " Only documentation. "}}]}}"#,
+        added, docs
+    );
+    let provider = MockProvider::with_replies(&[&reply]);
+    let suggestion = semver::explain(&input, Some(&provider)).await.unwrap();
+
+    assert_eq!((suggestion.bump, suggestion.next.as_deref()), (Bump::Major, Some("v2.0.0")));
+    let rated: Vec<(Bump, Basis)> = suggestion.commits.iter().map(|c| (c.rating.bump, c.rating.basis)).collect();
+    let bases = [Basis::Header, Basis::Header, Basis::Model, Basis::Api];
+    let bumps = [Bump::Major, Bump::Patch, Bump::None, Bump::Minor];
+    let expected: Vec<(Bump, Basis)> = bumps.into_iter().zip(bases).collect();
+    assert_eq!(rated, expected);
+    assert_eq!(suggestion.commits[2].rating.reason.text(), "Only documentation.");
+    assert_eq!(suggestion.commits[3].rating.reason.text(), "Adds public API: crate::check.");
+
+    let requests = provider.requests();
+    let question = requests[0].iter().find(|m| m.content.contains("Short SHA:")).unwrap().content.clone();
+    assert!(question.contains("Reword the README") && !question.contains("accept empty input"), "{}", question);
+    assert!(question.contains("Public API: Adds public API: crate::check."), "{}", question);
+}
+
+#[tokio::test]
+async fn conventional_commits_are_rated_without_a_model() {
+    let mut fixture = FixtureRepo::linear(1);
+    tag(&fixture, "0.3.1", fixture.head());
+    fixture.commit("feat: add --json", &[Change::Write("main.txt", b"json\n")]);
+    fixture.commit("ci: cache builds", &[Change::Write("ci.yml", b"cache: true\n")]);
+
+    let input = semver::prepare(&fixture.repo).unwrap();
+    assert_eq!(input.estimate().requests, 0);
+    let suggestion = semver::explain(&input, None).await.unwrap();
+
+    // Below 1.0.0, a feature is a patch release
+    assert_eq!((suggestion.bump, suggestion.next.as_deref()), (Bump::Minor, Some("0.3.2")));
+    let out = semver::render(&suggestion, OutputFormat::Markdown);
+    assert!(out.starts_with("## Version bump\n\nminor: 0.3.1 → 0.3.2\n\n## Commits\n\n- [none] "), "{}", out);
+    assert!(out.contains(" ci: cache builds: A ci commit, which users do not notice.\n- [minor] "), "{}", out);
+    let json: serde_json::Value = serde_json::from_str(&semver::render(&suggestion, OutputFormat::Json)).unwrap();
+    assert_eq!((json["bump"].as_str(), json["next"].as_str()), (Some("minor"), Some("0.3.2")));
+    // The reasons are codes, not the prose of the UI language
+    let reason = serde_json::json!({ "kind": "header", "type": "feat", "breaking": false });
+    assert_eq!(json["commits"][1]["reason"], reason);
+
+    // Nothing since the tag is no release, in JSON too
+    tag(&fixture, "0.3.2", fixture.head());
+    let input = semver::prepare(&fixture.repo).unwrap();
+    let suggestion = semver::explain(&input, None).await.unwrap();
+    let json: serde_json::Value = serde_json::from_str(&semver::render(&suggestion, OutputFormat::Json)).unwrap();
+    assert_eq!((json["bump"].as_str(), json["commits"].as_array().map(Vec::len)), (Some("none"), Some(0)));
+}
+
+#[test]
+fn the_next_version_follows_the_last_tag() {
+    let cases = [
+        ("v1.2.4", Bump::Minor, Some("v1.3.0")),
+        ("v1.2.4", Bump::Patch, Some("v1.2.5")),
+        ("v1", Bump::Major, Some("v2.0.0")),
+        ("0.3.1", Bump::Major, Some("0.4.0")),
+        ("v2.0.0-rc.1", Bump::Patch, Some("v2.0.0")),
+        ("v1.2.4", Bump::None, None),
+        ("latest", Bump::Minor, None),
+    ];
+    for (tag, bump, next) in cases {
+        assert_eq!(semver::next_version(tag, bump).as_deref(), next, "{} {:?}", tag, bump);
+    }
+}
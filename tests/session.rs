@@ -0,0 +1,75 @@
+mod common;
+
+use common::{FixtureRepo, MockProvider};
+use wtf::error::WtfError;
+use wtf::render::OutputFormat;
+use wtf::session::{self, Session};
+use wtf::tui::{self, CommitSource};
+
+fn temp_data_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("wtf-session-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn sessions_are_saved_listed_and_loaded() {
+    let dir = temp_data_dir("roundtrip");
+    let mut first = Session::new("tui", "/work/app".to_string(), None);
+    first.record("Explain commit [abc1234] Fix login", "It fixes the session timeout.", &["abc1234"]);
+    first.save(&dir).unwrap();
+    first.record("Explain commit [def5678] Add retry", "It retries, see also [abc1234].", &["def5678"]);
+    let path = first.save(&dir).unwrap();
+    assert_eq!(path, dir.join("sessions").join(format!("{}.json", first.id)));
+
+    // Started in the same second, so it must not overwrite the first one
+    let mut second = Session::new("tui", "/work/app".to_string(), None);
+    second.started = first.started;
+    second.id = first.id.clone();
+    second.record("Explain commit [0123abc] Bump", "A version bump.", &["0123abc"]);
+    second.save(&dir).unwrap();
+    assert_eq!(second.id, format!("{}-2", first.id));
+
+    assert_eq!(session::list(&dir).len(), 2);
+    let loaded = Session::load(&dir, &first.id).unwrap();
+    assert_eq!(loaded.entries.len(), 2);
+    assert_eq!(loaded.entries[1].commits, ["def5678", "abc1234"]);
+    assert!(matches!(Session::load(&dir, "nope"), Err(WtfError::UnknownSession { .. })));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn exports_a_shareable_transcript() {
+    let mut session = Session::new("tui", "/work/app".to_string(), Some("https://github.com/o/app/commit".to_string()));
+    session.record("Explain commit [abc1234] Fix login", "The timeout was too short [abc1234].", &["abc1234"]);
+
+    let markdown = session::render(&session, OutputFormat::Markdown);
+    assert!(markdown.starts_with(&format!("# wtf session {}\n", session.id)), "{}", markdown);
+    assert!(markdown.contains("## 1. Explain commit [abc1234] Fix login\n"));
+    assert!(markdown.contains("too short [abc1234](https://github.com/o/app/commit/abc1234)."));
+    assert!(markdown.contains("_Commits: [abc1234](https://github.com/o/app/commit/abc1234)_"));
+
+    let terminal = session::render(&session, OutputFormat::Terminal);
+    assert!(terminal.contains("--- 1. Explain commit [abc1234] Fix login ---"));
+    let json: serde_json::Value = serde_json::from_str(&session::render(&session, OutputFormat::Json)).unwrap();
+    assert_eq!(json["entries"][0]["commits"][0], "abc1234");
+}
+
+#[test]
+fn the_tui_records_its_explanations() {
+    let fixture = FixtureRepo::linear(2);
+    let dir = temp_data_dir("tui");
+    let provider = MockProvider::with_replies(&["It adds a file."]);
+    let commits = tui::load_commits(&fixture.repo, 10).unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut source = tui::RepoSource::new(&fixture.repo, &provider, runtime.handle().clone());
+    source.record_to(Session::new("tui", "/work/app".to_string(), None), dir.clone());
+    source.explain(&commits[0]).unwrap();
+
+    let recorded = source.session().unwrap();
+    assert_eq!(recorded.entries[0].question, format!("Explain commit [{}] Add file 1", commits[0].short_id));
+    assert_eq!(recorded.entries[0].commits, [commits[0].short_id.clone()]);
+    assert_eq!(Session::load(&dir, &recorded.id).unwrap().entries, recorded.entries);
+    let _ = std::fs::remove_dir_all(&dir);
+}
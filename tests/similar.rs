@@ -0,0 +1,82 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use std::path::Path;
+use wtf::render::OutputFormat;
+use wtf::similar::{self, Target};
+
+fn history() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit(
+        "Fix session timeout on login",
+        &[Change::Write("src/auth/login.rs", b"fn login() { let session_timeout = 30; }\n")],
+    );
+    fixture.commit("Document the release process", &[Change::Write("docs/release.md", b"# Releasing\n")]);
+    fixture.commit("Update the changelog", &[Change::Write("CHANGELOG.md", b"## 1.0\n")]);
+    fixture.commit("Tune parser buffers", &[Change::Write("src/parser.rs", b"fn parse() { let buffer = 8; }\n")]);
+    fixture.commit(
+        "Fix session timeout on login again",
+        &[Change::Write("src/auth/login.rs", b"fn login() { let session_timeout = 60; }\n")],
+    );
+    fixture
+}
+
+#[test]
+fn ranks_commits_by_shared_terms() {
+    let fixture = history();
+    let change = similar::change(&fixture.repo, &Target::Commit("HEAD".to_string())).unwrap().unwrap();
+    assert_eq!(change.paths, ["src/auth/login.rs"]);
+
+    let matches = similar::find_similar(&fixture.repo, &change, 100, 3).unwrap();
+
+    assert_eq!(matches[0].summary, "Fix session timeout on login");
+    assert!(matches[0].score > 0.5, "{}", matches[0].score);
+    assert!(matches[0].shared.contains(&"src/auth/login.rs".to_string()), "{:?}", matches[0].shared);
+    assert!(matches.iter().all(|found| found.short_id != change.short_id.clone().unwrap()));
+    assert!(!matches.iter().any(|found| found.summary == "Update the changelog"));
+}
+
+#[test]
+fn compares_the_staged_changes() {
+    let fixture = history();
+    let head_tree = fixture.repo.head().unwrap().peel_to_tree().unwrap();
+    let mut index = fixture.repo.index().unwrap();
+    index.read_tree(&head_tree).unwrap();
+    index.write().unwrap();
+    assert!(similar::change(&fixture.repo, &Target::Staged).unwrap().is_none());
+
+    std::fs::create_dir_all(fixture.dir.join("src")).unwrap();
+    std::fs::write(fixture.dir.join("src/parser.rs"), "fn parse() { let buffer = 64; }\n").unwrap();
+    index.add_path(Path::new("src/parser.rs")).unwrap();
+    index.write().unwrap();
+
+    let change = similar::change(&fixture.repo, &Target::Staged).unwrap().unwrap();
+    assert_eq!(change.short_id, None);
+    let matches = similar::find_similar(&fixture.repo, &change, 100, 1).unwrap();
+    assert_eq!(matches[0].summary, "Tune parser buffers");
+}
+
+#[tokio::test]
+async fn summarizes_how_similar_commits_handled_it() {
+    let fixture = history();
+    let change = similar::change(&fixture.repo, &Target::Commit("HEAD".to_string())).unwrap().unwrap();
+    let matches = similar::find_similar(&fixture.repo, &change, 100, 2).unwrap();
+    let estimate = similar::estimate(&change, &matches);
+    let provider = MockProvider::with_replies(&["The timeout was raised before [abc1234]."]);
+
+    let result = similar::summarize(change, matches, &provider).await.unwrap();
+
+    assert_eq!(estimate.requests, 1);
+    let requests = provider.requests();
+    let question = &requests[0][1].content;
+    assert!(question.contains("session_timeout = 60"), "{}", question);
+    assert!(question.contains("Similar commit ("));
+    assert!(question.contains("Fix session timeout on login\n"));
+
+    let text = similar::render(&result, OutputFormat::Terminal, None);
+    assert!(text.contains("Commits similar to ["));
+    assert!(text.contains("% similar, shares "), "{}", text);
+    assert!(text.contains("The timeout was raised before"));
+    let json: serde_json::Value = serde_json::from_str(&similar::render(&result, OutputFormat::Json, None)).unwrap();
+    assert_eq!(json["matches"].as_array().unwrap().len(), result.matches.len());
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::git;
+use wtf::render::OutputFormat;
+use wtf::standup::{self, Author, Since};
+
+#[test]
+fn windows_start_at_midnight_a_date_or_an_age_before_now() {
+    // 2023-11-14 22:13:20 UTC
+    let now = 1_700_000_000;
+    let start = |spec: &str| spec.parse::<Since>().map(|since| since.start(now));
+    assert_eq!(start("today"), Ok(git::parse_date("2023-11-14").unwrap()));
+    assert_eq!(start("Yesterday"), Ok(git::parse_date("2023-11-13").unwrap()));
+    assert_eq!(start("2 days ago"), Ok(now - 2 * 86_400));
+    assert_eq!(start("3.hours.ago"), Ok(now - 3 * 3_600));
+    assert_eq!(start("1 week"), Ok(now - 7 * 86_400));
+    assert_eq!(git::parse_date("2024-02-29").map(git::format_date).as_deref(), Some("2024-02-29"));
+    assert_eq!(git::parse_date("2023-02-29"), None);
+    assert!(start("last sprint").is_err() && start("2 fortnights ago").is_err());
+    assert!(standup::is_wip("WIP: parser") && standup::is_wip("fixup! Add parser") && !standup::is_wip("Wipe cache"));
+}
+
+#[tokio::test]
+async fn the_update_covers_the_authors_commits_in_the_window_on_every_branch() {
+    let mut fixture = FixtureRepo::linear(2);
+    let since = fixture.clock() + 1;
+    fixture.set_author("Ada Lovelace", "ada@example.com");
+    fixture.commit("Add the parser", &[Change::Write("src/parse.rs", b"fn parse() {}\n")]);
+    fixture.set_author("Someone Else", "else@example.com");
+    fixture.commit("Fix the build", &[Change::Write("build.rs", b"fn main() {}\n")]);
+    fixture.set_author("Ada Lovelace", "ada@example.com");
+    let head = fixture.head();
+    let wip = fixture.commit_on("refs/heads/lexer", &[head], "WIP: lexer", &[Change::Write("src/lex.rs", b"fn lex\n")]);
+    fixture.repo.config().unwrap().set_str("user.name", "Ada Lovelace").unwrap();
+    fixture.repo.config().unwrap().set_str("user.email", "ADA@example.com").unwrap();
+
+    let author = Author::resolve(&fixture.repo, "me").unwrap();
+    let input = standup::prepare(&fixture.repo, &author, since).unwrap();
+    let subjects: Vec<(&str, Option<&str>, bool)> =
+        input.commits.iter().map(|c| (c.subject.as_str(), c.branch.as_deref(), c.wip)).collect();
+    assert_eq!(subjects, [("WIP: lexer", Some("lexer"), true), ("Add the parser", None, false)]);
+    assert_eq!(input.commits[0].id, wip);
+    assert_eq!(input.estimate().requests, 1);
+    let others = Author::resolve(&fixture.repo, "else@").unwrap();
+    assert_eq!(standup::prepare(&fixture.repo, &others, since).unwrap().commits.len(), 1);
+
+    let provider = MockProvider::with_replies(&[
+        r#"{"done": ["Added the parser (abc1234)."], "in_progress": [" Writing the lexer. ", ""], "blockers": []}"#,
+    ]);
+    let update = standup::explain(&input, &provider).await.unwrap();
+    assert_eq!(update.in_progress, ["Writing the lexer."]);
+    let requests = provider.requests();
+    let question = &requests[0][1].content;
+    assert!(question.starts_with("Write a standup update for Ada Lovelace "), "{}", question);
+    assert!(question.contains("Branch: lexer (not merged)\nMarked as work in progress\nFiles: src/lex.rs\n"));
+    assert!(!question.contains("Fix the build"), "{}", question);
+
+    let out = standup::render(&update, OutputFormat::Markdown);
+    let date = git::format_date(since);
+    assert!(out.starts_with(&format!("## Standup for Ada Lovelace\n\n2 commit(s) since {}.\n", date)), "{}", out);
+    assert!(out.contains("## Done\n\n- Added the parser (abc1234).\n"), "{}", out);
+    assert!(out.ends_with("## Blockers\n\n- None\n"), "{}", out);
+}
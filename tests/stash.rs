@@ -0,0 +1,64 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use git2::StashFlags;
+use wtf::render::OutputFormat;
+use wtf::stash::{self, StashReport};
+
+// Two stashes: an edit to notes.txt, then a new untracked file
+fn repo_with_stashes() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Add notes", &[Change::Write("notes.txt", b"one\n")]);
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    let signature = git2::Signature::now("Fixture Author", "author@example.com").unwrap();
+    let workdir = fixture.dir.clone();
+    std::fs::write(workdir.join("notes.txt"), "one\ntwo\n").unwrap();
+    fixture.repo.stash_save(&signature, "half a second line", None).unwrap();
+    std::fs::write(workdir.join("draft.txt"), "idea\n").unwrap();
+    fixture.repo.stash_save(&signature, "a draft", Some(StashFlags::INCLUDE_UNTRACKED)).unwrap();
+    fixture
+}
+
+#[test]
+fn each_stash_is_diffed_against_the_commit_it_was_made_on() {
+    let mut fixture = repo_with_stashes();
+
+    let stashes = stash::collect(&mut fixture.repo).unwrap();
+
+    assert_eq!(stashes.len(), 2);
+    assert_eq!(stashes[0].name(), "stash@{0}");
+    assert!(stashes[0].message.ends_with("a draft"), "{}", stashes[0].message);
+    assert_eq!(stashes[0].untracked, ["draft.txt"]);
+    assert!(stashes[0].files.is_empty());
+    assert_eq!(stashes[1].files.len(), 1);
+    assert_eq!((stashes[1].files[0].path.as_str(), stashes[1].files[0].added), ("notes.txt", 1));
+    assert!(stashes[1].patch.contains("+two"), "{}", stashes[1].patch);
+}
+
+#[tokio::test]
+async fn all_stashes_are_explained_in_one_request() {
+    let mut fixture = repo_with_stashes();
+    let stashes = stash::collect(&mut fixture.repo).unwrap();
+    let provider = MockProvider::with_replies(&["stash@{0}: a new draft. stash@{1}: a second line."]);
+
+    let explanation = stash::explain(&stashes, &provider).await.unwrap();
+
+    assert_eq!(provider.requests().len(), 1);
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.contains("Stash: stash@{1}\n"), "{}", question);
+    assert!(question.contains("Untracked files: draft.txt\n"), "{}", question);
+    let report = StashReport { stashes, explanation: Some(explanation) };
+    let markdown = stash::render(&report, OutputFormat::Markdown);
+    assert!(markdown.starts_with("## Stashes (2)\n\n- stash@{0}: "), "{}", markdown);
+    assert!(markdown.contains("## What They Hold\n\nstash@{0}: a new draft."), "{}", markdown);
+}
+
+#[test]
+fn no_stashes_says_so() {
+    let mut fixture = FixtureRepo::linear(1);
+
+    let stashes = stash::collect(&mut fixture.repo).unwrap();
+
+    let markdown = stash::render(&StashReport { stashes, explanation: None }, OutputFormat::Markdown);
+    assert_eq!(markdown, "## Stashes (0)\n\nThere are no stashes.\n");
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::render::OutputFormat;
+use wtf::state::{self, Operation, StatusReport};
+
+// master and feature both change line 1 of notes.txt; master is checked out
+fn repo_with_diverged_branches() -> (FixtureRepo, git2::Oid) {
+    let mut fixture = FixtureRepo::linear(1);
+    let base = fixture.commit("Add notes", &[Change::Write("notes.txt", b"one\n")]);
+    fixture.commit("Say it on master", &[Change::Write("notes.txt", b"master\n")]);
+    let feature = fixture.commit_on(
+        "refs/heads/feature",
+        &[base],
+        "Say it on feature",
+        &[Change::Write("notes.txt", b"feature\n")],
+    );
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    (fixture, feature)
+}
+
+#[test]
+fn a_merge_with_conflicts_is_reported() {
+    let (fixture, feature) = repo_with_diverged_branches();
+    let incoming = fixture.repo.find_annotated_commit(feature).unwrap();
+    fixture.repo.merge(&[&incoming], None, None).unwrap();
+
+    let state = state::collect(&fixture.repo).unwrap();
+
+    assert_eq!(state.operation, Some(Operation::Merge));
+    assert!(state.incoming.is_some());
+    assert_eq!(state.conflicts, ["notes.txt"]);
+    assert_eq!(state.status.branch.as_deref(), Some("master"));
+    assert_eq!(state.detached_at, None);
+    let report = StatusReport { state, explanation: None };
+    let markdown = state::render(&report, OutputFormat::Markdown);
+    assert!(markdown.contains("- A git merge is in progress\n"), "{}", markdown);
+    assert!(markdown.contains("- 1 file(s) with conflicts: notes.txt\n"), "{}", markdown);
+}
+
+#[tokio::test]
+async fn a_rebase_in_progress_is_explained_with_its_step() {
+    let (fixture, _) = repo_with_diverged_branches();
+    let master = fixture.head();
+    fixture.repo.set_head_detached(master).unwrap();
+    let dir = fixture.repo.path().join("rebase-merge");
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, contents) in
+        [("msgnum", "1\n"), ("end", "2\n"), ("head-name", "refs/heads/feature\n"), ("onto", &format!("{}\n", master))]
+    {
+        std::fs::write(dir.join(file), contents).unwrap();
+    }
+    std::fs::write(dir.join("interactive"), "").unwrap();
+    let provider = MockProvider::with_replies(&["Finish with git rebase --continue."]);
+
+    let state = state::collect(&fixture.repo).unwrap();
+    assert_eq!(state.operation, Some(Operation::Rebase));
+    let progress = state.progress.clone().unwrap();
+    assert_eq!((progress.branch.as_deref(), progress.step, progress.total), (Some("feature"), 1, 2));
+    assert!(state.detached_at.as_deref().unwrap().ends_with(" Say it on master"));
+    state::explain(&state, &provider).await.unwrap();
+
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.contains("In progress: git rebase\n"), "{}", question);
+    assert!(question.contains("Rebasing feature onto "), "{}", question);
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::MockProvider;
+use serde::Deserialize;
+use serde_json::json;
+use wtf::provider::Message;
+use wtf::structured::{self, validate, Schema};
+use wtf::WtfError;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Classification {
+    kind: String,
+    breaking: bool,
+}
+
+fn classification_schema() -> Schema {
+    Schema::new(
+        "classification",
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": { "type": "string", "enum": ["feature", "fix", "chore"] },
+                "breaking": { "type": "boolean" },
+            },
+            "required": ["kind", "breaking"],
+            "additionalProperties": false,
+        }),
+    )
+}
+
+#[test]
+fn valid_values_pass() {
+    let schema = classification_schema();
+    assert!(validate(&json!({ "kind": "fix", "breaking": false }), &schema.schema).is_empty());
+}
+
+#[test]
+fn violations_are_reported_with_paths() {
+    let schema = classification_schema();
+    let errors = validate(&json!({ "kind": "rewrite", "extra": 1 }), &schema.schema);
+
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    assert!(errors.iter().any(|e| e.contains("missing required field \"breaking\"")));
+    assert!(errors.iter().any(|e| e.starts_with("$.kind:")));
+    assert!(errors.iter().any(|e| e == "$.extra: unexpected field"));
+}
+
+#[test]
+fn nested_arrays_are_checked() {
+    let schema = json!({
+        "type": "array",
+        "minItems": 1,
+        "items": { "type": "object", "properties": { "line": { "type": "integer" } } },
+    });
+
+    assert_eq!(validate(&json!([]), &schema), vec!["$: expected at least 1 items, got 0"]);
+    assert_eq!(
+        validate(&json!([{ "line": 3 }, { "line": "four" }]), &schema),
+        vec!["$[1].line: expected integer, got string"]
+    );
+}
+
+#[tokio::test]
+async fn request_parses_fenced_replies() {
+    let provider = MockProvider::with_replies(&["```json\n{\"kind\": \"feature\", \"breaking\": true}\n```"]);
+    let messages = [Message::user("classify this")];
+
+    let result: Classification = structured::request(&provider, &messages, &classification_schema())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result,
+        Classification {
+            kind: "feature".to_string(),
+            breaking: true
+        }
+    );
+    // The default implementation spells out the schema for the model
+    let sent = &provider.requests()[0];
+    assert!(sent.last().unwrap().content.contains("\"enum\""));
+}
+
+#[tokio::test]
+async fn request_rejects_schema_violations() {
+    let provider = MockProvider::with_replies(&["{\"kind\": \"feature\"}"]);
+    let messages = [Message::user("classify this")];
+
+    let err = structured::request_with_reasks::<Classification>(&provider, &messages, &classification_schema(), 0)
+        .await
+        .unwrap_err();
+
+    match err {
+        WtfError::InvalidStructuredResponse { errors } => {
+            assert_eq!(errors, vec!["$: missing required field \"breaking\""]);
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+}
+
+#[tokio::test]
+async fn invalid_replies_are_re_asked_with_the_errors() {
+    let provider = MockProvider::with_replies(&[
+        "not json at all",
+        "{\"kind\": \"feature\"}",
+        "{\"kind\": \"fix\", \"breaking\": false}",
+    ]);
+    let messages = [Message::user("classify this")];
+
+    let result: Classification = structured::request(&provider, &messages, &classification_schema())
+        .await
+        .unwrap();
+
+    assert_eq!(result.kind, "fix");
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 3);
+    let correction = &requests[2];
+    assert_eq!(correction[correction.len() - 3].content, "{\"kind\": \"feature\"}");
+    assert!(correction[correction.len() - 2].content.contains("missing required field \"breaking\""));
+}
+
+#[tokio::test]
+async fn re_asking_gives_up_after_the_limit() {
+    let provider = MockProvider::with_replies(&["[]", "[]", "[]", "[]"]);
+    let messages = [Message::user("classify this")];
+
+    let err = structured::request_with_reasks::<Classification>(&provider, &messages, &classification_schema(), 2)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, WtfError::InvalidStructuredResponse { .. }));
+    assert_eq!(provider.requests().len(), 3);
+}
@@ -0,0 +1,61 @@
+use std::time::Duration;
+use wtf::error::exit_code;
+use wtf::telemetry::{self, duration_bucket, Event, Settings};
+
+fn temp_config_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("wtf-telemetry-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn telemetry_is_off_until_turned_on() {
+    let dir = temp_config_dir("toggle");
+    assert_eq!(Settings::load(&dir), Settings { enabled: false });
+
+    Settings { enabled: true }.save(&dir).unwrap();
+    assert!(Settings::load(&dir).enabled);
+
+    // DO_NOT_TRACK wins over the stored choice. This is the only test in
+    // this binary that touches the environment.
+    assert!(telemetry::is_enabled(&dir));
+    std::env::set_var("DO_NOT_TRACK", "1");
+    assert!(!telemetry::is_enabled(&dir));
+    std::env::remove_var("DO_NOT_TRACK");
+
+    Settings { enabled: false }.save(&dir).unwrap();
+    assert!(!telemetry::is_enabled(&dir));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn corrupt_settings_mean_off() {
+    let dir = temp_config_dir("corrupt");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("telemetry.json"), "{not json").unwrap();
+    assert!(!Settings::load(&dir).enabled);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn durations_are_bucketed() {
+    assert_eq!(duration_bucket(Duration::from_millis(400)), "<1s");
+    assert_eq!(duration_bucket(Duration::from_secs(9)), "1-10s");
+    assert_eq!(duration_bucket(Duration::from_secs(42)), "10-60s");
+    assert_eq!(duration_bucket(Duration::from_secs(200)), "1-5m");
+    assert_eq!(duration_bucket(Duration::from_secs(3600)), ">5m");
+}
+
+#[test]
+fn events_carry_only_coarse_fields() {
+    let event = Event::new("analyze", Duration::from_secs(12), exit_code::AUTH);
+    assert_eq!(event.outcome, "auth");
+    assert_eq!(event.duration, "10-60s");
+
+    let json = serde_json::to_value(&event).unwrap();
+    let mut fields: Vec<&String> = json.as_object().unwrap().keys().collect();
+    fields.sort();
+    assert_eq!(fields, ["command", "duration", "os", "outcome", "version"]);
+    assert_eq!(Event::new("telemetry", Duration::ZERO, 0).outcome, "ok");
+    assert_eq!(Event::new("lint-commits", Duration::ZERO, exit_code::LINT).outcome, "lint");
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use wtf::render::OutputFormat;
+use wtf::timeline::{self, ChangeKind};
+use wtf::WtfError;
+
+const LONG_FILE: &[u8] = b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+
+// README, then app.txt: created, edited, renamed to main.txt, rewritten
+fn file_with_history() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Start the app", &[Change::Write("app.txt", LONG_FILE)]);
+    fixture.commit("Unrelated change", &[Change::Write("other.txt", b"x\n")]);
+    fixture.commit("Tweak line 3", &[Change::Write("app.txt", b"1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n")]);
+    fixture.commit("Rename app to main", &[Change::Rename("app.txt", "main.txt")]);
+    fixture.commit("Rewrite main", &[Change::Write("main.txt", b"all new\n")]);
+    fixture
+}
+
+#[test]
+fn history_follows_renames_oldest_first() {
+    let fixture = file_with_history();
+
+    let events = timeline::file_history(&fixture.repo, "main.txt").unwrap();
+
+    let summaries: Vec<_> = events.iter().map(|e| e.summary.as_str()).collect();
+    assert_eq!(summaries, ["Start the app", "Tweak line 3", "Rename app to main", "Rewrite main"]);
+    let kinds: Vec<_> = events.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        [
+            ChangeKind::Created,
+            ChangeKind::Modified,
+            ChangeKind::Renamed { from: "app.txt".to_string() },
+            ChangeKind::Rewritten,
+        ]
+    );
+    assert_eq!(events[0].path, "app.txt");
+    assert_eq!((events[1].added, events[1].removed), (1, 1));
+    assert!(events[1].patch.contains("three"));
+}
+
+#[test]
+fn renames_and_rewrites_start_new_eras() {
+    let fixture = file_with_history();
+    let events = timeline::file_history(&fixture.repo, "main.txt").unwrap();
+
+    let eras = timeline::eras(events);
+
+    let sizes: Vec<_> = eras.iter().map(|era| era.events.len()).collect();
+    assert_eq!(sizes, [2, 1, 1]);
+    assert_eq!(eras[0].authors(), ["Fixture Author"]);
+}
+
+#[tokio::test]
+async fn summarizes_each_era_then_the_whole_story() {
+    let fixture = file_with_history();
+    let provider = MockProvider::new();
+
+    let input = timeline::prepare(&fixture.repo, "main.txt").unwrap();
+    assert_eq!(input.estimate().requests, 4);
+    let timeline = timeline::run(&input, &provider).await.unwrap();
+
+    assert_eq!(provider.requests().len(), 4);
+    assert_eq!(timeline.eras.len(), 3);
+    assert_eq!(timeline.eras[1].path, "main.txt");
+    assert_eq!(timeline.narrative, "mock response 4");
+    let first_era = &provider.requests()[0];
+    assert!(first_era.last().unwrap().content.contains("Tweak line 3 (modified, +1 -1)"));
+
+    let plain = timeline::render(&timeline, OutputFormat::Plain);
+    assert!(plain.starts_with("Section 1 of 4: History of main.txt"), "{}", plain);
+    let json: serde_json::Value = serde_json::from_str(&timeline::render(&timeline, OutputFormat::Json)).unwrap();
+    assert_eq!(json["eras"][0]["commits"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn unknown_paths_are_reported() {
+    let fixture = FixtureRepo::linear(2);
+
+    let err = timeline::prepare(&fixture.repo, "missing.txt").unwrap_err();
+
+    assert!(matches!(err, WtfError::UnknownPath { .. }));
+    assert_eq!(err.exit_code(), wtf::error::exit_code::USAGE);
+}
+
+#[test]
+fn dates_are_formatted_in_utc() {
+    assert_eq!(wtf::git::format_date(0), "1970-01-01");
+    assert_eq!(wtf::git::format_date(1_700_000_000), "2023-11-14");
+    assert_eq!(wtf::git::format_date(951_782_400), "2000-02-29");
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::{FixtureRepo, MockProvider};
+use wtf::analysis::{self, AnalysisOptions};
+
+fn options() -> AnalysisOptions {
+    AnalysisOptions { num_commits: 3, topics: true, ..AnalysisOptions::default() }
+}
+
+fn short_ids(fixture: &FixtureRepo) -> Vec<String> {
+    let (commits, _) = wtf::git::select_commits(&fixture.repo, 3).unwrap();
+    commits.iter().map(|c| c.as_object().short_id().unwrap().as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn commits_are_grouped_into_themes_before_the_report_is_written() {
+    let fixture = FixtureRepo::linear(4);
+    let ids = short_ids(&fixture);
+    // A commit in two themes, one in none and one that was not analyzed
+    let reply = format!(
+        r#"{{"topics": [
+            {{"name": "Docs", "shas": ["{}"]}},
+            {{"name": " File setup ", "shas": ["{}", "{}", "0000000"]}}
+        ]}}"#,
+        ids[2], ids[0], ids[2]
+    );
+    let provider = MockProvider::with_replies(&[&reply, "Project.", "Commits.", "Edits."]);
+
+    let report = analysis::analyze(&fixture.repo, &provider, &options()).await.unwrap();
+
+    let topics: Vec<(&str, Vec<&str>)> = report
+        .topics
+        .iter()
+        .flatten()
+        .map(|topic| (topic.name.as_str(), topic.commits.iter().map(String::as_str).collect()))
+        .collect();
+    let expected = [("File setup", vec![ids[0].as_str()]), ("Docs", vec![&ids[2]]), ("Other changes", vec![&ids[1]])];
+    assert_eq!(topics, expected);
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 4);
+    assert!(requests[0][1].content.contains(&format!("Short SHA: {}", ids[1])));
+    let commits = requests[2].last().unwrap().content.clone();
+    assert!(commits.contains("The data is grouped by theme."), "{}", commits);
+    let first = commits.find("Theme: File setup\n\nCommit: ").unwrap();
+    let last = commits.find("Theme: Other changes\n\nCommit: ").unwrap();
+    assert!(first < commits.find("Theme: Docs\n\n").unwrap() && commits[last..].contains("Add file 2"), "{}", commits);
+    let edits = &requests[3].last().unwrap().content;
+    assert!(edits.contains(&format!("Theme: Docs\n\nCommit: {}\n", ids[2])), "{}", edits);
+}
+
+#[test]
+fn estimate_counts_the_topics_request_and_skips_batching() {
+    let fixture = FixtureRepo::linear(4);
+    let input = analysis::prepare(&fixture.repo, &options()).unwrap();
+
+    let plain = input.estimate(&AnalysisOptions { topics: false, ..options() }, usize::MAX);
+    let grouped = input.estimate(&options(), usize::MAX);
+    assert_eq!(grouped.requests, plain.requests + 1);
+    assert!(grouped.prompt_tokens > plain.prompt_tokens);
+
+    // Batches would split up the themes
+    let batched = AnalysisOptions { batch_size: 1, ..options() };
+    assert_eq!(input.estimate(&batched, usize::MAX).requests, grouped.requests);
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use common::{FixtureRepo, MockProvider};
+use wtf::tui::picker::{self, PickAction, Picker};
+use wtf::tui::{self, Action, App, CommitEntry, CommitSource, DetailView, Key};
+
+#[derive(Default)]
+struct FakeSource {
+    explained: Vec<String>,
+    diffs: Vec<String>,
+}
+
+impl CommitSource for FakeSource {
+    fn diff(&mut self, commit: &CommitEntry) -> wtf::Result<String> {
+        self.diffs.push(commit.id.clone());
+        Ok(format!("diff --git a/{0} b/{0}\n+line", commit.short_id))
+    }
+
+    fn explain(&mut self, commit: &CommitEntry) -> wtf::Result<String> {
+        self.explained.push(commit.id.clone());
+        Ok(format!("Explanation of {}", commit.summary))
+    }
+}
+
+fn entries() -> Vec<CommitEntry> {
+    ["Fix login bug", "Add README", "Refactor parser"]
+        .iter()
+        .enumerate()
+        .map(|(i, summary)| CommitEntry {
+            id: format!("{}{}", i, "a".repeat(39)),
+            short_id: format!("{}aaaaaa", i),
+            summary: summary.to_string(),
+            author: if i == 1 { "Bob" } else { "Alice" }.to_string(),
+            time: 1_700_000_000 + i as i64,
+        })
+        .collect()
+}
+
+#[test]
+fn parses_plain_keys_and_escape_sequences() {
+    assert_eq!(
+        tui::terminal::parse_keys(b"jk\r\x1b[A\x1b[B\x1b[5~\x1b[6~\x1bOH\x1b[F\t\x7f\x03"),
+        vec![
+            Key::Char('j'),
+            Key::Char('k'),
+            Key::Enter,
+            Key::Up,
+            Key::Down,
+            Key::PageUp,
+            Key::PageDown,
+            Key::Home,
+            Key::End,
+            Key::Tab,
+            Key::Backspace,
+            Key::CtrlC,
+        ]
+    );
+    assert_eq!(tui::terminal::parse_keys(b"\x1b"), vec![Key::Esc]);
+    assert_eq!(tui::terminal::parse_keys("ä".as_bytes()), vec![Key::Char('ä')]);
+}
+
+#[test]
+fn navigation_stays_within_the_list() {
+    let mut app = App::new(entries());
+
+    app.handle_key(Key::Up, 10);
+    assert_eq!(app.selected().unwrap().summary, "Fix login bug");
+    app.handle_key(Key::Char('j'), 10);
+    assert_eq!(app.selected().unwrap().summary, "Add README");
+    app.handle_key(Key::End, 10);
+    app.handle_key(Key::Down, 10);
+    assert_eq!(app.selected().unwrap().summary, "Refactor parser");
+    app.handle_key(Key::Char('g'), 10);
+    assert_eq!(app.selected().unwrap().summary, "Fix login bug");
+    assert_eq!(app.handle_key(Key::Char('q'), 10), Action::Quit);
+}
+
+#[test]
+fn search_filters_by_summary_and_author() {
+    let mut app = App::new(entries());
+
+    for key in [Key::Char('/'), Key::Char('b'), Key::Char('o'), Key::Char('b')] {
+        assert_eq!(app.handle_key(key, 10), Action::Continue);
+    }
+    let visible: Vec<_> = app.visible().iter().map(|c| c.summary.clone()).collect();
+    assert_eq!(visible, ["Add README"]);
+
+    // 'q' is part of the query while searching, not a quit
+    assert_eq!(app.handle_key(Key::Char('q'), 10), Action::Continue);
+    assert!(app.visible().is_empty());
+
+    app.handle_key(Key::Esc, 10);
+    assert_eq!(app.visible().len(), 3);
+}
+
+#[test]
+fn explanations_are_requested_once_per_commit() {
+    let mut app = App::new(entries());
+    let mut source = FakeSource::default();
+
+    assert_eq!(app.handle_key(Key::Enter, 10), Action::Explain);
+    app.explain_selected(&mut source);
+    assert_eq!(app.handle_key(Key::Enter, 10), Action::Continue);
+    assert_eq!(source.explained.len(), 1);
+
+    let screen = app.render(100, 20).join("\n");
+    assert!(screen.contains("Explanation of Fix login bug"), "{}", screen);
+}
+
+#[test]
+fn tab_toggles_to_the_diff() {
+    let mut app = App::new(entries());
+    let mut source = FakeSource::default();
+
+    app.handle_key(Key::Tab, 10);
+    assert_eq!(app.view(), DetailView::Diff);
+    app.load_diff(&mut source);
+    app.load_diff(&mut source);
+    assert_eq!(source.diffs.len(), 1);
+    assert!(app.render(100, 20).join("\n").contains("diff --git a/0aaaaaa"));
+
+    app.handle_key(Key::Char('d'), 10);
+    assert_eq!(app.view(), DetailView::Explanation);
+}
+
+#[test]
+fn render_fills_the_screen() {
+    let mut app = App::new(entries());
+
+    for (width, height) in [(80, 24), (30, 5), (200, 60)] {
+        let lines = app.render(width, height);
+        assert_eq!(lines.len(), height);
+        assert!(lines[0].chars().count() <= width);
+    }
+}
+
+#[test]
+fn fit_and_wrap_respect_width() {
+    assert_eq!(tui::fit("abc", 5), "abc  ");
+    assert_eq!(tui::fit("abcdef", 3), "abc");
+    assert_eq!(tui::wrap("one two three", 7), ["one two", "three"]);
+    assert_eq!(tui::wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+}
+
+#[test]
+fn fuzzy_score_prefers_tight_matches() {
+    assert!(picker::fuzzy_score("flb", "Fix login bug").is_some());
+    assert!(picker::fuzzy_score("blf", "Fix login bug").is_none());
+    assert_eq!(picker::fuzzy_score("", "anything"), Some(0));
+
+    let login = picker::fuzzy_score("fixlog", "Fix login").unwrap();
+    let changelog = picker::fuzzy_score("fixlog", "Fix a typo in the changelog").unwrap();
+    assert!(login > changelog);
+}
+
+#[test]
+fn picker_filters_and_multi_selects() {
+    let mut picker = Picker::new(entries());
+
+    for c in "ref".chars() {
+        picker.handle_key(Key::Char(c));
+    }
+    assert_eq!(picker.visible()[0].summary, "Refactor parser");
+
+    picker.handle_key(Key::Tab);
+    for _ in 0..3 {
+        picker.handle_key(Key::Backspace);
+    }
+    assert_eq!(picker.visible().len(), 3);
+    picker.handle_key(Key::Tab);
+    assert_eq!(picker.handle_key(Key::Enter), PickAction::Done);
+
+    // Marked commits come back in log order, whatever order they were marked in
+    let chosen: Vec<_> = picker.chosen().into_iter().map(|c| c.summary).collect();
+    assert_eq!(chosen, ["Fix login bug", "Refactor parser"]);
+}
+
+#[test]
+fn picker_without_marks_chooses_the_cursor() {
+    let mut picker = Picker::new(entries());
+
+    picker.handle_key(Key::Down);
+    assert_eq!(picker.chosen()[0].summary, "Add README");
+    assert_eq!(picker.handle_key(Key::Esc), PickAction::Cancel);
+
+    for c in "zzz".chars() {
+        picker.handle_key(Key::Char(c));
+    }
+    assert!(picker.chosen().is_empty());
+    assert_eq!(picker.handle_key(Key::Enter), PickAction::Continue);
+    assert_eq!(picker.render(40, 10).len(), 10);
+}
+
+#[test]
+fn load_commits_and_explain_from_a_repository() {
+    let fixture = FixtureRepo::linear(3);
+    let provider = MockProvider::with_replies(&["It adds a file."]);
+
+    let commits = tui::load_commits(&fixture.repo, 10).unwrap();
+    assert_eq!(commits.len(), 3);
+    assert_eq!(commits[0].summary, "Add file 2");
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut source = tui::RepoSource::new(&fixture.repo, &provider, runtime.handle().clone());
+    assert!(source.diff(&commits[0]).unwrap().contains("src/file2.txt"));
+    assert_eq!(source.explain(&commits[0]).unwrap(), "It adds a file.");
+
+    let prompt = &provider.requests()[0];
+    assert!(prompt.iter().any(|m| m.content.contains("Add file 2")));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use std::path::Path;
+use wtf::git::Uncommitted;
+use wtf::render::OutputFormat;
+use wtf::uncommitted;
+use wtf::WtfError;
+
+// notes.txt committed, then one edit staged and another on top in the work tree
+fn repo_with_changes() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(1);
+    fixture.commit("Add notes", &[Change::Write("notes.txt", b"one\n")]);
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    std::fs::write(fixture.dir.join("notes.txt"), "one\ntwo\n").unwrap();
+    let mut index = fixture.repo.index().unwrap();
+    index.add_path(Path::new("notes.txt")).unwrap();
+    index.write().unwrap();
+    std::fs::write(fixture.dir.join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+    fixture
+}
+
+#[test]
+fn staged_unstaged_and_all_changes_are_diffed_separately() {
+    let fixture = repo_with_changes();
+
+    let staged = uncommitted::prepare(&fixture.repo, Uncommitted::Staged, &[]).unwrap();
+    let unstaged = uncommitted::prepare(&fixture.repo, Uncommitted::Unstaged, &[]).unwrap();
+    let all = uncommitted::prepare(&fixture.repo, Uncommitted::All, &[]).unwrap();
+
+    assert!(staged.patch.contains("+two\n") && !staged.patch.contains("+three"), "{}", staged.patch);
+    assert!(unstaged.patch.contains("+three\n") && !unstaged.patch.contains("+two"), "{}", unstaged.patch);
+    assert_eq!((all.files[0].added, all.files[0].removed), (2, 0));
+}
+
+#[test]
+fn a_clean_work_tree_has_nothing_to_explain() {
+    let fixture = FixtureRepo::linear(2);
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+
+    let err = uncommitted::prepare(&fixture.repo, Uncommitted::Staged, &[]).unwrap_err();
+    assert!(matches!(err, WtfError::NoChanges { staged: true }), "{:?}", err);
+    assert!(matches!(
+        uncommitted::prepare(&fixture.repo, Uncommitted::All, &[]),
+        Err(WtfError::NoChanges { staged: false })
+    ));
+}
+
+#[tokio::test]
+async fn the_staged_patch_is_explained() {
+    let fixture = repo_with_changes();
+    let input = uncommitted::prepare(&fixture.repo, Uncommitted::Staged, &[]).unwrap();
+    let provider = MockProvider::with_replies(&["It adds a second note."]);
+
+    assert_eq!(input.estimate(10_000).requests, 1);
+    let report = uncommitted::explain(&input, &provider, 10_000).await.unwrap();
+
+    let question = provider.requests()[0].last().unwrap().content.clone();
+    assert!(question.starts_with("Here are the staged changes"), "{}", question);
+    assert!(question.contains("+two"), "{}", question);
+    let markdown = uncommitted::render(&report, OutputFormat::Markdown);
+    assert!(markdown.contains("- notes.txt: +1 -0 lines\n"), "{}", markdown);
+    assert!(markdown.ends_with("It adds a second note.\n"), "{}", markdown);
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::{Change, FixtureRepo};
+use wtf::watch::RefWatcher;
+
+#[test]
+fn existing_commits_are_not_new() {
+    let fixture = FixtureRepo::linear(3);
+    let mut watcher = RefWatcher::new(&fixture.repo).unwrap();
+
+    assert!(watcher.poll(&fixture.repo).unwrap().is_empty());
+}
+
+#[test]
+fn reports_new_commits_oldest_first_and_only_once() {
+    let mut fixture = FixtureRepo::linear(2);
+    let mut watcher = RefWatcher::new(&fixture.repo).unwrap();
+
+    let first = fixture.commit("Add notes", &[Change::Write("notes.txt", b"a\n")]);
+    let second = fixture.commit("Update notes", &[Change::Write("notes.txt", b"b\n")]);
+
+    assert_eq!(watcher.poll(&fixture.repo).unwrap(), vec![first, second]);
+    assert!(watcher.poll(&fixture.repo).unwrap().is_empty());
+}
+
+#[test]
+fn notices_commits_on_other_branches() {
+    let mut fixture = FixtureRepo::linear(2);
+    let base = fixture.head();
+    let mut watcher = RefWatcher::new(&fixture.repo).unwrap();
+
+    let feature = fixture.commit_on("refs/heads/feature", &[base], "Start feature", &[Change::Write("f.txt", b"f\n")]);
+
+    assert_eq!(watcher.poll(&fixture.repo).unwrap(), vec![feature]);
+}
+
+#[test]
+fn rewinding_a_branch_is_not_new() {
+    let fixture = FixtureRepo::linear(3);
+    let head = fixture.head();
+    let parent = fixture.repo.find_commit(head).unwrap().parent_id(0).unwrap();
+    let mut watcher = RefWatcher::new(&fixture.repo).unwrap();
+
+    let branch = fixture.repo.head().unwrap().name().unwrap().to_string();
+    fixture.repo.reference(&branch, parent, true, "reset").unwrap();
+    assert!(watcher.poll(&fixture.repo).unwrap().is_empty());
+}
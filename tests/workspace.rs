@@ -0,0 +1,99 @@
+mod common;
+
+use common::{Change, FixtureRepo, MockProvider};
+use git2::Repository;
+use wtf::render::OutputFormat;
+use wtf::workspace::{self, Workspace};
+
+fn temp_workspace(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("wtf-workspace-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// A fixture whose branch has an upstream two commits behind it
+fn ahead_of_upstream() -> FixtureRepo {
+    let mut fixture = FixtureRepo::linear(2);
+    let pushed = fixture.head();
+    fixture.commit("Add retry", &[Change::Write("retry.rs", b"fn retry() {}\n")]);
+    fixture.commit("Tune retry delays", &[Change::Write("retry.rs", b"fn retry() { wait(); }\n")]);
+    fixture.repo.remote("origin", "https://example.com/app.git").unwrap();
+    fixture.repo.reference("refs/remotes/origin/main", pushed, true, "fixture").unwrap();
+    let name = fixture.repo.head().unwrap().shorthand().unwrap().to_string();
+    let mut branch = fixture.repo.find_branch(&name, git2::BranchType::Local).unwrap();
+    branch.set_upstream(Some("origin/main")).unwrap();
+    drop(branch);
+    fixture
+}
+
+#[test]
+fn status_counts_changes_and_unpushed_commits() {
+    let fixture = ahead_of_upstream();
+    fixture.repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    std::fs::write(fixture.dir.join("retry.rs"), "fn retry() { wait(); wait(); }\n").unwrap();
+    std::fs::write(fixture.dir.join("notes.txt"), "todo\n").unwrap();
+
+    let status = workspace::status(&fixture.repo, "app".to_string()).unwrap();
+
+    assert_eq!(status.branch.as_deref(), fixture.repo.head().unwrap().shorthand());
+    assert_eq!((status.changes.modified, status.changes.untracked, status.changes.staged), (1, 1, 0));
+    assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+    assert_eq!((status.ahead, status.behind), (2, 0));
+    assert_eq!(status.unpushed, ["Tune retry delays", "Add retry"]);
+    assert_eq!(status.recent.len(), 3);
+    assert!(!status.is_settled());
+
+    fixture.repo.set_head_detached(fixture.head()).unwrap();
+    assert_eq!(workspace::status(&fixture.repo, "app".to_string()).unwrap().branch, None);
+}
+
+#[test]
+fn finds_repositories_up_to_the_given_depth() {
+    let dir = temp_workspace("find");
+    Repository::init(dir.join("api")).unwrap();
+    Repository::init(dir.join("apps/web")).unwrap();
+    Repository::init(dir.join(".cache/hidden")).unwrap();
+    std::fs::create_dir_all(dir.join("notes")).unwrap();
+
+    let found = workspace::find_repositories(&dir, 1).unwrap();
+    assert_eq!(found, [dir.join("api")]);
+    let found = workspace::find_repositories(&dir, 2).unwrap();
+    assert_eq!(found, [dir.join("api"), dir.join("apps/web")]);
+
+    let statuses = workspace::scan(&dir, 2).unwrap();
+    let names: Vec<&str> = statuses.iter().map(|status| status.name.as_str()).collect();
+    assert_eq!(names, ["api", "apps/web"]);
+    assert_eq!(statuses[0].branch, None);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn summarizes_everything_in_flight_at_once() {
+    let fixture = ahead_of_upstream();
+    let clean = FixtureRepo::linear(1);
+    let repositories = vec![
+        workspace::status(&fixture.repo, "app".to_string()).unwrap(),
+        workspace::status(&clean.repo, "docs".to_string()).unwrap(),
+    ];
+    let estimate = workspace::estimate(&repositories);
+    let provider = MockProvider::with_replies(&["app has two unpushed retry commits."]);
+
+    let summary = workspace::summarize(&repositories, &provider).await.unwrap();
+
+    assert_eq!(estimate.requests, 1);
+    let requests = provider.requests();
+    let question = &requests[0][1].content;
+    assert!(question.contains("Repository: app\n"), "{}", question);
+    assert!(question.contains("Upstream: origin/main (2 ahead, 0 behind)"));
+    assert!(question.contains("Unpushed commits:\n- Tune retry delays\n- Add retry\n"));
+    assert!(question.contains("Repository: docs\n"));
+
+    let report = Workspace { repositories, summary: Some(summary) };
+    let text = workspace::render(&report, OutputFormat::Terminal);
+    assert!(text.contains("2 ahead, 0 behind"), "{}", text);
+    assert!(text.contains("no upstream"));
+    assert!(text.contains("app has two unpushed retry commits."));
+    let json: serde_json::Value = serde_json::from_str(&workspace::render(&report, OutputFormat::Json)).unwrap();
+    assert_eq!(json["repositories"][0]["ahead"], 2);
+}